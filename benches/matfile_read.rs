@@ -1,5 +1,7 @@
 use criterion::{Criterion, criterion_group, criterion_main};
+use std::hint::black_box;
 
+use matrw::bench_support::Workload;
 use matrw::*;
 
 #[path = "../tests/shared_functions.rs"]
@@ -7,11 +9,47 @@ mod shared_functions;
 
 use crate::shared_functions::{MatlabCommand, TestFile};
 
+/// Workloads of `matrw::bench_support` with their size parameter
+const WORKLOADS: [(Workload, usize); 6] = [
+    (Workload::LargeMatrix, 2000),
+    (Workload::StructureArray, 10000),
+    (Workload::CellArray, 10000),
+    (Workload::Sparse, 20000),
+    (Workload::ManySmallVariables, 50000),
+    (Workload::Scalars, 100000),
+];
+
 /// Read data from MAT-file
 fn read_data(filepath: &str) {
     let _ = load_matfile(filepath).expect("Failure");
 }
 
+/// Read synthetic files of `matrw::bench_support`, from memory
+fn workload_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("read workloads");
+    group.sample_size(10);
+    for (workload, size) in WORKLOADS {
+        for compress in [false, true] {
+            let data = save_matfile_v7_to_u8(workload.generate(size), compress).unwrap();
+            let mode = if compress { "compressed" } else { "uncompressed" };
+            group.bench_function(format!("read {workload} {size}, {mode}"), |b| {
+                b.iter(|| load_matfile_from_u8(black_box(&data)).expect("Failure"))
+            });
+        }
+    }
+    group.finish();
+}
+
+/// Query the dimensions of many small variables, which are stored inline by `MatDim`
+fn dim_benchmark(c: &mut Criterion) {
+    let matfile = Workload::ManySmallVariables.generate(50000);
+    let mut group = c.benchmark_group("dimensions");
+    group.bench_function("numel of 50000 small variables", |b| {
+        b.iter(|| black_box(&matfile).iter().map(|(_, v)| v.numel()).sum::<usize>())
+    });
+    group.finish();
+}
+
 fn criterion_benchmark(c: &mut Criterion) {
     let filepath = TestFile {
         path: "benches/large.mat",
@@ -26,5 +64,5 @@ fn criterion_benchmark(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, criterion_benchmark);
+criterion_group!(benches, workload_benchmark, dim_benchmark, criterion_benchmark);
 criterion_main!(benches);
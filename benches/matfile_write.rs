@@ -3,6 +3,7 @@ use rand::prelude::*;
 use rand_pcg::Pcg64Mcg;
 use std::hint::black_box;
 
+use matrw::bench_support::Workload;
 use matrw::*;
 
 #[path = "../tests/shared_functions.rs"]
@@ -29,6 +30,26 @@ fn write_big_matrix(n: usize) {
     let _ = save_matfile_v7(file.path, mat, false);
 }
 
+/// Workloads of `matrw::bench_support` with their size parameter
+const WORKLOADS: [(Workload, usize); 5] = [
+    (Workload::StructureArray, 10000),
+    (Workload::CellArray, 10000),
+    (Workload::Sparse, 20000),
+    (Workload::ManySmallVariables, 5000),
+    (Workload::Scalars, 100000),
+];
+
+fn compression_options() -> [(&'static str, SaveOptions); 3] {
+    [
+        ("uncompressed", SaveOptions::new()),
+        ("compressed", SaveOptions::new().compress(true)),
+        (
+            "compressed if smaller",
+            SaveOptions::new().compress(true).compress_if_smaller(true),
+        ),
+    ]
+}
+
 fn criterion_benchmark(c: &mut Criterion) {
     let mut group = c.benchmark_group("sample-size-example");
     group.sample_size(10);
@@ -36,6 +57,50 @@ fn criterion_benchmark(c: &mut Criterion) {
         b.iter(|| write_big_matrix(black_box(15000)))
     });
     group.finish();
+
+    let mut group = c.benchmark_group("write workloads");
+    group.sample_size(10);
+    for (workload, size) in WORKLOADS {
+        for compress in [false, true] {
+            let mode = if compress { "compressed" } else { "uncompressed" };
+            group.bench_function(format!("write {workload} {size}, {mode}"), |b| {
+                b.iter_batched(
+                    || workload.generate(size),
+                    |mat| save_matfile_v7_to_u8(black_box(mat), compress),
+                    criterion::BatchSize::LargeInput,
+                )
+            });
+        }
+    }
+    group.finish();
+
+    // Output size of the compression modes. Variables are always compressed independently, as
+    // MATLAB can't read a shared dictionary or variables grouped into one compressed element, so
+    // compress_if_smaller only gains on variables which compression makes larger, like the
+    // scalars, and not on the small structures, which all shrink.
+    let n = 5000;
+    for workload in [Workload::ManySmallVariables, Workload::Scalars] {
+        for (mode, options) in compression_options() {
+            let size = save_matfile_v7_to_u8_with_options(workload.generate(n), &options)
+                .unwrap()
+                .len();
+            println!("{workload} {n}, {mode}: {size} bytes");
+        }
+    }
+
+    let workload = Workload::ManySmallVariables;
+    let mut group = c.benchmark_group("many-small-variables");
+    group.sample_size(10);
+    for (mode, options) in compression_options() {
+        group.bench_function(format!("write {n} small structures, {mode}"), |b| {
+            b.iter_batched(
+                || workload.generate(n),
+                |mat| save_matfile_v7_to_u8_with_options(black_box(mat), &options),
+                criterion::BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
 }
 
 criterion_group!(benches, criterion_benchmark);
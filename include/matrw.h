@@ -0,0 +1,91 @@
+/*
+ * C interface of matrw, available with the cargo feature `capi`.
+ *
+ * A MatrwFile returned by matrw_load, matrw_load_from_buffer or matrw_matfile_new is owned by the
+ * caller and must be released with matrw_matfile_free. Variables, dimensions and data obtained
+ * from a MatrwFile are borrowed and stay valid until the file is modified or released.
+ *
+ * Functions that can fail return NULL or -1. The reason is available from matrw_last_error.
+ */
+
+#ifndef MATRW_H
+#define MATRW_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Class of a variable, using the values of MATLAB's mxClassID */
+typedef enum MatrwClass {
+    MATRW_UNKNOWN = 0,
+    MATRW_CELL = 1,
+    MATRW_STRUCT = 2,
+    MATRW_LOGICAL = 3,
+    MATRW_CHAR = 4,
+    MATRW_DOUBLE = 6,
+    MATRW_SINGLE = 7,
+    MATRW_INT8 = 8,
+    MATRW_UINT8 = 9,
+    MATRW_INT16 = 10,
+    MATRW_UINT16 = 11,
+    MATRW_INT32 = 12,
+    MATRW_UINT32 = 13,
+    MATRW_INT64 = 14,
+    MATRW_UINT64 = 15
+} MatrwClass;
+
+typedef struct MatrwFile MatrwFile;
+typedef struct MatrwVariable MatrwVariable;
+
+/* Errors */
+
+/* Message of the last error on the calling thread, or NULL */
+const char *matrw_last_error(void);
+
+/* MAT-files */
+
+MatrwFile *matrw_matfile_new(void);
+void matrw_matfile_free(MatrwFile *file);
+
+MatrwFile *matrw_load(const char *path);
+MatrwFile *matrw_load_from_buffer(const uint8_t *data, size_t len);
+int matrw_save(const MatrwFile *file, const char *path, bool compress);
+
+size_t matrw_matfile_num_variables(const MatrwFile *file);
+/* Copies the nul-terminated name into buf and returns the untruncated length, 0 if out of range */
+/* Names longer than buf are truncated at a character boundary, so buf holds valid UTF-8 */
+size_t matrw_matfile_variable_name(const MatrwFile *file, size_t index, char *buf, size_t buf_len);
+const MatrwVariable *matrw_matfile_get(const MatrwFile *file, const char *name);
+
+/* real and imag must be aligned for the C type of class_id, e.g. to 8 bytes for MATRW_DOUBLE */
+/* Logical data is one byte per element, imag may be NULL */
+int matrw_matfile_insert_numeric(MatrwFile *file, const char *name, MatrwClass class_id,
+                                 const size_t *dims, size_t ndims, const void *real,
+                                 const void *imag);
+int matrw_matfile_insert_string(MatrwFile *file, const char *name, const char *value);
+
+/* Variables */
+
+MatrwClass matrw_variable_class(const MatrwVariable *var);
+bool matrw_variable_is_sparse(const MatrwVariable *var);
+bool matrw_variable_is_complex(const MatrwVariable *var);
+size_t matrw_variable_ndims(const MatrwVariable *var);
+const size_t *matrw_variable_dims(const MatrwVariable *var);
+size_t matrw_variable_numel(const MatrwVariable *var);
+
+/* Column-major data of numeric arrays. Characters are uint32_t code points, logicals one byte */
+const void *matrw_variable_real_data(const MatrwVariable *var);
+const void *matrw_variable_imag_data(const MatrwVariable *var);
+
+const MatrwVariable *matrw_variable_field(const MatrwVariable *var, const char *name);
+const MatrwVariable *matrw_variable_element(const MatrwVariable *var, size_t index);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* MATRW_H */
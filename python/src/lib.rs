@@ -0,0 +1,267 @@
+//! Python bindings of matrw.
+//!
+//! Numeric arrays of a loaded `MatFile` are returned as read-only numpy arrays sharing memory
+//! with the file. Each array keeps the file alive. Structures are returned as `dict`, cell and
+//! structure arrays as `list` and character row vectors as `str`.
+
+use indexmap::IndexMap;
+use matrw::{
+    load_matfile, load_matfile_from_u8, save_matfile_v7, CellArray, MatFile, MatVariable, MatlabType,
+    MatrwError, NumericArray, Structure, VariableClass,
+};
+use numpy::ndarray::{ArrayView, IxDyn, ShapeBuilder};
+use numpy::{Complex64, Element, PyArray, PyReadonlyArrayDyn};
+use pyo3::exceptions::{PyIOError, PyKeyError, PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyComplex, PyDict, PyFloat, PyList, PyLong, PyString};
+
+fn to_pyerr(err: MatrwError) -> PyErr {
+    match err {
+        MatrwError::IoError(err) => PyIOError::new_err(err.to_string()),
+        err => PyValueError::new_err(err.to_string()),
+    }
+}
+
+/// Loaded MAT-file. Variables are accessed by name.
+#[pyclass(frozen, name = "MatFile", module = "matrw")]
+struct PyMatFile {
+    inner: MatFile,
+}
+
+#[pymethods]
+impl PyMatFile {
+    fn keys(&self) -> Vec<String> {
+        self.inner.names().map(str::to_string).collect()
+    }
+
+    fn __len__(&self) -> usize {
+        self.inner.names().len()
+    }
+
+    fn __contains__(&self, name: &str) -> bool {
+        self.inner.get(name).is_some()
+    }
+
+    fn __getitem__(slf: &Bound<'_, Self>, name: &str) -> PyResult<PyObject> {
+        let var = slf
+            .get()
+            .inner
+            .get(name)
+            .ok_or_else(|| PyKeyError::new_err(name.to_string()))?;
+        to_python(var, slf.as_any())
+    }
+}
+
+// ============================================================================
+// MAT-file to Python
+// ============================================================================
+
+/// Read-only numpy array of `dim` sharing `data`, which is owned by `owner`.
+fn view<T: Element>(data: &[T], dim: &[usize], owner: &Bound<'_, PyAny>) -> PyResult<PyObject> {
+    let view =
+        ArrayView::from_shape(IxDyn(dim).f(), data).map_err(|e| PyValueError::new_err(e.to_string()))?;
+
+    // SAFETY: `owner` is a frozen `MatFile`, so `data` is neither moved nor modified while the
+    // array keeps `owner` alive.
+    let array = unsafe { PyArray::borrow_from_array_bound(&view, owner.clone()) };
+    array.getattr("flags")?.setattr("writeable", false)?;
+
+    Ok(array.into_any().unbind())
+}
+
+fn numeric_view(value: &MatlabType, dim: &[usize], owner: &Bound<'_, PyAny>) -> PyResult<PyObject> {
+    match value {
+        MatlabType::U8(v) => view(v, dim, owner),
+        MatlabType::I8(v) => view(v, dim, owner),
+        MatlabType::U16(v) => view(v, dim, owner),
+        MatlabType::I16(v) => view(v, dim, owner),
+        MatlabType::U32(v) => view(v, dim, owner),
+        MatlabType::I32(v) => view(v, dim, owner),
+        MatlabType::U64(v) => view(v, dim, owner),
+        MatlabType::I64(v) => view(v, dim, owner),
+        MatlabType::F32(v) => view(v, dim, owner),
+        MatlabType::F64(v) => view(v, dim, owner),
+        MatlabType::BOOL(v) => view(v, dim, owner),
+        MatlabType::UTF8(v) | MatlabType::UTF16(v) => {
+            let py = owner.py();
+            let rows = dim.first().copied().unwrap_or(0);
+            if rows <= 1 {
+                return Ok(v.iter().collect::<String>().into_py(py));
+            }
+            // Character matrices become a list of their rows
+            let rows: Vec<String> = (0..rows)
+                .map(|i| v.iter().skip(i).step_by(rows).collect())
+                .collect();
+            Ok(rows.into_py(py))
+        }
+        // `MatlabType` is non-exhaustive, types added to matrw later are rejected until supported
+        other => Err(PyTypeError::new_err(match VariableClass::numeric(other) {
+            Some(class) => format!("Class '{class}' is not supported"),
+            None => "Numeric type is not supported".to_string(),
+        })),
+    }
+}
+
+fn to_list(value: &[MatVariable], owner: &Bound<'_, PyAny>) -> PyResult<PyObject> {
+    let items = value
+        .iter()
+        .map(|v| to_python(v, owner))
+        .collect::<PyResult<Vec<_>>>()?;
+    Ok(PyList::new_bound(owner.py(), items).into_any().unbind())
+}
+
+fn to_python(var: &MatVariable, owner: &Bound<'_, PyAny>) -> PyResult<PyObject> {
+    let py = owner.py();
+    match var {
+        MatVariable::NumericArray(arr) => {
+            let real = numeric_view(&arr.value, &arr.dim, owner)?;
+            let Some(imag) = &arr.value_cmp else {
+                return Ok(real);
+            };
+            // Real and imaginary parts are stored separately, complex arrays are copied
+            let imag = numeric_view(imag, &arr.dim, owner)?;
+            let imag = imag.call_method1(py, "__mul__", (PyComplex::from_doubles_bound(py, 0.0, 1.0),))?;
+            real.call_method1(py, "__add__", (imag,))
+        }
+        MatVariable::Structure(s) => {
+            let dict = PyDict::new_bound(py);
+            for (name, v) in s.value.iter() {
+                dict.set_item(name, to_python(v, owner)?)?;
+            }
+            Ok(dict.into_any().unbind())
+        }
+        MatVariable::StructureArray(s) => to_list(&s.value, owner),
+        MatVariable::CellArray(c) => to_list(&c.value, owner),
+        MatVariable::SparseArray(_) => Err(PyTypeError::new_err("Sparse arrays are not supported")),
+        MatVariable::Null | MatVariable::Unsupported => Ok(py.None()),
+        // `MatVariable` is non-exhaustive, variants added to matrw later are rejected until
+        // supported
+        _ => Err(PyTypeError::new_err("Variable type is not supported")),
+    }
+}
+
+// ============================================================================
+// Python to MAT-file
+// ============================================================================
+
+fn numeric_array(dim: Vec<usize>, value: MatlabType, value_cmp: Option<MatlabType>) -> PyResult<MatVariable> {
+    NumericArray::new(dim, value, value_cmp)
+        .map(MatVariable::NumericArray)
+        .map_err(to_pyerr)
+}
+
+/// Dimensions and column-major data of a numpy array.
+fn from_array<T: Element + Copy>(arr: PyReadonlyArrayDyn<'_, T>) -> (Vec<usize>, Vec<T>) {
+    let view = arr.as_array();
+    let dim = match view.shape() {
+        [] => vec![1, 1],
+        [n] => vec![1, *n],
+        shape => shape.to_vec(),
+    };
+    // The transpose reverses the axes, so its logical order is column-major
+    (dim, view.t().iter().copied().collect())
+}
+
+fn from_numpy(obj: &Bound<'_, PyAny>) -> PyResult<MatVariable> {
+    macro_rules! try_real {
+        ($($t:ty),*) => {
+            $(
+            if let Ok(arr) = obj.extract::<PyReadonlyArrayDyn<'_, $t>>() {
+                let (dim, value) = from_array(arr);
+                return numeric_array(dim, MatlabType::from(value), None);
+            }
+            )*
+        };
+    }
+    try_real!(f64, f32, i8, u8, i16, u16, i32, u32, i64, u64, bool);
+
+    if let Ok(arr) = obj.extract::<PyReadonlyArrayDyn<'_, Complex64>>() {
+        let (dim, value) = from_array(arr);
+        let (re, im): (Vec<f64>, Vec<f64>) = value.into_iter().map(|c| (c.re, c.im)).unzip();
+        return numeric_array(dim, MatlabType::from(re), Some(MatlabType::from(im)));
+    }
+
+    Err(PyTypeError::new_err(format!(
+        "Cannot convert '{}' to a MATLAB variable",
+        obj.get_type().name()?
+    )))
+}
+
+fn from_python(obj: &Bound<'_, PyAny>) -> PyResult<MatVariable> {
+    if let Ok(s) = obj.downcast::<PyString>() {
+        return Ok(MatVariable::from(s.to_str()?));
+    }
+    if let Ok(b) = obj.downcast::<PyBool>() {
+        return Ok(MatVariable::from(b.is_true()));
+    }
+    if obj.is_instance_of::<PyLong>() {
+        return Ok(MatVariable::from(obj.extract::<i64>()?));
+    }
+    if obj.is_instance_of::<PyFloat>() {
+        return Ok(MatVariable::from(obj.extract::<f64>()?));
+    }
+    if let Ok(c) = obj.downcast::<PyComplex>() {
+        return Ok(MatVariable::from((c.real(), c.imag())));
+    }
+    if let Ok(dict) = obj.downcast::<PyDict>() {
+        let mut fields = IndexMap::new();
+        for (name, value) in dict.iter() {
+            fields.insert(name.extract::<String>()?, from_python(&value)?);
+        }
+        return Ok(MatVariable::Structure(Structure::new(fields)));
+    }
+    if let Ok(list) = obj.downcast::<PyList>() {
+        let cells = list
+            .iter()
+            .map(|v| from_python(&v))
+            .collect::<PyResult<Vec<_>>>()?;
+        return CellArray::new(vec![1, cells.len()], cells)
+            .map(MatVariable::CellArray)
+            .map_err(to_pyerr);
+    }
+    from_numpy(obj)
+}
+
+// ============================================================================
+// Module
+// ============================================================================
+
+/// Load a MAT-file from `path`.
+#[pyfunction]
+fn load(path: &str) -> PyResult<PyMatFile> {
+    load_matfile(path)
+        .map(|inner| PyMatFile { inner })
+        .map_err(to_pyerr)
+}
+
+/// Load a MAT-file from bytes.
+#[pyfunction]
+fn loads(data: &[u8]) -> PyResult<PyMatFile> {
+    load_matfile_from_u8(data)
+        .map(|inner| PyMatFile { inner })
+        .map_err(to_pyerr)
+}
+
+/// Save the variables of the dict `variables` to `path`.
+///
+/// Raises `ValueError`, if a key is not a valid MATLAB variable name.
+#[pyfunction]
+#[pyo3(signature = (path, variables, compress = false))]
+fn save(path: &str, variables: &Bound<'_, PyDict>, compress: bool) -> PyResult<()> {
+    let mut file = MatFile::new();
+    for (name, value) in variables.iter() {
+        file.try_insert(&name.extract::<String>()?, from_python(&value)?)
+            .map_err(to_pyerr)?;
+    }
+    save_matfile_v7(path, file, compress).map_err(to_pyerr)
+}
+
+#[pymodule]
+#[pyo3(name = "matrw")]
+fn matrw_python(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyMatFile>()?;
+    m.add_function(wrap_pyfunction!(load, m)?)?;
+    m.add_function(wrap_pyfunction!(loads, m)?)?;
+    m.add_function(wrap_pyfunction!(save, m)?)?;
+    Ok(())
+}
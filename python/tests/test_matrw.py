@@ -0,0 +1,72 @@
+import numpy as np
+import pytest
+
+import matrw
+
+
+@pytest.fixture
+def path(tmp_path):
+    return str(tmp_path / "test.mat")
+
+
+def test_numeric_round_trip(path):
+    a = np.arange(6.0).reshape(2, 3)
+    matrw.save(path, {"a": a, "b": np.array([1, 2, 3], dtype=np.int16)})
+
+    mat = matrw.load(path)
+    assert mat.keys() == ["a", "b"]
+    assert len(mat) == 2 and "a" in mat and "c" not in mat
+    np.testing.assert_array_equal(mat["a"], a)
+    # One-dimensional arrays are saved as row vectors
+    assert mat["b"].dtype == np.int16
+    np.testing.assert_array_equal(mat["b"], [[1, 2, 3]])
+
+
+def test_views_are_read_only(path):
+    matrw.save(path, {"a": np.ones((3, 4))})
+    mat = matrw.load(path)
+
+    a = mat["a"]
+    assert not a.flags.writeable
+    with pytest.raises(ValueError):
+        a[0, 0] = 2.0
+    # Views keep the file alive
+    del mat
+    assert a.sum() == 12.0
+
+
+def test_nested_round_trip(path):
+    data = {
+        "s": {"name": "abc", "x": 2.5, "flag": True},
+        "c": ["text", 1, [np.zeros((2, 2))]],
+        "z": np.array([1 + 2j, 3 - 4j]),
+    }
+    matrw.save(path, data, compress=True)
+
+    mat = matrw.load(path)
+    s = mat["s"]
+    assert s["name"] == "abc"
+    assert s["x"].item() == 2.5
+    assert s["flag"].item() is True
+    c = mat["c"]
+    assert c[0] == "text" and c[1].item() == 1
+    np.testing.assert_array_equal(c[2][0], np.zeros((2, 2)))
+    np.testing.assert_array_equal(mat["z"], [[1 + 2j, 3 - 4j]])
+
+
+def test_loads_and_errors(path):
+    matrw.save(path, {"a": 1.0})
+    with open(path, "rb") as f:
+        mat = matrw.loads(f.read())
+    assert mat["a"].item() == 1.0
+
+    with pytest.raises(KeyError):
+        mat["b"]
+    with pytest.raises(TypeError):
+        matrw.save(path, {"a": object()})
+    with pytest.raises(ValueError, match="MATLAB keyword"):
+        matrw.save(path, {"end": 1.0})
+    with pytest.raises(ValueError, match="does not start with a letter"):
+        matrw.save(path, {"1a": 1.0})
+    with pytest.raises((ValueError, OSError)):
+        matrw.loads(b"not a MAT-file")
@@ -0,0 +1,844 @@
+//! C-compatible interface.
+//!
+//! The declarations of all functions are provided in the header `include/matrw.h`, which the
+//! tests of this module check against the functions and their types. Build a
+//! shared or static library with
+//!
+//! ```text
+//! cargo rustc --release --features capi --crate-type cdylib
+//! cargo rustc --release --features capi --crate-type staticlib
+//! ```
+//!
+//! [`MatFile`] and [`MatVariable`] are passed as opaque pointers. A `MatFile` returned by
+//! `matrw_load*` or `matrw_matfile_new` is owned by the caller and must be released with
+//! [`matrw_matfile_free`]. Variables, dimensions and data obtained from a `MatFile` are borrowed
+//! and stay valid until the `MatFile` is modified or released.
+//!
+//! Functions that can fail return `NULL` or a negative value. The reason is available from
+//! [`matrw_last_error`]. Panics don't unwind into the caller, they are reported as such failures.
+
+use std::cell::RefCell;
+use std::ffi::{CStr, CString, c_char, c_int, c_void};
+use std::fs::File;
+use std::io::BufWriter;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::ptr;
+use std::slice;
+
+use crate::interface::fileio::{SaveOptions, load_matfile, load_matfile_from_u8};
+use crate::interface::helper::invalid_variable_name;
+use crate::interface::writer::MatFileWriter;
+use crate::{MatFile, MatVariable, MatlabType, MatrwError, NumericArray, VariableClass};
+
+/// [`VariableClass`] for C, using the values of MATLAB's `mxClassID`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatrwClass {
+    Unknown = 0,
+    Cell = 1,
+    Struct = 2,
+    Logical = 3,
+    Char = 4,
+    Double = 6,
+    Single = 7,
+    Int8 = 8,
+    UInt8 = 9,
+    Int16 = 10,
+    UInt16 = 11,
+    Int32 = 12,
+    UInt32 = 13,
+    Int64 = 14,
+    UInt64 = 15,
+}
+
+impl From<VariableClass> for MatrwClass {
+    /// Sparse arrays and objects are `Unknown`, sparse arrays have the class of their values.
+    fn from(class: VariableClass) -> Self {
+        match class {
+            VariableClass::Double => Self::Double,
+            VariableClass::Single => Self::Single,
+            VariableClass::Int8 => Self::Int8,
+            VariableClass::UInt8 => Self::UInt8,
+            VariableClass::Int16 => Self::Int16,
+            VariableClass::UInt16 => Self::UInt16,
+            VariableClass::Int32 => Self::Int32,
+            VariableClass::UInt32 => Self::UInt32,
+            VariableClass::Int64 => Self::Int64,
+            VariableClass::UInt64 => Self::UInt64,
+            VariableClass::Char => Self::Char,
+            VariableClass::Logical => Self::Logical,
+            VariableClass::Cell => Self::Cell,
+            VariableClass::Struct => Self::Struct,
+            VariableClass::Sparse | VariableClass::Object => Self::Unknown,
+        }
+    }
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_error(msg: impl ToString) {
+    let msg = CString::new(msg.to_string().replace('\0', " ")).expect("No interior nul bytes");
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some(msg));
+}
+
+fn fail<T>(err: MatrwError, value: T) -> T {
+    set_error(err);
+    value
+}
+
+/// Run `f`, returning `fallback` and setting the last error, if it panics. Panics must not
+/// unwind into the calling C code.
+fn guard<T>(fallback: T, f: impl FnOnce() -> T) -> T {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(value) => value,
+        Err(panic) => {
+            let msg = panic
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_default();
+            set_error(format!("Internal error: {}", msg));
+            fallback
+        }
+    }
+}
+
+unsafe fn str_arg<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        set_error("Argument is NULL");
+        return None;
+    }
+    match unsafe { CStr::from_ptr(s) }.to_str() {
+        Ok(s) => Some(s),
+        Err(_) => {
+            set_error("Argument is not valid UTF-8");
+            None
+        }
+    }
+}
+
+/// Numeric array behind `var`, which must be `NULL` or a valid variable outliving `'a`
+unsafe fn numeric<'a>(var: *const MatVariable) -> Option<&'a NumericArray> {
+    match unsafe { var.as_ref() } {
+        Some(MatVariable::NumericArray(arr)) => Some(arr),
+        _ => None,
+    }
+}
+
+fn data_ptr(value: &MatlabType) -> *const c_void {
+    match value {
+        MatlabType::U8(v) => v.as_ptr().cast(),
+        MatlabType::I8(v) => v.as_ptr().cast(),
+        MatlabType::U16(v) => v.as_ptr().cast(),
+        MatlabType::I16(v) => v.as_ptr().cast(),
+        MatlabType::U32(v) => v.as_ptr().cast(),
+        MatlabType::I32(v) => v.as_ptr().cast(),
+        MatlabType::U64(v) => v.as_ptr().cast(),
+        MatlabType::I64(v) => v.as_ptr().cast(),
+        MatlabType::F32(v) => v.as_ptr().cast(),
+        MatlabType::F64(v) => v.as_ptr().cast(),
+        MatlabType::UTF8(v) | MatlabType::UTF16(v) => v.as_ptr().cast(),
+        MatlabType::BOOL(v) => v.as_ptr().cast(),
+    }
+}
+
+/// Copy `n` elements of `class` from `data`.
+unsafe fn read_data(class: MatrwClass, data: *const c_void, n: usize) -> Result<MatlabType, MatrwError> {
+    macro_rules! copy {
+        ($t:ty) => {
+            unsafe { slice::from_raw_parts(data.cast::<$t>(), n) }.to_vec()
+        };
+    }
+    Ok(match class {
+        MatrwClass::UInt8 => MatlabType::U8(copy!(u8)),
+        MatrwClass::Int8 => MatlabType::I8(copy!(i8)),
+        MatrwClass::UInt16 => MatlabType::U16(copy!(u16)),
+        MatrwClass::Int16 => MatlabType::I16(copy!(i16)),
+        MatrwClass::UInt32 => MatlabType::U32(copy!(u32)),
+        MatrwClass::Int32 => MatlabType::I32(copy!(i32)),
+        MatrwClass::UInt64 => MatlabType::U64(copy!(u64)),
+        MatrwClass::Int64 => MatlabType::I64(copy!(i64)),
+        MatrwClass::Single => MatlabType::F32(copy!(f32)),
+        MatrwClass::Double => MatlabType::F64(copy!(f64)),
+        MatrwClass::Logical => MatlabType::BOOL(copy!(u8).into_iter().map(|x| x != 0).collect()),
+        class => {
+            return Err(MatrwError::TypeConstruction(format!(
+                "Cannot create numeric array of class {:?}.",
+                class
+            )));
+        }
+    })
+}
+
+// ============================================================================
+// Errors
+// ============================================================================
+
+/// Message of the last error on the calling thread, or `NULL`.
+///
+/// The message stays valid until the next failing call on the same thread.
+#[unsafe(no_mangle)]
+pub extern "C" fn matrw_last_error() -> *const c_char {
+    guard(ptr::null(), || {
+        LAST_ERROR.with(|e| e.borrow().as_ref().map_or(ptr::null(), |msg| msg.as_ptr()))
+    })
+}
+
+// ============================================================================
+// MatFile
+// ============================================================================
+
+/// Create an empty MAT-file.
+#[unsafe(no_mangle)]
+pub extern "C" fn matrw_matfile_new() -> *mut MatFile {
+    guard(ptr::null_mut(), || Box::into_raw(Box::new(MatFile::new())))
+}
+
+/// Release a MAT-file.
+///
+/// # Safety
+///
+/// `file` must be `NULL` or a pointer returned by this library, which was not released yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn matrw_matfile_free(file: *mut MatFile) {
+    guard((), || {
+        if !file.is_null() {
+            drop(unsafe { Box::from_raw(file) });
+        }
+    })
+}
+
+/// Load a MAT-file from `path`. Returns `NULL` on failure.
+///
+/// # Safety
+///
+/// `path` must be a nul-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn matrw_load(path: *const c_char) -> *mut MatFile {
+    guard(ptr::null_mut(), || {
+        let Some(path) = (unsafe { str_arg(path) }) else {
+            return ptr::null_mut();
+        };
+        match load_matfile(path) {
+            Ok(file) => Box::into_raw(Box::new(file)),
+            Err(err) => fail(err, ptr::null_mut()),
+        }
+    })
+}
+
+/// Load a MAT-file from `len` bytes at `data`. Returns `NULL` on failure.
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn matrw_load_from_buffer(data: *const u8, len: usize) -> *mut MatFile {
+    guard(ptr::null_mut(), || {
+        if data.is_null() {
+            set_error("Argument is NULL");
+            return ptr::null_mut();
+        }
+        match load_matfile_from_u8(unsafe { slice::from_raw_parts(data, len) }) {
+            Ok(file) => Box::into_raw(Box::new(file)),
+            Err(err) => fail(err, ptr::null_mut()),
+        }
+    })
+}
+
+/// Save `file` to `path`, optionally compressed. Returns `0` on success, `-1` on failure.
+///
+/// # Safety
+///
+/// `file` must be a valid MAT-file and `path` a nul-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn matrw_save(file: *const MatFile, path: *const c_char, compress: bool) -> c_int {
+    guard(-1, || {
+        let (Some(file), Some(path)) = (unsafe { file.as_ref() }, unsafe { str_arg(path) }) else {
+            set_error("Argument is NULL");
+            return -1;
+        };
+
+        match save(file, path, compress) {
+            Ok(()) => 0,
+            Err(err) => fail(err, -1),
+        }
+    })
+}
+
+/// Write the variables of `file` one by one, so that only one of them is copied at a time.
+fn save(file: &MatFile, path: &str, compress: bool) -> Result<(), MatrwError> {
+    let writer = BufWriter::new(File::create(path)?);
+    let mut writer = MatFileWriter::new(writer, &SaveOptions::new().compress(compress))?;
+    for entry in file.try_iter() {
+        let (name, var) = entry?;
+        writer.write_variable(name, var.clone())?;
+    }
+    writer.finish()?;
+    Ok(())
+}
+
+/// Number of variables in `file`.
+///
+/// # Safety
+///
+/// `file` must be a valid MAT-file.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn matrw_matfile_num_variables(file: *const MatFile) -> usize {
+    guard(0, || {
+        unsafe { file.as_ref() }.map_or(0, |file| file.names().len())
+    })
+}
+
+/// Copy the nul-terminated name of variable `index` into `buf` of size `buf_len`.
+///
+/// Returns the length of the name without nul terminator, or `0`, if `index` is out of range.
+/// The name is truncated to a character boundary, if `buf` is too small, so that `buf` holds
+/// valid UTF-8.
+///
+/// # Safety
+///
+/// `file` must be a valid MAT-file and `buf` must point to `buf_len` writable bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn matrw_matfile_variable_name(
+    file: *const MatFile,
+    index: usize,
+    buf: *mut c_char,
+    buf_len: usize,
+) -> usize {
+    guard(0, || {
+        let Some(name) = unsafe { file.as_ref() }.and_then(|file| file.name_at(index)) else {
+            return 0;
+        };
+        if !buf.is_null() && buf_len > 0 {
+            let n = name.floor_char_boundary(buf_len - 1);
+            unsafe {
+                ptr::copy_nonoverlapping(name.as_ptr().cast::<c_char>(), buf, n);
+                *buf.add(n) = 0;
+            }
+        }
+        name.len()
+    })
+}
+
+/// Variable `name` of `file`, or `NULL`, if it does not exist.
+///
+/// # Safety
+///
+/// `file` must be a valid MAT-file and `name` a nul-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn matrw_matfile_get(file: *const MatFile, name: *const c_char) -> *const MatVariable {
+    guard(ptr::null(), || {
+        match (unsafe { file.as_ref() }, unsafe { str_arg(name) }) {
+            (Some(file), Some(name)) => file.get(name).map_or(ptr::null(), |var| var as *const _),
+            _ => ptr::null(),
+        }
+    })
+}
+
+/// Insert a numeric or logical array of `class` as variable `name`.
+///
+/// `dims` holds `ndims` dimensions, `real` and, for complex arrays, `imag` hold the data in
+/// column-major order. Logical data is read as one byte per element. `imag` may be `NULL`.
+/// Returns `0` on success, `-1` on failure.
+///
+/// # Safety
+///
+/// `file` must be a valid MAT-file, `name` a nul-terminated string, `dims` must point to `ndims`
+/// values and `real` and `imag` to as many elements of `class` as given by the dimensions,
+/// aligned for the C type of the class.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn matrw_matfile_insert_numeric(
+    file: *mut MatFile,
+    name: *const c_char,
+    class: MatrwClass,
+    dims: *const usize,
+    ndims: usize,
+    real: *const c_void,
+    imag: *const c_void,
+) -> c_int {
+    guard(-1, || {
+        let (Some(file), Some(name)) = (unsafe { file.as_mut() }, unsafe { str_arg(name) }) else {
+            return -1;
+        };
+        if dims.is_null() || real.is_null() {
+            set_error("Argument is NULL");
+            return -1;
+        }
+        if let Some(err) = invalid_variable_name(name) {
+            set_error(err);
+            return -1;
+        }
+
+        let dim = unsafe { slice::from_raw_parts(dims, ndims) }.to_vec();
+        let Some(n) = dim.iter().try_fold(1usize, |n, &d| n.checked_mul(d)) else {
+            set_error("Number of elements exceeds the address space");
+            return -1;
+        };
+        let arr = unsafe { read_data(class, real, n) }.and_then(|value| {
+            let value_cmp = match imag.is_null() {
+                true => None,
+                false => Some(unsafe { read_data(class, imag, n) }?),
+            };
+            NumericArray::new(dim, value, value_cmp)
+        });
+
+        match arr {
+            Ok(arr) => {
+                file.insert(name, MatVariable::NumericArray(arr));
+                0
+            }
+            Err(err) => fail(err, -1),
+        }
+    })
+}
+
+/// Insert the nul-terminated UTF-8 string `value` as character row vector `name`.
+///
+/// Returns `0` on success, `-1` on failure.
+///
+/// # Safety
+///
+/// `file` must be a valid MAT-file, `name` and `value` nul-terminated strings.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn matrw_matfile_insert_string(
+    file: *mut MatFile,
+    name: *const c_char,
+    value: *const c_char,
+) -> c_int {
+    guard(-1, || {
+        let (Some(file), Some(name), Some(value)) =
+            (unsafe { file.as_mut() }, unsafe { str_arg(name) }, unsafe {
+                str_arg(value)
+            })
+        else {
+            return -1;
+        };
+        if let Some(err) = invalid_variable_name(name) {
+            set_error(err);
+            return -1;
+        }
+
+        file.insert(name, MatVariable::from(value));
+        0
+    })
+}
+
+// ============================================================================
+// MatVariable
+// ============================================================================
+
+/// Class of `var`. Structure arrays are `MATRW_STRUCT`, sparse arrays have the class of their
+/// values.
+///
+/// # Safety
+///
+/// `var` must be `NULL` or a valid variable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn matrw_variable_class(var: *const MatVariable) -> MatrwClass {
+    guard(MatrwClass::Unknown, || match unsafe { var.as_ref() } {
+        Some(MatVariable::SparseArray(arr)) => VariableClass::of_type(&arr.value).into(),
+        Some(var) => VariableClass::of(var).map_or(MatrwClass::Unknown, MatrwClass::from),
+        None => MatrwClass::Unknown,
+    })
+}
+
+/// Return if `var` is a sparse array.
+///
+/// # Safety
+///
+/// `var` must be `NULL` or a valid variable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn matrw_variable_is_sparse(var: *const MatVariable) -> bool {
+    guard(false, || {
+        matches!(unsafe { var.as_ref() }, Some(MatVariable::SparseArray(_)))
+    })
+}
+
+/// Return if `var` is a complex numeric or sparse array.
+///
+/// # Safety
+///
+/// `var` must be `NULL` or a valid variable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn matrw_variable_is_complex(var: *const MatVariable) -> bool {
+    guard(false, || {
+        unsafe { var.as_ref() }
+            .and_then(MatVariable::is_complex)
+            .unwrap_or(false)
+    })
+}
+
+/// Number of dimensions of `var`.
+///
+/// # Safety
+///
+/// `var` must be `NULL` or a valid variable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn matrw_variable_ndims(var: *const MatVariable) -> usize {
+    guard(0, || unsafe { var.as_ref() }.map_or(0, |var| var.dim().len()))
+}
+
+/// Dimensions of `var`, `matrw_variable_ndims` values, or `NULL` for scalar structures and
+/// other variables without stored dimensions.
+///
+/// # Safety
+///
+/// `var` must be `NULL` or a valid variable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn matrw_variable_dims(var: *const MatVariable) -> *const usize {
+    guard(ptr::null(), || match unsafe { var.as_ref() } {
+        Some(MatVariable::NumericArray(arr)) => arr.dim.as_ptr(),
+        Some(MatVariable::SparseArray(arr)) => arr.dim.as_ptr(),
+        Some(MatVariable::CellArray(arr)) => arr.dim.as_ptr(),
+        Some(MatVariable::StructureArray(arr)) => arr.dim.as_ptr(),
+        _ => ptr::null(),
+    })
+}
+
+/// Number of elements of `var`.
+///
+/// # Safety
+///
+/// `var` must be `NULL` or a valid variable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn matrw_variable_numel(var: *const MatVariable) -> usize {
+    guard(0, || unsafe { var.as_ref() }.map_or(0, |var| var.dim().numel()))
+}
+
+/// Real data of a numeric array in column-major order, or `NULL` for other variables.
+///
+/// Elements have the C type of the class. Logical elements are one byte, characters are
+/// 32 bit Unicode code points.
+///
+/// # Safety
+///
+/// `var` must be `NULL` or a valid variable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn matrw_variable_real_data(var: *const MatVariable) -> *const c_void {
+    guard(ptr::null(), || {
+        unsafe { numeric(var) }.map_or(ptr::null(), |arr| data_ptr(&arr.value))
+    })
+}
+
+/// Imaginary data of a complex numeric array, or `NULL` for real arrays and other variables.
+///
+/// # Safety
+///
+/// `var` must be `NULL` or a valid variable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn matrw_variable_imag_data(var: *const MatVariable) -> *const c_void {
+    guard(ptr::null(), || {
+        unsafe { numeric(var) }
+            .and_then(|arr| arr.value_cmp.as_ref())
+            .map_or(ptr::null(), data_ptr)
+    })
+}
+
+/// Field `name` of a scalar structure, or `NULL`.
+///
+/// # Safety
+///
+/// `var` must be `NULL` or a valid variable and `name` a nul-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn matrw_variable_field(
+    var: *const MatVariable,
+    name: *const c_char,
+) -> *const MatVariable {
+    guard(ptr::null(), || {
+        match (unsafe { var.as_ref() }, unsafe { str_arg(name) }) {
+            (Some(var), Some(name)) => var.get(name).map_or(ptr::null(), |v| v as *const _),
+            _ => ptr::null(),
+        }
+    })
+}
+
+/// Element `index` in column-major order of a cell or structure array, or `NULL`.
+///
+/// # Safety
+///
+/// `var` must be `NULL` or a valid variable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn matrw_variable_element(var: *const MatVariable, index: usize) -> *const MatVariable {
+    guard(ptr::null(), || {
+        unsafe { var.as_ref() }
+            .and_then(|var| var.get(index))
+            .map_or(ptr::null(), |v| v as *const _)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{matfile, matvar, save_matfile_v7_to_u8};
+
+    #[test]
+    fn load_and_access() {
+        let data = save_matfile_v7_to_u8(
+            matfile!(
+                x: matvar!([[1.0, 2.0], [3.0, 4.0]]),
+                s: matvar!({ n: 7i32 }),
+                c: matvar!(["ab", 1.0]),
+            ),
+            true,
+        )
+        .unwrap();
+
+        unsafe {
+            let file = matrw_load_from_buffer(data.as_ptr(), data.len());
+            assert!(!file.is_null());
+            assert_eq!(matrw_matfile_num_variables(file), 3);
+
+            let mut buf = [0 as c_char; 2];
+            assert_eq!(matrw_matfile_variable_name(file, 0, buf.as_mut_ptr(), 2), 1);
+            assert_eq!(CStr::from_ptr(buf.as_ptr()).to_str(), Ok("x"));
+
+            let x = matrw_matfile_get(file, c"x".as_ptr());
+            assert_eq!(matrw_variable_class(x), MatrwClass::Double);
+            assert_eq!(
+                slice::from_raw_parts(matrw_variable_dims(x), matrw_variable_ndims(x)),
+                [2, 2]
+            );
+            let real = matrw_variable_real_data(x).cast::<f64>();
+            assert_eq!(
+                slice::from_raw_parts(real, matrw_variable_numel(x)),
+                [1.0, 3.0, 2.0, 4.0]
+            );
+            assert!(matrw_variable_imag_data(x).is_null());
+
+            let s = matrw_matfile_get(file, c"s".as_ptr());
+            assert_eq!(matrw_variable_class(s), MatrwClass::Struct);
+            let n = matrw_variable_field(s, c"n".as_ptr());
+            assert_eq!(*matrw_variable_real_data(n).cast::<i32>(), 7);
+
+            let c = matrw_matfile_get(file, c"c".as_ptr());
+            assert_eq!(
+                matrw_variable_class(matrw_variable_element(c, 0)),
+                MatrwClass::Char
+            );
+            assert!(matrw_variable_element(c, 2).is_null());
+
+            assert!(matrw_matfile_get(file, c"y".as_ptr()).is_null());
+            matrw_matfile_free(file);
+        }
+    }
+
+    #[test]
+    fn insert_and_errors() {
+        unsafe {
+            let file = matrw_matfile_new();
+
+            let dims = [1usize, 2];
+            let real = [1i16, 2];
+            let imag = [3i16, 4];
+            let ret = matrw_matfile_insert_numeric(
+                file,
+                c"z".as_ptr(),
+                MatrwClass::Int16,
+                dims.as_ptr(),
+                2,
+                real.as_ptr().cast(),
+                imag.as_ptr().cast(),
+            );
+            assert_eq!(ret, 0);
+            assert_eq!(
+                matrw_matfile_insert_string(file, c"t".as_ptr(), c"abc".as_ptr()),
+                0
+            );
+
+            let z = matrw_matfile_get(file, c"z".as_ptr());
+            assert!(matrw_variable_is_complex(z));
+            assert_eq!(*matrw_variable_imag_data(z).cast::<i16>().add(1), 4);
+            assert_eq!((&*file)["t"], matvar!("abc"));
+
+            let ret = matrw_matfile_insert_string(file, c"1t".as_ptr(), c"abc".as_ptr());
+            assert_eq!(ret, -1);
+            assert_eq!(
+                CStr::from_ptr(matrw_last_error()).to_str(),
+                Ok("Invalid variable name '1t', it does not start with a letter")
+            );
+
+            assert!(matrw_load(c"does/not/exist.mat".as_ptr()).is_null());
+            assert!(!matrw_last_error().is_null());
+
+            matrw_matfile_free(file);
+        }
+    }
+
+    #[test]
+    fn overflowing_dims() {
+        unsafe {
+            let file = matrw_matfile_new();
+            let dims = [usize::MAX, 2];
+            let real = [0u8; 2];
+            let ret = matrw_matfile_insert_numeric(
+                file,
+                c"x".as_ptr(),
+                MatrwClass::UInt8,
+                dims.as_ptr(),
+                2,
+                real.as_ptr().cast(),
+                ptr::null(),
+            );
+            assert_eq!(ret, -1);
+            assert_eq!(
+                CStr::from_ptr(matrw_last_error()).to_str(),
+                Ok("Number of elements exceeds the address space")
+            );
+            matrw_matfile_free(file);
+        }
+    }
+
+    #[test]
+    fn truncated_names_are_utf8() {
+        let mut file = MatFile::new();
+        file.insert_unchecked("xé", matvar!(1.0));
+        file.insert_unchecked("y", matvar!(2.0));
+
+        unsafe {
+            let mut buf = [1 as c_char; 3];
+            assert_eq!(matrw_matfile_variable_name(&file, 0, buf.as_mut_ptr(), 3), 3);
+            assert_eq!(CStr::from_ptr(buf.as_ptr()).to_str(), Ok("x"));
+            assert_eq!(matrw_matfile_variable_name(&file, 1, buf.as_mut_ptr(), 3), 1);
+            assert_eq!(CStr::from_ptr(buf.as_ptr()).to_str(), Ok("y"));
+            assert_eq!(matrw_matfile_variable_name(&file, 2, buf.as_mut_ptr(), 3), 0);
+        }
+    }
+
+    #[test]
+    fn guard_catches_panics() {
+        assert_eq!(guard(-1, || panic!("boom")), -1);
+        let msg = unsafe { CStr::from_ptr(matrw_last_error()) };
+        assert_eq!(msg.to_str(), Ok("Internal error: boom"));
+    }
+
+    /// C type of the Rust type `ty` used in the signatures of this module
+    fn c_type(ty: &str) -> &'static str {
+        match ty {
+            "" => "void",
+            "*const c_char" => "const char*",
+            "*mut c_char" => "char*",
+            "*const MatFile" => "const MatrwFile*",
+            "*mut MatFile" => "MatrwFile*",
+            "*const MatVariable" => "const MatrwVariable*",
+            "*const usize" => "const size_t*",
+            "*const u8" => "const uint8_t*",
+            "*const c_void" => "const void*",
+            "usize" => "size_t",
+            "c_int" => "int",
+            "bool" => "bool",
+            "MatrwClass" => "MatrwClass",
+            _ => panic!("No C type for {}", ty),
+        }
+    }
+
+    /// Remove the whitespace around `*` and collapse the remaining whitespace.
+    fn normalize(decl: &str) -> String {
+        decl.split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .replace(" *", "*")
+            .replace("* ", "*")
+    }
+
+    /// Strip the parameter name from the C declaration `param`.
+    fn c_param_type(param: &str) -> String {
+        let param = normalize(param);
+        let end = param.trim_end_matches(|c: char| c.is_alphanumeric() || c == '_');
+        end.trim_end().to_string()
+    }
+
+    #[test]
+    fn header_matches_functions() {
+        let header = include_str!("../include/matrw.h");
+        let source = include_str!("capi.rs");
+
+        // Signatures declared in this module, as `(name, return type, parameter types)` in C
+        let mut functions = Vec::new();
+        for part in source.split("extern \"C\" fn ").skip(1) {
+            let (name, rest) = part.split_once('(').unwrap();
+            let (params, rest) = rest.split_once(')').unwrap();
+            let ret = rest
+                .split_once('{')
+                .unwrap()
+                .0
+                .trim()
+                .trim_start_matches("->")
+                .trim();
+            let params: Vec<&str> = params
+                .split(',')
+                .map(str::trim)
+                .filter(|p| !p.is_empty())
+                .map(|p| c_type(p.split_once(": ").unwrap().1))
+                .collect();
+            functions.push((name.to_string(), c_type(ret).to_string(), params));
+        }
+        assert!(!functions.is_empty());
+
+        // Prototypes in the header, without comments
+        let code: String = header
+            .lines()
+            .filter(|l| !l.trim_start().starts_with("/*") && !l.trim_start().starts_with('#'))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let mut prototypes = Vec::new();
+        for decl in code
+            .split(';')
+            .filter(|d| d.contains("matrw_") && d.contains('('))
+        {
+            let (head, params) = decl.split_once('(').unwrap();
+            let head = normalize(head);
+            let name_start = head.rfind(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap() + 1;
+            let params = params.trim().trim_end_matches(')');
+            let params: Vec<String> = match params.trim() {
+                "void" => Vec::new(),
+                params => params.split(',').map(c_param_type).collect(),
+            };
+            prototypes.push((
+                head[name_start..].to_string(),
+                head[..name_start].trim().to_string(),
+                params,
+            ));
+        }
+
+        assert_eq!(prototypes.len(), functions.len(), "{prototypes:?}");
+        for (name, ret, params) in &functions {
+            let prototype = prototypes.iter().find(|p| &p.0 == name);
+            let Some((_, c_ret, c_params)) = prototype else {
+                panic!("{} missing in header", name);
+            };
+            assert_eq!(c_ret, ret, "return type of {}", name);
+            assert_eq!(c_params, params, "parameters of {}", name);
+        }
+    }
+
+    #[test]
+    fn header_matches_class_values() {
+        let header = include_str!("../include/matrw.h");
+        for class in [
+            MatrwClass::Unknown,
+            MatrwClass::Cell,
+            MatrwClass::Struct,
+            MatrwClass::Logical,
+            MatrwClass::Char,
+            MatrwClass::Double,
+            MatrwClass::Single,
+            MatrwClass::Int8,
+            MatrwClass::UInt8,
+            MatrwClass::Int16,
+            MatrwClass::UInt16,
+            MatrwClass::Int32,
+            MatrwClass::UInt32,
+            MatrwClass::Int64,
+            MatrwClass::UInt64,
+        ] {
+            let entry = format!(
+                "MATRW_{} = {}",
+                format!("{:?}", class).to_uppercase(),
+                class as i32
+            );
+            assert!(header.contains(&entry), "{} missing in header", entry);
+        }
+    }
+}
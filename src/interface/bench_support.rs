@@ -0,0 +1,270 @@
+//! Synthetic MAT-files for benchmarks.
+//!
+//! Each [`Workload`] generates a [`MatFile`] of a typical shape, scaled by a size parameter. The
+//! data is pseudo-random but deterministic, so the same workload and size always give the same
+//! file. The benchmarks of matrw are built on these workloads, [`measure`] allows to reproduce
+//! them without criterion:
+//!
+//! ```
+//! use matrw::SaveOptions;
+//! use matrw::bench_support::{Workload, measure};
+//!
+//! for workload in Workload::ALL {
+//!     let report = measure(workload, 10, &SaveOptions::new()).unwrap();
+//!     println!("{report}");
+//! }
+//! ```
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use indexmap::IndexMap;
+
+use crate::interface::fileio::{SaveOptions, load_matfile_from_u8, save_matfile_v7_to_u8_with_options};
+use crate::{
+    CellArray, MatFile, MatVariable, MatlabType, MatrwError, NumericArray, SparseArray, Structure,
+    StructureArray, matvar,
+};
+
+/// Nonzero elements per column of [`Workload::Sparse`]
+const SPARSE_COLUMN_NNZ: usize = 10;
+
+/// Shape of a synthetic MAT-file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Workload {
+    /// One dense `size` x `size` double matrix
+    LargeMatrix,
+    /// One 1 x `size` structure array of records with a scalar, a vector, a string and a logical
+    StructureArray,
+    /// One 1 x `size` cell array of vectors, strings and nested cells
+    CellArray,
+    /// One `size` x `size` sparse double matrix with up to 10 nonzero elements per column
+    Sparse,
+    /// `size` variables, each a small structure
+    ManySmallVariables,
+    /// `size` scalar variables of the numeric classes, like a dump of parameters
+    Scalars,
+}
+
+impl Workload {
+    /// All workloads
+    pub const ALL: [Workload; 6] = [
+        Workload::LargeMatrix,
+        Workload::StructureArray,
+        Workload::CellArray,
+        Workload::Sparse,
+        Workload::ManySmallVariables,
+        Workload::Scalars,
+    ];
+
+    /// Name of the workload, as used in benchmark ids
+    pub fn name(&self) -> &'static str {
+        match self {
+            Workload::LargeMatrix => "large-matrix",
+            Workload::StructureArray => "structure-array",
+            Workload::CellArray => "cell-array",
+            Workload::Sparse => "sparse",
+            Workload::ManySmallVariables => "many-small-variables",
+            Workload::Scalars => "scalars",
+        }
+    }
+
+    /// Generate the MAT-file of the workload with the size parameter `size`.
+    pub fn generate(&self, size: usize) -> MatFile {
+        let mut rng = SplitMix64(size as u64);
+        let mut matfile = MatFile::new();
+        match self {
+            Workload::LargeMatrix => {
+                let data = (0..size * size).map(|_| rng.next_f64()).collect();
+                matfile.insert("matrix", numeric(vec![size, size], MatlabType::F64(data)));
+            }
+            Workload::StructureArray => {
+                let records = (0..size).map(|i| record(i, &mut rng)).collect();
+                let records = StructureArray::from_structures(vec![1, size], records).unwrap();
+                matfile.insert("records", MatVariable::StructureArray(records));
+            }
+            Workload::CellArray => {
+                let value = (0..size).map(|i| cell_element(i, &mut rng)).collect();
+                let cell = CellArray::new(vec![1, size], value).unwrap();
+                matfile.insert("cells", MatVariable::CellArray(cell));
+            }
+            Workload::Sparse => {
+                let mut ir = Vec::new();
+                let mut jc = vec![0];
+                for _ in 0..size {
+                    let mut rows: Vec<usize> = (0..SPARSE_COLUMN_NNZ.min(size))
+                        .map(|_| rng.next_below(size))
+                        .collect();
+                    rows.sort_unstable();
+                    rows.dedup();
+                    ir.extend(rows);
+                    jc.push(ir.len());
+                }
+                let value = (0..ir.len()).map(|_| rng.next_f64()).collect();
+                let sparse = SparseArray::new(size, size, ir, jc, MatlabType::F64(value), None).unwrap();
+                matfile.insert("sparse", MatVariable::SparseArray(sparse));
+            }
+            Workload::ManySmallVariables => {
+                for i in 0..size {
+                    matfile.insert(&format!("s{i}"), record(i, &mut rng));
+                }
+            }
+            Workload::Scalars => {
+                for i in 0..size {
+                    let x = rng.next_f64();
+                    let var = match i % 4 {
+                        0 | 1 => matvar!(x),
+                        2 => matvar!(i as f64),
+                        _ => matvar!((x * 1000.0) as i32),
+                    };
+                    matfile.insert(&format!("p{i}"), var);
+                }
+            }
+        }
+        matfile
+    }
+}
+
+impl fmt::Display for Workload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Result of [`measure`].
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub workload: Workload,
+    /// Size parameter of the workload
+    pub size: usize,
+    /// Size of the written MAT-file in bytes
+    pub bytes: usize,
+    /// Time to write the file into memory
+    pub write: Duration,
+    /// Time to read the file from memory
+    pub read: Duration,
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} (size {}): {} bytes, write {:?}, read {:?}",
+            self.workload, self.size, self.bytes, self.write, self.read
+        )
+    }
+}
+
+///
+/// Write the file of `workload` with the size parameter `size` and `options` into memory, read
+/// it again and report the size of the file and the time taken.
+///
+/// Generating the file is not part of the measured time. A single run is measured, use the
+/// benchmarks of matrw for statistically sound measurements.
+///
+pub fn measure(workload: Workload, size: usize, options: &SaveOptions) -> Result<Report, MatrwError> {
+    let matfile = workload.generate(size);
+
+    let start = Instant::now();
+    let data = save_matfile_v7_to_u8_with_options(matfile, options)?;
+    let write = start.elapsed();
+
+    let start = Instant::now();
+    load_matfile_from_u8(&data)?;
+    let read = start.elapsed();
+
+    Ok(Report {
+        workload,
+        size,
+        bytes: data.len(),
+        write,
+        read,
+    })
+}
+
+/// Small structure, like a record of a measurement
+fn record(i: usize, rng: &mut SplitMix64) -> MatVariable {
+    let position = [rng.next_f64(), rng.next_f64(), rng.next_f64()];
+    let mut map = IndexMap::new();
+    map.insert("id".to_string(), matvar!(i as f64));
+    map.insert(
+        "position".to_string(),
+        numeric(vec![1, 3], MatlabType::F64(position.to_vec())),
+    );
+    map.insert("label".to_string(), matvar!(format!("record {i}").as_str()));
+    map.insert("valid".to_string(), matvar!(!i.is_multiple_of(3)));
+    MatVariable::Structure(Structure::new(map))
+}
+
+/// Element of [`Workload::CellArray`], cycling through vectors, strings and nested cells
+fn cell_element(i: usize, rng: &mut SplitMix64) -> MatVariable {
+    match i % 3 {
+        0 => {
+            let len = 1 + i % 16;
+            numeric(
+                vec![1, len],
+                MatlabType::F64((0..len).map(|_| rng.next_f64()).collect()),
+            )
+        }
+        1 => matvar!(format!("element {i}").as_str()),
+        _ => {
+            let value = vec![matvar!(i as f64), matvar!(format!("nested {i}").as_str())];
+            MatVariable::CellArray(CellArray::new(vec![1, 2], value).unwrap())
+        }
+    }
+}
+
+fn numeric(dim: Vec<usize>, value: MatlabType) -> MatVariable {
+    MatVariable::NumericArray(NumericArray::new(dim, value, None).unwrap())
+}
+
+/// Deterministic pseudo-random numbers, see <https://prng.di.unimi.it/splitmix64.c>
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in [0, 1)
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn next_below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::save_matfile_v7_to_u8;
+
+    #[test]
+    fn workloads_roundtrip() {
+        for workload in Workload::ALL {
+            let matfile = workload.generate(20);
+            let data = save_matfile_v7_to_u8(workload.generate(20), false).unwrap();
+            let loaded = load_matfile_from_u8(&data).unwrap();
+
+            assert_eq!(loaded.iter().count(), matfile.iter().count(), "{workload}");
+            for (name, var) in matfile.iter() {
+                assert_eq!(&loaded[name.as_str()], var, "{workload}: {name}");
+            }
+        }
+        assert_eq!(Workload::ManySmallVariables.generate(20).iter().count(), 20);
+    }
+
+    #[test]
+    fn measure_reports_size() {
+        let report = measure(Workload::Sparse, 20, &SaveOptions::new()).unwrap();
+        let data = save_matfile_v7_to_u8(Workload::Sparse.generate(20), false).unwrap();
+        assert_eq!(report.bytes, data.len());
+        assert!(report.to_string().starts_with("sparse (size 20): "));
+    }
+}
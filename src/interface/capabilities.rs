@@ -0,0 +1,197 @@
+//! Description of what this build of matrw can read and write.
+//!
+//! [`capabilities`] lists the supported MAT-file versions and array classes and the cargo
+//! features compiled in, so that an application can tell its users which contents of a file its
+//! build of matrw doesn't support, e.g. together with [`MatFile::skip_report`].
+//!
+//! [`MatFile::skip_report`]: crate::MatFile::skip_report
+
+use std::fmt;
+
+use crate::interface::types::matlab_types::VariableClass;
+
+/// Version of the MAT-file format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum FormatVersion {
+    /// Level 4 MAT-files of MATLAB 4 and older
+    V4,
+    /// Level 5 MAT-files without compression, written by MATLAB's `save -v6`
+    V6,
+    /// Level 5 MAT-files with compression and Unicode characters, written by MATLAB's `save -v7`
+    V7,
+    /// HDF5-based MAT-files, written by MATLAB's `save -v7.3`
+    V73,
+}
+
+impl fmt::Display for FormatVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::V4 => write!(f, "v4"),
+            Self::V6 => write!(f, "v6"),
+            Self::V7 => write!(f, "v7"),
+            Self::V73 => write!(f, "v7.3"),
+        }
+    }
+}
+
+/// Support of one MAT-file version, see [`Capabilities::formats`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct FormatSupport {
+    /// MAT-file version described
+    pub version: FormatVersion,
+    /// Files of this version can be loaded
+    pub read: bool,
+    /// Files of this version can be saved
+    pub write: bool,
+}
+
+/// What this build of matrw supports, see [`capabilities`].
+///
+/// The struct is `#[non_exhaustive]`, so that later versions can report more.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Capabilities {
+    /// Version of matrw
+    pub version: &'static str,
+    /// Support of each MAT-file version
+    pub formats: Vec<FormatSupport>,
+    /// Classes read and written. Objects are read as [`crate::MatVariable::Unsupported`].
+    pub classes: Vec<VariableClass>,
+    /// Compressed variables written by MATLAB can be read, which requires one of the `deflate`
+    /// features. Without them, only the stored deflate blocks matrw then writes are read.
+    pub decompression: bool,
+    /// Cargo features compiled in
+    pub features: Vec<&'static str>,
+}
+
+impl Capabilities {
+    /// Whether files of `version` can be loaded
+    pub fn reads_version(&self, version: FormatVersion) -> bool {
+        self.formats.iter().any(|f| f.version == version && f.read)
+    }
+
+    /// Whether files of `version` can be saved
+    pub fn writes_version(&self, version: FormatVersion) -> bool {
+        self.formats.iter().any(|f| f.version == version && f.write)
+    }
+
+    /// Whether arrays of `class` are read and written
+    pub fn supports_class(&self, class: VariableClass) -> bool {
+        self.classes.contains(&class)
+    }
+
+    /// Whether the cargo feature `name` is compiled in
+    pub fn has_feature(&self, name: &str) -> bool {
+        self.features.contains(&name)
+    }
+}
+
+/// Cargo features, which change what matrw can do, with whether they are compiled in
+const FEATURES: [(&str, bool); 14] = [
+    ("deflate", cfg!(feature = "deflate")),
+    ("deflate-miniz", cfg!(feature = "deflate-miniz")),
+    ("deflate-zlib-ng", cfg!(feature = "deflate-zlib-ng")),
+    ("deflate-zlib-rs", cfg!(feature = "deflate-zlib-rs")),
+    ("serde", cfg!(feature = "serde")),
+    ("chrono", cfg!(feature = "chrono")),
+    ("num-complex", cfg!(feature = "num-complex")),
+    ("regex", cfg!(feature = "regex")),
+    ("toml", cfg!(feature = "toml")),
+    ("yaml", cfg!(feature = "yaml")),
+    ("xlsx", cfg!(feature = "xlsx")),
+    ("zip", cfg!(feature = "zip")),
+    ("capi", cfg!(feature = "capi")),
+    ("testing", cfg!(feature = "testing")),
+];
+
+///
+/// Report the MAT-file versions, array classes and cargo features supported by this build.
+///
+/// Example
+/// ```
+/// use matrw::{FormatVersion, VariableClass, capabilities};
+///
+/// let caps = capabilities();
+/// assert!(caps.reads_version(FormatVersion::V7));
+/// assert!(!caps.reads_version(FormatVersion::V73));
+/// assert!(caps.supports_class(VariableClass::Sparse));
+/// assert!(!caps.supports_class(VariableClass::Object));
+/// assert_eq!(caps.has_feature("serde"), cfg!(feature = "serde"));
+/// ```
+///
+pub fn capabilities() -> Capabilities {
+    use VariableClass::*;
+
+    let format = |version, supported| FormatSupport {
+        version,
+        read: supported,
+        write: supported,
+    };
+
+    Capabilities {
+        version: env!("CARGO_PKG_VERSION"),
+        formats: vec![
+            format(FormatVersion::V4, false),
+            format(FormatVersion::V6, true),
+            format(FormatVersion::V7, true),
+            format(FormatVersion::V73, false),
+        ],
+        classes: vec![
+            Double, Single, Int8, UInt8, Int16, UInt16, Int32, UInt32, Int64, UInt64, Char, Logical, Cell,
+            Struct, Sparse,
+        ],
+        decompression: cfg!(feature = "deflate"),
+        features: FEATURES
+            .iter()
+            .filter(|(_, enabled)| *enabled)
+            .map(|(name, _)| *name)
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SaveOptions, load_matfile_from_u8, matfile, matvar, save_matfile_v7_to_u8_with_options};
+
+    #[test]
+    fn classes_round_trip() {
+        let caps = capabilities();
+        let matfile = || {
+            matfile!(
+                a: matvar!({ x: [1u8, 2u8], y: "ab" }),
+                b: matvar!([1.0, 2.0]).to_sparse().unwrap(),
+                c: matvar!([1i64, "cell"]),
+            )
+        };
+        for options in [SaveOptions::new(), SaveOptions::new().v6()] {
+            let data = save_matfile_v7_to_u8_with_options(matfile(), &options).unwrap();
+            let loaded = load_matfile_from_u8(&data).unwrap();
+            assert!(loaded.skip_report().is_empty());
+        }
+        for class in [
+            VariableClass::Struct,
+            VariableClass::Cell,
+            VariableClass::Char,
+            VariableClass::UInt8,
+            VariableClass::Int64,
+            VariableClass::Sparse,
+        ] {
+            assert!(caps.supports_class(class), "{class}");
+        }
+        assert!(!caps.supports_class(VariableClass::Object));
+    }
+
+    #[test]
+    fn versions_and_features() {
+        let caps = capabilities();
+        assert!(caps.writes_version(FormatVersion::V6));
+        assert!(!caps.writes_version(FormatVersion::V4));
+        assert_eq!(FormatVersion::V73.to_string(), "v7.3");
+        assert_eq!(caps.decompression, caps.has_feature("deflate"));
+        assert_eq!(caps.has_feature("xlsx"), cfg!(feature = "xlsx"));
+        assert!(!caps.has_feature("full"));
+    }
+}
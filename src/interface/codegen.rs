@@ -0,0 +1,327 @@
+//! Generate Rust type definitions from MAT-file contents.
+
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use crate::MatFile;
+use crate::MatlabType;
+use crate::interface::types::structure::Structure;
+use crate::interface::variable::MatVariable;
+
+/// Name of the generated top-level struct.
+const ROOT_STRUCT: &str = "MatFileData";
+
+/// Names of types the generated code refers to, which generated structs must not shadow.
+const RESERVED_TYPES: [&str; 8] = [
+    "String",
+    "Vec",
+    "Option",
+    "Box",
+    "Result",
+    "Serialize",
+    "Deserialize",
+    "Debug",
+];
+
+/// Rust keywords which can't be used as field names without escaping.
+const KEYWORDS: [&str; 51] = [
+    "as", "break", "const", "continue", "crate", "else", "enum", "extern", "false", "fn", "for", "if",
+    "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "self", "Self",
+    "static", "struct", "super", "trait", "true", "type", "unsafe", "use", "where", "while", "async",
+    "await", "dyn", "abstract", "become", "box", "do", "final", "macro", "override", "priv", "typeof",
+    "unsized", "virtual", "yield", "try",
+];
+
+/// Infer Rust struct definitions matching the variables of a [`MatFile`].
+///
+/// The returned source code contains a struct `MatFileData` with one field per variable and one
+/// additional struct per nested structure, all deriving [`serde::Serialize`] and
+/// [`serde::Deserialize`], so that it can be used with [`crate::from_matfile`] and
+/// [`crate::to_matfile`]. Scalars map to primitive types, vectors to `Vec<_>`, matrices to
+/// `Vec<Vec<_>>`, character arrays to `String`, cell arrays of character arrays to
+/// `Vec<String>` and sparse arrays to [`crate::SparseMatrix`]. Variables not supported by the
+/// typed interface are emitted as comments.
+///
+/// ```
+/// use matrw::{matfile, matvar};
+/// use matrw::codegen::infer_schema;
+///
+/// let matfile = matfile!(
+///     a: matvar!([1.0, 2.0, 3.0]),
+///     s: matvar!({ name: "x", n: 3u8 }),
+/// );
+///
+/// let code = infer_schema(&matfile);
+///
+/// assert!(code.contains("pub struct MatFileData {"));
+/// assert!(code.contains("    pub a: Vec<f64>,"));
+/// assert!(code.contains("    pub s: S,"));
+/// assert!(code.contains("    pub name: String,"));
+/// assert!(code.contains("    pub n: u8,"));
+/// ```
+pub fn infer_schema(matfile: &MatFile) -> String {
+    let mut generator = Generator::default();
+    generator.used_names.insert(ROOT_STRUCT.to_string());
+    generator
+        .used_names
+        .extend(RESERVED_TYPES.iter().map(|name| name.to_string()));
+
+    let fields: Vec<(&String, &MatVariable)> = matfile.iter().collect();
+    generator.add_struct(ROOT_STRUCT.to_string(), &fields);
+
+    let mut code = String::from("use serde::{Deserialize, Serialize};\n");
+    for def in generator.structs {
+        code.push('\n');
+        code.push_str(&def);
+    }
+
+    code
+}
+
+#[derive(Default)]
+struct Generator {
+    structs: Vec<String>,
+    used_names: HashSet<String>,
+}
+
+impl Generator {
+    /// Emit a struct definition. Nested structures are emitted after their parent.
+    fn add_struct(&mut self, name: String, fields: &[(&String, &MatVariable)]) {
+        let index = self.structs.len();
+        self.structs.push(String::new());
+
+        let mut def = String::new();
+        if fields
+            .iter()
+            .any(|(f, _)| f.chars().any(|c| c.is_ascii_uppercase()))
+        {
+            def.push_str("#[allow(non_snake_case)]\n");
+        }
+        def.push_str("#[derive(Debug, Serialize, Deserialize)]\n");
+        let _ = writeln!(def, "pub struct {} {{", name);
+
+        for (field, value) in fields {
+            match self.field_type(field, value) {
+                Ok(ty) => {
+                    let (attr, ident) = field_ident(field);
+                    if let Some(attr) = attr {
+                        let _ = writeln!(def, "    {}", attr);
+                    }
+                    let _ = writeln!(def, "    pub {}: {},", ident, ty);
+                }
+                Err(reason) => {
+                    let _ = writeln!(
+                        def,
+                        "    // {}: {} not supported by the typed interface",
+                        field, reason
+                    );
+                }
+            }
+        }
+        def.push_str("}\n");
+
+        self.structs[index] = def;
+    }
+
+    /// Type of a field, or a description of the variable if it can't be represented.
+    fn field_type(&mut self, field: &str, value: &MatVariable) -> Result<String, &'static str> {
+        match value {
+            MatVariable::NumericArray(arr) => {
+                let ty = match arr.value {
+                    MatlabType::UTF8(_) | MatlabType::UTF16(_) if arr.dim.len() == 2 && arr.dim[0] > 1 => {
+                        return Ok("Vec<String>".to_string());
+                    }
+                    MatlabType::UTF8(_) | MatlabType::UTF16(_) => return Ok("String".to_string()),
+                    _ => element_type(&arr.value),
+                };
+                if arr.is_scalar() {
+                    Ok(ty.to_string())
+                } else if arr.dim.len() == 2 && arr.dim.iter().all(|&d| d > 1) {
+                    Ok(format!("Vec<Vec<{}>>", ty))
+                } else {
+                    Ok(format!("Vec<{}>", ty))
+                }
+            }
+            MatVariable::SparseArray(arr) if arr.is_complex() => Err("complex sparse array"),
+            MatVariable::SparseArray(arr) => Ok(format!("matrw::SparseMatrix<{}>", element_type(&arr.value))),
+            MatVariable::Structure(s) => Ok(self.nested_struct(field, s)),
+            MatVariable::StructureArray(_) => Err("structure array"),
+            MatVariable::CellArray(cell) if !cell.value.is_empty() && cell.value.iter().all(is_char) => {
+                Ok("Vec<String>".to_string())
+            }
+            MatVariable::CellArray(_) => Err("cell array"),
+            MatVariable::Null | MatVariable::Unsupported => Err("variable"),
+        }
+    }
+
+    fn nested_struct(&mut self, field: &str, s: &Structure) -> String {
+        let base = struct_name(field);
+        let mut name = base.clone();
+        let mut n = 2;
+        while self.used_names.contains(&name) {
+            name = format!("{}{}", base, n);
+            n += 1;
+        }
+        self.used_names.insert(name.clone());
+
+        let fields: Vec<(&String, &MatVariable)> = s.value.iter().collect();
+        self.add_struct(name.clone(), &fields);
+
+        name
+    }
+}
+
+fn is_char(var: &MatVariable) -> bool {
+    matches!(
+        var,
+        MatVariable::NumericArray(arr) if matches!(arr.value, MatlabType::UTF8(_) | MatlabType::UTF16(_))
+    )
+}
+
+fn element_type(value: &MatlabType) -> &'static str {
+    match value {
+        MatlabType::U8(_) => "u8",
+        MatlabType::I8(_) => "i8",
+        MatlabType::U16(_) => "u16",
+        MatlabType::I16(_) => "i16",
+        MatlabType::U32(_) => "u32",
+        MatlabType::I32(_) => "i32",
+        MatlabType::U64(_) => "u64",
+        MatlabType::I64(_) => "i64",
+        MatlabType::F32(_) => "f32",
+        MatlabType::F64(_) => "f64",
+        MatlabType::UTF8(_) | MatlabType::UTF16(_) => "char",
+        MatlabType::BOOL(_) => "bool",
+    }
+}
+
+/// Convert a MATLAB name like `my_field` into a struct name like `MyField`.
+fn struct_name(field: &str) -> String {
+    field
+        .split('_')
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            let mut c = s.chars();
+            match c.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + c.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+/// Rust identifier for a field, with a serde attribute if renaming is required.
+fn field_ident(field: &str) -> (Option<String>, String) {
+    match field {
+        "self" | "Self" | "super" | "crate" => (
+            Some(format!("#[serde(rename = \"{}\")]", field)),
+            format!("{}_", field),
+        ),
+        f if KEYWORDS.contains(&f) => (None, format!("r#{}", f)),
+        f => (None, f.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CellArray, matfile, matvar};
+
+    #[test]
+    fn nested_structs() {
+        let matfile = matfile!(
+            x: matvar!(1.0),
+            m: matvar!([[1.0, 2.0], [3.0, 4.0]]),
+            data: matvar!({
+                values: [1i32, 2, 3],
+                inner_data: { flag: true },
+            }),
+            other: matvar!({
+                inner_data: { v: 1.0f32 },
+            }),
+        );
+
+        let code = infer_schema(&matfile);
+
+        let expected = "use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MatFileData {
+    pub x: f64,
+    pub m: Vec<Vec<f64>>,
+    pub data: Data,
+    pub other: Other,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Data {
+    pub values: Vec<i32>,
+    pub inner_data: InnerData,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InnerData {
+    pub flag: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Other {
+    pub inner_data: InnerData2,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InnerData2 {
+    pub v: f32,
+}
+";
+        assert_eq!(code, expected);
+    }
+
+    #[test]
+    fn unsupported_and_sparse() {
+        let matfile = matfile!(
+            c: MatVariable::CellArray(CellArray::new(vec![1, 2], vec![matvar!(1.0), matvar!("a")]).unwrap()),
+            sp: matvar!([[1.0, 0.0], [0.0, 1.0]]).to_sparse().unwrap(),
+            names: matvar!(["ab", "c"]),
+        );
+
+        let code = infer_schema(&matfile);
+
+        assert!(code.contains("    // c: cell array not supported by the typed interface\n"));
+        assert!(code.contains("    pub sp: matrw::SparseMatrix<f64>,\n"));
+        assert!(code.contains("    pub names: Vec<String>,\n"));
+    }
+
+    #[test]
+    fn reserved_struct_names() {
+        let matfile = matfile!(
+            vec: matvar!({ x: 1.0 }),
+            string: matvar!({ option: { y: "a" } }),
+            serialize: matvar!({ z: true }),
+        );
+
+        let code = infer_schema(&matfile);
+
+        assert!(code.contains("    pub vec: Vec2,\n"));
+        assert!(code.contains("pub struct Vec2 {"));
+        assert!(code.contains("    pub string: String2,\n"));
+        assert!(code.contains("    pub option: Option2,\n"));
+        assert!(code.contains("    pub y: String,\n"));
+        assert!(code.contains("    pub serialize: Serialize2,\n"));
+        assert!(!code.contains("struct Vec {") && !code.contains("struct String {"));
+    }
+
+    #[test]
+    fn identifiers() {
+        assert_eq!(struct_name("my_field"), "MyField");
+        assert_eq!(struct_name("A"), "A");
+        assert_eq!(field_ident("type"), (None, "r#type".to_string()));
+        assert_eq!(
+            field_ident("self"),
+            (
+                Some("#[serde(rename = \"self\")]".to_string()),
+                "self_".to_string()
+            )
+        );
+    }
+}
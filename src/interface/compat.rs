@@ -0,0 +1,89 @@
+//! Comparison of matrw with MATLAB, using the corpus of [`crate::fixtures`].
+//!
+//! [`check`] loads a fixture and compares the variables read with those MATLAB wrote, [`compare`]
+//! compares a file read otherwise, e.g. by a downstream crate wrapping matrw. Differences are
+//! reported like those of [`crate::roundtrip_check`], as the variable read is missing, added or
+//! changed. Values are compared by [`MatVariable::content_hash`], so NaNs compare equal and the
+//! storage type doesn't matter.
+//!
+//! Example
+//! ```
+//! # #[cfg(feature = "deflate")]
+//! # {
+//! let failures = matrw::compat::check_all().unwrap();
+//! for (name, differences) in &failures {
+//!     println!("{}: {:?}", name, differences);
+//! }
+//! assert!(failures.is_empty());
+//! # }
+//! ```
+//!
+//! [`MatVariable::content_hash`]: crate::MatVariable::content_hash
+
+use crate::interface::error::MatrwError;
+use crate::interface::fixtures::{self, Fixture};
+use crate::interface::matfile::MatFile;
+use crate::interface::roundtrip::{RoundTripDifference, compare_files};
+
+///
+/// Differences between the variables of `fixture` and `matfile`, empty if they agree.
+///
+/// A [`RoundTripDifference::Missing`] variable of the fixture is not part of `matfile`, an
+/// [`RoundTripDifference::Added`] one is not part of the fixture.
+///
+pub fn compare(fixture: &Fixture, matfile: &MatFile) -> Vec<RoundTripDifference> {
+    compare_files(&fixture.expected(), matfile)
+}
+
+///
+/// Load `fixture` and report how the variables read differ from those of MATLAB, see [`compare`].
+///
+/// Fails with the errors of [`crate::load_matfile_from_u8`], if the fixture can't be read, e.g.
+/// compressed ones without the feature `deflate`.
+///
+pub fn check(fixture: &Fixture) -> Result<Vec<RoundTripDifference>, MatrwError> {
+    Ok(compare(fixture, &fixture.load()?))
+}
+
+///
+/// [`check`] every fixture, returning the names and differences of those, which don't agree.
+///
+pub fn check_all() -> Result<Vec<(&'static str, Vec<RoundTripDifference>)>, MatrwError> {
+    let mut failures = Vec::new();
+    for fixture in fixtures::all() {
+        let differences = check(fixture)?;
+        if !differences.is_empty() {
+            failures.push((fixture.name, differences));
+        }
+    }
+    Ok(failures)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matvar;
+
+    #[test]
+    fn differences_to_fixture() {
+        let fixture = fixtures::get("struct_fields").unwrap();
+        assert!(check(fixture).unwrap().is_empty());
+
+        let mut matfile = fixture.load().unwrap();
+        matfile.insert("s", matvar!({ a: 42.0, b: 3.0 }));
+        matfile.insert("c", matvar!(1.0));
+        assert_eq!(
+            compare(fixture, &matfile),
+            vec![
+                RoundTripDifference::Changed { path: "s.b".into() },
+                RoundTripDifference::Added { path: "c".into() },
+            ]
+        );
+    }
+
+    #[cfg(feature = "deflate")]
+    #[test]
+    fn all_fixtures_agree() {
+        assert_eq!(check_all().unwrap(), vec![]);
+    }
+}
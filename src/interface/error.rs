@@ -1,8 +1,13 @@
 use core::fmt;
+#[cfg(feature = "serde")]
 use std::fmt::Display;
 
 /// Error types
+///
+/// The enum is `#[non_exhaustive]`, matches need a wildcard arm. The message is available via
+/// [`Display`](fmt::Display), the underlying IO or parser error via [`std::error::Error::source`].
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum MatrwError {
     IoError(std::io::Error),
     BinrwError(binrw::Error),
@@ -10,6 +15,9 @@ pub enum MatrwError {
     AccessError(String),
     SerdeError(String),
     TypeConstruction(String),
+    /// Loading a file would take more memory than the given limit in bytes, see
+    /// [`crate::LoadOptions::max_memory`]
+    MemoryLimit(u64),
 }
 
 impl fmt::Display for MatrwError {
@@ -21,11 +29,20 @@ impl fmt::Display for MatrwError {
             MatrwError::AccessError(msg) => write!(f, "{}", msg),
             MatrwError::SerdeError(e) => write!(f, "Serde error {}", e),
             MatrwError::TypeConstruction(msg) => write!(f, "Type construction error {}", msg),
+            MatrwError::MemoryLimit(limit) => write!(f, "Memory limit of {} bytes exceeded", limit),
         }
     }
 }
 
-impl std::error::Error for MatrwError {}
+impl std::error::Error for MatrwError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MatrwError::IoError(e) => Some(e),
+            MatrwError::BinrwError(e) => Some(e),
+            _ => None,
+        }
+    }
+}
 
 impl From<binrw::Error> for MatrwError {
     fn from(value: binrw::Error) -> Self {
@@ -39,6 +56,7 @@ impl From<std::io::Error> for MatrwError {
     }
 }
 
+#[cfg(feature = "serde")]
 impl serde::ser::Error for MatrwError {
     fn custom<T>(msg: T) -> Self
     where
@@ -48,6 +66,7 @@ impl serde::ser::Error for MatrwError {
     }
 }
 
+#[cfg(feature = "serde")]
 impl serde::de::Error for MatrwError {
     fn custom<T>(msg: T) -> Self
     where
@@ -0,0 +1,582 @@
+//! Event-based reading of MAT-files.
+//!
+//! [`read_events`] parses a MAT-file and passes every part of it as an [`Event`] to a closure,
+//! without building [`crate::MatVariable`]s. Array data is passed on in chunks of bounded size
+//! and compressed variables are decompressed while they are read, so the memory needed does not
+//! depend on the size of the file or its variables. This allows to transcode MAT-files to other
+//! formats on the fly.
+//!
+//! ```
+//! use matrw::{Event, MatFileDataTypes, matfile, matvar, read_events, save_matfile_v7_to_u8};
+//!
+//! let matfile = matfile!(x: matvar!([0.5, 1.5, 2.5]), s: matvar!({ a: "text" }));
+//! let data = save_matfile_v7_to_u8(matfile, true).unwrap();
+//!
+//! let mut names = Vec::new();
+//! let mut data_type = None;
+//! let mut doubles = Vec::new();
+//! read_events(data.as_slice(), |event| {
+//!     match event {
+//!         Event::VariableStart { name, .. } => names.push(name.to_string()),
+//!         Event::DataStart { data_type: t, .. } => data_type = Some(t),
+//!         Event::NumericChunk(bytes) if data_type == Some(MatFileDataTypes::MiDOUBLE) => {
+//!             doubles.extend(bytes.chunks(8).map(|b| f64::from_ne_bytes(b.try_into().unwrap())));
+//!         }
+//!         _ => {}
+//!     }
+//!     Ok(())
+//! })
+//! .unwrap();
+//!
+//! assert_eq!(names, vec!["x", "s", "a"]);
+//! assert_eq!(doubles, vec![0.5, 1.5, 2.5]);
+//! ```
+
+use std::io::{self, Cursor, Read};
+
+use binrw::{BinReaderExt, Endian};
+
+use crate::interface::error::MatrwError;
+use crate::parser::header::{self, MatFileHeader, MatFileVerFlag};
+use crate::parser::v7::flags::{MatFileDataTypes, MatlabArrayTypes};
+use crate::parser::v7::types::compressed_array::{read_element, read_u32};
+use crate::parser::v7::types::subelements::tag::{SMALL_DATA_MAX, padding, split_small_tag};
+use crate::parser::v7::zlib;
+
+/// Maximum number of bytes of a [`Event::NumericChunk`], a multiple of the size of every data type
+const CHUNK_SIZE: usize = 1 << 16;
+
+/// Part of the data of an array, see [`Event::DataStart`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataPart {
+    /// Values, or real parts of complex values
+    Real,
+    /// Imaginary parts of complex values
+    Imaginary,
+    /// Row indices of the nonzero elements of a sparse array
+    RowIndices,
+    /// Offsets of the columns of a sparse array into its nonzero elements
+    ColumnIndices,
+}
+
+/// Part of a MAT-file, passed to the closure of [`read_events`].
+///
+/// Events of a variable are enclosed by [`Event::VariableStart`] and [`Event::VariableEnd`].
+/// Between them follow the events of its contents: the data of numeric, character and sparse
+/// arrays, the cells of cell arrays and the fields of structures, which are variables again.
+#[derive(Debug, PartialEq)]
+pub enum Event<'a> {
+    /// Start of a variable, a structure field or a cell
+    VariableStart {
+        /// Name of the variable or field, empty for cells
+        name: &'a str,
+        /// Class as stored, logical arrays have class [`MatlabArrayTypes::MxUINT8CLASS`]
+        class: MatlabArrayTypes,
+        dims: &'a [usize],
+        complex: bool,
+        logical: bool,
+    },
+    /// Field names of the structure or structure array started last.
+    ///
+    /// The fields of each element follow in this order, the elements in column-major order.
+    FieldNames(&'a [String]),
+    /// Start of a part of the data of the array started last.
+    ///
+    /// The data follows in [`Event::NumericChunk`]s with `num_bytes` bytes in total. Values have
+    /// the type `data_type`, which may be smaller than the class of the array, e.g.
+    /// [`MatFileDataTypes::MiUINT8`] for a double array of small integers.
+    DataStart {
+        part: DataPart,
+        data_type: MatFileDataTypes,
+        num_bytes: u32,
+    },
+    /// Chunk of the data started last, whole values in native byte order
+    NumericChunk(&'a [u8]),
+    /// End of the variable started last
+    VariableEnd,
+    /// Data element, which is not a supported variable and was skipped, named if it is an array
+    Skipped { name: Option<&'a str> },
+}
+
+///
+/// Read the MAT-file from `reader` and call `on_event` for every [`Event`].
+///
+/// Reading stops at the first error returned by `on_event`. Subsystem data is not read.
+///
+pub fn read_events<R, F>(mut reader: R, mut on_event: F) -> Result<(), MatrwError>
+where
+    R: Read,
+    F: FnMut(Event<'_>) -> Result<(), MatrwError>,
+{
+    let mut data = [0u8; header::HEADER_SIZE];
+    reader.read_exact(&mut data)?;
+    let matheader = Cursor::new(&data).read_le::<MatFileHeader>()?;
+    if matheader.matfile_ver == MatFileVerFlag::V73 {
+        return Err(MatrwError::MatFile73Error);
+    }
+
+    // Variables end where the subsystem starts
+    let offset = matheader.header_subsystem_data_offset_field;
+    let limit = offset.checked_sub(header::HEADER_SIZE as u64).unwrap_or(u64::MAX);
+    let mut parser = Parser {
+        endian: matheader.matfile_endian,
+        chunk: vec![0; CHUNK_SIZE],
+        on_event: &mut on_event,
+    };
+    let mut variables = reader.take(limit);
+    while parser.top_level_element(&mut variables)? {}
+    Ok(())
+}
+
+struct Parser<'f> {
+    endian: Endian,
+    /// Buffer of the [`Event::NumericChunk`]s
+    chunk: Vec<u8>,
+    on_event: &'f mut dyn FnMut(Event<'_>) -> Result<(), MatrwError>,
+}
+
+impl Parser<'_> {
+    fn emit(&mut self, event: Event<'_>) -> Result<(), MatrwError> {
+        (self.on_event)(event)
+    }
+
+    /// Read the next variable of the file, returns `false` at its end.
+    fn top_level_element(&mut self, reader: &mut dyn Read) -> Result<bool, MatrwError> {
+        // Reading ends, when no further element starts, like for [`crate::load_matfile`]
+        let mut tag = [0u8; 4];
+        let n = (&mut *reader).take(4).read(&mut tag)?;
+        if n == 0 {
+            return Ok(false);
+        }
+        if n < 4 {
+            reader.read_exact(&mut tag[n..]).map_err(|_| truncated())?;
+        }
+        let tag = u32_from(tag, self.endian);
+        if split_small_tag(tag).is_some() {
+            skip(reader, SMALL_DATA_MAX as u64)?;
+            self.emit(Event::Skipped { name: None })?;
+            return Ok(true);
+        }
+
+        let num_bytes = read_u32(reader, self.endian).map_err(|_| truncated())?;
+        if tag == MatFileDataTypes::MiCOMPRESSED as u32 {
+            let mut compressed = (&mut *reader).take(num_bytes as u64);
+            self.element(&mut zlib::decoder(&mut compressed), None)?;
+            io::copy(&mut compressed, &mut io::sink())?;
+        } else {
+            self.element_data(reader, tag, num_bytes, None)?;
+            // The last element of a file may lack the padding
+            skip(reader, padding(num_bytes, false) as u64)?;
+        }
+        Ok(true)
+    }
+
+    /// Read an element, an array or a skipped element.
+    fn element(&mut self, reader: &mut dyn Read, field: Option<&str>) -> Result<(), MatrwError> {
+        let data_type = read_u32(reader, self.endian)?;
+        if split_small_tag(data_type).is_some() {
+            skip(reader, SMALL_DATA_MAX as u64)?;
+            return self.emit(Event::Skipped { name: field });
+        }
+        let num_bytes = read_u32(reader, self.endian)?;
+        self.element_data(reader, data_type, num_bytes, field)?;
+        skip(reader, padding(num_bytes, false) as u64)
+    }
+
+    /// Read the `num_bytes` bytes of data of an element, whose tag has been read.
+    fn element_data(
+        &mut self,
+        reader: &mut dyn Read,
+        data_type: u32,
+        num_bytes: u32,
+        field: Option<&str>,
+    ) -> Result<(), MatrwError> {
+        let mut data = (&mut *reader).take(num_bytes as u64);
+        if data_type == MatFileDataTypes::MiMATRIX as u32 {
+            self.array(&mut data, num_bytes, field)?;
+        } else {
+            self.emit(Event::Skipped { name: field })?;
+        }
+        // Arrays of unsupported classes are skipped after their header
+        io::copy(&mut data, &mut io::sink())?;
+        if data.limit() != 0 {
+            return Err(truncated());
+        }
+        Ok(())
+    }
+
+    /// Read the data of a `miMATRIX` element with `num_bytes` bytes.
+    ///
+    /// Fields of structures are stored without names, they get the name `field`.
+    fn array(
+        &mut self,
+        reader: &mut dyn Read,
+        num_bytes: u32,
+        field: Option<&str>,
+    ) -> Result<(), MatrwError> {
+        let name = field.unwrap_or_default();
+        if num_bytes == 0 {
+            // Empty element, as written for empty cells and fields
+            self.emit(Event::VariableStart {
+                name,
+                class: MatlabArrayTypes::MxDOUBLECLASS,
+                dims: &[0, 0],
+                complex: false,
+                logical: false,
+            })?;
+            return self.emit(Event::VariableEnd);
+        }
+
+        let (_, flags) = read_element(reader, self.endian)?;
+        let flags = u32_from(
+            flags
+                .get(..4)
+                .and_then(|b| b.try_into().ok())
+                .ok_or_else(|| invalid("array flags"))?,
+            self.endian,
+        );
+        let class = MatlabArrayTypes::from_u8(flags as u8);
+        let complex = flags & 0x800 != 0;
+        let logical = flags & 0x200 != 0;
+
+        // Objects don't start with dimensions and name
+        let Some(class) = class.filter(|c| {
+            !matches!(
+                c,
+                MatlabArrayTypes::MxOBJECTCLASS
+                    | MatlabArrayTypes::MxHANDLECLASS
+                    | MatlabArrayTypes::MxOPAQUECLASS
+            )
+        }) else {
+            return self.emit(Event::Skipped { name: field });
+        };
+
+        let (_, dims) = read_element(reader, self.endian)?;
+        let dims: Vec<usize> = dims
+            .chunks_exact(4)
+            .map(|b| u32_from(b.try_into().unwrap(), self.endian) as usize)
+            .collect();
+        let (_, stored_name) = read_element(reader, self.endian)?;
+        let stored_name = String::from_utf8_lossy(&stored_name);
+        let name = if field.is_some() { name } else { &stored_name };
+        let numel = dims
+            .iter()
+            .try_fold(1usize, |n, &d| n.checked_mul(d))
+            .ok_or_else(|| invalid("dimensions"))?;
+
+        self.emit(Event::VariableStart {
+            name,
+            class,
+            dims: &dims,
+            complex,
+            logical,
+        })?;
+        match class {
+            MatlabArrayTypes::MxCELLCLASS => {
+                for _ in 0..numel {
+                    self.element(reader, Some(""))?;
+                }
+            }
+            MatlabArrayTypes::MxSTRUCTCLASS => {
+                let (_, len) = read_element(reader, self.endian)?;
+                let len = len
+                    .get(..4)
+                    .map(|b| u32_from(b.try_into().unwrap(), self.endian) as usize)
+                    .filter(|&len| len > 0)
+                    .ok_or_else(|| invalid("field name length"))?;
+                let (_, names) = read_element(reader, self.endian)?;
+                let names: Vec<String> = names
+                    .chunks(len)
+                    .map(|name| String::from_utf8_lossy(name).trim_end_matches('\0').to_string())
+                    .collect();
+
+                self.emit(Event::FieldNames(&names))?;
+                for _ in 0..numel {
+                    for name in &names {
+                        self.element(reader, Some(name))?;
+                    }
+                }
+            }
+            MatlabArrayTypes::MxSPARSECLASS => {
+                self.data(reader, DataPart::RowIndices)?;
+                self.data(reader, DataPart::ColumnIndices)?;
+                self.data(reader, DataPart::Real)?;
+                if complex {
+                    self.data(reader, DataPart::Imaginary)?;
+                }
+            }
+            _ => {
+                self.data(reader, DataPart::Real)?;
+                if complex {
+                    self.data(reader, DataPart::Imaginary)?;
+                }
+            }
+        }
+        self.emit(Event::VariableEnd)
+    }
+
+    /// Read a data subelement and pass it on in chunks.
+    fn data(&mut self, reader: &mut dyn Read, part: DataPart) -> Result<(), MatrwError> {
+        let tag = read_u32(reader, self.endian)?;
+        let (data_type, num_bytes, small) = match split_small_tag(tag) {
+            Some((data_type, num_bytes)) => (data_type, num_bytes.min(SMALL_DATA_MAX), true),
+            None => (tag, read_u32(reader, self.endian)?, false),
+        };
+        let data_type = MatFileDataTypes::from_u32(data_type)
+            .filter(|t| value_size(*t).is_some())
+            .ok_or_else(|| invalid("data type"))?;
+        let size = value_size(data_type).unwrap();
+
+        self.emit(Event::DataStart {
+            part,
+            data_type,
+            num_bytes,
+        })?;
+        let mut remaining = num_bytes as usize;
+        while remaining > 0 {
+            let n = remaining.min(CHUNK_SIZE);
+            let mut chunk = std::mem::take(&mut self.chunk);
+            reader.read_exact(&mut chunk[..n]).map_err(|_| truncated())?;
+            if self.endian != Endian::NATIVE {
+                chunk[..n]
+                    .chunks_exact_mut(size)
+                    .for_each(|value| value.reverse());
+            }
+            let result = self.emit(Event::NumericChunk(&chunk[..n]));
+            self.chunk = chunk;
+            result?;
+            remaining -= n;
+        }
+        skip(reader, padding(num_bytes, small) as u64)
+    }
+}
+
+/// Size of a value of the data type `data_type`, `None` for types which aren't numbers
+fn value_size(data_type: MatFileDataTypes) -> Option<usize> {
+    use MatFileDataTypes::*;
+    match data_type {
+        MiINT8 | MiUINT8 | MiUTF8 => Some(1),
+        MiINT16 | MiUINT16 | MiUTF16 => Some(2),
+        MiINT32 | MiUINT32 | MiSINGLE | MiUTF32 => Some(4),
+        MiINT64 | MiUINT64 | MiDOUBLE => Some(8),
+        _ => None,
+    }
+}
+
+fn u32_from(bytes: [u8; 4], endian: Endian) -> u32 {
+    match endian {
+        Endian::Big => u32::from_be_bytes(bytes),
+        Endian::Little => u32::from_le_bytes(bytes),
+    }
+}
+
+/// Skip up to `n` bytes, padding may be missing at the end of the data
+fn skip(reader: &mut dyn Read, n: u64) -> Result<(), MatrwError> {
+    io::copy(&mut (&mut *reader).take(n), &mut io::sink())?;
+    Ok(())
+}
+
+fn truncated() -> MatrwError {
+    MatrwError::IoError(io::Error::new(
+        io::ErrorKind::UnexpectedEof,
+        "Truncated data element",
+    ))
+}
+
+fn invalid(what: &str) -> MatrwError {
+    MatrwError::IoError(io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Invalid {what} of array"),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interface::writer::MatFileWriter;
+    use crate::{
+        CellArray, MatFile, MatVariable, MatlabType, NumericArray, SaveOptions, SparseArray, matvar,
+        save_matfile_v7_to_u8,
+    };
+
+    /// Events of `data` as text, chunks of doubles as their values
+    fn events(data: &[u8]) -> Vec<String> {
+        let mut events = Vec::new();
+        let mut data_type = None;
+        read_events(data, |event| {
+            events.push(match event {
+                Event::VariableStart {
+                    name,
+                    class,
+                    dims,
+                    complex,
+                    logical,
+                } => format!("start {name} {class} {dims:?} {complex} {logical}"),
+                Event::FieldNames(names) => format!("fields {names:?}"),
+                Event::DataStart {
+                    part,
+                    data_type: t,
+                    num_bytes,
+                } => {
+                    data_type = Some(t);
+                    format!("data {part:?} {t} {num_bytes}")
+                }
+                Event::NumericChunk(bytes) if data_type == Some(MatFileDataTypes::MiDOUBLE) => {
+                    let values: Vec<f64> = bytes
+                        .chunks(8)
+                        .map(|b| f64::from_ne_bytes(b.try_into().unwrap()))
+                        .collect();
+                    format!("chunk {values:?}")
+                }
+                Event::NumericChunk(bytes) => format!("chunk {bytes:?}"),
+                Event::VariableEnd => "end".to_string(),
+                Event::Skipped { name } => format!("skipped {name:?}"),
+            });
+            Ok(())
+        })
+        .unwrap();
+        events
+    }
+
+    fn variables() -> MatFile {
+        let sparse =
+            SparseArray::new(2, 2, vec![1], vec![0, 0, 1], MatlabType::F64(vec![0.5]), None).unwrap();
+        let complex = NumericArray::new(
+            vec![1, 1],
+            MatlabType::F64(vec![0.25]),
+            Some(MatlabType::F64(vec![-0.5])),
+        )
+        .unwrap();
+        let inner = CellArray::new(vec![1, 1], vec![matvar!(1.5)]).unwrap();
+        let cell = CellArray::new(vec![1, 2], vec![matvar!(0.5), MatVariable::CellArray(inner)]).unwrap();
+
+        let mut matfile = MatFile::new();
+        matfile.insert("x", matvar!([0.5, 1.5]));
+        matfile.insert("s", matvar!([{ a: true, b: "ab" }, { a: false, b: "c" }]));
+        matfile.insert("c", MatVariable::CellArray(cell));
+        matfile.insert("sp", MatVariable::SparseArray(sparse));
+        matfile.insert("z", MatVariable::NumericArray(complex));
+        matfile
+    }
+
+    #[test]
+    fn events_of_variables() {
+        let expected = vec![
+            "start x double [1, 2] false false",
+            "data Real miDOUBLE 16",
+            "chunk [0.5, 1.5]",
+            "end",
+            "start s struct [1, 2] false false",
+            r#"fields ["a", "b"]"#,
+            "start a uint8 [1, 1] false true",
+            "data Real miUINT8 1",
+            "chunk [1]",
+            "end",
+            "start b char [1, 2] false false",
+            "data Real miUTF8 2",
+            "chunk [97, 98]",
+            "end",
+            "start a uint8 [1, 1] false true",
+            "data Real miUINT8 1",
+            "chunk [0]",
+            "end",
+            "start b char [1, 1] false false",
+            "data Real miUTF8 1",
+            "chunk [99]",
+            "end",
+            "end",
+            "start c cell [1, 2] false false",
+            "start  double [1, 1] false false",
+            "data Real miDOUBLE 8",
+            "chunk [0.5]",
+            "end",
+            "start  cell [1, 1] false false",
+            "start  double [1, 1] false false",
+            "data Real miDOUBLE 8",
+            "chunk [1.5]",
+            "end",
+            "end",
+            "end",
+            "start sp sparse [2, 2] false false",
+            "data RowIndices miINT32 4",
+            "chunk [1, 0, 0, 0]",
+            "data ColumnIndices miINT32 12",
+            "chunk [0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0]",
+            "data Real miDOUBLE 8",
+            "chunk [0.5]",
+            "end",
+            "start z double [1, 1] true false",
+            "data Real miDOUBLE 8",
+            "chunk [0.25]",
+            "data Imaginary miDOUBLE 8",
+            "chunk [-0.5]",
+            "end",
+        ];
+        for compress in [false, true] {
+            let data = save_matfile_v7_to_u8(variables(), compress).unwrap();
+            assert_eq!(events(&data), expected, "compress {compress}");
+        }
+    }
+
+    #[test]
+    fn skipped_and_large_elements() {
+        let large: Vec<f64> = (0..CHUNK_SIZE / 4).map(|i| i as f64 + 0.5).collect();
+        let mut writer = MatFileWriter::new(Cursor::new(Vec::new()), &SaveOptions::new()).unwrap();
+        writer.write_raw_element(0x4242, b"vendor data").unwrap();
+        writer.write_variable("x", matvar!(large.clone())).unwrap();
+        let data = writer.finish().unwrap().into_inner();
+
+        let mut chunks = Vec::new();
+        let mut values = Vec::new();
+        read_events(data.as_slice(), |event| {
+            match event {
+                Event::Skipped { name } => assert_eq!(name, None),
+                Event::NumericChunk(bytes) => {
+                    chunks.push(bytes.len());
+                    values.extend(bytes.chunks(8).map(|b| f64::from_ne_bytes(b.try_into().unwrap())));
+                }
+                _ => {}
+            }
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(chunks, vec![CHUNK_SIZE, CHUNK_SIZE]);
+        assert_eq!(values, large);
+
+        // Truncated data is an error, errors of the closure stop reading
+        assert!(read_events(&data[..data.len() - 100], |_| Ok(())).is_err());
+        let mut count = 0;
+        let result = read_events(data.as_slice(), |_| {
+            count += 1;
+            Err(MatrwError::AccessError("stop".to_string()))
+        });
+        assert!(result.is_err());
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn big_endian_files() {
+        // Header and a 1x2 int16 array `ab` of a big-endian file
+        let mut data = Vec::new();
+        let mut header = MatFileHeader::new(MatFileVerFlag::V7);
+        header.matfile_endian = Endian::Big;
+        binrw::BinWrite::write_options(&header, &mut Cursor::new(&mut data), Endian::Big, ()).unwrap();
+        let element: Vec<u32> = vec![14, 48, 6, 8, 10, 0, 5, 8, 1, 2];
+        for word in element {
+            data.extend(word.to_be_bytes());
+        }
+        // Name and data in the small data element format
+        data.extend([0x00, 0x02, 0x00, 0x01, b'a', b'b', 0, 0]);
+        data.extend([0x00, 0x04, 0x00, 0x03, 0x01, 0x02, 0xff, 0xfe]);
+
+        assert_eq!(
+            events(&data),
+            vec![
+                "start ab int16 [1, 2] false false",
+                "data Real miINT16 4",
+                &format!("chunk {:?}", [0x0102i16, -2].map(i16::to_ne_bytes).concat()),
+                "end",
+            ]
+        );
+    }
+}
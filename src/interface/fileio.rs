@@ -5,16 +5,28 @@ use binrw::io::Cursor;
 use binrw::io::TakeSeekExt;
 use std::fs::File;
 use std::io::BufWriter;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
 use std::io::Write;
 
 use crate::interface::error::MatrwError;
-use crate::interface::matfile::MatFile;
+use crate::interface::matfile::{MatFile, Subsystem};
+use crate::interface::metadata::{HeaderInfo, Metadata};
+use crate::interface::types::cell_array::CellArray;
+use crate::interface::types::matlab_types::MatlabType;
+use crate::interface::types::numeric_array::NumericArray;
+use crate::interface::types::structure::check_same_fields;
 use crate::interface::variable::MatVariable;
+use crate::interface::writer::MatFileWriter;
 use crate::parser::header;
 use crate::parser::header::{MatFileHeader, MatFileVerFlag};
-use crate::parser::v7::matfile7::MatFile7;
-
-use super::types::compressed_array::CompressedArray;
+use crate::parser::v7::matfile7::{Convert, ParseOptions, parse_variables};
+use crate::parser::v7::sparse_reader::SparseConversion;
+use crate::parser::v7::stream::ElementStream;
+use crate::parser::v7::types::subelements::array_numeric_data::array_data::DoubleStorage;
+#[cfg(feature = "deflate")]
+use crate::parser::v7::zlib;
 
 /// Load MAT-file data from file.
 ///
@@ -36,29 +48,9 @@ use super::types::compressed_array::CompressedArray;
 /// ```
 pub fn load_matfile(path: &str) -> Result<MatFile, MatrwError> {
     let f = File::open(path)?;
-    let f_bytes = f.metadata().expect("Cannot read file metadata").len();
-    let mut reader = BufReader::new(f);
-
-    // Read the header to find out the file version and the endian
-    let matheader = match reader.read_le::<MatFileHeader>() {
-        Ok(header) => header,
-        Err(err) => return Err(MatrwError::BinrwError(err)),
-    };
+    let f_bytes = f.metadata()?.len();
 
-    let endian = matheader.matfile_endian;
-    let subsystem_offset = matheader.header_subsystem_data_offset_field;
-    // Get the size to read out. In case the MAT-file contains objects, we want to ignore the
-    // subsystem for now.
-    let limit = if subsystem_offset != 0 {
-        subsystem_offset
-    } else {
-        f_bytes
-    } - header::HEADER_SIZE as u64;
-
-    match matheader.matfile_ver {
-        MatFileVerFlag::V7 => Ok(reader.take_seek(limit).read_type::<MatFile7>(endian)?.into()),
-        MatFileVerFlag::V73 => Err(MatrwError::MatFile73Error),
-    }
+    read_matfile_v7(&mut BufReader::new(f), f_bytes, ReadMode::Eager, false)
 }
 
 /// Write MAT-file
@@ -77,42 +69,2286 @@ pub fn load_matfile(path: &str) -> Result<MatFile, MatrwError> {
 /// # let _ = std::fs::remove_file("test.mat");
 /// ```
 pub fn save_matfile_v7(path: &str, matfile: MatFile, compress: bool) -> Result<(), MatrwError> {
+    save_matfile_v7_with_options(path, matfile, &SaveOptions::new().compress(compress))
+}
+
+/// Write MAT-file into a buffer
+///
+/// Example
+/// ```
+/// use matrw::{matfile, matvar, load_matfile_from_u8, save_matfile_v7_to_u8};
+///
+/// let matfile = matfile!(a: matvar!(42.0));
+///
+/// let data: Vec<u8> = save_matfile_v7_to_u8(matfile, true)
+///         .expect("Could not write MAT-file");
+///
+/// let matfile = load_matfile_from_u8(&data).unwrap();
+/// assert_eq!(matfile["a"].to_f64(), Some(42.0));
+/// ```
+pub fn save_matfile_v7_to_u8(matfile: MatFile, compress: bool) -> Result<Vec<u8>, MatrwError> {
+    save_matfile_v7_to_u8_with_options(matfile, &SaveOptions::new().compress(compress))
+}
+
+/// Write MAT-file in the format of MATLAB's `save -v6`
+///
+/// The `-v6` format is the version 7 format without compression and without Unicode: characters
+/// are stored as 16-bit code units, characters beyond U+FFFF are replaced by U+FFFD. Files in
+/// this format can be read by MATLAB 6 and later versions.
+///
+/// MAT-files of this format are read by [`load_matfile`], like version 7 ones.
+///
+/// Example
+/// ```
+/// use matrw::{matfile, matvar, save_matfile_v6};
+///
+/// let matfile = matfile!(a: matvar!(42.0), s: matvar!("text"));
+///
+/// save_matfile_v6("test_v6.mat", matfile)
+///         .expect("Could not write MAT-file");
+///
+/// # let _ = std::fs::remove_file("test_v6.mat");
+/// ```
+pub fn save_matfile_v6(path: &str, matfile: MatFile) -> Result<(), MatrwError> {
+    save_matfile_v7_with_options(path, matfile, &SaveOptions::new().v6())
+}
+
+/// Write MAT-file in the format of MATLAB's `save -v6` into a buffer, see [`save_matfile_v6`]
+pub fn save_matfile_v6_to_u8(matfile: MatFile) -> Result<Vec<u8>, MatrwError> {
+    save_matfile_v7_to_u8_with_options(matfile, &SaveOptions::new().v6())
+}
+
+/// Options for writing MAT-files
+///
+/// Example
+/// ```
+/// use matrw::{matfile, matvar, save_matfile_v7_with_options, SaveOptions};
+///
+/// let matfile = matfile!(s: matvar!({ b: 1.0, a: 2.0 }));
+/// let options = SaveOptions::new().compress(true).sort_struct_fields(true);
+///
+/// save_matfile_v7_with_options("test.mat", matfile, &options)
+///         .expect("Could not write MAT-file");
+///
+/// # let _ = std::fs::remove_file("test.mat");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SaveOptions {
+    compress: bool,
+    sort_struct_fields: bool,
+    fill_struct_fields: bool,
+    double_storage: DoubleStorage,
+    keep_subsystem: bool,
+    metadata: Option<Metadata>,
+    strip_metadata: bool,
+    write_variable_meta: bool,
+    v6: bool,
+    compress_if_smaller: bool,
+    deduplicate: bool,
+    strict: bool,
+}
+
+impl SaveOptions {
+    /// Default options: no compression, field order is kept.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Compress variables.
+    ///
+    /// Each variable is compressed on its own, MATLAB can't read variables sharing a dictionary
+    /// or a compressed element. Files of many small variables therefore compress less than a
+    /// single variable of the same data, see [`SaveOptions::compress_if_smaller`].
+    pub fn compress(mut self, compress: bool) -> Self {
+        self.compress = compress;
+        self
+    }
+
+    /// With compression, only compress the variables which get smaller by it.
+    ///
+    /// Each variable is compressed on its own, as MATLAB requires: a compressed variable carries a
+    /// zlib header, a checksum and the tag of its compressed element, and can't refer to the data
+    /// of other variables. Variables with little redundancy, like short or noisy data, get larger
+    /// by compression. With this option such variables are written uncompressed, so every
+    /// variable takes at most the size of its uncompressed element.
+    ///
+    /// Example
+    /// ```
+    /// use matrw::{MatFile, SaveOptions, matvar, save_matfile_v7_to_u8_with_options};
+    ///
+    /// let matfile = || {
+    ///     let mut matfile = MatFile::new();
+    ///     for i in 0..100 {
+    ///         matfile.insert(&format!("v{i}"), matvar!({ id: i as f64, ok: true }));
+    ///     }
+    ///     matfile
+    /// };
+    ///
+    /// let compressed = SaveOptions::new().compress(true);
+    /// let adaptive = SaveOptions::new().compress(true).compress_if_smaller(true);
+    /// let size = |options| save_matfile_v7_to_u8_with_options(matfile(), options).unwrap().len();
+    ///
+    /// assert!(size(&adaptive) <= size(&compressed));
+    /// assert!(size(&adaptive) <= size(&SaveOptions::new()));
+    /// ```
+    pub fn compress_if_smaller(mut self, compress_if_smaller: bool) -> Self {
+        self.compress_if_smaller = compress_if_smaller;
+        self
+    }
+
+    /// With compression, compress the data of identical variables only once.
+    ///
+    /// A compressed variable holds its name inside the compressed data, so identical variables
+    /// of different names are still written once each. With this option, the data after the name
+    /// is compressed for the first of them and reused for the others, a shared calibration table
+    /// exported under dozens of names is compressed once. Tags and names are then stored
+    /// uncompressed, and variables with less than 1 KiB of data are compressed as usual.
+    /// Identical data is recognized by its SHA-256 digest, so only the compressed data of every
+    /// distinct variable is kept until the file is written.
+    ///
+    /// Example
+    /// ```
+    /// use matrw::{MatFile, SaveOptions, load_matfile_from_u8, matvar, save_matfile_v7_to_u8_with_options};
+    ///
+    /// let table = matvar!(vec![0.25; 10_000]);
+    /// let mut matfile = MatFile::new();
+    /// for i in 0..20 {
+    ///     matfile.insert(&format!("cal{i}"), table.clone());
+    /// }
+    ///
+    /// let options = SaveOptions::new().compress(true).deduplicate(true);
+    /// let data = save_matfile_v7_to_u8_with_options(matfile, &options).unwrap();
+    /// assert_eq!(load_matfile_from_u8(&data).unwrap()["cal19"], table);
+    /// ```
+    pub fn deduplicate(mut self, deduplicate: bool) -> Self {
+        self.deduplicate = deduplicate;
+        self
+    }
+
+    /// Sort the fields of all structures alphabetically, including nested ones, so that the
+    /// written field order does not depend on how the data was constructed.
+    pub fn sort_struct_fields(mut self, sort: bool) -> Self {
+        self.sort_struct_fields = sort;
+        self
+    }
+
+    /// Write cell arrays of scalar structures with differing fields as structure arrays, filling
+    /// missing fields with `[]`, see [`crate::CellArray::try_into_structure_array_filled`].
+    ///
+    /// [`crate::matvar`] collects structures with differing fields in a cell array, as MATLAB
+    /// only allows structure arrays of identical fields. With this option such record lists are
+    /// read back as structure arrays. Cell arrays of structures with the same fields are written
+    /// as they are.
+    ///
+    /// Example
+    /// ```
+    /// use matrw::{SaveOptions, load_matfile_from_u8, matfile, matvar, save_matfile_v7_to_u8_with_options};
+    ///
+    /// let matfile = matfile!(runs: matvar!([{ id: 1.0 }, { id: 2.0, failed: true }]));
+    ///
+    /// let options = SaveOptions::new().fill_struct_fields(true);
+    /// let data = save_matfile_v7_to_u8_with_options(matfile, &options).unwrap();
+    /// let runs = &load_matfile_from_u8(&data).unwrap()["runs"];
+    /// assert_eq!(runs.fieldnames(), Some(vec!["id".to_string(), "failed".to_string()]));
+    /// assert_eq!(runs[0]["failed"], matvar!([]));
+    /// ```
+    pub fn fill_struct_fields(mut self, fill: bool) -> Self {
+        self.fill_struct_fields = fill;
+        self
+    }
+
+    /// Store integral double values, which do not fit into 32-bit integers, as `miINT64` or
+    /// `miUINT64` instead of `miDOUBLE`. The array class is still double.
+    ///
+    /// MATLAB itself never writes 64-bit storage types for double arrays, so this is disabled by
+    /// default.
+    pub fn int64_storage(mut self, int64_storage: bool) -> Self {
+        self.double_storage.int64 = int64_storage;
+        self
+    }
+
+    /// Store double arrays as `miSINGLE`, if all their values are exactly representable in single
+    /// precision, halving their size. The array class is still double, values are never rounded.
+    ///
+    /// Arrays of class single are always written as single, independent of this option.
+    pub fn compress_floats_to_single(mut self, compress: bool) -> Self {
+        self.double_storage.single = compress;
+        self
+    }
+
+    /// Write the subsystem data of a loaded MAT-file, see [`MatFile::subsystem_data`], after the
+    /// variables and point the header to it. The file is then written in the byte order of the
+    /// file the subsystem was read from.
+    ///
+    /// By default the subsystem is dropped and the header offset is zero. Only keep it, if the
+    /// variables referring to it are written unchanged.
+    pub fn keep_subsystem(mut self, keep: bool) -> Self {
+        self.keep_subsystem = keep;
+        self
+    }
+
+    /// Stamp `metadata` into the written file, replacing metadata of a loaded file, see
+    /// [`MatFile::stamp_metadata`].
+    pub fn metadata(mut self, metadata: Metadata) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+
+    /// Do not write any metadata, including metadata of a loaded file.
+    pub fn strip_metadata(mut self, strip: bool) -> Self {
+        self.strip_metadata = strip;
+        self
+    }
+
+    /// Write the metadata attached to variables, see [`MatFile::set_meta`]. It is not written by
+    /// default, as it is meant to be kept in memory only. Saving fails with
+    /// [`MatrwError::AccessError`], if metadata is attached and the file has a variable
+    /// [`crate::VARIABLE_META_VARIABLE`] of its own.
+    ///
+    /// ```
+    /// use matrw::{SaveOptions, load_matfile_from_u8, matfile, matvar, save_matfile_v7_to_u8_with_options};
+    ///
+    /// let mut matfile = matfile!(x: matvar!(1.0));
+    /// matfile.set_meta("x", "stage", "calibrated").unwrap();
+    ///
+    /// let options = SaveOptions::new().write_variable_meta(true);
+    /// let data = save_matfile_v7_to_u8_with_options(matfile, &options).unwrap();
+    /// let loaded = load_matfile_from_u8(&data).unwrap();
+    /// assert_eq!(loaded.meta("x", "stage"), Some(&matvar!("calibrated")));
+    /// ```
+    pub fn write_variable_meta(mut self, write: bool) -> Self {
+        self.write_variable_meta = write;
+        self
+    }
+
+    /// Fail instead of writing data, which would not be read back as it is.
+    ///
+    /// Without this option, such data is written silently:
+    /// - characters, which the character data of the file can't hold, are dropped. These are
+    ///   non-ASCII characters, or characters beyond U+FFFF in the `-v6` format, which become
+    ///   U+FFFD,
+    /// - [`MatVariable::Unsupported`] variables are written as empty arrays,
+    /// - 1x1 structure arrays are read back as [`MatVariable::Structure`].
+    ///
+    /// Example
+    /// ```
+    /// use matrw::{MatrwError, SaveOptions, matfile, matvar, save_matfile_v7_to_u8_with_options};
+    ///
+    /// let options = SaveOptions::new().strict(true);
+    ///
+    /// let err = save_matfile_v7_to_u8_with_options(matfile!(s: matvar!({ name: "Zoë" })), &options);
+    /// assert!(matches!(
+    ///     err,
+    ///     Err(MatrwError::TypeConstruction(msg)) if msg == "Variable 's.name' contains characters, which can't be written"
+    /// ));
+    /// ```
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Write in the `-v6` format: no compression, characters as `miUINT16`.
+    pub(crate) fn v6(mut self) -> Self {
+        self.compress = false;
+        self.v6 = true;
+        self
+    }
+
+    /// Write variables as compressed elements.
+    pub(crate) fn is_compress(&self) -> bool {
+        self.compress
+    }
+
+    /// Keep the compression of a variable only if it makes the variable smaller.
+    pub(crate) fn is_compress_if_smaller(&self) -> bool {
+        self.compress && self.compress_if_smaller
+    }
+
+    /// Reuse the compressed data of identical variables.
+    pub(crate) fn is_deduplicate(&self) -> bool {
+        self.compress && self.deduplicate
+    }
+
+    /// Take the metadata to stamp, `None` if metadata is stripped.
+    pub(crate) fn take_metadata(&mut self) -> Option<Metadata> {
+        self.metadata.take().filter(|_| !self.strip_metadata)
+    }
+}
+
+/// Write MAT-file with [`SaveOptions`]
+pub fn save_matfile_v7_with_options(
+    path: &str,
+    matfile: MatFile,
+    options: &SaveOptions,
+) -> Result<(), MatrwError> {
     let f = File::create(path)?;
     let mut writer = BufWriter::new(f);
 
-    let matheader = MatFileHeader::new(MatFileVerFlag::V7);
+    write_matfile_v7(&mut writer, matfile, options)
+}
+
+/// Write MAT-file into a buffer with [`SaveOptions`]
+pub fn save_matfile_v7_to_u8_with_options(
+    matfile: MatFile,
+    options: &SaveOptions,
+) -> Result<Vec<u8>, MatrwError> {
+    let mut writer = Cursor::new(Vec::new());
+
+    write_matfile_v7(&mut writer, matfile, options)?;
+
+    Ok(writer.into_inner())
+}
+
+/// Load a gzip-compressed MAT-file, e.g. `data.mat.gz`.
+///
+/// The whole file is compressed, unlike the variables of MAT-files saved with compression. The
+/// file is decompressed while it is read, see [`load_matfile_from_stream`]. Fails like
+/// [`load_matfile`], and with [`MatrwError::IoError`], if the file is no valid gzip file.
+/// [`load_matfile_gz_from_reader`] reads from other streams.
+///
+/// Requires the feature `deflate`.
+///
+/// Example
+/// ```
+/// use matrw::{SaveOptions, load_matfile_gz, matfile, matvar, save_matfile_v7_gz};
+///
+/// save_matfile_v7_gz("data.mat.gz", matfile!(a: matvar!([1.0, 2.0])), &SaveOptions::new()).unwrap();
+///
+/// let matfile = load_matfile_gz("data.mat.gz").unwrap();
+/// assert_eq!(matfile["a"], matvar!([1.0, 2.0]));
+///
+/// # let _ = std::fs::remove_file("data.mat.gz");
+/// ```
+#[cfg(feature = "deflate")]
+pub fn load_matfile_gz(path: &str) -> Result<MatFile, MatrwError> {
+    load_matfile_gz_from_reader(BufReader::new(File::open(path)?))
+}
+
+/// Load a gzip-compressed MAT-file from `reader`, e.g. a pipe or a network stream, see
+/// [`load_matfile_gz`]
+///
+/// Requires the feature `deflate`.
+#[cfg(feature = "deflate")]
+pub fn load_matfile_gz_from_reader<R: Read>(reader: R) -> Result<MatFile, MatrwError> {
+    load_matfile_from_stream(flate2::read::MultiGzDecoder::new(reader))
+}
+
+/// Write MAT-file with [`SaveOptions`] and compress the whole file with gzip, see
+/// [`load_matfile_gz`] and [`save_matfile_v7_gz_to_writer`].
+///
+/// Requires the feature `deflate`.
+#[cfg(feature = "deflate")]
+pub fn save_matfile_v7_gz(path: &str, matfile: MatFile, options: &SaveOptions) -> Result<(), MatrwError> {
+    let writer = BufWriter::new(File::create(path)?);
+    save_matfile_v7_gz_to_writer(writer, matfile, options)?.flush()?;
+    Ok(())
+}
+
+/// Write MAT-file with [`SaveOptions`] into `writer`, compressed with gzip, and return `writer`
+///
+/// The file is compressed while it is written, see [`save_matfile_v7_to_writer`]. Requires the
+/// feature `deflate`.
+#[cfg(feature = "deflate")]
+pub fn save_matfile_v7_gz_to_writer<W: Write>(
+    writer: W,
+    matfile: MatFile,
+    options: &SaveOptions,
+) -> Result<W, MatrwError> {
+    let encoder = flate2::write::GzEncoder::new(writer, flate2::Compression::new(zlib::DEFAULT_LEVEL));
+    Ok(save_matfile_v7_to_writer(encoder, matfile, options)?.finish()?)
+}
+
+/// Write MAT-file with [`SaveOptions`] into `writer`, e.g. a compressing or encrypting writer,
+/// and return `writer`
+///
+/// `writer` doesn't need to be seekable, the file is written while the variables are written.
+/// Only files keeping subsystem data with [`SaveOptions::keep_subsystem`] are written into
+/// memory first, since the header stores the position of the subsystem data. Compressors and
+/// encryptors, e.g. of the crates `zstd` or `age`, wrap `writer` to write `data.mat.zst` files or
+/// encrypted files, see [`load_matfile_from_stream`].
+///
+/// Example
+/// ```
+/// use matrw::{SaveOptions, load_matfile_from_stream, matfile, matvar, save_matfile_v7_to_writer};
+///
+/// let data = save_matfile_v7_to_writer(Vec::new(), matfile!(a: matvar!(1.0)), &SaveOptions::new()).unwrap();
+///
+/// let matfile = load_matfile_from_stream(data.as_slice()).unwrap();
+/// assert_eq!(matfile["a"], matvar!(1.0));
+/// ```
+pub fn save_matfile_v7_to_writer<W: Write>(
+    mut writer: W,
+    matfile: MatFile,
+    options: &SaveOptions,
+) -> Result<W, MatrwError> {
+    if options.keep_subsystem && matfile.subsystem_data().is_some() {
+        writer.write_all(&save_matfile_v7_to_u8_with_options(matfile, options)?)?;
+    } else {
+        write_matfile_v7(&mut binrw::io::NoSeek::new(&mut writer), matfile, options)?;
+    }
+    Ok(writer)
+}
+
+/// Write the variables of `matfile` into as many MAT-files as needed to keep each of them at most
+/// `max_bytes` large.
+///
+/// The files are called `<prefix>_part1.mat`, `<prefix>_part2.mat` and so on, and are returned in
+/// this order. Variables are never split and keep their order: each file takes as many of the
+/// following variables as fit. Metadata set with [`SaveOptions::metadata`] is written into every
+/// file, subsystem data is not written.
+///
+/// Each variable is encoded and written to its file before the next one, so a single encoded
+/// variable is held in memory besides `matfile`. The parts are written under temporary names,
+/// `<prefix>_part1.mat.tmp` and so on, and renamed when all of them are written. Existing parts
+/// are then replaced, and the parts following the last one, left from an earlier save into more
+/// parts, are removed.
+///
+/// Returns [`MatrwError::TypeConstruction`], if a single variable does not fit into `max_bytes`.
+/// On any error while writing, the temporary files are removed and existing parts are kept.
+///
+/// Example
+/// ```
+/// use matrw::{SaveOptions, load_matfile, matfile, matvar, save_matfile_sharded};
+///
+/// let matfile = matfile!(a: matvar!(vec![0.5; 100]), b: matvar!(vec![1.5; 100]), c: matvar!("text"));
+///
+/// let prefix = std::env::temp_dir().join(format!("doc_sharded_{}", std::process::id()));
+/// let prefix = prefix.to_str().unwrap();
+///
+/// let paths = save_matfile_sharded(prefix, matfile, 1200, &SaveOptions::new()).unwrap();
+/// assert_eq!(paths, vec![format!("{prefix}_part1.mat"), format!("{prefix}_part2.mat")]);
+/// assert_eq!(load_matfile(&paths[1]).unwrap()["c"], matvar!("text"));
+///
+/// # for path in paths { let _ = std::fs::remove_file(path); }
+/// ```
+pub fn save_matfile_sharded(
+    prefix: &str,
+    matfile: MatFile,
+    max_bytes: u64,
+    options: &SaveOptions,
+) -> Result<Vec<String>, MatrwError> {
+    let mut temporary = Vec::new();
+    if let Err(err) = write_shards(prefix, matfile, max_bytes, options, &mut temporary) {
+        for path in temporary {
+            let _ = std::fs::remove_file(path);
+        }
+        return Err(err);
+    }
+
+    let mut paths = Vec::with_capacity(temporary.len());
+    for (i, path) in temporary.iter().enumerate() {
+        let part = shard_path(prefix, i + 1);
+        if let Err(err) = std::fs::rename(path, &part) {
+            for path in &temporary[i..] {
+                let _ = std::fs::remove_file(path);
+            }
+            return Err(err.into());
+        }
+        paths.push(part);
+    }
+    remove_shards(prefix, paths.len() + 1)?;
+    Ok(paths)
+}
+
+/// Path of the part `i`, counted from 1, of [`save_matfile_sharded`]
+fn shard_path(prefix: &str, i: usize) -> String {
+    format!("{}_part{}.mat", prefix, i)
+}
 
+/// Write the parts of [`save_matfile_sharded`] under temporary names, adding the temporary paths
+/// to `paths` as they are created.
+fn write_shards(
+    prefix: &str,
+    matfile: MatFile,
+    max_bytes: u64,
+    options: &SaveOptions,
+    paths: &mut Vec<String>,
+) -> Result<(), MatrwError> {
+    // Metadata to stamp replaces the metadata of the file in every part
     let mut matfile = matfile;
-    if compress {
-        for (_, val) in matfile.iter_mut() {
-            *val = MatVariable::Compressed(CompressedArray {
-                value: Box::new(val.to_owned()),
-            });
+    if options.strip_metadata || options.metadata.is_some() {
+        matfile.remove_metadata();
+    }
+
+    // Header and metadata are part of every file
+    let overhead = MatFileWriter::new(Cursor::new(Vec::new()), options)?
+        .finish()?
+        .into_inner()
+        .len() as u64;
+    let element_options = SaveOptions {
+        metadata: None,
+        ..options.clone()
+    };
+
+    let start_shard = |paths: &mut Vec<String>| -> Result<_, MatrwError> {
+        let path = format!("{}.tmp", shard_path(prefix, paths.len() + 1));
+        let writer = MatFileWriter::new(BufWriter::new(File::create(&path)?), options)?;
+        paths.push(path);
+        Ok(writer)
+    };
+
+    let mut shard: Option<MatFileWriter<BufWriter<File>>> = None;
+    let mut shard_size = overhead;
+    for entry in matfile.try_into_iter() {
+        let (name, var) = entry?;
+        let mut writer = MatFileWriter::new(Cursor::new(Vec::new()), &element_options)?;
+        writer.write_variable_unchecked(&name, var)?;
+        let element = writer.finish()?.into_inner().split_off(header::HEADER_SIZE);
+
+        let size = element.len() as u64;
+        if overhead + size > max_bytes {
+            return Err(MatrwError::TypeConstruction(format!(
+                "Variable '{}' needs {} bytes, which exceeds the limit of {} bytes per file.",
+                name,
+                overhead + size,
+                max_bytes
+            )));
+        }
+        if shard.is_none() || shard_size + size > max_bytes {
+            if let Some(full) = shard.take() {
+                full.finish()?;
+            }
+            shard = Some(start_shard(paths)?);
+            shard_size = overhead;
+        }
+        shard.as_mut().expect("Shard started").write_encoded(&element)?;
+        shard_size += size;
+    }
+
+    // A MAT-file without variables is still written as one file
+    match shard {
+        Some(shard) => shard.finish()?,
+        None => start_shard(paths)?.finish()?,
+    };
+    Ok(())
+}
+
+/// Remove the consecutive parts of [`save_matfile_sharded`] from part `first` on.
+fn remove_shards(prefix: &str, first: usize) -> Result<(), MatrwError> {
+    let mut i = first;
+    loop {
+        match std::fs::remove_file(shard_path(prefix, i)) {
+            Ok(()) => i += 1,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(err.into()),
         }
     }
+}
+
+fn write_matfile_v7<W: Write + Seek>(
+    writer: &mut W,
+    matfile: MatFile,
+    options: &SaveOptions,
+) -> Result<(), MatrwError> {
+    let mut matheader = MatFileHeader::new(MatFileVerFlag::V7);
+
+    let mut matfile = matfile;
+    let mut options = options.clone();
+    let subsystem = matfile.take_subsystem().filter(|_| options.keep_subsystem);
+    if options.strip_metadata {
+        matfile.remove_metadata();
+    } else if let Some(metadata) = options.take_metadata() {
+        matfile.stamp_metadata(metadata);
+    }
+    if options.write_variable_meta {
+        matfile.stamp_variable_meta()?;
+    }
+    if let Some(subsystem) = &subsystem {
+        matheader.matfile_endian = subsystem.endian;
+    }
+    // Check all variables before writing any of them, which decompresses them
+    for entry in matfile.try_iter() {
+        let (name, val) = entry?;
+        ensure_element_size(name, val)?;
+        if options.strict {
+            ensure_lossless(name, val, options.v6)?;
+        }
+    }
+
+    let endian = matheader.matfile_endian;
+    let start = writer.stream_position()?;
+    let mut file = MatFileWriter::with_header(&mut *writer, &options, matheader)?;
+    for (name, val) in matfile.into_iter() {
+        file.write_variable_unchecked(&name, val)?;
+    }
+    file.finish()?;
 
-    let _ = matheader.write_options(&mut writer, matheader.matfile_endian, ());
-    let _ = MatFile7::from(matfile).write_options(&mut writer, matheader.matfile_endian, ());
-    let _ = writer.flush();
+    if let Some(subsystem) = subsystem {
+        let end = writer.stream_position()?;
+        writer.write_all(&subsystem.data)?;
+
+        writer.seek(SeekFrom::Start(
+            start + header::HEADER_SUBSYSTEM_DATA_OFFSET_POS as u64,
+        ))?;
+        (end - start).write_options(writer, endian, ())?;
+        writer.seek(SeekFrom::End(0))?;
+    }
+    writer.flush()?;
 
     Ok(())
 }
 
+/// Apply the per-variable `options` to `var` called `name`.
+pub(crate) fn prepare_variable(
+    name: &str,
+    var: MatVariable,
+    options: &SaveOptions,
+) -> Result<MatVariable, MatrwError> {
+    let mut var = var;
+    if options.fill_struct_fields {
+        var.walk_mut(|_, val| {
+            if let MatVariable::CellArray(cells) = val
+                && !cells.value.is_empty()
+                && cells
+                    .value
+                    .iter()
+                    .all(|cell| matches!(cell, MatVariable::Structure(_)))
+                && !check_same_fields(&cells.value)
+            {
+                let cells = CellArray {
+                    dim: cells.dim.clone(),
+                    value: std::mem::take(&mut cells.value),
+                };
+                let s = cells.try_into_structure_array_filled();
+                *val = MatVariable::StructureArray(s.expect("All cells are structures"));
+            }
+        });
+    }
+    if options.sort_struct_fields {
+        var.sort_keys();
+    }
+    if options.double_storage != DoubleStorage::default() || options.v6 {
+        var.walk_mut(|_, val| {
+            if let MatVariable::NumericArray(arr) = val {
+                arr.double_storage = options.double_storage;
+                arr.utf16_storage = options.v6;
+            }
+        });
+    }
+    ensure_element_size(name, &var)?;
+    ensure_defined_classes(name, &var)?;
+    if options.strict {
+        ensure_lossless(name, &var, options.v6)?;
+    }
+    Ok(var)
+}
+
+/// Fail, if `var` cannot be written as a MAT-file element.
+///
+/// Element sizes and dimensions are stored as 32-bit integers. The size is estimated from above,
+/// so variables within a few hundred bytes of 4 GiB may be rejected.
+fn ensure_element_size(name: &str, var: &MatVariable) -> Result<(), MatrwError> {
+    let size = element_size_bound(var);
+    let dims_fit = !matches!(var.dim().iter().max(), Some(&d) if d > u32::MAX as usize);
+
+    match size {
+        Some(size) if size <= u32::MAX as u64 && dims_fit => Ok(()),
+        _ => Err(MatrwError::TypeConstruction(format!(
+            "Variable '{}' exceeds the maximum MAT-file element size of 4 GiB.",
+            name
+        ))),
+    }
+}
+
+/// Fail, if `var` contains data which is not read back as written, see [`SaveOptions::strict`].
+fn ensure_lossless(name: &str, var: &MatVariable, v6: bool) -> Result<(), MatrwError> {
+    let mut error = None;
+    var.walk(|path, val| {
+        if error.is_some() {
+            return;
+        }
+        let message = match val {
+            MatVariable::NumericArray(arr) => match &arr.value {
+                MatlabType::UTF8(v) | MatlabType::UTF16(v)
+                    if v.iter().any(|&c| {
+                        if v6 || arr.utf16_storage {
+                            c > '\u{ffff}'
+                        } else {
+                            !c.is_ascii()
+                        }
+                    }) =>
+                {
+                    "contains characters, which can't be written"
+                }
+                _ => return,
+            },
+            MatVariable::StructureArray(arr) if arr.dim.iter().all(|&d| d == 1) => {
+                "is a 1x1 structure array, which is read as a structure"
+            }
+            MatVariable::Unsupported => "is not supported and would be written as an empty array",
+            _ => return,
+        };
+        error = Some(MatrwError::TypeConstruction(format!(
+            "Variable '{}' {message}",
+            variable_path(name, &path.to_string())
+        )));
+    });
+
+    error.map_or(Ok(()), Err)
+}
+
+/// Fail, if `var` contains a complex logical or complex character array, which MAT-files can't
+/// hold. Such arrays can only be built by setting the imaginary part directly.
+fn ensure_defined_classes(name: &str, var: &MatVariable) -> Result<(), MatrwError> {
+    let mut error = None;
+    var.walk(|path, val| {
+        let (value, is_complex) = match val {
+            MatVariable::NumericArray(arr) => (&arr.value, arr.value_cmp.is_some()),
+            MatVariable::SparseArray(arr) => (&arr.value, arr.value_cmp.is_some()),
+            _ => return,
+        };
+        let kind = match value {
+            MatlabType::BOOL(_) => "logical",
+            MatlabType::UTF8(_) | MatlabType::UTF16(_) => "character",
+            _ => return,
+        };
+        if is_complex && error.is_none() {
+            error = Some(MatrwError::TypeConstruction(format!(
+                "Variable '{}' is a complex {kind} array, which MAT-files can't hold.",
+                variable_path(name, &path.to_string())
+            )));
+        }
+    });
+
+    error.map_or(Ok(()), Err)
+}
+
+/// `name` followed by the `path` of a value within it
+fn variable_path(name: &str, path: &str) -> String {
+    let sep = if path.is_empty() || path.starts_with(['(', '{']) {
+        ""
+    } else {
+        "."
+    };
+    format!("{name}{sep}{path}")
+}
+
+/// Upper bound of the written size of `var` in bytes, `None` on overflow.
+fn element_size_bound(var: &MatVariable) -> Option<u64> {
+    // Tag, flags, dimensions of up to 6 dimensions and a name of up to 63 characters
+    const OVERHEAD: u64 = 8 + 16 + 32 + 72;
+
+    fn data(len: usize, bytes: u64) -> Option<u64> {
+        (len as u64).checked_mul(bytes)?.checked_add(16)
+    }
+    fn values(value: &MatlabType) -> Option<u64> {
+        let bytes = match value {
+            MatlabType::U8(_) | MatlabType::I8(_) | MatlabType::BOOL(_) => 1,
+            MatlabType::U16(_) | MatlabType::I16(_) => 2,
+            MatlabType::U32(_) | MatlabType::I32(_) | MatlabType::F32(_) | MatlabType::UTF8(_) => 4,
+            MatlabType::U64(_) | MatlabType::I64(_) | MatlabType::F64(_) | MatlabType::UTF16(_) => 8,
+        };
+        data(value.len(), bytes)
+    }
+    fn sum<'a>(vars: impl Iterator<Item = &'a MatVariable>) -> Option<u64> {
+        vars.map(element_size_bound)
+            .try_fold(0u64, |acc, size| acc.checked_add(size?))
+    }
+
+    let content = match var {
+        MatVariable::NumericArray(arr) => {
+            let cmp = arr.value_cmp.as_ref().map_or(Some(0), values)?;
+            values(&arr.value)?.checked_add(cmp)?
+        }
+        MatVariable::SparseArray(arr) => {
+            let cmp = arr.value_cmp.as_ref().map_or(Some(0), values)?;
+            let indices = data(arr.ir.len(), 4)?.checked_add(data(arr.jc.len(), 4)?)?;
+            values(&arr.value)?.checked_add(cmp)?.checked_add(indices)?
+        }
+        MatVariable::CellArray(arr) => sum(arr.value.iter())?,
+        MatVariable::Structure(s) => data(s.value.len(), 64)?.checked_add(sum(s.value.values())?)?,
+        // Elements are written as their fields, without own array header
+        MatVariable::StructureArray(arr) => {
+            let fields = arr.value.iter().flat_map(|s| match s {
+                MatVariable::Structure(s) => s.value.values().collect(),
+                _ => vec![s],
+            });
+            data(arr.fieldnames().len(), 64)?.checked_add(sum(fields)?)?
+        }
+        MatVariable::Null | MatVariable::Unsupported => 0,
+    };
+
+    content.checked_add(OVERHEAD)
+}
+
+/// Load MAT-file data from file, deferring decompression.
+///
+/// Works like [`load_matfile`], but compressed variables are kept compressed and only
+/// decompressed when they are accessed for the first time, e.g. via [`MatFile::get`]. This reduces load time, if only a few variables of a large file are
+/// used. [`MatFile::materialize`] decompresses all remaining variables.
+///
+/// Corrupt compressed data is only found on access. [`MatFile::try_get`], [`MatFile::try_take`]
+/// and [`MatFile::materialize`] return the error, the other accessors return such variables as
+/// [`MatVariable::Unsupported`], and saving fails.
+///
+/// Example
+/// ```
+/// use matrw::{load_matfile_lazy, MatFile};
+///
+/// let path = concat!(
+///         env!("CARGO_MANIFEST_DIR"),
+///         "/tests/example_v7.mat"
+///         );
+/// let mut matfile: MatFile = load_matfile_lazy(path)
+///         .expect("Could not load MAT-file.");
+/// matfile.materialize().expect("Could not decompress MAT-file.");
+/// ```
+pub fn load_matfile_lazy(path: &str) -> Result<MatFile, MatrwError> {
+    let f = File::open(path)?;
+    let f_bytes = f.metadata()?.len();
+
+    read_matfile_v7(&mut BufReader::new(f), f_bytes, ReadMode::Lazy, false)
+}
+
+/// Load MAT-file from u8, deferring decompression
+///
+/// See [`load_matfile_lazy`].
+pub fn load_matfile_lazy_from_u8(data: &[u8]) -> Result<MatFile, MatrwError> {
+    read_matfile_v7(&mut Cursor::new(data), data.len() as u64, ReadMode::Lazy, false)
+}
+
 /// Load MAT-file from u8
 ///
 pub fn load_matfile_from_u8(data: &[u8]) -> Result<MatFile, MatrwError> {
-    let mut cursor = Cursor::new(data);
+    read_matfile_v7(&mut Cursor::new(data), data.len() as u64, ReadMode::Eager, false)
+}
 
-    // Read the header to find out the file version and the endian
-    let matheader = match cursor.read_le::<MatFileHeader>() {
-        Ok(header) => header,
-        Err(err) => return Err(MatrwError::BinrwError(err)),
+/// Load MAT-file from `reader`, which contains nothing but the MAT-file
+///
+/// The MAT-file is read from the start of `reader`, e.g. an open file or a decompressing reader
+/// implementing [`Seek`]. Readers which can't seek are loaded with [`load_matfile_from_stream`].
+///
+/// Example
+/// ```
+/// use matrw::{load_matfile_from_reader, matfile, matvar, save_matfile_v7_to_u8};
+/// use std::io::Cursor;
+///
+/// let data = save_matfile_v7_to_u8(matfile!(a: matvar!(1.0)), true).unwrap();
+///
+/// let matfile = load_matfile_from_reader(Cursor::new(data)).unwrap();
+/// assert_eq!(matfile["a"], matvar!(1.0));
+/// ```
+pub fn load_matfile_from_reader<R: Read + Seek>(reader: R) -> Result<MatFile, MatrwError> {
+    let mut reader = reader;
+    let size = reader.seek(SeekFrom::End(0))?;
+    reader.rewind()?;
+    read_matfile_v7(&mut reader, size, ReadMode::Eager, false)
+}
+
+/// Load MAT-file from `reader`, which doesn't need to be seekable, e.g. a pipe, a network stream or
+/// a decompressing or decrypting reader.
+///
+/// The file is read while the variables are parsed, and only the top-level variable being read is
+/// buffered besides the variables read, instead of the whole file. Decompressors and decryptors,
+/// e.g. of the crates `zstd` or `age`, wrap the file to read `data.mat.zst` files or encrypted
+/// files, see [`save_matfile_v7_to_writer`]. Fails like [`load_matfile_from_reader`], and with
+/// [`MatrwError::IoError`] on errors of `reader`.
+///
+/// Example
+/// ```
+/// use matrw::{load_matfile_from_stream, matfile, matvar, save_matfile_v7_to_u8};
+///
+/// let data = save_matfile_v7_to_u8(matfile!(a: matvar!(1.0)), true).unwrap();
+///
+/// // Slices can't seek
+/// let matfile = load_matfile_from_stream(data.as_slice()).unwrap();
+/// assert_eq!(matfile["a"], matvar!(1.0));
+/// ```
+pub fn load_matfile_from_stream<R: Read>(reader: R) -> Result<MatFile, MatrwError> {
+    let mut stream = ElementStream::new(reader);
+    // The subsystem data extends to the end of the file, whose size is unknown
+    let matfile = read_matfile_v7(&mut stream, u64::MAX, ReadMode::Eager, false);
+    match stream.take_error() {
+        Some(err) => Err(err.into()),
+        None => matfile,
+    }
+}
+
+/// Options for reading MAT-files
+///
+/// Example
+/// ```
+/// use matrw::{LoadOptions, load_matfile_from_u8_with_options, matfile, matvar, save_matfile_v7_to_u8};
+///
+/// let data = save_matfile_v7_to_u8(matfile!(a: matvar!([1u8, 2u8])), false).unwrap();
+///
+/// let options = LoadOptions::new().normalize_numeric_to_f64(true);
+/// let matfile = load_matfile_from_u8_with_options(&data, &options).unwrap();
+/// assert_eq!(matfile["a"].to_vec_f64(), Some(vec![1.0, 2.0]));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct LoadOptions {
+    normalize_numeric_to_f64: bool,
+    strict_utf16: bool,
+    sparse_density: Option<f64>,
+    max_memory: Option<u64>,
+    strict_dimensions: bool,
+}
+
+impl LoadOptions {
+    /// Default options: variables are loaded as stored.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Convert all numeric arrays of integer and single class to double, so that
+    /// [`MatVariable::to_vec_f64`] succeeds for every numeric array. Character, logical and sparse
+    /// arrays are kept.
+    ///
+    /// Doubles need eight bytes per value, so an `uint8` array takes eight times its memory.
+    /// 64-bit integers beyond 2^53 lose precision.
+    pub fn normalize_numeric_to_f64(mut self, normalize: bool) -> Self {
+        self.normalize_numeric_to_f64 = normalize;
+        self
+    }
+
+    /// Fail on character data that is not valid UTF-16, instead of reading unpaired surrogates
+    /// as U+FFFD. The code units as stored are available with [`crate::NumericArray::utf16_code_units`]
+    /// in either case.
+    pub fn strict_utf16(mut self, strict: bool) -> Self {
+        self.strict_utf16 = strict;
+        self
+    }
+
+    /// Convert 2D double and logical arrays, of which at most the fraction `density` of elements
+    /// is nonzero, to sparse arrays. Scalars and empty arrays are kept.
+    ///
+    /// Top-level arrays are converted while their values are read, and decompressed, in chunks:
+    /// only their nonzero values are kept, so a mostly-zero 20000x20000 double array never takes
+    /// its dense 3.2 GB. Arrays with more nonzero values are read again as dense arrays. Arrays
+    /// nested in structures and cells are read as dense arrays and converted after reading. With
+    /// [`LoadOptions::normalize_numeric_to_f64`], integer and single arrays are converted too.
+    ///
+    /// ```
+    /// use matrw::{LoadOptions, MatVariable, load_matfile_from_u8_with_options, matfile, matvar, save_matfile_v7_to_u8};
+    ///
+    /// let data = save_matfile_v7_to_u8(matfile!(a: matvar!([[0.0, 0.0, 0.0], [0.0, 0.0, 1.5]])), true).unwrap();
+    ///
+    /// let options = LoadOptions::new().sparse_below_density(0.2);
+    /// let matfile = load_matfile_from_u8_with_options(&data, &options).unwrap();
+    /// let MatVariable::SparseArray(a) = &matfile["a"] else { panic!("Not sparse") };
+    /// assert_eq!(a.nnz(), 1);
+    /// ```
+    pub fn sparse_below_density(mut self, density: f64) -> Self {
+        self.sparse_density = Some(density);
+        self
+    }
+
+    /// Fail with [`MatrwError::MemoryLimit`], if the loaded file would take more than `bytes`
+    /// of memory, see [`MatFile::memory_usage`].
+    ///
+    /// Sizes are checked before memory is allocated for them: the sizes of elements and of
+    /// decompressed data declared by their tags, and the sizes of numeric arrays declared by
+    /// their dimensions and classes. Compressed data is decompressed no further than the
+    /// remaining memory, so a small compressed upload can't inflate beyond the limit. The memory
+    /// of each variable is added after it is read, see [`MatVariable::memory_usage`].
+    ///
+    /// ```
+    /// use matrw::{LoadOptions, MatrwError, load_matfile_from_u8_with_options, matfile, matvar, save_matfile_v7_to_u8};
+    ///
+    /// let data = save_matfile_v7_to_u8(matfile!(a: matvar!(vec![0.5; 1000])), true).unwrap();
+    ///
+    /// let options = LoadOptions::new().max_memory(4096);
+    /// let result = load_matfile_from_u8_with_options(&data, &options);
+    /// assert!(matches!(result, Err(MatrwError::MemoryLimit(4096))));
+    /// ```
+    pub fn max_memory(mut self, bytes: u64) -> Self {
+        self.max_memory = Some(bytes);
+        self
+    }
+
+    /// Fail with [`MatrwError::TypeConstruction`] on numeric arrays, which store another number
+    /// of values than their dimensions declare, as some writers do.
+    ///
+    /// By default, such arrays are read with the values dropped or padded with zeros to fit the
+    /// dimensions, and a warning in [`MatFile::warnings`]. Compressed variables of
+    /// [`load_matfile_lazy`] are fitted without warning when accessed.
+    pub fn strict_dimensions(mut self, strict: bool) -> Self {
+        self.strict_dimensions = strict;
+        self
+    }
+
+    fn converts_variables(&self) -> bool {
+        self.normalize_numeric_to_f64 || self.sparse_density.is_some() || self.max_memory.is_some()
+    }
+}
+
+/// Load MAT-file data from file with [`LoadOptions`]
+pub fn load_matfile_with_options(path: &str, options: &LoadOptions) -> Result<MatFile, MatrwError> {
+    let f = File::open(path)?;
+    let f_bytes = f.metadata()?.len();
+
+    read_matfile_with_options(&mut BufReader::new(f), f_bytes, options)
+}
+
+/// Load MAT-file from u8 with [`LoadOptions`]
+pub fn load_matfile_from_u8_with_options(data: &[u8], options: &LoadOptions) -> Result<MatFile, MatrwError> {
+    read_matfile_with_options(&mut Cursor::new(data), data.len() as u64, options)
+}
+
+/// Concatenate the numeric variable `name` of the MAT-files at `paths` along dimension `dim`,
+/// counted from 0 for the rows, see [`NumericArray::concat`].
+///
+/// Files are loaded one after the other and only `name` is decompressed, so the other variables
+/// of the files are never held in memory. Returns [`MatrwError::AccessError`], if a file does not
+/// contain a numeric array `name`, and [`MatrwError::TypeConstruction`], if the arrays differ in
+/// class or size. Arrays are numbered in the order of `paths` in errors.
+///
+/// Example
+/// ```
+/// use matrw::{MatlabType, concat_variable, matfile, matvar, save_matfile_v7};
+///
+/// let dir = std::env::temp_dir();
+/// let part1 = dir.join(format!("doc_concat1_{}.mat", std::process::id()));
+/// let part2 = dir.join(format!("doc_concat2_{}.mat", std::process::id()));
+/// let (part1, part2) = (part1.to_str().unwrap(), part2.to_str().unwrap());
+///
+/// save_matfile_v7(part1, matfile!(signal: matvar!([1.0, 2.0]), t: matvar!(0.0)), true).unwrap();
+/// save_matfile_v7(part2, matfile!(signal: matvar!([3.0, 4.0]), t: matvar!(1.0)), true).unwrap();
+///
+/// let signal = concat_variable(&[part1, part2], "signal", 1).unwrap();
+/// assert_eq!(signal.dim, vec![1, 4]);
+/// assert_eq!(signal.value, MatlabType::from(vec![1.0, 2.0, 3.0, 4.0]));
+///
+/// # let _ = std::fs::remove_file(part1);
+/// # let _ = std::fs::remove_file(part2);
+/// ```
+pub fn concat_variable(paths: &[&str], name: &str, dim: usize) -> Result<NumericArray, MatrwError> {
+    let mut arrays = Vec::with_capacity(paths.len());
+    for path in paths {
+        match load_matfile_lazy(path)?.take(name) {
+            Some(MatVariable::NumericArray(arr)) => arrays.push(arr),
+            Some(_) => {
+                return Err(MatrwError::AccessError(format!(
+                    "Variable '{}' of '{}' is not a numeric array",
+                    name, path
+                )));
+            }
+            None => {
+                return Err(MatrwError::AccessError(format!(
+                    "Variable '{}' does not exist in '{}'",
+                    name, path
+                )));
+            }
+        }
+    }
+
+    NumericArray::concat(&arrays, dim)
+}
+
+fn read_matfile_with_options<R: Read + Seek>(
+    reader: &mut R,
+    size: u64,
+    options: &LoadOptions,
+) -> Result<MatFile, MatrwError> {
+    let convert = |var: &mut MatVariable| {
+        convert_variable(var, options);
+        Ok(())
+    };
+    let mode = if options.converts_variables() {
+        let parse_options = ParseOptions {
+            max_memory: options.max_memory,
+            sparse: options.sparse_density.map(|density| SparseConversion {
+                density,
+                numeric: options.normalize_numeric_to_f64,
+            }),
+        };
+        ReadMode::Converting(&convert, parse_options)
+    } else {
+        ReadMode::Eager
     };
+    let matfile = read_matfile_v7(reader, size, mode, options.strict_dimensions)?;
+    if options.max_memory.is_some() {
+        // Names and subsystem data count as well
+        check_memory(matfile.memory_usage(), options)?;
+    }
+
+    if options.strict_utf16 {
+        let mut invalid = None;
+        matfile.walk(|path, val| {
+            if let MatVariable::NumericArray(arr) = val
+                && arr.has_invalid_utf16()
+            {
+                invalid.get_or_insert_with(|| path.to_string());
+            }
+        });
+        if let Some(path) = invalid {
+            return Err(MatrwError::TypeConstruction(format!(
+                "Variable '{path}' contains character data that is not valid UTF-16"
+            )));
+        }
+    }
+
+    Ok(matfile)
+}
+
+fn check_memory(used: u64, options: &LoadOptions) -> Result<(), MatrwError> {
+    match options.max_memory {
+        Some(limit) if used > limit => Err(MatrwError::MemoryLimit(limit)),
+        _ => Ok(()),
+    }
+}
+
+/// Apply the conversions of `options` to `var` and its nested variables.
+fn convert_variable(var: &mut MatVariable, options: &LoadOptions) {
+    var.walk_mut(|_, val| {
+        let MatVariable::NumericArray(arr) = val else {
+            return;
+        };
+        if options.normalize_numeric_to_f64
+            && !matches!(
+                arr.value,
+                MatlabType::UTF8(_) | MatlabType::UTF16(_) | MatlabType::BOOL(_)
+            )
+        {
+            arr.value = MatlabType::F64(arr.value.to_f64());
+            arr.value_cmp = arr.value_cmp.as_ref().map(|v| MatlabType::F64(v.to_f64()));
+        }
+        if let Some(density) = options.sparse_density
+            && is_sparse(arr, density)
+            && let MatVariable::NumericArray(arr) = std::mem::replace(val, MatVariable::Null)
+        {
+            *val = arr.to_sparse().expect("2D double or logical array");
+        }
+    });
+}
+
+/// Whether `arr` is a 2D double or logical array with more than one element, of which at most the
+/// fraction `density` is nonzero.
+fn is_sparse(arr: &NumericArray, density: f64) -> bool {
+    let numel = arr.value.len();
+    if arr.dim.len() != 2 || numel < 2 {
+        return false;
+    }
+
+    let nonzero = match (&arr.value, &arr.value_cmp) {
+        (MatlabType::F64(re), Some(MatlabType::F64(im))) => {
+            re.iter().zip(im).filter(|&(&a, &b)| a != 0.0 || b != 0.0).count()
+        }
+        (MatlabType::F64(re), None) => re.iter().filter(|&&x| x != 0.0).count(),
+        (MatlabType::BOOL(v), None) => v.iter().filter(|&&x| x).count(),
+        _ => return false,
+    };
+    nonzero as f64 <= density * numel as f64
+}
+
+/// Offset of the subsystem data, if the header points into the file.
+///
+/// Files without subsystem data fill the offset field with zeros or spaces.
+pub(crate) fn subsystem_offset(matheader: &MatFileHeader, size: u64) -> Option<u64> {
+    let offset = matheader.header_subsystem_data_offset_field;
+    (offset >= header::HEADER_SIZE as u64 && offset < size).then_some(offset)
+}
+
+/// How [`read_matfile_v7`] reads the variables
+enum ReadMode<'a> {
+    /// All variables at once
+    Eager,
+    /// Compressed variables are decompressed when accessed, see [`load_matfile_lazy`]
+    Lazy,
+    /// Variables are passed to the function one after the other, while they are read, with the
+    /// memory limit and sparse conversion, see [`LoadOptions::max_memory`]
+    Converting(Convert<'a>, ParseOptions),
+}
+
+/// With `strict`, see [`LoadOptions::strict_dimensions`].
+fn read_matfile_v7<R: Read + Seek>(
+    reader: &mut R,
+    size: u64,
+    mode: ReadMode,
+    strict: bool,
+) -> Result<MatFile, MatrwError> {
+    // Read the header to find out the file version and the endian
+    let matheader = reader.read_le::<MatFileHeader>()?;
+    if matheader.matfile_ver == MatFileVerFlag::V73 {
+        return Err(MatrwError::MatFile73Error);
+    }
 
     let endian = matheader.matfile_endian;
+    let offset = subsystem_offset(&matheader, size);
+
+    // Variables end where the subsystem starts
+    let limit = offset.unwrap_or(size) - header::HEADER_SIZE as u64;
+    let mut variables = reader.take_seek(limit);
+    let mut matfile = match mode {
+        ReadMode::Eager => parse_variables(&mut variables, endian, false, None, strict, Default::default())?,
+        ReadMode::Lazy => parse_variables(&mut variables, endian, true, None, strict, Default::default())?,
+        ReadMode::Converting(convert, options) => {
+            parse_variables(&mut variables, endian, false, Some(convert), strict, options)?
+        }
+    };
+    matfile.set_header(HeaderInfo::parse(&matheader.text));
+    matfile.restore_variable_meta();
+
+    if let Some(offset) = offset {
+        let mut data = Vec::new();
+        reader.seek(SeekFrom::Start(offset))?;
+        reader.read_to_end(&mut data)?;
+        // Streams of unknown size may end before the offset
+        if !data.is_empty() {
+            matfile.set_subsystem(Subsystem { data, endian });
+        }
+    }
+
+    Ok(matfile)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interface::metadata::METADATA_VARIABLE;
+    use crate::{matfile, matvar};
+
+    #[test]
+    fn sorted_struct_fields() {
+        let matfile = matfile!(
+            s: matvar!({ b: 1.0, a: { d: 1.0, c: 2.0 } }),
+            t: matvar!([{ y: 1.0, x: 2.0 }, { y: 3.0, x: 4.0 }]),
+        );
+        let options = SaveOptions::new().sort_struct_fields(true);
+
+        let data = save_matfile_v7_to_u8_with_options(matfile, &options).unwrap();
+        let matfile = load_matfile_from_u8(&data).unwrap();
+
+        assert_eq!(
+            matfile["s"].fieldnames(),
+            Some(vec!["a".to_string(), "b".to_string()])
+        );
+        assert_eq!(
+            matfile["s"]["a"].fieldnames(),
+            Some(vec!["c".to_string(), "d".to_string()])
+        );
+        assert_eq!(
+            matfile["t"].fieldnames(),
+            Some(vec!["x".to_string(), "y".to_string()])
+        );
+        assert_eq!(matfile["t"][1]["x"].to_f64(), Some(4.0));
+    }
+
+    #[test]
+    fn strict_save() {
+        let options = SaveOptions::new().strict(true);
+        let save = |matfile| {
+            save_matfile_v7_to_u8_with_options(matfile, &options).map_err(|e| match e {
+                MatrwError::TypeConstruction(msg) => msg,
+                e => e.to_string(),
+            })
+        };
+
+        let data = save(matfile!(a: matvar!("ascii"), s: matvar!([{ x: 1.0 }, { x: 2.0 }]))).unwrap();
+        assert!(load_matfile_from_u8(&data).is_ok());
+
+        assert_eq!(
+            save(matfile!(c: matvar!([{ x: 1.0 }]))).unwrap_err(),
+            "Variable 'c' is a 1x1 structure array, which is read as a structure"
+        );
+        let cell = MatVariable::CellArray(
+            crate::CellArray::new(vec![1, 2], vec![matvar!(1.0), matvar!("ö")]).unwrap(),
+        );
+        assert_eq!(
+            save(matfile!(c: cell)).unwrap_err(),
+            "Variable 'c{2}' contains characters, which can't be written"
+        );
+        assert_eq!(
+            save(matfile!(s: matvar!({ u: MatVariable::Unsupported }))).unwrap_err(),
+            "Variable 's.u' is not supported and would be written as an empty array"
+        );
+
+        // MatFileWriter checks each variable
+        let mut writer = MatFileWriter::new(Cursor::new(Vec::new()), &options).unwrap();
+        assert!(writer.write_variable("u", MatVariable::Unsupported).is_err());
+    }
+
+    #[test]
+    fn unsupported_written_empty() {
+        // Written as `[]`, without strict mode
+        let empty =
+            MatVariable::NumericArray(NumericArray::new(vec![0, 0], Vec::<f64>::new().into(), None).unwrap());
+        for compress in [false, true] {
+            let data = save_matfile_v7_to_u8(matfile!(u: MatVariable::Unsupported), compress).unwrap();
+            let loaded = load_matfile_from_u8(&data).unwrap();
+            assert_eq!(loaded["u"], empty);
+            assert!(loaded.warnings().is_empty());
+        }
+
+        let cell = MatVariable::CellArray(
+            crate::CellArray::new(vec![1, 2], vec![matvar!(1.0), MatVariable::Unsupported]).unwrap(),
+        );
+        let data = save_matfile_v7_to_u8(matfile!(c: cell), false).unwrap();
+        assert_eq!(load_matfile_from_u8(&data).unwrap()["c"][1], empty);
+    }
+
+    #[test]
+    fn lazy_decompression() {
+        let original = matfile!(
+            a: matvar!([1.0, 2.0, 3.0]),
+            s: matvar!({ x: "abc", y: [1i8, 2i8] }),
+        );
+        let data = save_matfile_v7_to_u8(matfile!(a: original["a"].clone(), s: original["s"].clone()), true)
+            .unwrap();
+
+        let mut matfile = load_matfile_lazy_from_u8(&data).unwrap();
+        assert!(!matfile.is_decompressed("a"));
+        assert!(!matfile.is_decompressed("s"));
+        assert_eq!(matfile.names().collect::<Vec<_>>(), vec!["a", "s"]);
+
+        assert_eq!(matfile["s"]["y"], original["s"]["y"]);
+        assert!(!matfile.is_decompressed("a"));
+        assert!(matfile.is_decompressed("s"));
+
+        matfile.materialize().unwrap();
+        for (name, var) in matfile.iter() {
+            assert_eq!(var, &original[name.as_str()]);
+        }
+
+        // Uncompressed variables are read as usual
+        let data = save_matfile_v7_to_u8(original, false).unwrap();
+        let matfile = load_matfile_lazy_from_u8(&data).unwrap();
+        assert!(matfile.is_decompressed("a"));
+        assert!(matches!(
+            matfile.iter().next(),
+            Some((_, MatVariable::NumericArray(_)))
+        ));
+    }
+
+    #[test]
+    fn lazy_corrupt_variable() {
+        let a = MatVariable::from((0..10000).map(|x| (x * x) as f64).collect::<Vec<_>>());
+        let data = save_matfile_v7_to_u8(matfile!(a: a, b: matvar!(4.0)), true).unwrap();
+        // Break the checksum at the end of the compressed data of `a`, its name can still be read
+        let mut data = data;
+        let size = u32::from_le_bytes(data[132..136].try_into().unwrap()) as usize;
+        data[136 + size - 1] ^= 0xff;
+
+        let load = || load_matfile_lazy_from_u8(&data).unwrap();
+        assert!(load().try_get("a").is_err());
+        assert_eq!(load().try_get("b").unwrap(), Some(&matvar!(4.0)));
+        assert!(load().try_take("a").is_err());
+        assert!(load().materialize().is_err());
+
+        // Infallible access returns the variable as unsupported
+        assert_eq!(load()["a"], MatVariable::Unsupported);
+        assert_eq!(load().take("a"), Some(MatVariable::Unsupported));
+        assert_eq!(load().into_iter().count(), 2);
+        let mut matfile = load();
+        assert_eq!(
+            matfile.iter_mut().next().unwrap().1,
+            &mut MatVariable::Unsupported
+        );
+
+        // Saving fails instead of writing the variable as unsupported
+        assert!(save_matfile_v7_to_u8(load(), false).is_err());
+        assert!(
+            save_matfile_sharded(
+                &std::env::temp_dir().join("corrupt").to_string_lossy(),
+                load(),
+                1 << 20,
+                &SaveOptions::new()
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn int64_storage() {
+        use crate::MatlabType;
+        use crate::parser::v7::flags::MatFileDataTypes;
+
+        let large = vec![1.0, 2f64.powi(60), 2f64.powi(63) + 2f64.powi(62)];
+        let negative = vec![-1.0, -(2f64.powi(60))];
+        let matfile = || {
+            let mut m = MatFile::new();
+            m.insert("a", MatVariable::from(large.clone()));
+            m.insert("b", MatVariable::from(negative.clone()));
+            m
+        };
+        let storage = |var: &MatVariable| match var {
+            MatVariable::NumericArray(arr) => (arr.storage_type(), arr.value.clone()),
+            _ => panic!("Expected numeric array"),
+        };
+
+        let data = save_matfile_v7_to_u8(matfile(), false).unwrap();
+        let m = load_matfile_from_u8(&data).unwrap();
+        assert_eq!(storage(&m["a"]).0, Some(MatFileDataTypes::MiDOUBLE));
+
+        for compress in [false, true] {
+            let options = SaveOptions::new().int64_storage(true).compress(compress);
+            let data = save_matfile_v7_to_u8_with_options(matfile(), &options).unwrap();
+            let m = load_matfile_from_u8(&data).unwrap();
+
+            assert_eq!(
+                storage(&m["a"]),
+                (Some(MatFileDataTypes::MiUINT64), MatlabType::F64(large.clone()))
+            );
+            assert_eq!(
+                storage(&m["b"]),
+                (Some(MatFileDataTypes::MiINT64), MatlabType::F64(negative.clone()))
+            );
+        }
+    }
+
+    #[test]
+    fn subsystem_data() {
+        // Variables of a file, followed by an element standing in for the subsystem
+        let mut data = save_matfile_v7_to_u8(matfile!(a: matvar!(1.0), b: matvar!("x")), false).unwrap();
+        let subsystem = save_matfile_v7_to_u8(matfile!(s: matvar!([1u8, 2u8, 3u8])), true).unwrap()
+            [header::HEADER_SIZE..]
+            .to_vec();
+        let offset = data.len() as u64;
+        data.extend(&subsystem);
+        let pos = header::HEADER_SUBSYSTEM_DATA_OFFSET_POS;
+        data[pos..pos + 8].copy_from_slice(&offset.to_le_bytes());
+
+        for lazy in [false, true] {
+            let matfile = match lazy {
+                false => load_matfile_from_u8(&data).unwrap(),
+                true => load_matfile_lazy_from_u8(&data).unwrap(),
+            };
+            assert_eq!(matfile.iter().count(), 2);
+            assert_eq!(matfile.subsystem_data(), Some(subsystem.as_slice()));
+        }
+        let matfile = load_matfile_from_stream(data.as_slice()).unwrap();
+        assert_eq!(matfile["b"], matvar!("x"));
+        assert_eq!(matfile.subsystem_data(), Some(subsystem.as_slice()));
+
+        // Dropped by default
+        let matfile = load_matfile_from_u8(&data).unwrap();
+        let written = save_matfile_v7_to_u8(matfile, false).unwrap();
+        assert_eq!(&written[pos..pos + 8], &[0; 8]);
+        assert_eq!(load_matfile_from_u8(&written).unwrap().subsystem_data(), None);
+
+        // Rewritten behind the variables, which are compressed now
+        let matfile = load_matfile_from_u8(&data).unwrap();
+        let options = SaveOptions::new().compress(true).keep_subsystem(true);
+        let written = save_matfile_v7_to_u8_with_options(matfile, &options).unwrap();
+        let offset = u64::from_le_bytes(written[pos..pos + 8].try_into().unwrap());
+        assert_eq!(offset as usize, written.len() - subsystem.len());
+
+        let matfile = load_matfile_from_u8(&written).unwrap();
+        assert_eq!(matfile["b"], matvar!("x"));
+        assert_eq!(matfile.subsystem_data(), Some(subsystem.as_slice()));
+    }
+
+    #[test]
+    fn normalize_numeric_to_f64() {
+        let matfile = matfile!(
+            a: matvar!([1i16, 300i16]),
+            b: matvar!({ c: [1.5f32], d: "x", e: true }),
+        );
+        let data = save_matfile_v7_to_u8(matfile, true).unwrap();
+
+        let options = LoadOptions::new().normalize_numeric_to_f64(true);
+        let matfile = load_matfile_from_u8_with_options(&data, &options).unwrap();
+        assert_eq!(matfile["a"].to_vec_f64(), Some(vec![1.0, 300.0]));
+        assert_eq!(matfile["b"]["c"].to_vec_f64(), Some(vec![1.5]));
+        assert_eq!(matfile["b"]["d"], matvar!("x"));
+        assert_eq!(matfile["b"]["e"], matvar!(true));
+
+        // Off by default
+        let matfile = load_matfile_from_u8_with_options(&data, &LoadOptions::new()).unwrap();
+        assert_eq!(matfile["a"].to_vec_f64(), None);
+    }
+
+    #[cfg(feature = "deflate")]
+    #[test]
+    fn gzip_wrapper() {
+        let path = std::env::temp_dir().join("matrw_wrapper.mat.gz");
+        let path = path.to_str().unwrap();
+        let matfile = || matfile!(a: matvar!(vec![0.5; 1000]), s: matvar!({ t: "text" }));
+
+        save_matfile_v7_gz(path, matfile(), &SaveOptions::new()).unwrap();
+        let data = std::fs::read(path).unwrap();
+        assert_eq!(&data[..2], b"\x1f\x8b");
+        assert!(data.len() < save_matfile_v7_to_u8(matfile(), false).unwrap().len());
+
+        let loaded = load_matfile_gz(path).unwrap();
+        assert_eq!(loaded["a"], matfile()["a"]);
+        assert_eq!(loaded["s"]["t"], matvar!("text"));
+
+        // Written to and read from streams, which can't seek
+        let data =
+            save_matfile_v7_gz_to_writer(Vec::new(), matfile(), &SaveOptions::new().compress(true)).unwrap();
+        let loaded = load_matfile_gz_from_reader(data.as_slice()).unwrap();
+        assert_eq!(loaded["a"], matfile()["a"]);
+
+        // Plain MAT-files are no gzip files
+        save_matfile_v7(path, matfile(), false).unwrap();
+        assert!(matches!(load_matfile_gz(path), Err(MatrwError::IoError(_))));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn stream_reader() {
+        /// Reader returning one byte at a time, which can't seek
+        struct Bytewise<'a>(&'a [u8]);
+
+        impl Read for Bytewise<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                let len = self.0.len().min(buf.len()).min(1);
+                buf[..len].copy_from_slice(&self.0[..len]);
+                self.0 = &self.0[len..];
+                Ok(len)
+            }
+        }
+
+        let fixtures = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures");
+        for entry in std::fs::read_dir(fixtures).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_none_or(|ext| ext != "mat") {
+                continue;
+            }
+            let data = std::fs::read(&path).unwrap();
+            // Compressed fixtures are read with the feature `deflate` only
+            let Ok(expected) = load_matfile_from_u8(&data) else {
+                continue;
+            };
+            let expected = format!("{expected:?}");
+            let matfile = load_matfile_from_stream(Bytewise(&data)).unwrap();
+            assert_eq!(format!("{matfile:?}"), expected, "{}", path.display());
+            assert_eq!(matfile.subsystem_data(), None);
+        }
+
+        for compress in [false, true] {
+            let matfile = || matfile!(a: matvar!(vec![0.5; 1000]), s: matvar!({ t: "text" }));
+            let options = SaveOptions::new().compress(compress);
+            let data = save_matfile_v7_to_writer(Vec::new(), matfile(), &options).unwrap();
+            assert_eq!(data, save_matfile_v7_to_u8(matfile(), compress).unwrap());
+            let loaded = load_matfile_from_stream(Bytewise(&data)).unwrap();
+            assert_eq!(loaded["a"], matfile()["a"]);
+            assert_eq!(loaded["s"]["t"], matvar!("text"));
+        }
+
+        let err = load_matfile_from_stream(&b"MATLAB 5.0"[..]).unwrap_err();
+        assert!(matches!(err, MatrwError::BinrwError(_) | MatrwError::IoError(_)));
+    }
+
+    #[test]
+    fn logical_round_trip() {
+        let logical = |dim: Vec<usize>| {
+            let n = dim.iter().product::<usize>();
+            let value = (0..n).map(|i| i % 3 == 0).collect::<Vec<_>>();
+            MatVariable::NumericArray(NumericArray::new(dim, MatlabType::BOOL(value), None).unwrap())
+        };
+        let matfile = || {
+            let mut matfile = MatFile::new();
+            for (name, dim) in [
+                ("e", vec![0, 0]),
+                ("e3", vec![0, 3]),
+                ("s", vec![1, 1]),
+                ("m", vec![2, 3]),
+            ] {
+                matfile.insert(name, logical(dim));
+            }
+            matfile.insert("nd", logical(vec![2, 3, 2]));
+            matfile.insert("sp", matvar!([[true, false], [false, true]]).to_sparse().unwrap());
+            matfile.insert("st", matvar!({ flags: [true, false] }));
+            matfile
+        };
+
+        let expected = matfile();
+        let check = |loaded: MatFile| {
+            for (name, var) in expected.iter() {
+                assert_eq!(&loaded[name.as_str()], var);
+                assert_eq!(loaded[name.as_str()].is_logical(), name != "st", "{name}");
+            }
+            assert!(loaded["st"]["flags"].is_logical());
+        };
+        for compress in [false, true] {
+            let data = save_matfile_v7_to_u8(matfile(), compress).unwrap();
+            check(load_matfile_from_u8(&data).unwrap());
+            check(load_matfile_lazy_from_u8(&data).unwrap());
+        }
+        check(load_matfile_from_u8(&save_matfile_v6_to_u8(matfile()).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn fill_struct_fields() {
+        let matfile = || {
+            matfile!(
+                s: matvar!({ runs: [{ id: 1.0 }, { ok: false, id: 2.0 }] }),
+                same: matvar!([{ id: 1.0 }, { id: 2.0 }, "text"]),
+            )
+        };
+        let write = |options: &SaveOptions| {
+            load_matfile_from_u8(&save_matfile_v7_to_u8_with_options(matfile(), options).unwrap()).unwrap()
+        };
+
+        let loaded = write(&SaveOptions::new());
+        assert!(matches!(loaded["s"]["runs"], MatVariable::CellArray(_)));
+
+        let loaded = write(&SaveOptions::new().fill_struct_fields(true).compress(true));
+        let MatVariable::StructureArray(runs) = &loaded["s"]["runs"] else {
+            panic!("Not a structure array")
+        };
+        assert_eq!(runs.fieldnames(), vec!["id", "ok"]);
+        assert_eq!(loaded["s"]["runs"][0]["ok"], matvar!([]));
+        assert_eq!(loaded["s"]["runs"][1]["ok"], matvar!(false));
+        assert_eq!(loaded["same"], matfile()["same"]);
+    }
+
+    #[test]
+    fn empty_matfile_round_trip() {
+        for compress in [false, true] {
+            let data = save_matfile_v7_to_u8(matfile!(), compress).unwrap();
+            assert_eq!(data.len(), 128);
+            assert_eq!(load_matfile_from_u8(&data).unwrap().names().count(), 0);
+            assert_eq!(load_matfile_lazy_from_u8(&data).unwrap().names().count(), 0);
+            let loaded = load_matfile_from_reader(Cursor::new(data)).unwrap();
+            assert!(loaded.warnings().is_empty());
+        }
+
+        let path = std::env::temp_dir().join("matrw_empty.mat");
+        let path = path.to_str().unwrap();
+        save_matfile_v7(path, MatFile::new(), false).unwrap();
+        assert_eq!(load_matfile(path).unwrap().names().count(), 0);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn load_from_reader() {
+        let matfile = || matfile!(a: matvar!(vec![0.5; 100]), s: matvar!({ t: "text" }));
+        for compress in [false, true] {
+            let data = save_matfile_v7_to_u8(matfile(), compress).unwrap();
+
+            // The reader's position doesn't matter
+            let mut reader = Cursor::new(data);
+            reader.seek(SeekFrom::End(0)).unwrap();
+            let loaded = load_matfile_from_reader(reader).unwrap();
+            assert_eq!(loaded["a"], matfile()["a"]);
+            assert_eq!(loaded["s"]["t"], matvar!("text"));
+        }
+
+        let path = std::env::temp_dir().join("matrw_reader.mat");
+        save_matfile_v7(path.to_str().unwrap(), matfile(), true).unwrap();
+        let loaded = load_matfile_from_reader(std::fs::File::open(&path).unwrap()).unwrap();
+        assert_eq!(loaded["a"], matfile()["a"]);
+        std::fs::remove_file(path).unwrap();
+
+        assert!(load_matfile_from_reader(Cursor::new(b"MATLAB".to_vec())).is_err());
+    }
+
+    #[test]
+    fn sharded_save() {
+        let prefix = std::env::temp_dir().join(format!("matrw_sharded_{}", std::process::id()));
+        let prefix = prefix.to_str().unwrap();
+        let names: Vec<String> = (0..10).map(|i| format!("v{i}")).collect();
+        let matfile = || {
+            let mut matfile = MatFile::new();
+            names
+                .iter()
+                .for_each(|name| matfile.insert(name, matvar!(vec![0.5; 50])));
+            matfile
+        };
+        let options = SaveOptions::new().metadata(Metadata::new("tool", "1.0"));
+
+        let max_bytes = 1500;
+        let paths = save_matfile_sharded(prefix, matfile(), max_bytes, &options).unwrap();
+        assert!(paths.len() > 1);
+        let mut written = Vec::new();
+        for path in paths.iter() {
+            assert!(std::fs::metadata(path).unwrap().len() <= max_bytes);
+            let part = load_matfile(path).unwrap();
+            assert_eq!(part.metadata().map(|m| m.tool), Some("tool".to_string()));
+            written.extend(
+                part.names()
+                    .filter(|&name| name != METADATA_VARIABLE)
+                    .map(str::to_string),
+            );
+            std::fs::remove_file(path).unwrap();
+        }
+        assert_eq!(written, names);
+
+        // Parts of an earlier save of more parts are removed
+        let stale = save_matfile_sharded(prefix, matfile(), max_bytes, &options).unwrap();
+        let paths = save_matfile_sharded(prefix, matfile(), 1 << 20, &options).unwrap();
+        assert_eq!(paths, vec![format!("{prefix}_part1.mat")]);
+        assert!(stale[1..].iter().all(|path| !std::path::Path::new(path).exists()));
+        assert_eq!(load_matfile(&paths[0]).unwrap().names().count(), names.len() + 1);
+
+        // Failed saves keep the parts of the earlier save
+        let err = save_matfile_sharded(prefix, matfile(), 300, &options).unwrap_err();
+        assert!(matches!(err, MatrwError::TypeConstruction(_)));
+        assert_eq!(load_matfile(&paths[0]).unwrap().names().count(), names.len() + 1);
+        let mut partial = matfile();
+        partial.insert("large", matvar!(vec![0.5; 1000]));
+        let err = save_matfile_sharded(prefix, partial, max_bytes, &options).unwrap_err();
+        assert!(matches!(err, MatrwError::TypeConstruction(_)));
+        assert_eq!(load_matfile(&paths[0]).unwrap().names().count(), names.len() + 1);
+        assert!(!std::path::Path::new(&format!("{prefix}_part1.mat.tmp")).exists());
+
+        let paths = save_matfile_sharded(prefix, MatFile::new(), 300, &SaveOptions::new()).unwrap();
+        assert_eq!(load_matfile(&paths[0]).unwrap().names().count(), 0);
+        std::fs::remove_file(&paths[0]).unwrap();
+    }
+
+    #[test]
+    fn sparse_below_density() {
+        let is_sparse = |var: &MatVariable| matches!(var, MatVariable::SparseArray(_));
+        let MatVariable::NumericArray(a) = matvar!([[0.0, 0.0, 0.0], [0.0, 0.0, 2.0]]) else {
+            panic!("Not a numeric array")
+        };
+        let sparse = a.to_sparse().unwrap();
+
+        for compress in [false, true] {
+            let matfile = matfile!(
+                a: matvar!([[0.0, 0.0, 0.0], [0.0, 0.0, 2.0]]),
+                b: matvar!([[1.0, 0.0], [3.0, 4.0]]),
+                c: matvar!([0i8, 0i8, 0i8, 1i8]),
+                s: matvar!({ l: [false, false, false, true], z: 0.0, t: "abc" }),
+            );
+            let data = save_matfile_v7_to_u8(matfile, compress).unwrap();
+
+            let options = LoadOptions::new().sparse_below_density(0.25);
+            let matfile = load_matfile_from_u8_with_options(&data, &options).unwrap();
+            assert_eq!(matfile["a"], sparse);
+            assert_eq!(matfile["b"], matvar!([[1.0, 0.0], [3.0, 4.0]]));
+            assert_eq!(matfile["c"], matvar!([0i8, 0i8, 0i8, 1i8]));
+            assert!(is_sparse(&matfile["s"]["l"]));
+            assert_eq!(matfile["s"]["z"], matvar!(0.0));
+            assert_eq!(matfile["s"]["t"], matvar!("abc"));
+
+            let options = options.normalize_numeric_to_f64(true);
+            let matfile = load_matfile_from_u8_with_options(&data, &options).unwrap();
+            assert!(is_sparse(&matfile["c"]));
+        }
+    }
+
+    #[test]
+    fn sparse_below_density_while_reading() {
+        let numeric = |dim: Vec<usize>, value: MatlabType, value_cmp: Option<MatlabType>| {
+            NumericArray::new(dim, value, value_cmp).unwrap()
+        };
+        let mut re = vec![0.0; 12];
+        let mut im = vec![0.0; 12];
+        (re[1], re[7], im[7], im[10]) = (1.5, -2.0, 0.5, f64::NAN);
+        let mut flags = vec![false; 12];
+        flags[11] = true;
+        let mut ints = vec![0i16; 12];
+        ints[4] = -3;
+        let mut singles = vec![0f32; 12];
+        singles[0] = 0.25;
+        let complex = numeric(vec![3, 4], re.into(), Some(im.into()));
+        let logical = numeric(vec![4, 3], MatlabType::BOOL(flags), None);
+        let int = numeric(vec![2, 6], ints.into(), None);
+        let single = numeric(vec![6, 2], singles.into(), None);
+
+        for compress in [false, true] {
+            let matfile = matfile!(
+                complex: MatVariable::NumericArray(complex.clone()),
+                logical: MatVariable::NumericArray(logical.clone()),
+                int: MatVariable::NumericArray(int.clone()),
+                single: MatVariable::NumericArray(single.clone()),
+            );
+            let data = save_matfile_v7_to_u8(matfile, compress).unwrap();
+            let options = LoadOptions::new().sparse_below_density(0.25);
+            let matfile = load_matfile_from_u8_with_options(&data, &options).unwrap();
+            // Same as the conversion of the dense arrays, NaN counts as nonzero
+            let sparse = complex.clone().to_sparse().unwrap();
+            let MatVariable::SparseArray(s) = &sparse else {
+                panic!("Not sparse")
+            };
+            assert_eq!(s.nnz(), 3);
+            assert_eq!(format!("{:?}", matfile["complex"]), format!("{sparse:?}"));
+            assert_eq!(matfile["logical"], logical.clone().to_sparse().unwrap());
+            assert_eq!(matfile["int"], MatVariable::NumericArray(int.clone()));
+
+            let options = options.normalize_numeric_to_f64(true);
+            let matfile = load_matfile_from_u8_with_options(&data, &options).unwrap();
+            let normalized = |dim: Vec<usize>, arr: &NumericArray| {
+                let value = MatlabType::F64(arr.value.to_f64());
+                numeric(dim, value, None).to_sparse().unwrap()
+            };
+            assert_eq!(matfile["int"], normalized(vec![2, 6], &int));
+            assert_eq!(matfile["single"], normalized(vec![6, 2], &single));
+        }
+    }
+
+    #[test]
+    fn sparse_below_density_within_memory_limit() {
+        // The dense array would take 8 MB
+        let mut value = vec![0.0; 1000 * 1000];
+        (value[0], value[123_456], value[999_999]) = (1.0, 2.0, 3.0);
+        let dense = NumericArray::new(vec![1000, 1000], value.into(), None).unwrap();
+
+        for compress in [false, true] {
+            let data = save_matfile_v7_to_u8(matfile!(a: MatVariable::NumericArray(dense.clone())), compress)
+                .unwrap();
+            let options = LoadOptions::new().max_memory(100_000);
+            let err = load_matfile_from_u8_with_options(&data, &options).unwrap_err();
+            assert!(matches!(err, MatrwError::MemoryLimit(_)));
+
+            let options = options.sparse_below_density(0.01);
+            let matfile = load_matfile_from_u8_with_options(&data, &options).unwrap();
+            assert_eq!(matfile["a"], dense.clone().to_sparse().unwrap());
+        }
+    }
+
+    #[test]
+    fn memory_limit() {
+        for compress in [false, true] {
+            let matfile = matfile!(small: matvar!(0.5), large: matvar!(vec![0.5; 10000]));
+            let used = matfile.memory_usage();
+            let data = save_matfile_v7_to_u8(matfile, compress).unwrap();
+
+            let options = LoadOptions::new().max_memory(used);
+            let loaded = load_matfile_from_u8_with_options(&data, &options).unwrap();
+            assert_eq!(loaded.memory_usage(), used);
+
+            // Variables count as they are read
+            let options = LoadOptions::new().max_memory(used - 1);
+            let result = load_matfile_from_u8_with_options(&data, &options);
+            assert!(matches!(result, Err(MatrwError::MemoryLimit(limit)) if limit == used - 1));
+
+            let options = LoadOptions::new().max_memory(1000).normalize_numeric_to_f64(true);
+            let err = load_matfile_from_u8_with_options(&data, &options).unwrap_err();
+            assert_eq!(err.to_string(), "Memory limit of 1000 bytes exceeded");
+        }
+    }
+
+    #[test]
+    fn memory_limit_before_allocation() {
+        let data = save_matfile_v7_to_u8(matfile!(a: matvar!([0.5, 1.5])), false).unwrap();
+        let options = LoadOptions::new().max_memory(1 << 20);
+
+        // Dimensions of 100000x100000 doubles, of which two are stored
+        let dims = [5, 0, 0, 0, 8, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0];
+        let pos = data.windows(16).position(|w| w == dims).unwrap();
+        let mut huge = data.clone();
+        huge[pos + 8..pos + 16].copy_from_slice(&[0xa0, 0x86, 0x01, 0x00, 0xa0, 0x86, 0x01, 0x00]);
+        let result = load_matfile_from_u8_with_options(&huge, &options);
+        assert!(matches!(result, Err(MatrwError::MemoryLimit(_))));
+
+        // Compressed data inflating to far more than the element it contains
+        let mut raw = data[header::HEADER_SIZE..].to_vec();
+        raw.resize(raw.len() + (16 << 20), 0);
+        let compressed = crate::parser::v7::zlib::compress(&raw).unwrap();
+        let mut inflating = data[..header::HEADER_SIZE].to_vec();
+        inflating.extend_from_slice(&15u32.to_le_bytes());
+        inflating.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+        inflating.extend_from_slice(&compressed);
+        let loaded = load_matfile_from_u8_with_options(&inflating, &options).unwrap();
+        assert_eq!(loaded["a"], matvar!([0.5, 1.5]));
+
+        // Compressed element declaring more than the limit
+        let data = save_matfile_v7_to_u8(matfile!(a: matvar!(vec![0.0; 1 << 18])), true).unwrap();
+        let result = load_matfile_from_u8_with_options(&data, &options);
+        assert!(matches!(result, Err(MatrwError::MemoryLimit(_))));
+    }
+
+    #[test]
+    fn utf16_code_units() {
+        use crate::{FromMatVariable, MatlabType};
+
+        let data = save_matfile_v7_to_u8(matfile!(s: matvar!("ab")), false).unwrap();
+        let pos = data.windows(6).position(|w| w == b"\x10\x00\x02\x00ab").unwrap();
+        // Replace the characters by uint16 code units, like MATLAB stores them
+        let with_units = |units: &[u8; 4]| {
+            let mut data = data.clone();
+            data[pos..pos + 4].copy_from_slice(b"\x04\x00\x04\x00");
+            data[pos + 4..pos + 8].copy_from_slice(units);
+            data
+        };
+
+        // 'a' and an unpaired surrogate
+        let invalid = with_units(b"\x61\x00\x00\xd8");
+        let matfile = load_matfile_from_u8(&invalid).unwrap();
+        let MatVariable::NumericArray(arr) = &matfile["s"] else {
+            panic!("Not a numeric array")
+        };
+        assert_eq!(arr.value, MatlabType::UTF16(vec!['a', '\u{FFFD}']));
+        assert_eq!(arr.utf16_code_units(), Some(vec![0x61, 0xD800]));
+
+        let strict = LoadOptions::new().strict_utf16(true);
+        let err = load_matfile_from_u8_with_options(&invalid, &strict).unwrap_err();
+        assert!(matches!(err, MatrwError::TypeConstruction(_)));
+
+        // A surrogate pair is one character of two code units
+        let pair = with_units(b"\x3d\xd8\x00\xde");
+        let matfile = load_matfile_from_u8_with_options(&pair, &strict).unwrap();
+        assert_eq!(matfile["s"].dim(), [1, 2]);
+        assert_eq!(String::from_matvar(&matfile["s"]).as_deref(), Some("\u{1F600}"));
+
+        // Changed characters replace the stored code units
+        let mut matfile = matfile;
+        let mut s = matfile.take("s").unwrap();
+        if let MatVariable::NumericArray(arr) = &mut s {
+            arr.value = MatlabType::UTF16(vec!['x', 'y']);
+        }
+        assert_eq!(String::from_matvar(&s).as_deref(), Some("xy"));
+        matfile.insert("s", s);
+        let data = save_matfile_v6_to_u8(matfile).unwrap();
+        let matfile = load_matfile_from_u8(&data).unwrap();
+        assert_eq!(String::from_matvar(&matfile["s"]).as_deref(), Some("xy"));
+    }
+
+    #[test]
+    fn dimension_length_mismatch() {
+        let data = save_matfile_v7_to_u8(
+            matfile!(a: matvar!([1.5, 2.5, 3.5]), c: matvar!({ x: [1i8, 2i8, 3i8] })),
+            false,
+        )
+        .unwrap();
+        // Declare 1x`n` instead of 1x3 elements of both arrays
+        let with_columns = |n: u8| {
+            let mut data = data.clone();
+            let dims = b"\x05\x00\x00\x00\x08\x00\x00\x00\x01\x00\x00\x00\x03\x00\x00\x00";
+            let positions: Vec<_> = (0..data.len() - dims.len())
+                .filter(|&i| data[i..i + dims.len()] == *dims)
+                .collect();
+            assert_eq!(positions.len(), 2);
+            for pos in positions {
+                data[pos + 12] = n;
+            }
+            data
+        };
+
+        let matfile = load_matfile_from_u8(&with_columns(2)).unwrap();
+        assert_eq!(matfile["a"].to_vec_f64().unwrap(), vec![1.5, 2.5]);
+        assert_eq!(matfile["c"]["x"].to_vec_i8().unwrap(), vec![1, 2]);
+        assert_eq!(
+            matfile.warnings(),
+            [
+                "Variable 'a' declares 2 elements but stores 3 values, the last 1 values were dropped",
+                "Variable 'c' declares 2 elements but stores 3 values, the last 1 values were dropped",
+            ]
+        );
+
+        let matfile = load_matfile_from_u8(&with_columns(5)).unwrap();
+        assert_eq!(matfile["a"].to_vec_f64().unwrap(), vec![1.5, 2.5, 3.5, 0.0, 0.0]);
+        assert_eq!(matfile["c"]["x"].dim(), [1, 5]);
+        assert_eq!(
+            matfile.warnings()[0],
+            "Variable 'a' declares 5 elements but stores 3 values, 2 zeros were appended"
+        );
+
+        let strict = LoadOptions::new().strict_dimensions(true);
+        let err = load_matfile_from_u8_with_options(&with_columns(5), &strict).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Type construction error Variable 'a' declares 5 elements, but stores 3 values."
+        );
+        assert!(load_matfile_from_u8_with_options(&data, &strict).is_ok());
+    }
+
+    #[test]
+    fn complex_logical_and_char() {
+        let data = save_matfile_v7_to_u8(
+            matfile!(a: matvar!([(1u8, 2u8)]), b: matvar!("ab"), c: matvar!([1u8, 0u8])),
+            false,
+        )
+        .unwrap();
+        // Set the flags byte of the array flags of each array
+        let flagged = |flags: [u8; 3]| {
+            let mut data = data.clone();
+            let tag = b"\x06\x00\x00\x00\x08\x00\x00\x00";
+            let positions: Vec<_> = (0..data.len() - tag.len())
+                .filter(|&i| data[i..i + tag.len()] == *tag)
+                .collect();
+            assert_eq!(positions.len(), 3);
+            for (pos, flags) in positions.into_iter().zip(flags) {
+                data[pos + 9] = flags;
+            }
+            data
+        };
+
+        // Complex logical and complex character arrays are skipped
+        let matfile = load_matfile_from_u8(&flagged([0x0a, 0x08, 0x00])).unwrap();
+        assert_eq!(matfile["a"], MatVariable::Unsupported);
+        assert_eq!(matfile["b"], MatVariable::Unsupported);
+        assert_eq!(matfile["c"].to_vec_u8(), Some(vec![1, 0]));
+        assert_eq!(
+            matfile.warnings(),
+            [
+                "Variable 'a' is a complex logical array, which MATLAB does not define, and was not read",
+                "Variable 'b' is a complex character array, which MATLAB does not define, and was not read",
+            ]
+        );
+
+        // The logical flag makes a real numeric array logical
+        let matfile = load_matfile_from_u8(&flagged([0x08, 0x00, 0x02])).unwrap();
+        assert_eq!(matfile["c"].to_vec_bool(), Some(vec![true, false]));
+        assert!(matfile.warnings().is_empty());
+
+        // Such arrays can't be built or saved
+        assert_eq!(
+            NumericArray::new(
+                vec![],
+                MatlabType::from(vec![true]),
+                Some(MatlabType::from(vec![false]))
+            )
+            .unwrap_err()
+            .to_string(),
+            "Type construction error Logical arrays can't be complex."
+        );
+        assert!(
+            NumericArray::new(
+                vec![],
+                MatlabType::from(vec!['a']),
+                Some(MatlabType::from(vec!['b']))
+            )
+            .is_err()
+        );
+        assert!(
+            crate::SparseArray::new(
+                1,
+                1,
+                vec![0],
+                vec![0, 1],
+                MatlabType::from(vec![true]),
+                Some(MatlabType::from(vec![true]))
+            )
+            .is_err()
+        );
+        let mut b = matvar!("ab");
+        if let MatVariable::NumericArray(arr) = &mut b {
+            arr.value_cmp = Some(arr.value.clone());
+        }
+        assert_eq!(
+            save_matfile_v7_to_u8(matfile!(s: matvar!({ b: b })), false)
+                .unwrap_err()
+                .to_string(),
+            "Type construction error Variable 's.b' is a complex character array, which MAT-files can't hold."
+        );
+    }
+
+    #[test]
+    fn v6_format() {
+        use crate::FromMatVariable;
+        use crate::parser::v7::flags::MatFileDataTypes;
+
+        let compressed =
+            save_matfile_v7_to_u8(matfile!(a: matvar!([1.0, 2.0]), s: matvar!({ t: "abc" })), true).unwrap();
+        let mut matfile = load_matfile_lazy_from_u8(&compressed).unwrap();
+        matfile.insert("u", matvar!("\u{e4}\u{f6}\u{1F600}"));
+        let data = save_matfile_v6_to_u8(matfile).unwrap();
+
+        // Top-level elements are arrays, not compressed
+        let mut pos = 128;
+        while pos < data.len() {
+            let tag = |i: usize| u32::from_le_bytes(data[i..i + 4].try_into().unwrap());
+            assert_eq!(tag(pos), MatFileDataTypes::MiMATRIX as u32);
+            pos += 8 + tag(pos + 4) as usize;
+        }
+        // Characters are stored as uint16 code units
+        assert!(
+            data.windows(14)
+                .any(|w| w == b"\x04\x00\x00\x00\x06\x00\x00\x00a\x00b\x00c\x00")
+        );
+
+        let matfile = load_matfile_from_u8(&data).unwrap();
+        assert_eq!(matfile["a"], matvar!([1.0, 2.0]));
+        assert_eq!(String::from_matvar(&matfile["s"]["t"]).as_deref(), Some("abc"));
+        // Not representable with 16 bits
+        assert_eq!(
+            String::from_matvar(&matfile["u"]).as_deref(),
+            Some("\u{e4}\u{f6}\u{FFFD}")
+        );
+    }
+
+    #[test]
+    fn skip_unsupported_elements() {
+        let mut data =
+            save_matfile_v7_to_u8(matfile!(a: matvar!(1.0), x: matvar!([1.5, 2.5])), false).unwrap();
+
+        // Real part of 'x' stored with a reserved data type
+        let pos = data
+            .windows(8)
+            .position(|w| w == b"\x09\x00\x00\x00\x10\x00\x00\x00")
+            .unwrap();
+        data[pos] = 10;
+        // Element of a reserved data type between the variables
+        let mut element = 11u32.to_le_bytes().to_vec();
+        element.extend(8u32.to_le_bytes());
+        element.extend([0xff; 8]);
+        data.extend(element);
+
+        for matfile in [
+            load_matfile_from_u8(&data).unwrap(),
+            load_matfile_lazy_from_u8(&data).unwrap(),
+        ] {
+            assert_eq!(matfile["a"], matvar!(1.0));
+            assert_eq!(matfile["x"], MatVariable::Unsupported);
+            assert_eq!(matfile.iter().count(), 2);
+
+            let warnings = matfile.warnings();
+            assert_eq!(warnings.len(), 2);
+            assert!(warnings[0].contains("'x'"));
+            assert!(warnings[1].contains("type reserved"));
+        }
+    }
+
+    #[test]
+    fn unnamed_variables() {
+        for compress in [false, true] {
+            let options = SaveOptions::new().compress(compress);
+            let mut writer = MatFileWriter::new(Cursor::new(Vec::new()), &options).unwrap();
+            writer.write_variable_unchecked("", matvar!(1.0)).unwrap();
+            writer.write_variable("unnamed_2", matvar!(2.0)).unwrap();
+            writer.write_variable_unchecked("", matvar!(3.0)).unwrap();
+            let data = writer.finish().unwrap().into_inner();
+
+            for matfile in [
+                load_matfile_from_u8(&data).unwrap(),
+                load_matfile_lazy_from_u8(&data).unwrap(),
+            ] {
+                assert_eq!(
+                    matfile.names().collect::<Vec<_>>(),
+                    vec!["unnamed_1", "unnamed_2", "unnamed_3"]
+                );
+                assert_eq!(matfile["unnamed_1"], matvar!(1.0));
+                assert_eq!(matfile["unnamed_3"], matvar!(3.0));
+                assert_eq!(
+                    matfile.warnings(),
+                    [
+                        "Variable without name was read as 'unnamed_1'",
+                        "Variable without name was read as 'unnamed_3'"
+                    ]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn element_size_limits() {
+        use crate::{MatlabType, NumericArray};
+
+        // Dimensions are written as 32-bit integers
+        let arr = NumericArray::new(vec![u32::MAX as usize + 1, 0], MatlabType::F64(vec![]), None).unwrap();
+        let err = save_matfile_v7_to_u8(matfile!(a: MatVariable::NumericArray(arr)), false).unwrap_err();
+        assert!(matches!(err, MatrwError::TypeConstruction(_)));
+
+        // A corrupt size of a compressed element is not allocated upfront
+        let mut data = save_matfile_v7_to_u8(MatFile::new(), false).unwrap();
+        data.extend(15u32.to_le_bytes());
+        data.extend(u32::MAX.to_le_bytes());
+        data.extend([0; 16]);
+        assert!(load_matfile_lazy_from_u8(&data).is_err());
+    }
+
+    #[test]
+    fn many_variables() {
+        // More variables than fit into 16 bits
+        let n = 66_000;
+        let mut matfile = MatFile::new();
+        for i in 0..n {
+            matfile.insert(&format!("v{i}"), matvar!(i as f64));
+        }
+        let data = save_matfile_v7_to_u8(matfile, false).unwrap();
+
+        for matfile in [
+            load_matfile_from_u8(&data).unwrap(),
+            load_matfile_lazy_from_u8(&data).unwrap(),
+        ] {
+            assert_eq!(matfile.iter().count(), n);
+            assert_eq!(matfile["v65536"].to_f64(), Some(65536.0));
+            assert_eq!(
+                matfile[format!("v{}", n - 1).as_str()].to_f64(),
+                Some((n - 1) as f64)
+            );
+        }
+    }
+
+    #[test]
+    fn truncated_files() {
+        let data = save_matfile_v7_to_u8(matfile!(a: matvar!(1.0), b: matvar!([0.5, 1.5])), false).unwrap();
+
+        // A truncated last variable is an error, not the end of the file
+        for len in [data.len() - 1, data.len() - 20] {
+            assert!(load_matfile_from_u8(&data[..len]).is_err());
+            assert!(load_matfile_lazy_from_u8(&data[..len]).is_err());
+        }
+
+        // Bytes too few for an element are ignored
+        let mut data = data;
+        data.extend([0; 3]);
+        assert_eq!(load_matfile_from_u8(&data).unwrap().iter().count(), 2);
+        assert_eq!(load_matfile_lazy_from_u8(&data).unwrap().iter().count(), 2);
+    }
+
+    #[cfg(feature = "deflate")]
+    #[test]
+    fn compress_if_smaller() {
+        // Bytes without redundancy, which get larger by compression
+        let noise: Vec<u8> = (0..256u32)
+            .map(|i| (i.wrapping_mul(2654435761) >> 13) as u8)
+            .collect();
+        let matfile = || {
+            matfile!(
+                a: MatVariable::NumericArray(
+                    crate::NumericArray::new(vec![1, 256], crate::MatlabType::U8(noise.clone()), None).unwrap()
+                ),
+                b: matvar!(1.0),
+                z: MatVariable::NumericArray(
+                    crate::NumericArray::new(vec![1, 1000], crate::MatlabType::F64(vec![0.0; 1000]), None)
+                        .unwrap()
+                ),
+            )
+        };
+        let save = |options: &SaveOptions| save_matfile_v7_to_u8_with_options(matfile(), options).unwrap();
+
+        let plain = save(&SaveOptions::new());
+        let compressed = save(&SaveOptions::new().compress(true));
+        let adaptive = save(&SaveOptions::new().compress(true).compress_if_smaller(true));
+        assert!(adaptive.len() < plain.len());
+        assert!(adaptive.len() < compressed.len());
+
+        // Only the noise isn't compressed
+        let lazy = load_matfile_lazy_from_u8(&adaptive).unwrap();
+        let tags: Vec<u32> = lazy
+            .names()
+            .map(|name| !lazy.is_decompressed(name) as u32)
+            .collect();
+        assert_eq!(tags, vec![0, 1, 1]);
+
+        let matfile = load_matfile_from_u8(&adaptive).unwrap();
+        assert_eq!(matfile["a"].to_vec::<u8>().as_ref(), Some(&noise));
+        assert_eq!(matfile["b"], matvar!(1.0));
+        assert_eq!(matfile["z"].to_vec::<f64>().map(|v| v.len()), Some(1000));
+
+        // Without compression the option has no effect
+        assert_eq!(save(&SaveOptions::new().compress_if_smaller(true)), plain);
+    }
+
+    #[cfg(feature = "deflate")]
+    #[test]
+    fn variable_info() {
+        let matfile = || matfile!(a: matvar!(1.0), z: matvar!(vec![0.0; 1000]));
+        let plain = save_matfile_v7_to_u8(matfile(), false).unwrap();
+        let compressed = save_matfile_v7_to_u8(matfile(), true).unwrap();
+
+        let m = load_matfile_from_u8(&plain).unwrap();
+        let (a, z) = (m.variable_info("a").unwrap(), m.variable_info("z").unwrap());
+        assert_eq!(a.disk_bytes + z.disk_bytes, plain.len() as u64 - 128);
+        assert_eq!((a.uncompressed_bytes, a.compressed), (a.disk_bytes, false));
+        assert_eq!(z.compression_ratio(), 1.0);
+        assert_eq!((a.offset, z.byte_range().start), (128, a.byte_range().end));
+
+        for m in [
+            load_matfile_from_u8(&compressed).unwrap(),
+            load_matfile_lazy_from_u8(&compressed).unwrap(),
+        ] {
+            let info = m.variable_info("z").unwrap();
+            assert!(info.compressed);
+            assert_eq!(info.uncompressed_bytes, z.disk_bytes);
+            assert!(info.disk_bytes < info.uncompressed_bytes / 10);
+            let total: u64 = m.names().map(|n| m.variable_info(n).unwrap().disk_bytes).sum();
+            assert_eq!(total, compressed.len() as u64 - 128);
+
+            // The range holds the compressed element with its tag
+            let range = info.byte_range();
+            assert_eq!(range.end, compressed.len() as u64);
+            let element = &compressed[range.start as usize..range.end as usize];
+            assert_eq!(
+                element[..4],
+                (crate::MatFileDataTypes::MiCOMPRESSED as u32).to_le_bytes()
+            );
+            assert_eq!(element[4..8], (info.disk_bytes as u32 - 8).to_le_bytes());
+        }
 
-    match matheader.matfile_ver {
-        MatFileVerFlag::V7 => Ok(cursor.read_type::<MatFile7>(endian)?.into()),
-        MatFileVerFlag::V73 => Err(MatrwError::MatFile73Error),
+        let mut m = load_matfile_from_u8(&plain).unwrap();
+        m.insert("a", matvar!(2.0));
+        assert_eq!(m.variable_info("a"), None);
+        assert_eq!(m.variable_info("y"), None);
+        assert!(matfile().variable_info("z").is_none());
     }
 }
@@ -0,0 +1,386 @@
+//! Corpus of MAT-files with data written by MATLAB.
+//!
+//! Each [`Fixture`] holds a small MAT-file, the MATLAB code to reproduce it and the variables it
+//! contains. The files are bundled from the directory `fixtures`.
+//!
+//! Only 4 of the 24 files were saved by MATLAB as a whole, see [`Fixture::origin`]:
+//! `header_only`, `numeric_classes`, `numeric_two_doubles` and `struct_fields_compressed`. The
+//! other 20 consist of a data element written by MATLAB behind the header of `header_only`, so
+//! they share its timestamp. Their data elements are as written by MATLAB, the files are not.
+//!
+//! The corpus covers
+//! - compressed real scalars of all numeric classes and a one-character char array, in one file,
+//! - uncompressed empty, scalar and row double and uint8 arrays, in the small and normal data
+//!   element formats, a compressed double row and two compressed double scalars,
+//! - an uncompressed complex uint8 scalar and logical row,
+//! - uncompressed double and logical sparse arrays,
+//! - uncompressed empty and nested cells, structures and structure arrays, and a compressed
+//!   structure.
+//!
+//! It doesn't cover complex arrays of other classes than uint8, e.g. int16, int32 or single,
+//! global variables, char data beyond ASCII, which MATLAB stores as UTF-16, compressed logical
+//! arrays, multidimensional arrays, and objects, e.g. strings, tables or function handles. Code
+//! depending on them is checked against other data than this corpus.
+//!
+//! Use the corpus to check that code reading MAT-files agrees with MATLAB, e.g. with
+//! [`crate::compat::check`]:
+//!
+//! ```
+//! # #[cfg(feature = "deflate")]
+//! # {
+//! for fixture in matrw::fixtures::all() {
+//!     let matfile = fixture.load().unwrap();
+//!     assert_eq!(matfile.iter().count(), fixture.expected().iter().count(), "{}", fixture.name);
+//!     for (name, var) in fixture.expected() {
+//!         assert_eq!(matfile[name.as_str()], var, "{}: {}", fixture.name, name);
+//!     }
+//! }
+//! # }
+//! ```
+
+use std::f64::consts::PI;
+
+use indexmap::IndexMap;
+
+use crate::interface::fileio::load_matfile_from_u8;
+use crate::{
+    CellArray, MatFile, MatVariable, MatlabType, MatrwError, NumericArray, SparseArray, Structure,
+    StructureArray, matvar,
+};
+
+/// How the file of a [`Fixture`] was created
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Origin {
+    /// The whole file was saved by MATLAB.
+    Matlab,
+    /// The data element written by MATLAB follows the header of another file saved by MATLAB.
+    Assembled,
+}
+
+/// MAT-file with data written by MATLAB, with the variables it contains.
+#[derive(Debug, Clone, Copy)]
+pub struct Fixture {
+    /// Name of the fixture, the file name in `fixtures` without extension
+    pub name: &'static str,
+    /// MATLAB code creating the variables, or the whole file for [`Origin::Matlab`]
+    pub matlab: &'static str,
+    /// How the file was created
+    pub origin: Origin,
+    /// Content of the file
+    pub data: &'static [u8],
+    expected: fn() -> Vec<(&'static str, MatVariable)>,
+}
+
+impl Fixture {
+    /// Load the file.
+    pub fn load(&self) -> Result<MatFile, MatrwError> {
+        load_matfile_from_u8(self.data)
+    }
+
+    /// Variables in the file, as created by the MATLAB code, in file order.
+    pub fn expected(&self) -> MatFile {
+        let mut matfile = MatFile::new();
+        for (name, var) in (self.expected)() {
+            matfile.insert(name, var);
+        }
+        matfile
+    }
+}
+
+macro_rules! fixture {
+    ($name:literal, $matlab:literal, $expected:expr) => {
+        fixture!(Assembled, $name, $matlab, $expected)
+    };
+    ($origin:ident, $name:literal, $matlab:literal, $expected:expr) => {
+        Fixture {
+            name: $name,
+            matlab: $matlab,
+            origin: Origin::$origin,
+            data: include_bytes!(concat!("../../fixtures/", $name, ".mat")),
+            expected: $expected,
+        }
+    };
+}
+
+static FIXTURES: [Fixture; 24] = [
+    fixture!(Matlab, "header_only", "save('header_only.mat');", Vec::new),
+    fixture!(
+        "numeric_empty",
+        "var_empty = []; save('numeric_empty.mat', 'var_empty', '-nocompression');",
+        || vec![("var_empty", numeric(vec![0, 0], MatlabType::F64(vec![]), None))]
+    ),
+    fixture!(
+        "numeric_uint8",
+        "var_uint8 = uint8(99); save('numeric_uint8.mat', 'var_uint8');",
+        || vec![("var_uint8", matvar!(99u8))]
+    ),
+    fixture!(
+        "numeric_uint8_complex",
+        "var_u8_cmp = uint8(9 + 1i); save('numeric_uint8_complex.mat', 'var_u8_cmp');",
+        || {
+            vec![(
+                "var_u8_cmp",
+                numeric(vec![1, 1], MatlabType::U8(vec![9]), Some(MatlabType::U8(vec![1]))),
+            )]
+        }
+    ),
+    fixture!(
+        "numeric_uint8_row_small",
+        "arr_u8 = uint8([33,44,55]); save('numeric_uint8_row_small.mat', 'arr_u8');",
+        || vec![("arr_u8", matvar!(u8: [33, 44, 55]))]
+    ),
+    fixture!(
+        "numeric_uint8_row",
+        "arr_u8 = uint8([33,44,55,66,77]); save('numeric_uint8_row.mat', 'arr_u8');",
+        || vec![("arr_u8", matvar!(u8: [33, 44, 55, 66, 77]))]
+    ),
+    fixture!(
+        "numeric_double",
+        "var_double = double(99.9); save('numeric_double.mat', 'var_double');",
+        || vec![("var_double", matvar!(99.9))]
+    ),
+    fixture!(
+        "numeric_double_row",
+        "a = [11,22,33,44,55,66,77,88,99]; save('numeric_double_row.mat', 'a', '-nocompression');",
+        || vec![("a", matvar!([11., 22., 33., 44., 55., 66., 77., 88., 99.]))]
+    ),
+    fixture!(
+        "logical_row",
+        "a = [false,true]; save('logical_row.mat', 'a', '-nocompression');",
+        || vec![(
+            "a",
+            numeric(vec![1, 2], MatlabType::BOOL(vec![false, true]), None)
+        )]
+    ),
+    fixture!(
+        Matlab,
+        "numeric_classes",
+        "a = uint8(8); b = int8(-8); c = uint16(16); d = int16(-16); e = uint32(32); f = int32(-32); \
+         g = uint64(64); h = int64(-64); i = single(32); j = double(64); k = 'x'; \
+         save('numeric_classes.mat');",
+        || {
+            vec![
+                ("a", matvar!(8u8)),
+                ("b", numeric(vec![1, 1], MatlabType::I8(vec![-8]), None)),
+                ("c", matvar!(16u16)),
+                ("d", numeric(vec![1, 1], MatlabType::I16(vec![-16]), None)),
+                ("e", matvar!(32u32)),
+                ("f", numeric(vec![1, 1], MatlabType::I32(vec![-32]), None)),
+                ("g", matvar!(64u64)),
+                ("h", numeric(vec![1, 1], MatlabType::I64(vec![-64]), None)),
+                ("i", matvar!(32f32)),
+                ("j", matvar!(64f64)),
+                ("k", matvar!("x")),
+            ]
+        }
+    ),
+    fixture!(
+        Matlab,
+        "numeric_two_doubles",
+        "a = 42; b = pi; save('numeric_two_doubles.mat', 'a', 'b');",
+        || vec![("a", matvar!(42.)), ("b", matvar!(PI))]
+    ),
+    fixture!(
+        "compressed_double_row",
+        "a = [11,22,33,44,55,66,77,88,99]; save('compressed_double_row.mat', 'a');",
+        || vec![("a", matvar!([11., 22., 33., 44., 55., 66., 77., 88., 99.]))]
+    ),
+    fixture!(
+        "sparse_double",
+        "a = sparse([1], [1], [1], 1, 1); save('sparse_double.mat', 'a');",
+        || vec![("a", sparse(1, 1, vec![0], vec![0, 1], MatlabType::F64(vec![1.])))]
+    ),
+    fixture!(
+        "sparse_logical",
+        "a = sparse([3], [3], [true], 3, 3); save('sparse_logical.mat', 'a');",
+        || {
+            vec![(
+                "a",
+                sparse(3, 3, vec![2], vec![0, 0, 0, 1], MatlabType::BOOL(vec![true])),
+            )]
+        }
+    ),
+    fixture!(
+        "cell_empty",
+        "var_empty = {}; save('cell_empty.mat', 'var_empty', '-nocompression');",
+        || vec![("var_empty", cell(vec![0, 0], vec![]))]
+    ),
+    fixture!(
+        "cell_numeric",
+        "A = [ 1,2,3; 4,5,6 ]; B = [ 7,8,9; 10,11,12 ]; C = { A, B }; save('cell_numeric.mat', 'C');",
+        || {
+            vec![(
+                "C",
+                cell(
+                    vec![1, 2],
+                    vec![
+                        matvar!([[1., 2., 3.], [4., 5., 6.]]),
+                        matvar!([[7., 8., 9.], [10., 11., 12.]]),
+                    ],
+                ),
+            )]
+        }
+    ),
+    fixture!(
+        "cell_struct_and_numeric",
+        "A = struct('a', 42.0); B = [ 1, 2, 3, 4 ]; C = { A, B }; \
+         save('cell_struct_and_numeric.mat', 'C');",
+        || vec![(
+            "C",
+            cell(vec![1, 2], vec![matvar!({ a: 42. }), matvar!([1., 2., 3., 4.])])
+        )]
+    ),
+    fixture!(
+        "cell_struct_array_and_numeric",
+        "A(1,1).a = 42.0; A(1,1).b = 43.0; A(1,2).a = 44.0; A(1,2).b = 45.0; B = [ 1, 2, 3, 4 ]; \
+         C = { A, B }; save('cell_struct_array_and_numeric.mat', 'C');",
+        || {
+            vec![(
+                "C",
+                cell(
+                    vec![1, 2],
+                    vec![
+                        matvar!([{ a: 42., b: 43. }, { a: 44., b: 45. }]),
+                        matvar!([1., 2., 3., 4.]),
+                    ],
+                ),
+            )]
+        }
+    ),
+    fixture!(
+        "cell_cell_and_numeric",
+        "A = { 42.0, 43.0 }; B = [ 1, 2, 3, 4 ]; C = { A, B }; save('cell_cell_and_numeric.mat', 'C');",
+        || {
+            vec![(
+                "C",
+                cell(
+                    vec![1, 2],
+                    vec![
+                        cell(vec![1, 2], vec![matvar!(42.), matvar!(43.)]),
+                        matvar!([1., 2., 3., 4.]),
+                    ],
+                ),
+            )]
+        }
+    ),
+    fixture!(
+        "struct_empty",
+        "S = struct(); save('struct_empty.mat', 'S', '-nocompression');",
+        || vec![("S", MatVariable::Structure(Structure::new(IndexMap::new())))]
+    ),
+    fixture!(
+        "struct_fields",
+        "s.a = 42; s.b = pi; save('struct_fields.mat', 's', '-nocompression');",
+        || vec![("s", matvar!({ a: 42., b: PI }))]
+    ),
+    fixture!(
+        Matlab,
+        "struct_fields_compressed",
+        "s.a = 42; s.b = pi; save('struct_fields_compressed.mat', 's');",
+        || vec![("s", matvar!({ a: 42., b: PI }))]
+    ),
+    fixture!(
+        "struct_array_row",
+        "s(1).a = 42; s(2).a = 43; s(1).b = 52; s(2).b = 53; \
+         save('struct_array_row.mat', 's', '-nocompression');",
+        || vec![("s", matvar!([{ a: 42., b: 52. }, { a: 43., b: 53. }]))]
+    ),
+    fixture!(
+        "struct_array_square",
+        "s(1,1).a = 42; s(1,2).a = 43; s(2,1).a = 44; s(2,2).a = 45; \
+         s(1,1).b = 52; s(1,2).b = 53; s(2,1).b = 54; s(2,2).b = 55; \
+         save('struct_array_square.mat', 's', '-nocompression');",
+        || {
+            let elements = [(42., 52.), (44., 54.), (43., 53.), (45., 55.)]
+                .iter()
+                .map(|&(a, b)| matvar!({ a: a, b: b }))
+                .collect();
+            vec![(
+                "s",
+                MatVariable::StructureArray(StructureArray::from_structures(vec![2, 2], elements).unwrap()),
+            )]
+        }
+    ),
+];
+
+/// All fixtures of the corpus.
+pub fn all() -> &'static [Fixture] {
+    &FIXTURES
+}
+
+/// The fixture called `name`, `None` if there is no such fixture.
+pub fn get(name: &str) -> Option<&'static Fixture> {
+    FIXTURES.iter().find(|fixture| fixture.name == name)
+}
+
+fn numeric(dim: Vec<usize>, value: MatlabType, value_cmp: Option<MatlabType>) -> MatVariable {
+    MatVariable::NumericArray(NumericArray::new(dim, value, value_cmp).unwrap())
+}
+
+fn sparse(dim_i: usize, dim_j: usize, ir: Vec<usize>, jc: Vec<usize>, value: MatlabType) -> MatVariable {
+    MatVariable::SparseArray(SparseArray::new(dim_i, dim_j, ir, jc, value, None).unwrap())
+}
+
+fn cell(dim: Vec<usize>, value: Vec<MatVariable>) -> MatVariable {
+    MatVariable::CellArray(CellArray::new(dim, value).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "deflate")]
+    use crate::save_matfile_v7_to_u8;
+
+    #[cfg(feature = "deflate")]
+    #[test]
+    fn fixtures_match_expected() {
+        for fixture in all() {
+            let matfile = fixture.load().unwrap();
+            let expected = fixture.expected();
+            let names: Vec<&String> = matfile.iter().map(|(name, _)| name).collect();
+            let expected_names: Vec<&String> = expected.iter().map(|(name, _)| name).collect();
+            assert_eq!(names, expected_names, "{}", fixture.name);
+
+            for (name, var) in expected.iter() {
+                assert_eq!(&matfile[name.as_str()], var, "{}: {}", fixture.name, name);
+            }
+        }
+    }
+
+    #[cfg(feature = "deflate")]
+    #[test]
+    fn fixtures_roundtrip() {
+        for fixture in all() {
+            for compress in [false, true] {
+                let data = save_matfile_v7_to_u8(fixture.load().unwrap(), compress).unwrap();
+                let matfile = crate::load_matfile_from_u8(&data).unwrap();
+                for (name, var) in fixture.expected().iter() {
+                    assert_eq!(&matfile[name.as_str()], var, "{}: {}", fixture.name, name);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn origins() {
+        let headers: Vec<&[u8]> = all()
+            .iter()
+            .filter(|f| f.origin == Origin::Assembled)
+            .map(|f| &f.data[..116])
+            .collect();
+        assert!(
+            headers
+                .iter()
+                .all(|h| *h == &get("header_only").unwrap().data[..116])
+        );
+        // As listed by the module documentation
+        assert_eq!(all().iter().filter(|f| f.origin == Origin::Matlab).count(), 4);
+        assert_eq!(headers.len(), 20);
+    }
+
+    #[test]
+    fn get_by_name() {
+        assert!(all().iter().all(|f| get(f.name).is_some()));
+        assert!(get("missing").is_none());
+    }
+}
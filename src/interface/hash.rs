@@ -0,0 +1,324 @@
+//! Content hashes of variables and MAT-files.
+//!
+//! The hash covers what a variable means, not how it is stored: the on-disk storage type,
+//! compression, the character encoding and the order of structure fields and variables do not
+//! change it. `-0.0` hashes like `0.0` and all NaNs hash alike. The hash is computed with 64-bit
+//! FNV-1a and is stable across platforms and matrw versions.
+
+use crate::interface::matfile::MatFile;
+use crate::interface::metadata::METADATA_VARIABLE;
+use crate::interface::types::matlab_types::MatlabType;
+use crate::interface::variable::MatVariable;
+
+/// 64-bit FNV-1a
+struct Fnv(u64);
+
+impl Fnv {
+    fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 ^= b as u64;
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn usizes(&mut self, values: &[usize]) {
+        self.u64(values.len() as u64);
+        values.iter().for_each(|&v| self.u64(v as u64));
+    }
+
+    fn str(&mut self, value: &str) {
+        self.u64(value.len() as u64);
+        self.bytes(value.as_bytes());
+    }
+
+    fn values(&mut self, value: &MatlabType) {
+        macro_rules! ints {
+            ($tag:expr, $v:expr) => {{
+                self.str($tag);
+                self.u64($v.len() as u64);
+                $v.iter().for_each(|x| self.bytes(&x.to_le_bytes()));
+            }};
+        }
+
+        match value {
+            MatlabType::U8(v) => ints!("uint8", v),
+            MatlabType::I8(v) => ints!("int8", v),
+            MatlabType::U16(v) => ints!("uint16", v),
+            MatlabType::I16(v) => ints!("int16", v),
+            MatlabType::U32(v) => ints!("uint32", v),
+            MatlabType::I32(v) => ints!("int32", v),
+            MatlabType::U64(v) => ints!("uint64", v),
+            MatlabType::I64(v) => ints!("int64", v),
+            MatlabType::F32(v) => {
+                let v: Vec<u64> = v.iter().map(|&x| normalize(x as f64)).collect();
+                ints!("single", v)
+            }
+            MatlabType::F64(v) => {
+                let v: Vec<u64> = v.iter().map(|&x| normalize(x)).collect();
+                ints!("double", v)
+            }
+            MatlabType::UTF8(v) | MatlabType::UTF16(v) => {
+                let v: Vec<u32> = v.iter().map(|&c| c as u32).collect();
+                ints!("char", v)
+            }
+            MatlabType::BOOL(v) => {
+                let v: Vec<u8> = v.iter().map(|&b| b as u8).collect();
+                ints!("logical", v)
+            }
+        }
+    }
+
+    fn variable(&mut self, var: &MatVariable) {
+        match var {
+            MatVariable::NumericArray(arr) => {
+                self.str("numeric");
+                self.usizes(&arr.dim);
+                self.values(&arr.value);
+                if let Some(cmp) = &arr.value_cmp {
+                    self.values(cmp);
+                }
+            }
+            MatVariable::SparseArray(arr) => {
+                self.str("sparse");
+                self.usizes(&arr.dim);
+                self.usizes(&arr.ir);
+                self.usizes(&arr.jc);
+                self.values(&arr.value);
+                if let Some(cmp) = &arr.value_cmp {
+                    self.values(cmp);
+                }
+            }
+            MatVariable::CellArray(arr) => {
+                self.str("cell");
+                self.usizes(&arr.dim);
+                arr.value.iter().for_each(|v| self.variable(v));
+            }
+            MatVariable::Structure(s) => {
+                self.str("struct");
+                let mut fields: Vec<_> = s.value.iter().collect();
+                fields.sort_by(|a, b| a.0.cmp(b.0));
+                self.u64(fields.len() as u64);
+                for (name, v) in fields {
+                    self.str(name);
+                    self.variable(v);
+                }
+            }
+            MatVariable::StructureArray(arr) => {
+                self.str("struct array");
+                self.usizes(&arr.dim);
+                let mut names = arr.fieldnames();
+                names.sort();
+                names.iter().for_each(|name| self.str(name));
+                arr.value.iter().for_each(|v| self.variable(v));
+            }
+            MatVariable::Null => self.str("null"),
+            MatVariable::Unsupported => self.str("unsupported"),
+        }
+    }
+}
+
+/// Bit pattern of `x`, with a single zero and a single NaN. Single values are represented
+/// exactly as double, so they are hashed after conversion.
+fn normalize(x: f64) -> u64 {
+    if x == 0.0 {
+        0
+    } else if x.is_nan() {
+        f64::NAN.to_bits()
+    } else {
+        x.to_bits()
+    }
+}
+
+impl MatVariable {
+    ///
+    /// Hash of the content of the variable.
+    ///
+    /// The storage type, compression, character encoding and the order of structure fields are
+    /// not part of the hash, so the hash is independent of the MAT-file writer.
+    ///
+    /// ```
+    /// use matrw::{load_matfile_from_u8, matfile, matvar, save_matfile_v7_to_u8};
+    ///
+    /// let var = matvar!({ a: [1.0, 2.0], b: "text" });
+    ///
+    /// let data = save_matfile_v7_to_u8(matfile!(v: var.clone()), true).unwrap();
+    /// let loaded = load_matfile_from_u8(&data).unwrap();
+    ///
+    /// assert_eq!(loaded["v"].content_hash(), var.content_hash());
+    /// assert_ne!(matvar!({ a: [1.0, 3.0], b: "text" }).content_hash(), var.content_hash());
+    /// ```
+    ///
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = Fnv::new();
+        hasher.variable(self);
+        hasher.0
+    }
+}
+
+impl MatFile {
+    ///
+    /// Hash of the names and contents of all variables, see [`MatVariable::content_hash`].
+    ///
+    /// The metadata variable is skipped, because its creation time changes with every write.
+    ///
+    pub fn content_hash(&self) -> u64 {
+        let mut variables: Vec<_> = self
+            .iter()
+            .filter(|(name, _)| *name != METADATA_VARIABLE)
+            .collect();
+        variables.sort_by(|a, b| a.0.cmp(b.0));
+
+        let mut hasher = Fnv::new();
+        for (name, var) in variables {
+            hasher.str(name);
+            hasher.variable(var);
+        }
+        hasher.0
+    }
+}
+
+/// Round constants of SHA-256
+const SHA256_K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// SHA-256 digest of `data`, to identify byte strings without keeping them
+pub(crate) fn sha256(data: &[u8]) -> [u8; 32] {
+    let mut state: [u32; 8] = [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+    ];
+
+    // Message padded with a one bit, zeros and the length in bits to a multiple of 64 bytes
+    let full = data.len() / 64 * 64;
+    let mut tail = data[full..].to_vec();
+    tail.push(0x80);
+    tail.resize(if tail.len() > 56 { 120 } else { 56 }, 0);
+    tail.extend_from_slice(&(data.len() as u64).wrapping_mul(8).to_be_bytes());
+
+    for block in data[..full].chunks_exact(64).chain(tail.chunks_exact(64)) {
+        let mut w = [0u32; 64];
+        for (w, word) in w.iter_mut().zip(block.chunks_exact(4)) {
+            *w = u32::from_be_bytes(word.try_into().expect("Chunks have 4 bytes."));
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16].wrapping_add(s0).wrapping_add(w[i - 7]).wrapping_add(s1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = state;
+        for i in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (e & f) ^ (!e & g);
+            let t1 = h
+                .wrapping_add(s1)
+                .wrapping_add(ch)
+                .wrapping_add(SHA256_K[i])
+                .wrapping_add(w[i]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(maj);
+            (h, g, f, e, d, c, b, a) = (g, f, e, d.wrapping_add(t1), c, b, a, t1.wrapping_add(t2));
+        }
+        for (s, x) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *s = s.wrapping_add(x);
+        }
+    }
+
+    let mut digest = [0u8; 32];
+    for (bytes, s) in digest.chunks_exact_mut(4).zip(state) {
+        bytes.copy_from_slice(&s.to_be_bytes());
+    }
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MatlabType, Metadata, NumericArray, matfile, matvar};
+
+    #[test]
+    fn sha256_digests() {
+        let hex = |data: &[u8]| {
+            sha256(data)
+                .iter()
+                .map(|b| format!("{b:02x}"))
+                .collect::<String>()
+        };
+        assert_eq!(
+            hex(b""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        // Padding spills into a second block
+        assert_eq!(
+            hex(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"),
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+        );
+        assert_eq!(
+            hex(&[b'a'; 1000]),
+            "41edece42d63e8d9bf515a9ba6932e1c20cbc9f5a5d134645adb5db1b9737ea3"
+        );
+    }
+
+    #[test]
+    fn content_hash_semantics() {
+        let a = matvar!({ x: 1.0, y: "ab" });
+        let b = matvar!({ y: "ab", x: 1.0 });
+        assert_eq!(a.content_hash(), b.content_hash());
+
+        let double = |dim: Vec<usize>, x: Vec<f64>| {
+            MatVariable::NumericArray(NumericArray::new(dim, MatlabType::F64(x), None).unwrap())
+        };
+
+        // Class and dimensions are part of the content
+        assert_ne!(matvar!(1.0).content_hash(), matvar!(1u8).content_hash());
+        assert_ne!(
+            double(vec![1, 2], vec![1.0, 2.0]).content_hash(),
+            double(vec![2, 1], vec![1.0, 2.0]).content_hash()
+        );
+
+        assert_eq!(
+            double(vec![1, 1], vec![0.0]).content_hash(),
+            double(vec![1, 1], vec![-0.0]).content_hash()
+        );
+        assert_eq!(
+            double(vec![1, 1], vec![f64::NAN]).content_hash(),
+            double(vec![1, 1], vec![-f64::NAN]).content_hash()
+        );
+
+        // Stable across versions
+        assert_eq!(matvar!(1.0).content_hash(), 0x4b2e_de89_0a2d_6698);
+    }
+
+    #[test]
+    fn matfile_content_hash() {
+        let mut m1 = matfile!(a: matvar!(1.0), b: matvar!([1i16, 2i16]));
+        let m2 = matfile!(b: matvar!([1i16, 2i16]), a: matvar!(1.0));
+        assert_eq!(m1.content_hash(), m2.content_hash());
+
+        m1.stamp_metadata(Metadata::default());
+        assert_eq!(m1.content_hash(), m2.content_hash());
+
+        let m3 = matfile!(a: matvar!(1.0), c: matvar!([1i16, 2i16]));
+        assert_ne!(m1.content_hash(), m3.content_hash());
+    }
+}
@@ -2,53 +2,141 @@
 //! Module containing helper functions
 //!
 
+/// MATLAB keywords, see MATLAB's `iskeyword`
+static MATLAB_KEYWORDS: [&str; 20] = [
+    "break",
+    "case",
+    "catch",
+    "classdef",
+    "continue",
+    "else",
+    "elseif",
+    "end",
+    "for",
+    "function",
+    "global",
+    "if",
+    "otherwise",
+    "parfor",
+    "persistent",
+    "return",
+    "spmd",
+    "switch",
+    "try",
+    "while",
+];
+
+/// Maximum length of MATLAB variable names, see MATLAB's `namelengthmax`
+const MAX_NAME_LENGTH: usize = 63;
+
 ///
 /// Return if string `name` is a valid MATALB variable name.
 ///
 pub fn is_valid_variable_name(name: &str) -> bool {
-    if name.is_empty() {
-        return false;
-    }
+    invalid_variable_name(name).is_none()
+}
 
-    static MATLABKEYWORDS: [&str; 20] = [
-        "break",
-        "case",
-        "catch",
-        "classdef",
-        "continue",
-        "else",
-        "elseif",
-        "end",
-        "for",
-        "function",
-        "global",
-        "if",
-        "otherwise",
-        "parfor",
-        "persistent",
-        "return",
-        "spmd",
-        "switch",
-        "try",
-        "while",
-    ];
+///
+/// Return why `name` is not a valid MATLAB variable name, or `None` if it is valid.
+///
+/// ```
+/// use matrw::interface::helper::invalid_variable_name;
+///
+/// assert_eq!(invalid_variable_name("end").as_deref(), Some("Invalid variable name 'end', it is a MATLAB keyword"));
+/// assert_eq!(invalid_variable_name("x1"), None);
+/// ```
+///
+pub fn invalid_variable_name(name: &str) -> Option<String> {
+    // Variable name must start with a letter, then followed by letters, numbers or underscores.
+    let reason = if name.is_empty() {
+        "it is empty"
+    } else if MATLAB_KEYWORDS.contains(&name) {
+        "it is a MATLAB keyword"
+    } else if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        "it does not start with a letter"
+    } else if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        "it contains characters other than letters, digits and underscores"
+    } else if name.len() > MAX_NAME_LENGTH {
+        "it is longer than 63 characters"
+    } else {
+        return None;
+    };
 
-    if MATLABKEYWORDS.contains(&name) {
-        return false;
-    }
+    Some(format!("Invalid variable name '{}', {}", name, reason))
+}
 
-    // Variable name must start with a letter, then followed by letters, numbers or underscores.
-    let ok_first = name.chars().next().unwrap().is_ascii_alphabetic();
-    let ok_symbols = name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
-    let ok_len = name.len() <= 63;
+/// Year, month, day, hour, minute, second and weekday from `0` for Sunday of the UTC time
+/// `secs` seconds after the Unix epoch
+fn utc_fields(secs: i64) -> [i64; 7] {
+    let (days, secs) = (secs.div_euclid(86400), secs.rem_euclid(86400));
+
+    // Civil date of the day count, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
 
-    ok_first && ok_symbols && ok_len
+    [
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60,
+        (days + 4).rem_euclid(7),
+    ]
+}
+
+fn utc_now() -> [i64; 7] {
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64);
+    utc_fields(secs)
+}
+
+/// Current UTC time like `Tue Oct 14 09:05:00 2025`, the format of MATLAB's header text
+pub(crate) fn utc_header_time() -> String {
+    const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    let [year, month, day, hour, minute, second, weekday] = utc_now();
+    format!(
+        "{} {} {:02} {:02}:{:02}:{:02} {}",
+        WEEKDAYS[weekday as usize],
+        MONTHS[month as usize - 1],
+        day,
+        hour,
+        minute,
+        second,
+        year
+    )
+}
+
+/// Current UTC time in RFC 3339 format
+pub(crate) fn utc_rfc3339() -> String {
+    let [year, month, day, hour, minute, second, _] = utc_now();
+    format!("{year:04}-{month:02}-{day:02}T{hour:02}:{minute:02}:{second:02}+00:00")
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn utc_time() {
+        assert_eq!(utc_fields(0), [1970, 1, 1, 0, 0, 0, 4]);
+        assert_eq!(utc_fields(1_700_000_000), [2023, 11, 14, 22, 13, 20, 2]);
+        assert_eq!(utc_fields(951_782_400), [2000, 2, 29, 0, 0, 0, 2]);
+        assert_eq!(utc_header_time().len(), 24);
+        assert_eq!(utc_rfc3339().len(), 25);
+    }
+
     #[test]
     fn valid_variable_names() {
         // Valid names
@@ -70,4 +158,26 @@ mod tests {
             "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa_too_long_variable_name"
         ));
     }
+
+    #[test]
+    fn invalid_variable_name_reasons() {
+        let reason = |name: &str| invalid_variable_name(name).unwrap();
+
+        assert_eq!(reason(""), "Invalid variable name '', it is empty");
+        assert_eq!(
+            reason("for"),
+            "Invalid variable name 'for', it is a MATLAB keyword"
+        );
+        assert_eq!(
+            reason("_a"),
+            "Invalid variable name '_a', it does not start with a letter"
+        );
+        assert_eq!(
+            reason("a-b"),
+            "Invalid variable name 'a-b', it contains characters other than letters, digits and underscores"
+        );
+        assert!(reason(&"a".repeat(64)).ends_with("it is longer than 63 characters"));
+        assert!(MATLAB_KEYWORDS.iter().all(|k| !is_valid_variable_name(k)));
+        assert!(is_valid_variable_name("ans"));
+    }
 }
@@ -1,4 +1,7 @@
-use crate::interface::types::array::ArrayType;
+use std::ops::Sub;
+
+use crate::interface::types::array::{ArrayType, numel};
+use crate::interface::types::dim::MatDim;
 use crate::interface::variable::MatVariable;
 
 pub trait Index: private::Sealed {
@@ -81,6 +84,107 @@ impl Index for &str {
     }
 }
 
+/// Index relative to the end of an array, mirroring MATLAB's `end` keyword.
+///
+/// `Idx::End` is the last element, `Idx::End - n` the element `n` positions before it. Linear
+/// indices count all elements in column-major order, subscripts count elements along their
+/// dimension.
+///
+/// ```
+/// use matrw::{matvar, Idx, OwnedIndex};
+///
+/// let a = matvar!([1, 2, 3, 4]);
+/// assert_eq!(a.elem(Idx::End).to_i32(), Some(4));
+/// assert_eq!(a.elem(Idx::End - 1).to_i32(), Some(3));
+/// assert_eq!(a.elem(Idx::At(0)).to_i32(), Some(1));
+///
+/// let b = matvar!([[1, 2], [3, 4]]);
+/// assert_eq!(b.elem([Idx::End, Idx::At(0)]).to_i32(), Some(3));
+///
+/// let c = matvar!(["abc", 42., 1]);
+/// assert_eq!(c[Idx::End - 1].to_f64(), Some(42.));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Idx {
+    /// Zero-based position from the start
+    At(usize),
+    /// Last position
+    End,
+    /// Position the given number of elements before the last one
+    EndMinus(usize),
+}
+
+impl Idx {
+    /// Zero-based position within `len` elements, `None` if out of range.
+    pub fn resolve(self, len: usize) -> Option<usize> {
+        match self {
+            Idx::At(i) => (i < len).then_some(i),
+            Idx::End => len.checked_sub(1),
+            Idx::EndMinus(n) => len.checked_sub(n.checked_add(1)?),
+        }
+    }
+}
+
+impl From<usize> for Idx {
+    fn from(value: usize) -> Self {
+        Idx::At(value)
+    }
+}
+
+impl Sub<usize> for Idx {
+    type Output = Idx;
+
+    /// # Panics
+    ///
+    /// Panics, if a position from the start is subtracted below zero.
+    fn sub(self, rhs: usize) -> Self::Output {
+        match self {
+            Idx::At(i) => Idx::At(i - rhs),
+            Idx::End => Idx::EndMinus(rhs),
+            Idx::EndMinus(n) => Idx::EndMinus(n + rhs),
+        }
+    }
+}
+
+fn dim(v: &MatVariable) -> Option<&MatDim> {
+    match v {
+        MatVariable::NumericArray(n) => Some(n.dim()),
+        MatVariable::SparseArray(n) => Some(n.dim()),
+        MatVariable::CellArray(n) => Some(n.dim()),
+        MatVariable::StructureArray(n) => Some(n.dim()),
+        _ => None,
+    }
+}
+
+impl Index for Idx {
+    fn index_into_clone(&self, v: &MatVariable) -> Option<MatVariable> {
+        let index = self.resolve(numel(dim(v)?)?)?;
+        index.index_into_clone(v)
+    }
+    fn index_into_ref<'a>(&self, v: &'a MatVariable) -> Option<&'a MatVariable> {
+        let index = self.resolve(numel(dim(v)?)?)?;
+        index.index_into_ref(v)
+    }
+}
+
+impl<const N: usize> Index for [Idx; N] {
+    fn index_into_clone(&self, v: &MatVariable) -> Option<MatVariable> {
+        let subs = resolve_subscripts(self, dim(v)?)?;
+        subs.as_slice().index_into_clone(v)
+    }
+    fn index_into_ref<'a>(&self, v: &'a MatVariable) -> Option<&'a MatVariable> {
+        let subs = resolve_subscripts(self, dim(v)?)?;
+        subs.as_slice().index_into_ref(v)
+    }
+}
+
+fn resolve_subscripts(idx: &[Idx], dim: &[usize]) -> Option<Vec<usize>> {
+    if idx.len() != dim.len() {
+        return None;
+    }
+    idx.iter().zip(dim).map(|(i, &d)| i.resolve(d)).collect()
+}
+
 mod private {
     pub trait Sealed {}
     impl Sealed for usize {}
@@ -93,5 +197,7 @@ mod private {
     impl Sealed for [usize; 6] {}
     impl Sealed for str {}
     impl Sealed for String {}
+    impl Sealed for super::Idx {}
+    impl<const N: usize> Sealed for [super::Idx; N] {}
     impl<T> Sealed for &T where T: ?Sized + Sealed {}
 }
@@ -93,6 +93,16 @@
 ///         },
 ///         ]);
 /// ```
+/// ```
+/// # use matrw::{matvar, MatlabType};
+/// #
+/// // Construct a row vector of class int16. Numeric values, also inside of structures and
+/// // cells, are converted to the annotated class, which is one of `u8`, `i8`, `u16`, `i16`,
+/// // `u32`, `i32`, `u64`, `i64`, `f32` and `f64`.
+/// let var = matvar!(i16: [1, 2, 3]);
+///
+/// assert_eq!(var.numeric_type(), Some(&MatlabType::I16(vec![1, 2, 3])));
+/// ```
 ///
 #[macro_export]
 macro_rules! matvar {
@@ -126,10 +136,10 @@ macro_rules! matvar_internal {
     // Done with trailing comma.
     (@array [$($elems:expr,)*]) => {{
         let v = vec![$(($elems),)*];
-        if v.iter().all(|x| matches!(x, $crate::MatVariable::NumericArray(_))) && $crate::check_same_dim(&v) && $crate::check_same_type(&v) {
+        if $crate::MatVariable::same_numeric_class(&v) && $crate::MatVariable::same_shape(&v) {
             $crate::MatVariable::NumericArray($crate::NumericArray::from_nested_matvar(vec![1, v.len()], v).unwrap())
         } else if v.iter().all(|x| matches!(x, $crate::MatVariable::Structure(_))) && $crate::check_same_fields(&v) {
-            $crate::MatVariable::StructureArray($crate::StructureArray::from_structures(vec![1, v.len()], v))
+            $crate::MatVariable::StructureArray($crate::StructureArray::from_structures(vec![1, v.len()], v).unwrap())
         } else {
             $crate::MatVariable::CellArray($crate::CellArray::new(vec![1, v.len()], v).unwrap())
         }
@@ -138,10 +148,10 @@ macro_rules! matvar_internal {
     // Done without trailing comma.
     (@array [$($elems:expr),*]) => {{
         let v = vec![$(($elems)),*];
-        if v.iter().all(|x| matches!(x, $crate::MatVariable::NumericArray(_))) && $crate::check_same_dim(&v) && $crate::check_same_type(&v) {
+        if $crate::MatVariable::same_numeric_class(&v) && $crate::MatVariable::same_shape(&v) {
             $crate::MatVariable::NumericArray($crate::NumericArray::from_nested_matvar(vec![1, v.len()], v).unwrap())
         } else if v.iter().all(|x| matches!(x, $crate::MatVariable::Structure(_))) && $crate::check_same_fields(&v) {
-            $crate::MatVariable::StructureArray($crate::StructureArray::from_structures(vec![1, v.len()], v))
+            $crate::MatVariable::StructureArray($crate::StructureArray::from_structures(vec![1, v.len()], v).unwrap())
         } else  {
             $crate::MatVariable::CellArray($crate::CellArray::new(vec![1, v.len()], v).unwrap())
         }
@@ -217,6 +227,25 @@ macro_rules! matvar_internal {
         }))
     };
 
+    // Match a class annotation
+    ($class:ident : $($tt:tt)+) => {{
+        let mut var = $crate::matvar_internal!($($tt)+);
+        var.convert_numeric($crate::matvar_internal!(@class $class))
+            .expect("Class annotations are numeric");
+        var
+    }};
+
+    (@class u8) => { $crate::VariableClass::UInt8 };
+    (@class i8) => { $crate::VariableClass::Int8 };
+    (@class u16) => { $crate::VariableClass::UInt16 };
+    (@class i16) => { $crate::VariableClass::Int16 };
+    (@class u32) => { $crate::VariableClass::UInt32 };
+    (@class i32) => { $crate::VariableClass::Int32 };
+    (@class u64) => { $crate::VariableClass::UInt64 };
+    (@class i64) => { $crate::VariableClass::Int64 };
+    (@class f32) => { $crate::VariableClass::Single };
+    (@class f64) => { $crate::VariableClass::Double };
+
     // Any Serialize type: numbers, strings, struct literals, variables etc.
     // Must be below every other rule.
     ($other:expr) => {{
@@ -332,6 +361,22 @@ mod tests {
         let v = matvar!([(1.0, 42.), (2.0, 43.), (3.0, 44.)]);
         println!("v = {:#?}", v);
     }
+
+    #[test]
+    fn macro_class_annotation() {
+        use crate::MatlabType;
+
+        let v = matvar!(u8: [[1, 2], [3, 300]]);
+        assert_eq!(v.numeric_type(), Some(&MatlabType::U8(vec![1, 3, 2, 255])));
+
+        let s = matvar!(f32: { a: 1, b: "x", c: [1.5, 2.5] });
+        assert_eq!(s["a"].to_f32(), Some(1.0));
+        assert_eq!(s["b"].numeric_type(), Some(&MatlabType::from("x")));
+        assert_eq!(s["c"].to_vec_f32(), Some(vec![1.5, 2.5]));
+
+        let x = matvar!(i64: 7);
+        assert_eq!(x.to_i64(), Some(7));
+    }
 }
 
 ///
@@ -349,6 +394,9 @@ mod tests {
 ///     // Insert another variable "b"
 ///     b: matvar!(42.),
 /// );
+///
+/// // Create an empty MAT-file
+/// let empty = matfile!();
 /// ```
 ///
 /// # Panics
@@ -365,6 +413,10 @@ mod tests {
 ///
 #[macro_export]
 macro_rules! matfile {
+    () => {
+        $crate::MatFile::new()
+    };
+
     ($($matfile:tt)+) => {
         $crate::matfile_internal!($($matfile)+)
     }
@@ -1,20 +1,154 @@
 use std::ops::Index;
 
+use binrw::Endian;
 use indexmap::IndexMap;
-use indexmap::map::{Iter, IterMut};
 
-use crate::interface::helper::is_valid_variable_name;
+use crate::MatrwError;
+use crate::interface::helper::invalid_variable_name;
+use crate::interface::metadata::HeaderInfo;
+use crate::interface::schema::{Schema, ValidationReport};
+use crate::interface::skip_report::SkipReport;
+use crate::interface::types::compressed_array::CompressedArray;
+use crate::interface::types::matlab_types::{MatlabTypeMarker, VariableClass};
+use crate::interface::types::numeric_array::NumericArray;
 use crate::interface::variable::MatVariable;
-use crate::parser::v7::matfile7::MatFile7;
+use crate::parser::v7::matfile7::{MatFile7, insert_variable};
 
 ///
 /// MAT-file container
 ///
 /// Stores MAT-file variables using a variable name as unique key.
 ///
+/// # Order
+///
+/// Variables keep their order: the insertion order for variables created in Rust and the file
+/// order for variables read from a file. Iteration, printing, writing and serialization follow
+/// it, and so do the fields of structures. This order is part of the API. Replacing a variable
+/// with [`MatFile::insert`] keeps its position, [`MatFile::take`] keeps the order of the
+/// others. [`MatFile::sort_keys`] sorts variables and fields by name instead.
+///
+/// ```
+/// use matrw::{matfile, matvar};
+///
+/// let mut matfile = matfile!(z: matvar!(1.0), a: matvar!({ y: 1.0, b: 2.0 }));
+/// matfile.insert("m", matvar!(3.0));
+/// let names: Vec<_> = matfile.names().collect();
+/// assert_eq!(names, vec!["z", "a", "m"]);
+///
+/// matfile.sort_keys();
+/// let names: Vec<_> = matfile.names().collect();
+/// assert_eq!(names, vec!["a", "m", "z"]);
+/// assert_eq!(matfile["a"].fieldnames(), Some(vec!["b".to_string(), "y".to_string()]));
+/// ```
+///
 #[derive(Debug)]
 pub struct MatFile {
-    data: IndexMap<String, MatVariable>,
+    data: IndexMap<String, Entry>,
+    subsystem: Option<Subsystem>,
+    header: Option<HeaderInfo>,
+    warnings: Vec<String>,
+    skipped: SkipReport,
+    /// Metadata attached to variables, see [`MatFile::set_meta`]
+    pub(crate) variable_meta: IndexMap<String, IndexMap<String, MatVariable>>,
+    /// Sizes of the variables read from a file, see [`MatFile::variable_info`]
+    variable_info: IndexMap<String, VariableInfo>,
+}
+
+/// Sizes of a variable in the file it was read from, see [`MatFile::variable_info`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VariableInfo {
+    /// Position of the tag of the element, from the start of the file
+    pub offset: u64,
+    /// Bytes of the element in the file, including tag and padding
+    pub disk_bytes: u64,
+    /// Bytes of the element after decompression, including its tag. Equal to `disk_bytes` for
+    /// variables stored without compression.
+    pub uncompressed_bytes: u64,
+    /// Whether the variable is stored in a compressed element
+    pub compressed: bool,
+}
+
+impl VariableInfo {
+    /// Uncompressed bytes per byte in the file, `1.0` for variables stored without compression
+    pub fn compression_ratio(&self) -> f64 {
+        self.uncompressed_bytes as f64 / self.disk_bytes as f64
+    }
+
+    ///
+    /// Bytes of the element in the file, including its tag. For compressed variables these are
+    /// the `miCOMPRESSED` tag followed by the zlib stream, which can be copied or hashed without
+    /// decompressing.
+    ///
+    pub fn byte_range(&self) -> std::ops::Range<u64> {
+        self.offset..self.offset + self.disk_bytes
+    }
+}
+
+/// Variable of a `MatFile`, compressed variables of lazily loaded files are decompressed on
+/// first access.
+#[derive(Debug)]
+pub(crate) enum Entry {
+    Loaded(MatVariable),
+    Compressed(CompressedArray),
+}
+
+impl Entry {
+    fn try_value(&self) -> Result<&MatVariable, MatrwError> {
+        match self {
+            Entry::Loaded(var) => Ok(var),
+            Entry::Compressed(c) => c.try_value(),
+        }
+    }
+
+    /// Contained variable, [`MatVariable::Unsupported`] if its compressed data is corrupt
+    fn value(&self) -> &MatVariable {
+        self.try_value().unwrap_or(&MatVariable::Unsupported)
+    }
+
+    /// Contained variable, replacing the compressed data by it.
+    fn try_value_mut(&mut self) -> Result<&mut MatVariable, MatrwError> {
+        if let Entry::Compressed(c) = self {
+            *self = Entry::Loaded(c.try_take_value()?);
+        }
+        match self {
+            Entry::Loaded(var) => Ok(var),
+            Entry::Compressed(_) => unreachable!("Variable is decompressed."),
+        }
+    }
+
+    /// Contained variable, replacing the compressed data by it. Corrupt compressed data is
+    /// replaced by [`MatVariable::Unsupported`].
+    pub(crate) fn value_mut(&mut self) -> &mut MatVariable {
+        if self.try_value_mut().is_err() {
+            *self = Entry::Loaded(MatVariable::Unsupported);
+        }
+        self.try_value_mut().expect("Variable is decompressed.")
+    }
+
+    fn try_into_value(self) -> Result<MatVariable, MatrwError> {
+        match self {
+            Entry::Loaded(var) => Ok(var),
+            Entry::Compressed(mut c) => c.try_take_value(),
+        }
+    }
+
+    /// Contained variable, [`MatVariable::Unsupported`] if its compressed data is corrupt
+    fn into_value(self) -> MatVariable {
+        self.try_into_value().unwrap_or(MatVariable::Unsupported)
+    }
+}
+
+impl From<MatVariable> for Entry {
+    fn from(value: MatVariable) -> Self {
+        Entry::Loaded(value)
+    }
+}
+
+/// Raw subsystem data of a MAT-file, as found at the offset given in the file header
+#[derive(Debug, Clone)]
+pub(crate) struct Subsystem {
+    pub data: Vec<u8>,
+    pub endian: Endian,
 }
 
 impl MatFile {
@@ -24,14 +158,202 @@ impl MatFile {
     pub fn new() -> Self {
         Self {
             data: IndexMap::new(),
+            subsystem: None,
+            header: None,
+            warnings: Vec::new(),
+            skipped: SkipReport::default(),
+            variable_meta: IndexMap::new(),
+            variable_info: IndexMap::new(),
+        }
+    }
+
+    ///
+    /// Create `MatFile` of the numeric arrays `arrays`, in the given order.
+    ///
+    /// This builds a file from computed arrays without the macro [`crate::matfile`]. Arrays in
+    /// row-major order are converted with [`NumericArray::from_row_major`]. Returns
+    /// [`MatrwError::AccessError`], if a name is not a valid MATLAB variable name or is given more
+    /// than once.
+    ///
+    /// Only [`NumericArray`]s are taken. matrw doesn't depend on `ndarray`, so there is no
+    /// `ndarray` feature and no overload for `ArrayD<f64>`. Such an array `a` in any memory layout
+    /// converts with `from_row_major(a.shape().to_vec(), a.iter().copied().collect())`, as its
+    /// iterator visits the elements in logical, row-major order.
+    ///
+    /// ```
+    /// use matrw::{MatFile, NumericArray};
+    ///
+    /// let shape = vec![2, 3];
+    /// let data = vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+    /// let grid = NumericArray::from_row_major(shape, data).unwrap();
+    /// let t = NumericArray::from_row_major(vec![3], vec![0.0, 0.5, 1.0]).unwrap();
+    ///
+    /// let matfile = MatFile::from_arrays([("grid", grid), ("t", t)]).unwrap();
+    /// assert_eq!(matfile["grid"].to_vec_f64(), Some(vec![1.0, 4.0, 2.0, 5.0, 3.0, 6.0]));
+    /// assert_eq!(matfile["t"].dim(), [1, 3]);
+    /// ```
+    ///
+    pub fn from_arrays<I, S>(arrays: I) -> Result<Self, MatrwError>
+    where
+        I: IntoIterator<Item = (S, NumericArray)>,
+        S: AsRef<str>,
+    {
+        let mut matfile = MatFile::new();
+        for (name, arr) in arrays {
+            let name = name.as_ref();
+            if matfile.contains(name) {
+                return Err(MatrwError::AccessError(format!(
+                    "Variable '{}' created more than once",
+                    name
+                )));
+            }
+            matfile.try_insert(name, MatVariable::NumericArray(arr))?;
         }
+        Ok(matfile)
+    }
+
+    /// Create `MatFile` from variables read from a file.
+    pub(crate) fn from_variables(
+        data: IndexMap<String, Entry>,
+        variable_info: IndexMap<String, VariableInfo>,
+        warnings: Vec<String>,
+        skipped: SkipReport,
+    ) -> Self {
+        Self {
+            data,
+            subsystem: None,
+            header: None,
+            warnings,
+            skipped,
+            variable_meta: IndexMap::new(),
+            variable_info,
+        }
+    }
+
+    ///
+    /// Warnings about data elements that were skipped when reading the file.
+    ///
+    /// Elements with reserved or unknown data types and arrays with unsupported contents don't
+    /// fail the whole file. Skipped arrays, whose name is known, are loaded as
+    /// [`MatVariable::Unsupported`]. Variables without name are loaded as `unnamed_1`,
+    /// `unnamed_2` and so on, with a warning each.
+    ///
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    ///
+    /// Class, name, position and reason of the data elements that were skipped when reading the
+    /// file, the same elements [`MatFile::warnings`] describes.
+    ///
+    /// Compressed variables of lazily loaded files, which haven't been decompressed while
+    /// loading, are not checked.
+    ///
+    pub fn skip_report(&self) -> &SkipReport {
+        &self.skipped
+    }
+
+    ///
+    /// Bytes of memory held by the variables, their names and the subsystem data, see
+    /// [`MatVariable::memory_usage`].
+    ///
+    /// Variables of lazily loaded files, which haven't been decompressed yet, count with their
+    /// compressed data. Nothing is decompressed.
+    ///
+    /// Example
+    /// ```
+    /// use matrw::{matfile, matvar};
+    ///
+    /// let matfile = matfile!(a: matvar!(vec![0.5; 1000]));
+    /// assert!(matfile.memory_usage() > 8 * 1000);
+    /// ```
+    ///
+    pub fn memory_usage(&self) -> u64 {
+        let variables: u64 = self
+            .data
+            .iter()
+            .map(|(name, entry)| {
+                let bytes = match entry {
+                    Entry::Loaded(var) => var.memory_usage(),
+                    Entry::Compressed(c) => c.memory_usage(),
+                };
+                (size_of::<(String, Entry)>() + name.len()) as u64 + bytes
+            })
+            .sum();
+        variables + self.subsystem.as_ref().map_or(0, |s| s.data.len() as u64)
+    }
+
+    ///
+    /// Sizes of variable `name` in the file it was read from, to find the variables, which take
+    /// the space of a file.
+    ///
+    /// Returns `None`, if the variable does not exist or was inserted or replaced after reading.
+    /// Changes of the variable in place don't update the sizes.
+    ///
+    /// Example
+    /// ```
+    /// use matrw::{load_matfile_from_u8, matfile, matvar, save_matfile_v7_to_u8};
+    ///
+    /// let data = save_matfile_v7_to_u8(matfile!(halves: matvar!(vec![0.5; 1000])), true).unwrap();
+    /// let matfile = load_matfile_from_u8(&data).unwrap();
+    ///
+    /// let info = matfile.variable_info("halves").unwrap();
+    /// assert!(info.compressed);
+    /// assert!(info.uncompressed_bytes > 8 * 1000);
+    /// # #[cfg(feature = "deflate")]
+    /// assert!(info.compression_ratio() > 10.0);
+    ///
+    /// // The compressed element as stored in the file
+    /// let range = info.byte_range();
+    /// let element = &data[range.start as usize..range.end as usize];
+    /// assert_eq!(element[..4], 15u32.to_le_bytes()); // miCOMPRESSED
+    /// ```
+    ///
+    pub fn variable_info(&self, name: &str) -> Option<VariableInfo> {
+        self.variable_info.get(name).copied()
+    }
+
+    /// Attach the subsystem data read from a file.
+    pub(crate) fn set_subsystem(&mut self, subsystem: Subsystem) {
+        self.subsystem = Some(subsystem);
+    }
+
+    /// Detach the subsystem data for writing.
+    pub(crate) fn take_subsystem(&mut self) -> Option<Subsystem> {
+        self.subsystem.take()
+    }
+
+    ///
+    /// Raw subsystem data, if the MAT-file was read from a file containing one.
+    ///
+    /// MATLAB stores data shared among objects, e.g. the properties of MCOS objects like `string`
+    /// or `table`, in a subsystem at the end of the file. The data is kept as is and only written
+    /// back with [`crate::SaveOptions::keep_subsystem`].
+    ///
+    pub fn subsystem_data(&self) -> Option<&[u8]> {
+        self.subsystem.as_ref().map(|s| s.data.as_slice())
+    }
+
+    /// Attach the header text read from a file.
+    pub(crate) fn set_header(&mut self, header: HeaderInfo) {
+        self.header = Some(header);
+    }
+
+    ///
+    /// Descriptive text of the file header, if the MAT-file was read from a file.
+    ///
+    /// The text names platform and creation time, see [`HeaderInfo::likely_writer`] to tell
+    /// which tool wrote the file.
+    ///
+    pub fn header(&self) -> Option<&HeaderInfo> {
+        self.header.as_ref()
     }
 
     ///
     /// Insert a `MatVariable` called `name`.
     ///
     /// A valid MATLAB variable
-    /// - has a length between 1 and 64 characters,
+    /// - has a length between 1 and 63 characters,
     /// - starts with a ascii alphabetic character,
     /// - contains only ascii alpha-numeric or underscore characters,
     /// - does not match any reserved keyword.
@@ -41,22 +363,169 @@ impl MatFile {
     /// # Panics
     ///
     /// Panics, if `name` does not meet the criteria for a valid MATLAB variable
-    /// name.
+    /// name, see [`MatFile::try_insert`].
     ///
     pub fn insert(&mut self, name: &str, value: MatVariable) {
-        if !is_valid_variable_name(name) {
-            panic!("Invalid variable name");
+        if let Some(err) = invalid_variable_name(name) {
+            panic!("{}", err);
+        }
+        self.variable_info.shift_remove(name);
+        self.data.insert(name.to_string(), value.into());
+    }
+
+    ///
+    /// Insert a `MatVariable` called `name`, see [`MatFile::insert`].
+    ///
+    /// Returns [`MatrwError::AccessError`] naming the reason, if `name` is not a valid MATLAB
+    /// variable name, e.g. a keyword like `end`.
+    ///
+    /// ```
+    /// use matrw::{MatFile, matvar};
+    ///
+    /// let mut matfile = MatFile::new();
+    /// let err = matfile.try_insert("end", matvar!(1.0)).unwrap_err();
+    /// assert_eq!(err.to_string(), "Invalid variable name 'end', it is a MATLAB keyword");
+    /// assert!(matfile.try_insert("stop", matvar!(1.0)).is_ok());
+    /// ```
+    ///
+    pub fn try_insert(&mut self, name: &str, value: MatVariable) -> Result<(), MatrwError> {
+        if let Some(err) = invalid_variable_name(name) {
+            return Err(MatrwError::AccessError(err));
         }
-        self.data.insert(name.to_string(), value);
+        self.variable_info.shift_remove(name);
+        self.data.insert(name.to_string(), value.into());
+        Ok(())
+    }
+
+    /// Insert a variable without validating its name, for reserved names like the metadata
+    /// variable.
+    pub(crate) fn insert_unchecked(&mut self, name: &str, value: MatVariable) {
+        self.variable_info.shift_remove(name);
+        self.data.insert(name.to_string(), value.into());
     }
 
     ///
     /// Take variable out of container.
     ///
-    /// Returns `MatVariable` stored under `name`. If not existing, returns `None`.
+    /// Returns `MatVariable` stored under `name`. If not existing, returns `None`. Metadata
+    /// attached to the variable is dropped, see [`MatFile::set_meta`].
+    ///
+    /// A compressed variable with corrupt data is returned as [`MatVariable::Unsupported`], see
+    /// [`MatFile::try_take`].
     ///
     pub fn take(&mut self, name: &str) -> Option<MatVariable> {
-        self.data.shift_remove(name)
+        self.variable_meta.shift_remove(name);
+        self.variable_info.shift_remove(name);
+        self.data.shift_remove(name).map(Entry::into_value)
+    }
+
+    ///
+    /// Take variable out of container, like [`MatFile::take`].
+    ///
+    /// Returns [`MatrwError::IoError`] or [`MatrwError::BinrwError`], if the variable is
+    /// compressed and its data is corrupt. The variable is kept in the file then.
+    ///
+    pub fn try_take(&mut self, name: &str) -> Result<Option<MatVariable>, MatrwError> {
+        match self.data.get(name) {
+            Some(entry) => entry.try_value()?,
+            None => return Ok(None),
+        };
+        Ok(self.take(name))
+    }
+
+    ///
+    /// Return a reference to variable `name`, or `None` if it does not exist.
+    ///
+    /// Compressed variables of files loaded with [`crate::load_matfile_lazy`] are decompressed
+    /// on first access. A compressed variable with corrupt data is returned as
+    /// [`MatVariable::Unsupported`], see [`MatFile::try_get`].
+    ///
+    pub fn get(&self, name: &str) -> Option<&MatVariable> {
+        self.data.get(name).map(Entry::value)
+    }
+
+    ///
+    /// Return a reference to variable `name`, like [`MatFile::get`].
+    ///
+    /// Returns [`MatrwError::IoError`] or [`MatrwError::BinrwError`], if the variable is
+    /// compressed and its data is corrupt.
+    ///
+    /// ```
+    /// use matrw::{load_matfile_lazy_from_u8, matfile, matvar, save_matfile_v7_to_u8};
+    ///
+    /// let data = save_matfile_v7_to_u8(matfile!(a: matvar!([1.0, 2.0])), true).unwrap();
+    /// let matfile = load_matfile_lazy_from_u8(&data).unwrap();
+    ///
+    /// assert_eq!(matfile.try_get("a").unwrap(), Some(&matvar!([1.0, 2.0])));
+    /// assert_eq!(matfile.try_get("b").unwrap(), None);
+    /// ```
+    ///
+    pub fn try_get(&self, name: &str) -> Result<Option<&MatVariable>, MatrwError> {
+        self.data.get(name).map(Entry::try_value).transpose()
+    }
+
+    ///
+    /// Return if variable `name` exists and is decompressed, i.e. accessing it doesn't decompress
+    /// data.
+    ///
+    /// Only variables of files loaded with [`crate::load_matfile_lazy`] are kept compressed
+    /// until they are accessed.
+    ///
+    pub fn is_decompressed(&self, name: &str) -> bool {
+        match self.data.get(name) {
+            Some(Entry::Loaded(_)) => true,
+            Some(Entry::Compressed(c)) => c.is_decompressed(),
+            None => false,
+        }
+    }
+
+    ///
+    /// Copy the real part of variable `name` into `buf` in column-major order.
+    ///
+    /// Returns [`MatrwError::AccessError`], if the variable does not exist or is not a numeric
+    /// array, and [`MatrwError::TypeConstruction`], if `buf` does not match class or number of
+    /// elements of the variable, see [`crate::NumericArray::copy_to`].
+    ///
+    /// ```
+    /// use matrw::{matfile, matvar};
+    ///
+    /// let matfile = matfile!(x: matvar!([[1.0, 2.0], [3.0, 4.0]]));
+    ///
+    /// let mut buf = vec![0.0; 4];
+    /// matfile.read_variable_into::<f64>("x", &mut buf).unwrap();
+    /// assert_eq!(buf, vec![1.0, 3.0, 2.0, 4.0]);
+    /// ```
+    ///
+    pub fn read_variable_into<T: MatlabTypeMarker>(
+        &self,
+        name: &str,
+        buf: &mut [T],
+    ) -> Result<(), MatrwError> {
+        match self.get(name) {
+            Some(MatVariable::NumericArray(arr)) => arr.copy_to(buf),
+            Some(_) => Err(MatrwError::AccessError(format!(
+                "Variable '{}' is not a numeric array",
+                name
+            ))),
+            None => Err(MatrwError::AccessError(format!(
+                "Variable '{}' does not exist",
+                name
+            ))),
+        }
+    }
+
+    ///
+    /// Decompress all compressed variables and drop their compressed data.
+    ///
+    /// Returns [`MatrwError::IoError`] or [`MatrwError::BinrwError`], if the data of a
+    /// compressed variable is corrupt.
+    ///
+    pub fn materialize(&mut self) -> Result<(), MatrwError> {
+        for entry in self.data.values_mut() {
+            entry.try_value_mut()?;
+        }
+
+        Ok(())
     }
 
     ///
@@ -69,27 +538,293 @@ impl MatFile {
     ///
     /// Return iterator over variables.
     ///
-    pub fn iter(&self) -> Iter<'_, String, MatVariable> {
-        self.data.iter()
+    /// Compressed variables are decompressed, when the iterator reaches them, and corrupt ones
+    /// are returned as [`MatVariable::Unsupported`], see [`MatFile::get`]. [`MatFile::names`]
+    /// iterates without decompressing.
+    ///
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (&String, &MatVariable)> + ExactSizeIterator {
+        self.data.iter().map(|(name, entry)| (name, entry.value()))
+    }
+
+    /// Iterator over variables, failing for compressed variables with corrupt data
+    pub(crate) fn try_iter(&self) -> impl Iterator<Item = Result<(&String, &MatVariable), MatrwError>> {
+        self.data
+            .iter()
+            .map(|(name, entry)| entry.try_value().map(|var| (name, var)))
+    }
+
+    /// Owning iterator over variables, failing for compressed variables with corrupt data
+    pub(crate) fn try_into_iter(self) -> impl Iterator<Item = Result<(String, MatVariable), MatrwError>> {
+        self.data
+            .into_iter()
+            .map(|(name, entry)| entry.try_into_value().map(|var| (name, var)))
     }
 
     ///
     /// Return mutable iterator over variables.
     ///
-    pub fn iter_mut(&mut self) -> IterMut<'_, String, MatVariable> {
-        self.data.iter_mut()
+    /// Compressed variables are decompressed and replace their compressed data. Corrupt ones are
+    /// replaced by [`MatVariable::Unsupported`], see [`MatFile::materialize`] to fail instead.
+    ///
+    pub fn iter_mut(
+        &mut self,
+    ) -> impl DoubleEndedIterator<Item = (&String, &mut MatVariable)> + ExactSizeIterator {
+        self.data
+            .iter_mut()
+            .map(|(name, entry)| (name, entry.value_mut()))
+    }
+
+    ///
+    /// Return iterator over the variable names.
+    ///
+    pub fn names(&self) -> impl DoubleEndedIterator<Item = &str> + ExactSizeIterator {
+        self.data.keys().map(String::as_str)
+    }
+
+    /// Name of the variable at `index`, without decompressing it
+    #[cfg(feature = "capi")]
+    pub(crate) fn name_at(&self, index: usize) -> Option<&str> {
+        self.data.get_index(index).map(|(name, _)| name.as_str())
+    }
+
+    ///
+    /// Check variables against `schema`.
+    ///
+    /// Returns a report listing every missing variable and every variable or
+    /// structure field with a class or shape different from the schema.
+    ///
+    pub fn validate(&self, schema: &Schema) -> ValidationReport {
+        schema.validate(self)
+    }
+
+    ///
+    /// Build a new `MatFile` by transforming every variable.
+    ///
+    /// The closure receives name and value of each variable and returns the
+    /// name and value to insert into the new `MatFile`, or `None` to drop the
+    /// variable. Variable order is kept, and so is the metadata attached to the
+    /// variables, see [`MatFile::set_meta`].
+    ///
+    /// Returns [`MatrwError::AccessError`], if a returned name is not a valid
+    /// MATLAB variable name or is returned more than once.
+    ///
+    /// ```
+    /// use matrw::{matfile, matvar};
+    ///
+    /// let matfile = matfile!(a: matvar!(1.0), tmp: matvar!(2.0), b: matvar!("x"));
+    ///
+    /// let renamed = matfile
+    ///     .map_variables(|name, var| match name.as_str() {
+    ///         "tmp" => None,
+    ///         _ => Some((format!("{}_old", name), var)),
+    ///     })
+    ///     .unwrap();
+    ///
+    /// assert!(renamed.contains("a_old"));
+    /// assert!(renamed.contains("b_old"));
+    /// assert!(!renamed.contains("tmp_old"));
+    /// ```
+    ///
+    pub fn map_variables<F>(mut self, mut f: F) -> Result<MatFile, MatrwError>
+    where
+        F: FnMut(String, MatVariable) -> Option<(String, MatVariable)>,
+    {
+        let mut matfile = MatFile::new();
+        let mut variable_meta = std::mem::take(&mut self.variable_meta);
+
+        for (name, var) in self {
+            let meta = variable_meta.shift_remove(&name);
+            let Some((name, var)) = f(name, var) else {
+                continue;
+            };
+            if let Some(meta) = meta {
+                matfile.variable_meta.insert(name.clone(), meta);
+            }
+            if let Some(err) = invalid_variable_name(&name) {
+                return Err(MatrwError::AccessError(err));
+            }
+            if matfile.data.insert(name.clone(), var.into()).is_some() {
+                return Err(MatrwError::AccessError(format!(
+                    "Variable '{}' created more than once",
+                    name
+                )));
+            }
+        }
+
+        Ok(matfile)
+    }
+
+    ///
+    /// Read the variables `names` from the MAT-file at `path` and insert them, replacing
+    /// variables of the same name.
+    ///
+    /// The file is loaded like [`crate::load_matfile_lazy`], so only the requested variables are
+    /// decompressed. Either all requested variables are inserted or none: returns
+    /// [`MatrwError::AccessError`], if a variable does not exist in the file, and the errors of
+    /// [`crate::load_matfile`], if the file or a requested variable can't be read. Names given
+    /// more than once are read once. Subsystem data and warnings of the other file are not taken
+    /// over.
+    ///
+    /// ```
+    /// use matrw::{MatFile, load_matfile};
+    ///
+    /// let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/example_v7.mat");
+    /// let all = load_matfile(path).unwrap();
+    /// let (name, _) = all.iter().next().unwrap();
+    ///
+    /// let mut matfile = MatFile::new();
+    /// matfile.load_more(path, &[name]).unwrap();
+    /// assert!(matfile.contains(name));
+    /// assert!(matfile.load_more(path, &["does_not_exist"]).is_err());
+    /// ```
+    ///
+    pub fn load_more(&mut self, path: &str, names: &[&str]) -> Result<(), MatrwError> {
+        let other = crate::load_matfile_lazy(path)?;
+        self.merge_variables(other, names)
+    }
+
+    ///
+    /// Read the variables `names` from MAT-file data `data` and insert them.
+    ///
+    /// See [`MatFile::load_more`].
+    ///
+    pub fn load_more_from_u8(&mut self, data: &[u8], names: &[&str]) -> Result<(), MatrwError> {
+        let other = crate::load_matfile_lazy_from_u8(data)?;
+        self.merge_variables(other, names)
+    }
+
+    fn merge_variables(&mut self, mut other: MatFile, names: &[&str]) -> Result<(), MatrwError> {
+        let mut vars = Vec::with_capacity(names.len());
+        for &name in names {
+            if vars.iter().any(|(taken, _)| *taken == name) {
+                continue;
+            }
+            let var = match other.data.shift_remove(name) {
+                Some(entry) => entry.try_into_value()?,
+                None => {
+                    return Err(MatrwError::AccessError(format!(
+                        "Variable '{}' does not exist",
+                        name
+                    )));
+                }
+            };
+            vars.push((name, var));
+        }
+
+        for (name, var) in vars {
+            self.variable_info.shift_remove(name);
+            self.data.insert(name.to_string(), var.into());
+        }
+        Ok(())
+    }
+
+    ///
+    /// Sort variables by name and the fields of all structures alphabetically, see
+    /// [`MatVariable::sort_keys`].
+    ///
+    pub fn sort_keys(&mut self) {
+        self.data.sort_keys();
+        self.data
+            .values_mut()
+            .for_each(|entry| entry.value_mut().sort_keys());
+    }
+
+    /// Rename the variables, for which `rename` returns a new name, keeping their order,
+    /// metadata and sizes. Nothing is renamed, if a new name is invalid or taken twice.
+    #[cfg(feature = "regex")]
+    pub(crate) fn rename_variables<F>(&mut self, mut rename: F) -> Result<usize, MatrwError>
+    where
+        F: FnMut(&str) -> Option<String>,
+    {
+        let names: Vec<Option<String>> = self.data.keys().map(|name| rename(name)).collect();
+        let mut taken = indexmap::IndexSet::new();
+        for (old, new) in self.data.keys().zip(names.iter()) {
+            if let Some(err) = new.as_deref().and_then(invalid_variable_name) {
+                return Err(MatrwError::AccessError(err));
+            }
+            let name = new.as_ref().unwrap_or(old);
+            if !taken.insert(name) {
+                return Err(MatrwError::AccessError(format!(
+                    "Variable '{}' created more than once",
+                    name
+                )));
+            }
+        }
+
+        let renamed = names.iter().flatten().count();
+        let data = std::mem::take(&mut self.data);
+        let mut variable_meta = std::mem::take(&mut self.variable_meta);
+        let mut variable_info = std::mem::take(&mut self.variable_info);
+        for ((old, entry), new) in data.into_iter().zip(names) {
+            let name = new.unwrap_or_else(|| old.clone());
+            if let Some(meta) = variable_meta.shift_remove(&old) {
+                self.variable_meta.insert(name.clone(), meta);
+            }
+            if let Some(info) = variable_info.shift_remove(&old) {
+                self.variable_info.insert(name.clone(), info);
+            }
+            self.data.insert(name, entry);
+        }
+        Ok(renamed)
+    }
+
+    ///
+    /// Convert all numeric arrays to `class`, including arrays inside structures and cells.
+    ///
+    /// See [`MatVariable::convert_numeric`], fails for classes which are not numeric.
+    ///
+    /// ```
+    /// use matrw::{VariableClass, MatlabType, matfile, matvar};
+    ///
+    /// let mut matfile = matfile!(a: matvar!([1.0, 2.5]), s: matvar!({ n: 3i32, name: "x" }));
+    /// matfile.convert_numeric(VariableClass::Single).unwrap();
+    ///
+    /// assert_eq!(matfile["a"].to_vec_f32(), Some(vec![1.0, 2.5]));
+    /// assert_eq!(matfile["s"]["n"].to_f32(), Some(3.0));
+    /// assert!(matches!(matfile["s"]["name"].numeric_type(), Some(MatlabType::UTF8(_))));
+    /// ```
+    ///
+    pub fn convert_numeric(&mut self, class: VariableClass) -> Result<(), MatrwError> {
+        for entry in self.data.values_mut() {
+            entry.value_mut().convert_numeric(class)?;
+        }
+        Ok(())
     }
 }
 
 impl IntoIterator for MatFile {
     type Item = (String, MatVariable);
-    type IntoIter = indexmap::map::IntoIter<String, MatVariable>;
+    type IntoIter = IntoIter;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.data.into_iter()
+        IntoIter(self.data.into_iter())
     }
 }
 
+/// Owning iterator over the variables of a [`MatFile`], decompressing compressed variables.
+#[derive(Debug)]
+pub struct IntoIter(indexmap::map::IntoIter<String, Entry>);
+
+impl Iterator for IntoIter {
+    type Item = (String, MatVariable);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(name, entry)| (name, entry.into_value()))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for IntoIter {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(name, entry)| (name, entry.into_value()))
+    }
+}
+
+impl ExactSizeIterator for IntoIter {}
+
 impl Default for MatFile {
     fn default() -> Self {
         Self::new()
@@ -100,18 +835,21 @@ impl Index<&str> for MatFile {
     type Output = MatVariable;
 
     fn index(&self, index: &str) -> &Self::Output {
-        self.data.get(index).unwrap_or(&MatVariable::Null)
+        self.get(index).unwrap_or(&MatVariable::Null)
     }
 }
 
 impl From<MatFile7> for MatFile {
     fn from(value: MatFile7) -> Self {
-        let mut matfile = MatFile {
-            data: IndexMap::new(),
-        };
+        let mut matfile = MatFile::new();
 
-        for (key, value) in value.data.into_iter() {
-            matfile.data.insert(key.clone(), MatVariable::from(value));
+        for (_, value) in value.data.into_iter() {
+            insert_variable(
+                &mut matfile.data,
+                &mut matfile.warnings,
+                &mut matfile.skipped,
+                value,
+            );
         }
 
         matfile
@@ -149,4 +887,151 @@ mod tests {
             m["A"].elem([14999, 14999]).to_f64().unwrap()
         )
     }
+
+    #[test]
+    fn map_variables_errors() {
+        let matfile = crate::matfile!(a: crate::matvar!(1.0), b: crate::matvar!(2.0));
+        let err = matfile
+            .map_variables(|_, var| Some(("x".to_string(), var)))
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Variable 'x' created more than once");
+
+        let matfile = crate::matfile!(a: crate::matvar!(1.0));
+        assert!(
+            matfile
+                .map_variables(|_, var| Some(("1a".to_string(), var)))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn from_arrays_errors() {
+        let arr = || NumericArray::from_row_major(vec![2], vec![1i32, 2]).unwrap();
+        let names = ["a".to_string(), "b".to_string()];
+        let matfile = MatFile::from_arrays(names.iter().map(|name| (name, arr()))).unwrap();
+        assert_eq!(matfile.names().collect::<Vec<_>>(), ["a", "b"]);
+
+        let err = MatFile::from_arrays([("a", arr()), ("a", arr())]).unwrap_err();
+        assert_eq!(err.to_string(), "Variable 'a' created more than once");
+        let err = MatFile::from_arrays([("end", arr())]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid variable name 'end', it is a MATLAB keyword"
+        );
+    }
+
+    #[test]
+    fn read_variable_into_errors() {
+        let matfile = crate::matfile!(x: crate::matvar!([1i16, 2i16]), s: crate::matvar!({}));
+
+        let mut buf = [0i16; 2];
+        matfile.read_variable_into("x", &mut buf).unwrap();
+        assert_eq!(buf, [1, 2]);
+
+        assert!(matches!(
+            matfile.read_variable_into("y", &mut buf),
+            Err(MatrwError::AccessError(_))
+        ));
+        assert!(matches!(
+            matfile.read_variable_into("s", &mut buf),
+            Err(MatrwError::AccessError(_))
+        ));
+        assert!(matches!(
+            matfile.read_variable_into("x", &mut [0.0f64; 2]),
+            Err(MatrwError::TypeConstruction(_))
+        ));
+        assert!(matches!(
+            matfile.read_variable_into("x", &mut [0i16; 3]),
+            Err(MatrwError::TypeConstruction(_))
+        ));
+    }
+
+    #[test]
+    fn variable_order() {
+        use crate::{load_matfile_from_u8, matfile, matvar, save_matfile_v7_to_u8};
+
+        let names = |m: &MatFile| m.iter().map(|(name, _)| name.clone()).collect::<Vec<_>>();
+        let mut matfile = matfile!(c: matvar!(1.0), a: matvar!({ z: 1.0, y: 2.0 }), b: matvar!(2.0));
+
+        matfile.insert("c", matvar!(3.0));
+        matfile.take("a");
+        matfile.insert("a", matvar!({ z: 1.0, y: 2.0 }));
+        assert_eq!(names(&matfile), ["c", "b", "a"]);
+
+        // File order is kept on reading, for variables and fields
+        let data = save_matfile_v7_to_u8(matfile, true).unwrap();
+        let matfile = load_matfile_from_u8(&data).unwrap();
+        assert_eq!(names(&matfile), ["c", "b", "a"]);
+        assert_eq!(
+            matfile["a"].fieldnames(),
+            Some(vec!["z".to_string(), "y".to_string()])
+        );
+    }
+
+    #[test]
+    fn load_more_variables() {
+        use crate::{FromMatVariable, matfile, matvar, save_matfile_v7_to_u8};
+
+        let base = save_matfile_v7_to_u8(matfile!(a: matvar!(1.5), b: matvar!("base")), false).unwrap();
+        let extra =
+            save_matfile_v7_to_u8(matfile!(b: matvar!("extra"), c: matvar!([0.5, 2.5])), true).unwrap();
+
+        let mut matfile = crate::load_matfile_from_u8(&base).unwrap();
+        matfile.load_more_from_u8(&extra, &["c", "b"]).unwrap();
+        assert_eq!(matfile["a"].to_f64(), Some(1.5));
+        assert_eq!(String::from_matvar(&matfile["b"]), Some("extra".to_string()));
+        assert_eq!(matfile["c"].to_vec_f64(), Some(vec![0.5, 2.5]));
+        assert!(matches!(matfile.data["c"], Entry::Loaded(_)));
+
+        // Nothing is inserted, if one of the variables is missing
+        let mut matfile = crate::load_matfile_from_u8(&base).unwrap();
+        let err = matfile.load_more_from_u8(&extra, &["c", "d"]).unwrap_err();
+        assert_eq!(err.to_string(), "Variable 'd' does not exist");
+        assert!(!matfile.contains("c"));
+
+        // Variables can be read again from the same file
+        matfile.load_more_from_u8(&base, &["a"]).unwrap();
+        assert_eq!(matfile["a"].to_f64(), Some(1.5));
+
+        // Names given twice are read once
+        matfile.load_more_from_u8(&extra, &["c", "c"]).unwrap();
+        assert_eq!(matfile["c"].to_vec_f64(), Some(vec![0.5, 2.5]));
+    }
+
+    #[test]
+    fn convert_numeric_nested() {
+        use crate::{CellArray, MatlabType, NumericArray, matvar};
+
+        let complex = NumericArray::new(
+            vec![1, 2],
+            MatlabType::from(vec![1.4, 300.0]),
+            Some(MatlabType::from(vec![-2.6, f64::NAN])),
+        )
+        .unwrap();
+        let mut matfile = crate::matfile!(
+            z: MatVariable::NumericArray(complex),
+            c: MatVariable::CellArray(
+                CellArray::new(vec![1, 3], vec![matvar!(-1i32), matvar!(true), matvar!("a")]).unwrap()
+            ),
+            sp: matvar!([[1.0, 0.0], [0.0, 2.0]]).to_sparse().unwrap(),
+        );
+
+        matfile.convert_numeric(VariableClass::UInt8).unwrap();
+        assert!(matfile.convert_numeric(VariableClass::Cell).is_err());
+
+        let MatVariable::NumericArray(z) = &matfile["z"] else {
+            panic!("Expected numeric array");
+        };
+        assert_eq!(z.value, MatlabType::U8(vec![1, 255]));
+        assert_eq!(z.value_cmp, Some(MatlabType::U8(vec![0, 0])));
+        assert_eq!(matfile["c"][0].numeric_type(), Some(&MatlabType::U8(vec![0])));
+        assert_eq!(
+            matfile["c"][1].numeric_type(),
+            Some(&MatlabType::BOOL(vec![true]))
+        );
+        assert_eq!(matfile["c"][2].numeric_type(), Some(&MatlabType::UTF8(vec!['a'])));
+        assert!(
+            matches!(&matfile["sp"], MatVariable::SparseArray(sp) if matches!(sp.value, MatlabType::F64(_)))
+        );
+    }
 }
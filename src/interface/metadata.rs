@@ -0,0 +1,572 @@
+//! Provenance metadata of MAT-files.
+//!
+//! [`Metadata`] is stored as a scalar structure in the variable [`METADATA_VARIABLE`]. The name
+//! starts with underscores, so it can't clash with user variables and MATLAB does not load it into
+//! the workspace.
+//!
+//! [`HeaderInfo`] holds the descriptive text of the file header, which names platform and
+//! creation time and hints at the tool that wrote the file.
+
+#[cfg(feature = "chrono")]
+use chrono::NaiveDateTime;
+use indexmap::IndexMap;
+
+use crate::interface::error::MatrwError;
+use crate::interface::helper::{invalid_variable_name, utc_rfc3339};
+use crate::interface::matfile::MatFile;
+use crate::interface::types::structure::Structure;
+use crate::interface::variable::{FromMatVariable, MatVariable};
+
+/// Name of the variable holding the [`Metadata`] of a MAT-file
+pub const METADATA_VARIABLE: &str = "__matrw_meta__";
+
+/// Name of the variable holding the metadata attached to variables, see [`MatFile::set_meta`]
+pub const VARIABLE_META_VARIABLE: &str = "__matrw_varmeta__";
+
+/// Provenance of a MAT-file: the tool that wrote it, when and from which revision.
+///
+/// Example
+/// ```
+/// use matrw::{MatFile, Metadata};
+///
+/// let mut matfile = MatFile::new();
+/// matfile.stamp_metadata(Metadata::new("pipeline", "2.1.0").git_hash("3f2a9c1"));
+///
+/// let meta = matfile.metadata().unwrap();
+/// assert_eq!(meta.tool, "pipeline");
+/// assert_eq!(meta.git_hash.as_deref(), Some("3f2a9c1"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Metadata {
+    /// Name of the writing tool
+    pub tool: String,
+    /// Version of the writing tool
+    pub version: String,
+    /// Creation time in RFC 3339 format, in UTC
+    pub created: String,
+    /// Revision the writing tool was built from
+    pub git_hash: Option<String>,
+}
+
+impl Metadata {
+    ///
+    /// Metadata of `tool` in `version`, created now.
+    ///
+    pub fn new(tool: &str, version: &str) -> Self {
+        Self {
+            tool: tool.to_string(),
+            version: version.to_string(),
+            created: utc_rfc3339(),
+            git_hash: None,
+        }
+    }
+
+    ///
+    /// Set the revision the tool was built from.
+    ///
+    pub fn git_hash(mut self, hash: &str) -> Self {
+        self.git_hash = Some(hash.to_string());
+        self
+    }
+
+    pub(crate) fn to_matvar(&self) -> MatVariable {
+        let mut fields = IndexMap::new();
+        fields.insert("tool".to_string(), MatVariable::from(self.tool.as_str()));
+        fields.insert("version".to_string(), MatVariable::from(self.version.as_str()));
+        fields.insert("created".to_string(), MatVariable::from(self.created.as_str()));
+        fields.insert(
+            "git_hash".to_string(),
+            MatVariable::from(self.git_hash.as_deref().unwrap_or("")),
+        );
+        MatVariable::Structure(Structure::new(fields))
+    }
+
+    fn from_matvar(var: &MatVariable) -> Option<Self> {
+        let field = |name: &str| var.get(name).and_then(String::from_matvar);
+
+        Some(Self {
+            tool: field("tool")?,
+            version: field("version")?,
+            created: field("created")?,
+            git_hash: field("git_hash").filter(|hash| !hash.is_empty()),
+        })
+    }
+}
+
+/// Metadata of matrw itself.
+impl Default for Metadata {
+    fn default() -> Self {
+        Self::new("matrw", env!("CARGO_PKG_VERSION"))
+    }
+}
+
+/// Descriptive text of the header of a MAT-file, see [`MatFile::header`].
+///
+/// Example
+/// ```
+/// use matrw::{LikelyWriter, load_matfile};
+///
+/// let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/example_v7.mat");
+/// let matfile = load_matfile(path).unwrap();
+///
+/// let header = matfile.header().unwrap();
+/// assert_eq!(header.platform.as_deref(), Some("GLNXA64"));
+/// # #[cfg(feature = "chrono")]
+/// assert!(header.created_at.is_some());
+/// assert_eq!(header.likely_writer(), LikelyWriter::Matlab { release: None });
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct HeaderInfo {
+    /// Complete text without trailing padding
+    pub text: String,
+    /// Platform the file was created on, e.g. `GLNXA64`, `PCWIN64` or `posix`
+    pub platform: Option<String>,
+    /// Creation time as written, usually local time. Requires the feature `chrono`.
+    #[cfg(feature = "chrono")]
+    pub created_at: Option<NaiveDateTime>,
+    /// Text besides version, platform and creation time
+    pub free_text: String,
+}
+
+/// Tool that most likely wrote a MAT-file, see [`HeaderInfo::likely_writer`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LikelyWriter {
+    /// MATLAB, with the release like `R2023b`, if the text names it. MATLAB itself doesn't.
+    Matlab { release: Option<String> },
+    /// `scipy.io.savemat`
+    Scipy,
+    /// GNU Octave
+    Octave,
+    /// matrw
+    Matrw,
+    /// The text doesn't match any known tool
+    Unknown,
+}
+
+/// Formats of the creation time written by MATLAB and SciPy, and by Octave
+#[cfg(feature = "chrono")]
+const TIME_FORMATS: [&str; 2] = ["%a %b %e %H:%M:%S %Y", "%Y-%m-%d %H:%M:%S UTC"];
+
+/// Shapes of [`TIME_FORMATS`]: `a` is a letter, `9` a digit and `_` a digit or space
+const TIME_SHAPES: [&str; 2] = ["aaa aaa _9 99:99:99 9999", "9999-99-99 99:99:99 UTC"];
+
+/// Creation time at the start of `part` and the rest of `part`, if `part` starts with one of
+/// [`TIME_SHAPES`]
+fn split_time(part: &str) -> Option<(&str, &str)> {
+    let matches = |shape: &str| {
+        part.len() >= shape.len()
+            && part.bytes().zip(shape.bytes()).all(|(c, s)| match s {
+                b'a' => c.is_ascii_alphabetic(),
+                b'9' => c.is_ascii_digit(),
+                b'_' => c == b' ' || c.is_ascii_digit(),
+                s => c == s,
+            })
+    };
+    TIME_SHAPES
+        .iter()
+        .find(|shape| matches(shape))
+        .map(|shape| part.split_at(shape.len()))
+}
+
+impl HeaderInfo {
+    pub(crate) fn parse(text: &[u8]) -> Self {
+        let text = String::from_utf8_lossy(text)
+            .trim_end_matches([' ', '\0'])
+            .to_string();
+
+        let rest = ["MATLAB 5.0 MAT-file", "MATLAB 7.3 MAT-file"]
+            .iter()
+            .find_map(|version| text.strip_prefix(version))
+            .unwrap_or(&text)
+            .trim_start_matches([',', ' ']);
+
+        let mut platform = None;
+        #[cfg(feature = "chrono")]
+        let mut created_at = None;
+        let mut has_time = false;
+        let mut free = Vec::new();
+        for part in rest.split(", ").map(str::trim) {
+            if let Some(name) = part.strip_prefix("Platform:") {
+                platform = Some(name.trim().to_string());
+                continue;
+            }
+            let time = part.strip_prefix("Created on:").unwrap_or(part).trim();
+            if !has_time && let Some((time, remainder)) = split_time(time) {
+                has_time = true;
+                #[cfg(feature = "chrono")]
+                {
+                    created_at = TIME_FORMATS
+                        .iter()
+                        .find_map(|format| NaiveDateTime::parse_from_str(time, format).ok());
+                }
+                #[cfg(not(feature = "chrono"))]
+                let _ = time;
+                free.push(remainder.trim());
+                continue;
+            }
+            free.push(part);
+        }
+        free.retain(|part| !part.is_empty());
+
+        Self {
+            platform,
+            #[cfg(feature = "chrono")]
+            created_at,
+            free_text: free.join(", "),
+            text,
+        }
+    }
+
+    ///
+    /// Guess the tool that wrote the file from the header text.
+    ///
+    /// Octave and matrw name themselves, SciPy writes the platform as `posix` or `nt` and omits
+    /// the comma after `MAT-file`. Other files with a platform are taken as written by MATLAB.
+    ///
+    pub fn likely_writer(&self) -> LikelyWriter {
+        let free_text = self.free_text.to_lowercase();
+        if free_text.contains("octave") {
+            LikelyWriter::Octave
+        } else if free_text.contains("matrw") {
+            LikelyWriter::Matrw
+        } else if matches!(self.platform.as_deref(), Some("posix" | "nt")) || !self.text.contains("MAT-file,")
+        {
+            LikelyWriter::Scipy
+        } else if self.platform.is_some() {
+            let release = self
+                .text
+                .split(|c: char| !c.is_ascii_alphanumeric())
+                .find(|word| {
+                    let bytes = word.as_bytes();
+                    bytes.len() == 6
+                        && bytes[0] == b'R'
+                        && bytes[1..5].iter().all(u8::is_ascii_digit)
+                        && matches!(bytes[5], b'a' | b'b')
+                })
+                .map(str::to_string);
+            LikelyWriter::Matlab { release }
+        } else {
+            LikelyWriter::Unknown
+        }
+    }
+}
+
+impl MatFile {
+    ///
+    /// Store `metadata` in the variable [`METADATA_VARIABLE`], replacing existing metadata.
+    ///
+    pub fn stamp_metadata(&mut self, metadata: Metadata) {
+        self.insert_unchecked(METADATA_VARIABLE, metadata.to_matvar());
+    }
+
+    ///
+    /// Read the metadata, if the file contains a well-formed [`METADATA_VARIABLE`].
+    ///
+    pub fn metadata(&self) -> Option<Metadata> {
+        self.get(METADATA_VARIABLE).and_then(Metadata::from_matvar)
+    }
+
+    ///
+    /// Remove and return the metadata variable.
+    ///
+    pub fn remove_metadata(&mut self) -> Option<MatVariable> {
+        self.take(METADATA_VARIABLE)
+    }
+
+    ///
+    /// Attach `value` under `key` to the variable `name`, replacing a value of the same key.
+    ///
+    /// Attached metadata carries provenance or flags along with a variable between processing
+    /// stages. It is dropped with the variable by [`MatFile::take`] and follows renames by
+    /// [`MatFile::map_variables`]. It is only written with [`crate::SaveOptions::write_variable_meta`],
+    /// as the structure [`VARIABLE_META_VARIABLE`] with a field per variable, and attached again
+    /// when such a file is loaded. Loaded variables of that name, which don't have this structure,
+    /// are kept as they are.
+    ///
+    /// Returns [`MatrwError::AccessError`], if there is no variable `name` or `key` is not a valid
+    /// MATLAB field name.
+    ///
+    /// Example
+    /// ```
+    /// use matrw::{matfile, matvar};
+    ///
+    /// let mut matfile = matfile!(signal: matvar!([0.5, 1.5]));
+    /// matfile.set_meta("signal", "source", "sensor_3").unwrap();
+    /// matfile.set_meta("signal", "filtered", true).unwrap();
+    ///
+    /// assert_eq!(matfile.meta("signal", "source"), Some(&matvar!("sensor_3")));
+    /// assert_eq!(matfile.meta_of("signal").unwrap().len(), 2);
+    /// assert!(matfile.set_meta("other", "source", "x").is_err());
+    /// ```
+    ///
+    pub fn set_meta(
+        &mut self,
+        name: &str,
+        key: &str,
+        value: impl Into<MatVariable>,
+    ) -> Result<(), MatrwError> {
+        if !self.contains(name) {
+            return Err(MatrwError::AccessError(format!(
+                "Variable '{}' does not exist",
+                name
+            )));
+        }
+        if let Some(err) = invalid_variable_name(key) {
+            return Err(MatrwError::AccessError(err.replacen(
+                "variable",
+                "metadata key",
+                1,
+            )));
+        }
+        self.variable_meta
+            .entry(name.to_string())
+            .or_default()
+            .insert(key.to_string(), value.into());
+        Ok(())
+    }
+
+    ///
+    /// Metadata attached to the variable `name` under `key`, see [`MatFile::set_meta`].
+    ///
+    pub fn meta(&self, name: &str, key: &str) -> Option<&MatVariable> {
+        self.variable_meta.get(name)?.get(key)
+    }
+
+    ///
+    /// All metadata attached to the variable `name`, in the order it was attached.
+    ///
+    pub fn meta_of(&self, name: &str) -> Option<&IndexMap<String, MatVariable>> {
+        self.variable_meta.get(name)
+    }
+
+    ///
+    /// Remove and return the metadata attached to the variable `name` under `key`.
+    ///
+    pub fn remove_meta(&mut self, name: &str, key: &str) -> Option<MatVariable> {
+        let meta = self.variable_meta.get_mut(name)?;
+        let value = meta.shift_remove(key);
+        if meta.is_empty() {
+            self.variable_meta.shift_remove(name);
+        }
+        value
+    }
+
+    /// Store the attached metadata in the variable [`VARIABLE_META_VARIABLE`], which fails with
+    /// [`MatrwError::AccessError`], if a variable of that name exists already.
+    pub(crate) fn stamp_variable_meta(&mut self) -> Result<(), MatrwError> {
+        if self.variable_meta.is_empty() {
+            return Ok(());
+        }
+        if self.contains(VARIABLE_META_VARIABLE) {
+            return Err(MatrwError::AccessError(format!(
+                "Variable '{}' is reserved for the metadata attached to variables",
+                VARIABLE_META_VARIABLE
+            )));
+        }
+        let fields = self
+            .variable_meta
+            .iter()
+            .map(|(name, meta)| (name.clone(), MatVariable::Structure(Structure::new(meta.clone()))))
+            .collect();
+        self.insert_unchecked(
+            VARIABLE_META_VARIABLE,
+            MatVariable::Structure(Structure::new(fields)),
+        );
+        Ok(())
+    }
+
+    /// Attach the metadata of [`VARIABLE_META_VARIABLE`] to the variables and remove it, if it is
+    /// a structure as written by [`MatFile::stamp_variable_meta`]: with at least one field, each a
+    /// structure named like another variable. Otherwise it is a variable of the user.
+    pub(crate) fn restore_variable_meta(&mut self) {
+        match self.get(VARIABLE_META_VARIABLE) {
+            Some(MatVariable::Structure(s))
+                if !s.value.is_empty()
+                    && s.value.iter().all(|(name, meta)| {
+                        matches!(meta, MatVariable::Structure(_))
+                            && name != VARIABLE_META_VARIABLE
+                            && self.contains(name)
+                    }) => {}
+            _ => return,
+        }
+        let Some(MatVariable::Structure(s)) = self.take(VARIABLE_META_VARIABLE) else {
+            return;
+        };
+        for (name, meta) in s.value {
+            if let MatVariable::Structure(meta) = meta {
+                self.variable_meta.insert(name, meta.value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SaveOptions, load_matfile_from_u8, matfile, matvar, save_matfile_v7_to_u8_with_options};
+
+    #[test]
+    fn header_info() {
+        let header = |text: &str| HeaderInfo::parse(format!("{text:116}").as_bytes());
+
+        let matlab = header("MATLAB 5.0 MAT-file, Platform: PCWIN64, Created on: Thu Apr 11 10:23:45 2019");
+        assert_eq!(matlab.platform.as_deref(), Some("PCWIN64"));
+        #[cfg(feature = "chrono")]
+        assert_eq!(
+            matlab.created_at.map(|t| t.to_string()).as_deref(),
+            Some("2019-04-11 10:23:45")
+        );
+        assert_eq!(matlab.free_text, "");
+        assert_eq!(matlab.likely_writer(), LikelyWriter::Matlab { release: None });
+
+        let matlab = header(
+            "MATLAB 7.3 MAT-file, Platform: GLNXA64, Created on: Sun May 19 14:40:48 2024 HDF5 schema 1.00 .",
+        );
+        assert_eq!(matlab.free_text, "HDF5 schema 1.00 .");
+        #[cfg(feature = "chrono")]
+        assert!(matlab.created_at.is_some());
+
+        let matlab =
+            header("MATLAB 5.0 MAT-file, Platform: MACA64, Created on: Sat Sep  6 00:46:14 2025, R2024b");
+        assert_eq!(
+            matlab.likely_writer(),
+            LikelyWriter::Matlab {
+                release: Some("R2024b".to_string())
+            }
+        );
+        #[cfg(feature = "chrono")]
+        assert_eq!(
+            matlab.created_at.map(|t| t.to_string()).as_deref(),
+            Some("2025-09-06 00:46:14")
+        );
+
+        let scipy = header("MATLAB 5.0 MAT-file Platform: posix, Created on: Mon Jan  1 12:00:00 2024");
+        assert_eq!(scipy.platform.as_deref(), Some("posix"));
+        #[cfg(feature = "chrono")]
+        assert!(scipy.created_at.is_some());
+        assert_eq!(scipy.likely_writer(), LikelyWriter::Scipy);
+
+        let octave = header("MATLAB 5.0 MAT-file, written by Octave 7.1.0, 2022-05-11 09:51:43 UTC");
+        assert_eq!(octave.platform, None);
+        assert_eq!(octave.free_text, "written by Octave 7.1.0");
+        #[cfg(feature = "chrono")]
+        assert!(octave.created_at.is_some());
+        assert_eq!(octave.likely_writer(), LikelyWriter::Octave);
+
+        assert_eq!(
+            header("MATLAB 5.0 MAT-file, something else").likely_writer(),
+            LikelyWriter::Unknown
+        );
+        assert_eq!(
+            header("MATLAB 5.0 MAT-file, something else").free_text,
+            "something else"
+        );
+
+        let data = crate::save_matfile_v7_to_u8(matfile!(a: matvar!(1.0)), false).unwrap();
+        let matfile = load_matfile_from_u8(&data).unwrap();
+        let header = matfile.header().unwrap();
+        assert_eq!(header.likely_writer(), LikelyWriter::Matrw);
+        #[cfg(feature = "chrono")]
+        {
+            // Written in UTC independent of the feature
+            let age = chrono::Utc::now().naive_utc() - header.created_at.unwrap();
+            assert!(header.platform.is_some() && age.num_minutes().abs() <= 1);
+        }
+        assert_eq!(
+            header.free_text,
+            format!("written by matrw {}", env!("CARGO_PKG_VERSION"))
+        );
+        assert_eq!(MatFile::new().header(), None);
+    }
+
+    #[test]
+    fn metadata_round_trip() {
+        let meta = Metadata::default();
+        assert_eq!(meta.tool, "matrw");
+        assert!(meta.created.ends_with("+00:00"));
+
+        let mut matfile = matfile!(a: matvar!(1.0));
+        matfile.stamp_metadata(meta.clone());
+
+        let data = save_matfile_v7_to_u8_with_options(matfile, &SaveOptions::new().compress(true)).unwrap();
+        let mut matfile = load_matfile_from_u8(&data).unwrap();
+        assert_eq!(matfile.metadata(), Some(meta));
+
+        assert!(matfile.remove_metadata().is_some());
+        assert_eq!(matfile.metadata(), None);
+        assert_eq!(matfile.iter().count(), 1);
+    }
+
+    #[test]
+    fn stamp_on_save() {
+        let meta = Metadata::new("tool", "1.0").git_hash("abc");
+        let options = SaveOptions::new().metadata(meta.clone());
+
+        let data = save_matfile_v7_to_u8_with_options(matfile!(a: matvar!(1.0)), &options).unwrap();
+        let matfile = load_matfile_from_u8(&data).unwrap();
+        assert_eq!(matfile.metadata(), Some(meta));
+
+        // Opt out of passing on metadata of a loaded file
+        let options = SaveOptions::new().strip_metadata(true);
+        let data = save_matfile_v7_to_u8_with_options(matfile, &options).unwrap();
+        let matfile = load_matfile_from_u8(&data).unwrap();
+        assert_eq!(matfile.metadata(), None);
+        assert!(matfile.contains("a"));
+    }
+
+    #[test]
+    fn variable_meta() {
+        let mut matfile = matfile!(a: matvar!(1.0), b: matvar!("text"));
+        matfile.set_meta("a", "source", "sensor").unwrap();
+        matfile.set_meta("a", "gain", 2.0).unwrap();
+        matfile.set_meta("b", "source", "manual").unwrap();
+        assert!(matfile.set_meta("a", "not valid", 1.0).is_err());
+        assert_eq!(matfile.remove_meta("a", "gain"), Some(matvar!(2.0)));
+
+        // Attachments are not written by default
+        let data = save_matfile_v7_to_u8_with_options(matfile, &SaveOptions::new()).unwrap();
+        let mut matfile = load_matfile_from_u8(&data).unwrap();
+        assert!(matfile.meta_of("a").is_none());
+        assert!(!matfile.contains(VARIABLE_META_VARIABLE));
+
+        matfile.set_meta("a", "source", "sensor").unwrap();
+        matfile.set_meta("b", "source", "manual").unwrap();
+        let matfile = matfile
+            .map_variables(|name, var| (name != "b").then(|| (format!("{name}_raw"), var)))
+            .unwrap();
+        assert_eq!(matfile.meta("a_raw", "source"), Some(&matvar!("sensor")));
+        assert!(matfile.meta_of("b").is_none());
+
+        let options = SaveOptions::new().write_variable_meta(true);
+        let data = save_matfile_v7_to_u8_with_options(matfile, &options).unwrap();
+        let mut loaded = load_matfile_from_u8(&data).unwrap();
+        assert!(!loaded.contains(VARIABLE_META_VARIABLE));
+        assert_eq!(loaded.meta("a_raw", "source"), Some(&matvar!("sensor")));
+
+        loaded.take("a_raw");
+        assert!(loaded.meta_of("a_raw").is_none());
+
+        // Variables of the reserved name, which another tool wrote
+        let user = || {
+            let mut matfile = matfile!(a: matvar!(1.0));
+            matfile.insert_unchecked(
+                VARIABLE_META_VARIABLE,
+                matvar!({ a: matvar!({ unit: "V" }), b: matvar!({ unit: "A" }) }),
+            );
+            matfile
+        };
+        let data = save_matfile_v7_to_u8_with_options(user(), &options).unwrap();
+        let loaded = load_matfile_from_u8(&data).unwrap();
+        assert!(loaded.contains(VARIABLE_META_VARIABLE));
+        assert!(loaded.meta_of("a").is_none());
+
+        let mut matfile = user();
+        matfile.set_meta("a", "source", "sensor").unwrap();
+        let err = save_matfile_v7_to_u8_with_options(matfile, &options).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Variable '__matrw_varmeta__' is reserved for the metadata attached to variables"
+        );
+    }
+}
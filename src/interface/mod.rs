@@ -1,11 +1,37 @@
 //! User interface
 
+pub mod bench_support;
+pub mod capabilities;
+pub mod codegen;
+pub mod compat;
 pub mod error;
+pub mod events;
 pub mod fileio;
+pub mod fixtures;
+pub mod hash;
 pub mod helper;
 pub mod index;
 pub mod macros;
 pub mod matfile;
+pub mod metadata;
+#[cfg(feature = "regex")]
+pub mod rename;
+pub mod roundtrip;
+pub mod schema;
+#[cfg(feature = "serde")]
 pub mod serde;
+pub mod skip_report;
+pub mod stats;
+pub mod testing;
+pub mod timeseries;
+pub mod transcode;
 pub mod types;
+pub mod units;
 pub mod variable;
+pub mod visit;
+pub mod watch;
+pub mod writer;
+#[cfg(feature = "xlsx")]
+pub mod xlsx;
+#[cfg(feature = "zip")]
+pub mod zip;
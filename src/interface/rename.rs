@@ -0,0 +1,255 @@
+//! Renaming variables and structure fields by regular expressions.
+//!
+//! Files of some instruments prefix every variable with a serial number, e.g. `SN1234_voltage`.
+//! [`MatFile::rename_matching`] maps such names with a [`Regex`] and a replacement, which may
+//! refer to capture groups like [`Regex::replace`]. [`MatFile::rename_matching_recursive`] also
+//! renames the fields of all structures.
+
+use std::borrow::Cow;
+
+use regex::Regex;
+
+use crate::interface::error::MatrwError;
+use crate::interface::helper::invalid_variable_name;
+use crate::interface::matfile::MatFile;
+use crate::interface::types::structure_array::StructureArray;
+use crate::interface::variable::MatVariable;
+use crate::interface::visit::PathElem;
+
+/// New name of `name`, `None` if the first match of `regex` is replaced by itself or there is no
+/// match
+fn renamed(regex: &Regex, replacement: &str, name: &str) -> Option<String> {
+    match regex.replace(name, replacement) {
+        Cow::Owned(new) if new != name => Some(new),
+        _ => None,
+    }
+}
+
+/// Fail, if renaming the fields `names` of the structure at `path` gives an invalid or duplicate
+/// field name
+fn check_fields(path: &str, names: &[String], regex: &Regex, replacement: &str) -> Result<(), MatrwError> {
+    let mut taken = indexmap::IndexSet::new();
+    for name in names {
+        let new = renamed(regex, replacement, name);
+        if let Some(err) = new.as_deref().and_then(invalid_variable_name) {
+            return Err(MatrwError::AccessError(format!(
+                "{} in '{}'",
+                err.replacen("variable", "field", 1),
+                path
+            )));
+        }
+        let name = new.unwrap_or_else(|| name.clone());
+        if !taken.insert(name.clone()) {
+            return Err(MatrwError::AccessError(format!(
+                "Field '{}' created more than once in '{}'",
+                name, path
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Rename the fields of `var`, if it is a structure or structure array, returns the number of
+/// renamed fields
+fn rename_fields(var: &mut MatVariable, regex: &Regex, replacement: &str) -> usize {
+    let Some(names) = var.fieldnames() else {
+        return 0;
+    };
+    let renamed_count = names
+        .iter()
+        .filter(|name| renamed(regex, replacement, name).is_some())
+        .count();
+    if renamed_count == 0 {
+        return 0;
+    }
+
+    let rename = |name: String, var| Some((renamed(regex, replacement, &name).unwrap_or(name), var));
+    *var = match std::mem::replace(var, MatVariable::Null) {
+        MatVariable::Structure(s) => {
+            MatVariable::Structure(s.map_fields(rename).expect("Field names are checked"))
+        }
+        // Elements carry the field names, an array without elements only its field names
+        MatVariable::StructureArray(s) if s.value.is_empty() => {
+            let names = names
+                .into_iter()
+                .map(|name| renamed(regex, replacement, &name).unwrap_or(name))
+                .collect();
+            MatVariable::StructureArray(
+                StructureArray::new(s.dim.into_vec(), names, Vec::new()).expect("Dimension has no elements"),
+            )
+        }
+        MatVariable::StructureArray(s) => {
+            MatVariable::StructureArray(s.map_fields(rename).expect("Field names are checked"))
+        }
+        other => other,
+    };
+    renamed_count
+}
+
+impl MatFile {
+    ///
+    /// Rename the variables matching `regex`, replacing the first match by `replacement`, see
+    /// [`Regex::replace`] for references to capture groups like `$1`.
+    ///
+    /// Returns the number of renamed variables. Variables keep their order. Returns
+    /// [`MatrwError::AccessError`] without renaming anything, if a new name is no valid MATLAB
+    /// variable name or is taken by two variables.
+    ///
+    /// Example
+    /// ```
+    /// use matrw::{Regex, matfile, matvar};
+    ///
+    /// let mut matfile = matfile!(
+    ///     SN1234_voltage: matvar!([1.0, 2.0]),
+    ///     SN1234_current: matvar!([0.1, 0.2]),
+    ///     comment: matvar!("bench 3"),
+    /// );
+    ///
+    /// let regex = Regex::new(r"^SN\d+_").unwrap();
+    /// assert_eq!(matfile.rename_matching(&regex, "").unwrap(), 2);
+    /// assert_eq!(matfile.names().collect::<Vec<_>>(), ["voltage", "current", "comment"]);
+    ///
+    /// // Would give two variables `x`
+    /// let mut matfile = matfile!(x_1: matvar!(1.0), x_2: matvar!(2.0));
+    /// assert!(matfile.rename_matching(&Regex::new(r"_\d$").unwrap(), "").is_err());
+    /// assert!(matfile.contains("x_1"));
+    /// ```
+    ///
+    pub fn rename_matching(&mut self, regex: &Regex, replacement: &str) -> Result<usize, MatrwError> {
+        self.rename_variables(|name| renamed(regex, replacement, name))
+    }
+
+    ///
+    /// Rename the variables and the fields of all structures matching `regex`, including
+    /// structures nested in structures and cells, see [`MatFile::rename_matching`].
+    ///
+    /// Returns the number of renamed variables and fields, a field of a structure array counts
+    /// once. Returns [`MatrwError::AccessError`] without renaming anything, if a new name is
+    /// invalid or taken twice within a file or structure.
+    ///
+    /// Example
+    /// ```
+    /// use matrw::{Regex, matfile, matvar};
+    ///
+    /// let mut matfile = matfile!(
+    ///     dev_config: matvar!({ dev_gain: 2.0, offset: 0.0 }),
+    ///     runs: matvar!([{ dev_t: 1.0 }, { dev_t: 2.0 }]),
+    /// );
+    ///
+    /// let renamed = matfile.rename_matching_recursive(&Regex::new("^dev_").unwrap(), "").unwrap();
+    /// assert_eq!(renamed, 3);
+    /// assert_eq!(matfile["config"]["gain"].to_f64(), Some(2.0));
+    /// assert_eq!(matfile["runs"][1]["t"].to_f64(), Some(2.0));
+    /// ```
+    ///
+    pub fn rename_matching_recursive(
+        &mut self,
+        regex: &Regex,
+        replacement: &str,
+    ) -> Result<usize, MatrwError> {
+        let mut result = Ok(());
+        self.walk(|path, var| {
+            if let (Ok(()), Some(names)) = (&result, var.fieldnames()) {
+                result = check_fields(&path.to_string(), &names, regex, replacement);
+            }
+        });
+        result?;
+
+        // Elements of structure arrays are renamed with their array
+        let mut renamed = self.rename_matching(regex, replacement)?;
+        self.walk_mut(|path, var| {
+            if !matches!(path.elems().last(), Some(PathElem::Element(_))) {
+                renamed += rename_fields(var, regex, replacement);
+            }
+        });
+        Ok(renamed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MatVariable, load_matfile_from_u8, matfile, matvar, save_matfile_v7_to_u8};
+
+    #[test]
+    fn rename_matching() {
+        let regex = Regex::new(r"^SN(\d+)_(\w+)$").unwrap();
+        let mut matfile = matfile!(
+            SN7_a: matvar!(1.0),
+            b: matvar!(2.0),
+            SN8_c: matvar!({ SN9_d: 3.0 }),
+        );
+        matfile.set_meta("SN7_a", "unit", "V").unwrap();
+        assert_eq!(matfile.rename_matching(&regex, "${2}_sn$1").unwrap(), 2);
+        assert_eq!(matfile.names().collect::<Vec<_>>(), ["a_sn7", "b", "c_sn8"]);
+        assert_eq!(matfile.meta("a_sn7", "unit"), Some(&matvar!("V")));
+        // Fields are only renamed recursively
+        assert_eq!(matfile["c_sn8"].fieldnames().unwrap(), vec!["SN9_d"]);
+
+        let err = matfile
+            .rename_matching(&Regex::new("^a_sn7$").unwrap(), "1x")
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid variable name '1x', it does not start with a letter"
+        );
+        let err = matfile
+            .rename_matching(&Regex::new("^b$").unwrap(), "a_sn7")
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Variable 'a_sn7' created more than once");
+        assert_eq!(matfile.names().collect::<Vec<_>>(), ["a_sn7", "b", "c_sn8"]);
+    }
+
+    #[test]
+    fn rename_matching_recursive() {
+        let regex = Regex::new("^raw").unwrap();
+        let MatVariable::StructureArray(empty) = matvar!([{ rawX: 1.0 }]) else {
+            unreachable!()
+        };
+        let empty = StructureArray::new(vec![0, 0], empty.fieldnames(), vec![]).unwrap();
+        let mut matfile = matfile!(
+            rawS: matvar!({ rawA: 1.0, cells: [{ rawB: 2.0 }, "rawC"] }),
+            e: MatVariable::StructureArray(empty),
+        );
+        assert_eq!(matfile.rename_matching_recursive(&regex, "s").unwrap(), 4);
+
+        let data = save_matfile_v7_to_u8(matfile, false).unwrap();
+        let matfile = load_matfile_from_u8(&data).unwrap();
+        assert_eq!(matfile["sS"].fieldnames().unwrap(), vec!["sA", "cells"]);
+        assert_eq!(matfile["sS"]["cells"][0]["sB"].to_f64(), Some(2.0));
+        assert_eq!(
+            matfile["sS"]["cells"][1].to_matlab_string().as_deref(),
+            Some("rawC")
+        );
+        assert_eq!(matfile["e"].fieldnames().unwrap(), vec!["sX"]);
+
+        // Replacements matching again rename the fields of structure arrays once
+        let mut matfile = matfile!(runs: matvar!([{ f: 1.0 }, { f: 2.0 }]));
+        assert_eq!(
+            matfile
+                .rename_matching_recursive(&Regex::new("^f").unwrap(), "ff")
+                .unwrap(),
+            1
+        );
+        assert_eq!(matfile["runs"].fieldnames().unwrap(), vec!["ff"]);
+        let data = save_matfile_v7_to_u8(matfile, false).unwrap();
+        assert_eq!(
+            load_matfile_from_u8(&data).unwrap()["runs"][1]["ff"].to_f64(),
+            Some(2.0)
+        );
+
+        let mut matfile = matfile!(s: matvar!({ a: { x1: 1.0, x2: 2.0 } }));
+        let err = matfile
+            .rename_matching_recursive(&Regex::new(r"\d$").unwrap(), "")
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Field 'x' created more than once in 's.a'");
+        let err = matfile
+            .rename_matching_recursive(&Regex::new("^x").unwrap(), "_")
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid field name '_1', it does not start with a letter in 's.a'"
+        );
+        assert_eq!(matfile["s"]["a"].fieldnames().unwrap(), vec!["x1", "x2"]);
+    }
+}
@@ -0,0 +1,289 @@
+//! Round-trip checks of MAT-files.
+//!
+//! [`roundtrip_check`] loads a MAT-file, writes it back into memory as faithfully as matrw can and
+//! compares both the variables read back and the bytes written with the original. This shows,
+//! whether matrw can rewrite a file without changing it, and serves as a regression check of
+//! reader and writer.
+
+use std::fmt;
+use std::fs;
+
+use crate::interface::error::MatrwError;
+use crate::interface::fileio::{
+    SaveOptions, load_matfile_from_u8, load_matfile_lazy_from_u8, save_matfile_v7_to_u8_with_options,
+};
+use crate::interface::matfile::MatFile;
+use crate::interface::variable::MatVariable;
+use crate::parser::header::HEADER_SUBSYSTEM_DATA_OFFSET_POS;
+
+/// Single difference between a MAT-file and the file written back by matrw
+///
+/// Variables are identified by their path, e.g. `s.field` for a structure field or `c{2}` for
+/// the second element of a cell array.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RoundTripDifference {
+    /// Variable of the file, which is not read back
+    Missing { path: String },
+    /// Variable, which is read back but is not part of the file
+    Added { path: String },
+    /// Variable with a different class, size, field names or values
+    Changed { path: String },
+    /// Variables are read back in a different order
+    Order,
+}
+
+impl fmt::Display for RoundTripDifference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing { path } => write!(f, "'{}' is missing", path),
+            Self::Added { path } => write!(f, "'{}' is added", path),
+            Self::Changed { path } => write!(f, "'{}' is changed", path),
+            Self::Order => write!(f, "Variables are reordered"),
+        }
+    }
+}
+
+/// Result of [`roundtrip_check`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RoundTripReport {
+    differences: Vec<RoundTripDifference>,
+    original_size: usize,
+    written_size: usize,
+    first_byte_difference: Option<usize>,
+}
+
+impl RoundTripReport {
+    /// Return if all variables are read back unchanged.
+    ///
+    /// Storage types, compression and character encoding may still differ, see
+    /// [`RoundTripReport::is_bit_exact`].
+    pub fn is_lossless(&self) -> bool {
+        self.differences.is_empty()
+    }
+
+    /// Return if the written file is identical to the original, except for the descriptive text
+    /// of the header, which names the writer and the creation time.
+    pub fn is_bit_exact(&self) -> bool {
+        self.first_byte_difference.is_none()
+    }
+
+    pub fn differences(&self) -> &[RoundTripDifference] {
+        &self.differences
+    }
+
+    /// Size of the original file in bytes
+    pub fn original_size(&self) -> usize {
+        self.original_size
+    }
+
+    /// Size of the written file in bytes
+    pub fn written_size(&self) -> usize {
+        self.written_size
+    }
+
+    /// Offset of the first byte, which differs between original and written file, ignoring the
+    /// header text. The offset is the size of the smaller file, if it is a prefix of the other.
+    pub fn first_byte_difference(&self) -> Option<usize> {
+        self.first_byte_difference
+    }
+}
+
+/// One difference per line, followed by the byte comparison
+impl fmt::Display for RoundTripReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for d in self.differences.iter() {
+            writeln!(f, "{}", d)?;
+        }
+        match self.first_byte_difference {
+            Some(offset) => writeln!(
+                f,
+                "Bytes differ from offset {} ({} bytes written, {} bytes original)",
+                offset, self.written_size, self.original_size
+            ),
+            None => writeln!(f, "Bytes are identical"),
+        }
+    }
+}
+
+///
+/// Load the MAT-file at `path`, write it back into memory and report the differences.
+///
+/// The file is written with the options preserving most of it: subsystem data and metadata are
+/// kept, and variables are compressed, if the original file contains compressed variables.
+///
+/// Fails with the errors of [`crate::load_matfile`] and [`crate::save_matfile_v7`], if the file
+/// can't be read or its variables can't be written.
+///
+/// Example
+/// ```
+/// use matrw::roundtrip_check;
+///
+/// let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/example_v7.mat");
+/// let report = roundtrip_check(path).unwrap();
+///
+/// for difference in report.differences() {
+///     println!("{}", difference);
+/// }
+/// assert!(report.original_size() > 0);
+/// ```
+///
+pub fn roundtrip_check(path: &str) -> Result<RoundTripReport, MatrwError> {
+    roundtrip_check_u8(&fs::read(path)?)
+}
+
+/// Load the MAT-file `data`, write it back into memory and report the differences, see
+/// [`roundtrip_check`]
+pub fn roundtrip_check_u8(data: &[u8]) -> Result<RoundTripReport, MatrwError> {
+    let mut original = load_matfile_lazy_from_u8(data)?;
+    let compressed = original.names().any(|name| !original.is_decompressed(name));
+    original.materialize()?;
+
+    // Writing consumes the MAT-file, the original is kept for the comparison
+    let options = SaveOptions::new().compress(compressed).keep_subsystem(true);
+    let written = save_matfile_v7_to_u8_with_options(load_matfile_from_u8(data)?, &options)?;
+    let read_back = load_matfile_from_u8(&written)?;
+
+    Ok(RoundTripReport {
+        differences: compare_files(&original, &read_back),
+        original_size: data.len(),
+        written_size: written.len(),
+        first_byte_difference: first_byte_difference(data, &written),
+    })
+}
+
+pub(crate) fn compare_files(original: &MatFile, read_back: &MatFile) -> Vec<RoundTripDifference> {
+    let mut differences = Vec::new();
+
+    for (name, var) in original.iter() {
+        match read_back.get(name) {
+            Some(other) => compare_variables(name.clone(), var, other, &mut differences),
+            None => differences.push(RoundTripDifference::Missing { path: name.clone() }),
+        }
+    }
+    for name in read_back.names().filter(|name| !original.contains(name)) {
+        differences.push(RoundTripDifference::Added {
+            path: name.to_string(),
+        });
+    }
+
+    let common = |m: &MatFile| {
+        m.names()
+            .filter(|name| original.contains(name) && read_back.contains(name))
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+    };
+    if common(original) != common(read_back) {
+        differences.push(RoundTripDifference::Order);
+    }
+
+    differences
+}
+
+/// Report the innermost nodes of `original` and `read_back`, which differ.
+///
+/// Values are compared by [`MatVariable::content_hash`], so NaNs compare equal and the storage
+/// type does not matter.
+fn compare_variables(
+    path: String,
+    original: &MatVariable,
+    read_back: &MatVariable,
+    differences: &mut Vec<RoundTripDifference>,
+) {
+    let same_fields = original.fieldnames() == read_back.fieldnames();
+    if same_fields && original.content_hash() == read_back.content_hash() {
+        return;
+    }
+
+    let before = differences.len();
+    match (original, read_back) {
+        (MatVariable::Structure(a), MatVariable::Structure(b)) if same_fields => {
+            for (name, var) in a.value.iter() {
+                compare_variables(format!("{}.{}", path, name), var, &b.value[name], differences);
+            }
+        }
+        (MatVariable::StructureArray(a), MatVariable::StructureArray(b)) if same_fields && a.dim == b.dim => {
+            for (i, (x, y)) in a.value.iter().zip(b.value.iter()).enumerate() {
+                compare_variables(format!("{}({})", path, i + 1), x, y, differences);
+            }
+        }
+        (MatVariable::CellArray(a), MatVariable::CellArray(b)) if a.dim == b.dim => {
+            for (i, (x, y)) in a.value.iter().zip(b.value.iter()).enumerate() {
+                compare_variables(format!("{}{{{}}}", path, i + 1), x, y, differences);
+            }
+        }
+        _ => {}
+    }
+
+    // The container itself differs, if none of its children do
+    if differences.len() == before {
+        differences.push(RoundTripDifference::Changed { path });
+    }
+}
+
+/// Offset of the first differing byte after the header text.
+fn first_byte_difference(original: &[u8], written: &[u8]) -> Option<usize> {
+    let start = HEADER_SUBSYSTEM_DATA_OFFSET_POS;
+    let common = original.len().min(written.len());
+    (start..common)
+        .find(|&i| original[i] != written[i])
+        .or((original.len() != written.len()).then_some(common))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{matfile, matvar, save_matfile_v7_to_u8};
+
+    #[test]
+    fn roundtrip_of_written_files() {
+        let nan = f64::NAN;
+        for compress in [false, true] {
+            let matfile = matfile!(
+                a: matvar!([1.0, nan]),
+                s: matvar!({ b: "text", c: [{ x: 1i8 }, { x: 2i8 }] }),
+            );
+            let data = save_matfile_v7_to_u8(matfile, compress).unwrap();
+
+            let report = roundtrip_check_u8(&data).unwrap();
+            assert!(report.is_lossless(), "{}", report);
+            assert!(report.is_bit_exact(), "{}", report);
+            assert_eq!(report.written_size(), data.len());
+        }
+    }
+
+    #[test]
+    fn roundtrip_differences() {
+        // Storage types are not kept, the values are
+        let options = SaveOptions::new().compress_floats_to_single(true);
+        let data = save_matfile_v7_to_u8_with_options(matfile!(a: matvar!([0.5, 1.5])), &options).unwrap();
+        let report = roundtrip_check_u8(&data).unwrap();
+        assert!(report.is_lossless());
+        assert!(!report.is_bit_exact());
+        assert!(report.written_size() > report.original_size());
+
+        let original = matfile!(s: matvar!({ a: 1.0, u: MatVariable::Unsupported }), c: matvar!([1.0]));
+        let read_back = matfile!(c: matvar!([2.0]), s: matvar!({ a: 1.0, u: [] }), n: matvar!(1.0));
+        assert_eq!(
+            compare_files(&original, &read_back),
+            vec![
+                RoundTripDifference::Changed {
+                    path: "s.u".to_string()
+                },
+                RoundTripDifference::Changed {
+                    path: "c".to_string()
+                },
+                RoundTripDifference::Added {
+                    path: "n".to_string()
+                },
+                RoundTripDifference::Order,
+            ]
+        );
+
+        assert_eq!(first_byte_difference(&[0; 200], &[0; 200]), None);
+        assert_eq!(first_byte_difference(&[0; 200], &[0; 210]), Some(200));
+        let mut changed = [0; 200];
+        changed[150] = 1;
+        changed[10] = 1;
+        assert_eq!(first_byte_difference(&[0; 200], &changed), Some(150));
+    }
+}
@@ -0,0 +1,505 @@
+//! Validation of MAT-file contents against an expected schema.
+
+use std::fmt;
+
+use indexmap::IndexMap;
+#[cfg(feature = "serde")]
+use serde::de::DeserializeOwned;
+
+use crate::MatFile;
+#[cfg(feature = "serde")]
+use crate::MatrwError;
+#[cfg(feature = "serde")]
+use crate::interface::serde::trace::trace_schema;
+pub use crate::interface::types::matlab_types::VariableClass;
+use crate::interface::variable::MatVariable;
+
+/// Expected properties of a single variable or structure field
+///
+/// Every property is optional: a `VariableSchema` without class, shape or fields accepts any
+/// variable.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VariableSchema {
+    class: Option<VariableClass>,
+    shape: Option<Vec<Option<usize>>>,
+    fields: Option<Schema>,
+    optional: bool,
+}
+
+impl VariableSchema {
+    /// Expect a variable of class `class`.
+    pub fn new(class: VariableClass) -> Self {
+        Self {
+            class: Some(class),
+            ..Default::default()
+        }
+    }
+
+    /// Accept a variable of any class and shape.
+    pub fn any() -> Self {
+        Self::default()
+    }
+
+    /// Expect a variable with the given dimensions, where `None` accepts any size.
+    ///
+    /// ```
+    /// use matrw::{VariableClass, VariableSchema};
+    ///
+    /// // Column vector of arbitrary length
+    /// let schema = VariableSchema::new(VariableClass::Double).with_shape(&[None, Some(1)]);
+    /// ```
+    pub fn with_shape(mut self, shape: &[Option<usize>]) -> Self {
+        self.shape = Some(shape.to_vec());
+        self
+    }
+
+    /// Expect a structure with the given fields. For structure arrays, every element is checked.
+    pub fn with_fields(mut self, fields: Schema) -> Self {
+        self.fields = Some(fields);
+        self
+    }
+
+    /// Do not report the variable if it is missing.
+    pub fn optional(mut self) -> Self {
+        self.optional = true;
+        self
+    }
+
+    pub fn class(&self) -> Option<VariableClass> {
+        self.class
+    }
+
+    pub fn shape(&self) -> Option<&[Option<usize>]> {
+        self.shape.as_deref()
+    }
+
+    pub fn fields(&self) -> Option<&Schema> {
+        self.fields.as_ref()
+    }
+
+    pub fn is_optional(&self) -> bool {
+        self.optional
+    }
+
+    #[cfg(feature = "serde")]
+    pub(crate) fn set_class(&mut self, class: VariableClass) {
+        self.class = Some(class);
+    }
+
+    #[cfg(feature = "serde")]
+    pub(crate) fn set_shape(&mut self, shape: &[Option<usize>]) {
+        self.shape = Some(shape.to_vec());
+    }
+
+    #[cfg(feature = "serde")]
+    pub(crate) fn set_fields(&mut self, fields: Schema) {
+        self.fields = Some(fields);
+    }
+
+    #[cfg(feature = "serde")]
+    pub(crate) fn set_optional(&mut self) {
+        self.optional = true;
+    }
+}
+
+/// Expected variables of a MAT-file
+///
+/// A schema is either built programmatically or derived from a type implementing
+/// [`serde::Deserialize`] with [`Schema::from_type`].
+///
+/// ```
+/// use matrw::{matfile, matvar, Schema, VariableClass, VariableSchema};
+///
+/// let schema = Schema::new()
+///     .variable("t", VariableSchema::new(VariableClass::Double).with_shape(&[Some(1), None]))
+///     .variable("name", VariableSchema::new(VariableClass::Char));
+///
+/// let matfile = matfile!(t: matvar!([1.0, 2.0, 3.0]), name: matvar!(1u8));
+///
+/// let report = matfile.validate(&schema);
+/// assert!(!report.is_valid());
+/// assert_eq!(report.to_string(), "'name' has class 'uint8', expected 'char'\n");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Schema {
+    variables: IndexMap<String, VariableSchema>,
+}
+
+impl Schema {
+    /// Create an empty schema, accepting any MAT-file.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the expected variable `name`.
+    pub fn variable(mut self, name: &str, schema: VariableSchema) -> Self {
+        self.insert(name, schema);
+        self
+    }
+
+    /// Add the expected variable `name`, replacing a previous entry.
+    pub fn insert(&mut self, name: &str, schema: VariableSchema) {
+        self.variables.insert(name.to_string(), schema);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&VariableSchema> {
+        self.variables.get(name)
+    }
+
+    pub fn iter(&self) -> indexmap::map::Iter<'_, String, VariableSchema> {
+        self.variables.iter()
+    }
+
+    /// Derive the schema from a struct implementing [`serde::Deserialize`]. Requires the feature
+    /// `serde`.
+    ///
+    /// Each field is mapped to the class [`crate::from_matfile`] expects for it. Scalar fields
+    /// expect a `1x1` variable, `Option<_>` fields are optional and nested structs add their
+    /// fields. Returns an error for types which can not be read from a MAT-file, e.g. enums or
+    /// maps.
+    ///
+    /// ```
+    /// use matrw::{Schema, VariableClass};
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Measurement {
+    ///     t: Vec<f64>,
+    ///     count: u32,
+    ///     label: Option<String>,
+    /// }
+    ///
+    /// let schema = Schema::from_type::<Measurement>().unwrap();
+    ///
+    /// assert_eq!(schema.get("t").unwrap().class(), Some(VariableClass::Double));
+    /// assert_eq!(schema.get("count").unwrap().shape(), Some(&[Some(1), Some(1)][..]));
+    /// assert!(schema.get("label").unwrap().is_optional());
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn from_type<T: DeserializeOwned>() -> Result<Self, MatrwError> {
+        match trace_schema::<T>()?.fields {
+            Some(schema) => Ok(schema),
+            None => Err(MatrwError::SerdeError(
+                "Schema can only be derived from a struct".to_string(),
+            )),
+        }
+    }
+
+    /// Check `matfile` against the schema. See [`MatFile::validate`].
+    pub fn validate(&self, matfile: &MatFile) -> ValidationReport {
+        let mut report = ValidationReport::default();
+        for (name, schema) in self.iter() {
+            let var = if matfile.contains(name) {
+                Some(&matfile[name])
+            } else {
+                None
+            };
+            check_variable(name.clone(), var, schema, &mut report);
+        }
+        report
+    }
+}
+
+/// Single deviation of a MAT-file from a [`Schema`]
+///
+/// Variables are identified by their path, e.g. `s.field` for a structure field or `s(2).field`
+/// for the field of the second element of a structure array.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SchemaViolation {
+    Missing {
+        path: String,
+    },
+    WrongClass {
+        path: String,
+        expected: VariableClass,
+        /// `None` for [`MatVariable::Null`]
+        found: Option<VariableClass>,
+    },
+    WrongShape {
+        path: String,
+        expected: Vec<Option<usize>>,
+        found: Vec<usize>,
+    },
+}
+
+impl SchemaViolation {
+    pub fn path(&self) -> &str {
+        match self {
+            Self::Missing { path } | Self::WrongClass { path, .. } | Self::WrongShape { path, .. } => path,
+        }
+    }
+}
+
+impl fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing { path } => write!(f, "'{}' is missing", path),
+            Self::WrongClass {
+                path,
+                expected,
+                found,
+            } => match found {
+                Some(found) => write!(f, "'{}' has class '{}', expected '{}'", path, found, expected),
+                None => write!(f, "'{}' has unsupported class, expected '{}'", path, expected),
+            },
+            Self::WrongShape {
+                path,
+                expected,
+                found,
+            } => {
+                let expected: Vec<String> = expected
+                    .iter()
+                    .map(|d| d.map_or("*".to_string(), |d| d.to_string()))
+                    .collect();
+                let found: Vec<String> = found.iter().map(|d| d.to_string()).collect();
+                write!(
+                    f,
+                    "'{}' has size {}, expected {}",
+                    path,
+                    found.join("x"),
+                    expected.join("x")
+                )
+            }
+        }
+    }
+}
+
+/// Result of [`MatFile::validate`] listing every [`SchemaViolation`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ValidationReport {
+    violations: Vec<SchemaViolation>,
+}
+
+impl ValidationReport {
+    /// Return if the MAT-file matches the schema.
+    pub fn is_valid(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    pub fn violations(&self) -> &[SchemaViolation] {
+        &self.violations
+    }
+}
+
+/// One violation per line
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for v in self.violations.iter() {
+            writeln!(f, "{}", v)?;
+        }
+        Ok(())
+    }
+}
+
+fn check_variable(
+    path: String,
+    var: Option<&MatVariable>,
+    schema: &VariableSchema,
+    report: &mut ValidationReport,
+) {
+    let Some(var) = var else {
+        if !schema.optional {
+            report.violations.push(SchemaViolation::Missing { path });
+        }
+        return;
+    };
+
+    if let Some(expected) = schema.class {
+        let found = VariableClass::of(var);
+        if found != Some(expected) {
+            report.violations.push(SchemaViolation::WrongClass {
+                path,
+                expected,
+                found,
+            });
+            return;
+        }
+    }
+
+    if let (Some(expected), Some(found)) = (&schema.shape, dim(var)) {
+        let matches = expected.len() == found.len()
+            && expected
+                .iter()
+                .zip(found.iter())
+                .all(|(e, f)| e.is_none_or(|e| e == *f));
+        if !matches {
+            report.violations.push(SchemaViolation::WrongShape {
+                path: path.clone(),
+                expected: expected.clone(),
+                found: found.to_vec(),
+            });
+        }
+    }
+
+    if let Some(fields) = &schema.fields {
+        match var {
+            MatVariable::Structure(_) => check_fields(&path, var, fields, report),
+            MatVariable::StructureArray(arr) => {
+                for (i, elem) in arr.value.iter().enumerate() {
+                    check_fields(&format!("{}({})", path, i + 1), elem, fields, report);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn check_fields(path: &str, var: &MatVariable, fields: &Schema, report: &mut ValidationReport) {
+    let MatVariable::Structure(s) = var else {
+        return;
+    };
+    for (name, schema) in fields.iter() {
+        check_variable(format!("{}.{}", path, name), s.get(name), schema, report);
+    }
+}
+
+fn dim(var: &MatVariable) -> Option<&[usize]> {
+    match var {
+        MatVariable::NumericArray(arr) => Some(&arr.dim),
+        MatVariable::SparseArray(arr) => Some(&arr.dim),
+        MatVariable::CellArray(arr) => Some(&arr.dim),
+        MatVariable::StructureArray(arr) => Some(&arr.dim),
+        MatVariable::Structure(_) => Some(&[1, 1]),
+        MatVariable::Null | MatVariable::Unsupported => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{matfile, matvar};
+
+    #[test]
+    fn report_all_violations() {
+        let schema = Schema::new()
+            .variable(
+                "a",
+                VariableSchema::new(VariableClass::Double).with_shape(&[Some(1), None]),
+            )
+            .variable("b", VariableSchema::new(VariableClass::Single))
+            .variable("c", VariableSchema::any())
+            .variable("d", VariableSchema::any().optional())
+            .variable(
+                "s",
+                VariableSchema::new(VariableClass::Struct).with_fields(
+                    Schema::new()
+                        .variable("x", VariableSchema::new(VariableClass::Double))
+                        .variable("y", VariableSchema::new(VariableClass::Char)),
+                ),
+            );
+
+        let matfile = matfile!(
+            a: matvar!([[1.0, 2.0], [3.0, 4.0]]),
+            b: matvar!(1.0),
+            s: matvar!({ x: 1.0 }),
+        );
+
+        let report = matfile.validate(&schema);
+
+        assert_eq!(
+            report.violations(),
+            &[
+                SchemaViolation::WrongShape {
+                    path: "a".to_string(),
+                    expected: vec![Some(1), None],
+                    found: vec![2, 2],
+                },
+                SchemaViolation::WrongClass {
+                    path: "b".to_string(),
+                    expected: VariableClass::Single,
+                    found: Some(VariableClass::Double),
+                },
+                SchemaViolation::Missing {
+                    path: "c".to_string()
+                },
+                SchemaViolation::Missing {
+                    path: "s.y".to_string()
+                },
+            ]
+        );
+        assert_eq!(
+            report.to_string(),
+            "'a' has size 2x2, expected 1x*\n\
+             'b' has class 'double', expected 'single'\n\
+             'c' is missing\n\
+             's.y' is missing\n"
+        );
+    }
+
+    #[test]
+    fn valid_matfile() {
+        let schema = Schema::new().variable(
+            "a",
+            VariableSchema::new(VariableClass::Logical).with_shape(&[None, None]),
+        );
+        let matfile = matfile!(a: matvar!([true, false]));
+
+        assert!(matfile.validate(&schema).is_valid());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn schema_from_type() {
+        use crate::SparseMatrix;
+        use serde::Deserialize;
+
+        #[allow(dead_code)]
+        #[derive(Deserialize)]
+        struct Inner {
+            flag: bool,
+        }
+
+        #[allow(dead_code)]
+        #[derive(Deserialize)]
+        struct Example {
+            a: f64,
+            b: Vec<i16>,
+            c: String,
+            d: Option<u8>,
+            s: Inner,
+            sp: SparseMatrix<f64>,
+        }
+
+        let schema = Schema::from_type::<Example>().unwrap();
+
+        let scalar = [Some(1), Some(1)];
+        let expected = Schema::new()
+            .variable(
+                "a",
+                VariableSchema::new(VariableClass::Double).with_shape(&scalar),
+            )
+            .variable("b", VariableSchema::new(VariableClass::Int16))
+            .variable("c", VariableSchema::new(VariableClass::Char))
+            .variable(
+                "d",
+                VariableSchema::new(VariableClass::UInt8)
+                    .with_shape(&scalar)
+                    .optional(),
+            )
+            .variable(
+                "s",
+                VariableSchema::new(VariableClass::Struct).with_fields(Schema::new().variable(
+                    "flag",
+                    VariableSchema::new(VariableClass::Logical).with_shape(&scalar),
+                )),
+            )
+            .variable("sp", VariableSchema::new(VariableClass::Sparse));
+
+        assert_eq!(schema, expected);
+
+        let matfile = matfile!(
+            a: matvar!(1.0),
+            b: matvar!([1i16, 2i16]),
+            c: matvar!("text"),
+            s: matvar!({ flag: true }),
+            sp: matvar!([1.0, 0.0]).to_sparse().unwrap(),
+        );
+        assert!(matfile.validate(&schema).is_valid());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn schema_from_unsupported_type() {
+        assert!(Schema::from_type::<std::collections::HashMap<String, f64>>().is_err());
+    }
+}
@@ -3,11 +3,18 @@
 use crate::MatFile;
 use crate::MatrwError;
 
+use std::collections::HashSet;
+
 use serde::Deserialize;
 
+use crate::MatlabType;
+use crate::interface::serde::sparse::{SPARSE_MATRIX_FIELDS, SPARSE_MATRIX_TOKEN};
+use crate::interface::types::cell_array::CellArray;
+use crate::interface::types::numeric_array::NumericArray;
+use crate::interface::types::sparse_array::SparseArray;
 use crate::interface::variable::MatVariable;
 use serde::{
-    de::{Deserializer, IntoDeserializer, MapAccess, value::SeqDeserializer},
+    de::{Deserializer, IntoDeserializer, MapAccess, SeqAccess, value::SeqDeserializer},
     forward_to_deserialize_any,
 };
 
@@ -16,10 +23,15 @@ use serde::{
 /// Current supported deserializations:
 /// - [x] [`MatVariable::NumericArray`] to scalar for all supported numeric types and `char`
 /// - [x] [`MatVariable::NumericArray`] to `Vec<_>` for all supported numeric types and `char`
+/// - [x] [`MatVariable::NumericArray`] to `Vec<Vec<_>>` with one `Vec` per row of a 2D array
+/// - [x] [`MatVariable::NumericArray`] scalar to `num_complex::Complex<_>` (feature
+///   `num-complex`) or any `(re, im)` tuple
 /// - [x] [`MatVariable::NumericArray`] to `String` for `char` data
-/// - [x] [`MatVariable::Structure`] to `struct`
+/// - [x] [`MatVariable::CellArray`] to `Vec<_>`, e.g. `Vec<String>` for a cell array of `char` rows
+/// - [x] 2D `char` [`MatVariable::NumericArray`] to `Vec<String>` with one `String` per row
+/// - [x] [`MatVariable::Structure`] to `struct`, including `#[serde(flatten)]` fields
+/// - [x] [`MatVariable::SparseArray`] to [`crate::SparseMatrix`]
 /// - [ ] [`MatVariable::StructureArray`]
-/// - [ ] [`MatVariable::CellArray`]
 /// ```
 /// use matrw::{matfile, matvar, MatFile, from_matfile};
 /// use serde::Deserialize;
@@ -70,24 +82,136 @@ where
     T::deserialize(deserializer)
 }
 
+/// Deserialize [`MatFile`] like [`from_matfile`], rejecting matrices flattened into sequences.
+///
+/// [`from_matfile`] reads a variable of any shape into a `Vec<_>` field, concatenating the
+/// columns of a matrix. With this function, deserializing a variable with more than one
+/// non-singleton dimension into a sequence is an error, unless its path is allowed in `hints`.
+///
+/// ```
+/// use matrw::{matfile, matvar, from_matfile_checked, ShapeHints};
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Example {
+///     v: Vec<f64>,
+///     m: Vec<f64>,
+/// }
+///
+/// let matfile = matfile!(
+///     v: matvar!([1.0, 2.0, 3.0]),
+///     m: matvar!([[1.0, 2.0], [3.0, 4.0]]),
+/// );
+///
+/// // "m" is a 2x2 matrix
+/// assert!(from_matfile_checked::<Example>(&matfile, &ShapeHints::new()).is_err());
+///
+/// let hints = ShapeHints::new().allow_flatten("m");
+/// let e: Example = from_matfile_checked(&matfile, &hints).unwrap();
+/// assert_eq!(e.m, vec![1.0, 3.0, 2.0, 4.0]);
+/// ```
+pub fn from_matfile_checked<'a, T>(matfile: &'a MatFile, hints: &'a ShapeHints) -> Result<T, MatrwError>
+where
+    T: Deserialize<'a>,
+{
+    let deserializer = MatFileDeserializer {
+        matfile,
+        hints: Some(hints),
+    };
+    T::deserialize(deserializer)
+}
+
+impl MatFile {
+    ///
+    /// Deserialize variable `name` into a type which implements [`serde::Deserialize`], like a
+    /// field of [`from_matfile`].
+    ///
+    /// Returns [`MatrwError::AccessError`], if the variable does not exist, and
+    /// [`MatrwError::SerdeError`], if it does not fit into `T`.
+    ///
+    /// Example
+    /// ```
+    /// use matrw::{matfile, matvar};
+    ///
+    /// let matfile = matfile!(
+    ///     x: matvar!([1.0, 2.0, 3.0]),
+    ///     name: matvar!("run 1"),
+    /// );
+    ///
+    /// let x: Vec<f64> = matfile.get_typed("x").unwrap();
+    /// assert_eq!(x, vec![1.0, 2.0, 3.0]);
+    /// assert_eq!(matfile.get_typed::<String>("name").unwrap(), "run 1");
+    ///
+    /// assert!(matfile.get_typed::<f64>("name").is_err());
+    /// assert!(matfile.get_typed::<f64>("y").is_err());
+    /// ```
+    ///
+    pub fn get_typed<'a, T>(&'a self, name: &str) -> Result<T, MatrwError>
+    where
+        T: Deserialize<'a>,
+    {
+        let matvar = self
+            .get(name)
+            .ok_or_else(|| MatrwError::AccessError(format!("Variable '{}' does not exist", name)))?;
+        T::deserialize(MatVariableDeserializer {
+            matvar,
+            hints: None,
+            path: name.to_string(),
+        })
+    }
+}
+
+/// Variables which [`from_matfile_checked`] may flatten into sequences
+///
+/// Variables are identified by their path, e.g. `a` for the variable `a` and `s.b` for the
+/// field `b` of the structure `s`.
+#[derive(Debug, Clone, Default)]
+pub struct ShapeHints {
+    flatten: HashSet<String>,
+}
+
+impl ShapeHints {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow flattening the variable at `path` into a sequence.
+    pub fn allow_flatten(mut self, path: &str) -> Self {
+        self.flatten.insert(path.to_string());
+        self
+    }
+
+    /// Return if the variable at `path` may be flattened into a sequence.
+    pub fn is_flatten_allowed(&self, path: &str) -> bool {
+        self.flatten.contains(path)
+    }
+}
+
 pub struct MatFileDeserializer<'de> {
     matfile: &'de MatFile,
+    hints: Option<&'de ShapeHints>,
 }
 
 impl<'de> MatFileDeserializer<'de> {
     pub fn new(matfile: &'de MatFile) -> Self {
-        Self { matfile }
+        Self { matfile, hints: None }
     }
 }
 
 impl<'de> Deserializer<'de> for MatFileDeserializer<'de> {
     type Error = MatrwError;
 
-    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    /// Provides all variables, e.g. for structs with `#[serde(flatten)]` fields
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de>,
     {
-        unimplemented!()
+        visitor.visit_map(VariablesMapAccess {
+            iter: self.matfile.iter(),
+            value: None,
+            hints: self.hints,
+            prefix: None,
+        })
     }
 
     fn deserialize_struct<V>(
@@ -139,11 +263,17 @@ impl<'a, 'de> MapAccess<'de> for MatFileMapAccess<'a, 'de> {
         V: serde::de::DeserializeSeed<'de>,
     {
         let key = self.fields[self.id - 1];
-        let matvar = &self.de.matfile[key];
-
-        match matvar {
-            MatVariable::NumericArray(_) => seed.deserialize(MatVariableDeserializer { matvar }),
-            MatVariable::Structure(_) => seed.deserialize(MatVariableDeserializer { matvar }),
+        let de = MatVariableDeserializer {
+            matvar: &self.de.matfile[key],
+            hints: self.de.hints,
+            path: key.to_string(),
+        };
+
+        match de.matvar {
+            MatVariable::NumericArray(_) => seed.deserialize(de),
+            MatVariable::Structure(_) => seed.deserialize(de),
+            MatVariable::SparseArray(_) => seed.deserialize(de),
+            MatVariable::CellArray(_) => seed.deserialize(de),
             _ => unimplemented!(),
         }
     }
@@ -152,16 +282,95 @@ impl<'a, 'de> MapAccess<'de> for MatFileMapAccess<'a, 'de> {
 #[allow(dead_code)]
 struct MatVariableDeserializer<'de> {
     matvar: &'de MatVariable,
+    hints: Option<&'de ShapeHints>,
+    /// Path of the variable, used in error messages
+    path: String,
+}
+
+impl MatVariableDeserializer<'_> {
+    fn unexpected(&self, expected: &str) -> MatrwError {
+        MatrwError::SerdeError(format!(
+            "Variable '{}' can not be deserialized into {}",
+            self.path, expected
+        ))
+    }
+
+    /// Fail if a matrix would be flattened into a sequence without being allowed in the hints.
+    fn check_flatten(&self, dim: &[usize]) -> Result<(), MatrwError> {
+        let Some(hints) = self.hints else {
+            return Ok(());
+        };
+        if dim.iter().filter(|&&d| d > 1).count() > 1 && !hints.is_flatten_allowed(&self.path) {
+            let dim: Vec<String> = dim.iter().map(|d| d.to_string()).collect();
+            return Err(MatrwError::SerdeError(format!(
+                "Variable '{}' of size {} would be flattened into a sequence",
+                self.path,
+                dim.join("x")
+            )));
+        }
+        Ok(())
+    }
 }
 
 impl<'de> Deserializer<'de> for MatVariableDeserializer<'de> {
     type Error = MatrwError;
 
-    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    /// Self-describing deserialization, used by serde e.g. to buffer `#[serde(flatten)]` fields
+    ///
+    /// Scalars map to primitive values, vectors to sequences of values and 2D arrays to
+    /// sequences of rows. Character data maps to strings instead.
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de>,
     {
-        unimplemented!()
+        match self.matvar {
+            MatVariable::NumericArray(arr) => {
+                if arr.is_complex() {
+                    return Err(MatrwError::SerdeError(format!(
+                        "Variable '{}' is complex, which requires a typed field",
+                        self.path
+                    )));
+                }
+
+                let is_char = matches!(arr.value, MatlabType::UTF8(_) | MatlabType::UTF16(_));
+                let is_vector = arr.dim.iter().filter(|&&d| d > 1).count() <= 1;
+                let mode = if is_vector || arr.dim.len() != 2 {
+                    SeqMode::Elements
+                } else {
+                    SeqMode::Rows
+                };
+
+                match (is_char, mode) {
+                    (true, SeqMode::Elements) => self.deserialize_string(visitor),
+                    (false, SeqMode::Elements) if arr.is_scalar() => visit_element(&arr.value, 0, visitor),
+                    (_, mode) => {
+                        self.check_flatten(&arr.dim)?;
+                        visitor.visit_seq(MatrixSeqAccess {
+                            de: &self,
+                            arr,
+                            id: 0,
+                            mode,
+                        })
+                    }
+                }
+            }
+            MatVariable::Structure(s) => visitor.visit_map(VariablesMapAccess {
+                iter: s.value.iter(),
+                value: None,
+                hints: self.hints,
+                prefix: Some(&self.path),
+            }),
+            MatVariable::CellArray(cell) => visitor.visit_seq(CellSeqAccess {
+                de: &self,
+                cell,
+                id: 0,
+            }),
+            MatVariable::SparseArray(sparse) => visitor.visit_map(SparseMapAccess { sparse, id: 0 }),
+            MatVariable::Null => visitor.visit_unit(),
+            MatVariable::StructureArray(_) | MatVariable::Unsupported => Err(MatrwError::SerdeError(
+                format!("Variable '{}' is not supported", self.path),
+            )),
+        }
     }
 
     fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error>
@@ -169,52 +378,24 @@ impl<'de> Deserializer<'de> for MatVariableDeserializer<'de> {
         V: serde::de::Visitor<'de>,
     {
         match self.matvar {
-            MatVariable::NumericArray(_) => {
-                let vec_u8: Option<Vec<u8>> = self.matvar.to_vec_u8();
-                let vec_i8: Option<Vec<i8>> = self.matvar.to_vec_i8();
-                let vec_u16: Option<Vec<u16>> = self.matvar.to_vec_u16();
-                let vec_i16: Option<Vec<i16>> = self.matvar.to_vec_i16();
-                let vec_u32: Option<Vec<u32>> = self.matvar.to_vec_u32();
-                let vec_i32: Option<Vec<i32>> = self.matvar.to_vec_i32();
-                let vec_u64: Option<Vec<u64>> = self.matvar.to_vec_u64();
-                let vec_i64: Option<Vec<i64>> = self.matvar.to_vec_i64();
-                let vec_f32: Option<Vec<f32>> = self.matvar.to_vec_f32();
-                let vec_f64: Option<Vec<f64>> = self.matvar.to_vec_f64();
-                let vec_char: Option<Vec<char>> = self.matvar.to_vec_char();
-
-                if let Some(value) = vec_u8 {
-                    visitor.visit_seq(SeqDeserializer::new(value.into_iter()).into_deserializer())
-                } else if let Some(value) = vec_i8 {
-                    visitor.visit_seq(SeqDeserializer::new(value.into_iter()).into_deserializer())
-                } else if let Some(value) = vec_u16 {
-                    visitor.visit_seq(SeqDeserializer::new(value.into_iter()).into_deserializer())
-                } else if let Some(value) = vec_i16 {
-                    visitor.visit_seq(SeqDeserializer::new(value.into_iter()).into_deserializer())
-                } else if let Some(value) = vec_u32 {
-                    visitor.visit_seq(SeqDeserializer::new(value.into_iter()).into_deserializer())
-                } else if let Some(value) = vec_i32 {
-                    visitor.visit_seq(SeqDeserializer::new(value.into_iter()).into_deserializer())
-                } else if let Some(value) = vec_u64 {
-                    visitor.visit_seq(SeqDeserializer::new(value.into_iter()).into_deserializer())
-                } else if let Some(value) = vec_i64 {
-                    visitor.visit_seq(SeqDeserializer::new(value.into_iter()).into_deserializer())
-                } else if let Some(value) = vec_f32 {
-                    visitor.visit_seq(SeqDeserializer::new(value.into_iter()).into_deserializer())
-                } else if let Some(value) = vec_f64 {
-                    visitor.visit_seq(SeqDeserializer::new(value.into_iter()).into_deserializer())
-                } else if let Some(value) = vec_char {
-                    visitor.visit_seq(SeqDeserializer::new(value.into_iter()).into_deserializer())
-                } else {
-                    Err(MatrwError::SerdeError("Unknown numeric type".to_string()))
-                }
-            }
-            _ => unimplemented!(),
+            MatVariable::NumericArray(arr) => visitor.visit_seq(MatrixSeqAccess {
+                de: &self,
+                arr,
+                id: 0,
+                mode: SeqMode::Unknown,
+            }),
+            MatVariable::CellArray(cell) => visitor.visit_seq(CellSeqAccess {
+                de: &self,
+                cell,
+                id: 0,
+            }),
+            _ => Err(self.unexpected("a sequence")),
         }
     }
 
     fn deserialize_struct<V>(
         self,
-        _name: &'static str,
+        name: &'static str,
         fields: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value, Self::Error>
@@ -223,7 +404,13 @@ impl<'de> Deserializer<'de> for MatVariableDeserializer<'de> {
     {
         match self.matvar {
             MatVariable::Structure(_) => visitor.visit_map(MatVariableMapAccess::new(&self, fields, 0)),
-            _ => unimplemented!(),
+            MatVariable::SparseArray(sparse) if name == SPARSE_MATRIX_TOKEN => {
+                visitor.visit_map(SparseMapAccess { sparse, id: 0 })
+            }
+            MatVariable::SparseArray(_) => Err(MatrwError::SerdeError(
+                "Sparse variables can only be deserialized into SparseMatrix".to_string(),
+            )),
+            _ => Err(self.unexpected("a struct")),
         }
     }
 
@@ -241,7 +428,7 @@ impl<'de> Deserializer<'de> for MatVariableDeserializer<'de> {
                     Err(MatrwError::SerdeError("Expected u8".to_string()))
                 }
             }
-            _ => unimplemented!(),
+            _ => Err(self.unexpected("u8")),
         }
     }
 
@@ -259,7 +446,7 @@ impl<'de> Deserializer<'de> for MatVariableDeserializer<'de> {
                     Err(MatrwError::SerdeError("Expected i8".to_string()))
                 }
             }
-            _ => unimplemented!(),
+            _ => Err(self.unexpected("i8")),
         }
     }
 
@@ -277,7 +464,7 @@ impl<'de> Deserializer<'de> for MatVariableDeserializer<'de> {
                     Err(MatrwError::SerdeError("Expected u16".to_string()))
                 }
             }
-            _ => unimplemented!(),
+            _ => Err(self.unexpected("u16")),
         }
     }
 
@@ -295,7 +482,7 @@ impl<'de> Deserializer<'de> for MatVariableDeserializer<'de> {
                     Err(MatrwError::SerdeError("Expected i16".to_string()))
                 }
             }
-            _ => unimplemented!(),
+            _ => Err(self.unexpected("i16")),
         }
     }
 
@@ -313,7 +500,7 @@ impl<'de> Deserializer<'de> for MatVariableDeserializer<'de> {
                     Err(MatrwError::SerdeError("Expected u32".to_string()))
                 }
             }
-            _ => unimplemented!(),
+            _ => Err(self.unexpected("u32")),
         }
     }
 
@@ -331,7 +518,7 @@ impl<'de> Deserializer<'de> for MatVariableDeserializer<'de> {
                     Err(MatrwError::SerdeError("Expected i32".to_string()))
                 }
             }
-            _ => unimplemented!(),
+            _ => Err(self.unexpected("i32")),
         }
     }
 
@@ -349,7 +536,7 @@ impl<'de> Deserializer<'de> for MatVariableDeserializer<'de> {
                     Err(MatrwError::SerdeError("Expected u64".to_string()))
                 }
             }
-            _ => unimplemented!(),
+            _ => Err(self.unexpected("u64")),
         }
     }
 
@@ -367,7 +554,7 @@ impl<'de> Deserializer<'de> for MatVariableDeserializer<'de> {
                     Err(MatrwError::SerdeError("Expected i64".to_string()))
                 }
             }
-            _ => unimplemented!(),
+            _ => Err(self.unexpected("i64")),
         }
     }
 
@@ -385,7 +572,7 @@ impl<'de> Deserializer<'de> for MatVariableDeserializer<'de> {
                     Err(MatrwError::SerdeError("Expected f32".to_string()))
                 }
             }
-            _ => unimplemented!(),
+            _ => Err(self.unexpected("f32")),
         }
     }
 
@@ -403,7 +590,7 @@ impl<'de> Deserializer<'de> for MatVariableDeserializer<'de> {
                     Err(MatrwError::SerdeError("Expected f64".to_string()))
                 }
             }
-            _ => unimplemented!(),
+            _ => Err(self.unexpected("f64")),
         }
     }
 
@@ -421,7 +608,7 @@ impl<'de> Deserializer<'de> for MatVariableDeserializer<'de> {
                     Err(MatrwError::SerdeError("Expected char".to_string()))
                 }
             }
-            _ => unimplemented!(),
+            _ => Err(self.unexpected("a char")),
         }
     }
 
@@ -439,7 +626,7 @@ impl<'de> Deserializer<'de> for MatVariableDeserializer<'de> {
                     Err(MatrwError::SerdeError("Expected bool".to_string()))
                 }
             }
-            _ => unimplemented!(),
+            _ => Err(self.unexpected("a bool")),
         }
     }
 
@@ -449,20 +636,35 @@ impl<'de> Deserializer<'de> for MatVariableDeserializer<'de> {
     {
         match self.matvar {
             MatVariable::NumericArray(_) => {
-                let vec: Option<Vec<char>> = self.matvar.to_vec_char();
-
-                if let Some(value) = vec {
-                    let s: String = value.into_iter().collect();
+                if let Some(s) = self.matvar.to_matlab_string() {
                     visitor.visit_string(s)
                 } else {
-                    Err(MatrwError::SerdeError("Expected char".to_string()))
+                    Err(MatrwError::SerdeError(
+                        "Expected character row vector".to_string(),
+                    ))
                 }
             }
-            _ => unimplemented!(),
+            _ => Err(self.unexpected("a string")),
         }
     }
 
-    forward_to_deserialize_any! {str bytes byte_buf option unit unit_struct newtype_struct tuple tuple_struct map enum identifier ignored_any}
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        match self.matvar {
+            MatVariable::NumericArray(arr) if len == 2 && arr.is_scalar() => {
+                visitor.visit_seq(ComplexSeqAccess { arr, id: 0 })
+            }
+            MatVariable::NumericArray(_) => Err(MatrwError::SerdeError(format!(
+                "Variable '{}' is not a scalar and can not be deserialized into a (re, im) tuple",
+                self.path
+            ))),
+            _ => Err(self.unexpected("a tuple")),
+        }
+    }
+
+    forward_to_deserialize_any! {str bytes byte_buf option unit unit_struct newtype_struct tuple_struct map enum identifier ignored_any}
 }
 
 struct MatVariableMapAccess<'a, 'de: 'a> {
@@ -499,19 +701,428 @@ impl<'a, 'de> MapAccess<'de> for MatVariableMapAccess<'a, 'de> {
         V: serde::de::DeserializeSeed<'de>,
     {
         let key = self.fields[self.id - 1];
-        let matvar = &self.de.matvar[key];
-
-        match matvar {
-            MatVariable::NumericArray(_) => seed.deserialize(MatVariableDeserializer { matvar }),
-            MatVariable::Structure(_) => seed.deserialize(MatVariableDeserializer { matvar }),
+        let de = MatVariableDeserializer {
+            matvar: &self.de.matvar[key],
+            hints: self.de.hints,
+            path: format!("{}.{}", self.de.path, key),
+        };
+
+        match de.matvar {
+            MatVariable::NumericArray(_) => seed.deserialize(de),
+            MatVariable::Structure(_) => seed.deserialize(de),
+            MatVariable::SparseArray(_) => seed.deserialize(de),
+            MatVariable::CellArray(_) => seed.deserialize(de),
             _ => unimplemented!(),
         }
     }
 }
 
+/// How the elements of a sequence are mapped to a [`NumericArray`].
+#[derive(PartialEq)]
+enum SeqMode {
+    /// Not known before the first element requests its type
+    Unknown,
+    /// Every array element, in column-major order
+    Elements,
+    /// Every row of a 2D array, as requested by e.g. `Vec<Vec<f64>>`
+    Rows,
+}
+
+/// Provides the elements or the rows of a [`NumericArray`] as a sequence.
+struct MatrixSeqAccess<'a, 'de: 'a> {
+    de: &'a MatVariableDeserializer<'de>,
+    arr: &'de NumericArray,
+    id: usize,
+    mode: SeqMode,
+}
+
+impl<'a, 'de> SeqAccess<'de> for MatrixSeqAccess<'a, 'de> {
+    type Error = MatrwError;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: serde::de::DeserializeSeed<'de>,
+    {
+        // Empty arrays may have rows, e.g. N×0 arrays, which only the element type can tell
+        if self.mode == SeqMode::Unknown
+            && self.arr.value.is_empty()
+            && self.arr.dim.len() == 2
+            && self.arr.dim[0] > 0
+        {
+            return match seed.deserialize(MatrixElementDeserializer { access: self }) {
+                Ok(value) => {
+                    self.id += 1;
+                    Ok(Some(value))
+                }
+                // Elements were requested, of which there are none
+                Err(_) if self.mode == SeqMode::Elements => Ok(None),
+                Err(err) => Err(err),
+            };
+        }
+
+        let len = match self.mode {
+            SeqMode::Rows => self.arr.dim[0],
+            _ => self.arr.value.len(),
+        };
+        if self.id >= len {
+            return Ok(None);
+        }
+
+        let value = seed.deserialize(MatrixElementDeserializer { access: self })?;
+        self.id += 1;
+
+        Ok(Some(value))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        match self.mode {
+            SeqMode::Rows => Some(self.arr.dim[0] - self.id),
+            _ => None,
+        }
+    }
+}
+
+/// Deserializes the next element of a [`MatrixSeqAccess`], determining its mode on first use.
+struct MatrixElementDeserializer<'s, 'a, 'de: 'a> {
+    access: &'s mut MatrixSeqAccess<'a, 'de>,
+}
+
+impl<'s, 'a, 'de> MatrixElementDeserializer<'s, 'a, 'de> {
+    /// Switch to [`SeqMode::Rows`], which requires a 2D array.
+    fn enter_rows(self) -> Result<&'s mut MatrixSeqAccess<'a, 'de>, MatrwError> {
+        let access = self.access;
+        if access.mode == SeqMode::Elements {
+            return Err(MatrwError::SerdeError(format!(
+                "Variable '{}' mixes elements and rows",
+                access.de.path
+            )));
+        }
+        if access.arr.dim.len() != 2 {
+            return Err(MatrwError::SerdeError(format!(
+                "Variable '{}' with {} dimensions can not be deserialized into nested sequences",
+                access.de.path,
+                access.arr.dim.len()
+            )));
+        }
+        access.mode = SeqMode::Rows;
+
+        Ok(access)
+    }
+}
+
+impl<'s, 'a, 'de> Deserializer<'de> for MatrixElementDeserializer<'s, 'a, 'de> {
+    type Error = MatrwError;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        match (&self.access.mode, &self.access.arr.value) {
+            (SeqMode::Rows, MatlabType::UTF8(_) | MatlabType::UTF16(_)) => {
+                return self.deserialize_string(visitor);
+            }
+            (SeqMode::Rows, _) => return self.deserialize_seq(visitor),
+            _ => {}
+        }
+
+        let access = self.access;
+        if access.mode == SeqMode::Unknown {
+            access.de.check_flatten(&access.arr.dim)?;
+            access.mode = SeqMode::Elements;
+        }
+        if access.id >= access.arr.value.len() {
+            return Err(MatrwError::SerdeError(format!(
+                "Variable '{}' has no element {}",
+                access.de.path,
+                access.id + 1
+            )));
+        }
+
+        visit_element(&access.arr.value, access.id, visitor)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        let access = self.enter_rows()?;
+        let arr = access.arr;
+
+        visitor.visit_seq(RowSeqAccess {
+            value: &arr.value,
+            index: access.id,
+            step: arr.dim[0],
+            remaining: arr.dim[1],
+        })
+    }
+
+    /// Rows of a 2D char array, with trailing padding removed like MATLAB `cellstr`
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        let access = self.enter_rows()?;
+        let arr = access.arr;
+
+        let (MatlabType::UTF8(v) | MatlabType::UTF16(v)) = &arr.value else {
+            return Err(MatrwError::SerdeError(format!(
+                "Variable '{}' is not a char array",
+                access.de.path
+            )));
+        };
+
+        let row: String = v.iter().skip(access.id).step_by(arr.dim[0]).collect();
+        visitor.visit_string(row.trim_end_matches(' ').to_string())
+    }
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        self.deserialize_string(visitor)
+    }
+
+    forward_to_deserialize_any! {bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char bytes byte_buf option unit unit_struct newtype_struct tuple tuple_struct map struct enum identifier ignored_any}
+}
+
+/// Provides the elements of a single row of a column-major array.
+struct RowSeqAccess<'de> {
+    value: &'de MatlabType,
+    index: usize,
+    step: usize,
+    remaining: usize,
+}
+
+impl<'de> SeqAccess<'de> for RowSeqAccess<'de> {
+    type Error = MatrwError;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: serde::de::DeserializeSeed<'de>,
+    {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+
+        let value = seed.deserialize(ElementDeserializer {
+            value: self.value,
+            index: self.index,
+        })?;
+        self.index += self.step;
+        self.remaining -= 1;
+
+        Ok(Some(value))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining)
+    }
+}
+
+/// Deserializes a single element of a [`MatlabType`].
+struct ElementDeserializer<'de> {
+    value: &'de MatlabType,
+    index: usize,
+}
+
+impl<'de> Deserializer<'de> for ElementDeserializer<'de> {
+    type Error = MatrwError;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        visit_element(self.value, self.index, visitor)
+    }
+
+    forward_to_deserialize_any! {bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string bytes byte_buf option unit unit_struct newtype_struct seq tuple tuple_struct map struct enum identifier ignored_any}
+}
+
+/// Provides every entry of a [`MatFile`] or [`crate::Structure`] as map.
+struct VariablesMapAccess<'p, 'de, I> {
+    iter: I,
+    value: Option<(&'de String, &'de MatVariable)>,
+    hints: Option<&'de ShapeHints>,
+    /// Path of the structure, `None` for the variables of a [`MatFile`]
+    prefix: Option<&'p str>,
+}
+
+impl<'p, 'de, I> MapAccess<'de> for VariablesMapAccess<'p, 'de, I>
+where
+    I: Iterator<Item = (&'de String, &'de MatVariable)>,
+{
+    type Error = MatrwError;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: serde::de::DeserializeSeed<'de>,
+    {
+        match self.iter.next() {
+            Some((key, value)) => {
+                self.value = Some((key, value));
+                seed.deserialize(key.as_str().into_deserializer()).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: serde::de::DeserializeSeed<'de>,
+    {
+        let (key, matvar) = self
+            .value
+            .take()
+            .expect("next_value_seed called before next_key_seed");
+        let path = match self.prefix {
+            Some(prefix) => format!("{}.{}", prefix, key),
+            None => key.clone(),
+        };
+
+        seed.deserialize(MatVariableDeserializer {
+            matvar,
+            hints: self.hints,
+            path,
+        })
+    }
+}
+
+/// Provides the elements of a [`CellArray`] in column-major order.
+struct CellSeqAccess<'a, 'de: 'a> {
+    de: &'a MatVariableDeserializer<'de>,
+    cell: &'de CellArray,
+    id: usize,
+}
+
+impl<'a, 'de> SeqAccess<'de> for CellSeqAccess<'a, 'de> {
+    type Error = MatrwError;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: serde::de::DeserializeSeed<'de>,
+    {
+        let Some(matvar) = self.cell.value.get(self.id) else {
+            return Ok(None);
+        };
+        self.id += 1;
+
+        seed.deserialize(MatVariableDeserializer {
+            matvar,
+            hints: self.de.hints,
+            path: format!("{}{{{}}}", self.de.path, self.id),
+        })
+        .map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.cell.value.len() - self.id)
+    }
+}
+
+/// Provides real and imaginary part of a scalar, e.g. for `num_complex::Complex`.
+///
+/// The imaginary part of real scalars is zero.
+struct ComplexSeqAccess<'de> {
+    arr: &'de NumericArray,
+    id: usize,
+}
+
+impl<'de> SeqAccess<'de> for ComplexSeqAccess<'de> {
+    type Error = MatrwError;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: serde::de::DeserializeSeed<'de>,
+    {
+        self.id += 1;
+        let value = match (self.id, &self.arr.value_cmp) {
+            (1, _) => &self.arr.value,
+            (2, Some(value_cmp)) => value_cmp,
+            (2, None) => return seed.deserialize(0u8.into_deserializer()).map(Some),
+            _ => return Ok(None),
+        };
+
+        seed.deserialize(ElementDeserializer { value, index: 0 })
+            .map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(2 - self.id.min(2))
+    }
+}
+
+fn visit_element<'de, V>(value: &MatlabType, i: usize, visitor: V) -> Result<V::Value, MatrwError>
+where
+    V: serde::de::Visitor<'de>,
+{
+    match value {
+        MatlabType::U8(v) => visitor.visit_u8(v[i]),
+        MatlabType::I8(v) => visitor.visit_i8(v[i]),
+        MatlabType::U16(v) => visitor.visit_u16(v[i]),
+        MatlabType::I16(v) => visitor.visit_i16(v[i]),
+        MatlabType::U32(v) => visitor.visit_u32(v[i]),
+        MatlabType::I32(v) => visitor.visit_i32(v[i]),
+        MatlabType::U64(v) => visitor.visit_u64(v[i]),
+        MatlabType::I64(v) => visitor.visit_i64(v[i]),
+        MatlabType::F32(v) => visitor.visit_f32(v[i]),
+        MatlabType::F64(v) => visitor.visit_f64(v[i]),
+        MatlabType::UTF8(v) | MatlabType::UTF16(v) => visitor.visit_char(v[i]),
+        MatlabType::BOOL(v) => visitor.visit_bool(v[i]),
+    }
+}
+
+/// Provides the fields of a [`crate::SparseMatrix`] from a [`SparseArray`].
+struct SparseMapAccess<'de> {
+    sparse: &'de SparseArray,
+    id: usize,
+}
+
+impl<'de> MapAccess<'de> for SparseMapAccess<'de> {
+    type Error = MatrwError;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: serde::de::DeserializeSeed<'de>,
+    {
+        if self.id < SPARSE_MATRIX_FIELDS.len() {
+            let key = SPARSE_MATRIX_FIELDS[self.id];
+            self.id += 1;
+            seed.deserialize(key.into_deserializer()).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: serde::de::DeserializeSeed<'de>,
+    {
+        let sparse = self.sparse;
+
+        match SPARSE_MATRIX_FIELDS[self.id - 1] {
+            "dim" => seed.deserialize(SeqDeserializer::new(sparse.dim.iter().copied())),
+            "ir" => seed.deserialize(SeqDeserializer::new(sparse.ir.iter().copied())),
+            "jc" => seed.deserialize(SeqDeserializer::new(sparse.jc.iter().copied())),
+            _ => {
+                if sparse.is_complex() {
+                    return Err(MatrwError::SerdeError(
+                        "Complex sparse variables are not supported".to_string(),
+                    ));
+                }
+                match &sparse.value {
+                    MatlabType::F64(v) => seed.deserialize(SeqDeserializer::new(v.iter().copied())),
+                    MatlabType::BOOL(v) => seed.deserialize(SeqDeserializer::new(v.iter().copied())),
+                    _ => Err(MatrwError::SerdeError("Unknown sparse type".to_string())),
+                }
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    #[cfg(feature = "deflate")]
     use crate::load_matfile_from_u8;
     use serde::Deserialize;
 
@@ -530,6 +1141,7 @@ mod tests {
     /// `>> k = 'x';`
     /// `>> save('example.mat');`
     ///
+    #[cfg(feature = "deflate")]
     const MATFILE7_NUMERIC_VARS: [u8; 631] = [
         0x4d, 0x41, 0x54, 0x4c, 0x41, 0x42, 0x20, 0x35, 0x2e, 0x30, 0x20, 0x4d, 0x41, 0x54, 0x2d, 0x66, 0x69,
         0x6c, 0x65, 0x2c, 0x20, 0x50, 0x6c, 0x61, 0x74, 0x66, 0x6f, 0x72, 0x6d, 0x3a, 0x20, 0x47, 0x4c, 0x4e,
@@ -571,6 +1183,7 @@ mod tests {
         0x01, 0x56,
     ];
 
+    #[cfg(feature = "deflate")]
     #[test]
     fn test_numeric_vars_vec() {
         #[allow(dead_code)]
@@ -590,13 +1203,14 @@ mod tests {
         }
 
         let matfile = load_matfile_from_u8(&MATFILE7_NUMERIC_VARS).unwrap();
-        let deserializer = MatFileDeserializer { matfile: &matfile };
+        let deserializer = MatFileDeserializer::new(&matfile);
 
         let s = Example::deserialize(deserializer);
 
         println!("{:#?}", s);
     }
 
+    #[cfg(feature = "deflate")]
     #[test]
     fn test_numeric_vars_scalar() {
         #[allow(dead_code)]
@@ -616,7 +1230,7 @@ mod tests {
         }
 
         let matfile = load_matfile_from_u8(&MATFILE7_NUMERIC_VARS).unwrap();
-        let deserializer = MatFileDeserializer { matfile: &matfile };
+        let deserializer = MatFileDeserializer::new(&matfile);
         println!("{:#?}", matfile);
 
         let s = Example::deserialize(deserializer);
@@ -630,6 +1244,7 @@ mod tests {
     /// `>> s.b = pi;`
     /// `>> save('example.mat', 's');`
     ///
+    #[cfg(feature = "deflate")]
     const MATFILE7_STRUCT: [u8; 213] = [
         0x4d, 0x41, 0x54, 0x4c, 0x41, 0x42, 0x20, 0x35, 0x2e, 0x30, 0x20, 0x4d, 0x41, 0x54, 0x2d, 0x66, 0x69,
         0x6c, 0x65, 0x2c, 0x20, 0x50, 0x6c, 0x61, 0x74, 0x66, 0x6f, 0x72, 0x6d, 0x3a, 0x20, 0x47, 0x4c, 0x4e,
@@ -646,6 +1261,7 @@ mod tests {
         0x2b, 0x72, 0x3a, 0x00, 0x00, 0xad, 0xe1, 0x05, 0x7d,
     ];
 
+    #[cfg(feature = "deflate")]
     #[test]
     fn test_struct() {
         #[allow(dead_code)]
@@ -662,10 +1278,326 @@ mod tests {
         }
 
         let matfile = load_matfile_from_u8(&MATFILE7_STRUCT).unwrap();
-        let deserializer = MatFileDeserializer { matfile: &matfile };
+        let deserializer = MatFileDeserializer::new(&matfile);
 
         let s = Example::deserialize(deserializer);
 
         println!("{:#?}", s);
     }
+
+    #[test]
+    fn test_sparse() {
+        use crate::{SparseMatrix, matfile, matvar, to_matfile};
+        use serde::Serialize;
+
+        #[derive(Deserialize, Serialize, Debug, PartialEq)]
+        struct S {
+            b: SparseMatrix<bool>,
+        }
+
+        #[derive(Deserialize, Serialize, Debug, PartialEq)]
+        struct Example {
+            a: SparseMatrix<f64>,
+            s: S,
+        }
+
+        let matfile = matfile!(
+            a: matvar!([[1., 0.], [0., 0.], [0., 4.]]).to_sparse().unwrap(),
+            s: matvar!({
+                b: matvar!([[false, true]]).to_sparse().unwrap(),
+            }),
+        );
+
+        let e: Example = from_matfile(&matfile).unwrap();
+
+        assert_eq!(e.a.nrows, 3);
+        assert_eq!(e.a.ncols, 2);
+        assert_eq!(e.a.ir, vec![0, 2]);
+        assert_eq!(e.a.jc, vec![0, 1, 2]);
+        assert_eq!(e.a.value, vec![1., 4.]);
+        assert_eq!(e.s.b.ir, vec![0]);
+        assert_eq!(e.s.b.jc, vec![0, 0, 1]);
+
+        // Round trip through the serializer
+        let matfile = to_matfile(&e).unwrap();
+        let e2: Example = from_matfile(&matfile).unwrap();
+        assert_eq!(e, e2);
+
+        // Wrong value type
+        #[derive(Deserialize, Debug)]
+        #[allow(dead_code)]
+        struct Wrong {
+            a: SparseMatrix<bool>,
+        }
+        assert!(from_matfile::<Wrong>(&matfile).is_err());
+    }
+
+    #[test]
+    fn test_checked_flatten() {
+        use crate::{matfile, matvar};
+
+        #[allow(dead_code)]
+        #[derive(Deserialize, Debug)]
+        struct S {
+            m: Vec<f64>,
+        }
+
+        #[allow(dead_code)]
+        #[derive(Deserialize, Debug)]
+        struct Example {
+            v: Vec<f64>,
+            s: S,
+        }
+
+        let matfile = matfile!(
+            v: matvar!([[1.0], [2.0], [3.0]]),
+            s: matvar!({
+                m: [[1.0, 2.0], [3.0, 4.0]],
+            }),
+        );
+
+        // Unchecked deserialization flattens silently
+        assert!(from_matfile::<Example>(&matfile).is_ok());
+
+        let err = from_matfile_checked::<Example>(&matfile, &ShapeHints::new()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Serde error Variable 's.m' of size 2x2 would be flattened into a sequence"
+        );
+
+        let hints = ShapeHints::new().allow_flatten("s.m");
+        let e: Example = from_matfile_checked(&matfile, &hints).unwrap();
+        assert_eq!(e.s.m, vec![1.0, 3.0, 2.0, 4.0]);
+    }
+
+    #[test]
+    fn test_nested_vec() {
+        use crate::{matfile, matvar, to_matfile};
+        use serde::Serialize;
+
+        #[derive(Deserialize, Serialize, Debug, PartialEq)]
+        struct Example {
+            m: Vec<Vec<f64>>,
+            c: Vec<Vec<u8>>,
+            v: Vec<i16>,
+        }
+
+        let matfile = matfile!(
+            m: matvar!([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]),
+            c: MatVariable::NumericArray(NumericArray::new(vec![2, 1], MatlabType::U8(vec![1, 2]), None).unwrap()),
+            v: matvar!([1i16, 2i16]),
+        );
+
+        let e: Example = from_matfile(&matfile).unwrap();
+        assert_eq!(e.m, vec![vec![1.0, 2.0, 3.0], vec![4.0, 5.0, 6.0]]);
+        assert_eq!(e.c, vec![vec![1], vec![2]]);
+        assert_eq!(e.v, vec![1, 2]);
+
+        // Rows are stored in column-major order again
+        let matfile = to_matfile(&e).unwrap();
+        assert_eq!(matfile["m"].dim(), vec![2, 3]);
+        assert_eq!(
+            matfile["m"].to_vec_f64().unwrap(),
+            vec![1.0, 4.0, 2.0, 5.0, 3.0, 6.0]
+        );
+        assert_eq!(matfile["c"].dim(), vec![2, 1]);
+        assert_eq!(from_matfile::<Example>(&matfile).unwrap(), e);
+
+        // Rows without elements
+        let empty = |dim: Vec<usize>| {
+            MatVariable::NumericArray(NumericArray::new(dim, MatlabType::F64(vec![]), None).unwrap())
+        };
+        let matfile = matfile!(m: empty(vec![3, 0]), c: empty(vec![0, 2]), v: empty(vec![2, 0]));
+        let e: Example = from_matfile(&matfile).unwrap();
+        assert_eq!(e.m, vec![Vec::<f64>::new(); 3]);
+        assert!(e.c.is_empty() && e.v.is_empty());
+        assert_eq!(to_matfile(&e).unwrap()["m"].dim(), vec![3, 0]);
+
+        // Rows of different length
+        let ragged = Example {
+            m: vec![vec![1.0], vec![1.0, 2.0]],
+            c: vec![],
+            v: vec![],
+        };
+        assert!(to_matfile(&ragged).is_err());
+    }
+
+    #[cfg(feature = "num-complex")]
+    #[test]
+    fn test_complex() {
+        use crate::{Complex32, Complex64, matfile, matvar, to_matfile};
+        use serde::Serialize;
+
+        #[derive(Deserialize, Serialize, Debug, PartialEq)]
+        struct Example {
+            gain: Complex64,
+            pole: Complex32,
+            real: Complex64,
+        }
+
+        let matfile = matfile!(
+            gain: MatVariable::NumericArray(
+                NumericArray::new(vec![1, 1], MatlabType::F64(vec![1.0]), Some(MatlabType::F64(vec![-2.0]))).unwrap()
+            ),
+            pole: MatVariable::NumericArray(
+                NumericArray::new(vec![1, 1], MatlabType::F32(vec![0.5]), Some(MatlabType::F32(vec![0.25]))).unwrap()
+            ),
+            real: matvar!(3.0),
+        );
+
+        let e: Example = from_matfile(&matfile).unwrap();
+        assert_eq!(e.gain, Complex64::new(1.0, -2.0));
+        assert_eq!(e.pole, Complex32::new(0.5, 0.25));
+        assert_eq!(e.real, Complex64::new(3.0, 0.0));
+
+        let matfile = to_matfile(&e).unwrap();
+        assert_eq!(matfile["gain"].is_complex(), Some(true));
+        assert_eq!(matfile["gain"].dim(), vec![1, 1]);
+        assert_eq!(from_matfile::<Example>(&matfile).unwrap(), e);
+
+        #[derive(Deserialize, Debug)]
+        #[allow(dead_code)]
+        struct Wrong {
+            v: Complex64,
+        }
+        let matfile = matfile!(v: matvar!([1.0, 2.0]));
+        assert!(from_matfile::<Wrong>(&matfile).is_err());
+    }
+
+    #[test]
+    fn test_vec_string() {
+        use crate::{matfile, matvar, to_matfile};
+        use serde::Serialize;
+
+        #[derive(Deserialize, Serialize, Debug, PartialEq)]
+        struct Example {
+            cellstr: Vec<String>,
+            charmat: Vec<String>,
+        }
+
+        // Matlab: charmat = ['ab '; 'cde'];
+        let charmat =
+            NumericArray::new(vec![2, 3], MatlabType::UTF8("acbd e".chars().collect()), None).unwrap();
+        let matfile = matfile!(
+            cellstr: matvar!(["x", "yz", ""]),
+            charmat: MatVariable::NumericArray(charmat),
+        );
+
+        let e: Example = from_matfile(&matfile).unwrap();
+        assert_eq!(e.cellstr, vec!["x", "yz", ""]);
+        assert_eq!(e.charmat, vec!["ab", "cde"]);
+
+        // Serialized as cell arrays
+        let matfile = to_matfile(&e).unwrap();
+        let MatVariable::CellArray(cell) = &matfile["charmat"] else {
+            panic!("Expected cell array");
+        };
+        assert_eq!(cell.dim, vec![1, 2]);
+        assert_eq!(from_matfile::<Example>(&matfile).unwrap(), e);
+    }
+
+    #[test]
+    fn test_flatten() {
+        use crate::{SparseMatrix, matfile, matvar, to_matfile};
+        use serde::Serialize;
+
+        #[derive(Deserialize, Serialize, Debug, PartialEq)]
+        struct Signals {
+            t: Vec<f64>,
+            m: Vec<Vec<f64>>,
+            name: String,
+            names: Vec<String>,
+            n: u8,
+            flag: bool,
+            sp: SparseMatrix<f64>,
+        }
+
+        #[derive(Deserialize, Serialize, Debug, PartialEq)]
+        struct Inner {
+            b: f64,
+        }
+
+        #[derive(Deserialize, Serialize, Debug, PartialEq)]
+        struct Outer {
+            a: f64,
+            #[serde(flatten)]
+            inner: Inner,
+        }
+
+        #[derive(Deserialize, Serialize, Debug, PartialEq)]
+        struct Example {
+            version: u8,
+            #[serde(flatten)]
+            signals: Signals,
+            s: Outer,
+        }
+
+        let matfile = matfile!(
+            version: matvar!(2u8),
+            t: matvar!([0.0, 0.1, 0.2]),
+            m: matvar!([[1.0, 2.0], [3.0, 4.0]]),
+            name: matvar!("abc"),
+            names: matvar!(["x", "yz"]),
+            n: matvar!(7u8),
+            flag: matvar!(true),
+            sp: matvar!([[0.0, 1.0]]).to_sparse().unwrap(),
+            s: matvar!({ a: 1.0, b: 2.0 }),
+        );
+
+        let e: Example = from_matfile(&matfile).unwrap();
+        assert_eq!(e.version, 2);
+        assert_eq!(e.signals.t, vec![0.0, 0.1, 0.2]);
+        assert_eq!(e.signals.m, vec![vec![1.0, 2.0], vec![3.0, 4.0]]);
+        assert_eq!(e.signals.name, "abc");
+        assert_eq!(e.signals.names, vec!["x", "yz"]);
+        assert_eq!(e.signals.n, 7);
+        assert!(e.signals.flag);
+        assert_eq!(e.signals.sp.ir, vec![0]);
+        assert_eq!(
+            e.s,
+            Outer {
+                a: 1.0,
+                inner: Inner { b: 2.0 }
+            }
+        );
+
+        // Flattened fields are serialized as top-level variables and sibling fields
+        let matfile = to_matfile(&e).unwrap();
+        assert!(matfile.contains("t"));
+        assert!(!matfile.contains("signals"));
+        assert_eq!(matfile["s"].fieldnames().unwrap(), vec!["a", "b"]);
+        assert_eq!(from_matfile::<Example>(&matfile).unwrap(), e);
+    }
+
+    #[test]
+    fn test_get_typed() {
+        use crate::{matfile, matvar};
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Point {
+            x: f64,
+            y: f64,
+        }
+
+        let matfile = matfile!(
+            p: matvar!({ x: 1.0, y: 2.0 }),
+            m: matvar!([[1.0, 2.0], [3.0, 4.0]]),
+            c: matvar!(["a", "bc"]),
+        );
+
+        assert_eq!(matfile.get_typed::<Point>("p").unwrap(), Point { x: 1.0, y: 2.0 });
+        assert_eq!(
+            matfile.get_typed::<Vec<Vec<f64>>>("m").unwrap(),
+            vec![vec![1.0, 2.0], vec![3.0, 4.0]]
+        );
+        assert_eq!(matfile.get_typed::<Vec<String>>("c").unwrap(), vec!["a", "bc"]);
+
+        let err = matfile.get_typed::<Point>("q").unwrap_err();
+        assert_eq!(err.to_string(), "Variable 'q' does not exist");
+        let err = matfile.get_typed::<Point>("m").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Serde error Variable 'm' can not be deserialized into a struct"
+        );
+    }
 }
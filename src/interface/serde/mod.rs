@@ -2,3 +2,12 @@
 
 pub mod de;
 pub mod ser;
+pub mod sparse;
+#[cfg(any(feature = "toml", feature = "yaml"))]
+pub(crate) mod text;
+#[cfg(feature = "toml")]
+pub mod toml;
+pub(crate) mod trace;
+pub mod value;
+#[cfg(feature = "yaml")]
+pub mod yaml;
@@ -3,14 +3,19 @@
 use crate::MatlabType;
 use crate::interface::error::MatrwError;
 use crate::interface::matfile::MatFile;
+use crate::interface::serde::sparse::SPARSE_MATRIX_TOKEN;
+use crate::interface::types::cell_array::CellArray;
 use crate::interface::types::numeric_array::NumericArray;
+use crate::interface::types::sparse_array::SparseArray;
 use crate::interface::types::structure::Structure;
 use crate::interface::variable::MatVariable;
 use indexmap::IndexMap;
 use serde::ser::Impossible;
 use serde::ser::Serialize;
+use serde::ser::SerializeMap;
 use serde::ser::SerializeSeq;
 use serde::ser::SerializeStruct;
+use serde::ser::SerializeTuple;
 use serde::ser::Serializer;
 
 /// Serialize [`MatFile`] from types which implement [`serde::Serialize`]
@@ -19,9 +24,15 @@ use serde::ser::Serializer;
 /// - [x] [`MatVariable::NumericArray`] from scalar for all supported numeric types and `char`
 /// - [x] [`MatVariable::NumericArray`] from `Vec<_>` for all supported numeric types and `char`
 /// - [x] [`MatVariable::NumericArray`] from `String` for `char` data
-/// - [x] [`MatVariable::Structure`] from `struct`
+/// - [x] [`MatVariable::CellArray`] of `char` rows from `Vec<String>`
+/// - [x] [`MatVariable::NumericArray`] from `bool` and `Vec<bool>`
+/// - [x] [`MatVariable::NumericArray`] from `Vec<Vec<_>>`, one `Vec` per row of a 2D array
+/// - [x] complex [`MatVariable::NumericArray`] scalar from `num_complex::Complex<f64>` and
+///   `Complex<f32>` (feature `num-complex`), or any `(re, im)` tuple of floats
+/// - [x] [`MatVariable::Structure`] from `struct`, including `#[serde(flatten)]` fields
+/// - [x] [`MatVariable::SparseArray`] from [`crate::SparseMatrix`]
 /// - [ ] [`MatVariable::StructureArray`]
-/// - [ ] [`MatVariable::CellArray`]
+/// - [ ] [`MatVariable::CellArray`] of other types
 /// ```
 /// use matrw::{matfile, matvar, MatFile, to_matfile};
 /// use serde::Serialize;
@@ -117,7 +128,7 @@ impl Serializer for MatFileSerializer {
     type SerializeTuple = Impossible<Self::Ok, Self::Error>;
     type SerializeTupleStruct = Impossible<Self::Ok, Self::Error>;
     type SerializeTupleVariant = Impossible<Self::Ok, Self::Error>;
-    type SerializeMap = Impossible<Self::Ok, Self::Error>;
+    type SerializeMap = MatFileMapSerializer;
     type SerializeStruct = Self;
     type SerializeStructVariant = Impossible<Self::Ok, Self::Error>;
 
@@ -286,7 +297,10 @@ impl Serializer for MatFileSerializer {
     }
 
     fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
-        todo!()
+        Ok(MatFileMapSerializer {
+            matfile: self.matfile,
+            key: None,
+        })
     }
 
     fn serialize_struct(
@@ -319,15 +333,105 @@ impl SerializeStruct for MatFileSerializer {
         let serializer = MatVariableSerializer {};
 
         let matvar = value.serialize(serializer)?;
-        self.matfile.insert(key, matvar);
+        insert_variable(&mut self.matfile, key, matvar)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(self.matfile)
+    }
+}
+
+/// Insert a serialized variable, failing with [`MatrwError::SerdeError`] for invalid names.
+fn insert_variable(matfile: &mut MatFile, name: &str, matvar: MatVariable) -> Result<(), MatrwError> {
+    matfile
+        .try_insert(name, matvar)
+        .map_err(|err| MatrwError::SerdeError(err.to_string()))
+}
+
+/// Serializes maps with string keys, e.g. structs containing `#[serde(flatten)]` fields, into a
+/// [`MatFile`].
+pub struct MatFileMapSerializer {
+    matfile: MatFile,
+    key: Option<String>,
+}
+
+impl SerializeMap for MatFileMapSerializer {
+    type Ok = MatFile;
+    type Error = MatrwError;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + serde::Serialize,
+    {
+        self.key = Some(key_to_string(key)?);
         Ok(())
     }
 
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + serde::Serialize,
+    {
+        let key = self
+            .key
+            .take()
+            .expect("serialize_value called before serialize_key");
+        let matvar = value.serialize(MatVariableSerializer {})?;
+        insert_variable(&mut self.matfile, &key, matvar)
+    }
+
     fn end(self) -> Result<Self::Ok, Self::Error> {
         Ok(self.matfile)
     }
 }
 
+/// Serializes maps with string keys into a [`MatVariable::Structure`].
+struct MatVariableMapSerializer {
+    map: IndexMap<String, MatVariable>,
+    key: Option<String>,
+}
+
+impl SerializeMap for MatVariableMapSerializer {
+    type Ok = MatVariable;
+    type Error = MatrwError;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + serde::Serialize,
+    {
+        self.key = Some(key_to_string(key)?);
+        Ok(())
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + serde::Serialize,
+    {
+        let key = self
+            .key
+            .take()
+            .expect("serialize_value called before serialize_key");
+        let matvar = value.serialize(MatVariableSerializer {})?;
+        self.map.insert(key, matvar);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(MatVariable::Structure(Structure { value: self.map }))
+    }
+}
+
+/// Map keys must be strings, which are used as variable or field names.
+fn key_to_string<T>(key: &T) -> Result<String, MatrwError>
+where
+    T: ?Sized + serde::Serialize,
+{
+    key.serialize(MatVariableSerializer {})
+        .ok()
+        .filter(|k| matches!(k, MatVariable::NumericArray(arr) if matches!(arr.value, MatlabType::UTF8(_))))
+        .and_then(|k| k.to_matlab_string())
+        .ok_or_else(|| MatrwError::SerdeError("Map keys must be strings".to_string()))
+}
+
 struct MatVariableSerializer {}
 
 impl Serializer for MatVariableSerializer {
@@ -335,15 +439,19 @@ impl Serializer for MatVariableSerializer {
     type Error = MatrwError;
 
     type SerializeSeq = MatVariableSeqSerializer;
-    type SerializeTuple = Impossible<Self::Ok, Self::Error>;
+    type SerializeTuple = ComplexSerializer;
     type SerializeTupleStruct = Impossible<Self::Ok, Self::Error>;
     type SerializeTupleVariant = Impossible<Self::Ok, Self::Error>;
-    type SerializeMap = Impossible<Self::Ok, Self::Error>;
+    type SerializeMap = MatVariableMapSerializer;
     type SerializeStruct = MatVariableStructSerializer;
     type SerializeStructVariant = Impossible<Self::Ok, Self::Error>;
 
-    fn serialize_bool(self, _v: bool) -> Result<Self::Ok, Self::Error> {
-        todo!()
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
+        Ok(MatVariable::NumericArray(NumericArray::new(
+            vec![1, 1],
+            MatlabType::from(vec![v]),
+            None,
+        )?))
     }
 
     fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
@@ -509,8 +617,13 @@ impl Serializer for MatVariableSerializer {
         Ok(MatVariableSeqSerializer::new())
     }
 
-    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
-        todo!()
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        if len != 2 {
+            return Err(MatrwError::SerdeError(
+                "Tuples are only supported as complex numbers (re, im)".to_string(),
+            ));
+        }
+        Ok(ComplexSerializer { parts: Vec::new() })
     }
 
     fn serialize_tuple_struct(
@@ -532,15 +645,17 @@ impl Serializer for MatVariableSerializer {
     }
 
     fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
-        todo!()
+        Ok(MatVariableMapSerializer {
+            map: IndexMap::new(),
+            key: None,
+        })
     }
 
-    fn serialize_struct(
-        self,
-        _name: &'static str,
-        _len: usize,
-    ) -> Result<Self::SerializeStruct, Self::Error> {
-        Ok(MatVariableStructSerializer { map: IndexMap::new() })
+    fn serialize_struct(self, name: &'static str, _len: usize) -> Result<Self::SerializeStruct, Self::Error> {
+        Ok(MatVariableStructSerializer {
+            map: IndexMap::new(),
+            sparse: name == SPARSE_MATRIX_TOKEN,
+        })
     }
 
     fn serialize_struct_variant(
@@ -554,6 +669,46 @@ impl Serializer for MatVariableSerializer {
     }
 }
 
+/// Serializes a `(re, im)` tuple, e.g. from `num_complex::Complex`, into a complex scalar.
+struct ComplexSerializer {
+    parts: Vec<MatVariableSeqVariants>,
+}
+
+impl SerializeTuple for ComplexSerializer {
+    type Ok = MatVariable;
+    type Error = MatrwError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + serde::Serialize,
+    {
+        self.parts.push(value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        let (re, im) = match self.parts[..] {
+            [MatVariableSeqVariants::F64(re), MatVariableSeqVariants::F64(im)] => {
+                (MatlabType::from(vec![re]), MatlabType::from(vec![im]))
+            }
+            [MatVariableSeqVariants::F32(re), MatVariableSeqVariants::F32(im)] => {
+                (MatlabType::from(vec![re]), MatlabType::from(vec![im]))
+            }
+            _ => {
+                return Err(MatrwError::SerdeError(
+                    "Complex numbers must consist of two f64 or two f32 values".to_string(),
+                ));
+            }
+        };
+
+        Ok(MatVariable::NumericArray(NumericArray::new(
+            vec![1, 1],
+            re,
+            Some(im),
+        )?))
+    }
+}
+
 struct MatVariableSeqSerializer {
     ty: MatVariableSeqVariants,
     vec_u8: Vec<u8>,
@@ -567,6 +722,11 @@ struct MatVariableSeqSerializer {
     vec_f32: Vec<f32>,
     vec_f64: Vec<f64>,
     vec_char: Vec<char>,
+    vec_bool: Vec<bool>,
+    /// Rows of a nested sequence like `Vec<Vec<T>>`
+    rows: Vec<MatVariable>,
+    /// Elements of a sequence of strings, stored as cell array
+    strings: Vec<MatVariable>,
 }
 
 impl MatVariableSeqSerializer {
@@ -584,6 +744,9 @@ impl MatVariableSeqSerializer {
             vec_f32: Vec::new(),
             vec_f64: Vec::new(),
             vec_char: Vec::new(),
+            vec_bool: Vec::new(),
+            rows: Vec::new(),
+            strings: Vec::new(),
         }
     }
 }
@@ -613,6 +776,9 @@ impl SerializeSeq for MatVariableSeqSerializer {
             MatVariableSeqVariants::F32(v) => self.vec_f32.push(v),
             MatVariableSeqVariants::F64(v) => self.vec_f64.push(v),
             MatVariableSeqVariants::Char(v) => self.vec_char.push(v),
+            MatVariableSeqVariants::Bool(v) => self.vec_bool.push(v),
+            MatVariableSeqVariants::Row(ref v) => self.rows.push(v.clone()),
+            MatVariableSeqVariants::Str(ref v) => self.strings.push(v.clone()),
         }
 
         self.ty = val;
@@ -622,7 +788,12 @@ impl SerializeSeq for MatVariableSeqSerializer {
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
         match self.ty {
-            MatVariableSeqVariants::None => unimplemented!(),
+            // Without elements the type is unknown, fall back to an empty double array like MATLAB
+            MatVariableSeqVariants::None => Ok(MatVariable::NumericArray(NumericArray::new(
+                vec![0, 0],
+                MatlabType::from(Vec::<f64>::new()),
+                None,
+            )?)),
             MatVariableSeqVariants::U8(_) => Ok(MatVariable::NumericArray(NumericArray::new(
                 vec![1, self.vec_u8.len()],
                 MatlabType::from(self.vec_u8),
@@ -678,6 +849,16 @@ impl SerializeSeq for MatVariableSeqSerializer {
                 MatlabType::from(self.vec_char),
                 None,
             )?)),
+            MatVariableSeqVariants::Bool(_) => Ok(MatVariable::NumericArray(NumericArray::new(
+                vec![1, self.vec_bool.len()],
+                MatlabType::from(self.vec_bool),
+                None,
+            )?)),
+            MatVariableSeqVariants::Row(_) => rows_to_matrix(self.rows),
+            MatVariableSeqVariants::Str(_) => Ok(MatVariable::CellArray(CellArray::new(
+                vec![1, self.strings.len()],
+                self.strings,
+            )?)),
         }
     }
 }
@@ -695,6 +876,55 @@ enum MatVariableSeqVariants {
     F32(f32),
     F64(f64),
     Char(char),
+    Bool(bool),
+    Row(MatVariable),
+    Str(MatVariable),
+}
+
+/// Serializes a nested sequence into a [`MatVariableSeqVariants::Row`].
+struct RowSerializer(MatVariableSeqSerializer);
+
+impl SerializeSeq for RowSerializer {
+    type Ok = MatVariableSeqVariants;
+    type Error = MatrwError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + serde::Serialize,
+    {
+        self.0.serialize_element(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(MatVariableSeqVariants::Row(self.0.end()?))
+    }
+}
+
+/// Combine the rows of a nested sequence into a matrix, stored in column-major order.
+fn rows_to_matrix(rows: Vec<MatVariable>) -> Result<MatVariable, MatrwError> {
+    let n_rows = rows.len();
+
+    // Empty rows carry no type information, fall back to double like MATLAB
+    if rows.iter().all(|r| r.dim().is_empty()) {
+        return Ok(MatVariable::NumericArray(NumericArray::new(
+            vec![n_rows, 0],
+            MatlabType::from(Vec::<f64>::new()),
+            None,
+        )?));
+    }
+
+    let n_cols = rows[0].dim().numel();
+    if rows.iter().any(|r| r.dim().numel() != n_cols) {
+        return Err(MatrwError::SerdeError(
+            "All rows of a nested sequence must have the same length".to_string(),
+        ));
+    }
+
+    // Single-element rows are scalars, which are combined according to the given dimensions
+    Ok(MatVariable::NumericArray(NumericArray::from_nested_matvar(
+        vec![n_rows, 1],
+        rows,
+    )?))
 }
 
 struct ValueSerializer;
@@ -703,7 +933,7 @@ impl Serializer for ValueSerializer {
     type Ok = MatVariableSeqVariants;
     type Error = MatrwError;
 
-    type SerializeSeq = Impossible<Self::Ok, Self::Error>;
+    type SerializeSeq = RowSerializer;
     type SerializeTuple = Impossible<Self::Ok, Self::Error>;
     type SerializeTupleStruct = Impossible<Self::Ok, Self::Error>;
     type SerializeTupleVariant = Impossible<Self::Ok, Self::Error>;
@@ -711,8 +941,8 @@ impl Serializer for ValueSerializer {
     type SerializeStruct = Impossible<Self::Ok, Self::Error>;
     type SerializeStructVariant = Impossible<Self::Ok, Self::Error>;
 
-    fn serialize_bool(self, _v: bool) -> Result<Self::Ok, Self::Error> {
-        todo!()
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
+        Ok(MatVariableSeqVariants::Bool(v))
     }
 
     fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
@@ -759,8 +989,10 @@ impl Serializer for ValueSerializer {
         Ok(MatVariableSeqVariants::Char(v))
     }
 
-    fn serialize_str(self, _v: &str) -> Result<Self::Ok, Self::Error> {
-        todo!()
+    fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
+        Ok(MatVariableSeqVariants::Str(
+            MatVariableSerializer {}.serialize_str(v)?,
+        ))
     }
 
     fn serialize_bytes(self, _v: &[u8]) -> Result<Self::Ok, Self::Error> {
@@ -816,7 +1048,7 @@ impl Serializer for ValueSerializer {
     }
 
     fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
-        todo!()
+        Ok(RowSerializer(MatVariableSeqSerializer::new()))
     }
 
     fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
@@ -866,6 +1098,54 @@ impl Serializer for ValueSerializer {
 
 struct MatVariableStructSerializer {
     map: IndexMap<String, MatVariable>,
+    sparse: bool,
+}
+
+impl MatVariableStructSerializer {
+    /// Take a field of a serialized [`crate::SparseMatrix`] as index vector.
+    fn take_indices(&mut self, key: &str) -> Result<Vec<usize>, MatrwError> {
+        match self.map.shift_remove(key) {
+            Some(MatVariable::NumericArray(arr)) => match arr.value {
+                MatlabType::U64(v) => Ok(v.into_iter().map(|x| x as usize).collect()),
+                v if v.is_empty() => Ok(vec![]),
+                _ => Err(MatrwError::SerdeError(format!(
+                    "Sparse field {} of wrong type",
+                    key
+                ))),
+            },
+            _ => Err(MatrwError::SerdeError(format!("Sparse field {} missing", key))),
+        }
+    }
+
+    fn end_sparse(mut self) -> Result<MatVariable, MatrwError> {
+        let dim = self.take_indices("dim")?;
+        let ir = self.take_indices("ir")?;
+        let jc = self.take_indices("jc")?;
+        let logical = matches!(
+            self.map.shift_remove("logical"),
+            Some(MatVariable::NumericArray(NumericArray {
+                value: MatlabType::BOOL(ref v),
+                ..
+            })) if v.first() == Some(&true)
+        );
+        let value = match self.map.shift_remove("value") {
+            Some(MatVariable::NumericArray(arr)) if arr.value.is_empty() && logical => {
+                MatlabType::BOOL(vec![])
+            }
+            Some(MatVariable::NumericArray(arr)) => arr.value,
+            _ => return Err(MatrwError::SerdeError("Sparse field value missing".to_string())),
+        };
+
+        if dim.len() != 2 {
+            return Err(MatrwError::SerdeError(
+                "Sparse field dim of wrong size".to_string(),
+            ));
+        }
+
+        Ok(MatVariable::SparseArray(SparseArray::new(
+            dim[0], dim[1], ir, jc, value, None,
+        )?))
+    }
 }
 
 impl SerializeStruct for MatVariableStructSerializer {
@@ -885,6 +1165,10 @@ impl SerializeStruct for MatVariableStructSerializer {
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
+        if self.sparse {
+            return self.end_sparse();
+        }
+
         Ok(MatVariable::Structure(Structure { value: self.map }))
     }
 }
@@ -956,4 +1240,61 @@ mod tests {
 
         println!("{:#?}", matfile)
     }
+
+    #[test]
+    fn serde_serialize_invalid_names() {
+        #[derive(Serialize)]
+        struct Range {
+            start: f64,
+            end: f64,
+        }
+
+        let err = Range { start: 0.0, end: 1.0 }
+            .serialize(MatFileSerializer::new())
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Serde error Invalid variable name 'end', it is a MATLAB keyword"
+        );
+    }
+
+    #[test]
+    fn serde_serialize_sparse() {
+        use crate::SparseMatrix;
+
+        #[derive(Serialize, Debug)]
+        struct Example {
+            a: SparseMatrix<f64>,
+            b: SparseMatrix<bool>,
+            c: SparseMatrix<bool>,
+        }
+
+        let e = Example {
+            a: SparseMatrix::new(3, 2, vec![0, 2], vec![0, 1, 2], vec![42., 43.]).unwrap(),
+            b: SparseMatrix::new(2, 2, vec![1], vec![0, 0, 1], vec![true]).unwrap(),
+            c: SparseMatrix::new(2, 2, vec![], vec![0, 0, 0], vec![]).unwrap(),
+        };
+
+        let matfile = to_matfile(e).unwrap();
+
+        let MatVariable::SparseArray(a) = &matfile["a"] else {
+            panic!("Not a sparse array");
+        };
+        assert_eq!(a.dim, vec![3, 2]);
+        assert_eq!(a.ir, vec![0, 2]);
+        assert_eq!(a.jc, vec![0, 1, 2]);
+        assert_eq!(a.value, MatlabType::F64(vec![42., 43.]));
+
+        let MatVariable::SparseArray(b) = &matfile["b"] else {
+            panic!("Not a sparse array");
+        };
+        assert!(b.is_logical());
+        assert_eq!(b.ir, vec![1]);
+
+        let MatVariable::SparseArray(c) = &matfile["c"] else {
+            panic!("Not a sparse array");
+        };
+        assert!(c.is_logical());
+        assert_eq!(c.nnz(), 0);
+    }
 }
@@ -0,0 +1,140 @@
+//! Sparse matrices in the typed interface.
+
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+use crate::MatlabType;
+use crate::interface::error::MatrwError;
+use crate::interface::types::matlab_types::MatlabTypeMarkerSparse;
+
+/// Struct name used to recognize [`SparseMatrix`] in the serializer and deserializer.
+pub(crate) const SPARSE_MATRIX_TOKEN: &str = "$__matrw_private_SparseMatrix";
+
+/// Fields provided by the deserializer for a sparse variable
+pub(crate) const SPARSE_MATRIX_FIELDS: &[&str] = &["dim", "ir", "jc", "value"];
+
+/// Sparse matrix in compressed sparse column (CSC) format
+///
+/// Use this type for fields of structs serialized with [`crate::to_matfile`] to obtain a
+/// [`crate::MatVariable::SparseArray`], or deserialized with [`crate::from_matfile`] to read one.
+/// Like MATLAB sparse arrays, only `f64` and `bool` values are supported. Complex sparse arrays
+/// can not be deserialized into a `SparseMatrix`.
+///
+/// ```
+/// use matrw::{to_matfile, MatVariable, SparseMatrix};
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Example {
+///     s: SparseMatrix<f64>,
+/// }
+///
+/// // Matlab: s = sparse([1, 3], [1, 2], [42., 43.], 3, 2);
+/// let s = SparseMatrix::new(3, 2, vec![0, 2], vec![0, 1, 2], vec![42., 43.]).unwrap();
+/// let matfile = to_matfile(Example { s }).unwrap();
+///
+/// assert!(matches!(matfile["s"], MatVariable::SparseArray(_)));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct SparseMatrix<T> {
+    pub nrows: usize,
+    pub ncols: usize,
+    /// Row index of every stored element
+    pub ir: Vec<usize>,
+    /// Index into `ir` and `value` of the first stored element of every column, followed by the
+    /// number of stored elements
+    pub jc: Vec<usize>,
+    pub value: Vec<T>,
+}
+
+impl<T: MatlabTypeMarkerSparse> SparseMatrix<T> {
+    /// Constructs a new `SparseMatrix`, checking the consistency of the CSC representation.
+    pub fn new(
+        nrows: usize,
+        ncols: usize,
+        ir: Vec<usize>,
+        jc: Vec<usize>,
+        value: Vec<T>,
+    ) -> Result<Self, MatrwError> {
+        if jc.len() != ncols + 1 {
+            return Err(MatrwError::TypeConstruction(format!(
+                "Specified jc size {} does not match number of columns {} + 1.",
+                jc.len(),
+                ncols
+            )));
+        }
+        if ir.len() != value.len() || jc[ncols] != value.len() {
+            return Err(MatrwError::TypeConstruction(format!(
+                "Sizes of ir ({}), value ({}) and last jc entry ({}) do not match.",
+                ir.len(),
+                value.len(),
+                jc[ncols]
+            )));
+        }
+        if jc.windows(2).any(|w| w[0] > w[1]) {
+            return Err(MatrwError::TypeConstruction(
+                "Entries of jc must not decrease.".to_string(),
+            ));
+        }
+        if let Some(i) = ir.iter().find(|&&i| i >= nrows) {
+            return Err(MatrwError::TypeConstruction(format!(
+                "Row index {} out of bounds for {} rows.",
+                i, nrows
+            )));
+        }
+
+        Ok(Self {
+            nrows,
+            ncols,
+            ir,
+            jc,
+            value,
+        })
+    }
+}
+
+impl<T: MatlabTypeMarkerSparse + Serialize> Serialize for SparseMatrix<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        // Empty value vectors carry no type information, the logical flag restores it
+        let logical = matches!(MatlabType::from(Vec::<T>::new()), MatlabType::BOOL(_));
+
+        let mut s = serializer.serialize_struct(SPARSE_MATRIX_TOKEN, 5)?;
+        s.serialize_field("dim", &[self.nrows, self.ncols][..])?;
+        s.serialize_field("ir", &self.ir)?;
+        s.serialize_field("jc", &self.jc)?;
+        s.serialize_field("value", &self.value)?;
+        s.serialize_field("logical", &logical)?;
+        s.end()
+    }
+}
+
+/// Representation of [`SparseMatrix`] as provided by the deserializer.
+///
+/// The name must match [`SPARSE_MATRIX_TOKEN`].
+#[derive(serde::Deserialize)]
+#[serde(rename = "$__matrw_private_SparseMatrix")]
+struct SparseMatrixRepr<T> {
+    dim: Vec<usize>,
+    ir: Vec<usize>,
+    jc: Vec<usize>,
+    value: Vec<T>,
+}
+
+impl<'de, T: MatlabTypeMarkerSparse + Deserialize<'de>> Deserialize<'de> for SparseMatrix<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let repr = SparseMatrixRepr::<T>::deserialize(deserializer)?;
+
+        if repr.dim.len() != 2 {
+            return Err(serde::de::Error::custom("Sparse matrix must be two-dimensional"));
+        }
+
+        SparseMatrix::new(repr.dim[0], repr.dim[1], repr.ir, repr.jc, repr.value)
+            .map_err(serde::de::Error::custom)
+    }
+}
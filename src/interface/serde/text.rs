@@ -0,0 +1,408 @@
+//! Values of the text formats TOML and YAML, between [`MatVariable`] and their syntax.
+//!
+//! Variables convert to and from [`Text`] with their [`serde`] implementations in
+//! [`crate::interface::serde::value`], so all text formats map variables alike.
+
+use indexmap::IndexMap;
+use serde::de::value::{Error, MapDeserializer, SeqDeserializer};
+use serde::de::{Deserialize, Deserializer, IntoDeserializer, Visitor};
+use serde::ser::{self, Impossible, Serialize, Serializer};
+
+use crate::interface::error::MatrwError;
+use crate::interface::variable::MatVariable;
+
+/// Value of a text format
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Text {
+    Bool(bool),
+    Int(i128),
+    Float(f64),
+    Str(String),
+    Array(Vec<Text>),
+    Table(IndexMap<String, Text>),
+    /// Missing value, which only YAML has
+    Null,
+}
+
+impl Text {
+    /// Values of `var`
+    pub(crate) fn from_variable(var: &MatVariable) -> Result<Self, MatrwError> {
+        var.serialize(TextSerializer)
+            .map_err(|e| MatrwError::SerdeError(e.to_string()))
+    }
+
+    /// Variable of these values
+    pub(crate) fn into_variable(self) -> Result<MatVariable, MatrwError> {
+        MatVariable::deserialize(self).map_err(|e| MatrwError::SerdeError(e.to_string()))
+    }
+}
+
+/// Quoted string with escapes, which is a basic string in TOML and a double-quoted scalar in YAML
+pub(crate) fn quote(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            '\u{8}' => quoted.push_str("\\b"),
+            '\u{c}' => quoted.push_str("\\f"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04X}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Finite number with a fraction or exponent, e.g. `1.0` or `1.5e+300`, which both formats read
+/// as a float, YAML 1.1 parsers included
+pub(crate) fn finite_float(v: f64) -> String {
+    let text = format!("{v:?}");
+    match text.split_once('e') {
+        Some((mantissa, exponent)) => {
+            let mantissa = if mantissa.contains('.') {
+                mantissa.to_string()
+            } else {
+                format!("{mantissa}.0")
+            };
+            let exponent = if exponent.starts_with('-') {
+                exponent.to_string()
+            } else {
+                format!("+{exponent}")
+            };
+            format!("{mantissa}e{exponent}")
+        }
+        None => text,
+    }
+}
+
+/// Serializer of values into [`Text`]
+struct TextSerializer;
+
+impl Serializer for TextSerializer {
+    type Ok = Text;
+    type Error = Error;
+    type SerializeSeq = SeqBuilder;
+    type SerializeTuple = SeqBuilder;
+    type SerializeTupleStruct = SeqBuilder;
+    type SerializeTupleVariant = Impossible<Text, Error>;
+    type SerializeMap = TableBuilder;
+    type SerializeStruct = TableBuilder;
+    type SerializeStructVariant = Impossible<Text, Error>;
+
+    fn serialize_bool(self, v: bool) -> Result<Text, Error> {
+        Ok(Text::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Text, Error> {
+        Ok(Text::Int(v.into()))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Text, Error> {
+        Ok(Text::Int(v.into()))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Text, Error> {
+        Ok(Text::Int(v.into()))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Text, Error> {
+        Ok(Text::Int(v.into()))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Text, Error> {
+        Ok(Text::Int(v.into()))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Text, Error> {
+        Ok(Text::Int(v.into()))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Text, Error> {
+        Ok(Text::Int(v.into()))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Text, Error> {
+        Ok(Text::Int(v.into()))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Text, Error> {
+        // The shortest decimal of the single, `0.1` instead of `0.10000000149011612`
+        Ok(Text::Float(v.to_string().parse().unwrap_or(v.into())))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Text, Error> {
+        Ok(Text::Float(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Text, Error> {
+        Ok(Text::Str(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Text, Error> {
+        Ok(Text::Str(v.to_string()))
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<Text, Error> {
+        Err(ser::Error::custom("Bytes can't be written to text formats"))
+    }
+
+    fn serialize_none(self) -> Result<Text, Error> {
+        Ok(Text::Null)
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<Text, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Text, Error> {
+        Ok(Text::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Text, Error> {
+        Ok(Text::Null)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<Text, Error> {
+        Ok(Text::Str(variant.to_string()))
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Text, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Text, Error> {
+        let mut table = IndexMap::new();
+        table.insert(variant.to_string(), value.serialize(self)?);
+        Ok(Text::Table(table))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SeqBuilder, Error> {
+        Ok(SeqBuilder(Vec::with_capacity(len.unwrap_or(0))))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SeqBuilder, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<SeqBuilder, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Error> {
+        Err(ser::Error::custom(
+            "Tuple variants can't be written to text formats",
+        ))
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<TableBuilder, Error> {
+        Ok(TableBuilder {
+            table: IndexMap::with_capacity(len.unwrap_or(0)),
+            key: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<TableBuilder, Error> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Error> {
+        Err(ser::Error::custom(
+            "Struct variants can't be written to text formats",
+        ))
+    }
+}
+
+/// Elements of a sequence being serialized
+struct SeqBuilder(Vec<Text>);
+
+impl ser::SerializeSeq for SeqBuilder {
+    type Ok = Text;
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.0.push(value.serialize(TextSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Text, Error> {
+        Ok(Text::Array(self.0))
+    }
+}
+
+impl ser::SerializeTuple for SeqBuilder {
+    type Ok = Text;
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Text, Error> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SeqBuilder {
+    type Ok = Text;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Text, Error> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+/// Entries of a map being serialized, with the key of the next value
+struct TableBuilder {
+    table: IndexMap<String, Text>,
+    key: Option<String>,
+}
+
+impl ser::SerializeMap for TableBuilder {
+    type Ok = Text;
+    type Error = Error;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), Error> {
+        match key.serialize(TextSerializer)? {
+            Text::Str(key) => {
+                self.key = Some(key);
+                Ok(())
+            }
+            _ => Err(ser::Error::custom("Keys of text formats must be strings")),
+        }
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| <Error as ser::Error>::custom("Value without key"))?;
+        self.table.insert(key, value.serialize(TextSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Text, Error> {
+        Ok(Text::Table(self.table))
+    }
+}
+
+impl ser::SerializeStruct for TableBuilder {
+    type Ok = Text;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, key: &'static str, value: &T) -> Result<(), Error> {
+        self.table
+            .insert(key.to_string(), value.serialize(TextSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Text, Error> {
+        Ok(Text::Table(self.table))
+    }
+}
+
+impl<'de> Deserializer<'de> for Text {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Text::Bool(v) => visitor.visit_bool(v),
+            Text::Int(v) => match (i64::try_from(v), u64::try_from(v)) {
+                (Ok(v), _) => visitor.visit_i64(v),
+                (_, Ok(v)) => visitor.visit_u64(v),
+                _ => visitor.visit_f64(v as f64),
+            },
+            Text::Float(v) => visitor.visit_f64(v),
+            Text::Str(v) => visitor.visit_string(v),
+            Text::Array(v) => visitor.visit_seq(SeqDeserializer::new(v.into_iter())),
+            Text::Table(v) => visitor.visit_map(MapDeserializer::new(v.into_iter())),
+            Text::Null => visitor.visit_unit(),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf option
+        unit unit_struct newtype_struct seq tuple tuple_struct map struct enum identifier
+        ignored_any
+    }
+}
+
+impl IntoDeserializer<'_, Error> for Text {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matvar;
+
+    #[test]
+    fn variable_values() {
+        let var = matvar!({ gain: 2.5f32, n: 3u8, name: "a\"b", on: true });
+        let Text::Table(table) = Text::from_variable(&var).unwrap() else {
+            panic!("structures are tables")
+        };
+        assert_eq!(table["gain"], Text::Float(2.5));
+        assert_eq!(table["n"], Text::Int(3));
+        assert_eq!(table["name"], Text::Str("a\"b".to_string()));
+        assert_eq!(table["on"], Text::Bool(true));
+
+        // Numbers are read back as doubles
+        let back = Text::Table(table).into_variable().unwrap();
+        assert_eq!(back, matvar!({ gain: 2.5, n: 3.0, name: "a\"b", on: true }));
+        assert_eq!(Text::Null.into_variable().unwrap(), MatVariable::empty_value());
+    }
+
+    #[test]
+    fn single_precision_decimals() {
+        assert_eq!(Text::from_variable(&matvar!(0.1f32)).unwrap(), Text::Float(0.1));
+    }
+
+    #[test]
+    fn quoted_and_float_syntax() {
+        assert_eq!(quote("a\"\\\n\u{1}é"), r#""a\"\\\n\u0001é""#);
+        assert_eq!(finite_float(1.0), "1.0");
+        assert_eq!(finite_float(-0.25), "-0.25");
+        assert_eq!(finite_float(1e300), "1.0e+300");
+        assert_eq!(finite_float(1.5e-7), "1.5e-7");
+    }
+}
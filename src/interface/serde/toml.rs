@@ -0,0 +1,706 @@
+//! TOML documents of variables.
+//!
+//! [`to_toml_string`] writes a structure as a [TOML](https://toml.io) document and
+//! [`from_toml_str`] reads one back, so configuration-style MAT-files can be edited as text. Values
+//! map like with the [`serde`] implementation of [`MatVariable`], see
+//! [`crate::interface::serde::value`]: numbers are read as double scalars, arrays of numbers as
+//! vectors and matrices, and so on.
+//!
+//! Nested structures and structure arrays are written as `[table]` and `[[array]]` sections, as
+//! long as no other field follows them in their structure, and as inline tables otherwise, so the
+//! field order is kept. Dates and times are not supported.
+
+use indexmap::IndexMap;
+
+use crate::interface::error::MatrwError;
+use crate::interface::serde::text::{Text, finite_float, quote};
+use crate::interface::variable::MatVariable;
+
+///
+/// Write the structure `var` as a TOML document, see the
+/// [module documentation](crate::interface::serde::toml).
+///
+/// Returns [`MatrwError::SerdeError`], if `var` is no structure or holds values which can't be
+/// serialized, e.g. complex or sparse arrays, or integers beyond the range of `i64`.
+///
+/// Requires the feature `toml`.
+///
+/// Example
+/// ```
+/// use matrw::{from_toml_str, matvar, to_toml_string};
+///
+/// let config = matvar!({ name: "pump", gain: 2.5, limits: [0.0, 10.0], motor: { id: 7.0 } });
+///
+/// let text = to_toml_string(&config).unwrap();
+/// assert_eq!(text, "name = \"pump\"\ngain = 2.5\nlimits = [0.0, 10.0]\n\n[motor]\nid = 7.0\n");
+/// assert_eq!(from_toml_str(&text).unwrap(), config);
+/// ```
+///
+pub fn to_toml_string(var: &MatVariable) -> Result<String, MatrwError> {
+    let Text::Table(table) = Text::from_variable(var)? else {
+        return Err(MatrwError::SerdeError(
+            "Only structures can be written as TOML documents".to_string(),
+        ));
+    };
+
+    let mut out = String::new();
+    write_table(&mut out, &mut Vec::new(), &table)?;
+    Ok(out.trim_start_matches('\n').to_string())
+}
+
+///
+/// Read the TOML document `text` as a structure, see the
+/// [module documentation](crate::interface::serde::toml).
+///
+/// Returns [`MatrwError::SerdeError`] with the line of the error, if `text` is no valid TOML or
+/// holds dates or times.
+///
+/// Requires the feature `toml`.
+///
+pub fn from_toml_str(text: &str) -> Result<MatVariable, MatrwError> {
+    Parser::new(text).document()?.into_variable()
+}
+
+/// Write the fields of `table` at the dotted `path`, the header of which is written already
+fn write_table(
+    out: &mut String,
+    path: &mut Vec<String>,
+    table: &IndexMap<String, Text>,
+) -> Result<(), MatrwError> {
+    // Fields after the last plain value become sections, which can't be followed by plain values
+    let sections_from = table
+        .values()
+        .rposition(|value| !is_section(value))
+        .map_or(0, |i| i + 1);
+
+    for (key, value) in table.iter().take(sections_from) {
+        out.push_str(&format!("{} = {}\n", toml_key(key), inline(value)?));
+    }
+    for (key, value) in table.iter().skip(sections_from) {
+        path.push(toml_key(key));
+        match value {
+            Text::Table(table) => {
+                out.push_str(&format!("\n[{}]\n", path.join(".")));
+                write_table(out, path, table)?;
+            }
+            Text::Array(elements) => {
+                for element in elements {
+                    let Text::Table(table) = element else {
+                        unreachable!()
+                    };
+                    out.push_str(&format!("\n[[{}]]\n", path.join(".")));
+                    write_table(out, path, table)?;
+                }
+            }
+            _ => unreachable!(),
+        }
+        path.pop();
+    }
+    Ok(())
+}
+
+/// Whether `value` can be written as a section, i.e. is a table or a non-empty array of tables
+fn is_section(value: &Text) -> bool {
+    match value {
+        Text::Table(_) => true,
+        Text::Array(elements) => !elements.is_empty() && elements.iter().all(|e| matches!(e, Text::Table(_))),
+        _ => false,
+    }
+}
+
+/// Key, bare if possible and quoted otherwise
+fn toml_key(key: &str) -> String {
+    let bare = key
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if bare && !key.is_empty() {
+        key.to_string()
+    } else {
+        quote(key)
+    }
+}
+
+/// Value on a single line
+fn inline(value: &Text) -> Result<String, MatrwError> {
+    Ok(match value {
+        Text::Bool(v) => v.to_string(),
+        Text::Int(v) => match i64::try_from(*v) {
+            Ok(v) => v.to_string(),
+            Err(_) => {
+                return Err(MatrwError::SerdeError(format!(
+                    "{v} is out of the range of TOML integers"
+                )));
+            }
+        },
+        Text::Float(v) if v.is_nan() => "nan".to_string(),
+        Text::Float(v) if v.is_infinite() => if *v > 0.0 { "inf" } else { "-inf" }.to_string(),
+        Text::Float(v) => finite_float(*v),
+        Text::Str(v) => quote(v),
+        Text::Array(elements) => {
+            let elements: Result<Vec<String>, MatrwError> = elements.iter().map(inline).collect();
+            format!("[{}]", elements?.join(", "))
+        }
+        Text::Table(table) if table.is_empty() => "{}".to_string(),
+        Text::Table(table) => {
+            let fields: Result<Vec<String>, MatrwError> = table
+                .iter()
+                .map(|(key, value)| Ok(format!("{} = {}", toml_key(key), inline(value)?)))
+                .collect();
+            format!("{{ {} }}", fields?.join(", "))
+        }
+        Text::Null => return Err(MatrwError::SerdeError("TOML has no null values".to_string())),
+    })
+}
+
+/// Step from a table into one of its values, when resolving keys and headers
+enum Step {
+    /// Into the table of the key, created if missing
+    Table,
+    /// Into a new table appended to the array of tables of the key, created if missing
+    NewArrayElement,
+}
+
+/// Recursive descent parser of TOML documents
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn new(text: &str) -> Self {
+        Self {
+            chars: text.chars().collect(),
+            pos: 0,
+        }
+    }
+
+    fn error(&self, msg: &str) -> MatrwError {
+        let line = self.chars[..self.pos.min(self.chars.len())]
+            .iter()
+            .filter(|&&c| c == '\n')
+            .count();
+        MatrwError::SerdeError(format!("TOML line {}: {msg}", line + 1))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn starts_with(&self, s: &str) -> bool {
+        s.chars()
+            .enumerate()
+            .all(|(i, c)| self.chars.get(self.pos + i) == Some(&c))
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), MatrwError> {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{c}'")))
+        }
+    }
+
+    fn skip_spaces(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        if self.peek() == Some('#') {
+            while !matches!(self.peek(), None | Some('\n')) {
+                self.pos += 1;
+            }
+        }
+    }
+
+    /// Skip whitespace, newlines and comments
+    fn skip_blank(&mut self) {
+        loop {
+            self.skip_spaces();
+            self.skip_comment();
+            match self.peek() {
+                Some('\n') => self.pos += 1,
+                Some('\r') if self.chars.get(self.pos + 1) == Some(&'\n') => self.pos += 2,
+                _ => return,
+            }
+        }
+    }
+
+    /// End of a line, after optional whitespace and comment
+    fn end_of_line(&mut self) -> Result<(), MatrwError> {
+        self.skip_spaces();
+        self.skip_comment();
+        if self.starts_with("\r\n") {
+            self.pos += 2;
+        } else if self.peek() == Some('\n') {
+            self.pos += 1;
+        } else if self.peek().is_some() {
+            return Err(self.error("expected the end of the line"));
+        }
+        Ok(())
+    }
+
+    fn document(&mut self) -> Result<Text, MatrwError> {
+        let mut root = IndexMap::new();
+        // Path of the table of the last header
+        let mut current: Vec<String> = Vec::new();
+
+        loop {
+            self.skip_blank();
+            if self.peek().is_none() {
+                return Ok(Text::Table(root));
+            }
+
+            if self.starts_with("[[") {
+                self.pos += 2;
+                let path = self.key()?;
+                self.skip_spaces();
+                if !self.starts_with("]]") {
+                    return Err(self.error("expected ']]'"));
+                }
+                self.pos += 2;
+                self.descend(&mut root, &path, Step::NewArrayElement)?;
+                current = path;
+            } else if self.peek() == Some('[') {
+                self.pos += 1;
+                let path = self.key()?;
+                self.skip_spaces();
+                self.expect(']')?;
+                self.descend(&mut root, &path, Step::Table)?;
+                current = path;
+            } else {
+                let (path, value) = self.key_value()?;
+                let table = self.descend(&mut root, &current, Step::Table)?;
+                self.insert(table, &path, value)?;
+            }
+            self.end_of_line()?;
+        }
+    }
+
+    /// Table at `path` from `root`, where the last key is stepped into with `last`
+    fn descend<'t>(
+        &self,
+        root: &'t mut IndexMap<String, Text>,
+        path: &[String],
+        last: Step,
+    ) -> Result<&'t mut IndexMap<String, Text>, MatrwError> {
+        let mut table = root;
+        for (i, key) in path.iter().enumerate() {
+            let new_element = i + 1 == path.len() && matches!(last, Step::NewArrayElement);
+            let entry = table.entry(key.clone()).or_insert_with(|| {
+                if new_element {
+                    Text::Array(Vec::new())
+                } else {
+                    Text::Table(IndexMap::new())
+                }
+            });
+            table = match (entry, new_element) {
+                (Text::Array(elements), true) => {
+                    elements.push(Text::Table(IndexMap::new()));
+                    match elements.last_mut() {
+                        Some(Text::Table(table)) => table,
+                        _ => unreachable!(),
+                    }
+                }
+                (Text::Table(table), false) => table,
+                // Headers within an array of tables refer to its last table
+                (Text::Array(elements), false) => match elements.last_mut() {
+                    Some(Text::Table(table)) => table,
+                    _ => return Err(self.error(&format!("'{key}' is no table"))),
+                },
+                _ => return Err(self.error(&format!("'{key}' is defined already"))),
+            };
+        }
+        Ok(table)
+    }
+
+    /// Insert `value` at the dotted key `path` into `table`
+    fn insert(
+        &self,
+        table: &mut IndexMap<String, Text>,
+        path: &[String],
+        value: Text,
+    ) -> Result<(), MatrwError> {
+        let (last, parents) = path.split_last().expect("keys aren't empty");
+        let mut table = table;
+        for key in parents {
+            table = match table
+                .entry(key.clone())
+                .or_insert_with(|| Text::Table(IndexMap::new()))
+            {
+                Text::Table(table) => table,
+                _ => return Err(self.error(&format!("'{key}' is no table"))),
+            };
+        }
+        if table.contains_key(last) {
+            return Err(self.error(&format!("'{last}' is defined already")));
+        }
+        table.insert(last.clone(), value);
+        Ok(())
+    }
+
+    fn key_value(&mut self) -> Result<(Vec<String>, Text), MatrwError> {
+        let path = self.key()?;
+        self.skip_spaces();
+        self.expect('=')?;
+        self.skip_spaces();
+        Ok((path, self.value()?))
+    }
+
+    /// Dotted key of bare and quoted parts
+    fn key(&mut self) -> Result<Vec<String>, MatrwError> {
+        let mut path = Vec::new();
+        loop {
+            self.skip_spaces();
+            let part = match self.peek() {
+                Some('"') => self.basic_string()?,
+                Some('\'') => self.literal_string()?,
+                _ => {
+                    let start = self.pos;
+                    while self
+                        .peek()
+                        .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+                    {
+                        self.pos += 1;
+                    }
+                    if start == self.pos {
+                        return Err(self.error("expected a key"));
+                    }
+                    self.chars[start..self.pos].iter().collect()
+                }
+            };
+            path.push(part);
+            self.skip_spaces();
+            if self.peek() != Some('.') {
+                return Ok(path);
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn value(&mut self) -> Result<Text, MatrwError> {
+        match self.peek() {
+            Some('"') if self.starts_with("\"\"\"") => self.multiline_string('"').map(Text::Str),
+            Some('\'') if self.starts_with("'''") => self.multiline_string('\'').map(Text::Str),
+            Some('"') => self.basic_string().map(Text::Str),
+            Some('\'') => self.literal_string().map(Text::Str),
+            Some('[') => self.array(),
+            Some('{') => self.inline_table(),
+            Some(_) if self.starts_with("true") => {
+                self.pos += 4;
+                Ok(Text::Bool(true))
+            }
+            Some(_) if self.starts_with("false") => {
+                self.pos += 5;
+                Ok(Text::Bool(false))
+            }
+            Some(_) => self.number(),
+            None => Err(self.error("expected a value")),
+        }
+    }
+
+    fn array(&mut self) -> Result<Text, MatrwError> {
+        self.expect('[')?;
+        let mut elements = Vec::new();
+        loop {
+            self.skip_blank();
+            if self.peek() == Some(']') {
+                self.pos += 1;
+                return Ok(Text::Array(elements));
+            }
+            elements.push(self.value()?);
+            self.skip_blank();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some(']') => {}
+                _ => return Err(self.error("expected ',' or ']'")),
+            }
+        }
+    }
+
+    fn inline_table(&mut self) -> Result<Text, MatrwError> {
+        self.expect('{')?;
+        let mut table = IndexMap::new();
+        self.skip_spaces();
+        if self.peek() == Some('}') {
+            self.pos += 1;
+            return Ok(Text::Table(table));
+        }
+        loop {
+            let (path, value) = self.key_value()?;
+            self.insert(&mut table, &path, value)?;
+            self.skip_spaces();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some('}') => {
+                    self.pos += 1;
+                    return Ok(Text::Table(table));
+                }
+                _ => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+    }
+
+    fn basic_string(&mut self) -> Result<String, MatrwError> {
+        self.expect('"')?;
+        let mut text = String::new();
+        loop {
+            match self.peek() {
+                Some('"') => {
+                    self.pos += 1;
+                    return Ok(text);
+                }
+                Some('\\') => text.push(self.escape()?),
+                Some('\n') | None => return Err(self.error("unterminated string")),
+                Some(c) => {
+                    text.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+    }
+
+    fn literal_string(&mut self) -> Result<String, MatrwError> {
+        self.expect('\'')?;
+        let start = self.pos;
+        loop {
+            match self.peek() {
+                Some('\'') => {
+                    self.pos += 1;
+                    return Ok(self.chars[start..self.pos - 1].iter().collect());
+                }
+                Some('\n') | None => return Err(self.error("unterminated string")),
+                Some(_) => self.pos += 1,
+            }
+        }
+    }
+
+    /// Multi-line basic or literal string, delimited by three `quote`s
+    fn multiline_string(&mut self, quote: char) -> Result<String, MatrwError> {
+        self.pos += 3;
+        // A newline right after the delimiter is trimmed
+        if self.starts_with("\r\n") {
+            self.pos += 2;
+        } else if self.peek() == Some('\n') {
+            self.pos += 1;
+        }
+
+        let mut text = String::new();
+        loop {
+            match self.peek() {
+                Some(c) if c == quote && self.starts_with(&quote.to_string().repeat(3)) => {
+                    // Up to two quotes before the delimiter belong to the string
+                    let n = self.chars[self.pos..].iter().take_while(|&&c| c == quote).count();
+                    if n > 5 {
+                        return Err(self.error("too many quotes"));
+                    }
+                    text.extend(std::iter::repeat_n(quote, n - 3));
+                    self.pos += n;
+                    return Ok(text);
+                }
+                Some('\\') if quote == '"' => {
+                    let after = self.chars[self.pos + 1..]
+                        .iter()
+                        .position(|c| !matches!(c, ' ' | '\t' | '\r'));
+                    if after.is_some_and(|i| self.chars[self.pos + 1 + i] == '\n') {
+                        // A backslash at the end of a line trims the following whitespace
+                        self.pos += 1;
+                        while matches!(self.peek(), Some(' ' | '\t' | '\r' | '\n')) {
+                            self.pos += 1;
+                        }
+                    } else {
+                        text.push(self.escape()?);
+                    }
+                }
+                Some(c) => {
+                    text.push(c);
+                    self.pos += 1;
+                }
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    /// Escape sequence of a basic string
+    fn escape(&mut self) -> Result<char, MatrwError> {
+        self.pos += 1;
+        let c = self.peek().ok_or_else(|| self.error("unterminated string"))?;
+        self.pos += 1;
+        let unicode = |parser: &mut Self, digits: usize| {
+            let hex: String = parser.chars.iter().skip(parser.pos).take(digits).collect();
+            parser.pos += digits;
+            u32::from_str_radix(&hex, 16)
+                .ok()
+                .filter(|_| hex.len() == digits)
+                .and_then(char::from_u32)
+                .ok_or_else(|| parser.error(&format!("invalid unicode escape '{hex}'")))
+        };
+        match c {
+            'b' => Ok('\u{8}'),
+            't' => Ok('\t'),
+            'n' => Ok('\n'),
+            'f' => Ok('\u{c}'),
+            'r' => Ok('\r'),
+            'e' => Ok('\u{1b}'),
+            '"' => Ok('"'),
+            '\\' => Ok('\\'),
+            'u' => unicode(self, 4),
+            'U' => unicode(self, 8),
+            c => Err(self.error(&format!("invalid escape '\\{c}'"))),
+        }
+    }
+
+    fn number(&mut self) -> Result<Text, MatrwError> {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '+' | '-' | '.' | ':'))
+        {
+            self.pos += 1;
+        }
+        let token: String = self.chars[start..self.pos].iter().collect();
+
+        let unsigned = token.trim_start_matches(['+', '-']);
+        match unsigned {
+            "inf" => {
+                return Ok(Text::Float(if token.starts_with('-') {
+                    f64::NEG_INFINITY
+                } else {
+                    f64::INFINITY
+                }));
+            }
+            "nan" => return Ok(Text::Float(f64::NAN)),
+            _ => {}
+        }
+        if !unsigned.starts_with(|c: char| c.is_ascii_digit()) {
+            return Err(self.error(&format!("invalid value '{token}'")));
+        }
+        if token.contains(':') || token[1..].contains('-') && !token.contains(['e', 'E']) {
+            return Err(self.error("dates and times are not supported"));
+        }
+
+        let digits = token.replace('_', "");
+        let radix = match digits.get(..2) {
+            Some("0x") => Some(16),
+            Some("0o") => Some(8),
+            Some("0b") => Some(2),
+            _ => None,
+        };
+        let parsed = match radix {
+            Some(radix) => i64::from_str_radix(&digits[2..], radix)
+                .ok()
+                .map(|v| Text::Int(v.into())),
+            None if digits.contains(['.', 'e', 'E']) => digits.parse::<f64>().ok().map(Text::Float),
+            None => digits.parse::<i64>().ok().map(|v| Text::Int(v.into())),
+        };
+        parsed.ok_or_else(|| self.error(&format!("invalid number '{token}'")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Structure, matvar};
+
+    #[test]
+    fn config_roundtrip() {
+        let config = matvar!({
+            name: "pump",
+            enabled: true,
+            gain: 2.5,
+            limits: [0.0, 10.0],
+            matrix: [[1.0, 2.0], [3.0, 4.0]],
+            tags: ["fast", "quiet"],
+            nested: { id: 7.0, unit: "m/s", deeper: { x: 1.0 } },
+            stages: [{ k: 1.0, sub: { a: 2.0 } }, { k: 2.0, sub: { a: 3.0 } }],
+        });
+        let text = to_toml_string(&config).unwrap();
+        assert_eq!(from_toml_str(&text).unwrap(), config);
+        assert!(text.contains("\n[nested.deeper]\nx = 1.0\n"));
+        assert!(text.contains("\n[[stages]]\nk = 2.0\n\n[stages.sub]\na = 3.0\n"));
+    }
+
+    #[test]
+    fn field_order_is_kept() {
+        let config = matvar!({ first: { a: 1.0 }, last: "plain" });
+        let text = to_toml_string(&config).unwrap();
+        assert_eq!(text, "first = { a = 1.0 }\nlast = \"plain\"\n");
+        assert_eq!(from_toml_str(&text).unwrap(), config);
+    }
+
+    #[test]
+    fn written_values() {
+        let mut fields = IndexMap::new();
+        fields.insert("odd key".to_string(), matvar!(1u64));
+        fields.insert("quote".to_string(), matvar!("a\"b\n"));
+        fields.insert("big".to_string(), matvar!(1e300));
+        fields.insert("inf".to_string(), matvar!(f64::INFINITY));
+        fields.insert("empty".to_string(), matvar!({}));
+        let text = to_toml_string(&MatVariable::Structure(Structure::new(fields)));
+        assert_eq!(
+            text.unwrap(),
+            "\"odd key\" = 1\nquote = \"a\\\"b\\n\"\nbig = 1.0e+300\ninf = inf\n\n[empty]\n"
+        );
+        assert!(to_toml_string(&matvar!(1.0)).is_err());
+        assert!(to_toml_string(&matvar!({ big: u64::MAX })).is_err());
+    }
+
+    #[test]
+    fn read_syntax() {
+        let text = r#"
+            # Comment
+            title = 'literal \n'  # Trailing comment
+            site."google.com" = true
+            ints = [ 1_000, 0x1F, 0o17, 0b11, -3, +4 ]
+            floats = [ 1e3, -0.5, inf, -inf ]
+            multi = """\
+            a \
+              b"""
+            raw = '''x\y'''
+            empty = []
+            point = { x = 1, y.z = 2 }
+
+            [[fruits]]
+            name = "apple"
+
+            [fruits.physical]
+            color = "red"
+
+            [[fruits]]
+            name = "banana"
+        "#;
+        let var = from_toml_str(text).unwrap();
+        assert_eq!(var["title"], matvar!("literal \\n"));
+        assert_eq!(var["site"]["google.com"], matvar!(true));
+        assert_eq!(var["ints"], matvar!([1000.0, 31.0, 15.0, 3.0, (-3.0), 4.0]));
+        assert_eq!(
+            var["floats"],
+            matvar!([1000.0, (-0.5), (f64::INFINITY), (f64::NEG_INFINITY)])
+        );
+        assert_eq!(var["multi"], matvar!("a b"));
+        assert_eq!(var["raw"], matvar!("x\\y"));
+        assert_eq!(var["empty"], MatVariable::empty_value());
+        assert_eq!(var["point"], matvar!({ x: 1.0, y: { z: 2.0 } }));
+        assert_eq!(var["fruits"][0]["physical"]["color"], matvar!("red"));
+        assert_eq!(var["fruits"][1]["name"], matvar!("banana"));
+    }
+
+    #[test]
+    fn read_errors() {
+        let error = |text: &str| match from_toml_str(text) {
+            Err(MatrwError::SerdeError(msg)) => msg,
+            other => panic!("expected an error, got {other:?}"),
+        };
+        assert_eq!(error("a = 1\na = 2"), "TOML line 2: 'a' is defined already");
+        assert_eq!(
+            error("a = 1979-05-27"),
+            "TOML line 1: dates and times are not supported"
+        );
+        assert_eq!(error("a = \"open"), "TOML line 1: unterminated string");
+        assert_eq!(error("a = 1 b = 2"), "TOML line 1: expected the end of the line");
+        assert_eq!(error("\n\na = [1, 2"), "TOML line 3: expected ',' or ']'");
+        assert_eq!(error("a = 1\n[a]"), "TOML line 2: 'a' is defined already");
+    }
+}
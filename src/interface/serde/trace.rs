@@ -0,0 +1,338 @@
+//! Derivation of a [`Schema`] from types implementing [`serde::Deserialize`].
+//!
+//! The [`Tracer`] deserializer records which method the type requests for every field and
+//! provides a placeholder value, prompting the type to continue with its next field.
+
+use serde::de::value::SeqDeserializer;
+use serde::de::{
+    DeserializeOwned, DeserializeSeed, Deserializer, IntoDeserializer, MapAccess, SeqAccess, Visitor,
+};
+
+use crate::MatrwError;
+use crate::interface::schema::{Schema, VariableClass, VariableSchema};
+use crate::interface::serde::sparse::{SPARSE_MATRIX_FIELDS, SPARSE_MATRIX_TOKEN};
+
+const SCALAR: [Option<usize>; 2] = [Some(1), Some(1)];
+
+/// Trace the schema of `T`.
+pub(crate) fn trace_schema<T: DeserializeOwned>() -> Result<VariableSchema, MatrwError> {
+    let mut schema = VariableSchema::any();
+    T::deserialize(Tracer { schema: &mut schema })?;
+    Ok(schema)
+}
+
+struct Tracer<'a> {
+    schema: &'a mut VariableSchema,
+}
+
+impl Tracer<'_> {
+    fn scalar(self, class: VariableClass) {
+        self.schema.set_class(class);
+        self.schema.set_shape(&SCALAR);
+    }
+
+    fn unsupported<T>(what: &str) -> Result<T, MatrwError> {
+        Err(MatrwError::SerdeError(format!(
+            "Cannot derive schema for {}",
+            what
+        )))
+    }
+}
+
+macro_rules! trace_scalar {
+    ($($method:ident => $visit:ident($value:expr), $class:ident;)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                self.scalar(VariableClass::$class);
+                visitor.$visit($value)
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for Tracer<'_> {
+    type Error = MatrwError;
+
+    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        Self::unsupported("self-describing types")
+    }
+
+    trace_scalar! {
+        deserialize_bool => visit_bool(false), Logical;
+        deserialize_u8 => visit_u8(0), UInt8;
+        deserialize_i8 => visit_i8(0), Int8;
+        deserialize_u16 => visit_u16(0), UInt16;
+        deserialize_i16 => visit_i16(0), Int16;
+        deserialize_u32 => visit_u32(0), UInt32;
+        deserialize_i32 => visit_i32(0), Int32;
+        deserialize_u64 => visit_u64(0), UInt64;
+        deserialize_i64 => visit_i64(0), Int64;
+        deserialize_f32 => visit_f32(0.0), Single;
+        deserialize_f64 => visit_f64(0.0), Double;
+        deserialize_char => visit_char(' '), Char;
+    }
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.schema.set_class(VariableClass::Char);
+        visitor.visit_string(String::new())
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.schema.set_optional();
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        // A single element reveals the element type
+        let mut element = VariableSchema::any();
+        let value = visitor.visit_seq(TracerSeqAccess {
+            schema: &mut element,
+            remaining: 1,
+        })?;
+
+        // Strings are read from cell arrays as well as from char matrices
+        let is_string = element.class() == Some(VariableClass::Char) && element.shape().is_none();
+        if let Some(class) = element.class().filter(|_| !is_string) {
+            self.schema.set_class(class);
+        }
+        if let Some(fields) = element.fields() {
+            self.schema.set_fields(fields.clone());
+        }
+
+        Ok(value)
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let mut element = VariableSchema::any();
+        let value = visitor.visit_seq(TracerSeqAccess {
+            schema: &mut element,
+            remaining: len,
+        })?;
+
+        if let Some(class) = element.class() {
+            self.schema.set_class(class);
+        }
+
+        Ok(value)
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        if name == SPARSE_MATRIX_TOKEN {
+            self.schema.set_class(VariableClass::Sparse);
+            return visitor.visit_map(SparseTracerMapAccess { id: 0 });
+        }
+
+        let mut schema = Schema::new();
+        let value = visitor.visit_map(TracerMapAccess {
+            schema: &mut schema,
+            fields,
+            id: 0,
+        })?;
+
+        self.schema.set_class(VariableClass::Struct);
+        self.schema.set_fields(schema);
+
+        Ok(value)
+    }
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_bytes<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        Self::unsupported("bytes")
+    }
+
+    fn deserialize_byte_buf<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        Self::unsupported("bytes")
+    }
+
+    fn deserialize_unit<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        Self::unsupported("unit")
+    }
+
+    fn deserialize_unit_struct<V>(self, _name: &'static str, _visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        Self::unsupported("unit structs")
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_map<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        Self::unsupported("maps")
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        Self::unsupported("enums")
+    }
+
+    fn deserialize_identifier<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        Self::unsupported("identifiers")
+    }
+}
+
+/// Provides `remaining` placeholder elements, all traced into the same schema.
+struct TracerSeqAccess<'a> {
+    schema: &'a mut VariableSchema,
+    remaining: usize,
+}
+
+impl<'de> SeqAccess<'de> for TracerSeqAccess<'_> {
+    type Error = MatrwError;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+        seed.deserialize(Tracer { schema: self.schema }).map(Some)
+    }
+}
+
+/// Traces every struct field into its own entry of `schema`.
+struct TracerMapAccess<'a> {
+    schema: &'a mut Schema,
+    fields: &'static [&'static str],
+    id: usize,
+}
+
+impl<'de> MapAccess<'de> for TracerMapAccess<'_> {
+    type Error = MatrwError;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        if self.id < self.fields.len() {
+            let key = self.fields[self.id];
+            self.id += 1;
+            seed.deserialize(key.into_deserializer()).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let mut field = VariableSchema::any();
+        let value = seed.deserialize(Tracer { schema: &mut field })?;
+        self.schema.insert(self.fields[self.id - 1], field);
+        Ok(value)
+    }
+}
+
+/// Provides an empty `0x0` sparse matrix.
+struct SparseTracerMapAccess {
+    id: usize,
+}
+
+impl<'de> MapAccess<'de> for SparseTracerMapAccess {
+    type Error = MatrwError;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        if self.id < SPARSE_MATRIX_FIELDS.len() {
+            let key = SPARSE_MATRIX_FIELDS[self.id];
+            self.id += 1;
+            seed.deserialize(key.into_deserializer()).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        match SPARSE_MATRIX_FIELDS[self.id - 1] {
+            "dim" => seed.deserialize(SeqDeserializer::new([0usize, 0].into_iter())),
+            "jc" => seed.deserialize(SeqDeserializer::new([0usize].into_iter())),
+            _ => seed.deserialize(SeqDeserializer::new(std::iter::empty::<usize>())),
+        }
+    }
+}
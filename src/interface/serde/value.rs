@@ -0,0 +1,328 @@
+//! [`serde::Serialize`] and [`serde::Deserialize`] for [`MatVariable`] itself.
+//!
+//! With these implementations, a tree of variables converts to and from any self-describing
+//! serde format, e.g. JSON with [serde_json](https://docs.rs/serde_json), and to and from TOML and
+//! YAML with [`crate::interface::serde::toml`] and [`crate::interface::serde::yaml`]. This is meant for configuration-style
+//! MAT-files of nested structures, scalars and strings, which can then be edited as text:
+//!
+//! ```
+//! use matrw::{MatVariable, matvar};
+//!
+//! let config = matvar!({ gain: 2.5, name: "motor", limits: [0.0, 10.0] });
+//!
+//! let text = serde_json::to_string(&config).unwrap();
+//! assert_eq!(text, r#"{"gain":2.5,"name":"motor","limits":[0.0,10.0]}"#);
+//!
+//! let parsed: MatVariable = serde_json::from_str(&text).unwrap();
+//! assert_eq!(parsed, config);
+//! ```
+//!
+//! Variables map to values as follows:
+//! - structures to maps, in field order,
+//! - numeric and logical scalars to numbers and booleans, vectors to sequences of them and
+//!   2D arrays to sequences of rows,
+//! - character row vectors to strings, character matrices to sequences of rows,
+//! - cell arrays and structure arrays, which are vectors, to sequences of their elements,
+//! - empty arrays and [`MatVariable::Null`] to empty sequences, since formats like TOML have no
+//!   null value.
+//!
+//! Complex, sparse and N-dimensional arrays are an error.
+//!
+//! The classes of numbers are not preserved by text formats: numbers deserialize as double
+//! scalars, like MATLAB's `jsondecode` does. Sequences of numbers or booleans become row
+//! vectors, sequences of equally long rows become matrices, sequences of structures with the
+//! same fields become structure arrays and other sequences become cell arrays.
+
+use std::fmt;
+
+use indexmap::IndexMap;
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{self, Serialize, SerializeSeq, Serializer};
+
+use crate::MatlabType;
+use crate::interface::error::MatrwError;
+use crate::interface::types::numeric_array::NumericArray;
+use crate::interface::types::structure::Structure;
+use crate::interface::variable::MatVariable;
+
+impl Serialize for MatVariable {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            MatVariable::NumericArray(arr) => serialize_numeric(arr, serializer),
+            MatVariable::Structure(s) => serializer.collect_map(s.value.iter()),
+            MatVariable::CellArray(cell) if is_vector(&cell.dim) => serializer.collect_seq(cell.value.iter()),
+            MatVariable::StructureArray(s) if is_vector(&s.dim) => serializer.collect_seq(s.value.iter()),
+            MatVariable::CellArray(_) | MatVariable::StructureArray(_) => Err(ser::Error::custom(
+                "Only cell arrays and structure arrays which are vectors can be serialized",
+            )),
+            // TOML has no null, the empty array is representable in all formats
+            MatVariable::Null => serializer.serialize_seq(Some(0))?.end(),
+            MatVariable::SparseArray(_) => Err(ser::Error::custom("Sparse arrays can't be serialized")),
+            MatVariable::Unsupported => Err(ser::Error::custom("Unsupported variables can't be serialized")),
+        }
+    }
+}
+
+/// At most one dimension is larger than 1
+fn is_vector(dim: &[usize]) -> bool {
+    dim.iter().filter(|&&d| d > 1).count() <= 1
+}
+
+fn serialize_numeric<S: Serializer>(arr: &NumericArray, serializer: S) -> Result<S::Ok, S::Error> {
+    if arr.is_complex() {
+        return Err(ser::Error::custom("Complex arrays can't be serialized"));
+    }
+    if !is_vector(&arr.dim) && arr.dim.len() != 2 {
+        return Err(ser::Error::custom("N-dimensional arrays can't be serialized"));
+    }
+
+    let rows = arr.dim[0];
+    match &arr.value {
+        MatlabType::UTF8(chars) | MatlabType::UTF16(chars) => {
+            if rows <= 1 {
+                // Decode surrogate pairs like `String::from_matvar`
+                let text = match arr.stored_utf16_units() {
+                    Some(units) if units.len() == chars.len() => String::from_utf16_lossy(units),
+                    _ => chars.iter().collect(),
+                };
+                return serializer.serialize_str(&text);
+            }
+            let mut seq = serializer.serialize_seq(Some(rows))?;
+            for r in 0..rows {
+                let row: String = chars.iter().skip(r).step_by(rows).collect();
+                seq.serialize_element(&row)?;
+            }
+            seq.end()
+        }
+        value if arr.is_scalar() => Element(value, 0).serialize(serializer),
+        value if is_vector(&arr.dim) => serializer.collect_seq((0..value.len()).map(|i| Element(value, i))),
+        value => serializer.collect_seq((0..rows).map(|r| Row { value, rows, r })),
+    }
+}
+
+/// Element `.1` of the array values `.0`
+struct Element<'a>(&'a MatlabType, usize);
+
+impl Serialize for Element<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let i = self.1;
+        match self.0 {
+            MatlabType::U8(v) => serializer.serialize_u8(v[i]),
+            MatlabType::I8(v) => serializer.serialize_i8(v[i]),
+            MatlabType::U16(v) => serializer.serialize_u16(v[i]),
+            MatlabType::I16(v) => serializer.serialize_i16(v[i]),
+            MatlabType::U32(v) => serializer.serialize_u32(v[i]),
+            MatlabType::I32(v) => serializer.serialize_i32(v[i]),
+            MatlabType::U64(v) => serializer.serialize_u64(v[i]),
+            MatlabType::I64(v) => serializer.serialize_i64(v[i]),
+            MatlabType::F32(v) => serializer.serialize_f32(v[i]),
+            MatlabType::F64(v) => serializer.serialize_f64(v[i]),
+            MatlabType::UTF8(v) | MatlabType::UTF16(v) => serializer.serialize_char(v[i]),
+            MatlabType::BOOL(v) => serializer.serialize_bool(v[i]),
+        }
+    }
+}
+
+/// Row `r` of a 2D array with `rows` rows, stored in column-major order
+struct Row<'a> {
+    value: &'a MatlabType,
+    rows: usize,
+    r: usize,
+}
+
+impl Serialize for Row<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let cols = self.value.len() / self.rows;
+        serializer.collect_seq((0..cols).map(|j| Element(self.value, self.r + j * self.rows)))
+    }
+}
+
+impl<'de> Deserialize<'de> for MatVariable {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(MatVariableVisitor)
+    }
+}
+
+struct MatVariableVisitor;
+
+impl<'de> Visitor<'de> for MatVariableVisitor {
+    type Value = MatVariable;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a number, boolean, string, sequence or map")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Self::Value, E> {
+        Ok(scalar(MatlabType::BOOL(vec![v])))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        self.visit_f64(v as f64)
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        self.visit_f64(v as f64)
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+        Ok(scalar(MatlabType::F64(vec![v])))
+    }
+
+    fn visit_char<E: de::Error>(self, v: char) -> Result<Self::Value, E> {
+        Ok(MatVariable::from(v.to_string().as_str()))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(MatVariable::from(v))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(MatVariable::empty_value())
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(MatVariable::empty_value())
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        MatVariable::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut elements = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(element) = seq.next_element()? {
+            elements.push(element);
+        }
+        from_elements(elements).map_err(de::Error::custom)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut fields = IndexMap::with_capacity(map.size_hint().unwrap_or(0));
+        while let Some((key, value)) = map.next_entry::<String, MatVariable>()? {
+            fields.insert(key, value);
+        }
+        Ok(MatVariable::Structure(Structure::new(fields)))
+    }
+}
+
+fn scalar(value: MatlabType) -> MatVariable {
+    MatVariable::NumericArray(NumericArray::new(vec![1, 1], value, None).unwrap())
+}
+
+/// Variable of the elements of a sequence
+fn from_elements(elements: Vec<MatVariable>) -> Result<MatVariable, MatrwError> {
+    if elements.is_empty() {
+        return Ok(MatVariable::empty_value());
+    }
+
+    // Rows of numbers or booleans of the same class and length, scalars being rows of length 1
+    let row_len = |var: &MatVariable| match var {
+        MatVariable::NumericArray(arr)
+            if arr.dim.len() == 2
+                && arr.dim[0] == 1
+                && arr.value_cmp.is_none()
+                && !matches!(arr.value, MatlabType::UTF8(_) | MatlabType::UTF16(_)) =>
+        {
+            Some((std::mem::discriminant(&arr.value), arr.dim[1]))
+        }
+        _ => None,
+    };
+    let first = row_len(&elements[0]);
+    if first.is_some_and(|(_, len)| len > 0) && elements.iter().all(|var| row_len(var) == first) {
+        return NumericArray::from_nested_matvar(vec![], elements).map(MatVariable::NumericArray);
+    }
+
+    Ok(MatVariable::from(elements))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CellArray, matvar};
+
+    fn roundtrip(var: &MatVariable) -> MatVariable {
+        serde_json::from_str(&serde_json::to_string(var).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn config_roundtrip() {
+        let config = matvar!({
+            name: "pump",
+            enabled: true,
+            gain: 2.5,
+            limits: [0.0, 10.0],
+            flags: [true, false],
+            matrix: [[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]],
+            nested: { id: 7.0, unit: "m/s" },
+            tags: ["fast", "quiet"],
+            stages: [{ k: 1.0 }, { k: 2.0 }],
+        });
+        assert_eq!(roundtrip(&config), config);
+    }
+
+    #[test]
+    fn serialized_values() {
+        let to_json = |var: MatVariable| serde_json::to_string(&var).unwrap();
+
+        assert_eq!(to_json(matvar!(3u8)), "3");
+        assert_eq!(to_json(matvar!([[1, 2], [3, 4]])), "[[1,2],[3,4]]");
+        assert_eq!(to_json(matvar!(["ab", "cd"])), r#"["ab","cd"]"#);
+        assert_eq!(to_json(MatVariable::empty_value()), "[]");
+        let chars = NumericArray::new(vec![2, 2], MatlabType::UTF8(vec!['a', 'c', 'b', 'd']), None).unwrap();
+        assert_eq!(to_json(MatVariable::NumericArray(chars)), r#"["ab","cd"]"#);
+
+        let cells = CellArray::new(vec![2, 2], vec![matvar!(1.0); 4]).unwrap();
+        assert!(serde_json::to_string(&MatVariable::CellArray(cells)).is_err());
+        let complex = NumericArray::new(
+            vec![1, 1],
+            MatlabType::F64(vec![1.0]),
+            Some(MatlabType::F64(vec![2.0])),
+        );
+        assert!(serde_json::to_string(&MatVariable::NumericArray(complex.unwrap())).is_err());
+    }
+
+    #[test]
+    fn no_null_values() {
+        // TOML has no null, so no value may serialize to one
+        fn has_null(value: &serde_json::Value) -> bool {
+            match value {
+                serde_json::Value::Null => true,
+                serde_json::Value::Array(values) => values.iter().any(has_null),
+                serde_json::Value::Object(map) => map.values().any(has_null),
+                _ => false,
+            }
+        }
+
+        let mut fields = IndexMap::new();
+        fields.insert("missing".to_string(), MatVariable::Null);
+        fields.insert("empty".to_string(), MatVariable::empty_value());
+        fields.insert("cells".to_string(), MatVariable::from(vec![MatVariable::Null]));
+        let config = MatVariable::Structure(Structure::new(fields));
+
+        let value = serde_json::to_value(&config).unwrap();
+        assert!(!has_null(&value));
+        assert_eq!(
+            serde_json::to_string(&config).unwrap(),
+            r#"{"missing":[],"empty":[],"cells":[[]]}"#
+        );
+        assert_eq!(roundtrip(&config)["missing"], MatVariable::empty_value());
+    }
+
+    #[test]
+    fn deserialized_values() {
+        let parse = |text: &str| serde_json::from_str::<MatVariable>(text).unwrap();
+
+        // Numbers are doubles, whatever their notation
+        assert_eq!(parse("3"), matvar!(3.0));
+        assert_eq!(parse("[1, 2.5]"), matvar!([1.0, 2.5]));
+        assert_eq!(parse("null"), MatVariable::empty_value());
+        assert_eq!(parse("[]"), MatVariable::empty_value());
+        // Mixed sequences and rows of different length are cell arrays
+        assert!(matches!(parse(r#"[1, "a"]"#), MatVariable::CellArray(_)));
+        assert!(matches!(parse("[[1, 2], [3]]"), MatVariable::CellArray(_)));
+        assert!(matches!(
+            parse(r#"[{"a": 1}, {"b": 2}]"#),
+            MatVariable::CellArray(_)
+        ));
+    }
+}
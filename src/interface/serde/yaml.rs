@@ -0,0 +1,775 @@
+//! YAML documents of variables.
+//!
+//! [`to_yaml_string`] writes a variable as a [YAML](https://yaml.org) document and
+//! [`from_yaml_str`] reads one back, so configuration-style MAT-files can be edited as text. Values
+//! map like with the [`serde`] implementation of [`MatVariable`], see
+//! [`crate::interface::serde::value`]: numbers are read as double scalars, sequences of numbers as
+//! vectors and matrices, and so on. Null values are read as empty arrays.
+//!
+//! Structures are written as block mappings, sequences holding structures as block sequences and
+//! other sequences in flow style, e.g. `[1.0, 2.0]`. Strings are always double-quoted, so no string
+//! is mistaken for a number or boolean.
+//!
+//! The YAML read is the subset used for configuration: block mappings and sequences, flow
+//! sequences and mappings, plain, single- and double-quoted scalars and comments, resolved with
+//! the core schema of YAML 1.2. Anchors, aliases, tags, block scalars like `|`, multi-line scalars
+//! and multiple documents are an error.
+
+use indexmap::IndexMap;
+
+use crate::interface::error::MatrwError;
+use crate::interface::serde::text::{Text, finite_float, quote};
+use crate::interface::variable::MatVariable;
+
+///
+/// Write `var` as a YAML document, see the [module documentation](crate::interface::serde::yaml).
+///
+/// Returns [`MatrwError::SerdeError`], if `var` holds values which can't be serialized, e.g.
+/// complex or sparse arrays.
+///
+/// Requires the feature `yaml`.
+///
+/// Example
+/// ```
+/// use matrw::{from_yaml_str, matvar, to_yaml_string};
+///
+/// let config = matvar!({ name: "pump", limits: [0.0, 10.0], stages: [{ k: 1.0 }, { k: 2.0 }] });
+///
+/// let text = to_yaml_string(&config).unwrap();
+/// assert_eq!(text, "name: \"pump\"\nlimits: [0.0, 10.0]\nstages:\n  - k: 1.0\n  - k: 2.0\n");
+/// assert_eq!(from_yaml_str(&text).unwrap(), config);
+/// ```
+///
+pub fn to_yaml_string(var: &MatVariable) -> Result<String, MatrwError> {
+    let mut out = String::new();
+    match Text::from_variable(var)? {
+        Text::Table(table) if !table.is_empty() => write_table(&mut out, 0, &table),
+        Text::Array(elements) if is_block(&elements) => write_sequence(&mut out, 0, &elements),
+        value => {
+            out.push_str(&flow(&value));
+            out.push('\n');
+        }
+    }
+    Ok(out)
+}
+
+///
+/// Read the YAML document `text` as a variable, see the
+/// [module documentation](crate::interface::serde::yaml).
+///
+/// Returns [`MatrwError::SerdeError`] with the line of the error, if `text` is no valid YAML or
+/// uses features beyond the subset read.
+///
+/// Requires the feature `yaml`.
+///
+pub fn from_yaml_str(text: &str) -> Result<MatVariable, MatrwError> {
+    let mut lines = lines(text)?;
+    if lines.is_empty() {
+        return Text::Null.into_variable();
+    }
+
+    let mut pos = 0;
+    let indent = lines[0].indent;
+    let value = parse_node(&mut lines, &mut pos, indent)?;
+    if let Some(line) = lines.get(pos) {
+        return Err(error(line.number, "unexpected indentation"));
+    }
+    value.into_variable()
+}
+
+fn error(line: usize, msg: &str) -> MatrwError {
+    MatrwError::SerdeError(format!("YAML line {line}: {msg}"))
+}
+
+/// Write the entries of the non-empty `table` as a block mapping, indented by `indent` spaces
+fn write_table(out: &mut String, indent: usize, table: &IndexMap<String, Text>) {
+    let pad = " ".repeat(indent);
+    for (key, value) in table {
+        let key = yaml_key(key);
+        match value {
+            Text::Table(table) if !table.is_empty() => {
+                out.push_str(&format!("{pad}{key}:\n"));
+                write_table(out, indent + 2, table);
+            }
+            Text::Array(elements) if is_block(elements) => {
+                out.push_str(&format!("{pad}{key}:\n"));
+                write_sequence(out, indent + 2, elements);
+            }
+            value => out.push_str(&format!("{pad}{key}: {}\n", flow(value))),
+        }
+    }
+}
+
+/// Write the elements of the block sequence `elements`, indented by `indent` spaces
+fn write_sequence(out: &mut String, indent: usize, elements: &[Text]) {
+    let pad = " ".repeat(indent);
+    for element in elements {
+        // Nested blocks start on the line of the dash, indented past it
+        let mut block = String::new();
+        match element {
+            Text::Table(table) if !table.is_empty() => write_table(&mut block, indent + 2, table),
+            Text::Array(elements) if is_block(elements) => write_sequence(&mut block, indent + 2, elements),
+            value => {
+                out.push_str(&format!("{pad}- {}\n", flow(value)));
+                continue;
+            }
+        }
+        out.push_str(&format!("{pad}- "));
+        out.push_str(&block[indent + 2..]);
+    }
+}
+
+/// Whether the sequence `elements` is written as a block, since it holds a non-empty mapping
+fn is_block(elements: &[Text]) -> bool {
+    elements.iter().any(|element| match element {
+        Text::Table(table) => !table.is_empty(),
+        Text::Array(elements) => is_block(elements),
+        _ => false,
+    })
+}
+
+/// Key, plain if it reads back as the same string with parsers of YAML 1.1 and 1.2
+fn yaml_key(key: &str) -> String {
+    let plain = key.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !matches!(
+            key.to_ascii_lowercase().as_str(),
+            "true" | "false" | "null" | "y" | "n" | "yes" | "no" | "on" | "off"
+        );
+    if plain { key.to_string() } else { quote(key) }
+}
+
+/// Value in flow style
+fn flow(value: &Text) -> String {
+    match value {
+        Text::Bool(v) => v.to_string(),
+        Text::Int(v) => v.to_string(),
+        Text::Float(v) if v.is_nan() => ".nan".to_string(),
+        Text::Float(v) if v.is_infinite() => if *v > 0.0 { ".inf" } else { "-.inf" }.to_string(),
+        Text::Float(v) => finite_float(*v),
+        Text::Str(v) => quote(v),
+        Text::Array(elements) => {
+            let elements: Vec<String> = elements.iter().map(flow).collect();
+            format!("[{}]", elements.join(", "))
+        }
+        Text::Table(table) => {
+            let entries: Vec<String> = table
+                .iter()
+                .map(|(key, value)| format!("{}: {}", yaml_key(key), flow(value)))
+                .collect();
+            format!("{{{}}}", entries.join(", "))
+        }
+        Text::Null => "null".to_string(),
+    }
+}
+
+/// Line with content, without comment
+struct Line {
+    /// Spaces before the content
+    indent: usize,
+    text: String,
+    /// Line number, from 1
+    number: usize,
+}
+
+/// Lines with content of `text`, where flow collections continued on later lines are joined
+fn lines(text: &str) -> Result<Vec<Line>, MatrwError> {
+    let mut lines: Vec<Line> = Vec::new();
+    let mut open: Option<Line> = None;
+
+    for (i, raw) in text.lines().enumerate() {
+        let number = i + 1;
+        let content = strip_comment(raw);
+        let trimmed = content.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+
+        if let Some(mut line) = open.take() {
+            line.text.push(' ');
+            line.text.push_str(trimmed);
+            if flow_depth(&line.text) > 0 {
+                open = Some(line);
+            } else {
+                lines.push(line);
+            }
+            continue;
+        }
+
+        let indent = content.len() - content.trim_start_matches(' ').len();
+        if content[indent..].starts_with('\t') {
+            return Err(error(number, "tabs can't indent"));
+        }
+        if trimmed.starts_with('%') {
+            return Err(error(number, "directives are not supported"));
+        }
+        if trimmed == "---" || trimmed.starts_with("--- ") {
+            if !lines.is_empty() {
+                return Err(error(number, "multiple documents are not supported"));
+            }
+            let rest = trimmed[3..].trim();
+            if rest.is_empty() {
+                continue;
+            }
+            // A value on the line of the marker
+            lines.push(Line {
+                indent: 0,
+                text: rest.to_string(),
+                number,
+            });
+            continue;
+        }
+        if trimmed == "..." {
+            break;
+        }
+
+        let line = Line {
+            indent,
+            text: trimmed.to_string(),
+            number,
+        };
+        if flow_depth(&line.text) > 0 {
+            open = Some(line);
+        } else {
+            lines.push(line);
+        }
+    }
+
+    match open {
+        Some(line) => Err(error(line.number, "unterminated flow collection")),
+        None => Ok(lines),
+    }
+}
+
+/// Call `f` with the position, character and flow nesting of the characters of `text` outside of
+/// quoted scalars, until it returns `true`, and return that position
+fn scan(text: &str, mut f: impl FnMut(usize, char, usize) -> bool) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut prev = None;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            // Quoted scalars start at the beginning of a value
+            '"' | '\'' if matches!(prev, None | Some(' ' | '\t' | '[' | '{' | ',' | ':' | '-')) => {
+                while let Some((_, q)) = chars.next() {
+                    match q {
+                        '\\' if c == '"' => {
+                            chars.next();
+                        }
+                        '\'' if c == '\'' && chars.peek().is_some_and(|&(_, next)| next == '\'') => {
+                            chars.next();
+                        }
+                        q if q == c => break,
+                        _ => {}
+                    }
+                }
+            }
+            c if f(i, c, depth) => return Some(i),
+            '[' | '{' => depth += 1,
+            ']' | '}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        prev = Some(c);
+    }
+    None
+}
+
+/// `line` without its comment
+fn strip_comment(line: &str) -> &str {
+    let mut prev = None;
+    let comment = scan(line, |i, c, _| {
+        let starts = c == '#' && (i == 0 || matches!(prev, Some(' ' | '\t')));
+        prev = Some(c);
+        starts
+    });
+    &line[..comment.unwrap_or(line.len())]
+}
+
+/// Nesting of flow collections open at the end of `text`
+fn flow_depth(text: &str) -> usize {
+    let mut depth = 0;
+    scan(text, |_, c, d| {
+        depth = match c {
+            '[' | '{' => d + 1,
+            ']' | '}' => d.saturating_sub(1),
+            _ => d,
+        };
+        false
+    });
+    depth
+}
+
+/// Whether `text` is an entry of a block sequence
+fn is_sequence_entry(text: &str) -> bool {
+    text == "-" || text.starts_with("- ")
+}
+
+/// Position of the colon separating the key of a block mapping entry `text` from its value
+fn key_separator(text: &str) -> Option<usize> {
+    scan(text, |i, c, depth| {
+        c == ':' && depth == 0 && text[i + 1..].chars().next().is_none_or(|next| next == ' ')
+    })
+}
+
+/// Node starting at `lines[*pos]`, indented by `indent` spaces
+fn parse_node(lines: &mut [Line], pos: &mut usize, indent: usize) -> Result<Text, MatrwError> {
+    let line = &lines[*pos];
+    if is_sequence_entry(&line.text) {
+        parse_sequence(lines, pos, indent)
+    } else if key_separator(&line.text).is_some() {
+        parse_mapping(lines, pos, indent)
+    } else {
+        let value = parse_value(&line.text, line.number)?;
+        *pos += 1;
+        Ok(value)
+    }
+}
+
+/// Node of the value of an entry, which is on the lines after it, or missing
+fn parse_child(
+    lines: &mut [Line],
+    pos: &mut usize,
+    indent: usize,
+    sequence_at_indent: bool,
+) -> Result<Text, MatrwError> {
+    match lines.get(*pos) {
+        Some(next) if next.indent > indent => {
+            let indent = next.indent;
+            parse_node(lines, pos, indent)
+        }
+        // The entries of a sequence in a mapping may be as indented as its key
+        Some(next) if sequence_at_indent && next.indent == indent && is_sequence_entry(&next.text) => {
+            parse_sequence(lines, pos, indent)
+        }
+        _ => Ok(Text::Null),
+    }
+}
+
+fn parse_sequence(lines: &mut [Line], pos: &mut usize, indent: usize) -> Result<Text, MatrwError> {
+    let mut elements = Vec::new();
+    while let Some(line) = lines.get_mut(*pos) {
+        if line.indent != indent || !is_sequence_entry(&line.text) {
+            break;
+        }
+        let rest = line.text[1..].trim_start();
+        if rest.is_empty() {
+            *pos += 1;
+            elements.push(parse_child(lines, pos, indent, false)?);
+        } else {
+            // The content after the dash is a node as indented as its column
+            let column = indent + line.text.len() - rest.len();
+            line.text = rest.to_string();
+            line.indent = column;
+            elements.push(parse_node(lines, pos, column)?);
+        }
+    }
+    Ok(Text::Array(elements))
+}
+
+fn parse_mapping(lines: &mut [Line], pos: &mut usize, indent: usize) -> Result<Text, MatrwError> {
+    let mut table = IndexMap::new();
+    while let Some(line) = lines.get(*pos) {
+        if line.indent != indent || is_sequence_entry(&line.text) {
+            break;
+        }
+        let number = line.number;
+        let Some(colon) = key_separator(&line.text) else {
+            return Err(error(number, "expected 'key: value'"));
+        };
+        let key = match parse_value(line.text[..colon].trim(), number)? {
+            Text::Str(key) => key,
+            Text::Null => return Err(error(number, "keys can't be empty")),
+            Text::Bool(v) => v.to_string(),
+            Text::Int(v) => v.to_string(),
+            Text::Float(_) => line.text[..colon].trim().to_string(),
+            _ => return Err(error(number, "keys must be scalars")),
+        };
+        let rest = line.text[colon + 1..].trim().to_string();
+
+        *pos += 1;
+        let value = if rest.is_empty() {
+            parse_child(lines, pos, indent, true)?
+        } else {
+            parse_value(&rest, number)?
+        };
+        if table.insert(key.clone(), value).is_some() {
+            return Err(error(number, &format!("'{key}' is defined already")));
+        }
+    }
+    Ok(Text::Table(table))
+}
+
+/// Scalar or flow collection, which is all of `text`
+fn parse_value(text: &str, number: usize) -> Result<Text, MatrwError> {
+    let mut parser = FlowParser {
+        chars: text.chars().collect(),
+        pos: 0,
+        number,
+    };
+    if let Some(c @ ('|' | '>' | '&' | '*' | '!')) = parser.peek() {
+        let feature = match c {
+            '|' | '>' => "block scalars",
+            '&' => "anchors",
+            '*' => "aliases",
+            _ => "tags",
+        };
+        return Err(parser.error(&format!("{feature} are not supported")));
+    }
+    let value = parser.value(false)?;
+    parser.skip_spaces();
+    match parser.peek() {
+        None => Ok(value),
+        Some(_) => Err(parser.error("unexpected characters after the value")),
+    }
+}
+
+/// Parser of scalars and flow collections on one line
+struct FlowParser {
+    chars: Vec<char>,
+    pos: usize,
+    number: usize,
+}
+
+impl FlowParser {
+    fn error(&self, msg: &str) -> MatrwError {
+        error(self.number, msg)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_spaces(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.pos += 1;
+        }
+    }
+
+    /// Value, which ends before `,`, `]`, `}` and `: ` within flow collections
+    fn value(&mut self, in_flow: bool) -> Result<Text, MatrwError> {
+        self.skip_spaces();
+        match self.peek() {
+            Some('[') => self.sequence(),
+            Some('{') => self.mapping(),
+            Some('"') => self.double_quoted().map(Text::Str),
+            Some('\'') => self.single_quoted().map(Text::Str),
+            _ => Ok(resolve(self.plain(in_flow).trim())),
+        }
+    }
+
+    fn plain(&mut self, in_flow: bool) -> String {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            let next = self.chars.get(self.pos + 1).copied();
+            let ends = in_flow
+                && (matches!(c, ',' | ']' | '}')
+                    || c == ':' && matches!(next, None | Some(' ' | ',' | ']' | '}')));
+            if ends {
+                break;
+            }
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    fn sequence(&mut self) -> Result<Text, MatrwError> {
+        self.pos += 1;
+        let mut elements = Vec::new();
+        loop {
+            self.skip_spaces();
+            if self.peek() == Some(']') {
+                self.pos += 1;
+                return Ok(Text::Array(elements));
+            }
+            elements.push(self.value(true)?);
+            self.skip_spaces();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some(']') => {}
+                _ => return Err(self.error("expected ',' or ']'")),
+            }
+        }
+    }
+
+    fn mapping(&mut self) -> Result<Text, MatrwError> {
+        self.pos += 1;
+        let mut table = IndexMap::new();
+        loop {
+            self.skip_spaces();
+            if self.peek() == Some('}') {
+                self.pos += 1;
+                return Ok(Text::Table(table));
+            }
+            let key = match self.value(true)? {
+                Text::Str(key) => key,
+                Text::Table(_) | Text::Array(_) => return Err(self.error("keys must be scalars")),
+                Text::Null => return Err(self.error("keys can't be empty")),
+                Text::Bool(v) => v.to_string(),
+                Text::Int(v) => v.to_string(),
+                Text::Float(v) => v.to_string(),
+            };
+            self.skip_spaces();
+            let value = if self.peek() == Some(':') {
+                self.pos += 1;
+                self.value(true)?
+            } else {
+                Text::Null
+            };
+            if table.insert(key.clone(), value).is_some() {
+                return Err(self.error(&format!("'{key}' is defined already")));
+            }
+            self.skip_spaces();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some('}') => {}
+                _ => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+    }
+
+    fn single_quoted(&mut self) -> Result<String, MatrwError> {
+        self.pos += 1;
+        let mut text = String::new();
+        loop {
+            match self.peek() {
+                Some('\'') if self.chars.get(self.pos + 1) == Some(&'\'') => {
+                    text.push('\'');
+                    self.pos += 2;
+                }
+                Some('\'') => {
+                    self.pos += 1;
+                    return Ok(text);
+                }
+                Some(c) => {
+                    text.push(c);
+                    self.pos += 1;
+                }
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    fn double_quoted(&mut self) -> Result<String, MatrwError> {
+        self.pos += 1;
+        let mut text = String::new();
+        loop {
+            match self.peek() {
+                Some('"') => {
+                    self.pos += 1;
+                    return Ok(text);
+                }
+                Some('\\') => {
+                    self.pos += 1;
+                    text.push(self.escape()?);
+                }
+                Some(c) => {
+                    text.push(c);
+                    self.pos += 1;
+                }
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    /// Escape sequence of a double-quoted scalar, after the backslash
+    fn escape(&mut self) -> Result<char, MatrwError> {
+        let c = self.peek().ok_or_else(|| self.error("unterminated string"))?;
+        self.pos += 1;
+        let digits = match c {
+            '0' => return Ok('\0'),
+            'a' => return Ok('\u{7}'),
+            'b' => return Ok('\u{8}'),
+            't' | '\t' => return Ok('\t'),
+            'n' => return Ok('\n'),
+            'v' => return Ok('\u{b}'),
+            'f' => return Ok('\u{c}'),
+            'r' => return Ok('\r'),
+            'e' => return Ok('\u{1b}'),
+            ' ' | '"' | '/' | '\\' => return Ok(c),
+            'N' => return Ok('\u{85}'),
+            '_' => return Ok('\u{a0}'),
+            'L' => return Ok('\u{2028}'),
+            'P' => return Ok('\u{2029}'),
+            'x' => 2,
+            'u' => 4,
+            'U' => 8,
+            c => return Err(self.error(&format!("invalid escape '\\{c}'"))),
+        };
+        let hex: String = self.chars.iter().skip(self.pos).take(digits).collect();
+        self.pos += digits;
+        let code = u32::from_str_radix(&hex, 16)
+            .ok()
+            .filter(|_| hex.len() == digits)
+            .ok_or_else(|| self.error(&format!("invalid escape '{hex}'")))?;
+
+        // Surrogate pairs, as written by JSON encoders
+        let code = if (0xD800..0xDC00).contains(&code) && self.chars[self.pos..].starts_with(&['\\', 'u']) {
+            let hex: String = self.chars.iter().skip(self.pos + 2).take(4).collect();
+            match u32::from_str_radix(&hex, 16) {
+                Ok(low @ 0xDC00..0xE000) if hex.len() == 4 => {
+                    self.pos += 6;
+                    0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00)
+                }
+                _ => code,
+            }
+        } else {
+            code
+        };
+        char::from_u32(code).ok_or_else(|| self.error(&format!("invalid escape '{hex}'")))
+    }
+}
+
+/// Value of the plain scalar `text`, by the core schema of YAML 1.2
+fn resolve(text: &str) -> Text {
+    match text {
+        "" | "~" | "null" | "Null" | "NULL" => return Text::Null,
+        "true" | "True" | "TRUE" => return Text::Bool(true),
+        "false" | "False" | "FALSE" => return Text::Bool(false),
+        ".nan" | ".NaN" | ".NAN" => return Text::Float(f64::NAN),
+        _ => {}
+    }
+
+    let (negative, unsigned) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    if matches!(unsigned, ".inf" | ".Inf" | ".INF") {
+        return Text::Float(if negative {
+            f64::NEG_INFINITY
+        } else {
+            f64::INFINITY
+        });
+    }
+
+    let radix = |prefix: &str, radix: u32| {
+        text.strip_prefix(prefix)
+            .filter(|digits| !digits.is_empty())
+            .and_then(|digits| i128::from_str_radix(digits, radix).ok())
+    };
+    if let Some(v) = radix("0x", 16).or_else(|| radix("0o", 8)) {
+        return Text::Int(v);
+    }
+
+    let is_digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    if is_digits(unsigned)
+        && let Ok(v) = text.parse::<i128>()
+    {
+        return Text::Int(v);
+    }
+
+    // [0-9]+(\.[0-9]*)? or \.[0-9]+, with an optional exponent
+    let (mantissa, exponent) = match unsigned.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (unsigned, None),
+    };
+    let mantissa_ok = match mantissa.split_once('.') {
+        Some((int, frac)) => {
+            (is_digits(int) || int.is_empty() && is_digits(frac)) && (frac.is_empty() || is_digits(frac))
+        }
+        None => is_digits(mantissa),
+    };
+    let exponent_ok = exponent.is_none_or(|e| is_digits(e.strip_prefix(['+', '-']).unwrap_or(e)));
+    if mantissa_ok
+        && exponent_ok
+        && let Ok(v) = text.parse::<f64>()
+    {
+        return Text::Float(v);
+    }
+
+    Text::Str(text.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matvar;
+
+    #[test]
+    fn config_roundtrip() {
+        let config = matvar!({
+            name: "pump",
+            enabled: true,
+            gain: 2.5,
+            limits: [0.0, 10.0],
+            matrix: [[1.0, 2.0], [3.0, 4.0]],
+            tags: ["fast", "quiet: yes # no comment"],
+            nested: { id: 7.0, unit: "m/s", deeper: { x: 1.0 } },
+            stages: [{ k: 1.0, sub: { a: 2.0 } }, { k: 2.0, sub: { a: 3.0 } }],
+            empty: {},
+            last: "after the nested ones",
+        });
+        let text = to_yaml_string(&config).unwrap();
+        assert_eq!(from_yaml_str(&text).unwrap(), config);
+        assert!(text.contains("\nstages:\n  - k: 1.0\n    sub:\n      a: 2.0\n  - k: 2.0\n"));
+    }
+
+    #[test]
+    fn written_values() {
+        assert_eq!(to_yaml_string(&matvar!(2.5)).unwrap(), "2.5\n");
+        assert_eq!(to_yaml_string(&matvar!([1u8, 2u8])).unwrap(), "[1, 2]\n");
+        assert_eq!(
+            to_yaml_string(&matvar!({ yes: f64::NAN, big: 1e300, s: "1.0" })).unwrap(),
+            "\"yes\": .nan\nbig: 1.0e+300\ns: \"1.0\"\n"
+        );
+        let nested = MatVariable::from(vec![MatVariable::from(vec![matvar!({ a: 1.0 })])]);
+        assert_eq!(to_yaml_string(&nested).unwrap(), "- - a: 1.0\n");
+        assert_eq!(from_yaml_str("- - a: 1.0\n").unwrap(), nested);
+    }
+
+    #[test]
+    fn read_syntax() {
+        let text = r##"
+            --- # Document start
+            plain: some text # Comment
+            'single': 'it''s'
+            double: "tab\tand é"
+            ints: [1, -2, 0x10, 0o17]
+            floats: [1e3, .5, -.inf, 2.]
+            words: [yes, 1.2.3, "#3"]
+            flow: {a: 1, b: [xs, yyy]}
+            multiline: [1,
+              2]
+            missing:
+            list:
+            - 1
+            - two:
+                three: 3
+            -
+              - nested
+        "##;
+        let var = from_yaml_str(text).unwrap();
+        assert_eq!(var["plain"], matvar!("some text"));
+        assert_eq!(var["single"], matvar!("it's"));
+        assert_eq!(var["double"], matvar!("tab\tand é"));
+        assert_eq!(var["ints"], matvar!([1.0, (-2.0), 16.0, 15.0]));
+        assert_eq!(var["floats"], matvar!([1000.0, 0.5, (f64::NEG_INFINITY), 2.0]));
+        assert_eq!(var["words"], matvar!(["yes", "1.2.3", "#3"]));
+        assert_eq!(var["flow"], matvar!({ a: 1.0, b: ["xs", "yyy"] }));
+        assert_eq!(var["multiline"], matvar!([1.0, 2.0]));
+        assert_eq!(var["missing"], MatVariable::empty_value());
+        assert_eq!(var["list"][0], matvar!(1.0));
+        assert_eq!(var["list"][1], matvar!({ two: { three: 3.0 } }));
+        assert_eq!(var["list"][2], MatVariable::from(vec![matvar!("nested")]));
+    }
+
+    #[test]
+    fn read_errors() {
+        let error = |text: &str| match from_yaml_str(text) {
+            Err(MatrwError::SerdeError(msg)) => msg,
+            other => panic!("expected an error, got {other:?}"),
+        };
+        assert_eq!(error("a: 1\na: 2"), "YAML line 2: 'a' is defined already");
+        assert_eq!(error("a: 1\n  b: 2"), "YAML line 2: unexpected indentation");
+        assert_eq!(
+            error("a: |\n  text"),
+            "YAML line 1: block scalars are not supported"
+        );
+        assert_eq!(error("a: &x 1"), "YAML line 1: anchors are not supported");
+        assert_eq!(error("a: [1, 2"), "YAML line 1: unterminated flow collection");
+        assert_eq!(error("a: \"open"), "YAML line 1: unterminated string");
+        assert_eq!(
+            error("a: 1\n---\nb: 2"),
+            "YAML line 2: multiple documents are not supported"
+        );
+    }
+}
@@ -0,0 +1,296 @@
+//! Machine-readable account of the data elements skipped while reading a MAT-file.
+//!
+//! [`MatFile::warnings`](crate::MatFile::warnings) describes skipped elements in words,
+//! [`SkipReport`] lists the same elements with their class, name and position in the file, so
+//! that the share of a file matrw could read can be measured.
+
+use std::fmt;
+use std::ops::Range;
+
+use indexmap::IndexMap;
+
+use crate::parser::v7::flags::{MatFileDataTypes, MatlabArrayTypes};
+use crate::parser::v7::types::unknown::Unknown7;
+
+/// Why a data element was skipped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SkipReason {
+    /// Array with a class or contents matrw can't read
+    UnsupportedArray,
+    /// Element of a known data type, which is not an array, e.g. a numeric element at the top
+    /// level of a file
+    NotAnArray,
+    /// Element of a reserved or unknown data type
+    UnknownDataType,
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnsupportedArray => write!(f, "unsupported array"),
+            Self::NotAnArray => write!(f, "not an array"),
+            Self::UnknownDataType => write!(f, "unknown data type"),
+        }
+    }
+}
+
+/// Data element skipped while reading a file
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkippedElement {
+    /// Name of the array, if its header could be read. Top-level arrays with a name are loaded
+    /// as [`crate::MatVariable::Unsupported`]. Elements of cells and structures have no name.
+    pub name: Option<String>,
+    /// Path of the element, e.g. `s.field` or `c{2}` like in [`crate::RoundTripDifference`].
+    /// Elements of cells and structures are loaded as [`crate::MatVariable::Unsupported`] at
+    /// their path. Top-level elements have their name as path, or an empty path without name.
+    pub path: String,
+    /// Class of the array, if its flags could be read and name a known class
+    pub class: Option<MatlabArrayTypes>,
+    /// Data type of the element, as stored
+    pub data_type: u32,
+    /// Bytes of the element in the file, including tag and padding. Elements inside compressed
+    /// elements cover the whole compressed element.
+    pub range: Range<u64>,
+    pub reason: SkipReason,
+}
+
+impl From<&Unknown7> for SkippedElement {
+    fn from(value: &Unknown7) -> Self {
+        let reason = match MatFileDataTypes::from_u32(value.data_type) {
+            Some(MatFileDataTypes::MiMATRIX) => SkipReason::UnsupportedArray,
+            Some(_) => SkipReason::NotAnArray,
+            None => SkipReason::UnknownDataType,
+        };
+        let name = value.array_name().map(str::to_string);
+        Self {
+            path: name.clone().unwrap_or_default(),
+            name,
+            class: value.array_class().and_then(MatlabArrayTypes::from_u8),
+            data_type: value.data_type,
+            range: value.position..value.position + value.size,
+            reason,
+        }
+    }
+}
+
+impl SkippedElement {
+    /// Element at `path` inside a cell or structure
+    pub(crate) fn nested(value: &Unknown7, path: String) -> Self {
+        Self {
+            name: None,
+            path,
+            ..Self::from(value)
+        }
+    }
+}
+
+/// Data elements skipped while reading a file, see [`crate::MatFile::skip_report`]
+///
+/// Example
+/// ```
+/// use matrw::{MatFileWriter, SaveOptions, SkipReason, load_matfile_from_u8, matvar};
+/// use std::io::Cursor;
+///
+/// let mut writer = MatFileWriter::new(Cursor::new(Vec::new()), &SaveOptions::new()).unwrap();
+/// writer.write_variable("a", matvar!(1.0)).unwrap();
+/// writer.write_raw_element(0x4242, b"vendor data").unwrap();
+/// let data = writer.finish().unwrap().into_inner();
+///
+/// let report = load_matfile_from_u8(&data).unwrap().skip_report().clone();
+/// assert_eq!(report.len(), 1);
+/// assert_eq!(report.elements()[0].reason, SkipReason::UnknownDataType);
+/// assert_eq!(report.skipped_bytes(), 8 + 16);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SkipReport {
+    elements: Vec<SkippedElement>,
+}
+
+impl SkipReport {
+    pub(crate) fn push(&mut self, element: SkippedElement) {
+        self.elements.push(element);
+    }
+
+    /// Skipped elements in the order of the file
+    pub fn elements(&self) -> &[SkippedElement] {
+        &self.elements
+    }
+
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    /// Total bytes of the skipped elements
+    pub fn skipped_bytes(&self) -> u64 {
+        self.elements.iter().map(|e| e.range.end - e.range.start).sum()
+    }
+
+    /// Number of skipped elements for each reason, in the order of first occurrence
+    pub fn counts_by_reason(&self) -> IndexMap<SkipReason, usize> {
+        let mut counts = IndexMap::new();
+        for e in self.elements.iter() {
+            *counts.entry(e.reason).or_default() += 1;
+        }
+        counts
+    }
+
+    /// Number of skipped arrays for each class, in the order of first occurrence. Arrays, whose
+    /// class is not known, count as `None`.
+    pub fn counts_by_class(&self) -> IndexMap<Option<MatlabArrayTypes>, usize> {
+        let mut counts = IndexMap::new();
+        for e in self
+            .elements
+            .iter()
+            .filter(|e| e.reason == SkipReason::UnsupportedArray)
+        {
+            *counts.entry(e.class).or_default() += 1;
+        }
+        counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        MatFileWriter, MatVariable, SaveOptions, load_matfile_from_u8, load_matfile_lazy_from_u8, matvar,
+    };
+    use binrw::io::Cursor;
+
+    /// Data of a `miMATRIX` element of class object named `x`
+    fn object_array() -> Vec<u8> {
+        let mut data = vec![6, 0, 0, 0, 8, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0];
+        data.extend([5, 0, 0, 0, 8, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0]);
+        data.extend([1, 0, 0, 0, 1, 0, 0, 0, b'x', 0, 0, 0, 0, 0, 0, 0]);
+        data
+    }
+
+    #[test]
+    fn skipped_elements() {
+        let mut writer = MatFileWriter::new(Cursor::new(Vec::new()), &SaveOptions::new()).unwrap();
+        writer.write_variable("a", matvar!(1.0)).unwrap();
+        writer.write_raw_element(14, &object_array()).unwrap();
+        writer.write_raw_element(9, &[0; 8]).unwrap();
+        writer.write_raw_element(0x4242, b"abc").unwrap();
+        let data = writer.finish().unwrap().into_inner();
+
+        for matfile in [
+            load_matfile_from_u8(&data).unwrap(),
+            load_matfile_lazy_from_u8(&data).unwrap(),
+        ] {
+            assert_eq!(matfile["x"], MatVariable::Unsupported);
+            let report = matfile.skip_report();
+            assert_eq!(report.len(), matfile.warnings().len());
+
+            let x = &report.elements()[0];
+            assert_eq!(x.name.as_deref(), Some("x"));
+            assert_eq!(x.path, "x");
+            assert_eq!(x.class, Some(MatlabArrayTypes::MxOBJECTCLASS));
+            assert_eq!(x.reason, SkipReason::UnsupportedArray);
+            assert_eq!(x.range.end - x.range.start, 8 + 48);
+            assert_eq!(&data[x.range.start as usize..][..4], &[14, 0, 0, 0]);
+
+            let elements = report.elements();
+            assert_eq!(elements[1].range, x.range.end..x.range.end + 16);
+            assert_eq!(
+                (elements[1].reason, elements[1].name.as_ref()),
+                (SkipReason::NotAnArray, None)
+            );
+            assert_eq!(elements[2].reason, SkipReason::UnknownDataType);
+            assert_eq!(elements[2].range.end, data.len() as u64);
+            assert_eq!(report.skipped_bytes(), 56 + 16 + 16);
+
+            let counts = report.counts_by_reason();
+            assert_eq!(counts.values().copied().collect::<Vec<_>>(), [1, 1, 1]);
+            assert_eq!(
+                report.counts_by_class()[&Some(MatlabArrayTypes::MxOBJECTCLASS)],
+                1
+            );
+        }
+    }
+
+    #[test]
+    fn skipped_nested_elements() {
+        let object = || {
+            let mut element = vec![14, 0, 0, 0, 48, 0, 0, 0];
+            element.extend(object_array());
+            element
+        };
+        let mut cell = vec![6, 0, 0, 0, 8, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0];
+        cell.extend([5, 0, 0, 0, 8, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0]);
+        cell.extend([1, 0, 0, 0, 1, 0, 0, 0, b'c', 0, 0, 0, 0, 0, 0, 0]);
+        cell.extend(object());
+        cell.extend(object());
+
+        let mut structure = vec![6, 0, 0, 0, 8, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0];
+        structure.extend([5, 0, 0, 0, 8, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0]);
+        structure.extend([1, 0, 0, 0, 1, 0, 0, 0, b's', 0, 0, 0, 0, 0, 0, 0]);
+        structure.extend([5, 0, 4, 0, 8, 0, 0, 0, 1, 0, 0, 0, 8, 0, 0, 0]);
+        structure.extend([b'a', 0, 0, 0, 0, 0, 0, 0]);
+        structure.extend(object());
+
+        let mut writer = MatFileWriter::new(Cursor::new(Vec::new()), &SaveOptions::new()).unwrap();
+        writer.write_raw_element(14, &cell).unwrap();
+        writer.write_raw_element(14, &structure).unwrap();
+        let data = writer.finish().unwrap().into_inner();
+
+        for matfile in [
+            load_matfile_from_u8(&data).unwrap(),
+            load_matfile_lazy_from_u8(&data).unwrap(),
+        ] {
+            assert_eq!(matfile["c"][1], MatVariable::Unsupported);
+            assert_eq!(matfile["s"]["a"], MatVariable::Unsupported);
+            assert_eq!(
+                matfile.warnings(),
+                [
+                    "Element 'c{1}' has unsupported contents and was not read",
+                    "Element 'c{2}' has unsupported contents and was not read",
+                    "Element 's.a' has unsupported contents and was not read",
+                ]
+            );
+
+            let report = matfile.skip_report();
+            let paths: Vec<_> = report.elements().iter().map(|e| e.path.as_str()).collect();
+            assert_eq!(paths, ["c{1}", "c{2}", "s.a"]);
+            for e in report.elements() {
+                assert_eq!(e.name, None);
+                assert_eq!(e.class, Some(MatlabArrayTypes::MxOBJECTCLASS));
+                assert_eq!(e.range.end - e.range.start, 8 + 48);
+                assert_eq!(&data[e.range.start as usize..][..4], &[14, 0, 0, 0]);
+            }
+            assert_eq!(report.elements()[0].range.end, report.elements()[1].range.start);
+        }
+    }
+
+    #[cfg(feature = "deflate")]
+    #[test]
+    fn skipped_compressed_elements() {
+        use crate::parser::v7::zlib;
+
+        let mut element = vec![14, 0, 0, 0, 48, 0, 0, 0];
+        element.extend(object_array());
+        let compressed = zlib::compress(&element).unwrap();
+
+        let mut writer = MatFileWriter::new(Cursor::new(Vec::new()), &SaveOptions::new()).unwrap();
+        writer.write_variable("a", matvar!(1.0)).unwrap();
+        let mut raw = (15u32).to_le_bytes().to_vec();
+        raw.extend((compressed.len() as u32).to_le_bytes());
+        raw.extend(&compressed);
+        writer.write_encoded(&raw).unwrap();
+        let data = writer.finish().unwrap().into_inner();
+
+        // Lazily loaded variables are only checked when decompressed
+        assert!(load_matfile_lazy_from_u8(&data).unwrap().skip_report().is_empty());
+
+        let matfile = load_matfile_from_u8(&data).unwrap();
+        assert_eq!(matfile["x"], MatVariable::Unsupported);
+        assert_eq!(matfile.warnings().len(), 1);
+        let x = &matfile.skip_report().elements()[0];
+        assert_eq!(x.class, Some(MatlabArrayTypes::MxOBJECTCLASS));
+        assert_eq!(x.range, data.len() as u64 - raw.len() as u64..data.len() as u64);
+    }
+}
@@ -0,0 +1,304 @@
+//! Size statistics of nested variables.
+//!
+//! [`MatVariable::stats`] and [`MatFile::stats`] traverse the whole tree of a variable, to find
+//! out which variables and classes take the memory of a large file.
+
+use indexmap::IndexMap;
+
+use crate::interface::matfile::MatFile;
+use crate::interface::schema::VariableClass;
+use crate::interface::types::matlab_types::MatlabType;
+use crate::interface::variable::MatVariable;
+use crate::interface::visit::PathElem;
+
+/// Statistics of the arrays of one class, see [`VariableStats::by_class`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ClassStats {
+    /// Number of arrays
+    pub arrays: usize,
+    /// Number of elements: values of numeric, character and logical arrays, stored nonzeros of
+    /// sparse arrays and elements of structure and cell arrays
+    pub elements: usize,
+    /// Bytes of the data, see [`VariableStats::numeric_bytes`]
+    pub bytes: u64,
+}
+
+/// Size statistics of a variable tree, see [`MatVariable::stats`].
+///
+/// Example
+/// ```
+/// use matrw::{VariableClass, matvar};
+///
+/// let var = matvar!({ t: [0.5, 1.5, 2.5], meta: { name: "run", ids: [1u16, 2u16] } });
+/// let stats = var.stats();
+///
+/// assert_eq!(stats.arrays, 5);
+/// assert_eq!(stats.elements, 8);
+/// assert_eq!(stats.numeric_bytes, 3 * 8 + 3 * 2 + 2 * 2);
+/// assert_eq!(stats.max_depth, 2);
+/// assert_eq!(stats.by_class[&VariableClass::Double].bytes, 24);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VariableStats {
+    /// Number of arrays, including structures and cell arrays. The elements of structure arrays
+    /// are not counted separately.
+    pub arrays: usize,
+    /// Number of values of numeric, character, logical and sparse arrays
+    pub elements: usize,
+    /// Bytes of the values as MATLAB holds them in memory: 2 bytes per character, 1 per logical,
+    /// imaginary parts, and 8 bytes per row and column index of sparse arrays. Containers add
+    /// nothing.
+    pub numeric_bytes: u64,
+    /// Deepest nesting of structures and cell arrays, `0` for a variable without children
+    pub max_depth: usize,
+    /// Statistics for each class, in the order of first occurrence
+    pub by_class: IndexMap<VariableClass, ClassStats>,
+}
+
+impl VariableStats {
+    fn add(&mut self, depth: usize, var: &MatVariable) {
+        let Some(class) = var.kind() else {
+            return;
+        };
+        let (elements, bytes, is_data) = match var {
+            MatVariable::NumericArray(arr) => {
+                let bytes = value_bytes(&arr.value) + arr.value_cmp.as_ref().map_or(0, value_bytes);
+                (arr.value.len(), bytes, true)
+            }
+            MatVariable::SparseArray(arr) => {
+                let bytes = value_bytes(&arr.value)
+                    + arr.value_cmp.as_ref().map_or(0, value_bytes)
+                    + 8 * (arr.ir.len() + arr.jc.len()) as u64;
+                (arr.value.len(), bytes, true)
+            }
+            MatVariable::CellArray(arr) => (arr.value.len(), 0, false),
+            MatVariable::StructureArray(arr) => (arr.value.len(), 0, false),
+            _ => (1, 0, false),
+        };
+
+        self.arrays += 1;
+        if is_data {
+            self.elements += elements;
+            self.numeric_bytes += bytes;
+        }
+        self.max_depth = self.max_depth.max(depth);
+
+        let stats = self.by_class.entry(class).or_default();
+        stats.arrays += 1;
+        stats.elements += elements;
+        stats.bytes += bytes;
+    }
+
+    fn merge(&mut self, other: VariableStats) {
+        self.arrays += other.arrays;
+        self.elements += other.elements;
+        self.numeric_bytes += other.numeric_bytes;
+        self.max_depth = self.max_depth.max(other.max_depth);
+        for (class, stats) in other.by_class {
+            let total = self.by_class.entry(class).or_default();
+            total.arrays += stats.arrays;
+            total.elements += stats.elements;
+            total.bytes += stats.bytes;
+        }
+    }
+}
+
+/// Bytes of `value` in MATLAB's memory
+fn value_bytes(value: &MatlabType) -> u64 {
+    let size = match value {
+        MatlabType::U8(_) | MatlabType::I8(_) | MatlabType::BOOL(_) => 1,
+        MatlabType::U16(_) | MatlabType::I16(_) | MatlabType::UTF8(_) | MatlabType::UTF16(_) => 2,
+        MatlabType::U32(_) | MatlabType::I32(_) | MatlabType::F32(_) => 4,
+        MatlabType::U64(_) | MatlabType::I64(_) | MatlabType::F64(_) => 8,
+    };
+    size * value.len() as u64
+}
+
+impl MatVariable {
+    ///
+    /// Count arrays, elements and bytes of this variable and all nested variables, see
+    /// [`VariableStats`].
+    ///
+    pub fn stats(&self) -> VariableStats {
+        let mut stats = VariableStats::default();
+        self.walk(|path, var| {
+            // Elements of structure arrays are part of the array
+            let depth = path
+                .elems()
+                .iter()
+                .filter(|elem| !matches!(elem, PathElem::Element(_)))
+                .count();
+            if !matches!(path.elems().last(), Some(PathElem::Element(_))) {
+                stats.add(depth, var);
+            }
+        });
+        stats
+    }
+
+    ///
+    /// Bytes of memory held by this variable and all nested variables.
+    ///
+    /// Counts the variables themselves, their values, dimensions, sparse indices and field names
+    /// as Rust holds them, e.g. 4 bytes per character. Unused capacity and allocator overhead are
+    /// not counted, so the allocated memory is somewhat larger. See [`VariableStats::numeric_bytes`]
+    /// for the memory MATLAB needs.
+    ///
+    /// Example
+    /// ```
+    /// use matrw::{MatVariable, matvar};
+    ///
+    /// let var = matvar!({ a: [0.5, 1.5], b: "text" });
+    /// let values = 2 * 8 + 4 * 4;
+    /// assert!(var.memory_usage() > values + 3 * std::mem::size_of::<MatVariable>() as u64);
+    /// ```
+    ///
+    pub fn memory_usage(&self) -> u64 {
+        let mut bytes = size_of::<MatVariable>() as u64;
+        self.walk(|_, var| bytes += heap_bytes(var));
+        bytes
+    }
+}
+
+/// Bytes allocated by `var` itself, including the slots of its children but not their contents
+fn heap_bytes(var: &MatVariable) -> u64 {
+    let dims = |dim: &[usize]| size_of_val(dim) as u64;
+    let slots = |n: usize| (size_of::<MatVariable>() * n) as u64;
+    let name = |name: &String| (size_of::<String>() + name.len()) as u64;
+    match var {
+        MatVariable::NumericArray(arr) => {
+            dims(&arr.dim)
+                + rust_value_bytes(&arr.value)
+                + arr.value_cmp.as_ref().map_or(0, rust_value_bytes)
+                + arr.stored_utf16_units().map_or(0, |units| 2 * units.len() as u64)
+        }
+        MatVariable::SparseArray(arr) => {
+            dims(&arr.dim)
+                + rust_value_bytes(&arr.value)
+                + arr.value_cmp.as_ref().map_or(0, rust_value_bytes)
+                + (size_of::<usize>() * (arr.ir.len() + arr.jc.len())) as u64
+        }
+        MatVariable::CellArray(arr) => dims(&arr.dim) + slots(arr.value.len()),
+        MatVariable::StructureArray(arr) => {
+            dims(&arr.dim) + slots(arr.value.len()) + arr.fieldnames_ref().iter().map(name).sum::<u64>()
+        }
+        MatVariable::Structure(s) => s.value.keys().map(|key| name(key) + slots(1)).sum(),
+        _ => 0,
+    }
+}
+
+/// Bytes of `value` in Rust's memory
+fn rust_value_bytes(value: &MatlabType) -> u64 {
+    let size = match value {
+        MatlabType::U8(_) | MatlabType::I8(_) | MatlabType::BOOL(_) => 1,
+        MatlabType::U16(_) | MatlabType::I16(_) => 2,
+        MatlabType::U32(_) | MatlabType::I32(_) | MatlabType::F32(_) => 4,
+        MatlabType::UTF8(_) | MatlabType::UTF16(_) => size_of::<char>() as u64,
+        MatlabType::U64(_) | MatlabType::I64(_) | MatlabType::F64(_) => 8,
+    };
+    size * value.len() as u64
+}
+
+impl MatFile {
+    ///
+    /// Statistics of all variables, see [`MatVariable::stats`].
+    ///
+    /// Variables are at depth `0`, like variables on their own.
+    ///
+    /// ```
+    /// use matrw::{matfile, matvar};
+    ///
+    /// let matfile = matfile!(small: matvar!(1.0), large: matvar!(vec![0.5; 1000]));
+    ///
+    /// let largest = matfile.iter().max_by_key(|(_, var)| var.stats().numeric_bytes).unwrap();
+    /// assert_eq!(largest.0, "large");
+    /// assert_eq!(matfile.stats().numeric_bytes, 8 * 1001);
+    /// ```
+    ///
+    pub fn stats(&self) -> VariableStats {
+        let mut stats = VariableStats::default();
+        for (_, var) in self.iter() {
+            stats.merge(var.stats());
+        }
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{matfile, matvar};
+
+    #[test]
+    fn stats_of_nested_variables() {
+        let cell = MatVariable::from(vec![matvar!("ab"), matvar!({ x: [true, false] })]);
+        let var = matvar!({
+            s: [{ a: 1i8 }, { a: 2i8 }],
+            c: cell,
+            z: matvar!([1f32, 2f32]),
+        });
+
+        let stats = var.stats();
+        // Root, structure array, two fields of it, cell, its two elements, field x and z
+        assert_eq!(stats.arrays, 9);
+        assert_eq!(stats.elements, 2 + 2 + 2 + 2);
+        assert_eq!(stats.numeric_bytes, 2 + 4 + 2 + 8);
+        // c{2}.x
+        assert_eq!(stats.max_depth, 3);
+
+        let structs = stats.by_class[&VariableClass::Struct];
+        assert_eq!((structs.arrays, structs.elements, structs.bytes), (3, 4, 0));
+        assert_eq!(stats.by_class[&VariableClass::Int8].elements, 2);
+        assert_eq!(stats.by_class[&VariableClass::Char].bytes, 4);
+        assert_eq!(stats.by_class[&VariableClass::Single].bytes, 8);
+        assert_eq!(stats.by_class.keys().next(), Some(&VariableClass::Struct));
+
+        let sparse = matvar!([[1.0, 0.0], [0.0, 2.0]]).to_sparse().unwrap();
+        let stats = sparse.stats();
+        assert_eq!(stats.elements, 2);
+        assert_eq!(stats.numeric_bytes, 2 * 8 + 2 * 8 + 3 * 8);
+        assert_eq!(stats.max_depth, 0);
+
+        assert_eq!(MatVariable::Null.stats(), VariableStats::default());
+
+        let matfile = matfile!(a: matvar!({ b: { c: 1.0 } }), d: sparse);
+        let stats = matfile.stats();
+        assert_eq!(stats.arrays, 4);
+        assert_eq!(stats.max_depth, 2);
+        assert_eq!(stats.numeric_bytes, 8 + 56);
+    }
+
+    #[test]
+    fn memory_usage() {
+        let node = size_of::<MatVariable>() as u64;
+        let dims = 2 * size_of::<usize>() as u64;
+        assert_eq!(MatVariable::Null.memory_usage(), node);
+        assert_eq!(matvar!([0.5, 1.5]).memory_usage(), node + dims + 16);
+        assert_eq!(matvar!("ab").memory_usage(), node + dims + 8);
+
+        // Field names and the slots of the fields
+        let field = (size_of::<String>() + 1) as u64 + node;
+        let scalar = dims + 8;
+        assert_eq!(
+            matvar!({ a: 0.5, b: 1.5 }).memory_usage(),
+            node + 2 * (field + scalar)
+        );
+
+        let cell = MatVariable::from(vec![matvar!(0.5), matvar!(1.5)]);
+        assert_eq!(cell.memory_usage(), node + dims + 2 * (node + scalar));
+    }
+
+    #[cfg(feature = "deflate")]
+    #[test]
+    fn memory_usage_lazy() {
+        use crate::{load_matfile_from_u8, load_matfile_lazy_from_u8, save_matfile_v7_to_u8};
+
+        // Compressed variables of lazy files count with their compressed data until accessed
+        let data = save_matfile_v7_to_u8(matfile!(a: matvar!(vec![0.5; 10000])), true).unwrap();
+        let lazy = load_matfile_lazy_from_u8(&data).unwrap();
+        let compressed = lazy.memory_usage();
+        assert!(compressed < 10000);
+        lazy.get("a");
+        assert!(lazy.memory_usage() > compressed + 80000);
+        assert!(load_matfile_from_u8(&data).unwrap().memory_usage() > 80000);
+    }
+}
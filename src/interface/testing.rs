@@ -0,0 +1,469 @@
+//! Utilities for testing code working with MAT-files.
+//!
+//! [`assert_matvar_eq!`](crate::assert_matvar_eq) and
+//! [`assert_matfile_eq!`](crate::assert_matfile_eq) compare variables and files and list the
+//! differing nodes by path instead of printing both values, see [`matvar_diff`].
+//! With the `serde` feature, [`assert_roundtrip`] checks that a data model survives a MAT-file.
+//! With the `testing` feature, `strategies` provides [`proptest`](https://docs.rs/proptest)
+//! strategies for arbitrary MAT-file variables.
+
+#[cfg(feature = "serde")]
+use std::fmt::Debug;
+
+#[cfg(feature = "serde")]
+use serde::{Serialize, de::DeserializeOwned};
+
+#[cfg(feature = "serde")]
+use crate::{
+    MatrwError,
+    interface::fileio::{load_matfile_from_u8, save_matfile_v7_to_u8},
+    interface::serde::{de::from_matfile, ser::to_matfile},
+};
+
+use crate::interface::matfile::MatFile;
+use crate::interface::types::matlab_types::{MatlabType, MatlabTypeMarker, NumericVisitor};
+use crate::interface::variable::MatVariable;
+
+#[cfg(feature = "testing")]
+pub mod strategies;
+
+/// Values shown per array in differences
+const SHOWN_VALUES: usize = 8;
+
+/// First values of an array as text
+struct FirstValues;
+
+impl NumericVisitor for FirstValues {
+    type Output = Vec<String>;
+
+    fn visit<T: MatlabTypeMarker>(self, values: &[T]) -> Vec<String> {
+        values.iter().take(SHOWN_VALUES).map(T::to_string).collect()
+    }
+}
+
+/// Class, size and leading values of `var`, e.g. `double 1x3 [1, 2, 3]`
+fn describe(var: &MatVariable) -> String {
+    let dim = var.dim();
+    let class = var.class_name().unwrap_or("nothing");
+    match var {
+        MatVariable::Null => "nothing".to_string(),
+        MatVariable::NumericArray(arr) if matches!(arr.value, MatlabType::UTF8(_) | MatlabType::UTF16(_)) => {
+            match var.to_matlab_string() {
+                Some(text) => format!("char {} '{}'", dim, text),
+                None => format!("char {}", dim),
+            }
+        }
+        MatVariable::NumericArray(arr) => {
+            let real = arr.value.visit_numeric(FirstValues);
+            let values: Vec<String> = match &arr.value_cmp {
+                Some(cmp) => real
+                    .iter()
+                    .zip(cmp.visit_numeric(FirstValues))
+                    .map(|(re, im)| match im.strip_prefix('-') {
+                        Some(im) => format!("{}-{}i", re, im),
+                        None => format!("{}+{}i", re, im),
+                    })
+                    .collect(),
+                None => real,
+            };
+            let more = if arr.value.len() > SHOWN_VALUES {
+                ", …"
+            } else {
+                ""
+            };
+            let complex = if arr.is_complex() { "complex " } else { "" };
+            format!("{}{} {} [{}{}]", complex, class, dim, values.join(", "), more)
+        }
+        MatVariable::SparseArray(arr) => format!("sparse {} {} with {} non-zeros", class, dim, arr.nnz()),
+        MatVariable::Structure(s) => format!("struct with fields {:?}", s.fieldnames()),
+        MatVariable::StructureArray(s) => format!("struct {} with fields {:?}", dim, s.fieldnames()),
+        _ => format!("{} {}", class, dim),
+    }
+}
+
+/// Path of the field `name` of the node at `path`
+fn field_path(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", path, name)
+    }
+}
+
+fn changed(path: &str, actual: &MatVariable, expected: &MatVariable) -> String {
+    let (actual, expected) = (describe(actual), describe(expected));
+    let storage = if actual == expected {
+        " (stored differently)"
+    } else {
+        ""
+    };
+    let at = if path.is_empty() {
+        String::new()
+    } else {
+        format!("{}: ", path)
+    };
+    format!("{}expected {}, found {}{}", at, expected, actual, storage)
+}
+
+fn diff(path: &str, actual: &MatVariable, expected: &MatVariable, differences: &mut Vec<String>) {
+    if actual == expected {
+        return;
+    }
+
+    let before = differences.len();
+    match (actual, expected) {
+        (MatVariable::Structure(a), MatVariable::Structure(b)) => {
+            for name in b.value.keys().filter(|name| !a.value.contains_key(*name)) {
+                differences.push(format!("{}: missing", field_path(path, name)));
+            }
+            for name in a.value.keys().filter(|name| !b.value.contains_key(*name)) {
+                differences.push(format!("{}: unexpected field", field_path(path, name)));
+            }
+            for (name, var) in a.value.iter() {
+                if let Some(other) = b.value.get(name) {
+                    diff(&field_path(path, name), var, other, differences);
+                }
+            }
+        }
+        (MatVariable::StructureArray(a), MatVariable::StructureArray(b))
+            if a.dim == b.dim && a.fieldnames() == b.fieldnames() =>
+        {
+            for (i, (x, y)) in a.value.iter().zip(b.value.iter()).enumerate() {
+                diff(&format!("{}({})", path, i + 1), x, y, differences);
+            }
+        }
+        (MatVariable::CellArray(a), MatVariable::CellArray(b)) if a.dim == b.dim => {
+            for (i, (x, y)) in a.value.iter().zip(b.value.iter()).enumerate() {
+                diff(&format!("{}{{{}}}", path, i + 1), x, y, differences);
+            }
+        }
+        (MatVariable::NumericArray(a), MatVariable::NumericArray(b))
+            if a.dim == b.dim
+                && a.value.len() > 1
+                && std::mem::discriminant(&a.value) == std::mem::discriminant(&b.value)
+                && a.is_complex() == b.is_complex() =>
+        {
+            let element = |arr: &crate::NumericArray, i| {
+                (
+                    arr.value.clone_at_index(i),
+                    arr.value_cmp.as_ref().map(|cmp| cmp.clone_at_index(i)),
+                )
+            };
+            let first = (0..a.value.len()).find(|&i| element(a, i) != element(b, i));
+            let mut line = changed(path, actual, expected);
+            if let Some(i) = first {
+                line.push_str(&format!(", first difference at element {}", i + 1));
+            }
+            differences.push(line);
+        }
+        _ => {}
+    }
+
+    // The node itself differs, if none of its children do, e.g. in field order
+    if differences.len() == before {
+        differences.push(changed(path, actual, expected));
+    }
+}
+
+///
+/// Differences between `actual` and `expected`, one line per differing node, empty if they are
+/// equal.
+///
+/// Nodes are named by their path in MATLAB syntax, e.g. `s.a` or `c{2}`, with class, size and the
+/// leading values of both. Only the innermost differing nodes are listed.
+///
+/// Example
+/// ```
+/// use matrw::matvar;
+/// use matrw::testing::matvar_diff;
+///
+/// let actual = matvar!({ a: [1.0, 2.0, 5.0], b: "x" });
+/// let expected = matvar!({ a: [1.0, 2.0, 3.0], c: "x" });
+///
+/// assert_eq!(
+///     matvar_diff(&actual, &expected),
+///     vec![
+///         "c: missing",
+///         "b: unexpected field",
+///         "a: expected double 1x3 [1, 2, 3], found double 1x3 [1, 2, 5], first difference at element 3",
+///     ]
+/// );
+/// ```
+///
+pub fn matvar_diff(actual: &MatVariable, expected: &MatVariable) -> Vec<String> {
+    let mut differences = Vec::new();
+    diff("", actual, expected, &mut differences);
+    differences
+}
+
+///
+/// Differences between the variables of `actual` and `expected`, see [`matvar_diff`]. Paths
+/// start with the variable name.
+///
+pub fn matfile_diff(actual: &MatFile, expected: &MatFile) -> Vec<String> {
+    let mut differences = Vec::new();
+    for name in expected.names().filter(|name| !actual.contains(name)) {
+        differences.push(format!("{}: missing", name));
+    }
+    for name in actual.names().filter(|name| !expected.contains(name)) {
+        differences.push(format!("{}: unexpected variable", name));
+    }
+    for (name, var) in actual.iter() {
+        if let Some(other) = expected.get(name) {
+            diff(name, var, other, &mut differences);
+        }
+    }
+
+    let common = |m: &MatFile, other: &MatFile| {
+        m.names()
+            .filter(|name| other.contains(name))
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+    };
+    if differences.is_empty() && common(actual, expected) != common(expected, actual) {
+        differences.push(format!(
+            "expected variables {:?}, found {:?}",
+            expected.names().collect::<Vec<_>>(),
+            actual.names().collect::<Vec<_>>()
+        ));
+    }
+    differences
+}
+
+/// Assert that two [`MatVariable`]s are equal, like [`assert_eq!`].
+///
+/// On failure, the panic message lists the differing nodes with their path, class, size and
+/// leading values, see [`testing::matvar_diff`](crate::testing::matvar_diff), instead of the
+/// debug output of both variables. An optional format string is added to the message.
+///
+/// # Panics
+///
+/// Panics, if the variables differ.
+///
+/// Example
+/// ```
+/// use matrw::{assert_matvar_eq, matvar};
+///
+/// assert_matvar_eq!(matvar!({ a: [1.0, 2.0] }), matvar!({ a: [1.0, 2.0] }));
+///
+/// let result = std::panic::catch_unwind(|| {
+///     assert_matvar_eq!(matvar!({ a: [1.0, 2.0] }), matvar!({ a: [1.0, 3.0] }), "case {}", 1);
+/// });
+/// assert!(result.is_err());
+/// ```
+#[macro_export]
+macro_rules! assert_matvar_eq {
+    (@report $differences:expr, $message:expr) => {{
+        let differences: Vec<String> = $differences;
+        if !differences.is_empty() {
+            panic!(
+                "assertion `actual == expected` failed{}\n  {}",
+                $message,
+                differences.join("\n  ")
+            );
+        }
+    }};
+    ($actual:expr, $expected:expr $(,)?) => {
+        $crate::assert_matvar_eq!(@report $crate::testing::matvar_diff(&$actual, &$expected), "")
+    };
+    ($actual:expr, $expected:expr, $($arg:tt)+) => {
+        $crate::assert_matvar_eq!(@report $crate::testing::matvar_diff(&$actual, &$expected), format!(": {}", format_args!($($arg)+)))
+    };
+}
+
+/// Assert that two [`MatFile`]s have the same variables in the same order, like [`assert_eq!`].
+///
+/// On failure, the panic message lists missing and unexpected variables and the differing nodes
+/// of the others, see [`testing::matfile_diff`](crate::testing::matfile_diff).
+///
+/// # Panics
+///
+/// Panics, if the files differ.
+///
+/// Example
+/// ```
+/// use matrw::{assert_matfile_eq, load_matfile_from_u8, matfile, matvar, save_matfile_v7_to_u8};
+///
+/// let data = save_matfile_v7_to_u8(matfile!(x: matvar!([1, 2])), true).unwrap();
+/// assert_matfile_eq!(load_matfile_from_u8(&data).unwrap(), matfile!(x: matvar!([1, 2])));
+/// ```
+#[macro_export]
+macro_rules! assert_matfile_eq {
+    ($actual:expr, $expected:expr $(,)?) => {
+        $crate::assert_matvar_eq!(@report $crate::testing::matfile_diff(&$actual, &$expected), "")
+    };
+    ($actual:expr, $expected:expr, $($arg:tt)+) => {
+        $crate::assert_matvar_eq!(@report $crate::testing::matfile_diff(&$actual, &$expected), format!(": {}", format_args!($($arg)+)))
+    };
+}
+
+/// Serialize `value` into an in-memory MAT-file, load it again and deserialize the result.
+#[cfg(feature = "serde")]
+pub fn roundtrip<T>(value: &T, compress: bool) -> Result<T, MatrwError>
+where
+    T: Serialize + DeserializeOwned,
+{
+    let data = save_matfile_v7_to_u8(to_matfile(value)?, compress)?;
+    let matfile = load_matfile_from_u8(&data)?;
+    from_matfile(&matfile)
+}
+
+/// Assert that `value` survives a round trip through a MAT-file unchanged.
+///
+/// The value is written with and without compression. Use this to check that a data model
+/// is fully supported by [`crate::to_matfile`] and [`crate::from_matfile`].
+///
+/// # Panics
+///
+/// Panics, if serialization, writing, reading or deserialization fails, or if the result
+/// differs from `value`.
+///
+/// ```
+/// use matrw::testing::assert_roundtrip;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize, PartialEq, Debug)]
+/// struct Model {
+///     gain: f64,
+///     samples: Vec<i32>,
+///     label: String,
+/// }
+///
+/// assert_roundtrip(&Model {
+///     gain: 1.5,
+///     samples: vec![1, 2, 3],
+///     label: "test".to_string(),
+/// });
+/// ```
+#[cfg(feature = "serde")]
+pub fn assert_roundtrip<T>(value: &T)
+where
+    T: Serialize + DeserializeOwned + PartialEq + Debug,
+{
+    for compress in [false, true] {
+        match roundtrip(value, compress) {
+            Ok(result) => assert_eq!(
+                &result, value,
+                "round trip changed value (compress = {})",
+                compress
+            ),
+            Err(err) => panic!("round trip failed (compress = {}): {}", compress, err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod diff_tests {
+    use super::*;
+    use crate::{matfile, matvar};
+
+    #[test]
+    fn matvar_diff_paths() {
+        assert!(matvar_diff(&matvar!({ a: 1.0 }), &matvar!({ a: 1.0 })).is_empty());
+
+        let actual = matvar!({ s: [{ x: 1.0 }, { x: 2i8 }], c: ["ab", (1.0, -2.0)] });
+        let expected = matvar!({ s: [{ x: 1.0 }, { x: 2.0 }], c: ["ac", (1.0, 2.0)] });
+        assert_eq!(
+            matvar_diff(&actual, &expected),
+            vec![
+                "s(2).x: expected double 1x1 [2], found int8 1x1 [2]",
+                "c{1}: expected char 1x2 'ac', found char 1x2 'ab', first difference at element 2",
+                "c{2}: expected complex double 1x1 [1+2i], found complex double 1x1 [1-2i]",
+            ]
+        );
+
+        // Containers differing themselves
+        assert_eq!(
+            matvar_diff(&matvar!({ a: 1.0, b: 2.0 }), &matvar!({ b: 2.0, a: 1.0 })),
+            vec!["expected struct with fields [\"b\", \"a\"], found struct with fields [\"a\", \"b\"]"]
+        );
+        assert_eq!(
+            matvar_diff(&MatVariable::from(vec![1.0; 9]), &matvar!([1.0, 2.0])),
+            vec!["expected double 1x2 [1, 2], found double 1x9 [1, 1, 1, 1, 1, 1, 1, 1, …]"]
+        );
+        assert_eq!(
+            matvar_diff(&MatVariable::Null, &matvar!([])),
+            vec!["expected double 0x0 [], found nothing"]
+        );
+    }
+
+    #[test]
+    fn matfile_diff_variables() {
+        let actual = matfile!(a: matvar!(1.0), b: matvar!(2.0), d: matvar!(4.0));
+        let expected = matfile!(a: matvar!(1.0), b: matvar!(3.0), c: matvar!(4.0));
+        assert_eq!(
+            matfile_diff(&actual, &expected),
+            vec![
+                "c: missing",
+                "d: unexpected variable",
+                "b: expected double 1x1 [3], found double 1x1 [2]",
+            ]
+        );
+
+        let reordered = matfile!(b: matvar!(3.0), a: matvar!(1.0));
+        let expected = matfile!(a: matvar!(1.0), b: matvar!(3.0));
+        assert_eq!(
+            matfile_diff(&reordered, &expected),
+            vec!["expected variables [\"a\", \"b\"], found [\"b\", \"a\"]"]
+        );
+        assert_matfile_eq!(matfile!(a: matvar!(1.0)), matfile!(a: matvar!(1.0)));
+    }
+
+    #[test]
+    #[should_panic(expected = "assertion `actual == expected` failed: case 3\n  a: expected double 1x1 [2]")]
+    fn assert_matvar_eq_message() {
+        assert_matvar_eq!(matvar!({ a: 1.0 }), matvar!({ a: 2.0 }), "case {}", 3);
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+    use crate::SparseMatrix;
+    use serde::Deserialize;
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Inner {
+        m: Vec<Vec<f64>>,
+        names: Vec<String>,
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Model {
+        a: u16,
+        b: Vec<f32>,
+        c: char,
+        d: bool,
+        s: Inner,
+        sp: SparseMatrix<f64>,
+    }
+
+    #[test]
+    fn model_roundtrip() {
+        assert_roundtrip(&Model {
+            a: 3,
+            b: vec![1.0, 2.5],
+            c: 'x',
+            d: true,
+            s: Inner {
+                m: vec![vec![1.0, 2.0], vec![3.0, 4.0]],
+                names: vec!["a".to_string(), "bc".to_string()],
+            },
+            sp: SparseMatrix::new(2, 2, vec![1], vec![0, 1, 1], vec![5.0]).unwrap(),
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "round trip")]
+    fn lossy_roundtrip() {
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Lossy {
+            // Nested Vecs are written as one array, `[[[]]]` as 1x0, which is read as `[[]]`
+            v: Vec<Vec<Vec<f64>>>,
+        }
+
+        assert_roundtrip(&Lossy {
+            v: vec![vec![vec![]]],
+        });
+    }
+}
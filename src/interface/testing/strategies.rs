@@ -0,0 +1,218 @@
+//! [`proptest`](mod@proptest) strategies generating arbitrary valid [`MatVariable`]s.
+//!
+//! Available with the `testing` feature. The generated variables can be written to MAT-files
+//! and read back without loss, which makes them suitable for property tests of code consuming
+//! MAT data.
+//!
+//! ```
+//! use matrw::testing::strategies::arb_matvariable;
+//! use matrw::{MatFile, load_matfile_from_u8, save_matfile_v7_to_u8};
+//! use proptest::prelude::*;
+//!
+//! proptest!(ProptestConfig::with_cases(8), |(var in arb_matvariable())| {
+//!     let mut matfile = MatFile::new();
+//!     matfile.insert("a", var.clone());
+//!
+//!     let data = save_matfile_v7_to_u8(matfile, false).unwrap();
+//!     let loaded = load_matfile_from_u8(&data).unwrap();
+//!     prop_assert_eq!(&loaded["a"], &var);
+//! });
+//! ```
+
+use indexmap::IndexMap;
+use proptest::collection::vec;
+use proptest::prelude::*;
+
+use crate::interface::helper::is_valid_variable_name;
+use crate::{
+    CellArray, MatFile, MatVariable, MatlabType, NumericArray, SparseArray, Structure, StructureArray,
+};
+
+/// Maximum number of elements along a single dimension.
+const MAX_DIM: usize = 4;
+
+/// Valid MATLAB variable and field names.
+pub fn arb_name() -> impl Strategy<Value = String> {
+    "[a-zA-Z][a-zA-Z0-9_]{0,15}".prop_filter("MATLAB keyword", |name| is_valid_variable_name(name))
+}
+
+/// Array dimensions with two or three non-empty dimensions.
+pub fn arb_dim() -> impl Strategy<Value = Vec<usize>> {
+    prop_oneof![
+        3 => vec(1..=MAX_DIM, 2),
+        1 => vec(1..=MAX_DIM, 3),
+    ]
+}
+
+/// Values of `n` elements of any numeric class. Floats are finite, characters are ASCII, as
+/// other characters are not written to MAT-files.
+fn arb_real(n: usize) -> impl Strategy<Value = MatlabType> {
+    prop_oneof![
+        vec(any::<u8>(), n).prop_map(MatlabType::U8),
+        vec(any::<i8>(), n).prop_map(MatlabType::I8),
+        vec(any::<u16>(), n).prop_map(MatlabType::U16),
+        vec(any::<i16>(), n).prop_map(MatlabType::I16),
+        vec(any::<u32>(), n).prop_map(MatlabType::U32),
+        vec(any::<i32>(), n).prop_map(MatlabType::I32),
+        vec(any::<u64>(), n).prop_map(MatlabType::U64),
+        vec(any::<i64>(), n).prop_map(MatlabType::I64),
+        vec(proptest::num::f32::NORMAL | proptest::num::f32::ZERO, n).prop_map(MatlabType::F32),
+        vec(proptest::num::f64::NORMAL | proptest::num::f64::ZERO, n).prop_map(MatlabType::F64),
+        vec(proptest::char::range('\u{0}', '\u{7f}'), n).prop_map(MatlabType::UTF8),
+        vec(any::<bool>(), n).prop_map(MatlabType::BOOL),
+    ]
+}
+
+/// Values of `n` elements with the same numeric class as `like`.
+fn arb_real_like(like: &MatlabType, n: usize) -> BoxedStrategy<MatlabType> {
+    match like {
+        MatlabType::U8(_) => vec(any::<u8>(), n).prop_map(MatlabType::U8).boxed(),
+        MatlabType::I8(_) => vec(any::<i8>(), n).prop_map(MatlabType::I8).boxed(),
+        MatlabType::U16(_) => vec(any::<u16>(), n).prop_map(MatlabType::U16).boxed(),
+        MatlabType::I16(_) => vec(any::<i16>(), n).prop_map(MatlabType::I16).boxed(),
+        MatlabType::U32(_) => vec(any::<u32>(), n).prop_map(MatlabType::U32).boxed(),
+        MatlabType::I32(_) => vec(any::<i32>(), n).prop_map(MatlabType::I32).boxed(),
+        MatlabType::U64(_) => vec(any::<u64>(), n).prop_map(MatlabType::U64).boxed(),
+        MatlabType::I64(_) => vec(any::<i64>(), n).prop_map(MatlabType::I64).boxed(),
+        MatlabType::F32(_) => vec(proptest::num::f32::NORMAL | proptest::num::f32::ZERO, n)
+            .prop_map(MatlabType::F32)
+            .boxed(),
+        MatlabType::F64(_) => vec(proptest::num::f64::NORMAL | proptest::num::f64::ZERO, n)
+            .prop_map(MatlabType::F64)
+            .boxed(),
+        _ => unreachable!("Only numeric classes can be complex"),
+    }
+}
+
+/// Numeric arrays of random class, shape and complexity.
+///
+/// Character and logical arrays are always real.
+pub fn arb_numeric() -> BoxedStrategy<MatVariable> {
+    arb_dim()
+        .prop_flat_map(|dim| {
+            let n: usize = dim.iter().product();
+            (Just(dim), arb_real(n), any::<bool>())
+        })
+        .prop_flat_map(|(dim, value, complex)| {
+            let n = value.len();
+            let cmp = match value {
+                MatlabType::UTF8(_) | MatlabType::UTF16(_) | MatlabType::BOOL(_) => Just(None).boxed(),
+                _ if complex => arb_real_like(&value, n).prop_map(Some).boxed(),
+                _ => Just(None).boxed(),
+            };
+            (Just(dim), Just(value), cmp)
+        })
+        .prop_map(|(dim, value, cmp)| {
+            MatVariable::NumericArray(NumericArray::new(dim, value, cmp).expect("Valid numeric array"))
+        })
+        .boxed()
+}
+
+/// Sparse arrays of class double (real or complex) or logical.
+pub fn arb_sparse() -> BoxedStrategy<MatVariable> {
+    (1..=MAX_DIM, 1..=MAX_DIM)
+        .prop_flat_map(|(m, n)| (Just(m), vec(vec(any::<bool>(), m), n), 0..3u8))
+        .prop_flat_map(|(m, pattern, kind)| {
+            let mut ir = Vec::new();
+            let mut jc = vec![0];
+            for col in &pattern {
+                ir.extend(col.iter().enumerate().filter(|(_, nz)| **nz).map(|(i, _)| i));
+                jc.push(ir.len());
+            }
+            let nnz = ir.len();
+            let nonzero = || proptest::num::f64::NORMAL;
+            let (value, cmp) = match kind {
+                0 => (
+                    Just(MatlabType::BOOL(vec![true; nnz])).boxed(),
+                    Just(None).boxed(),
+                ),
+                1 => (
+                    vec(nonzero(), nnz).prop_map(MatlabType::F64).boxed(),
+                    Just(None).boxed(),
+                ),
+                _ => (
+                    vec(nonzero(), nnz).prop_map(MatlabType::F64).boxed(),
+                    vec(nonzero(), nnz).prop_map(|v| Some(MatlabType::F64(v))).boxed(),
+                ),
+            };
+            (Just((m, pattern.len(), ir, jc)), value, cmp)
+        })
+        .prop_map(|((m, n, ir, jc), value, cmp)| {
+            MatVariable::SparseArray(SparseArray::new(m, n, ir, jc, value, cmp).expect("Valid sparse array"))
+        })
+        .boxed()
+}
+
+/// Structures with up to four fields, whose values are generated by `value`.
+pub fn arb_structure(value: impl Strategy<Value = MatVariable> + 'static) -> BoxedStrategy<MatVariable> {
+    vec((arb_name(), value), 0..4)
+        .prop_map(|fields| {
+            let map: IndexMap<String, MatVariable> = fields.into_iter().collect();
+            MatVariable::Structure(Structure::new(map))
+        })
+        .boxed()
+}
+
+/// Structure arrays with at least one field and two elements, whose values are generated by
+/// `value`. Scalar structure arrays are generated by [`arb_structure`].
+pub fn arb_structure_array(
+    value: impl Strategy<Value = MatVariable> + Clone + 'static,
+) -> BoxedStrategy<MatVariable> {
+    let dim = arb_dim().prop_filter("Scalar structure array", |dim| dim.iter().product::<usize>() > 1);
+    (vec(arb_name(), 1..4), dim)
+        .prop_map(|(mut names, dim)| {
+            names.sort();
+            names.dedup();
+            (names, dim)
+        })
+        .prop_flat_map(move |(names, dim)| {
+            let n = names.len() * dim.iter().product::<usize>();
+            (Just(names), Just(dim), vec(value.clone(), n))
+        })
+        .prop_map(|(names, dim, values)| {
+            MatVariable::StructureArray(
+                StructureArray::new(dim, names, values).expect("Valid structure array"),
+            )
+        })
+        .boxed()
+}
+
+/// Cell arrays whose elements are generated by `value`.
+pub fn arb_cell(value: impl Strategy<Value = MatVariable> + Clone + 'static) -> BoxedStrategy<MatVariable> {
+    arb_dim()
+        .prop_flat_map(move |dim| {
+            let n: usize = dim.iter().product();
+            (Just(dim), vec(value.clone(), n))
+        })
+        .prop_map(|(dim, values)| {
+            MatVariable::CellArray(CellArray::new(dim, values).expect("Valid cell array"))
+        })
+        .boxed()
+}
+
+/// Arbitrary variables, nesting structures, structure arrays and cell arrays up to three levels
+/// deep.
+pub fn arb_matvariable() -> BoxedStrategy<MatVariable> {
+    let leaf = prop_oneof![4 => arb_numeric(), 1 => arb_sparse()];
+    leaf.prop_recursive(3, 32, 4, |inner| {
+        prop_oneof![
+            arb_structure(inner.clone()),
+            arb_structure_array(inner.clone()),
+            arb_cell(inner),
+        ]
+    })
+    .boxed()
+}
+
+/// MAT-files with up to four arbitrary variables.
+pub fn arb_matfile() -> BoxedStrategy<MatFile> {
+    vec((arb_name(), arb_matvariable()), 0..4)
+        .prop_map(|vars| {
+            let mut matfile = MatFile::new();
+            for (name, var) in vars {
+                matfile.insert(&name, var);
+            }
+            matfile
+        })
+        .boxed()
+}
@@ -0,0 +1,329 @@
+//! Time and data arrays of Simulink and MATLAB time series.
+//!
+//! Simulink logs signals in several layouts. [`MatVariable::decode_timeseries`] brings the ones
+//! matrw can read into a [`Structure`] with the fields `Time` and `Data`:
+//! - the "Structure with time" format of Simulink, with the fields `time` and `signals`, whose
+//!   field `values` holds the data,
+//! - structures with the fields `Time` and `Data`, like `struct(ts)` returns for a `timeseries`
+//!   object `ts`,
+//! - structures with the fields `t` and `y`, as written by [`TimeSeries::to_matvar`].
+//!
+//! `timeseries` and `Simulink.SimulationData.Dataset` objects themselves are MCOS objects. Their
+//! properties are stored in the subsystem data of the file, which matrw does not decode, so they
+//! are read as [`MatVariable::Unsupported`]. Saving them with `struct(ts)` or in the "Structure
+//! with time" format makes them readable.
+//!
+//! For writing, [`TimeSeries::to_matvar`] stores a signal as a structure with the fields `name`,
+//! `t`, `y` and `units`, which MATLAB scripts can plot with `plot(s.t, s.y)`, and
+//! [`TimeSeries::decode`] reads any of the layouts above back.
+
+use indexmap::IndexMap;
+
+use crate::interface::error::MatrwError;
+use crate::interface::types::array::ensure_matching_dimension;
+use crate::interface::types::matlab_types::MatlabType;
+use crate::interface::types::numeric_array::NumericArray;
+use crate::interface::types::structure::Structure;
+use crate::interface::variable::{FromMatVariable, MatVariable};
+
+/// Signal sampled at the times `time`
+///
+/// Example
+/// ```
+/// use matrw::{FromMatVariable, NumericArray, TimeSeries, matfile};
+///
+/// let data = NumericArray::from_row_major(vec![3, 1], vec![20.5, 21.0, 21.4]).unwrap();
+/// let ts = TimeSeries::new("temperature", vec![0.0, 60.0, 120.0], data)
+///     .unwrap()
+///     .units("degC");
+///
+/// let matfile = matfile!(temperature: ts.to_matvar());
+/// assert_eq!(matfile["temperature"]["t"].to_vec_f64().unwrap(), vec![0.0, 60.0, 120.0]);
+/// assert_eq!(String::from_matvar(&matfile["temperature"]["units"]).unwrap(), "degC");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeSeries {
+    /// Name of the signal, empty if unknown
+    pub name: String,
+    /// Sample times, one per row of `data`
+    pub time: Vec<f64>,
+    /// Samples, one row per time step
+    pub data: NumericArray,
+    /// Units of the samples, empty if unknown
+    pub units: String,
+}
+
+impl TimeSeries {
+    ///
+    /// Time series without units.
+    ///
+    /// Returns [`MatrwError::TypeConstruction`], if the first dimension of `data` differs from
+    /// the number of sample times. Row vectors of data with one column per sample time are
+    /// transposed into columns.
+    ///
+    pub fn new(name: &str, time: Vec<f64>, mut data: NumericArray) -> Result<Self, MatrwError> {
+        if data.dim.len() == 2 && data.dim[0] == 1 && data.dim[0] != time.len() && data.dim[1] == time.len() {
+            data.dim.swap(0, 1);
+        }
+        ensure_matching_dimension(data.dim[0], time.len())?;
+
+        Ok(Self {
+            name: name.to_string(),
+            time,
+            data,
+            units: String::new(),
+        })
+    }
+
+    /// Set the units of the samples.
+    pub fn units(mut self, units: &str) -> Self {
+        self.units = units.to_string();
+        self
+    }
+
+    ///
+    /// Structure with the fields `name`, `t`, `y` and `units`. `t` is a column vector, `y` the
+    /// data with one row per time step.
+    ///
+    pub fn to_matvar(&self) -> MatVariable {
+        let n = self.time.len();
+        let t = NumericArray::new(vec![n, 1], MatlabType::from(self.time.clone()), None)
+            .expect("Dimension matches the number of times");
+
+        let mut fields = IndexMap::new();
+        fields.insert("name".to_string(), MatVariable::from(self.name.as_str()));
+        fields.insert("t".to_string(), MatVariable::NumericArray(t));
+        fields.insert("y".to_string(), MatVariable::NumericArray(self.data.clone()));
+        fields.insert("units".to_string(), MatVariable::from(self.units.as_str()));
+        MatVariable::Structure(Structure::new(fields))
+    }
+
+    ///
+    /// Time series of a logged signal, see [`MatVariable::decode_timeseries`].
+    ///
+    /// The fields `name` or `Name` and `units` are read, if they are character arrays, as
+    /// [`TimeSeries::to_matvar`] writes them. Returns [`MatrwError::AccessError`] like
+    /// [`MatVariable::decode_timeseries`], and [`MatrwError::TypeConstruction`], if the data
+    /// doesn't have one row per sample time.
+    ///
+    /// Example
+    /// ```
+    /// use matrw::{NumericArray, TimeSeries};
+    ///
+    /// let data = NumericArray::from_row_major(vec![2, 1], vec![1.5, 2.5]).unwrap();
+    /// let ts = TimeSeries::new("level", vec![0.0, 1.0], data).unwrap().units("m");
+    ///
+    /// assert_eq!(TimeSeries::decode(&ts.to_matvar()).unwrap(), ts);
+    /// ```
+    ///
+    pub fn decode(var: &MatVariable) -> Result<Self, MatrwError> {
+        let decoded = var.decode_timeseries()?;
+        let (Some(MatVariable::NumericArray(time)), Some(MatVariable::NumericArray(data))) =
+            (decoded.get("Time"), decoded.get("Data"))
+        else {
+            unreachable!("Decoded time series have numeric time and data");
+        };
+        let text = |names: &[&str]| {
+            names
+                .iter()
+                .find_map(|name| var.get(*name).and_then(String::from_matvar))
+                .unwrap_or_default()
+        };
+
+        let time = MatVariable::NumericArray(time.clone())
+            .to_vec_f64()
+            .ok_or_else(|| MatrwError::AccessError("Time is not real".to_string()))?;
+        let ts = Self::new(&text(&["name", "Name"]), time, data.clone())?;
+        Ok(ts.units(&text(&["units"])))
+    }
+}
+
+impl MatVariable {
+    ///
+    /// Time and data arrays of a logged signal, as a [`Structure`] with the fields `Time` and
+    /// `Data`, see the [module documentation](crate::interface::timeseries) for the recognized
+    /// layouts.
+    ///
+    /// `Time` must be a numeric vector, whose length matches the first or the last dimension of
+    /// the numeric `Data`. A single time step must match the first dimension, as any array ends
+    /// in a dimension of 1. Returns [`MatrwError::AccessError`] otherwise, for structures logging
+    /// more than one signal, and for MCOS objects.
+    ///
+    /// Example
+    /// ```
+    /// use matrw::matvar;
+    ///
+    /// // Simulink "Structure with time" format
+    /// let logged = matvar!({
+    ///     time: [0.0, 0.1, 0.2],
+    ///     signals: {
+    ///         values: [1.0, 2.0, 4.0],
+    ///         dimensions: 1.0,
+    ///         label: "speed",
+    ///     },
+    ///     blockName: "model/Scope",
+    /// });
+    ///
+    /// let ts = logged.decode_timeseries().unwrap();
+    /// assert_eq!(ts.fieldnames(), vec!["Time", "Data"]);
+    /// assert_eq!(ts.get("Data").unwrap().to_vec_f64().unwrap(), vec![1.0, 2.0, 4.0]);
+    /// ```
+    ///
+    pub fn decode_timeseries(&self) -> Result<Structure, MatrwError> {
+        let err = |msg: &str| Err(MatrwError::AccessError(msg.to_string()));
+
+        let s = match self {
+            MatVariable::Structure(s) => s,
+            MatVariable::Unsupported => {
+                return err("Object is not decoded, timeseries objects must be saved as structures");
+            }
+            _ => return err("Variable is no logged signal"),
+        };
+
+        let fields = |time, data| s.get(time).zip(s.get(data));
+        let (time, data) = if let Some(pair) = fields("Time", "Data").or_else(|| fields("t", "y")) {
+            pair
+        } else if let Some((time, signals)) = fields("time", "signals") {
+            match signals {
+                MatVariable::Structure(signals) => match signals.get("values") {
+                    Some(values) => (time, values),
+                    None => return err("Field 'signals' has no field 'values'"),
+                },
+                MatVariable::StructureArray(signals) => {
+                    return Err(MatrwError::AccessError(format!(
+                        "Structure logs {} signals, decode them one by one",
+                        signals.value.len()
+                    )));
+                }
+                _ => return err("Field 'signals' is not a structure"),
+            }
+        } else {
+            return err("Variable is no logged signal");
+        };
+
+        let (MatVariable::NumericArray(t), MatVariable::NumericArray(d)) = (time, data) else {
+            return err("Time and data are not numeric arrays");
+        };
+        let n = t.value.len();
+        if t.dim.iter().filter(|&&d| d > 1).count() > 1 {
+            return err("Time is not a vector");
+        }
+        let time_last = n > 1 && d.dim.last() == Some(&n);
+        if d.dim.first() != Some(&n) && !time_last {
+            return Err(MatrwError::AccessError(format!(
+                "Data of size {:?} does not match {} time steps",
+                d.dim, n
+            )));
+        }
+
+        let mut fields = IndexMap::new();
+        fields.insert("Time".to_string(), time.clone());
+        fields.insert("Data".to_string(), data.clone());
+        Ok(Structure::new(fields))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MatDim, load_matfile_from_u8, matfile, matvar, save_matfile_v7_to_u8};
+
+    #[test]
+    fn decode_timeseries() {
+        // struct(ts) of a timeseries with two channels, time along the first dimension
+        let ts = matvar!({
+            Name: "pos",
+            Time: [0.0, 1.0, 2.0],
+            Data: [[1.0, 10.0], [2.0, 20.0], [3.0, 30.0]],
+        });
+        let data = save_matfile_v7_to_u8(matfile!(ts: ts), false).unwrap();
+        let ts = load_matfile_from_u8(&data).unwrap()["ts"]
+            .decode_timeseries()
+            .unwrap();
+        assert_eq!(ts.fieldnames(), vec!["Time", "Data"]);
+        assert_eq!(ts.get("Data").unwrap().dim(), MatDim::from(vec![3, 2]));
+
+        // Time along the last dimension
+        let last = matvar!({ Time: [0.0, 1.0], Data: [[1.0, 2.0], [3.0, 4.0], [5.0, 6.0]] });
+        assert!(last.decode_timeseries().is_ok());
+
+        let err = |var: MatVariable| var.decode_timeseries().unwrap_err().to_string();
+        assert_eq!(
+            err(matvar!({ Time: [0.0, 1.0, 2.0], Data: [1.0, 2.0] })),
+            "Data of size [1, 2] does not match 3 time steps"
+        );
+        let column = NumericArray::from_row_major(vec![2, 1], vec![1.0, 2.0]).unwrap();
+        let mut one_step = IndexMap::new();
+        one_step.insert("Time".to_string(), matvar!(0.0));
+        one_step.insert("Data".to_string(), MatVariable::NumericArray(column));
+        assert_eq!(
+            err(MatVariable::Structure(Structure::new(one_step))),
+            "Data of size [2, 1] does not match 1 time steps"
+        );
+        assert!(
+            matvar!({ Time: [0.0], Data: [1.0, 2.0] })
+                .decode_timeseries()
+                .is_ok()
+        );
+        assert_eq!(
+            err(matvar!({ time: [0.0], signals: 1.0 })),
+            "Field 'signals' is not a structure"
+        );
+        assert_eq!(
+            err(matvar!({ time: [0.0], signals: [{ values: 1.0 }, { values: 2.0 }] })),
+            "Structure logs 2 signals, decode them one by one"
+        );
+        assert_eq!(
+            err(MatVariable::Unsupported),
+            "Object is not decoded, timeseries objects must be saved as structures"
+        );
+        assert_eq!(err(matvar!(1.0)), "Variable is no logged signal");
+    }
+
+    #[test]
+    fn time_series_to_matvar() {
+        let data = NumericArray::from_row_major(vec![2, 2], vec![1i16, 2, 3, 4]).unwrap();
+        let ts = TimeSeries::new("xy", vec![0.0, 0.5], data).unwrap().units("mm");
+        let data = save_matfile_v7_to_u8(matfile!(xy: ts.to_matvar()), false).unwrap();
+        let xy = &load_matfile_from_u8(&data).unwrap()["xy"];
+
+        assert_eq!(xy.fieldnames().unwrap(), vec!["name", "t", "y", "units"]);
+        assert_eq!(String::from_matvar(&xy["name"]).unwrap(), "xy");
+        assert_eq!(xy["t"].dim(), [2, 1]);
+        assert_eq!(xy["y"].to_vec_i16().unwrap(), vec![1, 3, 2, 4]);
+        assert_eq!(String::from_matvar(&xy["units"]).unwrap(), "mm");
+
+        // Row vectors are transposed, other shapes must match the time steps
+        let row = NumericArray::from_row_major(vec![1, 3], vec![1.0, 2.0, 3.0]).unwrap();
+        let ts = TimeSeries::new("r", vec![0.0, 1.0, 2.0], row).unwrap();
+        assert_eq!(ts.data.dim, [3, 1]);
+        assert_eq!(ts.units, "");
+        let wide = NumericArray::from_row_major(vec![2, 3], vec![0.0; 6]).unwrap();
+        assert!(TimeSeries::new("w", vec![0.0, 1.0, 2.0], wide).is_err());
+
+        // One sample time with several channels is a row already
+        let channels = NumericArray::from_row_major(vec![1, 3], vec![1.0, 2.0, 3.0]).unwrap();
+        let ts = TimeSeries::new("c", vec![0.0], channels).unwrap();
+        assert_eq!(ts.data.dim, [1, 3]);
+        let short = NumericArray::from_row_major(vec![1, 2], vec![1.0, 2.0]).unwrap();
+        assert!(TimeSeries::new("s", vec![0.0, 1.0, 2.0], short).is_err());
+    }
+
+    #[test]
+    fn time_series_round_trip() {
+        let data = NumericArray::from_row_major(vec![3, 2], vec![1i16, 2, 3, 4, 5, 6]).unwrap();
+        let ts = TimeSeries::new("xy", vec![0.0, 0.5, 1.0], data)
+            .unwrap()
+            .units("mm");
+        let data = save_matfile_v7_to_u8(matfile!(xy: ts.to_matvar()), false).unwrap();
+        let xy = &load_matfile_from_u8(&data).unwrap()["xy"];
+
+        assert_eq!(xy.decode_timeseries().unwrap().fieldnames(), vec!["Time", "Data"]);
+        assert_eq!(TimeSeries::decode(xy).unwrap(), ts);
+
+        // struct(ts) names its signal with `Name`, and has no units
+        let ts = TimeSeries::decode(&matvar!({ Name: "pos", Time: [0.0, 1.0], Data: [1.0, 2.0] })).unwrap();
+        assert_eq!((ts.name.as_str(), ts.units.as_str()), ("pos", ""));
+        assert_eq!(ts.data.dim, [2, 1]);
+    }
+}
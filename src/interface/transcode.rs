@@ -0,0 +1,324 @@
+//! Rewriting of MAT-files at the level of data elements.
+//!
+//! [`transcode`] copies the variables of a MAT-file into a new file, compressing, decompressing
+//! or recompressing them and dropping variables by name. Variables are never parsed, only their
+//! names are read from the headers of their elements, which makes this much faster than loading
+//! and saving a file.
+
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Cursor, Read, Seek, SeekFrom, Write};
+
+use binrw::{BinReaderExt, BinWrite, Endian};
+
+use crate::interface::error::MatrwError;
+use crate::interface::fileio::subsystem_offset;
+use crate::parser::header::{self, MatFileHeader, MatFileVerFlag};
+use crate::parser::v7::flags::MatFileDataTypes;
+use crate::parser::v7::types::compressed_array::{
+    compressed_variable_name, matrix_name, read_bytes, read_u32,
+};
+use crate::parser::v7::types::subelements::tag::{SMALL_DATA_MAX, padding, split_small_tag};
+use crate::parser::v7::zlib;
+
+/// Options for [`transcode`]
+///
+/// By default every variable is copied unchanged.
+///
+/// Example
+/// ```
+/// use matrw::{TranscodeOptions, load_matfile_from_u8, matfile, matvar, save_matfile_v7_to_u8, transcode_u8};
+///
+/// let data = save_matfile_v7_to_u8(matfile!(a: matvar!(vec![0.0; 1000]), b: matvar!("b")), false).unwrap();
+///
+/// let options = TranscodeOptions::new().compress(true).drop_variable("b");
+/// let transcoded = transcode_u8(&data, &options).unwrap();
+/// assert!(transcoded.len() < data.len());
+///
+/// let matfile = load_matfile_from_u8(&transcoded).unwrap();
+/// assert_eq!(matfile.iter().count(), 1);
+/// assert_eq!(matfile["a"], matvar!(vec![0.0; 1000]));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TranscodeOptions {
+    compress: Option<bool>,
+    level: Option<u32>,
+    drop: HashSet<String>,
+}
+
+impl TranscodeOptions {
+    /// Default options: variables are copied as they are.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Compress all variables, or decompress all variables with `false`.
+    pub fn compress(mut self, compress: bool) -> Self {
+        self.compress = Some(compress);
+        self
+    }
+
+    /// Compress with the level `level` from 0 to 9, larger levels are 9.
+    ///
+    /// Variables, which are compressed in the written file, are recompressed, also if they are
+    /// compressed already. Without this option, compressed variables are copied and variables are
+    /// compressed with the level used by [`crate::save_matfile_v7`].
+    pub fn level(mut self, level: u32) -> Self {
+        self.level = Some(level.min(9));
+        self
+    }
+
+    /// Do not copy the variable `name`.
+    pub fn drop_variable(mut self, name: &str) -> Self {
+        self.drop.insert(name.to_string());
+        self
+    }
+}
+
+///
+/// Rewrite the MAT-file `path_in` into `path_out` with `options`.
+///
+/// Data elements, which are not variables, are copied unchanged. Subsystem data is copied behind
+/// the variables, only keep it, if no variables referring to it are dropped.
+///
+pub fn transcode(path_in: &str, path_out: &str, options: &TranscodeOptions) -> Result<(), MatrwError> {
+    let file = File::open(path_in)?;
+    let size = file.metadata()?.len();
+    let mut reader = BufReader::new(file);
+    let mut writer = BufWriter::new(File::create(path_out)?);
+
+    transcode_elements(&mut reader, size, &mut writer, options)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Rewrite the MAT-file `data` into a buffer with `options`, see [`transcode`]
+pub fn transcode_u8(data: &[u8], options: &TranscodeOptions) -> Result<Vec<u8>, MatrwError> {
+    let mut writer = Cursor::new(Vec::new());
+    transcode_elements(&mut Cursor::new(data), data.len() as u64, &mut writer, options)?;
+    Ok(writer.into_inner())
+}
+
+fn transcode_elements<R: Read + Seek, W: Write + Seek>(
+    reader: &mut R,
+    size: u64,
+    writer: &mut W,
+    options: &TranscodeOptions,
+) -> Result<(), MatrwError> {
+    let mut data = [0u8; header::HEADER_SIZE];
+    reader.read_exact(&mut data)?;
+    let matheader = Cursor::new(&data).read_le::<MatFileHeader>()?;
+    if matheader.matfile_ver == MatFileVerFlag::V73 {
+        return Err(MatrwError::MatFile73Error);
+    }
+    let endian = matheader.matfile_endian;
+    writer.write_all(&data)?;
+
+    // Variables end where the subsystem starts, reading ends when no further element starts
+    let offset = subsystem_offset(&matheader, size);
+    let end = offset.unwrap_or(size);
+    while reader.stream_position()? + 4 <= end {
+        let data_type = read_u32(reader, endian)?;
+        if split_small_tag(data_type).is_some() {
+            let data = read_bytes(reader, SMALL_DATA_MAX as u64)?;
+            data_type.write_options(writer, endian, ())?;
+            writer.write_all(&data)?;
+            continue;
+        }
+
+        let num_bytes = read_u32(reader, endian)?;
+        let data = read_bytes(reader, num_bytes as u64)?;
+        let compressed = data_type == MatFileDataTypes::MiCOMPRESSED as u32;
+        if !compressed {
+            // The last element of a file may lack the padding
+            reader.seek(SeekFrom::Current(padding(num_bytes, false) as i64))?;
+        }
+
+        let name = if compressed {
+            compressed_variable_name(&data, endian).ok().flatten()
+        } else if data_type == MatFileDataTypes::MiMATRIX as u32 {
+            matrix_name(&mut data.as_slice(), endian).ok().flatten()
+        } else {
+            None
+        };
+        if name.is_some_and(|name| options.drop.contains(&name)) {
+            continue;
+        }
+
+        let is_variable = compressed || data_type == MatFileDataTypes::MiMATRIX as u32;
+        let compress = options.compress.unwrap_or(compressed) && is_variable;
+        match (compressed, compress) {
+            (true, true) if options.level.is_none() => write_element(writer, endian, data_type, &data)?,
+            (true, _) => {
+                let mut raw = Vec::new();
+                zlib::decompress(&data, &mut raw)?;
+                if compress {
+                    write_compressed(writer, endian, &raw, options)?;
+                } else {
+                    // Compressed elements may lack the padding of the contained element
+                    raw.resize(raw.len().next_multiple_of(8), 0);
+                    writer.write_all(&raw)?;
+                }
+            }
+            (false, true) => {
+                let mut raw = Cursor::new(Vec::new());
+                write_element(&mut raw, endian, data_type, &data)?;
+                write_compressed(writer, endian, &raw.into_inner(), options)?;
+            }
+            (false, false) => write_element(writer, endian, data_type, &data)?,
+        }
+    }
+
+    if let Some(offset) = offset {
+        let start = writer.stream_position()?;
+        reader.seek(SeekFrom::Start(offset))?;
+        std::io::copy(reader, writer)?;
+
+        writer.seek(SeekFrom::Start(header::HEADER_SUBSYSTEM_DATA_OFFSET_POS as u64))?;
+        start.write_options(writer, endian, ())?;
+        writer.seek(SeekFrom::End(0))?;
+    }
+    Ok(())
+}
+
+/// Write an element in the normal format, padded unless it is compressed.
+fn write_element<W: Write + Seek>(
+    writer: &mut W,
+    endian: Endian,
+    data_type: u32,
+    data: &[u8],
+) -> Result<(), MatrwError> {
+    let num_bytes = data.len() as u32;
+    data_type.write_options(writer, endian, ())?;
+    num_bytes.write_options(writer, endian, ())?;
+    writer.write_all(data)?;
+    if data_type != MatFileDataTypes::MiCOMPRESSED as u32 {
+        writer.write_all(&[0; 8][..padding(num_bytes, false) as usize])?;
+    }
+    Ok(())
+}
+
+/// Write the uncompressed element `raw` in a compressed element.
+fn write_compressed<W: Write + Seek>(
+    writer: &mut W,
+    endian: Endian,
+    raw: &[u8],
+    options: &TranscodeOptions,
+) -> Result<(), MatrwError> {
+    let compressed = zlib::compress_with_level(raw, options.level.unwrap_or(zlib::DEFAULT_LEVEL))?;
+    if u32::try_from(compressed.len()).is_err() {
+        return Err(MatrwError::TypeConstruction(
+            "Compressed variable exceeds the maximum element size of 4 GiB.".to_string(),
+        ));
+    }
+    write_element(writer, endian, MatFileDataTypes::MiCOMPRESSED as u32, &compressed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MatFile, load_matfile_from_u8, matfile, matvar, save_matfile_v7_to_u8};
+
+    fn variables() -> MatFile {
+        matfile!(
+            a: matvar!(vec![0.5; 500]),
+            b: matvar!("text"),
+            s: matvar!({ x: [1.0, 2.0], c: ["a", "b"] }),
+        )
+    }
+
+    /// Data types of the top-level elements
+    fn data_types(data: &[u8]) -> Vec<u32> {
+        let mut reader = Cursor::new(data);
+        reader.seek(SeekFrom::Start(header::HEADER_SIZE as u64)).unwrap();
+        let mut types = Vec::new();
+        while (reader.position() as usize) < data.len() {
+            types.push(read_u32(&mut reader, Endian::Little).unwrap());
+            let num_bytes = read_u32(&mut reader, Endian::Little).unwrap();
+            let padding = match types.last() {
+                Some(15) => 0,
+                _ => padding(num_bytes, false),
+            };
+            reader
+                .seek(SeekFrom::Current((num_bytes + padding) as i64))
+                .unwrap();
+        }
+        types
+    }
+
+    #[test]
+    fn compression_changes() {
+        let uncompressed = save_matfile_v7_to_u8(variables(), false).unwrap();
+        let compressed = save_matfile_v7_to_u8(variables(), true).unwrap();
+
+        // Copied unchanged by default
+        assert_eq!(
+            transcode_u8(&uncompressed, &TranscodeOptions::new()).unwrap(),
+            uncompressed
+        );
+        assert_eq!(
+            transcode_u8(&compressed, &TranscodeOptions::new()).unwrap(),
+            compressed
+        );
+
+        // Same bytes as written by matrw
+        let options = TranscodeOptions::new().compress(true);
+        assert_eq!(transcode_u8(&uncompressed, &options).unwrap(), compressed);
+        let options = TranscodeOptions::new().compress(false);
+        assert_eq!(transcode_u8(&compressed, &options).unwrap(), uncompressed);
+
+        let options = TranscodeOptions::new().level(0);
+        let stored = transcode_u8(&compressed, &options).unwrap();
+        assert_eq!(data_types(&stored), vec![15, 15, 15]);
+        let expected = load_matfile_from_u8(&uncompressed).unwrap();
+        let matfile = load_matfile_from_u8(&stored).unwrap();
+        for (name, var) in expected.iter() {
+            assert_eq!(&matfile[name.as_str()], var, "{name}");
+        }
+    }
+
+    #[test]
+    fn drop_variables() {
+        for compress in [false, true] {
+            let data = save_matfile_v7_to_u8(variables(), compress).unwrap();
+            let options = TranscodeOptions::new()
+                .drop_variable("b")
+                .drop_variable("missing");
+            let matfile = load_matfile_from_u8(&transcode_u8(&data, &options).unwrap()).unwrap();
+
+            let names: Vec<&String> = matfile.iter().map(|(name, _)| name).collect();
+            assert_eq!(names, vec!["a", "s"]);
+            assert_eq!(matfile["s"]["x"], matvar!([1.0, 2.0]));
+        }
+    }
+
+    #[test]
+    fn files_with_subsystem() {
+        let mut data = save_matfile_v7_to_u8(variables(), false).unwrap();
+        let subsystem = b"subsystem data\0\0".to_vec();
+        let offset = data.len() as u64;
+        data.extend(&subsystem);
+        let pos = header::HEADER_SUBSYSTEM_DATA_OFFSET_POS;
+        data[pos..pos + 8].copy_from_slice(&offset.to_le_bytes());
+
+        let transcoded = transcode_u8(&data, &TranscodeOptions::new().compress(true)).unwrap();
+        let matfile = load_matfile_from_u8(&transcoded).unwrap();
+        assert_eq!(matfile.subsystem_data(), Some(subsystem.as_slice()));
+        assert_eq!(matfile["b"], matvar!("text"));
+    }
+
+    #[test]
+    fn transcode_files() {
+        let path_in = std::env::temp_dir().join("matrw_transcode_in.mat");
+        let path_out = std::env::temp_dir().join("matrw_transcode_out.mat");
+        std::fs::write(&path_in, save_matfile_v7_to_u8(variables(), false).unwrap()).unwrap();
+
+        let options = TranscodeOptions::new().compress(true).level(1);
+        transcode(path_in.to_str().unwrap(), path_out.to_str().unwrap(), &options).unwrap();
+        let data = std::fs::read(&path_out).unwrap();
+        assert_eq!(load_matfile_from_u8(&data).unwrap()["b"], matvar!("text"));
+
+        let _ = std::fs::remove_file(path_in);
+        let _ = std::fs::remove_file(path_out);
+    }
+}
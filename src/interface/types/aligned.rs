@@ -0,0 +1,164 @@
+//! Module aligned
+//!
+//! This module defines the struct [`AlignedBuffer`], an owned slice with a chosen alignment.
+//!
+//! The values of a [`NumericArray`] are stored in a `Vec<T>`, which is aligned like `T`.
+//! Consumers requiring a larger alignment, e.g. 64 bytes for SIMD or FFTW, get a copy in an
+//! [`AlignedBuffer`] from [`NumericArray::to_aligned`].
+//!
+
+use std::alloc::{self, Layout};
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::ptr::{self, NonNull};
+use std::slice;
+
+use crate::MatrwError;
+use crate::interface::types::matlab_types::{MatlabType, MatlabTypeMarker, VariableClass};
+use crate::interface::types::numeric_array::NumericArray;
+
+/// Owned slice of `T`, whose first element is aligned to at least [`AlignedBuffer::align`]
+/// bytes
+///
+/// Example
+/// ```
+/// use matrw::{AlignedBuffer, matvar};
+///
+/// let arr = matvar!([1.0, 2.0, 3.0]);
+/// let data: AlignedBuffer<f64> = arr.as_numeric().unwrap().to_aligned(64).unwrap();
+/// assert_eq!(data.as_ptr() as usize % 64, 0);
+/// assert_eq!(&*data, &[1.0, 2.0, 3.0]);
+/// ```
+pub struct AlignedBuffer<T> {
+    ptr: NonNull<T>,
+    len: usize,
+    layout: Layout,
+}
+
+// The buffer owns its values like a `Box<[T]>`
+unsafe impl<T: Send> Send for AlignedBuffer<T> {}
+unsafe impl<T: Sync> Sync for AlignedBuffer<T> {}
+
+impl<T: Copy> AlignedBuffer<T> {
+    ///
+    /// Copy `values` into a buffer aligned to `align` bytes.
+    ///
+    /// Returns [`MatrwError::TypeConstruction`], if `align` is not a power of two or the buffer
+    /// exceeds the address space.
+    ///
+    pub fn from_slice(values: &[T], align: usize) -> Result<Self, MatrwError> {
+        let layout = Layout::array::<T>(values.len())
+            .and_then(|layout| layout.align_to(align))
+            .map_err(|_| {
+                MatrwError::TypeConstruction(format!(
+                    "Cannot allocate {} values aligned to {} bytes.",
+                    values.len(),
+                    align
+                ))
+            })?;
+
+        let ptr = if layout.size() == 0 {
+            NonNull::new(ptr::without_provenance_mut(layout.align())).expect("Alignment is not zero")
+        } else {
+            let Some(ptr) = NonNull::new(unsafe { alloc::alloc(layout) }.cast::<T>()) else {
+                alloc::handle_alloc_error(layout);
+            };
+            unsafe { ptr::copy_nonoverlapping(values.as_ptr(), ptr.as_ptr(), values.len()) };
+            ptr
+        };
+
+        Ok(Self {
+            ptr,
+            len: values.len(),
+            layout,
+        })
+    }
+}
+
+impl<T> AlignedBuffer<T> {
+    /// Alignment of the buffer in bytes, at least the alignment of `T`
+    pub fn align(&self) -> usize {
+        self.layout.align()
+    }
+}
+
+impl<T> Drop for AlignedBuffer<T> {
+    fn drop(&mut self) {
+        if self.layout.size() != 0 {
+            unsafe { alloc::dealloc(self.ptr.as_ptr().cast(), self.layout) };
+        }
+    }
+}
+
+impl<T> Deref for AlignedBuffer<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl<T> DerefMut for AlignedBuffer<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        unsafe { slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for AlignedBuffer<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl NumericArray {
+    ///
+    /// Copy the real part of the array into a buffer aligned to `align` bytes, in column-major
+    /// order.
+    ///
+    /// Returns [`MatrwError::TypeConstruction`], if `T` does not match the class of the array or
+    /// `align` is not a power of two.
+    ///
+    pub fn to_aligned<T: MatlabTypeMarker>(&self, align: usize) -> Result<AlignedBuffer<T>, MatrwError> {
+        let Some(data) = T::inner_ref(&self.value) else {
+            return Err(MatrwError::TypeConstruction(format!(
+                "Cannot read '{}' array into '{}' buffer.",
+                VariableClass::of_type(&self.value),
+                VariableClass::of_type(&MatlabType::from(Vec::<T>::new()))
+            )));
+        };
+        AlignedBuffer::from_slice(data, align)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aligned_copies() {
+        for align in [1, 8, 64, 4096] {
+            let values: Vec<i16> = (0..100).collect();
+            let mut buf = AlignedBuffer::from_slice(&values, align).unwrap();
+            assert_eq!(buf.as_ptr() as usize % align, 0);
+            assert!(buf.align() >= align.max(2));
+            buf[0] = 7;
+            assert_eq!(&buf[1..], &values[1..]);
+
+            let empty = AlignedBuffer::<f64>::from_slice(&[], align).unwrap();
+            assert!(empty.is_empty());
+            assert_eq!(empty.as_ptr() as usize % align.max(8), 0);
+        }
+        assert!(AlignedBuffer::from_slice(&[1u8], 48).is_err());
+    }
+
+    #[test]
+    fn aligned_arrays() {
+        let arr = NumericArray::new(vec![2, 2], MatlabType::from(vec![1u32, 2, 3, 4]), None).unwrap();
+        let buf = arr.to_aligned::<u32>(64).unwrap();
+        assert_eq!(format!("{:?}", buf), "[1, 2, 3, 4]");
+        assert_eq!(
+            arr.to_aligned::<f64>(64).unwrap_err().to_string(),
+            "Type construction error Cannot read 'uint32' array into 'double' buffer."
+        );
+    }
+}
@@ -1,9 +1,11 @@
+use crate::interface::types::dim::MatDim;
+use crate::interface::types::matlab_types::MatlabType;
 use crate::{MatrwError, interface::variable::MatVariable};
 
 /// Trait methods that array types share
 pub trait ArrayType {
     /// Get the dimension of the array
-    fn dim(&self) -> &Vec<usize>;
+    fn dim(&self) -> &MatDim;
 
     /// Get a borrowed value from a column-major index
     fn get_ref_colmaj(&self, index: usize) -> Option<&MatVariable>;
@@ -23,27 +25,107 @@ pub trait ArrayType {
 
     /// Get column-major index from multi-dimensional index
     fn column_index(&self, idx: &[usize]) -> Option<usize> {
-        // The index must have the same size as dimension
-        if self.dim().len() != idx.len() {
-            return None;
-        }
-        // Index components cannot fall out of range
-        for (i, v) in idx.iter().enumerate() {
-            if self.dim()[i] <= *v {
-                return None;
-            }
+        sub2ind(self.dim(), idx)
+    }
+
+    /// Number of elements, the product of the dimensions, `usize::MAX` if it does not fit into
+    /// `usize`. Like [`crate::MatDim`], an array without dimensions counts as `0x0`.
+    fn numel(&self) -> usize {
+        match &self.dim()[..] {
+            [] => 0,
+            dim => dim.iter().fold(1, |n, &d| n.saturating_mul(d)),
         }
+    }
+
+    /// True for exactly one element, like MATLAB's `isscalar`
+    fn is_scalar(&self) -> bool {
+        self.numel() == 1
+    }
+
+    /// True for no elements, like MATLAB's `isempty`
+    fn is_empty(&self) -> bool {
+        self.numel() == 0
+    }
+}
 
-        let mut v_idx = 0;
-        let mut stride = 1;
+/// Convert zero-based subscripts into a column-major linear index.
+///
+/// This is the index arithmetic used by [`crate::OwnedIndex`]. Returns `None`, if the number of
+/// subscripts differs from the number of dimensions, a subscript is out of range or the index
+/// does not fit into `usize`.
+///
+/// ```
+/// use matrw::sub2ind;
+///
+/// // 2x3 matrix: element (1, 2) is the last one
+/// assert_eq!(sub2ind(&[2, 3], &[1, 2]), Some(5));
+/// assert_eq!(sub2ind(&[2, 3], &[0, 1]), Some(2));
+/// assert_eq!(sub2ind(&[2, 3], &[2, 0]), None);
+/// assert_eq!(sub2ind(&[usize::MAX, 3], &[0, 2]), None);
+/// ```
+pub fn sub2ind(dims: &[usize], subs: &[usize]) -> Option<usize> {
+    if dims.len() != subs.len() {
+        return None;
+    }
 
-        for (i, dim) in idx.iter().zip(self.dim().iter()) {
-            v_idx += i * stride;
-            stride *= dim;
+    let mut index: usize = 0;
+    // Only strides, which are used with nonzero subscripts, have to fit
+    let mut stride = Some(1usize);
+
+    for (&sub, &dim) in subs.iter().zip(dims) {
+        if sub >= dim {
+            return None;
         }
+        if sub > 0 {
+            index = index.checked_add(sub.checked_mul(stride?)?)?;
+        }
+        stride = stride.and_then(|s| s.checked_mul(dim));
+    }
+
+    Some(index)
+}
 
-        Some(v_idx)
+/// Convert a column-major linear index into zero-based subscripts, the inverse of [`sub2ind`].
+///
+/// Returns `None`, if `idx` exceeds the number of elements.
+///
+/// ```
+/// use matrw::ind2sub;
+///
+/// assert_eq!(ind2sub(&[2, 3], 5), Some(vec![1, 2]));
+/// assert_eq!(ind2sub(&[2, 3, 2], 7), Some(vec![1, 0, 1]));
+/// assert_eq!(ind2sub(&[2, 3], 6), None);
+/// ```
+pub fn ind2sub(dims: &[usize], idx: usize) -> Option<Vec<usize>> {
+    // An overflowing number of elements exceeds every index, unless a dimension is zero
+    if dims.contains(&0) || numel(dims).is_some_and(|n| idx >= n) {
+        return None;
     }
+
+    let mut rest = idx;
+    let subs = dims
+        .iter()
+        .map(|&dim| {
+            let sub = rest % dim;
+            rest /= dim;
+            sub
+        })
+        .collect();
+
+    Some(subs)
+}
+
+/// Number of elements of `dims`, `None` if it does not fit into `usize`.
+pub(crate) fn numel(dims: &[usize]) -> Option<usize> {
+    dims.iter().try_fold(1usize, |n, &d| n.checked_mul(d))
+}
+
+/// Number of elements of `dims`, [`MatrwError::TypeConstruction`] if it does not fit into
+/// `usize`.
+pub(crate) fn ensure_numel(dims: &[usize]) -> Result<usize, MatrwError> {
+    numel(dims).ok_or_else(|| {
+        MatrwError::TypeConstruction(format!("Number of elements of dimension {:?} overflows.", dims))
+    })
 }
 
 #[macro_export]
@@ -51,7 +133,7 @@ pub trait ArrayType {
 macro_rules! impl_Array_for {
     ($type:ty) => {
         impl ArrayType for $type {
-            fn dim(&self) -> &Vec<usize> {
+            fn dim(&self) -> &MatDim {
                 &self.dim
             }
 
@@ -88,14 +170,55 @@ pub fn ensure_matching_complex_size(value_len: usize, value_comp_len: usize) ->
     }
 }
 
-pub fn normalize_dimension(dim: Vec<usize>, value_len: usize) -> Vec<usize> {
+/// Logical and character arrays can't have an imaginary part, MAT-files have no such class
+pub fn ensure_complex_allowed(value: &MatlabType, is_complex: bool) -> Result<(), MatrwError> {
+    match value {
+        MatlabType::BOOL(_) if is_complex => Err(MatrwError::TypeConstruction(
+            "Logical arrays can't be complex.".to_string(),
+        )),
+        MatlabType::UTF8(_) | MatlabType::UTF16(_) if is_complex => Err(MatrwError::TypeConstruction(
+            "Character arrays can't be complex.".to_string(),
+        )),
+        _ => Ok(()),
+    }
+}
+
+pub fn normalize_dimension(dim: Vec<usize>, value_len: usize) -> MatDim {
     if dim.is_empty() || (dim.len() == 1 && dim[0] > 0) {
         // Normalize the dimension vector. 1D-arrays are treated as 2D-matrices in
         // MAT-files.
-        vec![1, value_len]
-    } else if dim.len() == 1 && dim[0] == 0 {
-        vec![0, 0]
+        MatDim::from_slice(&[1, value_len])
     } else {
-        dim
+        MatDim::new(dim)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subscripts_roundtrip() {
+        let dims = [3, 1, 4, 2];
+        for idx in 0..dims.iter().product() {
+            let subs = ind2sub(&dims, idx).unwrap();
+            assert_eq!(sub2ind(&dims, &subs), Some(idx));
+        }
+        assert_eq!(sub2ind(&dims, &[0, 0, 0]), None);
+        assert_eq!(ind2sub(&[0, 0], 0), None);
+    }
+
+    #[test]
+    fn subscripts_overflow() {
+        let huge = [usize::MAX / 2, 4];
+        assert_eq!(sub2ind(&huge, &[1, 0]), Some(1));
+        assert_eq!(sub2ind(&huge, &[1, 2]), Some(usize::MAX));
+        assert_eq!(sub2ind(&huge, &[0, 3]), None);
+        assert_eq!(sub2ind(&[usize::MAX, usize::MAX, 1], &[1, 1, 0]), None);
+        assert_eq!(sub2ind(&[usize::MAX, usize::MAX, 1], &[5, 0, 0]), Some(5));
+
+        assert_eq!(ind2sub(&huge, usize::MAX), Some(vec![1, 2]));
+        assert_eq!(ind2sub(&[usize::MAX, 2, 0], 1), None);
+        assert_eq!(numel(&huge), None);
     }
 }
@@ -0,0 +1,216 @@
+//! Module bitmask
+//!
+//! This module defines the struct [`BitMask`], logical values packed into 64-bit words.
+//!
+//! Logical arrays of MAT-files take one byte per element, in memory as [`MatlabType::BOOL`] as
+//! well. [`NumericArray::to_bitvec`] packs them to one bit per element, e.g. for large masks
+//! processed further, and [`NumericArray::from_bitvec`] unpacks them for writing. They are always
+//! available and don't depend on the [bitvec](https://docs.rs/bitvec) crate.
+//!
+
+use std::ops::{BitAndAssign, BitOrAssign, Not};
+
+use crate::MatrwError;
+use crate::interface::types::matlab_types::MatlabType;
+use crate::interface::types::numeric_array::NumericArray;
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// Logical values stored with one bit per element
+///
+/// Bit `i` is bit `i % 64` of word `i / 64`. Bits past the length in the last word are zero.
+///
+/// Example
+/// ```
+/// use matrw::{BitMask, NumericArray, matvar};
+///
+/// let mask = matvar!([[true, false], [false, true]]);
+/// let mut bits = mask.as_numeric().unwrap().to_bitvec().unwrap();
+/// assert_eq!(bits.count_ones(), 2);
+///
+/// bits.set(2, true);
+/// let mask = NumericArray::from_bitvec(vec![2, 2], &bits).unwrap();
+/// assert_eq!(mask.real_to_vec::<bool>(), Some(vec![true, false, true, true]));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct BitMask {
+    len: usize,
+    words: Vec<u64>,
+}
+
+impl BitMask {
+    /// `len` bits, all false
+    pub fn new(len: usize) -> Self {
+        Self {
+            len,
+            words: vec![0; len.div_ceil(WORD_BITS)],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Bit `index`, `None` if out of bounds
+    pub fn get(&self, index: usize) -> Option<bool> {
+        (index < self.len).then(|| self.words[index / WORD_BITS] >> (index % WORD_BITS) & 1 == 1)
+    }
+
+    ///
+    /// Set bit `index` to `value`.
+    ///
+    /// # Panics
+    ///
+    /// Panics, if `index` is out of bounds.
+    ///
+    pub fn set(&mut self, index: usize, value: bool) {
+        assert!(
+            index < self.len,
+            "Bit {} out of bounds of {} bits",
+            index,
+            self.len
+        );
+        let mask = 1 << (index % WORD_BITS);
+        if value {
+            self.words[index / WORD_BITS] |= mask;
+        } else {
+            self.words[index / WORD_BITS] &= !mask;
+        }
+    }
+
+    /// Number of true bits, like MATLAB's `nnz`
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// Bits in order
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = bool> + '_ {
+        (0..self.len).map(|i| self.words[i / WORD_BITS] >> (i % WORD_BITS) & 1 == 1)
+    }
+
+    /// Packed words, see [`BitMask`] for the layout
+    pub fn as_words(&self) -> &[u64] {
+        &self.words
+    }
+
+    fn ensure_same_len(&self, other: &BitMask) {
+        assert_eq!(self.len, other.len, "Bit masks of different length");
+    }
+}
+
+impl FromIterator<bool> for BitMask {
+    fn from_iter<I: IntoIterator<Item = bool>>(iter: I) -> Self {
+        let mut bits = BitMask::default();
+        for (i, value) in iter.into_iter().enumerate() {
+            if i % WORD_BITS == 0 {
+                bits.words.push(0);
+            }
+            bits.words[i / WORD_BITS] |= (value as u64) << (i % WORD_BITS);
+            bits.len = i + 1;
+        }
+        bits
+    }
+}
+
+/// Element-wise and, like MATLAB's `&`. Panics for masks of different length.
+impl BitAndAssign<&BitMask> for BitMask {
+    fn bitand_assign(&mut self, rhs: &BitMask) {
+        self.ensure_same_len(rhs);
+        self.words.iter_mut().zip(&rhs.words).for_each(|(a, b)| *a &= b);
+    }
+}
+
+/// Element-wise or, like MATLAB's `|`. Panics for masks of different length.
+impl BitOrAssign<&BitMask> for BitMask {
+    fn bitor_assign(&mut self, rhs: &BitMask) {
+        self.ensure_same_len(rhs);
+        self.words.iter_mut().zip(&rhs.words).for_each(|(a, b)| *a |= b);
+    }
+}
+
+/// Element-wise negation, like MATLAB's `~`
+impl Not for BitMask {
+    type Output = BitMask;
+
+    fn not(mut self) -> BitMask {
+        self.words.iter_mut().for_each(|w| *w = !*w);
+        let tail = self.len % WORD_BITS;
+        if let (Some(last), true) = (self.words.last_mut(), tail != 0) {
+            *last &= (1 << tail) - 1;
+        }
+        self
+    }
+}
+
+impl NumericArray {
+    ///
+    /// Values of a logical array packed into bits, in column-major order. `None` for other
+    /// classes, see [`BitMask`].
+    ///
+    pub fn to_bitvec(&self) -> Option<BitMask> {
+        match &self.value {
+            MatlabType::BOOL(values) => Some(values.iter().copied().collect()),
+            _ => None,
+        }
+    }
+
+    ///
+    /// Logical array of dimension `dim` with the values `bits` in column-major order.
+    ///
+    /// Returns [`MatrwError::TypeConstruction`], if the product of `dim` differs from the number
+    /// of bits, see [`NumericArray::new`].
+    ///
+    pub fn from_bitvec(dim: Vec<usize>, bits: &BitMask) -> Result<Self, MatrwError> {
+        NumericArray::new(dim, MatlabType::BOOL(bits.iter().collect()), None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bitmask_roundtrip() {
+        let values: Vec<bool> = (0..130).map(|i| i % 3 == 0).collect();
+        let arr = NumericArray::new(vec![10, 13], MatlabType::BOOL(values.clone()), None).unwrap();
+
+        let bits = arr.to_bitvec().unwrap();
+        assert_eq!(bits.len(), 130);
+        assert_eq!(bits.as_words().len(), 3);
+        assert_eq!(bits.count_ones(), 44);
+        assert_eq!(bits.get(129), Some(true));
+        assert_eq!(bits.get(130), None);
+        assert_eq!(NumericArray::from_bitvec(vec![10, 13], &bits).unwrap(), arr);
+        assert!(NumericArray::from_bitvec(vec![2, 2], &bits).is_err());
+
+        let doubles = NumericArray::new(vec![1, 1], MatlabType::F64(vec![1.0]), None).unwrap();
+        assert_eq!(doubles.to_bitvec(), None);
+        assert!(BitMask::new(0).is_empty());
+    }
+
+    #[test]
+    fn bitmask_ops() {
+        let mut a: BitMask = [true, true, false, false].into_iter().collect();
+        let b: BitMask = [true, false, true, false].into_iter().collect();
+
+        let mut or = a.clone();
+        or |= &b;
+        assert_eq!(or.iter().collect::<Vec<_>>(), [true, true, true, false]);
+        a &= &b;
+        assert_eq!(a.iter().collect::<Vec<_>>(), [true, false, false, false]);
+
+        // Bits past the length stay zero
+        let not = !BitMask::new(65);
+        assert_eq!(not.count_ones(), 65);
+        assert_eq!(not.as_words()[1], 1);
+
+        let mut bits = BitMask::new(3);
+        bits.set(1, true);
+        bits.set(1, false);
+        assert_eq!(bits.count_ones(), 0);
+    }
+}
@@ -1,37 +1,587 @@
+//! Module cell_array
+//!
+//! This module defines the struct [`CellArray`], a multidimensional array of arbitrary variables.
+//!
+//! Like [`crate::NumericArray`], the cells are stored in column-major order, the product of `dim`
+//! is the number of cells and a one-dimensional `dim` is normalized to a row vector, see
+//! [`CellArray::new`].
+//!
+//! Empty cells, whether read from a file or constructed, are `[]`, an empty 0x0 double array,
+//! see [`MatVariable::empty_value`].
+//!
+
+use indexmap::IndexSet;
+
 use crate::MatrwError;
 use crate::impl_Array_for;
+use crate::interface::helper::invalid_variable_name;
 use crate::interface::types::array::ArrayType;
 use crate::interface::types::array::ensure_matching_dimension;
+use crate::interface::types::array::ensure_numel;
 use crate::interface::types::array::normalize_dimension;
-use crate::interface::variable::MatVariable;
+use crate::interface::types::dim::MatDim;
+use crate::interface::types::matlab_types::MatlabType;
+use crate::interface::types::numeric_array::NumericArray;
+use crate::interface::types::structure::Structure;
+use crate::interface::types::structure_array::StructureArray;
+use crate::interface::variable::{FromMatVariable, MatVariable, with_article};
 use crate::parser::v7::types::cell_array::CellArray7;
 
+/// [`CellArray`] contains any kind of MatVariable in multidimensional arrays.
+///
+/// Example
+/// ```
+/// use matrw::{CellArray, MatVariable, matvar};
+///
+/// let cells = CellArray::new(vec![2, 1], vec![matvar!("a"), matvar!(1.0)]).unwrap();
+/// let var = MatVariable::CellArray(cells);
+///
+/// assert_eq!(var[1].to_f64(), Some(1.0));
+/// ```
 #[derive(Debug, Clone)]
 pub struct CellArray {
-    pub dim: Vec<usize>,
+    /// Dimensions, at least two
+    pub dim: MatDim,
+    /// Cells in column-major order
     pub value: Vec<MatVariable>,
 }
 
-/// [`CellArray`] contains any kind of MatVariable in multidimensional arrays.
-///
 impl CellArray {
+    ///
+    /// Cell array of dimension `dim` with the cells `value` in column-major order.
+    ///
+    /// [`MatVariable::Null`] cells are stored as `[]`, see [`MatVariable::empty_value`]. Returns
+    /// [`MatrwError::TypeConstruction`], if the product of `dim` differs from the number of
+    /// cells.
+    ///
     pub fn new(dim: Vec<usize>, value: Vec<MatVariable>) -> Result<Self, MatrwError> {
         if !dim.is_empty() {
-            ensure_matching_dimension(dim.iter().product::<usize>(), value.len())?;
+            ensure_matching_dimension(ensure_numel(&dim)?, value.len())?;
         }
 
         let dim = normalize_dimension(dim, value.len());
+        let value = value
+            .into_iter()
+            .map(|cell| match cell {
+                MatVariable::Null => MatVariable::empty_value(),
+                cell => cell,
+            })
+            .collect();
 
         Ok(Self { dim, value })
     }
+
+    /// Convert every cell into `T`, in column-major order.
+    ///
+    /// Fails with [`MatrwError::AccessError`] naming the first cell that cannot be converted.
+    /// Numeric cells must have the class of `T`, see [`FromMatVariable`] for the supported types.
+    ///
+    /// ```
+    /// use matrw::{matvar, CellArray, MatVariable};
+    ///
+    /// let MatVariable::CellArray(names) = matvar!(["ab", "c"]) else { unreachable!() };
+    /// assert_eq!(names.to_vec_of::<String>().unwrap(), vec!["ab", "c"]);
+    ///
+    /// let mixed = CellArray::new(vec![1, 3], vec![matvar!(1.0), matvar!("c"), matvar!(3.0)]).unwrap();
+    /// let err = mixed.to_vec_of::<f64>().unwrap_err();
+    /// assert_eq!(err.to_string(), "Cell 1 is not an f64 scalar");
+    /// ```
+    pub fn to_vec_of<T: FromMatVariable>(&self) -> Result<Vec<T>, MatrwError> {
+        self.value
+            .iter()
+            .enumerate()
+            .map(|(i, cell)| {
+                T::from_matvar(cell).ok_or_else(|| {
+                    MatrwError::AccessError(format!("Cell {} is not {}", i, with_article(&T::expected())))
+                })
+            })
+            .collect()
+    }
+
+    /// Transform every cell with `f`, keeping the dimensions, like MATLAB's `cellfun` with
+    /// `'UniformOutput', false`.
+    ///
+    /// Cells are passed in column-major order. The first error of `f` is returned, the remaining
+    /// cells are not transformed.
+    ///
+    /// ```
+    /// use matrw::{CellArray, FromMatVariable, MatVariable, MatrwError, matvar};
+    ///
+    /// let names = CellArray::new(vec![2, 1], vec![matvar!(" a "), matvar!("b ")]).unwrap();
+    ///
+    /// let trimmed = names
+    ///     .map(|cell| match String::from_matvar(&cell) {
+    ///         Some(s) => Ok(MatVariable::from(s.trim())),
+    ///         None => Err(MatrwError::AccessError("Not a string".to_string())),
+    ///     })
+    ///     .unwrap();
+    /// assert_eq!(trimmed.dim, vec![2, 1]);
+    /// assert_eq!(trimmed.value[0], matvar!("a"));
+    /// ```
+    pub fn map<F, E>(self, f: F) -> Result<CellArray, E>
+    where
+        F: FnMut(MatVariable) -> Result<MatVariable, E>,
+    {
+        Ok(CellArray {
+            dim: self.dim,
+            value: self.value.into_iter().map(f).collect::<Result<_, E>>()?,
+        })
+    }
+
+    /// Convert a cell array of scalar structures into a structure array of the same dimensions,
+    /// a frequent artifact of files written from lists of records.
+    ///
+    /// All structures must have the same fields, in any order. The elements take the field
+    /// order of the first cell. Fails with [`MatrwError::AccessError`] naming the first cell,
+    /// which is no scalar structure or has other fields. A cell array without cells gives a
+    /// structure array without fields.
+    ///
+    /// ```
+    /// use matrw::{CellArray, MatVariable, matvar};
+    ///
+    /// let cells = CellArray::new(vec![2, 1], vec![matvar!({ x: 1.0, y: 2.0 }), matvar!({ y: 4.0, x: 3.0 })]).unwrap();
+    ///
+    /// let s = cells.try_into_structure_array().unwrap();
+    /// assert_eq!(s.dim, vec![2, 1]);
+    /// assert_eq!(s.fieldnames(), vec!["x", "y"]);
+    /// assert_eq!(MatVariable::StructureArray(s)[1]["x"].to_f64(), Some(3.0));
+    /// ```
+    pub fn try_into_structure_array(self) -> Result<StructureArray, MatrwError> {
+        if self.value.is_empty() {
+            return StructureArray::new(self.dim.into_vec(), Vec::new(), Vec::new());
+        }
+
+        let mut fieldnames: Option<Vec<String>> = None;
+        let mut value = Vec::with_capacity(self.value.len());
+        for (i, cell) in self.value.into_iter().enumerate() {
+            let MatVariable::Structure(mut s) = cell else {
+                return Err(MatrwError::AccessError(format!(
+                    "Cell {} is not a scalar structure",
+                    i
+                )));
+            };
+            match &fieldnames {
+                None => fieldnames = Some(s.fieldnames()),
+                Some(names) => {
+                    let names: Vec<&str> = names.iter().map(String::as_str).collect();
+                    s.reorder_fields(&names).map_err(|_| {
+                        MatrwError::AccessError(format!(
+                            "Cell {} has fields {:?}, which differ from fields {:?} of the first cell",
+                            i,
+                            s.fieldnames(),
+                            names
+                        ))
+                    })?;
+                }
+            }
+            value.push(MatVariable::Structure(s));
+        }
+        StructureArray::from_structures(self.dim.into_vec(), value)
+    }
+
+    /// Convert a cell array of scalar structures with differing fields into a structure array
+    /// of the same dimensions, like MATLAB's `struct` with default values.
+    ///
+    /// The fields are the fields of all cells, in the order they first occur, and fields missing
+    /// in a cell are filled with `[]`. Fails with [`MatrwError::AccessError`] naming the first
+    /// cell, which is no scalar structure. See [`CellArray::try_into_structure_array`] for cells
+    /// which must share their fields.
+    ///
+    /// ```
+    /// use matrw::{MatVariable, matvar};
+    ///
+    /// let MatVariable::CellArray(records) = matvar!([{ id: 1.0 }, { id: 2.0, note: "late" }]) else { unreachable!() };
+    ///
+    /// let s = MatVariable::StructureArray(records.try_into_structure_array_filled().unwrap());
+    /// assert_eq!(s.fieldnames(), Some(vec!["id".to_string(), "note".to_string()]));
+    /// assert_eq!(s[0]["note"], matvar!([]));
+    /// ```
+    pub fn try_into_structure_array_filled(self) -> Result<StructureArray, MatrwError> {
+        let mut fieldnames = IndexSet::new();
+        let mut structures = Vec::with_capacity(self.value.len());
+        for (i, cell) in self.value.into_iter().enumerate() {
+            let MatVariable::Structure(s) = cell else {
+                return Err(MatrwError::AccessError(format!(
+                    "Cell {} is not a scalar structure",
+                    i
+                )));
+            };
+            fieldnames.extend(s.as_map().keys().cloned());
+            structures.push(s.into_map());
+        }
+
+        let value = structures
+            .into_iter()
+            .map(|mut fields| {
+                let filled = fieldnames
+                    .iter()
+                    .map(|name| {
+                        (
+                            name.clone(),
+                            fields.swap_remove(name).unwrap_or_else(MatVariable::empty_value),
+                        )
+                    })
+                    .collect();
+                MatVariable::Structure(Structure::new(filled))
+            })
+            .collect();
+        CellArray { dim: self.dim, value }.try_into_structure_array()
+    }
+
+    /// Structure of the rows of the cell array as fields `fieldnames`, like MATLAB's
+    /// `cell2struct(c, fieldnames, 1)`.
+    ///
+    /// A cell array of one column gives a [`MatVariable::Structure`], one of N columns an Nx1
+    /// [`MatVariable::StructureArray`], whose elements hold the columns. Returns
+    /// [`MatrwError::TypeConstruction`], if the cell array has more than two dimensions, the
+    /// number of field names differs from the number of rows, or a field name is invalid or
+    /// given twice.
+    ///
+    /// ```
+    /// use matrw::{CellArray, MatVariable, matvar};
+    ///
+    /// let cells = CellArray::new(vec![2, 1], vec![matvar!(7.0), matvar!("probe")]).unwrap();
+    /// let s = cells.to_struct(&["id", "name"]).unwrap();
+    /// assert_eq!(s, matvar!({ id: 7.0, name: "probe" }));
+    ///
+    /// // One structure per column
+    /// let cells = CellArray::new(vec![1, 2], vec![matvar!(1.0), matvar!(2.0)]).unwrap();
+    /// let s = cells.to_struct(&["x"]).unwrap();
+    /// assert_eq!(s.dim(), [2, 1]);
+    /// assert_eq!(s[1]["x"], matvar!(2.0));
+    /// ```
+    pub fn to_struct(&self, fieldnames: &[&str]) -> Result<MatVariable, MatrwError> {
+        let &[rows, cols] = &self.dim[..] else {
+            return Err(MatrwError::TypeConstruction(format!(
+                "Cell array of size {:?} is not a matrix.",
+                self.dim
+            )));
+        };
+        if fieldnames.len() != rows {
+            return Err(MatrwError::TypeConstruction(format!(
+                "Number of field names {} does not match the {} rows of the cell array.",
+                fieldnames.len(),
+                rows
+            )));
+        }
+        for (i, name) in fieldnames.iter().enumerate() {
+            if let Some(err) = invalid_variable_name(name) {
+                return Err(MatrwError::TypeConstruction(format!(
+                    "{}.",
+                    err.replacen("variable", "field", 1)
+                )));
+            }
+            if fieldnames[..i].contains(name) {
+                return Err(MatrwError::TypeConstruction(format!(
+                    "Field '{}' is given more than once.",
+                    name
+                )));
+            }
+        }
+
+        let structures: Vec<MatVariable> = (0..cols)
+            .map(|j| {
+                let column = &self.value[j * rows..(j + 1) * rows];
+                let fields = fieldnames
+                    .iter()
+                    .zip(column)
+                    .map(|(name, cell)| (name.to_string(), cell.clone()))
+                    .collect();
+                MatVariable::Structure(Structure::new(fields))
+            })
+            .collect();
+
+        match structures.len() {
+            1 => Ok(structures.into_iter().next().expect("One structure")),
+            0 => {
+                let names = fieldnames.iter().map(|name| name.to_string()).collect();
+                Ok(MatVariable::StructureArray(StructureArray::new(
+                    vec![0, 1],
+                    names,
+                    Vec::new(),
+                )?))
+            }
+            n => Ok(MatVariable::StructureArray(StructureArray::from_structures(
+                vec![n, 1],
+                structures,
+            )?)),
+        }
+    }
+
+    /// Replace nested cell arrays by their cells, recursively, giving a 1xN cell array.
+    ///
+    /// Cells are taken in column-major order, the cells of a nested cell array where it is
+    /// located, like MATLAB's `[c{:}]` applied until no cell array is left.
+    ///
+    /// ```
+    /// use matrw::{CellArray, MatVariable, matvar};
+    ///
+    /// let inner = CellArray::new(vec![1, 2], vec![matvar!(2.0), matvar!("b")]).unwrap();
+    /// let cells = CellArray::new(vec![1, 3], vec![matvar!(1.0), MatVariable::CellArray(inner), matvar!(3.0)]).unwrap();
+    ///
+    /// let flat = cells.flatten();
+    /// assert_eq!(flat.dim, vec![1, 4]);
+    /// assert_eq!(flat.value[2], matvar!("b"));
+    /// ```
+    pub fn flatten(self) -> CellArray {
+        fn collect(cells: Vec<MatVariable>, flat: &mut Vec<MatVariable>) {
+            for cell in cells {
+                match cell {
+                    MatVariable::CellArray(c) => collect(c.value, flat),
+                    cell => flat.push(cell),
+                }
+            }
+        }
+
+        let mut flat = Vec::with_capacity(self.value.len());
+        collect(self.value, &mut flat);
+        CellArray {
+            dim: MatDim::from_slice(&[1, flat.len()]),
+            value: flat,
+        }
+    }
+
+    /// Flatten the cells, see [`CellArray::flatten`], and concatenate the numeric arrays along
+    /// dimension `dim`, counted from 0 for the rows, see [`NumericArray::concat`].
+    ///
+    /// This is MATLAB's `vertcat(c{:})` for `dim` 0 and `horzcat(c{:})` for `dim` 1. As in
+    /// MATLAB, empty 0x0 arrays are left out. Fails with [`MatrwError::AccessError`] naming the
+    /// first cell of the flattened cells, which is no numeric array, and with
+    /// [`MatrwError::TypeConstruction`], if the arrays differ in class or size.
+    ///
+    /// ```
+    /// use matrw::{CellArray, MatlabType, MatVariable, matvar};
+    ///
+    /// let trials = CellArray::new(vec![1, 2], vec![matvar!([1.0, 2.0]), matvar!([3.0])]).unwrap();
+    /// let nested = CellArray::new(vec![2, 1], vec![MatVariable::CellArray(trials), matvar!([])]).unwrap();
+    ///
+    /// let all = nested.flatten_numeric(1).unwrap();
+    /// assert_eq!(all.dim, vec![1, 3]);
+    /// assert_eq!(all.value, MatlabType::from(vec![1.0, 2.0, 3.0]));
+    /// ```
+    pub fn flatten_numeric(self, dim: usize) -> Result<NumericArray, MatrwError> {
+        let mut arrays = Vec::new();
+        for (i, cell) in self.flatten().value.into_iter().enumerate() {
+            match cell {
+                MatVariable::NumericArray(arr) if arr.dim.iter().all(|&d| d == 0) => {}
+                MatVariable::NumericArray(arr) => arrays.push(arr),
+                _ => {
+                    return Err(MatrwError::AccessError(format!(
+                        "Cell {} is not a numeric array",
+                        i
+                    )));
+                }
+            }
+        }
+
+        if arrays.is_empty() {
+            return NumericArray::new(vec![0, 0], MatlabType::F64(Vec::new()), None);
+        }
+        NumericArray::concat(&arrays, dim)
+    }
 }
 
 impl_Array_for!(CellArray);
 
 impl From<CellArray7> for CellArray {
     fn from(value: CellArray7) -> Self {
-        let dim = value.dim().into_iter().map(|x| x as usize).collect();
+        let dim = value.dimensions().to_matdim(1);
         let v = value.value().into_iter().map(|x| x.into()).collect();
         Self { dim, value: v }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matvar;
+
+    #[test]
+    fn flatten_nested_cells() {
+        let cell = |dim: Vec<usize>, value| MatVariable::CellArray(CellArray::new(dim, value).unwrap());
+        let inner = cell(
+            vec![2, 1],
+            vec![matvar!([1.0]), cell(vec![1, 1], vec![matvar!([2.0, 3.0])])],
+        );
+        let empty = cell(vec![0, 0], vec![]);
+        let MatVariable::CellArray(cells) = cell(vec![1, 3], vec![inner, empty, matvar!([4.0])]) else {
+            unreachable!()
+        };
+
+        let flat = cells.clone().flatten();
+        assert_eq!(flat.dim, vec![1, 3]);
+        assert_eq!(
+            flat.value,
+            vec![matvar!([1.0]), matvar!([2.0, 3.0]), matvar!([4.0])]
+        );
+
+        let row = cells.clone().flatten_numeric(1).unwrap();
+        assert_eq!(row.value, MatlabType::from(vec![1.0, 2.0, 3.0, 4.0]));
+        assert!(cells.flatten_numeric(0).is_err());
+
+        let mixed = CellArray::new(vec![1, 3], vec![matvar!([]), matvar!(1.0), matvar!({ a: 1.0 })]).unwrap();
+        let err = mixed.flatten_numeric(0).unwrap_err();
+        assert_eq!(err.to_string(), "Cell 2 is not a numeric array");
+
+        let only_empty = CellArray::new(vec![1, 1], vec![matvar!([])]).unwrap();
+        assert_eq!(only_empty.flatten_numeric(0).unwrap().dim, vec![0, 0]);
+    }
+
+    #[test]
+    fn cells_into_structure_array() {
+        let cells = CellArray::new(
+            vec![1, 2, 2],
+            vec![
+                matvar!({ a: 1.0, b: "x" }),
+                matvar!({ b: "y", a: 2.0 }),
+                matvar!({ a: 3.0, b: "z" }),
+                matvar!({ a: 4.0, b: "w" }),
+            ],
+        )
+        .unwrap();
+        let s = cells.clone().try_into_structure_array().unwrap();
+        assert_eq!(s.dim, vec![1, 2, 2]);
+        assert_eq!(s.fieldnames(), vec!["a", "b"]);
+        let records = s.into_records();
+        assert_eq!(records[1].keys().collect::<Vec<_>>(), ["a", "b"]);
+        assert_eq!(records[3]["b"], matvar!("w"));
+
+        let mut other = cells.clone();
+        other.value[2] = matvar!({ a: 3.0 });
+        let err = other.try_into_structure_array().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r#"Cell 2 has fields ["a"], which differ from fields ["a", "b"] of the first cell"#
+        );
+
+        let mut other = cells;
+        other.value[1] = matvar!([{ a: 1.0, b: "x" }, { a: 1.0, b: "x" }]);
+        let err = other.try_into_structure_array().unwrap_err();
+        assert_eq!(err.to_string(), "Cell 1 is not a scalar structure");
+
+        let empty = CellArray::new(vec![0, 3], vec![]).unwrap();
+        let s = empty.try_into_structure_array().unwrap();
+        assert_eq!((s.fieldnames().len(), s.dim), (0, vec![0, 3].into()));
+    }
+
+    #[test]
+    fn cells_into_filled_structure_array() {
+        let cells =
+            CellArray::new(vec![2, 1], vec![matvar!({ b: 1.0 }), matvar!({ a: "x", b: 2.0 })]).unwrap();
+        let s = cells.try_into_structure_array_filled().unwrap();
+        assert_eq!(s.dim, vec![2, 1]);
+        assert_eq!(s.fieldnames(), vec!["b", "a"]);
+        let records = s.into_records();
+        assert_eq!(records[0]["a"], matvar!([]));
+        assert_eq!(records[1]["a"], matvar!("x"));
+
+        let mixed = CellArray::new(vec![1, 2], vec![matvar!({ a: 1.0 }), matvar!(2.0)]).unwrap();
+        let err = mixed.try_into_structure_array_filled().unwrap_err();
+        assert_eq!(err.to_string(), "Cell 1 is not a scalar structure");
+    }
+
+    #[test]
+    fn map_cells() {
+        let cells = CellArray::new(
+            vec![2, 2],
+            vec![matvar!(1.0), matvar!(2.0), matvar!([3.0]), matvar!(4.0)],
+        )
+        .unwrap();
+
+        let scaled = cells
+            .clone()
+            .map(|cell| {
+                cell.to_f64()
+                    .map(|x| MatVariable::from(2.0 * x))
+                    .ok_or("Not a scalar")
+            })
+            .unwrap();
+        assert_eq!(scaled.dim, vec![2, 2]);
+        assert_eq!(scaled.to_vec_of::<f64>().unwrap(), vec![2.0, 4.0, 6.0, 8.0]);
+
+        let mut calls = 0;
+        let err = cells.map(|cell| {
+            calls += 1;
+            match cell.to_f64() {
+                Some(3.0) => Err(calls),
+                _ => Ok(cell),
+            }
+        });
+        assert_eq!(err.unwrap_err(), 3);
+        assert_eq!(calls, 3);
+    }
+
+    #[test]
+    fn struct_cell_conversions() {
+        let s = matvar!({ a: 1.0, b: "x", c: [1u8, 2u8] });
+        let MatVariable::Structure(structure) = &s else {
+            panic!("Not a structure")
+        };
+        let cells = structure.to_cell();
+        assert_eq!(cells.dim, vec![3, 1]);
+        assert_eq!(cells.to_struct(&["a", "b", "c"]).unwrap(), s);
+
+        // Columns become the elements of an Nx1 structure array
+        let cells = CellArray::new(vec![2, 3], (0..6).map(|i| matvar!(i as f64)).collect()).unwrap();
+        let MatVariable::StructureArray(arr) = cells.to_struct(&["x", "y"]).unwrap() else {
+            panic!("Not a structure array")
+        };
+        assert_eq!(arr.dim, vec![3, 1]);
+        assert_eq!(MatVariable::StructureArray(arr)[2]["y"], matvar!(5.0));
+
+        let no_fields = CellArray::new(vec![0, 2], vec![])
+            .unwrap()
+            .to_struct(&[])
+            .unwrap();
+        assert_eq!(
+            (no_fields.dim(), no_fields.fieldnames()),
+            (crate::MatDim::new(vec![2, 1]), Some(vec![]))
+        );
+        let no_columns = CellArray::new(vec![1, 0], vec![])
+            .unwrap()
+            .to_struct(&["a"])
+            .unwrap();
+        assert_eq!(no_columns.fieldnames(), Some(vec!["a".to_string()]));
+        assert_eq!(no_columns.dim(), [0, 1]);
+
+        let err = |cells: CellArray, names: &[&str]| cells.to_struct(names).unwrap_err().to_string();
+        let pair = || CellArray::new(vec![2, 1], vec![matvar!(1.0), matvar!(2.0)]).unwrap();
+        assert_eq!(
+            err(pair(), &["a"]),
+            "Type construction error Number of field names 1 does not match the 2 rows of the cell array."
+        );
+        assert_eq!(
+            err(pair(), &["a", "a"]),
+            "Type construction error Field 'a' is given more than once."
+        );
+        assert_eq!(
+            err(pair(), &["a", "end"]),
+            "Type construction error Invalid field name 'end', it is a MATLAB keyword."
+        );
+        let cube = CellArray::new(vec![1, 1, 2], vec![matvar!(1.0), matvar!(2.0)]).unwrap();
+        assert_eq!(
+            err(cube, &["a"]),
+            "Type construction error Cell array of size [1, 1, 2] is not a matrix."
+        );
+    }
+
+    #[test]
+    fn empty_cells() {
+        use crate::{load_matfile_from_u8, matfile, save_matfile_v7_to_u8};
+
+        let cells = CellArray::new(vec![1, 3], vec![MatVariable::Null, matvar!([]), matvar!("")]).unwrap();
+        assert_eq!(cells.value[0], MatVariable::empty_value());
+        assert!(cells.value[1].is_empty_value());
+        assert!(!cells.value[2].is_empty_value());
+
+        // Null cells placed after construction are written as `[]`
+        let mut cells = cells;
+        cells.value[1] = MatVariable::Null;
+        let data = save_matfile_v7_to_u8(matfile!(c: MatVariable::CellArray(cells)), false).unwrap();
+        let matfile = load_matfile_from_u8(&data).unwrap();
+        for i in 0..2 {
+            assert_eq!(matfile["c"][i], MatVariable::empty_value());
+        }
+        assert_eq!(matfile["c"][2], matvar!(""));
+    }
+}
@@ -1,15 +1,68 @@
+use std::sync::OnceLock;
+
+use binrw::Endian;
+
+use crate::MatrwError;
 use crate::interface::variable::MatVariable;
-use crate::parser::v7::types::compressed_array::CompressedArray7;
+use crate::parser::v7::types::compressed_array::decompress_variable;
 
+/// Variable stored as compressed MAT-file element.
+///
+/// Variables loaded with [`crate::load_matfile_lazy`] keep their compressed data, which is only
+/// decompressed on first access of the contained variable.
 #[derive(Debug, Clone)]
-pub struct CompressedArray {
-    pub value: Box<MatVariable>,
+pub(crate) struct CompressedArray {
+    value: OnceLock<Box<MatVariable>>,
+    data: Vec<u8>,
+    endian: Endian,
 }
 
-impl From<CompressedArray7> for CompressedArray {
-    fn from(value: CompressedArray7) -> Self {
+impl CompressedArray {
+    /// Construct a `CompressedArray` from the zlib compressed data of a MAT-file element.
+    pub(crate) fn lazy(data: Vec<u8>, endian: Endian) -> Self {
         Self {
-            value: Box::new(value.value().into()),
+            value: OnceLock::new(),
+            data,
+            endian,
         }
     }
+
+    ///
+    /// Return if the contained variable has been decompressed.
+    ///
+    pub(crate) fn is_decompressed(&self) -> bool {
+        self.value.get().is_some()
+    }
+
+    /// Bytes of the compressed data and of the contained variable, if it has been decompressed.
+    pub(crate) fn memory_usage(&self) -> u64 {
+        self.data.len() as u64 + self.value.get().map_or(0, |var| var.memory_usage())
+    }
+
+    ///
+    /// Return the contained variable, decompressing it on first access.
+    ///
+    /// Returns [`MatrwError::IoError`] or [`MatrwError::BinrwError`], if the compressed data is
+    /// corrupt.
+    ///
+    pub(crate) fn try_value(&self) -> Result<&MatVariable, MatrwError> {
+        if let Some(value) = self.value.get() {
+            return Ok(value);
+        }
+        let value = decompress_variable(&self.data, self.endian, u64::MAX)?.into();
+        Ok(self.value.get_or_init(|| Box::new(value)))
+    }
+
+    ///
+    /// Move out the contained variable, decompressing it if necessary, and drop the compressed
+    /// data.
+    ///
+    /// Returns the error of [`CompressedArray::try_value`] and keeps the compressed data, if it
+    /// is corrupt.
+    ///
+    pub(crate) fn try_take_value(&mut self) -> Result<MatVariable, MatrwError> {
+        self.try_value()?;
+        self.data = Vec::new();
+        Ok(*self.value.take().expect("Variable is decompressed."))
+    }
 }
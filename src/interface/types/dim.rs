@@ -0,0 +1,292 @@
+//! Module dim
+//!
+//! This module defines the struct [`MatDim`], the dimensions of a MATLAB array.
+//!
+
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::{Deref, DerefMut};
+
+/// Dimensions of an array, as returned by [`crate::MatVariable::dim`] and stored in the `dim`
+/// fields of the array types.
+///
+/// Like in MATLAB, arrays have at least two dimensions: [`MatDim::new`] normalizes a single
+/// dimension `n` to a row vector `1xn`. `MatDim` dereferences to a slice of the dimensions.
+///
+/// Up to four dimensions are stored inline, so arrays of the common two-dimensional sizes don't
+/// allocate for their dimensions, neither when read nor when their dimensions are taken.
+/// Dimensions convert from and into `Vec<usize>` and compare with vectors, arrays and slices.
+///
+/// Example
+/// ```
+/// use matrw::{MatDim, matvar};
+///
+/// let dim = matvar!([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]).dim();
+/// assert_eq!(dim, vec![2, 3]);
+/// assert_eq!(dim.numel(), 6);
+/// assert_eq!(dim.to_string(), "2x3");
+///
+/// assert_eq!(MatDim::new(vec![4]), vec![1, 4]);
+/// assert!(MatDim::new(vec![4]).is_vector());
+/// ```
+#[derive(Clone)]
+pub struct MatDim(Storage);
+
+/// Number of dimensions stored without allocation
+const INLINE: usize = 4;
+
+#[derive(Clone)]
+enum Storage {
+    Inline(u8, [usize; INLINE]),
+    Heap(Vec<usize>),
+}
+
+impl MatDim {
+    ///
+    /// Dimensions `dim`, with a single dimension `n` normalized to `1xn` and no dimensions to
+    /// `0x0`.
+    ///
+    pub fn new(dim: Vec<usize>) -> Self {
+        if dim.len() > INLINE {
+            Self(Storage::Heap(dim))
+        } else {
+            Self::from_slice(&dim)
+        }
+    }
+
+    ///
+    /// Dimensions copied from `dim`, normalized like [`MatDim::new`]. Does not allocate for up to
+    /// four dimensions.
+    ///
+    pub fn from_slice(dim: &[usize]) -> Self {
+        match dim {
+            [] | [0] => Self::inline(&[0, 0]),
+            &[n] => Self::inline(&[1, n]),
+            _ if dim.len() <= INLINE => Self::inline(dim),
+            _ => Self(Storage::Heap(dim.to_vec())),
+        }
+    }
+
+    fn inline(dim: &[usize]) -> Self {
+        let mut dims = [0; INLINE];
+        dims[..dim.len()].copy_from_slice(dim);
+        Self(Storage::Inline(dim.len() as u8, dims))
+    }
+
+    fn as_slice(&self) -> &[usize] {
+        match &self.0 {
+            Storage::Inline(len, dims) => &dims[..*len as usize],
+            Storage::Heap(dims) => dims,
+        }
+    }
+
+    /// Number of elements, `usize::MAX` if it does not fit into `usize`
+    pub fn numel(&self) -> usize {
+        self.iter().fold(1, |n, &d| n.saturating_mul(d))
+    }
+
+    /// Number of dimensions, at least two
+    pub fn ndims(&self) -> usize {
+        self.as_slice().len()
+    }
+
+    /// True for exactly one element, like MATLAB's `isscalar`
+    pub fn is_scalar(&self) -> bool {
+        self.numel() == 1
+    }
+
+    /// True for `1xn` and `nx1` with `n >= 1`, like MATLAB's `isvector`
+    pub fn is_vector(&self) -> bool {
+        self.ndims() == 2 && (self[0] == 1 || self[1] == 1) && self.numel() >= 1
+    }
+
+    /// True for no elements, like MATLAB's `isempty`
+    pub fn is_empty(&self) -> bool {
+        self.numel() == 0
+    }
+
+    /// Dimensions as vector
+    pub fn into_vec(self) -> Vec<usize> {
+        match self.0 {
+            Storage::Inline(..) => self.as_slice().to_vec(),
+            Storage::Heap(dims) => dims,
+        }
+    }
+}
+
+impl Deref for MatDim {
+    type Target = [usize];
+
+    fn deref(&self) -> &Self::Target {
+        self.as_slice()
+    }
+}
+
+/// The dimensions can be changed in place, but not their number
+impl DerefMut for MatDim {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match &mut self.0 {
+            Storage::Inline(len, dims) => &mut dims[..*len as usize],
+            Storage::Heap(dims) => dims,
+        }
+    }
+}
+
+impl From<Vec<usize>> for MatDim {
+    fn from(dim: Vec<usize>) -> Self {
+        Self::new(dim)
+    }
+}
+
+impl From<&[usize]> for MatDim {
+    fn from(dim: &[usize]) -> Self {
+        Self::from_slice(dim)
+    }
+}
+
+impl<const N: usize> From<[usize; N]> for MatDim {
+    fn from(dim: [usize; N]) -> Self {
+        Self::from_slice(&dim)
+    }
+}
+
+/// Dimensions collected without allocation for up to four of them, normalized like
+/// [`MatDim::new`]
+impl FromIterator<usize> for MatDim {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut iter = iter.into_iter();
+        let mut dims = [0; INLINE];
+        let mut len = 0;
+        for d in iter.by_ref() {
+            if len == INLINE {
+                let mut dims = dims.to_vec();
+                dims.push(d);
+                dims.extend(iter);
+                return Self(Storage::Heap(dims));
+            }
+            dims[len] = d;
+            len += 1;
+        }
+        Self::from_slice(&dims[..len])
+    }
+}
+
+impl From<MatDim> for Vec<usize> {
+    fn from(dim: MatDim) -> Self {
+        dim.into_vec()
+    }
+}
+
+/// Dimensions as list, like `[3, 4, 2]`, as the `dim` fields of the array types are shown
+impl fmt::Debug for MatDim {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_slice().fmt(f)
+    }
+}
+
+impl PartialEq for MatDim {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl Eq for MatDim {}
+
+impl Hash for MatDim {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_slice().hash(state);
+    }
+}
+
+impl PartialEq<Vec<usize>> for MatDim {
+    fn eq(&self, other: &Vec<usize>) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl PartialEq<MatDim> for Vec<usize> {
+    fn eq(&self, other: &MatDim) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl PartialEq<[usize]> for MatDim {
+    fn eq(&self, other: &[usize]) -> bool {
+        self.as_slice() == other
+    }
+}
+
+impl<const N: usize> PartialEq<[usize; N]> for MatDim {
+    fn eq(&self, other: &[usize; N]) -> bool {
+        self.as_slice() == other
+    }
+}
+
+impl<'a> IntoIterator for &'a MatDim {
+    type Item = &'a usize;
+    type IntoIter = std::slice::Iter<'a, usize>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.as_slice().iter()
+    }
+}
+
+/// Dimensions joined by `x`, like `3x4x2`
+impl fmt::Display for MatDim {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let dim: Vec<String> = self.iter().map(|d| d.to_string()).collect();
+        write!(f, "{}", dim.join("x"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn predicates() {
+        let cases = [
+            (vec![1, 1], true, true, false),
+            (vec![1, 5], false, true, false),
+            (vec![5, 1], false, true, false),
+            (vec![2, 3], false, false, false),
+            (vec![1, 1, 3], false, false, false),
+            (vec![0, 3], false, false, true),
+            (vec![1, 0], false, false, true),
+        ];
+        for (dim, scalar, vector, empty) in cases {
+            let d = MatDim::new(dim.clone());
+            assert_eq!(d.is_scalar(), scalar, "{dim:?}");
+            assert_eq!(d.is_vector(), vector, "{dim:?}");
+            assert_eq!(d.is_empty(), empty, "{dim:?}");
+        }
+    }
+
+    #[test]
+    fn normalization_and_display() {
+        assert_eq!(MatDim::new(vec![]), [0, 0]);
+        assert_eq!(MatDim::new(vec![0]), [0, 0]);
+        assert_eq!(MatDim::new(vec![3]), [1, 3]);
+        assert_eq!(MatDim::new(vec![3, 4, 2]).to_string(), "3x4x2");
+        assert_eq!(MatDim::new(vec![3, 4, 2]).ndims(), 3);
+        assert_eq!(MatDim::new(vec![usize::MAX, 2]).numel(), usize::MAX);
+        assert!(MatDim::new(vec![usize::MAX, 2, 0]).is_empty());
+    }
+
+    #[test]
+    fn inline_and_heap_storage() {
+        let inline = MatDim::from_slice(&[2, 3, 1, 4]);
+        let heap = MatDim::new(vec![2, 3, 1, 4, 5]);
+        assert_eq!(inline, MatDim::new(vec![2, 3, 1, 4]));
+        assert_eq!(inline.numel(), 24);
+        assert_eq!(heap.numel(), 120);
+        assert_eq!(heap.clone().into_vec(), vec![2, 3, 1, 4, 5]);
+        assert_eq!(MatDim::from_slice(&[2, 3, 1, 4, 5]), heap);
+        assert_eq!(MatDim::from_slice(&[7]), [1, 7]);
+        assert_eq!(format!("{:?}", inline), "[2, 3, 1, 4]");
+
+        assert_eq!((1..=6).collect::<MatDim>(), MatDim::new(vec![1, 2, 3, 4, 5, 6]));
+        assert_eq!([2, 3].into_iter().collect::<MatDim>(), inline[..2]);
+        assert_eq!([7].into_iter().collect::<MatDim>(), [1, 7]);
+    }
+}
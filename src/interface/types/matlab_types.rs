@@ -5,8 +5,14 @@
 
 use std::fmt::Display;
 
+use crate::interface::variable::MatVariable;
+
 /// Numeric types in MAT-files
+///
+/// The enum is `#[non_exhaustive]`, matches need a wildcard arm. [`MatlabType::as_slice`] and
+/// [`VariableClass::numeric`] give access to the values without matching.
 #[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
 pub enum MatlabType {
     U8(Vec<u8>),
     I8(Vec<i8>),
@@ -35,6 +41,28 @@ impl MatlabType {
         T::inner(self)
     }
 
+    ///
+    /// Values as slice of `T`, or `None` if the values are not of type `T`.
+    ///
+    /// ```
+    /// use matrw::MatlabType;
+    ///
+    /// let value = MatlabType::from(vec![1i16, 2]);
+    /// assert_eq!(value.as_slice::<i16>(), Some([1i16, 2].as_slice()));
+    /// assert_eq!(value.as_slice::<f64>(), None);
+    /// ```
+    ///
+    pub fn as_slice<T: MatlabTypeMarker>(&self) -> Option<&[T]> {
+        T::inner_ref(self).map(Vec::as_slice)
+    }
+
+    ///
+    /// Values as mutable slice of `T`, or `None` if the values are not of type `T`.
+    ///
+    pub fn as_mut_slice<T: MatlabTypeMarker>(&mut self) -> Option<&mut [T]> {
+        T::inner_mut(self).map(Vec::as_mut_slice)
+    }
+
     pub fn is_empty(&self) -> bool {
         match self {
             U8(items) => items.is_empty(),
@@ -53,8 +81,9 @@ impl MatlabType {
         }
     }
 
+    /// Value at `index`, `None` if out of bounds or if `T` does not match the class
     pub fn get<T: FromMatlabType>(&self, index: usize) -> Option<&T> {
-        T::inner_ref(self).unwrap().get(index)
+        T::inner_ref(self)?.get(index)
     }
 
     pub fn clone_at_index(&self, index: usize) -> MatlabType {
@@ -325,6 +354,310 @@ impl MatlabType {
             BOOL(items) => items.len(),
         }
     }
+
+    ///
+    /// Call `visitor` with the values as slice of their element type.
+    ///
+    /// This allows one generic function over all types instead of a match with an arm per
+    /// type, see [`NumericVisitor`].
+    ///
+    /// ```
+    /// use matrw::{MatlabType, MatlabTypeMarker, NumericVisitor};
+    ///
+    /// /// Number of non-zero values
+    /// struct CountNonZero;
+    ///
+    /// impl NumericVisitor for CountNonZero {
+    ///     type Output = usize;
+    ///
+    ///     fn visit<T: MatlabTypeMarker>(self, values: &[T]) -> usize {
+    ///         values.iter().filter(|v| !v.is_zero()).count()
+    ///     }
+    /// }
+    ///
+    /// assert_eq!(MatlabType::from(vec![0u8, 3, 4]).visit_numeric(CountNonZero), 2);
+    /// assert_eq!(MatlabType::from(vec![true, false]).visit_numeric(CountNonZero), 1);
+    /// ```
+    ///
+    pub fn visit_numeric<V: NumericVisitor>(&self, visitor: V) -> V::Output {
+        match self {
+            U8(v) => visitor.visit(v),
+            I8(v) => visitor.visit(v),
+            U16(v) => visitor.visit(v),
+            I16(v) => visitor.visit(v),
+            U32(v) => visitor.visit(v),
+            I32(v) => visitor.visit(v),
+            U64(v) => visitor.visit(v),
+            I64(v) => visitor.visit(v),
+            F32(v) => visitor.visit(v),
+            F64(v) => visitor.visit(v),
+            UTF8(v) | UTF16(v) => visitor.visit(v),
+            BOOL(v) => visitor.visit(v),
+        }
+    }
+
+    ///
+    /// Call `visitor` with the values as mutable slice of their element type, see
+    /// [`MatlabType::visit_numeric`].
+    ///
+    pub fn visit_numeric_mut<V: NumericVisitorMut>(&mut self, visitor: V) -> V::Output {
+        match self {
+            U8(v) => visitor.visit(v),
+            I8(v) => visitor.visit(v),
+            U16(v) => visitor.visit(v),
+            I16(v) => visitor.visit(v),
+            U32(v) => visitor.visit(v),
+            I32(v) => visitor.visit(v),
+            U64(v) => visitor.visit(v),
+            I64(v) => visitor.visit(v),
+            F32(v) => visitor.visit(v),
+            F64(v) => visitor.visit(v),
+            UTF8(v) | UTF16(v) => visitor.visit(v),
+            BOOL(v) => visitor.visit(v),
+        }
+    }
+
+    pub(crate) fn to_f64(&self) -> Vec<f64> {
+        match self {
+            U8(v) => v.iter().map(|&x| x as f64).collect(),
+            I8(v) => v.iter().map(|&x| x as f64).collect(),
+            U16(v) => v.iter().map(|&x| x as f64).collect(),
+            I16(v) => v.iter().map(|&x| x as f64).collect(),
+            U32(v) => v.iter().map(|&x| x as f64).collect(),
+            I32(v) => v.iter().map(|&x| x as f64).collect(),
+            U64(v) => v.iter().map(|&x| x as f64).collect(),
+            I64(v) => v.iter().map(|&x| x as f64).collect(),
+            F32(v) => v.iter().map(|&x| x as f64).collect(),
+            F64(v) => v.clone(),
+            UTF8(v) | UTF16(v) => v.iter().map(|&x| x as u32 as f64).collect(),
+            BOOL(v) => v.iter().map(|&x| x as u8 as f64).collect(),
+        }
+    }
+
+    /// Integer values, exact for all integer classes. Products of 64 bit integers fit into `i128`.
+    pub(crate) fn to_i128(&self) -> Vec<i128> {
+        match self {
+            U8(v) => v.iter().map(|&x| x as i128).collect(),
+            I8(v) => v.iter().map(|&x| x as i128).collect(),
+            U16(v) => v.iter().map(|&x| x as i128).collect(),
+            I16(v) => v.iter().map(|&x| x as i128).collect(),
+            U32(v) => v.iter().map(|&x| x as i128).collect(),
+            I32(v) => v.iter().map(|&x| x as i128).collect(),
+            U64(v) => v.iter().map(|&x| x as i128).collect(),
+            I64(v) => v.iter().map(|&x| x as i128).collect(),
+            _ => unreachable!("Not an integer class"),
+        }
+    }
+
+    /// Truncate to `len` values, or pad with zeros, `'\0'` or `false`.
+    pub(crate) fn resize(&mut self, len: usize) {
+        match self {
+            U8(v) => v.resize(len, 0),
+            I8(v) => v.resize(len, 0),
+            U16(v) => v.resize(len, 0),
+            I16(v) => v.resize(len, 0),
+            U32(v) => v.resize(len, 0),
+            I32(v) => v.resize(len, 0),
+            U64(v) => v.resize(len, 0),
+            I64(v) => v.resize(len, 0),
+            F32(v) => v.resize(len, 0.0),
+            F64(v) => v.resize(len, 0.0),
+            UTF8(v) | UTF16(v) => v.resize(len, '\0'),
+            BOOL(v) => v.resize(len, false),
+        }
+    }
+}
+
+// ============================================================================
+// Class
+// ============================================================================
+
+/// MATLAB class of a variable
+///
+/// The numeric classes also select the class of conversions, e.g. in
+/// [`crate::MatFile::convert_numeric`] and [`crate::NumericArray::from_bytes`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VariableClass {
+    Double,
+    Single,
+    Int8,
+    UInt8,
+    Int16,
+    UInt16,
+    Int32,
+    UInt32,
+    Int64,
+    UInt64,
+    Char,
+    Logical,
+    Cell,
+    /// Structures and structure arrays
+    Struct,
+    /// Sparse arrays of class `double` or `logical`
+    Sparse,
+    /// Objects like `string` or `table` and other data read as [`MatVariable::Unsupported`]
+    Object,
+}
+
+impl VariableClass {
+    /// Class of a variable, or `None` for [`MatVariable::Null`], see [`MatVariable::kind`].
+    pub fn of(var: &MatVariable) -> Option<Self> {
+        match var {
+            MatVariable::NumericArray(arr) => Some(Self::of_type(&arr.value)),
+            MatVariable::SparseArray(_) => Some(Self::Sparse),
+            MatVariable::CellArray(_) => Some(Self::Cell),
+            MatVariable::Structure(_) | MatVariable::StructureArray(_) => Some(Self::Struct),
+            MatVariable::Unsupported => Some(Self::Object),
+            MatVariable::Null => None,
+        }
+    }
+
+    /// Lowercase name of the class, like MATLAB's `class` returns it, except for `sparse`,
+    /// whose MATLAB class is `double` or `logical`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Double => "double",
+            Self::Single => "single",
+            Self::Int8 => "int8",
+            Self::UInt8 => "uint8",
+            Self::Int16 => "int16",
+            Self::UInt16 => "uint16",
+            Self::Int32 => "int32",
+            Self::UInt32 => "uint32",
+            Self::Int64 => "int64",
+            Self::UInt64 => "uint64",
+            Self::Char => "char",
+            Self::Logical => "logical",
+            Self::Cell => "cell",
+            Self::Struct => "struct",
+            Self::Sparse => "sparse",
+            Self::Object => "object",
+        }
+    }
+
+    /// Class of numeric data.
+    pub(crate) fn of_type(value: &MatlabType) -> Self {
+        match value {
+            MatlabType::U8(_) => Self::UInt8,
+            MatlabType::I8(_) => Self::Int8,
+            MatlabType::U16(_) => Self::UInt16,
+            MatlabType::I16(_) => Self::Int16,
+            MatlabType::U32(_) => Self::UInt32,
+            MatlabType::I32(_) => Self::Int32,
+            MatlabType::U64(_) => Self::UInt64,
+            MatlabType::I64(_) => Self::Int64,
+            MatlabType::F32(_) => Self::Single,
+            MatlabType::F64(_) => Self::Double,
+            MatlabType::UTF8(_) | MatlabType::UTF16(_) => Self::Char,
+            MatlabType::BOOL(_) => Self::Logical,
+        }
+    }
+
+    ///
+    /// Numeric class of `value`, or `None` for characters and logicals.
+    ///
+    pub fn numeric(value: &MatlabType) -> Option<Self> {
+        Some(Self::of_type(value)).filter(|class| class.is_numeric())
+    }
+
+    /// `true` for the integer and floating-point classes
+    pub fn is_numeric(self) -> bool {
+        self.is_integer() || matches!(self, Self::Single | Self::Double)
+    }
+
+    /// `true` for the integer classes
+    pub fn is_integer(self) -> bool {
+        matches!(
+            self,
+            Self::Int8
+                | Self::UInt8
+                | Self::Int16
+                | Self::UInt16
+                | Self::Int32
+                | Self::UInt32
+                | Self::Int64
+                | Self::UInt64
+        )
+    }
+
+    /// Bytes of one value of a numeric class, `None` for the other classes
+    pub fn element_size(self) -> Option<usize> {
+        match self {
+            Self::UInt8 | Self::Int8 => Some(1),
+            Self::UInt16 | Self::Int16 => Some(2),
+            Self::UInt32 | Self::Int32 | Self::Single => Some(4),
+            Self::UInt64 | Self::Int64 | Self::Double => Some(8),
+            _ => None,
+        }
+    }
+
+    ///
+    /// Convert `value` to this numeric class, `None` for the other classes.
+    ///
+    /// Like in MATLAB, values are rounded to the nearest integer and saturate at the bounds of
+    /// integer classes, NaN becomes zero. Characters and logicals are converted by their numeric
+    /// value.
+    ///
+    pub fn cast(self, value: &MatlabType) -> Option<MatlabType> {
+        if !self.is_numeric() {
+            return None;
+        }
+        Some(match Self::numeric(value) {
+            Some(class) if class == self => value.clone(),
+            Some(class) if class.is_integer() && self.is_integer() => self.cast_i128(value.to_i128()),
+            _ => self.cast_f64(value.to_f64()),
+        })
+    }
+
+    /// Convert values to this numeric class, rounding and saturating for integer classes.
+    pub(crate) fn cast_f64(self, v: Vec<f64>) -> MatlabType {
+        macro_rules! cast {
+            ($t:ty) => {
+                v.into_iter().map(|x| x.round() as $t).collect()
+            };
+        }
+        match self {
+            Self::UInt8 => U8(cast!(u8)),
+            Self::Int8 => I8(cast!(i8)),
+            Self::UInt16 => U16(cast!(u16)),
+            Self::Int16 => I16(cast!(i16)),
+            Self::UInt32 => U32(cast!(u32)),
+            Self::Int32 => I32(cast!(i32)),
+            Self::UInt64 => U64(cast!(u64)),
+            Self::Int64 => I64(cast!(i64)),
+            Self::Single => F32(v.into_iter().map(|x| x as f32).collect()),
+            Self::Double => F64(v),
+            _ => unreachable!("Not a numeric class"),
+        }
+    }
+
+    /// Convert integer values to this integer class, saturating at its bounds.
+    pub(crate) fn cast_i128(self, v: Vec<i128>) -> MatlabType {
+        macro_rules! cast {
+            ($t:ty) => {
+                v.into_iter()
+                    .map(|x| x.clamp(<$t>::MIN as i128, <$t>::MAX as i128) as $t)
+                    .collect()
+            };
+        }
+        match self {
+            Self::UInt8 => U8(cast!(u8)),
+            Self::Int8 => I8(cast!(i8)),
+            Self::UInt16 => U16(cast!(u16)),
+            Self::Int16 => I16(cast!(i16)),
+            Self::UInt32 => U32(cast!(u32)),
+            Self::Int32 => I32(cast!(i32)),
+            Self::UInt64 => U64(cast!(u64)),
+            Self::Int64 => I64(cast!(i64)),
+            _ => unreachable!("Not an integer class"),
+        }
+    }
+}
+
+impl Display for VariableClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
 }
 
 // ============================================================================
@@ -368,6 +701,9 @@ pub trait FromMatlabType {
     fn inner_ref(value: &MatlabType) -> Option<&Vec<Self>>
     where
         Self: Sized;
+    fn inner_mut(value: &mut MatlabType) -> Option<&mut Vec<Self>>
+    where
+        Self: Sized;
 }
 
 pub trait Zero {
@@ -375,7 +711,7 @@ pub trait Zero {
 }
 
 macro_rules! impl_MatlabTypeMarker {
-    ($t1: ty, $var: ident) => {
+    ($t1: ty, $var: ident $(| $alt: ident)*) => {
         impl IntoMatlabType for $t1 {
             fn to_matlab_type(vec: Vec<Self>) -> MatlabType {
                 $var(vec)
@@ -385,13 +721,19 @@ macro_rules! impl_MatlabTypeMarker {
         impl FromMatlabType for $t1 {
             fn inner(value: MatlabType) -> Option<Vec<Self>> {
                 match value {
-                    $var(v) => Some(v),
+                    $var(v) $(| $alt(v))* => Some(v),
                     _ => None,
                 }
             }
             fn inner_ref(value: &MatlabType) -> Option<&Vec<Self>> {
                 match value {
-                    $var(v) => Some(v),
+                    $var(v) $(| $alt(v))* => Some(v),
+                    _ => None,
+                }
+            }
+            fn inner_mut(value: &mut MatlabType) -> Option<&mut Vec<Self>> {
+                match value {
+                    $var(v) $(| $alt(v))* => Some(v),
                     _ => None,
                 }
             }
@@ -448,7 +790,8 @@ impl_MatlabTypeMarker!(u64, U64);
 impl_MatlabTypeMarker!(i64, I64);
 impl_MatlabTypeMarker!(f32, F32);
 impl_MatlabTypeMarker!(f64, F64);
-impl_MatlabTypeMarker!(char, UTF8);
+// Both character encodings are read as `char`, new character data is stored as UTF-8
+impl_MatlabTypeMarker!(char, UTF8 | UTF16);
 impl_MatlabTypeMarker!(bool, BOOL);
 
 pub trait MatlabTypeMarker: Copy + Display + FromMatlabType + IntoMatlabType + Zero {}
@@ -465,6 +808,37 @@ impl MatlabTypeMarker for f64 {}
 impl MatlabTypeMarker for char {}
 impl MatlabTypeMarker for bool {}
 
+/// Function generic over the element type of [`MatlabType`], see [`MatlabType::visit_numeric`].
+///
+/// Closures can't be generic, so the function is a trait method. Fields of the implementing type
+/// take the role of captured variables.
+pub trait NumericVisitor {
+    type Output;
+    /// Called with the values of the visited [`MatlabType`]
+    fn visit<T: MatlabTypeMarker>(self, values: &[T]) -> Self::Output;
+}
+
+/// Function generic over the element type of [`MatlabType`], see
+/// [`MatlabType::visit_numeric_mut`].
+pub trait NumericVisitorMut {
+    type Output;
+    /// Called with the values of the visited [`MatlabType`]
+    fn visit<T: MatlabTypeMarker>(self, values: &mut [T]) -> Self::Output;
+}
+
+/// Element types of arrays, which can be complex. Logical and character arrays can't.
+pub trait MatlabTypeMarkerComplex: MatlabTypeMarker {}
+impl MatlabTypeMarkerComplex for u8 {}
+impl MatlabTypeMarkerComplex for i8 {}
+impl MatlabTypeMarkerComplex for u16 {}
+impl MatlabTypeMarkerComplex for i16 {}
+impl MatlabTypeMarkerComplex for u32 {}
+impl MatlabTypeMarkerComplex for i32 {}
+impl MatlabTypeMarkerComplex for u64 {}
+impl MatlabTypeMarkerComplex for i64 {}
+impl MatlabTypeMarkerComplex for f32 {}
+impl MatlabTypeMarkerComplex for f64 {}
+
 pub trait MatlabTypeMarkerSparse: MatlabTypeMarker {}
 impl MatlabTypeMarkerSparse for f64 {}
 impl MatlabTypeMarkerSparse for bool {}
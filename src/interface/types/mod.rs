@@ -1,8 +1,12 @@
+pub mod aligned;
 pub mod array;
+pub mod bitmask;
 pub mod cell_array;
-pub mod compressed_array;
+pub(crate) mod compressed_array;
+pub mod dim;
 pub mod matlab_types;
 pub mod numeric_array;
+pub mod numeric_ops;
 pub mod sparse_array;
 pub mod structure;
 pub mod structure_array;
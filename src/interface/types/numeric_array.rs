@@ -2,22 +2,58 @@
 //!
 //! This module defines the struct [`NumericArray`] which describes multidimensional dense arrays of complex numeric data.
 //!
+//! The invariants of its fields are listed in the documentation of [`NumericArray`].
+//!
 
 use std::fmt::{Debug, Display};
 use std::mem::discriminant;
+use std::ops::Range;
 
 use crate::MatrwError;
 use crate::interface::types::array::{
-    ArrayType, ensure_matching_complex_size, ensure_matching_dimension, normalize_dimension,
+    ArrayType, ensure_complex_allowed, ensure_matching_complex_size, ensure_matching_dimension, ensure_numel,
+    ind2sub, normalize_dimension, sub2ind,
 };
-use crate::interface::types::matlab_types::{MatlabType, MatlabTypeMarker};
+use crate::interface::types::dim::MatDim;
+use crate::interface::types::matlab_types::{MatlabType, MatlabTypeMarker, VariableClass};
 use crate::interface::types::sparse_array::SparseArray;
 use crate::interface::variable::MatVariable;
+use crate::parser::v7::flags::MatFileDataTypes;
 use crate::parser::v7::types::numeric_array::NumericArray7;
-use crate::parser::v7::types::subelements::array_numeric_data::array_data_value::ArrayDataValueVar;
+use crate::parser::v7::types::subelements::array_numeric_data::array_data::DoubleStorage;
+use crate::parser::v7::types::subelements::array_numeric_data::array_data_value::{
+    ArrayDataValueVar, utf16_unit_chars,
+};
+
+/// Byte order of packed values, see [`NumericArray::from_bytes`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteOrder {
+    Little,
+    Big,
+}
+
+impl ByteOrder {
+    /// Byte order of the target platform
+    pub fn native() -> Self {
+        if cfg!(target_endian = "big") {
+            Self::Big
+        } else {
+            Self::Little
+        }
+    }
+}
 
 /// Contains vectors, matrices or multidimensional arrays of complex numeric data.
 ///
+/// Invariants, checked by [`NumericArray::new`]:
+/// - `value` holds the elements in column-major order, like MATLAB,
+/// - the product of `dim` is the number of elements,
+/// - `dim` has at least two entries. A `dim` with a single entry `n` is normalized to `[1, n]`,
+///   an empty `dim` to `[1, number of elements]`,
+/// - `value_cmp`, the imaginary part, if present, has the same class and length as `value`.
+///
+/// The fields are public. Code modifying them directly must keep these invariants.
+///
 /// Examples
 /// ```
 /// use matrw::{NumericArray, MatlabType, MatVariable};
@@ -29,16 +65,47 @@ use crate::parser::v7::types::subelements::array_numeric_data::array_data_value:
 ///     None,
 /// );
 /// ```
-#[derive(Debug, Clone, PartialEq)]
+///
+/// Arrays support elementwise `+`, `-` and `*` with scalars and arrays of the same
+/// dimensions. The result class follows MATLAB, e.g. integer and double gives integer.
+///
+/// ```
+/// use matrw::{NumericArray, MatlabType};
+///
+/// let signal = NumericArray::new(vec![1, 3], MatlabType::from(vec![10.0, 11.0, 12.0]), None).unwrap();
+/// let baseline = NumericArray::new(vec![1, 3], MatlabType::from(vec![1.0, 1.5, 2.0]), None).unwrap();
+///
+/// let v = ((&signal - &baseline).unwrap() * 2.0f64).unwrap();
+/// assert_eq!(v.value, MatlabType::from(vec![18.0, 19.0, 20.0]));
+/// ```
+#[derive(Debug, Clone)]
 pub struct NumericArray {
-    pub dim: Vec<usize>,
+    /// Dimensions, at least two
+    pub dim: MatDim,
+    /// Real part in column-major order
     pub value: MatlabType,
+    /// Imaginary part of complex arrays
     pub value_cmp: Option<MatlabType>,
+    storage_type: Option<MatFileDataTypes>,
+    /// Stored UTF-16 code units of character data with surrogates
+    utf16_units: Option<Vec<u16>>,
+    /// Additional storage types considered for double data on write
+    pub(crate) double_storage: DoubleStorage,
+    /// Write character data as `miUINT16` code units instead of `miUTF8`
+    pub(crate) utf16_storage: bool,
+}
+
+/// Two arrays are equal if their dimensions and values match. The on-disk storage type is
+/// metadata and does not take part in the comparison.
+impl PartialEq for NumericArray {
+    fn eq(&self, other: &Self) -> bool {
+        self.dim == other.dim && self.value == other.value && self.value_cmp == other.value_cmp
+    }
 }
 
 impl ArrayType for NumericArray {
     /// Get the dimension of the array
-    fn dim(&self) -> &Vec<usize> {
+    fn dim(&self) -> &MatDim {
         &self.dim
     }
 
@@ -120,19 +187,33 @@ impl NumericArray {
     ) -> Result<Self, MatrwError> {
         // Ensure dimensions match number of values
         if !dim.is_empty() {
-            ensure_matching_dimension(dim.iter().product::<usize>(), value.len())?;
+            ensure_matching_dimension(ensure_numel(&dim)?, value.len())?;
         }
+        // Normalize dimensions
+        let dim = normalize_dimension(dim, value.len());
+        Self::with_dim(dim, value, value_cmp)
+    }
+
+    /// Like [`NumericArray::new`], with dimensions `dim` matching the number of values.
+    pub(crate) fn with_dim(
+        dim: MatDim,
+        value: MatlabType,
+        value_cmp: Option<MatlabType>,
+    ) -> Result<Self, MatrwError> {
         // Ensure number of real and complex values match
         if let Some(cmp) = &value_cmp {
             ensure_matching_complex_size(value.len(), cmp.len())?;
         }
-        // Normalize dimensions
-        let dim = normalize_dimension(dim, value.len());
+        ensure_complex_allowed(&value, value_cmp.is_some())?;
 
         Ok(Self {
             dim,
             value,
             value_cmp,
+            storage_type: None,
+            utf16_units: None,
+            double_storage: DoubleStorage::default(),
+            utf16_storage: false,
         })
     }
 
@@ -197,7 +278,7 @@ impl NumericArray {
                 None
             };
 
-            Self::new(dim, value_new, value_comp_new)
+            Self::new(dim.into_vec(), value_new, value_comp_new)
         } else {
             // Check if input is 1D
             let is_row_vec = first_dim[0] == 1;
@@ -235,11 +316,24 @@ impl NumericArray {
     pub fn real_to_vec<T: MatlabTypeMarker>(&self) -> Option<Vec<T>> {
         self.value.clone().inner()
     }
+    /// First value of the real part, `None` for empty arrays or if `T` does not match the class
     pub fn real_to_scalar<T: MatlabTypeMarker>(&self) -> Option<T> {
-        Some(*self.value.get(0).unwrap())
+        self.value.get(0).copied()
+    }
+
+    /// Number of elements, see [`ArrayType::numel`]
+    pub fn numel(&self) -> usize {
+        ArrayType::numel(self)
     }
+
+    /// True for exactly one element, like MATLAB's `isscalar`
     pub fn is_scalar(&self) -> bool {
-        self.dim.iter().product::<usize>() == 1
+        ArrayType::is_scalar(self)
+    }
+
+    /// True for no elements, like MATLAB's `isempty`
+    pub fn is_empty(&self) -> bool {
+        ArrayType::is_empty(self)
     }
 
     /// Move out complex data into `Vec<T>`
@@ -263,12 +357,18 @@ impl NumericArray {
     pub fn comp_to_vec<T: MatlabTypeMarker>(&self) -> Option<Vec<T>> {
         self.value_cmp.as_ref().map(|x| x.clone().inner().unwrap())
     }
+    /// First value of the imaginary part, `None` for real or empty arrays or if `T` does not
+    /// match the class
     pub fn comp_to_scalar<T: MatlabTypeMarker>(&self) -> Option<T> {
-        Some(*self.value_cmp.as_ref().map(|x| x.get(0).unwrap()).unwrap())
+        self.value_cmp.as_ref()?.get(0).copied()
     }
 
     /// Convert to sparse matrix
     ///
+    /// Only elements that are exactly zero are dropped. Returns [`MatrwError::TypeConstruction`]
+    /// for arrays with more than two dimensions and for classes other than double and logical,
+    /// the only classes of sparse arrays in MATLAB.
+    ///
     /// ```
     /// use matrw::{NumericArray, MatlabType, MatVariable};
     ///
@@ -283,24 +383,66 @@ impl NumericArray {
     ///
     /// matches!(m_sparse, MatVariable::SparseArray(_));
     /// ```
-    pub fn to_sparse(self) -> Option<MatVariable> {
+    pub fn to_sparse(self) -> Result<MatVariable, MatrwError> {
+        self.to_sparse_with_tol(0.0)
+    }
+
+    /// Convert to sparse matrix, dropping elements with an absolute value of at most `eps`.
+    ///
+    /// For complex arrays an element is dropped only if both real and imaginary part are within
+    /// the tolerance. `NaN` values are always kept. The tolerance has no effect on `bool` arrays.
+    /// Fails like [`NumericArray::to_sparse`].
+    ///
+    /// ```
+    /// use matrw::{NumericArray, MatlabType, SparseArray, MatVariable};
+    ///
+    /// let m = NumericArray::new(vec![1, 3], MatlabType::from(vec![1.0, 1e-12, 3.0]), None).unwrap();
+    ///
+    /// let MatVariable::SparseArray(s) = m.to_sparse_with_tol(1e-9).unwrap() else { panic!() };
+    /// assert_eq!(s.nnz(), 2);
+    /// ```
+    pub fn to_sparse_with_tol(self, eps: f64) -> Result<MatVariable, MatrwError> {
         if self.dim.len() > 2 {
-            return None;
+            return Err(MatrwError::TypeConstruction(format!(
+                "Cannot convert array with {} dimensions {:?} to sparse, only 2D arrays are supported.",
+                self.dim.len(),
+                self.dim
+            )));
+        }
+        if !matches!(self.value, MatlabType::F64(_) | MatlabType::BOOL(_)) {
+            return Err(MatrwError::TypeConstruction(format!(
+                "Cannot convert '{}' array to sparse, only double and logical arrays are supported.",
+                VariableClass::of_type(&self.value)
+            )));
         }
 
         let n_rows = self.dim[0];
         let n_cols = self.dim[1];
-        let (ir, jc, data_real) = self.value.to_sparse(n_rows, n_cols);
-        let data_comp = if let Some(value_comp) = self.value_cmp {
-            let (_, _, data_comp) = value_comp.to_sparse(n_rows, n_cols);
-            Some(data_comp)
-        } else {
-            None
+
+        let (ir, jc, data_real, data_comp) = match (self.value, self.value_cmp) {
+            (MatlabType::F64(re), im) => {
+                let im = match im {
+                    Some(MatlabType::F64(im)) => Some(im),
+                    None => None,
+                    Some(_) => {
+                        return Err(MatrwError::TypeConstruction(
+                            "Real and imaginary part of different type.".to_string(),
+                        ));
+                    }
+                };
+                let (ir, jc, re, im) = f64_to_sparse(re, im, n_rows, n_cols, eps);
+                (ir, jc, MatlabType::F64(re), im.map(MatlabType::F64))
+            }
+            (value, value_cmp) => {
+                let (ir, jc, data_real) = value.to_sparse(n_rows, n_cols);
+                let data_comp = value_cmp.map(|v| v.to_sparse(n_rows, n_cols).2);
+                (ir, jc, data_real, data_comp)
+            }
         };
 
-        Some(MatVariable::SparseArray(
-            SparseArray::new(self.dim[0], self.dim[1], ir, jc, data_real, data_comp).unwrap(),
-        ))
+        Ok(MatVariable::SparseArray(SparseArray::new(
+            n_rows, n_cols, ir, jc, data_real, data_comp,
+        )?))
     }
 
     pub fn numeric_type(&self) -> &MatlabType {
@@ -310,15 +452,908 @@ impl NumericArray {
     pub fn is_complex(&self) -> bool {
         self.value_cmp.is_some()
     }
+
+    /// Returns `true` if the array holds logical values, which MATLAB keeps apart from the
+    /// numeric classes.
+    pub fn is_logical(&self) -> bool {
+        matches!(self.value, MatlabType::BOOL(_))
+    }
+
+    /// Data type the values were stored with in the MAT-file.
+    ///
+    /// MATLAB stores numeric data in the smallest data type able to represent it, e.g. a double
+    /// array `[1, 2, 3]` is written as `miUINT8`. The reader converts the data back into the array
+    /// class, this method reports the original on-disk type. Returns `None` for arrays that were
+    /// not read from a MAT-file.
+    ///
+    /// Example
+    /// ```
+    /// use matrw::{NumericArray, MatlabType};
+    ///
+    /// let arr = NumericArray::new(vec![1, 3], MatlabType::from(vec![1., 2., 3.]), None).unwrap();
+    /// assert_eq!(arr.storage_type(), None);
+    /// ```
+    pub fn storage_type(&self) -> Option<MatFileDataTypes> {
+        self.storage_type
+    }
+
+    /// UTF-16 code units of a character array, `None` for other classes.
+    ///
+    /// MATLAB stores characters as UTF-16 code units and counts each unit as one character.
+    /// Surrogates, which encode characters beyond U+FFFF in pairs or are broken in files of
+    /// faulty writers, have no character of their own and are read as U+FFFD. For character data
+    /// read from a MAT-file, this method returns the code units exactly as stored.
+    ///
+    /// Example
+    /// ```
+    /// use matrw::NumericArray;
+    ///
+    /// let arr = NumericArray::from("abc");
+    /// assert_eq!(arr.utf16_code_units(), Some(vec![0x61, 0x62, 0x63]));
+    /// ```
+    pub fn utf16_code_units(&self) -> Option<Vec<u16>> {
+        if let Some(units) = self.stored_utf16_units() {
+            return Some(units.to_vec());
+        }
+        match &self.value {
+            MatlabType::UTF8(v) | MatlabType::UTF16(v) => {
+                Some(v.iter().collect::<String>().encode_utf16().collect())
+            }
+            _ => None,
+        }
+    }
+
+    /// Code units of character data read with surrogates, `None` otherwise. The units are
+    /// dropped, once `value` was changed and they no longer decode to it.
+    pub(crate) fn stored_utf16_units(&self) -> Option<&[u16]> {
+        let units = self.utf16_units.as_deref()?;
+        match &self.value {
+            MatlabType::UTF8(v) | MatlabType::UTF16(v) if utf16_unit_chars(units) == *v => Some(units),
+            _ => None,
+        }
+    }
+
+    /// One code unit per character of a character array, as stored with `miUINT16`, `None` for
+    /// other classes. Stored code units are kept, characters beyond U+FFFF become U+FFFD.
+    pub(crate) fn utf16_storage_units(&self) -> Option<Vec<u16>> {
+        if let Some(units) = self.stored_utf16_units() {
+            return Some(units.to_vec());
+        }
+        match &self.value {
+            MatlabType::UTF8(v) | MatlabType::UTF16(v) => Some(
+                v.iter()
+                    .map(|&c| u16::try_from(u32::from(c)).unwrap_or(0xFFFD))
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Returns `true`, if character data read from a MAT-file contained surrogates without a
+    /// partner, which is not valid UTF-16.
+    pub(crate) fn has_invalid_utf16(&self) -> bool {
+        self.stored_utf16_units()
+            .is_some_and(|units| char::decode_utf16(units.iter().copied()).any(|c| c.is_err()))
+    }
+
+    /// Returns `true`, if any element is NaN.
+    ///
+    /// Only `single` and `double` arrays can contain NaN. A complex element is NaN, if its real
+    /// or imaginary part is NaN.
+    pub fn has_nan(&self) -> bool {
+        self.nan_count() > 0
+    }
+
+    /// Number of NaN elements.
+    ///
+    /// Example
+    /// ```
+    /// use matrw::{NumericArray, MatlabType};
+    ///
+    /// let arr = NumericArray::new(vec![1, 3], MatlabType::from(vec![1., f64::NAN, f64::NAN]), None).unwrap();
+    /// assert!(arr.has_nan());
+    /// assert_eq!(arr.nan_count(), 2);
+    /// ```
+    pub fn nan_count(&self) -> usize {
+        let re = nan_mask(&self.value);
+        match self.value_cmp.as_ref().map(nan_mask) {
+            Some(im) => re.iter().zip(im).filter(|&(&r, i)| r || i).count(),
+            None => re.iter().filter(|&&r| r).count(),
+        }
+    }
+
+    /// Replace NaN values by `with`.
+    ///
+    /// For complex arrays, real and imaginary parts are replaced independently. Arrays of other
+    /// classes than `single` and `double` are left unchanged.
+    ///
+    /// Example
+    /// ```
+    /// use matrw::{NumericArray, MatlabType};
+    ///
+    /// let mut arr = NumericArray::new(vec![1, 3], MatlabType::from(vec![1f32, f32::NAN, 3.]), None).unwrap();
+    /// arr.replace_nan(0.0);
+    /// assert_eq!(arr.value, MatlabType::from(vec![1f32, 0., 3.]));
+    /// ```
+    pub fn replace_nan(&mut self, with: f64) {
+        self.replace_float(|x| x.is_nan(), with);
+    }
+
+    /// Replace positive and negative infinite values by `with`.
+    ///
+    /// For complex arrays, real and imaginary parts are replaced independently. Arrays of other
+    /// classes than `single` and `double` are left unchanged.
+    ///
+    /// Example
+    /// ```
+    /// use matrw::{NumericArray, MatlabType};
+    ///
+    /// let mut arr = NumericArray::new(vec![1, 3], MatlabType::from(vec![f64::NEG_INFINITY, 2., f64::INFINITY]), None).unwrap();
+    /// arr.replace_inf(f64::NAN);
+    /// assert_eq!(arr.nan_count(), 2);
+    /// ```
+    pub fn replace_inf(&mut self, with: f64) {
+        self.replace_float(|x| x.is_infinite(), with);
+    }
+
+    fn replace_float(&mut self, pred: impl Fn(f64) -> bool, with: f64) {
+        let parts = std::iter::once(&mut self.value).chain(self.value_cmp.as_mut());
+        for part in parts {
+            match part {
+                MatlabType::F32(v) => v
+                    .iter_mut()
+                    .filter(|x| pred(**x as f64))
+                    .for_each(|x| *x = with as f32),
+                MatlabType::F64(v) => v.iter_mut().filter(|x| pred(**x)).for_each(|x| *x = with),
+                _ => {}
+            }
+        }
+    }
+
+    /// Report class, range and integrality of the values and the smallest class holding them
+    /// without loss, `None` for character and logical arrays.
+    ///
+    /// For complex arrays, real and imaginary parts are considered together.
+    ///
+    /// Example
+    /// ```
+    /// use matrw::{VariableClass, MatlabType, NumericArray};
+    ///
+    /// let arr = NumericArray::new(vec![1, 3], MatlabType::from(vec![-3.0, 0.0, 200.0]), None).unwrap();
+    /// let info = arr.precision_info().unwrap();
+    /// assert_eq!(info.class, VariableClass::Double);
+    /// assert_eq!(info.range, Some((-3.0, 200.0)));
+    /// assert!(info.all_integral);
+    /// assert_eq!(info.minimal_class, VariableClass::Int16);
+    /// ```
+    pub fn precision_info(&self) -> Option<PrecisionInfo> {
+        let class = VariableClass::numeric(&self.value)?;
+        let parts = std::iter::once(&self.value).chain(self.value_cmp.as_ref());
+
+        let (range, integer_range, fits_f32) = if class.is_integer() {
+            let values: Vec<i128> = parts.flat_map(MatlabType::to_i128).collect();
+            let range = values
+                .iter()
+                .min()
+                .zip(values.iter().max())
+                .map(|(&a, &b)| (a, b));
+            let fits_f32 = values.iter().all(|&x| x as f32 as i128 == x);
+            (range.map(|(a, b)| (a as f64, b as f64)), Some(range), fits_f32)
+        } else {
+            let values: Vec<f64> = parts.flat_map(MatlabType::to_f64).collect();
+            let range = values
+                .iter()
+                .filter(|x| !x.is_nan())
+                .fold(None, |range: Option<(f64, f64)>, &x| match range {
+                    Some((a, b)) => Some((a.min(x), b.max(x))),
+                    None => Some((x, x)),
+                });
+            let integral = values.iter().all(|x| x.is_finite() && x.fract() == 0.0);
+            let fits_f32 = values.iter().all(|&x| x.is_nan() || x as f32 as f64 == x);
+            let integer_range = integral.then(|| range.map(|(a, b)| (a as i128, b as i128)));
+            (range, integer_range, fits_f32)
+        };
+
+        let integer_class = integer_range.and_then(|range| {
+            INTEGER_CLASSES.into_iter().find(|&class| match range {
+                Some((min, max)) => {
+                    let (lo, hi) = integer_bounds(class);
+                    lo <= min && max <= hi
+                }
+                None => true,
+            })
+        });
+        let minimal_class = match integer_class {
+            Some(class) => class,
+            None if fits_f32 => VariableClass::Single,
+            None => VariableClass::Double,
+        };
+
+        Some(PrecisionInfo {
+            class,
+            range,
+            all_integral: integer_range.is_some(),
+            minimal_class,
+        })
+    }
+}
+
+/// Precision of the values of a [`NumericArray`], see [`NumericArray::precision_info`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrecisionInfo {
+    /// Class of the array
+    pub class: VariableClass,
+    /// Smallest and largest value without NaN, `None` for empty arrays or only NaN. Integers
+    /// beyond 2^53 are rounded.
+    pub range: Option<(f64, f64)>,
+    /// `true`, if all values are integers. NaN and infinite values are not.
+    pub all_integral: bool,
+    /// Smallest class holding all values exactly. Integer classes are preferred over `single`,
+    /// unsigned over signed classes of the same size.
+    pub minimal_class: VariableClass,
+}
+
+/// Integer classes by increasing size, unsigned first
+const INTEGER_CLASSES: [VariableClass; 8] = [
+    VariableClass::UInt8,
+    VariableClass::Int8,
+    VariableClass::UInt16,
+    VariableClass::Int16,
+    VariableClass::UInt32,
+    VariableClass::Int32,
+    VariableClass::UInt64,
+    VariableClass::Int64,
+];
+
+fn integer_bounds(class: VariableClass) -> (i128, i128) {
+    match class {
+        VariableClass::UInt8 => (0, u8::MAX as i128),
+        VariableClass::Int8 => (i8::MIN as i128, i8::MAX as i128),
+        VariableClass::UInt16 => (0, u16::MAX as i128),
+        VariableClass::Int16 => (i16::MIN as i128, i16::MAX as i128),
+        VariableClass::UInt32 => (0, u32::MAX as i128),
+        VariableClass::Int32 => (i32::MIN as i128, i32::MAX as i128),
+        VariableClass::UInt64 => (0, u64::MAX as i128),
+        VariableClass::Int64 => (i64::MIN as i128, i64::MAX as i128),
+        _ => unreachable!("Not an integer class"),
+    }
+}
+
+impl NumericArray {
+    /// Set all elements to `value`.
+    ///
+    /// `value` must have the class of the array. A complex array becomes real.
+    ///
+    /// Example
+    /// ```
+    /// use matrw::{NumericArray, MatlabType};
+    ///
+    /// let mut arr = NumericArray::new(vec![2, 2], MatlabType::from(vec![0.0; 4]), None).unwrap();
+    /// arr.fill(1.5).unwrap();
+    /// assert_eq!(arr.value, MatlabType::from(vec![1.5; 4]));
+    /// ```
+    pub fn fill<T: MatlabTypeMarker>(&mut self, value: T) -> Result<(), MatrwError> {
+        self.data_mut::<T>()?.fill(value);
+        self.value_cmp = None;
+        Ok(())
+    }
+
+    /// Overwrite column `j` of a matrix with `values`.
+    ///
+    /// `values` must have the class of the array and one element per row. The imaginary part of
+    /// the column is set to zero for complex arrays.
+    ///
+    /// Example
+    /// ```
+    /// use matrw::{NumericArray, MatlabType};
+    ///
+    /// let mut arr = NumericArray::new(vec![2, 3], MatlabType::from(vec![0i32; 6]), None).unwrap();
+    /// arr.set_column(1, &[7, 8]).unwrap();
+    /// assert_eq!(arr.value, MatlabType::from(vec![0i32, 0, 7, 8, 0, 0]));
+    /// ```
+    pub fn set_column<T: MatlabTypeMarker>(&mut self, j: usize, values: &[T]) -> Result<(), MatrwError> {
+        if self.dim.len() != 2 {
+            return Err(MatrwError::TypeConstruction(format!(
+                "Columns can only be set in matrices, found {} dimensions.",
+                self.dim.len()
+            )));
+        }
+        let (rows, cols) = (self.dim[0], self.dim[1]);
+        if j >= cols {
+            return Err(MatrwError::AccessError(format!(
+                "Column {} out of range for matrix with {} columns.",
+                j, cols
+            )));
+        }
+        ensure_matching_dimension(rows, values.len())?;
+
+        let range = j * rows..(j + 1) * rows;
+        self.data_mut::<T>()?[range.clone()].copy_from_slice(values);
+        if let Some(cmp) = &mut self.value_cmp {
+            set_zero(cmp, range);
+        }
+        Ok(())
+    }
+
+    /// Copy `other` into the array, placing its first element at the subscripts `start`.
+    ///
+    /// `other` must have the class of the array and fit into it. It may have fewer dimensions
+    /// than the array, missing dimensions are treated as 1. A real array becomes complex, if
+    /// `other` is complex.
+    ///
+    /// Example
+    /// ```
+    /// use matrw::{NumericArray, MatlabType};
+    ///
+    /// let mut result = NumericArray::new(vec![3, 3], MatlabType::from(vec![0.0; 9]), None).unwrap();
+    /// let segment = NumericArray::new(vec![2, 1], MatlabType::from(vec![1.0, 2.0]), None).unwrap();
+    ///
+    /// result.set_block(&[1, 2], &segment).unwrap();
+    /// assert_eq!(result.value, MatlabType::from(vec![0., 0., 0., 0., 0., 0., 0., 1., 2.]));
+    /// ```
+    pub fn set_block(&mut self, start: &[usize], other: &NumericArray) -> Result<(), MatrwError> {
+        self.ensure_same_class(&other.value)?;
+        if start.len() != self.dim.len() || other.dim.len() > self.dim.len() {
+            return Err(MatrwError::AccessError(format!(
+                "Block with {} dimensions and start {:?} does not match array with {} dimensions.",
+                other.dim.len(),
+                start,
+                self.dim.len()
+            )));
+        }
+
+        let mut block_dim = other.dim.to_vec();
+        block_dim.resize(self.dim.len(), 1);
+        if (0..self.dim.len()).any(|k| start[k] + block_dim[k] > self.dim[k]) {
+            return Err(MatrwError::AccessError(format!(
+                "Block of size {:?} at {:?} exceeds array of size {:?}.",
+                block_dim, start, self.dim
+            )));
+        }
+
+        let indices: Vec<usize> = (0..other.value.len())
+            .map(|i| {
+                let subs: Vec<usize> = ind2sub(&block_dim, i)
+                    .expect("Index within block")
+                    .iter()
+                    .zip(start)
+                    .map(|(s, o)| s + o)
+                    .collect();
+                sub2ind(&self.dim, &subs).expect("Subscripts within array")
+            })
+            .collect();
+
+        copy_elements(&mut self.value, &other.value, &indices);
+        match (&mut self.value_cmp, &other.value_cmp) {
+            (Some(cmp), Some(other_cmp)) => copy_elements(cmp, other_cmp, &indices),
+            (Some(cmp), None) => indices.iter().for_each(|&i| set_zero(cmp, i..i + 1)),
+            (None, Some(other_cmp)) => {
+                let mut cmp = self.value.clone();
+                let n = cmp.len();
+                set_zero(&mut cmp, 0..n);
+                copy_elements(&mut cmp, other_cmp, &indices);
+                self.value_cmp = Some(cmp);
+            }
+            (None, None) => {}
+        }
+
+        Ok(())
+    }
+
+    /// Concatenate `arrays` along dimension `dim`, counted from 0 for the rows.
+    ///
+    /// All arrays must have the same class and the same size in all other dimensions. Missing
+    /// dimensions are treated as 1, so vectors and matrices can be stacked along dimension 2. The
+    /// result is complex, if one of the arrays is complex.
+    ///
+    /// Example
+    /// ```
+    /// use matrw::{NumericArray, MatlabType};
+    ///
+    /// let a = NumericArray::new(vec![2, 1], MatlabType::from(vec![1.0, 2.0]), None).unwrap();
+    /// let b = NumericArray::new(vec![2, 2], MatlabType::from(vec![3.0, 4.0, 5.0, 6.0]), None).unwrap();
+    ///
+    /// let c = NumericArray::concat(&[a.clone(), b], 1).unwrap();
+    /// assert_eq!(c.dim, vec![2, 3]);
+    /// assert_eq!(c.value, MatlabType::from(vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]));
+    ///
+    /// let d = NumericArray::concat(&[a.clone(), a], 0).unwrap();
+    /// assert_eq!(d.dim, vec![4, 1]);
+    /// ```
+    pub fn concat(arrays: &[NumericArray], dim: usize) -> Result<NumericArray, MatrwError> {
+        let Some(first) = arrays.first() else {
+            return Err(MatrwError::TypeConstruction(
+                "Cannot concatenate an empty list of arrays.".to_string(),
+            ));
+        };
+
+        let ndim = arrays.iter().map(|a| a.dim.len()).max().unwrap_or(2).max(dim + 1);
+        let padded = |a: &NumericArray| {
+            let mut d = a.dim.to_vec();
+            d.resize(ndim, 1);
+            d
+        };
+
+        let mut result_dim = padded(first);
+        result_dim[dim] = 0;
+        for (i, a) in arrays.iter().enumerate() {
+            let (class, other) = (
+                VariableClass::of_type(&first.value),
+                VariableClass::of_type(&a.value),
+            );
+            if class != other {
+                return Err(MatrwError::TypeConstruction(format!(
+                    "Array {} of class '{}' does not match class '{}' of the first array.",
+                    i, other, class
+                )));
+            }
+            let d = padded(a);
+            if (0..ndim).any(|k| k != dim && d[k] != result_dim[k]) {
+                return Err(MatrwError::TypeConstruction(format!(
+                    "Array {} of size {:?} does not match size {:?} of the first array outside dimension {}.",
+                    i, a.dim, first.dim, dim
+                )));
+            }
+            result_dim[dim] += d[dim];
+        }
+
+        // Character data may be stored either way, the result takes the storage of the first
+        let values = arrays
+            .iter()
+            .map(|a| match (&first.value, a.value.clone()) {
+                (MatlabType::UTF8(_), MatlabType::UTF16(v)) => MatlabType::UTF8(v),
+                (MatlabType::UTF16(_), MatlabType::UTF8(v)) => MatlabType::UTF16(v),
+                (_, v) => v,
+            })
+            .collect();
+        let value = MatlabType::join(values).expect("At least one array");
+
+        // Trailing singleton dimensions beyond the second are dropped, like MATLAB does
+        while result_dim.len() > 2 && result_dim.last() == Some(&1) {
+            result_dim.pop();
+        }
+        let mut result = NumericArray::new(result_dim, value, None)?;
+
+        // Arrays follow each other in memory, if all dimensions after `dim` are 1
+        let contiguous = result.dim.iter().skip(dim + 1).all(|&n| n == 1);
+        if contiguous && arrays.iter().all(|a| a.value_cmp.is_none()) {
+            return Ok(result);
+        }
+
+        let mut start = vec![0; ndim];
+        for a in arrays {
+            start.truncate(result.dim.len());
+            result.set_block(&start, a)?;
+            start.resize(ndim, 0);
+            start[dim] += padded(a)[dim];
+        }
+        Ok(result)
+    }
+
+    /// Matrices `A(:,:,k)` of the array, one per page.
+    ///
+    /// Dimensions beyond the second are enumerated in column-major order, like MATLAB's linear
+    /// indexing of the pages. Matrices and vectors are a single page, as [`MatDim`] always holds
+    /// at least two dimensions.
+    ///
+    /// Example
+    /// ```
+    /// use matrw::{NumericArray, MatlabType};
+    ///
+    /// // Two 2x2 transforms, stacked along the third dimension
+    /// let values: Vec<f64> = (1..=8).map(f64::from).collect();
+    /// let stack = NumericArray::new(vec![2, 2, 2], MatlabType::from(values), None).unwrap();
+    ///
+    /// let pages = stack.pages();
+    /// assert_eq!(pages.len(), 2);
+    /// assert_eq!(pages[1].dim, vec![2, 2]);
+    /// assert_eq!(pages[1].value, MatlabType::from(vec![5.0, 6.0, 7.0, 8.0]));
+    ///
+    /// assert_eq!(NumericArray::from_pages(&pages).unwrap(), stack);
+    /// ```
+    pub fn pages(&self) -> Vec<NumericArray> {
+        let (rows, cols) = (self.dim[0], self.dim[1]);
+        let n_pages: usize = self.dim[2..].iter().product();
+        let size = rows * cols;
+
+        (0..n_pages)
+            .map(|k| {
+                let range = k * size..(k + 1) * size;
+                let page = |value: &MatlabType| slice_elements(value, range.clone());
+                let (value, value_cmp) = (page(&self.value), self.value_cmp.as_ref().map(page));
+                NumericArray::new(vec![rows, cols], value, value_cmp).expect("Page size matches")
+            })
+            .collect()
+    }
+
+    /// Stack matrices along the third dimension, the reverse of [`NumericArray::pages`].
+    ///
+    /// All pages must be matrices of the same size and class, see [`NumericArray::concat`].
+    pub fn from_pages(pages: &[NumericArray]) -> Result<NumericArray, MatrwError> {
+        if let Some((i, page)) = pages.iter().enumerate().find(|(_, p)| p.dim.len() > 2) {
+            return Err(MatrwError::TypeConstruction(format!(
+                "Page {} of size {:?} is not a matrix.",
+                i, page.dim
+            )));
+        }
+        Self::concat(pages, 2)
+    }
+
+    /// Copy the real part of the array into `buf` in column-major order.
+    ///
+    /// `buf` must match the class and the number of elements of the array.
+    ///
+    /// Example
+    /// ```
+    /// use matrw::{NumericArray, MatlabType};
+    ///
+    /// let arr = NumericArray::new(vec![2, 2], MatlabType::from(vec![1.0, 2.0, 3.0, 4.0]), None).unwrap();
+    ///
+    /// let mut buf = [0.0; 4];
+    /// arr.copy_to(&mut buf).unwrap();
+    /// assert_eq!(buf, [1.0, 2.0, 3.0, 4.0]);
+    ///
+    /// assert!(arr.copy_to(&mut [0.0f32; 4]).is_err());
+    /// assert!(arr.copy_to(&mut [0.0; 3]).is_err());
+    /// ```
+    pub fn copy_to<T: MatlabTypeMarker>(&self, buf: &mut [T]) -> Result<(), MatrwError> {
+        let Some(data) = T::inner_ref(&self.value) else {
+            return Err(MatrwError::TypeConstruction(format!(
+                "Cannot read '{}' array into '{}' buffer.",
+                VariableClass::of_type(&self.value),
+                VariableClass::of_type(&MatlabType::from(Vec::<T>::new()))
+            )));
+        };
+        self.debug_assert_layout(data.len());
+        ensure_matching_dimension(data.len(), buf.len())?;
+
+        buf.copy_from_slice(data);
+        Ok(())
+    }
+
+    /// Move the real part of the array out as `Box<[T]>` in column-major order, without copying
+    /// the values.
+    ///
+    /// The buffer is aligned like `T`. Consumers requiring a larger alignment, e.g. 64 bytes for
+    /// SIMD or FFTW, get a copy with [`NumericArray::to_aligned`].
+    ///
+    /// Returns [`MatrwError::TypeConstruction`], if `T` does not match the class of the array.
+    ///
+    /// Example
+    /// ```
+    /// use matrw::{NumericArray, MatlabType};
+    ///
+    /// let arr = NumericArray::new(vec![2, 2], MatlabType::from(vec![1.0, 2.0, 3.0, 4.0]), None).unwrap();
+    /// assert!(arr.clone().into_boxed_slice::<f32>().is_err());
+    ///
+    /// let data: Box<[f64]> = arr.into_boxed_slice().unwrap();
+    /// assert_eq!(&*data, &[1.0, 2.0, 3.0, 4.0]);
+    /// ```
+    pub fn into_boxed_slice<T: MatlabTypeMarker>(self) -> Result<Box<[T]>, MatrwError> {
+        self.debug_assert_layout(self.value.len());
+        let class = VariableClass::of_type(&self.value);
+        T::inner(self.value).map(Vec::into_boxed_slice).ok_or_else(|| {
+            MatrwError::TypeConstruction(format!(
+                "Cannot move '{}' array into '{}' buffer.",
+                class,
+                VariableClass::of_type(&MatlabType::from(Vec::<T>::new()))
+            ))
+        })
+    }
+
+    /// Copy the real part of the array into a `Vec<T>` in row-major order, i.e. with the last
+    /// subscript varying fastest, as expected by C, numpy or ndarray in standard layout.
+    ///
+    /// MATLAB and [`NumericArray::value`] store the elements in column-major order, the first
+    /// subscript varying fastest. Returns [`None`], if `T` does not match the class of the array.
+    ///
+    /// Example
+    /// ```
+    /// use matrw::{MatVariable, matvar};
+    ///
+    /// let m = matvar!([[1, 2], [3, 4]]);
+    /// assert_eq!(m.to_vec_i32().unwrap(), vec![1, 3, 2, 4]);
+    ///
+    /// let MatVariable::NumericArray(arr) = m else { unreachable!() };
+    /// assert_eq!(arr.to_row_major_vec::<i32>().unwrap(), vec![1, 2, 3, 4]);
+    /// ```
+    pub fn to_row_major_vec<T: MatlabTypeMarker>(&self) -> Option<Vec<T>> {
+        let data = T::inner_ref(&self.value)?;
+        self.debug_assert_layout(data.len());
+
+        Some(row_major_order(&self.dim).into_iter().map(|k| data[k]).collect())
+    }
+
+    /// Construct a real array from `value` in row-major order, i.e. with the last subscript
+    /// varying fastest. The values are reordered into the column-major order of MATLAB.
+    ///
+    /// `dim` is normalized like in [`NumericArray::new`]. Returns [`MatrwError::TypeConstruction`],
+    /// if the product of `dim` differs from the number of values.
+    ///
+    /// Example
+    /// ```
+    /// use matrw::{NumericArray, MatlabType};
+    ///
+    /// // The rows [1, 2, 3] and [4, 5, 6]
+    /// let arr = NumericArray::from_row_major(vec![2, 3], vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]).unwrap();
+    /// assert_eq!(arr.value, MatlabType::from(vec![1.0, 4.0, 2.0, 5.0, 3.0, 6.0]));
+    /// assert_eq!(arr.to_row_major_vec::<f64>().unwrap(), vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+    /// ```
+    pub fn from_row_major<T: MatlabTypeMarker>(dim: Vec<usize>, value: Vec<T>) -> Result<Self, MatrwError> {
+        if !dim.is_empty() {
+            ensure_matching_dimension(ensure_numel(&dim)?, value.len())?;
+        }
+        let dim = normalize_dimension(dim, value.len());
+
+        let mut data = value.clone();
+        for (&k, x) in row_major_order(&dim).iter().zip(value) {
+            data[k] = x;
+        }
+        Self::with_dim(dim, MatlabType::from(data), None)
+    }
+
+    /// Array of class `class` from the packed values `bytes` in byte order `order`, in
+    /// column-major order.
+    ///
+    /// Returns [`MatrwError::TypeConstruction`], if the length of `bytes` is not the number of
+    /// elements of `dim` times the size of the class. The bytes are copied into values of the
+    /// class, in one pass for [`ByteOrder::native`], and value by value for the other order.
+    ///
+    /// Example
+    /// ```
+    /// use matrw::{ByteOrder, VariableClass, MatlabType, NumericArray};
+    ///
+    /// // Two big-endian 16-bit samples from a sensor
+    /// let arr = NumericArray::from_bytes(vec![2, 1], VariableClass::Int16, vec![0x01, 0x00, 0xff, 0xfe], ByteOrder::Big).unwrap();
+    /// assert_eq!(arr.value, MatlabType::from(vec![256i16, -2]));
+    ///
+    /// assert_eq!(arr.to_raw_bytes(ByteOrder::Little).unwrap(), vec![0x00, 0x01, 0xfe, 0xff]);
+    /// assert!(NumericArray::from_bytes(vec![2, 1], VariableClass::Int16, vec![0; 3], ByteOrder::Big).is_err());
+    /// ```
+    pub fn from_bytes(
+        dim: Vec<usize>,
+        class: VariableClass,
+        bytes: Vec<u8>,
+        order: ByteOrder,
+    ) -> Result<Self, MatrwError> {
+        let Some(size) = class.element_size() else {
+            return Err(MatrwError::TypeConstruction(format!(
+                "Class {} has no packed values.",
+                class
+            )));
+        };
+        let numel = if dim.is_empty() {
+            bytes.len() / size
+        } else {
+            ensure_numel(&dim)?
+        };
+        if numel.checked_mul(size) != Some(bytes.len()) {
+            return Err(MatrwError::TypeConstruction(format!(
+                "{} bytes do not hold {} values of {} bytes each.",
+                bytes.len(),
+                numel,
+                size
+            )));
+        }
+
+        // Bytes in native order are copied at once, others converted value by value
+        macro_rules! decode {
+            ($variant:ident, $t:ty) => {
+                MatlabType::$variant(if order == ByteOrder::native() {
+                    let mut values = vec![<$t>::default(); numel];
+                    bytemuck::cast_slice_mut(&mut values).copy_from_slice(&bytes);
+                    values
+                } else {
+                    bytes
+                        .chunks_exact(size)
+                        .map(|b| {
+                            let b: [u8; std::mem::size_of::<$t>()] = b.try_into().expect("Chunk of the size");
+                            match order {
+                                ByteOrder::Little => <$t>::from_le_bytes(b),
+                                ByteOrder::Big => <$t>::from_be_bytes(b),
+                            }
+                        })
+                        .collect()
+                })
+            };
+        }
+        let value = match class {
+            VariableClass::UInt8 => MatlabType::U8(bytes),
+            VariableClass::Int8 => decode!(I8, i8),
+            VariableClass::UInt16 => decode!(U16, u16),
+            VariableClass::Int16 => decode!(I16, i16),
+            VariableClass::UInt32 => decode!(U32, u32),
+            VariableClass::Int32 => decode!(I32, i32),
+            VariableClass::UInt64 => decode!(U64, u64),
+            VariableClass::Int64 => decode!(I64, i64),
+            VariableClass::Single => decode!(F32, f32),
+            VariableClass::Double => decode!(F64, f64),
+            _ => unreachable!("Numeric classes have an element size"),
+        };
+        Self::new(dim, value, None)
+    }
+
+    /// Values in native byte order, in column-major order, without copying them.
+    ///
+    /// Returns `None` for complex, character and logical arrays.
+    ///
+    /// Example
+    /// ```
+    /// use matrw::{ByteOrder, NumericArray, VariableClass};
+    ///
+    /// let arr = NumericArray::from_row_major(vec![1, 2], vec![1u16, 2]).unwrap();
+    /// let bytes = arr.as_raw_bytes().unwrap();
+    /// assert_eq!(bytes.len(), 4);
+    ///
+    /// let copy = NumericArray::from_bytes(vec![1, 2], VariableClass::UInt16, bytes.to_vec(), ByteOrder::native());
+    /// assert_eq!(copy.unwrap(), arr);
+    /// ```
+    pub fn as_raw_bytes(&self) -> Option<&[u8]> {
+        if self.value_cmp.is_some() {
+            return None;
+        }
+        Some(match &self.value {
+            MatlabType::U8(v) => v,
+            MatlabType::I8(v) => bytemuck::cast_slice(v),
+            MatlabType::U16(v) => bytemuck::cast_slice(v),
+            MatlabType::I16(v) => bytemuck::cast_slice(v),
+            MatlabType::U32(v) => bytemuck::cast_slice(v),
+            MatlabType::I32(v) => bytemuck::cast_slice(v),
+            MatlabType::U64(v) => bytemuck::cast_slice(v),
+            MatlabType::I64(v) => bytemuck::cast_slice(v),
+            MatlabType::F32(v) => bytemuck::cast_slice(v),
+            MatlabType::F64(v) => bytemuck::cast_slice(v),
+            MatlabType::UTF8(_) | MatlabType::UTF16(_) | MatlabType::BOOL(_) => return None,
+        })
+    }
+
+    /// Copy of the values packed in byte order `order`, in column-major order, the reverse of
+    /// [`NumericArray::from_bytes`].
+    ///
+    /// Returns `None` for complex, character and logical arrays.
+    pub fn to_raw_bytes(&self, order: ByteOrder) -> Option<Vec<u8>> {
+        if self.value_cmp.is_some() {
+            return None;
+        }
+
+        macro_rules! encode {
+            ($v:expr) => {
+                $v.iter()
+                    .flat_map(|x| match order {
+                        ByteOrder::Little => x.to_le_bytes(),
+                        ByteOrder::Big => x.to_be_bytes(),
+                    })
+                    .collect()
+            };
+        }
+        Some(match &self.value {
+            MatlabType::U8(v) => v.clone(),
+            MatlabType::I8(v) => encode!(v),
+            MatlabType::U16(v) => encode!(v),
+            MatlabType::I16(v) => encode!(v),
+            MatlabType::U32(v) => encode!(v),
+            MatlabType::I32(v) => encode!(v),
+            MatlabType::U64(v) => encode!(v),
+            MatlabType::I64(v) => encode!(v),
+            MatlabType::F32(v) => encode!(v),
+            MatlabType::F64(v) => encode!(v),
+            MatlabType::UTF8(_) | MatlabType::UTF16(_) | MatlabType::BOOL(_) => return None,
+        })
+    }
+
+    /// The column-major contract every accessor relies on
+    fn debug_assert_layout(&self, len: usize) {
+        debug_assert_eq!(
+            self.dim.iter().product::<usize>(),
+            len,
+            "Dimensions {:?} don't match the number of values",
+            self.dim
+        );
+        debug_assert!(
+            self.value_cmp.as_ref().is_none_or(|cmp| cmp.len() == len),
+            "Real and imaginary parts differ in length"
+        );
+    }
+
+    fn data_mut<T: MatlabTypeMarker>(&mut self) -> Result<&mut Vec<T>, MatrwError> {
+        self.ensure_same_class(&MatlabType::from(Vec::<T>::new()))?;
+        Ok(T::inner_mut(&mut self.value).expect("Class checked"))
+    }
+
+    fn ensure_same_class(&self, value: &MatlabType) -> Result<(), MatrwError> {
+        let (class, other) = (VariableClass::of_type(&self.value), VariableClass::of_type(value));
+        if class != other {
+            return Err(MatrwError::TypeConstruction(format!(
+                "Cannot assign '{}' values to '{}' array.",
+                other, class
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Set the elements in `range` to zero.
+fn set_zero(value: &mut MatlabType, range: Range<usize>) {
+    match value {
+        MatlabType::U8(v) => v[range].fill(0),
+        MatlabType::I8(v) => v[range].fill(0),
+        MatlabType::U16(v) => v[range].fill(0),
+        MatlabType::I16(v) => v[range].fill(0),
+        MatlabType::U32(v) => v[range].fill(0),
+        MatlabType::I32(v) => v[range].fill(0),
+        MatlabType::U64(v) => v[range].fill(0),
+        MatlabType::I64(v) => v[range].fill(0),
+        MatlabType::F32(v) => v[range].fill(0.0),
+        MatlabType::F64(v) => v[range].fill(0.0),
+        MatlabType::UTF8(v) | MatlabType::UTF16(v) => v[range].fill('\0'),
+        MatlabType::BOOL(v) => v[range].fill(false),
+    }
+}
+
+/// Elements in `range`, of the same class.
+fn slice_elements(value: &MatlabType, range: Range<usize>) -> MatlabType {
+    match value {
+        MatlabType::U8(v) => MatlabType::U8(v[range].to_vec()),
+        MatlabType::I8(v) => MatlabType::I8(v[range].to_vec()),
+        MatlabType::U16(v) => MatlabType::U16(v[range].to_vec()),
+        MatlabType::I16(v) => MatlabType::I16(v[range].to_vec()),
+        MatlabType::U32(v) => MatlabType::U32(v[range].to_vec()),
+        MatlabType::I32(v) => MatlabType::I32(v[range].to_vec()),
+        MatlabType::U64(v) => MatlabType::U64(v[range].to_vec()),
+        MatlabType::I64(v) => MatlabType::I64(v[range].to_vec()),
+        MatlabType::F32(v) => MatlabType::F32(v[range].to_vec()),
+        MatlabType::F64(v) => MatlabType::F64(v[range].to_vec()),
+        MatlabType::UTF8(v) => MatlabType::UTF8(v[range].to_vec()),
+        MatlabType::UTF16(v) => MatlabType::UTF16(v[range].to_vec()),
+        MatlabType::BOOL(v) => MatlabType::BOOL(v[range].to_vec()),
+    }
+}
+
+/// Copy the elements of `src` to the positions `indices` of `dst`, which has the same class.
+fn copy_elements(dst: &mut MatlabType, src: &MatlabType, indices: &[usize]) {
+    fn copy<T: Copy>(dst: &mut [T], src: &[T], indices: &[usize]) {
+        for (&i, &x) in indices.iter().zip(src) {
+            dst[i] = x;
+        }
+    }
+
+    match (dst, src) {
+        (MatlabType::U8(d), MatlabType::U8(s)) => copy(d, s, indices),
+        (MatlabType::I8(d), MatlabType::I8(s)) => copy(d, s, indices),
+        (MatlabType::U16(d), MatlabType::U16(s)) => copy(d, s, indices),
+        (MatlabType::I16(d), MatlabType::I16(s)) => copy(d, s, indices),
+        (MatlabType::U32(d), MatlabType::U32(s)) => copy(d, s, indices),
+        (MatlabType::I32(d), MatlabType::I32(s)) => copy(d, s, indices),
+        (MatlabType::U64(d), MatlabType::U64(s)) => copy(d, s, indices),
+        (MatlabType::I64(d), MatlabType::I64(s)) => copy(d, s, indices),
+        (MatlabType::F32(d), MatlabType::F32(s)) => copy(d, s, indices),
+        (MatlabType::F64(d), MatlabType::F64(s)) => copy(d, s, indices),
+        (MatlabType::UTF8(d) | MatlabType::UTF16(d), MatlabType::UTF8(s) | MatlabType::UTF16(s)) => {
+            copy(d, s, indices)
+        }
+        (MatlabType::BOOL(d), MatlabType::BOOL(s)) => copy(d, s, indices),
+        _ => unreachable!("Classes checked before copying"),
+    }
+}
+
+/// NaN flags of all elements, all `false` for classes other than `single` and `double`.
+fn nan_mask(value: &MatlabType) -> Vec<bool> {
+    match value {
+        MatlabType::F32(v) => v.iter().map(|x| x.is_nan()).collect(),
+        MatlabType::F64(v) => v.iter().map(|x| x.is_nan()).collect(),
+        _ => vec![false; value.len()],
+    }
 }
 
 impl From<NumericArray7> for NumericArray {
     fn from(value: NumericArray7) -> Self {
         use ArrayDataValueVar::*;
 
+        let storage_type = value.storage_type();
         let (_name, dim, val, val_cmp) = value.value();
+        let mut utf16_units = None;
 
-        let value = match val {
+        let mut value = match val {
             ArrayValueU8(v) => MatlabType::U8(v),
             ArrayValueI8(v) => MatlabType::I8(v),
             ArrayValueU16(v) => MatlabType::U16(v),
@@ -331,10 +1366,15 @@ impl From<NumericArray7> for NumericArray {
             ArrayValueF64(v) => MatlabType::F64(v),
             ArrayValueUTF8(v) => MatlabType::UTF8(v),
             ArrayValueUTF16(v) => MatlabType::UTF16(v),
+            ArrayValueUTF16Units(v) => {
+                let chars = utf16_unit_chars(&v);
+                utf16_units = Some(v);
+                MatlabType::UTF16(chars)
+            }
             ArrayValueBOOL(v) => MatlabType::BOOL(v),
         };
 
-        let value_cmp = match val_cmp {
+        let mut value_cmp = match val_cmp {
             Some(ArrayValueU8(v)) => Some(MatlabType::U8(v)),
             Some(ArrayValueI8(v)) => Some(MatlabType::I8(v)),
             Some(ArrayValueU16(v)) => Some(MatlabType::U16(v)),
@@ -351,7 +1391,21 @@ impl From<NumericArray7> for NumericArray {
             _ => None,
         };
 
-        Self::new(dim, value, value_cmp).expect("Could not create NumericArray.")
+        // Files storing another number of values than the dimensions declare are reported when
+        // read, see `NumericArray7::length_mismatch`. The values are fitted to the dimensions.
+        let numel = dim.iter().product();
+        value.resize(numel);
+        if let Some(cmp) = &mut value_cmp {
+            cmp.resize(numel);
+        }
+        if let Some(units) = &mut utf16_units {
+            units.resize(numel, 0);
+        }
+
+        let mut arr = Self::with_dim(dim, value, value_cmp).expect("Could not create NumericArray.");
+        arr.storage_type = Some(storage_type);
+        arr.utf16_units = utf16_units;
+        arr
     }
 }
 
@@ -443,6 +1497,37 @@ impl Display for NumericArray {
     }
 }
 
+/// Column-major indices of the elements of an array of dimensions `dim`, in row-major order
+fn row_major_order(dim: &[usize]) -> Vec<usize> {
+    let n = dim.iter().product::<usize>();
+    let mut order = Vec::with_capacity(n);
+    if n == 0 {
+        return order;
+    }
+
+    let strides = dim
+        .iter()
+        .scan(1, |stride, &d| {
+            let s = *stride;
+            *stride *= d;
+            Some(s)
+        })
+        .collect::<Vec<_>>();
+    let mut subs = vec![0; dim.len()];
+    for _ in 0..n {
+        order.push(subs.iter().zip(&strides).map(|(i, s)| i * s).sum());
+        // Increment the subscripts, the last one fastest
+        for ax in (0..dim.len()).rev() {
+            subs[ax] += 1;
+            if subs[ax] < dim[ax] {
+                break;
+            }
+            subs[ax] = 0;
+        }
+    }
+    order
+}
+
 /// Convert a row vectors into a column-major representation
 ///
 ///
@@ -465,7 +1550,7 @@ fn nested_row_vecs_to_colmaj_array(
         }
     }
 
-    let n_cols = rows[0].dim().iter().product();
+    let n_cols = rows[0].dim().numel();
     let n_rows = rows.len();
 
     let mut rows_vec = vec![];
@@ -531,7 +1616,7 @@ fn nested_col_vecs_to_colmaj_array(
         }
     }
 
-    let n_rows = cols[0].dim().iter().product();
+    let n_rows = cols[0].dim().numel();
     let n_cols = cols.len();
 
     let mut cols_vec = vec![];
@@ -596,10 +1681,7 @@ fn flatten_higher_dim_nested_array(
         }
     }
 
-    let new_dim = vec![value[0].dim(), vec![value.len()]]
-        .into_iter()
-        .flatten()
-        .collect();
+    let new_dim = [value[0].dim().into_vec(), vec![value.len()]].concat();
 
     let mut new_value = vec![];
     for v in value.iter() {
@@ -634,37 +1716,116 @@ fn flatten_higher_dim_nested_array(
     Ok((new_dim, new_value, new_value_cmp))
 }
 
-/// Check of every `NumericArray` has the same dimension
-///
-pub fn check_same_dim(vec: &[MatVariable]) -> bool {
-    if vec.is_empty() {
-        return false;
+/// Collect the CSC representation of a dense `f64` matrix, keeping elements with an absolute value
+/// larger than `eps` in either the real or the imaginary part.
+fn f64_to_sparse(
+    re: Vec<f64>,
+    im: Option<Vec<f64>>,
+    n_rows: usize,
+    n_cols: usize,
+    eps: f64,
+) -> (Vec<usize>, Vec<usize>, Vec<f64>, Option<Vec<f64>>) {
+    let mut ir = vec![];
+    let mut jc = vec![0];
+    let mut re_new = vec![];
+    let mut im_new = im.as_ref().map(|_| vec![]);
+
+    let keep = |x: f64| x.is_nan() || x.abs() > eps;
+
+    for j in 0..n_cols {
+        for i in 0..n_rows {
+            let k = i + j * n_rows;
+            let im_k = im.as_ref().map(|v| v[k]);
+            if keep(re[k]) || im_k.is_some_and(keep) {
+                ir.push(i);
+                re_new.push(re[k]);
+                if let (Some(v), Some(x)) = (im_new.as_mut(), im_k) {
+                    v.push(x);
+                }
+            }
+        }
+        jc.push(ir.len());
     }
 
-    let first = vec.first().unwrap().dim();
+    (ir, jc, re_new, im_new)
+}
 
-    vec.iter().map(|x| x.dim() == first).into_iter().all(|x| x)
+/// Check if every variable has the same dimension
+///
+#[deprecated(note = "use MatVariable::same_shape")]
+pub fn check_same_dim(vec: &[MatVariable]) -> bool {
+    MatVariable::same_shape(vec)
 }
 
-/// Check of every `NumericArray` has the same type
+/// Check if every variable is a `NumericArray` of the same type
 ///
+#[deprecated(note = "use MatVariable::same_numeric_class")]
 pub fn check_same_type(vec: &[MatVariable]) -> bool {
-    if vec.is_empty() {
-        return false;
-    }
-
-    let _first = vec.first().unwrap().numeric_type();
-
-    vec.iter()
-        .map(|x| matches!(x.numeric_type(), _first))
-        .into_iter()
-        .all(|x| x)
+    MatVariable::same_numeric_class(vec)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::OwnedIndex;
+    use crate::interface::types::cell_array::CellArray;
+
+    #[test]
+    #[allow(deprecated)]
+    fn deprecated_checks() {
+        let vars = [crate::matvar!([1u8, 2u8]), crate::matvar!([3u8, 4u8])];
+        assert!(check_same_dim(&vars) && check_same_type(&vars));
+        let mixed = [crate::matvar!(1u8), crate::matvar!([2.0, 3.0])];
+        assert!(!check_same_dim(&mixed) && !check_same_type(&mixed));
+        assert!(!check_same_dim(&[]) && !check_same_type(&[]));
+    }
+
+    #[test]
+    fn precision_info() {
+        let info = |value: MatlabType, cmp: Option<MatlabType>| {
+            let n = value.len();
+            NumericArray::new(vec![1, n], value, cmp)
+                .unwrap()
+                .precision_info()
+                .unwrap()
+        };
+
+        let i = info(MatlabType::from(vec![0.5, -1.0, f64::NAN]), None);
+        assert_eq!(i.range, Some((-1.0, 0.5)));
+        assert!(!i.all_integral);
+        assert_eq!(i.minimal_class, VariableClass::Single);
+
+        let i = info(MatlabType::from(vec![0.1, 1.0]), None);
+        assert_eq!(i.minimal_class, VariableClass::Double);
+        let i = info(MatlabType::from(vec![1.0, f64::INFINITY]), None);
+        assert!(!i.all_integral);
+        assert_eq!(i.minimal_class, VariableClass::Single);
+        let i = info(MatlabType::from(vec![1e30]), None);
+        assert!(i.all_integral);
+        assert_eq!(i.minimal_class, VariableClass::Double);
+
+        let i = info(
+            MatlabType::from(vec![0.0, 255.0]),
+            Some(MatlabType::from(vec![0.0, -1.0])),
+        );
+        assert_eq!(i.range, Some((-1.0, 255.0)));
+        assert_eq!(i.minimal_class, VariableClass::Int16);
+
+        let i = info(MatlabType::from(vec![u64::MAX, 1]), None);
+        assert_eq!(i.class, VariableClass::UInt64);
+        assert_eq!(i.minimal_class, VariableClass::UInt64);
+        let i = info(MatlabType::from(vec![-5i64, 70000]), None);
+        assert_eq!(i.minimal_class, VariableClass::Int32);
+        let i = info(MatlabType::from(vec![-128i32, 127]), None);
+        assert_eq!(i.minimal_class, VariableClass::Int8);
+
+        let i = info(MatlabType::from(Vec::<f32>::new()), None);
+        assert_eq!(i.range, None);
+        assert!(i.all_integral);
+        assert_eq!(i.minimal_class, VariableClass::UInt8);
+
+        assert_eq!(NumericArray::from("abc").precision_info(), None);
+    }
 
     #[test]
     fn wrong_dim() {
@@ -672,6 +1833,14 @@ mod tests {
         let m = NumericArray::new(vec![1, 4], MatlabType::from(a), None);
 
         assert!(matches!(m.expect_err(""), MatrwError::TypeConstruction(_)));
+
+        // Product of the dimensions wraps to 2
+        let m = NumericArray::new(
+            vec![usize::MAX / 2 + 2, 2],
+            MatlabType::from(vec![1.0, 2.0]),
+            None,
+        );
+        assert!(matches!(m.expect_err(""), MatrwError::TypeConstruction(_)));
     }
     #[test]
     fn mixed_dim() {
@@ -840,4 +2009,326 @@ mod tests {
         assert_eq!(m_sparse.elem([2, 2]).to_f64(), Some(6.0));
         assert_eq!(m_sparse.elem([3, 2]).to_f64(), Some(0.0));
     }
+
+    #[test]
+    fn storage_type_from_parser() {
+        use crate::parser::v7::types::numeric_array::NumericArrayNew;
+        use binrw::io::Cursor;
+        use binrw::{BinReaderExt, BinWrite};
+
+        // Integer valued doubles are downcast to u8 when written
+        let mut bin = Cursor::new(vec![]);
+        NumericArray7::new(vec![1, 3], vec![1.0, 2.0, 3.0], None)
+            .write_le(&mut bin)
+            .unwrap();
+        bin.set_position(0);
+        let m = NumericArray::from(bin.read_le::<NumericArray7>().unwrap());
+
+        assert_eq!(m.storage_type(), Some(MatFileDataTypes::MiUINT8));
+        assert_eq!(m.value, MatlabType::F64(vec![1.0, 2.0, 3.0]));
+
+        // The storage type does not take part in comparisons
+        let n = NumericArray::new(vec![1, 3], MatlabType::F64(vec![1.0, 2.0, 3.0]), None).unwrap();
+        assert_eq!(n.storage_type(), None);
+        assert_eq!(m, n);
+    }
+    #[test]
+    fn sparse_conversion_with_tol() {
+        let a: Vec<f64> = vec![1.0, 1e-12, -1e-10, 0.0, f64::NAN, 2.0];
+        let m = NumericArray::new(vec![2, 3], MatlabType::from(a), None).unwrap();
+
+        let MatVariable::SparseArray(s) = m.to_sparse_with_tol(1e-9).unwrap() else {
+            panic!("Not a sparse array");
+        };
+
+        assert_eq!(s.ir, vec![0, 0, 1]);
+        assert_eq!(s.jc, vec![0, 1, 1, 3]);
+    }
+
+    #[test]
+    fn sparse_conversion_complex() {
+        // Elements with zero real part but non-zero imaginary part are kept
+        let re: Vec<f64> = vec![0.0, 1.0, 0.0];
+        let im: Vec<f64> = vec![2.0, 0.0, 0.0];
+        let m = NumericArray::new(vec![3, 1], MatlabType::from(re), Some(MatlabType::from(im))).unwrap();
+
+        let MatVariable::SparseArray(s) = m.to_sparse().unwrap() else {
+            panic!("Not a sparse array");
+        };
+
+        assert_eq!(s.ir, vec![0, 1]);
+        assert_eq!(s.value, MatlabType::F64(vec![0.0, 1.0]));
+        assert_eq!(s.value_cmp, Some(MatlabType::F64(vec![2.0, 0.0])));
+    }
+
+    #[test]
+    fn sparse_conversion_3d() {
+        let a: Vec<f64> = vec![1.0; 8];
+        let m = NumericArray::new(vec![2, 2, 2], MatlabType::from(a), None).unwrap();
+
+        assert!(matches!(m.to_sparse(), Err(MatrwError::TypeConstruction(_))));
+    }
+
+    #[test]
+    fn sparse_conversion_classes() {
+        let m = NumericArray::new(vec![1, 3], MatlabType::from(vec![true, false, true]), None).unwrap();
+        let MatVariable::SparseArray(s) = m.to_sparse_with_tol(5.0).unwrap() else {
+            panic!("Not a sparse array");
+        };
+        assert_eq!(s.value, MatlabType::BOOL(vec![true, true]));
+
+        let m = NumericArray::new(vec![1, 3], MatlabType::from(vec![1i16, 0, 2]), None).unwrap();
+        assert_eq!(
+            m.to_sparse().unwrap_err().to_string(),
+            "Type construction error Cannot convert 'int16' array to sparse, only double and logical arrays are supported."
+        );
+    }
+
+    #[test]
+    fn nan_complex() {
+        let re: Vec<f64> = vec![f64::NAN, 1.0, 2.0];
+        let im: Vec<f64> = vec![f64::NAN, f64::NAN, f64::INFINITY];
+        let mut m = NumericArray::new(vec![3, 1], MatlabType::from(re), Some(MatlabType::from(im))).unwrap();
+
+        assert_eq!(m.nan_count(), 2);
+
+        m.replace_nan(0.0);
+        m.replace_inf(-1.0);
+        assert!(!m.has_nan());
+        assert_eq!(m.value, MatlabType::F64(vec![0.0, 1.0, 2.0]));
+        assert_eq!(m.value_cmp, Some(MatlabType::F64(vec![0.0, 0.0, -1.0])));
+
+        let mut i = NumericArray::new(vec![1, 2], MatlabType::from(vec![1i32, 2]), None).unwrap();
+        i.replace_nan(0.0);
+        assert_eq!(i.nan_count(), 0);
+    }
+
+    #[test]
+    fn set_block_complex() {
+        let mut m = NumericArray::new(vec![2, 2, 2], MatlabType::from(vec![0i16; 8]), None).unwrap();
+        let b = NumericArray::new(
+            vec![1, 2],
+            MatlabType::from(vec![1i16, 2]),
+            Some(MatlabType::from(vec![3i16, 4])),
+        )
+        .unwrap();
+
+        m.set_block(&[1, 0, 1], &b).unwrap();
+        assert_eq!(m.value, MatlabType::I16(vec![0, 0, 0, 0, 0, 1, 0, 2]));
+        assert_eq!(m.value_cmp, Some(MatlabType::I16(vec![0, 0, 0, 0, 0, 3, 0, 4])));
+
+        // Does not fit
+        assert!(m.set_block(&[1, 1, 1], &b).is_err());
+        // Wrong class
+        let d = NumericArray::new(vec![1, 1], MatlabType::from(vec![1.0]), None).unwrap();
+        let err = m.set_block(&[0, 0, 0], &d).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Type construction error Cannot assign 'double' values to 'int16' array."
+        );
+
+        m.set_column(0, &[5i16, 6]).unwrap_err();
+        m.fill(9i16).unwrap();
+        assert_eq!(m.value, MatlabType::I16(vec![9; 8]));
+        assert_eq!(m.value_cmp, None);
+    }
+
+    #[test]
+    fn concat() {
+        let arr =
+            |dim: Vec<usize>, value: Vec<i32>| NumericArray::new(dim, MatlabType::from(value), None).unwrap();
+        let a = arr(vec![2, 2], vec![1, 2, 3, 4]);
+        let b = arr(vec![1, 2], vec![5, 6]);
+
+        let rows = NumericArray::concat(&[a.clone(), b.clone()], 0).unwrap();
+        assert_eq!(rows.dim, vec![3, 2]);
+        assert_eq!(rows.value, MatlabType::I32(vec![1, 2, 5, 3, 4, 6]));
+
+        let pages = NumericArray::concat(&[a.clone(), a.clone()], 2).unwrap();
+        assert_eq!(pages.dim, vec![2, 2, 2]);
+        assert_eq!(pages.value, MatlabType::I32(vec![1, 2, 3, 4, 1, 2, 3, 4]));
+        // A single array along a new dimension keeps its size
+        assert_eq!(NumericArray::concat(std::slice::from_ref(&a), 3).unwrap(), a);
+
+        let c = NumericArray::new(
+            vec![1, 2],
+            MatlabType::from(vec![7, 8]),
+            Some(MatlabType::from(vec![9, 10])),
+        )
+        .unwrap();
+        let complex = NumericArray::concat(&[b.clone(), c], 1).unwrap();
+        assert_eq!(complex.dim, vec![1, 4]);
+        assert_eq!(complex.value, MatlabType::I32(vec![5, 6, 7, 8]));
+        assert_eq!(complex.value_cmp, Some(MatlabType::I32(vec![0, 0, 9, 10])));
+
+        assert!(NumericArray::concat(&[], 0).is_err());
+        let err = NumericArray::concat(&[a.clone(), b.clone()], 1).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Type construction error Array 1 of size [1, 2] does not match size [2, 2] of the first array outside dimension 1."
+        );
+        let d = NumericArray::new(vec![1, 2], MatlabType::from(vec![1.0, 2.0]), None).unwrap();
+        let err = NumericArray::concat(&[b, d], 0).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Type construction error Array 1 of class 'double' does not match class 'int32' of the first array."
+        );
+    }
+
+    #[test]
+    fn pages() {
+        let values: Vec<i16> = (0..12).collect();
+        let arr = NumericArray::new(
+            vec![2, 1, 2, 3],
+            MatlabType::from(values),
+            Some(MatlabType::from(vec![1i16; 12])),
+        )
+        .unwrap();
+        let pages = arr.pages();
+        assert_eq!(pages.len(), 6);
+        assert_eq!(pages[5].dim, vec![2, 1]);
+        assert_eq!(pages[5].value, MatlabType::I16(vec![10, 11]));
+        assert_eq!(pages[5].value_cmp, Some(MatlabType::I16(vec![1, 1])));
+
+        // Pages of a 2x1x6 stack, the dimensions beyond the third are not restored
+        let stack = NumericArray::from_pages(&pages).unwrap();
+        assert_eq!(stack.dim, vec![2, 1, 6]);
+        assert_eq!((&stack.value, &stack.value_cmp), (&arr.value, &arr.value_cmp));
+
+        let matrix = NumericArray::new(vec![2, 2], MatlabType::from(vec![1.0; 4]), None).unwrap();
+        assert_eq!(matrix.pages(), vec![matrix.clone()]);
+        let empty = NumericArray::new(vec![0, 3, 2], MatlabType::from(Vec::<f64>::new()), None).unwrap();
+        assert_eq!(empty.pages().len(), 2);
+        assert_eq!(empty.pages()[0].dim, vec![0, 3]);
+        // A single dimension is a row vector, a single page
+        let mut vector = matrix.clone();
+        vector.dim = vec![4].into();
+        assert_eq!(vector.pages(), vec![vector.clone()]);
+
+        let err = NumericArray::from_pages(&[matrix, arr]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Type construction error Page 1 of size [2, 1, 2, 3] is not a matrix."
+        );
+        assert!(NumericArray::from_pages(&[]).is_err());
+    }
+
+    #[test]
+    fn raw_bytes() {
+        let arrays = [
+            MatlabType::from(vec![1u8, 255]),
+            MatlabType::from(vec![-1i8, 7]),
+            MatlabType::from(vec![1u16, 0xabcd]),
+            MatlabType::from(vec![-2i16, 300]),
+            MatlabType::from(vec![1u32, u32::MAX]),
+            MatlabType::from(vec![i32::MIN, 5]),
+            MatlabType::from(vec![1u64, u64::MAX]),
+            MatlabType::from(vec![i64::MIN, -5]),
+            MatlabType::from(vec![0.5f32, -1e30]),
+            MatlabType::from(vec![f64::MIN_POSITIVE, -2.5]),
+        ];
+        for value in arrays {
+            let class = VariableClass::numeric(&value).unwrap();
+            let arr = NumericArray::new(vec![1, 2], value, None).unwrap();
+            for order in [ByteOrder::Little, ByteOrder::Big, ByteOrder::native()] {
+                let bytes = arr.to_raw_bytes(order).unwrap();
+                assert_eq!(bytes.len(), 2 * class.element_size().unwrap());
+                let back = NumericArray::from_bytes(vec![1, 2], class, bytes, order).unwrap();
+                assert_eq!(back, arr);
+            }
+            let native = arr.to_raw_bytes(ByteOrder::native()).unwrap();
+            assert_eq!(arr.as_raw_bytes(), Some(native.as_slice()));
+        }
+
+        let be = NumericArray::from_bytes(vec![], VariableClass::UInt32, vec![0, 0, 1, 2], ByteOrder::Big)
+            .unwrap();
+        assert_eq!(
+            (be.dim, be.value),
+            (vec![1, 1].into(), MatlabType::U32(vec![0x0102]))
+        );
+        let err = NumericArray::from_bytes(vec![2, 2], VariableClass::Double, vec![0; 16], ByteOrder::Little)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Type construction error 16 bytes do not hold 4 values of 8 bytes each."
+        );
+        assert!(
+            NumericArray::from_bytes(vec![], VariableClass::Int16, vec![0; 3], ByteOrder::Little).is_err()
+        );
+        let err =
+            NumericArray::from_bytes(vec![], VariableClass::Cell, vec![0; 8], ByteOrder::Little).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Type construction error Class cell has no packed values."
+        );
+
+        let complex = NumericArray::new(
+            vec![1, 1],
+            MatlabType::from(vec![1.0]),
+            Some(MatlabType::from(vec![2.0])),
+        )
+        .unwrap();
+        assert_eq!(complex.to_raw_bytes(ByteOrder::Little), None);
+        assert_eq!(complex.as_raw_bytes(), None);
+        assert_eq!(NumericArray::from("ab").to_raw_bytes(ByteOrder::Little), None);
+        assert_eq!(NumericArray::from("ab").as_raw_bytes(), None);
+    }
+
+    #[test]
+    fn row_major_conversion() {
+        // 2x3x2 array, whose values are the row-major positions
+        let row_major: Vec<u16> = (0..12).collect();
+        let arr = NumericArray::from_row_major(vec![2, 3, 2], row_major.clone()).unwrap();
+        assert_eq!(arr.dim, [2, 3, 2]);
+        for i in 0..2 {
+            for j in 0..3 {
+                for k in 0..2 {
+                    let col_major = i + 2 * j + 6 * k;
+                    let value: u16 = *arr.value.get(col_major).unwrap();
+                    assert_eq!(value as usize, i * 6 + j * 2 + k);
+                }
+            }
+        }
+        assert_eq!(arr.to_row_major_vec::<u16>().unwrap(), row_major);
+        assert_eq!(arr.to_row_major_vec::<f64>(), None);
+
+        // Vectors keep their order
+        let v = NumericArray::from_row_major(vec![3], vec![1.0, 2.0, 3.0]).unwrap();
+        assert_eq!(v.dim, [1, 3]);
+        assert_eq!(v.real_to_vec::<f64>().unwrap(), [1.0, 2.0, 3.0]);
+
+        let empty = NumericArray::from_row_major(vec![0, 3], Vec::<f64>::new()).unwrap();
+        assert!(empty.to_row_major_vec::<f64>().unwrap().is_empty());
+
+        assert!(NumericArray::from_row_major(vec![2, 2], vec![1i8; 3]).is_err());
+    }
+
+    #[test]
+    fn empty_and_scalar() {
+        let empty = NumericArray::new(vec![0, 0], MatlabType::F64(vec![]), None).unwrap();
+        assert_eq!(empty.numel(), 0);
+        assert!(empty.is_empty() && !empty.is_scalar());
+        assert_eq!(empty.real_to_scalar::<f64>(), None);
+        assert_eq!(empty.comp_to_scalar::<f64>(), None);
+        assert_eq!(MatVariable::NumericArray(empty.clone()).to_f64(), None);
+
+        // Dimensions set directly, no dimensions are 0x0
+        let mut unnormalized = empty;
+        unnormalized.dim = vec![].into();
+        assert!(unnormalized.is_empty() && !unnormalized.is_scalar());
+        assert_eq!(MatVariable::NumericArray(unnormalized).to_scalar::<f64>(), None);
+
+        let scalar = NumericArray::new(vec![1, 1], MatlabType::from(vec![2i8]), None).unwrap();
+        assert!(scalar.is_scalar() && !scalar.is_empty());
+        assert_eq!(scalar.real_to_scalar::<i8>(), Some(2));
+        assert_eq!(scalar.real_to_scalar::<f64>(), None);
+        assert_eq!(scalar.comp_to_scalar::<i8>(), None);
+
+        let cells = CellArray::new(vec![2, 0], vec![]).unwrap();
+        assert!(ArrayType::is_empty(&cells));
+        assert_eq!(MatVariable::CellArray(cells).numel(), 0);
+        let sparse =
+            SparseArray::new(3, 2, vec![0], vec![0, 0, 1], MatlabType::from(vec![1.0]), None).unwrap();
+        assert_eq!(ArrayType::numel(&sparse), 6);
+    }
 }
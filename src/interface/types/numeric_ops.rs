@@ -0,0 +1,272 @@
+//! Module numeric_ops
+//!
+//! This module implements elementwise arithmetic (`+`, `-`, `*`) on [`NumericArray`].
+//!
+//! Operands must have the same dimensions, or one of them must be a scalar. The class of the
+//! result follows MATLAB:
+//!
+//! - integer and integer of the same class, double or single gives the integer class,
+//! - single and single or double gives single,
+//! - double and double gives double.
+//!
+//! Characters and logicals are treated as double. Integer results are rounded to the nearest
+//! integer and saturate at the bounds of their class. Combining different integer classes is an
+//! error.
+
+use std::ops::{Add, Mul, Sub};
+
+use crate::MatrwError;
+use crate::interface::types::matlab_types::{MatlabType, VariableClass};
+use crate::interface::types::numeric_array::NumericArray;
+
+#[derive(Clone, Copy)]
+enum Op {
+    Add,
+    Sub,
+    Mul,
+}
+
+impl Op {
+    fn real<T>(self, a: T, b: T) -> T
+    where
+        T: Add<Output = T> + Sub<Output = T> + Mul<Output = T>,
+    {
+        match self {
+            Op::Add => a + b,
+            Op::Sub => a - b,
+            Op::Mul => a * b,
+        }
+    }
+
+    /// Integer operation, which saturates at the bounds of `i128`, e.g. for the product of two
+    /// large `u64`. The result is saturated at the bounds of its class afterwards.
+    fn saturating(self, a: i128, b: i128) -> i128 {
+        match self {
+            Op::Add => a.saturating_add(b),
+            Op::Sub => a.saturating_sub(b),
+            Op::Mul => a.saturating_mul(b),
+        }
+    }
+
+    fn complex(self, (a, b): (f64, f64), (c, d): (f64, f64)) -> (f64, f64) {
+        match self {
+            Op::Add => (a + c, b + d),
+            Op::Sub => (a - c, b - d),
+            Op::Mul => (a * c - b * d, a * d + b * c),
+        }
+    }
+}
+
+/// Class of an arithmetic result. Characters and logicals count as double.
+fn class_of(value: &MatlabType) -> VariableClass {
+    VariableClass::numeric(value).unwrap_or(VariableClass::Double)
+}
+
+fn promote(a: VariableClass, b: VariableClass) -> Result<VariableClass, MatrwError> {
+    match (a, b) {
+        (a, b) if a == b => Ok(a),
+        (a, b) if a.is_integer() && b.is_integer() => Err(MatrwError::TypeConstruction(format!(
+            "Integers can only be combined with integers of the same class or double, found {} and {}.",
+            a, b
+        ))),
+        (a, _) if a.is_integer() => Ok(a),
+        (_, b) if b.is_integer() => Ok(b),
+        (VariableClass::Single, _) | (_, VariableClass::Single) => Ok(VariableClass::Single),
+        _ => Ok(VariableClass::Double),
+    }
+}
+
+/// Apply `op` to all pairs of elements, repeating scalar operands.
+fn zip_with<T: Copy>(a: &[T], b: &[T], f: impl Fn(T, T) -> T) -> Vec<T> {
+    match (a.len(), b.len()) {
+        (1, _) => b.iter().map(|&y| f(a[0], y)).collect(),
+        (_, 1) => a.iter().map(|&x| f(x, b[0])).collect(),
+        _ => a.iter().zip(b).map(|(&x, &y)| f(x, y)).collect(),
+    }
+}
+
+fn elementwise(op: Op, lhs: &NumericArray, rhs: &NumericArray) -> Result<NumericArray, MatrwError> {
+    let dim = if lhs.dim == rhs.dim || rhs.is_scalar() {
+        lhs.dim.clone()
+    } else if lhs.is_scalar() {
+        rhs.dim.clone()
+    } else {
+        let fmt = |d: &[usize]| d.iter().map(|d| d.to_string()).collect::<Vec<_>>().join("x");
+        return Err(MatrwError::TypeConstruction(format!(
+            "Array dimensions {} and {} do not match.",
+            fmt(&lhs.dim),
+            fmt(&rhs.dim)
+        )));
+    };
+
+    let class = promote(class_of(&lhs.value), class_of(&rhs.value))?;
+
+    if lhs.is_complex() || rhs.is_complex() {
+        let parts = |arr: &NumericArray| {
+            let re = arr.value.to_f64();
+            let im = match &arr.value_cmp {
+                Some(cmp) => cmp.to_f64(),
+                None => vec![0.0; re.len()],
+            };
+            re.into_iter().zip(im).collect::<Vec<(f64, f64)>>()
+        };
+        let (re, im): (Vec<f64>, Vec<f64>) = zip_with(&parts(lhs), &parts(rhs), |a, b| op.complex(a, b))
+            .into_iter()
+            .unzip();
+
+        // Like MATLAB, drop an imaginary part that is zero everywhere
+        let value_cmp = im.iter().any(|&x| x != 0.0).then(|| class.cast_f64(im));
+        return NumericArray::with_dim(dim, class.cast_f64(re), value_cmp);
+    }
+
+    let value = if class.is_integer() && class_of(&lhs.value) == class_of(&rhs.value) {
+        class.cast_i128(zip_with(&lhs.value.to_i128(), &rhs.value.to_i128(), |a, b| {
+            op.saturating(a, b)
+        }))
+    } else {
+        class.cast_f64(zip_with(&lhs.value.to_f64(), &rhs.value.to_f64(), |a: f64, b| {
+            op.real(a, b)
+        }))
+    };
+
+    NumericArray::with_dim(dim, value, None)
+}
+
+fn scalar(value: MatlabType) -> NumericArray {
+    NumericArray::new(vec![1, 1], value, None).expect("Could not create NumericArray.")
+}
+
+macro_rules! impl_op {
+    ($trait:ident, $method:ident, $op:expr) => {
+        impl $trait<&NumericArray> for &NumericArray {
+            type Output = Result<NumericArray, MatrwError>;
+
+            fn $method(self, rhs: &NumericArray) -> Self::Output {
+                elementwise($op, self, rhs)
+            }
+        }
+
+        impl $trait<NumericArray> for NumericArray {
+            type Output = Result<NumericArray, MatrwError>;
+
+            fn $method(self, rhs: NumericArray) -> Self::Output {
+                elementwise($op, &self, &rhs)
+            }
+        }
+
+        impl_op!(@scalar $trait, $method, $op, u8, i8, u16, i16, u32, i32, u64, i64, f32, f64);
+    };
+    (@scalar $trait:ident, $method:ident, $op:expr, $($t:ty),*) => {
+        $(
+        impl $trait<$t> for &NumericArray {
+            type Output = Result<NumericArray, MatrwError>;
+
+            fn $method(self, rhs: $t) -> Self::Output {
+                elementwise($op, self, &scalar(MatlabType::from(rhs)))
+            }
+        }
+
+        impl $trait<$t> for NumericArray {
+            type Output = Result<NumericArray, MatrwError>;
+
+            fn $method(self, rhs: $t) -> Self::Output {
+                elementwise($op, &self, &scalar(MatlabType::from(rhs)))
+            }
+        }
+        )*
+    };
+}
+
+impl_op!(Add, add, Op::Add);
+impl_op!(Sub, sub, Op::Sub);
+impl_op!(Mul, mul, Op::Mul);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn arr(dim: Vec<usize>, value: MatlabType) -> NumericArray {
+        NumericArray::new(dim, value, None).unwrap()
+    }
+
+    #[test]
+    fn scalar_and_array() {
+        let a = arr(vec![2, 2], MatlabType::from(vec![1.0, 2.0, 3.0, 4.0]));
+        let b = arr(vec![2, 2], MatlabType::from(vec![1.0, 1.0, 2.0, 2.0]));
+
+        assert_eq!(
+            (&a - 1.0f64).unwrap().value,
+            MatlabType::F64(vec![0.0, 1.0, 2.0, 3.0])
+        );
+        assert_eq!(
+            (&a * &b).unwrap().value,
+            MatlabType::F64(vec![1.0, 2.0, 6.0, 8.0])
+        );
+        assert_eq!((a + b).unwrap().dim, vec![2, 2]);
+    }
+
+    #[test]
+    fn promotion() {
+        let a = arr(vec![1, 3], MatlabType::from(vec![250u8, 10, 1]));
+
+        // Integer results are rounded and saturate
+        assert_eq!((&a + 10.4f64).unwrap().value, MatlabType::U8(vec![255, 20, 11]));
+        assert_eq!((&a - 2u8).unwrap().value, MatlabType::U8(vec![248, 8, 0]));
+        assert_eq!((&a * 0.5f32).unwrap().value, MatlabType::U8(vec![125, 5, 1]));
+
+        // Different integer classes
+        assert!((&a + 1i16).is_err());
+
+        let s = arr(vec![1, 2], MatlabType::from(vec![1.5f32, 2.0]));
+        assert_eq!((&s * 2.0f64).unwrap().value, MatlabType::F32(vec![3.0, 4.0]));
+
+        let l = arr(vec![1, 2], MatlabType::from(vec![true, false]));
+        assert_eq!((&l + 1.0f64).unwrap().value, MatlabType::F64(vec![2.0, 1.0]));
+
+        let i = arr(vec![1, 1], MatlabType::from(vec![i64::MAX - 1]));
+        assert_eq!((&i + 1i64).unwrap().value, MatlabType::I64(vec![i64::MAX]));
+
+        // Products beyond the range of i128 saturate as well
+        let u = arr(vec![1, 2], MatlabType::from(vec![u64::MAX, 3]));
+        assert_eq!(
+            (&u * u64::MAX).unwrap().value,
+            MatlabType::U64(vec![u64::MAX, u64::MAX])
+        );
+        let i = arr(vec![1, 2], MatlabType::from(vec![i64::MIN, i64::MAX]));
+        assert_eq!(
+            (&i * i64::MIN).unwrap().value,
+            MatlabType::I64(vec![i64::MAX, i64::MIN])
+        );
+        assert_eq!((&i - i64::MAX).unwrap().value, MatlabType::I64(vec![i64::MIN, 0]));
+    }
+
+    #[test]
+    fn complex() {
+        let a = NumericArray::new(
+            vec![1, 2],
+            MatlabType::from(vec![1.0, 2.0]),
+            Some(MatlabType::from(vec![1.0, -1.0])),
+        )
+        .unwrap();
+
+        let b = (&a * &a).unwrap();
+        assert_eq!(b.value, MatlabType::F64(vec![0.0, 3.0]));
+        assert_eq!(b.value_cmp, Some(MatlabType::F64(vec![2.0, -4.0])));
+
+        // Imaginary parts cancel
+        let c = (&a - &a).unwrap();
+        assert_eq!(c.value_cmp, None);
+    }
+
+    #[test]
+    fn dimension_mismatch() {
+        let a = arr(vec![1, 3], MatlabType::from(vec![1.0, 2.0, 3.0]));
+        let b = arr(vec![3, 1], MatlabType::from(vec![1.0, 2.0, 3.0]));
+
+        let err = (&a + &b).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Type construction error Array dimensions 1x3 and 3x1 do not match."
+        );
+    }
+}
@@ -1,32 +1,55 @@
+//! Module sparse_array
+//!
+//! This module defines the struct [`SparseArray`], a two-dimensional sparse matrix of class
+//! double or logical.
+//!
+//! The stored elements are in compressed sparse column order: `jc` has one entry per column plus
+//! one, the elements of column `j` are `jc[j]..jc[j + 1]`, and `ir` holds the row of each stored
+//! element, ascending within a column. Only nonzero elements need to be stored.
+//!
+
 use std::fmt::{Debug, Display};
-use std::ops::Deref;
+use std::ops::{Deref, Range};
 
 use crate::MatrwError;
-use crate::interface::types::array::{ArrayType, ensure_matching_complex_size};
-use crate::interface::types::matlab_types::MatlabType;
+use crate::interface::types::array::{ArrayType, ensure_complex_allowed, ensure_matching_complex_size};
+use crate::interface::types::dim::MatDim;
+use crate::interface::types::matlab_types::{MatlabType, MatlabTypeMarker};
 use crate::interface::types::numeric_array::NumericArray;
 use crate::interface::variable::MatVariable;
 use crate::parser::v7::types::sparse_array::SparseArray7;
-use crate::parser::v7::types::subelements::array_numeric_data::array_data_value::ArrayDataValueVar;
+use crate::parser::v7::types::subelements::array_numeric_data::array_data_value::{
+    ArrayDataValueVar, utf16_unit_chars,
+};
 
 /// Sparse array
 ///
 /// The [`SparseArray`] type represents sparse arrays in compressed sparse column (CSC) format.
 ///
+/// Besides the number of stored elements, MATLAB keeps track of the number of elements a sparse
+/// array has been allocated for (`nzmax`). It is available via [`SparseArray::nzmax`] and is
+/// written back to MAT-files unchanged.
+///
 #[derive(Debug, Clone)]
 pub struct SparseArray {
-    pub dim: Vec<usize>,
+    /// Dimensions, rows and columns
+    pub dim: MatDim,
+    /// Row index of each stored element
     pub ir: Vec<usize>,
+    /// Offset of the first stored element of each column, followed by the number of elements
     pub jc: Vec<usize>,
     #[allow(dead_code)]
     null_type: Box<MatVariable>,
+    nzmax: usize,
+    /// Real part of the stored elements
     pub value: MatlabType,
+    /// Imaginary part of the stored elements of complex arrays
     pub value_cmp: Option<MatlabType>,
 }
 
 impl ArrayType for SparseArray {
     /// Get the dimension of the array
-    fn dim(&self) -> &Vec<usize> {
+    fn dim(&self) -> &MatDim {
         &self.dim
     }
 
@@ -37,6 +60,9 @@ impl ArrayType for SparseArray {
 
     /// Get a cloned value from a multi-dimensional index
     fn get_clone_multidim(&self, idx: &[usize]) -> Option<MatVariable> {
+        if idx.len() != 2 {
+            return None;
+        }
         if idx[0] >= self.dim[0] {
             return None;
         }
@@ -71,16 +97,12 @@ impl ArrayType for SparseArray {
         }
     }
 
-    /// Get column-major index from multi-dimensional index
+    /// Get the position of the stored element at a multi-dimensional index, by binary search
+    /// over the rows of its column
     fn column_index(&self, idx: &[usize]) -> Option<usize> {
-        let jc = self.jc[idx[1]];
-        let nc = self.jc[idx[1] + 1] - jc;
-
-        if let Some(l) = (jc..jc + nc).find(|&f| self.ir[f] == idx[0]) {
-            return Some(l);
-        }
-
-        None
+        let column = self.get_column(idx[1])?;
+        let k = column.rows.binary_search(&idx[0]).ok()?;
+        Some(column.range.start + k)
     }
 }
 
@@ -97,6 +119,7 @@ impl SparseArray {
         if let Some(cmp) = &value_cmp {
             ensure_matching_complex_size(value.len(), cmp.len())?;
         }
+        ensure_complex_allowed(&value, value_cmp.is_some())?;
 
         let max_elem = dim_i * dim_j;
         if value.len() > max_elem {
@@ -117,8 +140,7 @@ impl SparseArray {
 
         let is_comp = value_cmp.is_some();
         let null_type = match value {
-            MatlabType::BOOL(_) if !is_comp => MatVariable::from(false),
-            MatlabType::BOOL(_) if is_comp => MatVariable::from((false, false)),
+            MatlabType::BOOL(_) => MatVariable::from(false),
             MatlabType::F64(_) if !is_comp => MatVariable::from(0.0),
             MatlabType::F64(_) if is_comp => MatVariable::from((0.0, 0.0)),
             _ => {
@@ -128,16 +150,56 @@ impl SparseArray {
             }
         };
 
+        // MATLAB allocates at least one element, even for all-zero sparse arrays
+        let nzmax = value.len().max(1);
+
         Ok(Self {
-            dim: vec![dim_i, dim_j],
+            dim: MatDim::from_slice(&[dim_i, dim_j]),
             ir,
             jc,
             null_type: Box::new(null_type),
+            nzmax,
             value,
             value_cmp,
         })
     }
 
+    /// Number of elements the sparse array is allocated for.
+    ///
+    /// This corresponds to MATLAB's `nzmax` and is always at least the number of stored elements
+    /// and at least one.
+    pub fn nzmax(&self) -> usize {
+        self.nzmax
+    }
+
+    /// Set the number of elements the sparse array is allocated for.
+    ///
+    /// Returns an error if `nzmax` is smaller than the number of stored elements or zero.
+    ///
+    /// Example
+    /// ```
+    /// use matrw::{SparseArray, MatlabType};
+    ///
+    /// let mut s = SparseArray::new(2, 2, vec![0], vec![0, 1, 1], MatlabType::from(vec![1.0]), None).unwrap();
+    /// assert_eq!(s.nzmax(), 1);
+    ///
+    /// s.set_nzmax(4).unwrap();
+    /// assert_eq!(s.nzmax(), 4);
+    /// assert!(s.set_nzmax(0).is_err());
+    /// ```
+    pub fn set_nzmax(&mut self, nzmax: usize) -> Result<(), MatrwError> {
+        if nzmax < self.value.len().max(1) {
+            return Err(MatrwError::TypeConstruction(format!(
+                "nzmax ({}) must not be smaller than the number of stored elements ({}) or zero.",
+                nzmax,
+                self.value.len()
+            )));
+        }
+        self.nzmax = nzmax;
+
+        Ok(())
+    }
+
     pub fn is_complex(&self) -> bool {
         self.value_cmp.is_some()
     }
@@ -145,12 +207,146 @@ impl SparseArray {
     pub fn numeric_type(&self) -> &MatlabType {
         &self.value
     }
+
+    /// Returns `true` if the sparse array holds logical values.
+    pub fn is_logical(&self) -> bool {
+        matches!(self.value, MatlabType::BOOL(_))
+    }
+
+    /// Number of stored elements.
+    ///
+    /// Like MATLAB's `nnz` this counts the explicitly stored entries.
+    pub fn nnz(&self) -> usize {
+        self.value.len()
+    }
+
+    /// Ratio of stored elements to the total number of elements.
+    ///
+    /// Returns `0.0` for arrays without elements.
+    ///
+    /// Example
+    /// ```
+    /// use matrw::{SparseArray, MatlabType};
+    ///
+    /// let s = SparseArray::new(2, 2, vec![0], vec![0, 1, 1], MatlabType::from(vec![1.0]), None).unwrap();
+    /// assert_eq!(s.nnz(), 1);
+    /// assert_eq!(s.density(), 0.25);
+    /// ```
+    pub fn density(&self) -> f64 {
+        let numel = self.dim.iter().product::<usize>();
+        if numel == 0 {
+            0.0
+        } else {
+            self.nnz() as f64 / numel as f64
+        }
+    }
+
+    /// Number of stored elements in each column.
+    pub fn count_nonzero_per_column(&self) -> Vec<usize> {
+        self.jc.windows(2).map(|w| w[1] - w[0]).collect()
+    }
+
+    /// Stored elements of column `j`, without copying them. `None` if `j` is out of bounds.
+    ///
+    /// Example
+    /// ```
+    /// use matrw::{SparseArray, MatlabType};
+    ///
+    /// // [1 0; 0 0; 2 3]
+    /// let s = SparseArray::new(3, 2, vec![0, 2, 2], vec![0, 2, 3], MatlabType::from(vec![1.0, 2.0, 3.0]), None).unwrap();
+    ///
+    /// let column = s.get_column(0).unwrap();
+    /// assert_eq!(column.rows, [0, 2]);
+    /// assert_eq!(column.values::<f64>(), Some(&[1.0, 2.0][..]));
+    /// assert_eq!(s.get_column(1).unwrap().range, 2..3);
+    /// assert!(s.get_column(2).is_none());
+    /// ```
+    pub fn get_column(&self, j: usize) -> Option<SparseColumn<'_>> {
+        let start = *self.jc.get(j)?;
+        let end = *self.jc.get(j + 1)?;
+        Some(SparseColumn {
+            rows: self.ir.get(start..end)?,
+            range: start..end,
+            value: &self.value,
+            value_cmp: self.value_cmp.as_ref(),
+        })
+    }
+
+    /// Sort the stored elements of each column by ascending row. Columns with offsets outside
+    /// of `ir` are left unchanged.
+    fn sort_columns(&mut self) {
+        fn permute<T: Copy>(values: &mut [T], order: &[usize]) {
+            let sorted: Vec<T> = order.iter().map(|&k| values[k]).collect();
+            values.copy_from_slice(&sorted);
+        }
+        fn permute_value(value: &mut MatlabType, range: Range<usize>, order: &[usize]) {
+            match value {
+                MatlabType::F64(v) => permute(&mut v[range], order),
+                MatlabType::BOOL(v) => permute(&mut v[range], order),
+                // Other classes are rejected by `SparseArray::new`
+                _ => {}
+            }
+        }
+
+        for j in 0..self.jc.len().saturating_sub(1) {
+            let Some(column) = self.get_column(j) else {
+                continue;
+            };
+            if column.rows.is_sorted() {
+                continue;
+            }
+            let range = column.range;
+            let mut order: Vec<usize> = (0..range.len()).collect();
+            order.sort_by_key(|&k| self.ir[range.start + k]);
+
+            permute(&mut self.ir[range.clone()], &order);
+            permute_value(&mut self.value, range.clone(), &order);
+            if let Some(cmp) = &mut self.value_cmp {
+                permute_value(cmp, range, &order);
+            }
+        }
+    }
+}
+
+/// Stored elements of a column of a [`SparseArray`], see [`SparseArray::get_column`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SparseColumn<'a> {
+    /// Rows of the stored elements, ascending for arrays read from files and as required by
+    /// MATLAB
+    pub rows: &'a [usize],
+    /// Positions of the stored elements in [`SparseArray::value`] and [`SparseArray::value_cmp`]
+    pub range: Range<usize>,
+    value: &'a MatlabType,
+    value_cmp: Option<&'a MatlabType>,
+}
+
+impl<'a> SparseColumn<'a> {
+    /// Number of stored elements
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Real parts of the stored elements, `None` if the array doesn't hold values of type `T`.
+    pub fn values<T: MatlabTypeMarker>(&self) -> Option<&'a [T]> {
+        Some(&self.value.as_slice::<T>()?[self.range.clone()])
+    }
+
+    /// Imaginary parts of the stored elements, `None` if the array is real or doesn't hold
+    /// values of type `T`.
+    pub fn values_cmp<T: MatlabTypeMarker>(&self) -> Option<&'a [T]> {
+        Some(&self.value_cmp?.as_slice::<T>()?[self.range.clone()])
+    }
 }
 
 impl From<SparseArray7> for SparseArray {
     fn from(value: SparseArray7) -> Self {
         use ArrayDataValueVar::*;
 
+        let nzmax = value.nzmax() as usize;
         let (_name, dim, ir, jc, val, val_cmp) = value.value();
         let dim_i = dim[0];
         let dim_j = dim[1];
@@ -168,6 +364,7 @@ impl From<SparseArray7> for SparseArray {
             ArrayValueF64(v) => MatlabType::F64(v),
             ArrayValueUTF8(v) => MatlabType::UTF8(v),
             ArrayValueUTF16(v) => MatlabType::UTF16(v),
+            ArrayValueUTF16Units(v) => MatlabType::UTF16(utf16_unit_chars(&v)),
             ArrayValueBOOL(v) => MatlabType::BOOL(v),
         };
 
@@ -184,11 +381,18 @@ impl From<SparseArray7> for SparseArray {
             Some(ArrayValueF64(v)) => Some(MatlabType::F64(v)),
             Some(ArrayValueUTF8(v)) => Some(MatlabType::UTF8(v)),
             Some(ArrayValueUTF16(v)) => Some(MatlabType::UTF16(v)),
+            Some(ArrayValueUTF16Units(v)) => Some(MatlabType::UTF16(utf16_unit_chars(&v))),
             Some(ArrayValueBOOL(v)) => Some(MatlabType::BOOL(v)),
             None => None,
         };
 
-        Self::new(dim_i, dim_j, ir, jc, value, value_cmp).unwrap()
+        let mut sparse = Self::new(dim_i, dim_j, ir, jc, value, value_cmp).unwrap();
+        // Files written by other tools may contain an nzmax inconsistent with the data
+        let _ = sparse.set_nzmax(nzmax);
+        // or rows out of order, which the binary search of `column_index` relies on
+        sparse.sort_columns();
+
+        sparse
     }
 }
 
@@ -239,6 +443,30 @@ mod tests {
 
         assert_eq!(m.elem([0, 0]).to_f64(), None);
     }
+    #[test]
+    fn sparse_index_long_columns() {
+        // Every third row of two columns, the second one complex
+        let n = 30_000;
+        let rows: Vec<usize> = (0..n).step_by(3).collect();
+        let nnz = rows.len();
+        let ir = [rows.clone(), rows].concat();
+        let value = MatlabType::from((0..2 * nnz).map(|k| k as f64).collect::<Vec<_>>());
+        let value_cmp = MatlabType::from((0..2 * nnz).map(|k| -(k as f64)).collect::<Vec<_>>());
+        let s = SparseArray::new(n, 2, ir, vec![0, nnz, 2 * nnz], value, Some(value_cmp)).unwrap();
+
+        let column = s.get_column(1).unwrap();
+        assert_eq!((column.len(), column.range.clone()), (nnz, nnz..2 * nnz));
+        assert_eq!(column.values::<f64>().unwrap()[0], nnz as f64);
+        assert_eq!(column.values_cmp::<f64>().unwrap()[1], -(nnz as f64 + 1.0));
+        assert_eq!(column.values::<f32>(), None);
+
+        let m = MatVariable::SparseArray(s);
+        assert_eq!(m.elem([29_997, 0]).to_f64(), Some((nnz - 1) as f64));
+        assert_eq!(m.elem([3, 1]).to_f64(), Some(nnz as f64 + 1.0));
+        assert_eq!(m.elem([4, 1]).to_f64(), Some(0.0));
+        assert_eq!(m.elem([n, 1]).to_f64(), None);
+    }
+
     #[test]
     fn sparse_index_1x1() {
         let dim_i = 1;
@@ -301,4 +529,83 @@ mod tests {
 
         assert_eq!(m.elem([1, 1]).to_f64().unwrap(), 1.0);
     }
+    #[test]
+    fn sparse_nzmax() {
+        let ir = vec![0, 2];
+        let jc = vec![0, 1, 2];
+        let a = MatlabType::from(vec![1.0, 2.0]);
+        let mut m = SparseArray::new(3, 2, ir, jc, a, None).unwrap();
+        assert_eq!(m.nzmax(), 2);
+
+        assert!(m.set_nzmax(1).is_err());
+        m.set_nzmax(10).unwrap();
+
+        // Write and read again
+        use crate::parser::v7::types::sparse_array::SparseArray7;
+        use binrw::io::Cursor;
+        use binrw::{BinReaderExt, BinWrite};
+
+        let mut bin = Cursor::new(vec![]);
+        SparseArray7::from(m).write_le(&mut bin).unwrap();
+        bin.set_position(0);
+        let m = SparseArray::from(bin.read_le::<SparseArray7>().unwrap());
+
+        assert_eq!(m.nzmax(), 10);
+        assert_eq!(m.ir, vec![0, 2]);
+    }
+    #[test]
+    fn sparse_nzmax_empty() {
+        let a = MatlabType::from(Vec::<f64>::new());
+        let m = SparseArray::new(2, 2, vec![], vec![0, 0, 0], a, None).unwrap();
+
+        assert_eq!(m.nzmax(), 1);
+    }
+    #[test]
+    fn sparse_accessors() {
+        let ir = vec![1, 0, 1, 2];
+        let jc = vec![0, 1, 1, 4];
+        let a = MatlabType::from(vec![1.0, 2.0, 3.0, 4.0]);
+        let m = SparseArray::new(4, 3, ir, jc, a, None).unwrap();
+
+        assert_eq!(m.nnz(), 4);
+        assert_eq!(m.density(), 4.0 / 12.0);
+        assert_eq!(m.count_nonzero_per_column(), vec![1, 0, 3]);
+        assert!(!m.is_logical());
+
+        let a = MatlabType::from(vec![true]);
+        let m = SparseArray::new(2, 1, vec![1], vec![0, 1], a, None).unwrap();
+        assert!(m.is_logical());
+
+        let a = MatlabType::from(Vec::<f64>::new());
+        let m = SparseArray::new(0, 0, vec![], vec![0], a, None).unwrap();
+        assert_eq!(m.nnz(), 0);
+        assert_eq!(m.density(), 0.0);
+        assert!(m.count_nonzero_per_column().is_empty());
+    }
+
+    #[test]
+    fn sparse_invalid_columns() {
+        let a = MatlabType::from(vec![1.0, 2.0]);
+        let mut m = SparseArray::new(3, 2, vec![0, 1], vec![0, 1, 2], a, None).unwrap();
+        m.jc = vec![0, 5, 2];
+        assert!(m.get_column(0).is_none());
+        assert!(m.get_column(1).is_none());
+        assert_eq!(m.get_clone_multidim(&[2, 0]), Some(MatVariable::from(0.0)));
+    }
+
+    #[test]
+    fn sparse_unsorted_rows() {
+        use crate::{load_matfile_from_u8, matfile, save_matfile_v7_to_u8};
+
+        let a = MatlabType::from(vec![1.0, 3.0, 2.0, 4.0]);
+        let m = SparseArray::new(3, 2, vec![0, 2, 1, 0], vec![0, 3, 4], a, None).unwrap();
+        let data = save_matfile_v7_to_u8(matfile!(s: MatVariable::SparseArray(m)), false).unwrap();
+
+        let MatVariable::SparseArray(m) = &load_matfile_from_u8(&data).unwrap()["s"] else {
+            panic!("Not sparse");
+        };
+        assert_eq!(m.ir, [0, 1, 2, 0]);
+        assert_eq!(m.value, MatlabType::from(vec![1.0, 2.0, 3.0, 4.0]));
+        assert_eq!(m.get_clone_multidim(&[2, 0]), Some(MatVariable::from(3.0)));
+    }
 }
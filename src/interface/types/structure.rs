@@ -1,12 +1,37 @@
+//! Module structure
+//!
+//! This module defines the struct [`Structure`], a scalar MATLAB structure. Fields keep their
+//! insertion order, or the file order when read, which is the order they are written in. The
+//! order is guaranteed, see [`crate::MatFile#order`].
+//!
+
 use std::fmt::Debug;
 
 use indexmap::IndexMap;
 
-use crate::interface::variable::MatVariable;
+use crate::MatrwError;
+use crate::interface::helper::invalid_variable_name;
+use crate::interface::types::cell_array::CellArray;
+use crate::interface::types::dim::MatDim;
+use crate::interface::variable::{FromMatVariable, MatVariable, with_article};
 use crate::parser::v7::types::structure::Structure7;
 
+/// Scalar structure, mapping field names to variables.
+///
+/// Example
+/// ```
+/// use indexmap::IndexMap;
+/// use matrw::{MatVariable, Structure, matvar};
+///
+/// let mut fields = IndexMap::new();
+/// fields.insert("gain".to_string(), matvar!(2.5));
+/// let var = MatVariable::Structure(Structure::new(fields));
+///
+/// assert_eq!(var["gain"].to_f64(), Some(2.5));
+/// ```
 #[derive(Debug, Clone)]
 pub struct Structure {
+    /// Fields in written order
     pub value: IndexMap<String, MatVariable>,
 }
 
@@ -23,13 +48,296 @@ impl Structure {
     pub fn take(&mut self, field: &str) -> Option<MatVariable> {
         self.value.shift_remove(field)
     }
+
+    /// Position of the field `field` in written order, counted from 0.
+    pub fn field_index(&self, field: &str) -> Option<usize> {
+        self.value.get_index_of(field)
+    }
+
+    /// Field names and values in written order, the positions of [`Structure::field_index`].
+    ///
+    /// ```
+    /// use matrw::{matvar, MatVariable};
+    ///
+    /// let MatVariable::Structure(s) = matvar!({ b: 1.0, a: "x" }) else { unreachable!() };
+    /// assert_eq!(s.field_index("a"), Some(1));
+    ///
+    /// let (name, value) = s.fields().nth(1).unwrap();
+    /// assert_eq!((name, value), ("a", &matvar!("x")));
+    /// ```
+    pub fn fields(&self) -> impl Iterator<Item = (&str, &MatVariable)> {
+        self.value.iter().map(|(name, value)| (name.as_str(), value))
+    }
+
+    /// Values of the fields as Nx1 cell array in written order, like MATLAB's `struct2cell(s)`.
+    ///
+    /// The names are given by [`Structure::fieldnames`], [`CellArray::to_struct`] converts back.
+    ///
+    /// ```
+    /// use matrw::{matvar, MatVariable};
+    ///
+    /// let MatVariable::Structure(s) = matvar!({ id: 7.0, name: "probe" }) else { unreachable!() };
+    /// let cells = s.to_cell();
+    /// assert_eq!(cells.dim, vec![2, 1]);
+    /// assert_eq!(cells.value[1], matvar!("probe"));
+    /// ```
+    pub fn to_cell(&self) -> CellArray {
+        CellArray {
+            dim: MatDim::from_slice(&[self.value.len(), 1]),
+            value: self.value.values().cloned().collect(),
+        }
+    }
+
+    /// Convert the field `field` into `T`.
+    ///
+    /// Fails with [`MatrwError::AccessError`], if the field does not exist or cannot be
+    /// converted. Numeric fields must have the class of `T`, see [`FromMatVariable`] for the
+    /// supported types.
+    ///
+    /// ```
+    /// use matrw::{matvar, MatVariable};
+    ///
+    /// let var = matvar!({ gain: 2.5, name: "motor", offsets: [1.0, 2.0] });
+    /// let MatVariable::Structure(s) = var else { unreachable!() };
+    /// assert_eq!(s.get_as::<f64>("gain").unwrap(), 2.5);
+    /// assert_eq!(s.get_as::<String>("name").unwrap(), "motor");
+    /// assert_eq!(s.get_as::<Vec<f64>>("offsets").unwrap(), vec![1.0, 2.0]);
+    ///
+    /// let err = s.get_as::<f64>("name").unwrap_err();
+    /// assert_eq!(err.to_string(), "Field 'name' is not an f64 scalar");
+    /// ```
+    pub fn get_as<T: FromMatVariable>(&self, field: &str) -> Result<T, MatrwError> {
+        let var = self
+            .get(field)
+            .ok_or_else(|| MatrwError::AccessError(format!("Field '{}' does not exist", field)))?;
+        T::from_matvar(var).ok_or_else(|| {
+            MatrwError::AccessError(format!(
+                "Field '{}' is not {}",
+                field,
+                with_article(&T::expected())
+            ))
+        })
+    }
+
+    /// Fields in written order, as a map from field names to variables.
+    ///
+    /// ```
+    /// use matrw::{matvar, MatVariable};
+    ///
+    /// let MatVariable::Structure(s) = matvar!({ a: 1.0, b: 2.0 }) else { unreachable!() };
+    /// let sum: f64 = s.as_map().values().filter_map(|v| v.to_f64()).sum();
+    /// assert_eq!(sum, 3.0);
+    /// ```
+    pub fn as_map(&self) -> &IndexMap<String, MatVariable> {
+        &self.value
+    }
+
+    /// Convert into the map from field names to variables, in written order.
+    pub fn into_map(self) -> IndexMap<String, MatVariable> {
+        self.value
+    }
+
+    /// Sort fields alphabetically, like MATLAB's `orderfields(s)`.
+    ///
+    /// ```
+    /// use matrw::{matvar, MatVariable};
+    ///
+    /// let MatVariable::Structure(mut s) = matvar!({ b: 1.0, a: 2.0 }) else { unreachable!() };
+    /// s.sort_fields();
+    /// assert_eq!(s.fieldnames(), vec!["a", "b"]);
+    /// ```
+    pub fn sort_fields(&mut self) {
+        self.value.sort_keys();
+    }
+
+    /// Order fields as given by `names`, like MATLAB's `orderfields(s, names)`.
+    ///
+    /// `names` must contain every field exactly once.
+    ///
+    /// ```
+    /// use matrw::{matvar, MatVariable};
+    ///
+    /// let MatVariable::Structure(mut s) = matvar!({ a: 1.0, b: 2.0, c: 3.0 }) else { unreachable!() };
+    /// s.reorder_fields(&["c", "a", "b"]).unwrap();
+    /// assert_eq!(s.fieldnames(), vec!["c", "a", "b"]);
+    /// ```
+    pub fn reorder_fields(&mut self, names: &[&str]) -> Result<(), MatrwError> {
+        ensure_permutation(&self.fieldnames(), names)?;
+        self.value
+            .sort_by_cached_key(|k, _| names.iter().position(|n| n == k));
+        Ok(())
+    }
+
+    /// Build a new structure by transforming every field, like MATLAB's `structfun`.
+    ///
+    /// The closure receives name and value of each field and returns the name and value of the
+    /// field of the new structure, or `None` to drop the field. Field order is kept.
+    ///
+    /// Returns [`MatrwError::AccessError`], if a returned name is not a valid MATLAB field name
+    /// or is returned more than once.
+    ///
+    /// ```
+    /// use matrw::{matvar, MatVariable};
+    ///
+    /// let MatVariable::Structure(s) = matvar!({ Gain: 2.5, Offset: 1.0, Debug: true }) else { unreachable!() };
+    ///
+    /// let s = s
+    ///     .map_fields(|name, var| match name.as_str() {
+    ///         "Debug" => None,
+    ///         _ => Some((name.to_lowercase(), var)),
+    ///     })
+    ///     .unwrap();
+    /// assert_eq!(s.fieldnames(), vec!["gain", "offset"]);
+    /// ```
+    pub fn map_fields<F>(self, mut f: F) -> Result<Structure, MatrwError>
+    where
+        F: FnMut(String, MatVariable) -> Option<(String, MatVariable)>,
+    {
+        let mut value = IndexMap::with_capacity(self.value.len());
+        for (name, var) in self.value {
+            let Some((name, var)) = f(name, var) else {
+                continue;
+            };
+            if let Some(err) = invalid_variable_name(&name) {
+                return Err(MatrwError::AccessError(err.replacen("variable", "field", 1)));
+            }
+            if value.contains_key(&name) {
+                return Err(MatrwError::AccessError(format!(
+                    "Field '{}' created more than once",
+                    name
+                )));
+            }
+            value.insert(name, var);
+        }
+        Ok(Structure { value })
+    }
+}
+
+/// How [`Structure::merge`] resolves fields present in both structures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergePolicy {
+    /// Keep the value of the structure merged into
+    KeepExisting,
+    /// Replace the value by the one of the other structure
+    Overwrite,
+    /// Fail with [`MatrwError::AccessError`]
+    Error,
+}
+
+impl Structure {
+    /// Merge the fields of `other` into this structure.
+    ///
+    /// Fields missing in this structure are appended. If both structures have a field and both
+    /// values are structures, they are merged recursively. Otherwise `policy` decides which value
+    /// is kept.
+    ///
+    /// ```
+    /// use matrw::{matvar, MatVariable, MergePolicy};
+    ///
+    /// let MatVariable::Structure(mut s) = matvar!({ a: 1.0, sub: { x: 1.0 } }) else { unreachable!() };
+    /// let MatVariable::Structure(t) = matvar!({ a: 2.0, sub: { y: 2.0 } }) else { unreachable!() };
+    ///
+    /// s.merge(t, MergePolicy::Overwrite).unwrap();
+    /// assert_eq!(s.get("a").unwrap().to_f64(), Some(2.0));
+    /// assert_eq!(s.get("sub").unwrap().fieldnames(), Some(vec!["x".to_string(), "y".to_string()]));
+    /// ```
+    pub fn merge(&mut self, other: Structure, policy: MergePolicy) -> Result<(), MatrwError> {
+        self.merge_at(other, policy, "")
+    }
+
+    fn merge_at(&mut self, other: Structure, policy: MergePolicy, path: &str) -> Result<(), MatrwError> {
+        for (field, value) in other.value {
+            let Some(existing) = self.value.get_mut(&field) else {
+                self.value.insert(field, value);
+                continue;
+            };
+
+            let field_path = format!("{}{}", path, field);
+            match (existing, value) {
+                (MatVariable::Structure(existing), MatVariable::Structure(value)) => {
+                    existing.merge_at(value, policy, &format!("{}.", field_path))?;
+                }
+                (existing, value) => match policy {
+                    MergePolicy::KeepExisting => {}
+                    MergePolicy::Overwrite => *existing = value,
+                    MergePolicy::Error => {
+                        return Err(MatrwError::AccessError(format!(
+                            "Field '{}' exists in both structures.",
+                            field_path
+                        )));
+                    }
+                },
+            }
+        }
+        Ok(())
+    }
+
+    /// Fill fields missing in this structure from `defaults`, recursing into nested structures.
+    ///
+    /// ```
+    /// use matrw::{matvar, MatVariable};
+    ///
+    /// let MatVariable::Structure(defaults) = matvar!({ rate: 100.0, filter: { order: 4.0, cutoff: 10.0 } })
+    /// else { unreachable!() };
+    /// let MatVariable::Structure(config) = matvar!({ filter: { cutoff: 20.0 } }) else { unreachable!() };
+    ///
+    /// let config = config.with_defaults(&defaults);
+    /// assert_eq!(config.get("rate").unwrap().to_f64(), Some(100.0));
+    /// assert_eq!(config.get("filter").unwrap()["cutoff"].to_f64(), Some(20.0));
+    /// assert_eq!(config.get("filter").unwrap()["order"].to_f64(), Some(4.0));
+    /// ```
+    pub fn with_defaults(mut self, defaults: &Structure) -> Self {
+        self.merge(defaults.clone(), MergePolicy::KeepExisting)
+            .expect("Keeping existing fields cannot fail");
+        self
+    }
+}
+
+/// Ensure that `names` contains every field of `fields` exactly once.
+pub(crate) fn ensure_permutation(fields: &[String], names: &[&str]) -> Result<(), MatrwError> {
+    let mut sorted_fields: Vec<&str> = fields.iter().map(String::as_str).collect();
+    let mut sorted_names = names.to_vec();
+    sorted_fields.sort_unstable();
+    sorted_names.sort_unstable();
+
+    if sorted_fields != sorted_names {
+        return Err(MatrwError::AccessError(format!(
+            "Field order {:?} does not match fields {:?}.",
+            names, fields
+        )));
+    }
+    Ok(())
+}
+
+/// Structure with the fields of `map`, in the order of the map.
+///
+/// Example
+/// ```
+/// use indexmap::IndexMap;
+/// use matrw::{Structure, matvar};
+///
+/// let map = IndexMap::from([("b".to_string(), matvar!(1.0)), ("a".to_string(), matvar!(2.0))]);
+/// let s = Structure::from(map.clone());
+/// assert_eq!(s.fieldnames(), vec!["b", "a"]);
+/// assert_eq!(IndexMap::from(s), map);
+/// ```
+impl From<IndexMap<String, MatVariable>> for Structure {
+    fn from(map: IndexMap<String, MatVariable>) -> Self {
+        Self::new(map)
+    }
+}
+
+impl From<Structure> for IndexMap<String, MatVariable> {
+    fn from(value: Structure) -> Self {
+        value.into_map()
+    }
 }
 
 impl From<Structure7> for Structure {
     fn from(value: Structure7) -> Self {
         let fieldnames = value.fieldnames();
         let mut map = IndexMap::new();
-        for (val, key) in value.value().into_iter().zip(fieldnames.into_iter()) {
+        for (val, key) in value.value().into_iter().zip(fieldnames) {
             map.insert(key, val.into());
         }
 
@@ -48,3 +356,94 @@ pub fn check_same_fields(vec: &[MatVariable]) -> bool {
 
     vec.iter().map(|x| x.fieldnames() == first).into_iter().all(|x| x)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matvar;
+
+    fn structure(var: MatVariable) -> Structure {
+        match var {
+            MatVariable::Structure(s) => s,
+            _ => panic!("Not a structure"),
+        }
+    }
+
+    #[test]
+    fn field_positions() {
+        let s = structure(matvar!({ z: 1.0, a: 2.0, m: 3.0 }));
+        assert_eq!(s.field_index("z"), Some(0));
+        assert_eq!(s.field_index("m"), Some(2));
+        assert_eq!(s.field_index("b"), None);
+        let names: Vec<&str> = s.fields().map(|(name, _)| name).collect();
+        assert_eq!(names, ["z", "a", "m"]);
+        assert_eq!(
+            s.fields().nth(s.field_index("a").unwrap()).unwrap().1,
+            &matvar!(2.0)
+        );
+        assert_eq!(structure(matvar!({})).fields().count(), 0);
+    }
+
+    #[test]
+    fn merge_policies() {
+        let base = structure(matvar!({ a: 1.0, s: { x: 1.0 } }));
+        let other = structure(matvar!({ s: { x: 2.0, y: 3.0 }, b: 4.0 }));
+
+        let mut keep = base.clone();
+        keep.merge(other.clone(), MergePolicy::KeepExisting).unwrap();
+        assert_eq!(keep.fieldnames(), vec!["a", "s", "b"]);
+        assert_eq!(keep.get("s").unwrap()["x"].to_f64(), Some(1.0));
+        assert_eq!(keep.get("s").unwrap()["y"].to_f64(), Some(3.0));
+
+        let mut err = base.clone();
+        let e = err.merge(other, MergePolicy::Error).unwrap_err();
+        assert_eq!(e.to_string(), "Field 's.x' exists in both structures.");
+    }
+
+    #[test]
+    fn get_as_errors() {
+        let s = structure(matvar!({ gain: 2.5, n: [1, 2] }));
+        assert_eq!(s.get_as::<Vec<i32>>("n").unwrap(), vec![1, 2]);
+
+        let err = s.get_as::<f64>("missing").unwrap_err();
+        assert_eq!(err.to_string(), "Field 'missing' does not exist");
+        let err = s.get_as::<i32>("gain").unwrap_err();
+        assert_eq!(err.to_string(), "Field 'gain' is not an i32 scalar");
+        let err = s.get_as::<Vec<u8>>("gain").unwrap_err();
+        assert_eq!(err.to_string(), "Field 'gain' is not a u8 array");
+        let err = s.get_as::<String>("n").unwrap_err();
+        assert_eq!(err.to_string(), "Field 'n' is not a character row vector");
+    }
+
+    #[test]
+    fn map_conversions() {
+        let s = structure(matvar!({ z: 1.0, a: { x: 2.0 } }));
+        assert_eq!(s.as_map().keys().collect::<Vec<_>>(), vec!["z", "a"]);
+
+        let mut map = s.clone().into_map();
+        map.insert("b".to_string(), matvar!("text"));
+        let s = Structure::from(map);
+        assert_eq!(s.fieldnames(), vec!["z", "a", "b"]);
+        assert_eq!(s.get("a").unwrap()["x"].to_f64(), Some(2.0));
+    }
+
+    #[test]
+    fn map_fields() {
+        let s = structure(matvar!({ a: 1.0, b: "text", c: 2.0 }));
+
+        let doubled = s
+            .clone()
+            .map_fields(|name, var| Some((format!("{name}2"), MatVariable::from(var.to_f64()? * 2.0))))
+            .unwrap();
+        assert_eq!(doubled.fieldnames(), vec!["a2", "c2"]);
+        assert_eq!(doubled.get("c2").unwrap().to_f64(), Some(4.0));
+
+        let err = s
+            .map_fields(|name, var| Some((format!("_{name}"), var)))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid field name '_a', it does not start with a letter"
+        );
+    }
+}
@@ -1,17 +1,48 @@
+//! Module structure_array
+//!
+//! This module defines the struct [`StructureArray`], a multidimensional array of structures.
+//!
+//! All elements are [`MatVariable::Structure`] with the same field names in the same order,
+//! stored in column-major order. As for [`crate::NumericArray`], the product of `dim` is the
+//! number of elements and a one-dimensional `dim` is normalized to a row vector.
+//!
+
 use std::fmt::Debug;
 
 use indexmap::IndexMap;
 
-use crate::interface::types::array::{ArrayType, ensure_matching_dimension, normalize_dimension};
-use crate::interface::types::structure::Structure;
+use crate::interface::types::array::{
+    ArrayType, ensure_matching_dimension, ensure_numel, normalize_dimension,
+};
+use crate::interface::types::dim::MatDim;
+use crate::interface::types::structure::{Structure, ensure_permutation};
 use crate::interface::variable::MatVariable;
 use crate::parser::v7::types::structure_array::StructureArray7;
 use crate::{MatrwError, impl_Array_for};
 
+/// Array of structures sharing the same fields.
+///
+/// Example
+/// ```
+/// use matrw::{MatVariable, StructureArray, matvar};
+///
+/// // Values are given field by field for each element
+/// let s = StructureArray::new(
+///     vec![1, 2],
+///     vec!["x".to_string(), "y".to_string()],
+///     vec![matvar!(1.0), matvar!(2.0), matvar!(3.0), matvar!(4.0)],
+/// )
+/// .unwrap();
+/// let var = MatVariable::StructureArray(s);
+///
+/// assert_eq!(var[1]["x"].to_f64(), Some(3.0));
+/// ```
 #[derive(Debug, Clone)]
 pub struct StructureArray {
-    pub dim: Vec<usize>,
+    /// Dimensions, at least two
+    pub dim: MatDim,
     fieldnames: Vec<String>,
+    /// Elements in column-major order, each a [`MatVariable::Structure`]
     pub value: Vec<MatVariable>,
 }
 
@@ -24,10 +55,15 @@ impl StructureArray {
         value: Vec<MatVariable>,
     ) -> Result<Self, MatrwError> {
         if !dim.is_empty() {
-            ensure_matching_dimension(dim.iter().product::<usize>() * fieldnames.len(), value.len())?;
+            let elems = ensure_numel(&dim)?;
+            let values = elems.checked_mul(fieldnames.len()).ok_or_else(|| {
+                MatrwError::TypeConstruction("Number of structure array values overflows.".to_string())
+            })?;
+            ensure_matching_dimension(values, value.len())?;
         }
 
-        let dim = normalize_dimension(dim, value.len());
+        let elems = value.len().checked_div(fieldnames.len()).unwrap_or(0);
+        let dim = normalize_dimension(dim, elems);
 
         let mut val = Vec::new();
         let mut v = value.into_iter();
@@ -45,27 +81,212 @@ impl StructureArray {
             value: val,
         })
     }
-    pub fn from_structures(dim: Vec<usize>, value: Vec<MatVariable>) -> Self {
-        let mut val = Vec::new();
-        for v in value.into_iter() {
-            val.push(v);
+
+    ///
+    /// Structure array of the elements `value`, each a [`MatVariable::Structure`] with the same
+    /// field names in the same order, in column-major order.
+    ///
+    /// Returns [`MatrwError::TypeConstruction`], if the product of `dim` differs from the number
+    /// of elements, or an element is no structure or has other fields than the first one. Without
+    /// elements, the structure array has no fields.
+    ///
+    /// Example
+    /// ```
+    /// use matrw::{StructureArray, matvar};
+    ///
+    /// let s = StructureArray::from_structures(vec![2, 1], vec![matvar!({ a: 1.0 }), matvar!({ a: 2.0 })]).unwrap();
+    /// assert_eq!(s.fieldnames(), vec!["a"]);
+    ///
+    /// assert!(StructureArray::from_structures(vec![2, 2], vec![matvar!({ a: 1.0 })]).is_err());
+    /// assert!(StructureArray::from_structures(vec![1, 2], vec![matvar!({ a: 1.0 }), matvar!({ b: 2.0 })]).is_err());
+    /// ```
+    ///
+    pub fn from_structures(dim: Vec<usize>, value: Vec<MatVariable>) -> Result<Self, MatrwError> {
+        if !dim.is_empty() {
+            ensure_matching_dimension(ensure_numel(&dim)?, value.len())?;
+        }
+
+        let fieldnames = match value.first() {
+            Some(first) => first.fieldnames().unwrap_or_default(),
+            None => Vec::new(),
+        };
+        for (i, v) in value.iter().enumerate() {
+            match v {
+                MatVariable::Structure(s) if s.fieldnames() == fieldnames => (),
+                MatVariable::Structure(s) => {
+                    return Err(MatrwError::TypeConstruction(format!(
+                        "Element {} has fields {:?}, which differ from fields {:?} of the first element.",
+                        i,
+                        s.fieldnames(),
+                        fieldnames
+                    )));
+                }
+                _ => {
+                    return Err(MatrwError::TypeConstruction(format!(
+                        "Element {} is not a scalar structure.",
+                        i
+                    )));
+                }
+            }
         }
-        let fieldnames = val[0].fieldnames().expect("Cannot read field names");
 
-        Self {
+        let dim = normalize_dimension(dim, value.len());
+
+        Ok(Self {
             dim,
             fieldnames,
-            value: val,
-        }
+            value,
+        })
     }
     pub fn fieldnames(&self) -> Vec<String> {
         self.fieldnames.clone()
     }
+
+    /// Field names without copying them
+    pub(crate) fn fieldnames_ref(&self) -> &[String] {
+        &self.fieldnames
+    }
+
+    /// Position of the field `field` in every element, counted from 0.
+    pub fn field_index(&self, field: &str) -> Option<usize> {
+        self.fieldnames.iter().position(|name| name == field)
+    }
+
+    /// Field names with the values of the field in all elements, in column-major order.
+    ///
+    /// Fields come in the order of [`StructureArray::fieldnames`].
+    ///
+    /// ```
+    /// use matrw::{MatVariable, matvar};
+    ///
+    /// let MatVariable::StructureArray(s) = matvar!([{ id: 1.0, ok: true }, { id: 2.0, ok: false }]) else {
+    ///     unreachable!()
+    /// };
+    /// assert_eq!(s.field_index("ok"), Some(1));
+    ///
+    /// let (name, values) = s.fields().next().unwrap();
+    /// assert_eq!(name, "id");
+    /// assert_eq!(values, vec![&matvar!(1.0), &matvar!(2.0)]);
+    /// ```
+    pub fn fields(&self) -> impl Iterator<Item = (&str, Vec<&MatVariable>)> {
+        self.fieldnames.iter().map(|name| {
+            let values = self
+                .value
+                .iter()
+                .filter_map(|s| match s {
+                    MatVariable::Structure(s) => s.get(name),
+                    _ => None,
+                })
+                .collect();
+            (name.as_str(), values)
+        })
+    }
+
+    /// Sort fields of all elements alphabetically, see [`Structure::sort_fields`].
+    pub fn sort_fields(&mut self) {
+        self.fieldnames.sort();
+        for s in self.value.iter_mut() {
+            if let MatVariable::Structure(s) = s {
+                s.sort_fields();
+            }
+        }
+    }
+
+    /// Order fields of all elements as given by `names`, see [`Structure::reorder_fields`].
+    pub fn reorder_fields(&mut self, names: &[&str]) -> Result<(), MatrwError> {
+        ensure_permutation(&self.fieldnames, names)?;
+        for s in self.value.iter_mut() {
+            if let MatVariable::Structure(s) = s {
+                s.reorder_fields(names)?;
+            }
+        }
+        self.fieldnames = names.iter().map(|n| n.to_string()).collect();
+        Ok(())
+    }
+
+    /// Build a new structure array by transforming the fields of every element, see
+    /// [`Structure::map_fields`].
+    ///
+    /// Elements are passed in column-major order. All elements must end up with the same fields in
+    /// the same order, otherwise [`MatrwError::AccessError`] is returned. Arrays without elements
+    /// keep their field names, as there is no field to pass to `f`.
+    ///
+    /// ```
+    /// use matrw::{MatVariable, matvar};
+    ///
+    /// let MatVariable::StructureArray(s) = matvar!([{ t_ms: 1.0 }, { t_ms: 2.5 }]) else { unreachable!() };
+    ///
+    /// let s = s
+    ///     .map_fields(|name, var| match name.as_str() {
+    ///         "t_ms" => Some(("t".to_string(), MatVariable::from(var.to_f64()? / 1000.0))),
+    ///         _ => Some((name, var)),
+    ///     })
+    ///     .unwrap();
+    /// assert_eq!(s.fieldnames(), vec!["t"]);
+    /// assert_eq!(MatVariable::StructureArray(s)[1]["t"].to_f64(), Some(0.0025));
+    /// ```
+    pub fn map_fields<F>(self, mut f: F) -> Result<StructureArray, MatrwError>
+    where
+        F: FnMut(String, MatVariable) -> Option<(String, MatVariable)>,
+    {
+        let mut fieldnames = None;
+        let mut value = Vec::with_capacity(self.value.len());
+        for (i, s) in self.value.into_iter().enumerate() {
+            let MatVariable::Structure(s) = s else {
+                value.push(s);
+                continue;
+            };
+            let s = s.map_fields(&mut f)?;
+            let names = fieldnames.get_or_insert_with(|| s.fieldnames());
+            if s.value.keys().ne(names.iter()) {
+                return Err(MatrwError::AccessError(format!(
+                    "Element {} has fields {:?}, which differ from fields {:?} of the first element.",
+                    i,
+                    s.fieldnames(),
+                    names
+                )));
+            }
+            value.push(MatVariable::Structure(s));
+        }
+
+        Ok(StructureArray {
+            dim: self.dim,
+            fieldnames: fieldnames.unwrap_or(self.fieldnames),
+            value,
+        })
+    }
+
+    /// Move out the fields of each element as a record, in column-major order.
+    ///
+    /// Every record has the fields of the array in the same order.
+    ///
+    /// Example
+    /// ```
+    /// use matrw::{MatVariable, matvar};
+    ///
+    /// let MatVariable::StructureArray(s) = matvar!([{ id: 1.0, name: "a" }, { id: 2.0, name: "b" }]) else {
+    ///     panic!("Not a structure array")
+    /// };
+    ///
+    /// let records = s.into_records();
+    /// assert_eq!(records.len(), 2);
+    /// assert_eq!(records[1]["name"], matvar!("b"));
+    /// assert_eq!(records[0].keys().collect::<Vec<_>>(), ["id", "name"]);
+    /// ```
+    pub fn into_records(self) -> Vec<IndexMap<String, MatVariable>> {
+        self.value
+            .into_iter()
+            .map(|s| match s {
+                MatVariable::Structure(s) => s.value,
+                _ => IndexMap::new(),
+            })
+            .collect()
+    }
 }
 
 impl From<StructureArray7> for StructureArray {
     fn from(value: StructureArray7) -> Self {
-        let dim: Vec<usize> = value.dim().clone().iter().map(|x| *x as usize).collect();
+        let dim: Vec<usize> = value.dim().iter().map(|x| *x as usize).collect();
         let fieldnames = value.fieldnames();
 
         Self::new(
@@ -164,4 +385,109 @@ mod tests {
         let v = s.get_ref_multidim(&[0, 2]);
         println!("{:#?}", v);
     }
+
+    #[test]
+    fn field_positions() {
+        use crate::matvar;
+
+        let MatVariable::StructureArray(s) = matvar!([{ b: 1.0, a: 2.0 }, { b: 3.0, a: 4.0 }]) else {
+            panic!("Not a structure array")
+        };
+        assert_eq!(s.field_index("a"), Some(1));
+        assert_eq!(s.field_index("c"), None);
+        let fields: Vec<_> = s.fields().collect();
+        assert_eq!(fields[1], ("a", vec![&matvar!(2.0), &matvar!(4.0)]));
+
+        // Without elements the fields have no values
+        let empty = StructureArray::new(vec![0, 0], vec!["x".to_string()], vec![]).unwrap();
+        assert_eq!(empty.field_index("x"), Some(0));
+        assert_eq!(empty.fields().collect::<Vec<_>>(), vec![("x", vec![])]);
+    }
+
+    #[test]
+    fn into_records() {
+        let fieldnames = vec!["b".to_string(), "a".to_string()];
+        let value = (1..=4).map(|x| MatVariable::from(x as f64)).collect();
+        let s = StructureArray::new(vec![2, 1], fieldnames, value).unwrap();
+
+        let records = s.into_records();
+        assert_eq!(records.len(), 2);
+        for (i, record) in records.iter().enumerate() {
+            assert_eq!(record.keys().collect::<Vec<_>>(), ["b", "a"]);
+            assert_eq!(record["b"], MatVariable::from(2.0 * i as f64 + 1.0));
+            assert_eq!(record["a"], MatVariable::from(2.0 * i as f64 + 2.0));
+        }
+
+        let empty = StructureArray::new(vec![0, 0], vec!["a".to_string()], vec![]).unwrap();
+        assert!(empty.into_records().is_empty());
+    }
+
+    #[test]
+    fn map_fields() {
+        let fieldnames = vec!["a".to_string(), "b".to_string()];
+        let value = (1..=4).map(|x| MatVariable::from(x as f64)).collect();
+        let s = StructureArray::new(vec![1, 2], fieldnames, value).unwrap();
+
+        let renamed = s
+            .clone()
+            .map_fields(|name, var| (name == "a").then(|| ("x".to_string(), var)))
+            .unwrap();
+        assert_eq!(renamed.fieldnames(), vec!["x"]);
+        assert_eq!(renamed.value[1]["x"], MatVariable::from(3.0));
+
+        // Fields must match between elements
+        let mut calls = 0;
+        let err = s
+            .clone()
+            .map_fields(|name, var| {
+                calls += 1;
+                (calls <= 2 || name == "a").then_some((name, var))
+            })
+            .unwrap_err();
+        assert!(err.to_string().starts_with("Element 1 has fields [\"a\"]"));
+
+        let err = s.map_fields(|_, var| Some(("x".to_string(), var))).unwrap_err();
+        assert_eq!(err.to_string(), "Field 'x' created more than once");
+
+        let empty = StructureArray::new(vec![0, 0], vec!["a".to_string()], vec![]).unwrap();
+        let empty = empty.map_fields(|_, _| None).unwrap();
+        assert_eq!(empty.fieldnames(), vec!["a"]);
+    }
+
+    #[test]
+    fn from_structures() {
+        use crate::matvar;
+
+        let s = StructureArray::from_structures(vec![], vec![matvar!({ a: 1.0 }); 3]).unwrap();
+        assert_eq!(s.dim, vec![1, 3]);
+        let s = StructureArray::from_structures(vec![0, 1], vec![]).unwrap();
+        assert_eq!(s.dim, vec![0, 1]);
+        assert!(s.fieldnames().is_empty());
+
+        let err = |dim, value| {
+            StructureArray::from_structures(dim, value)
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            err(vec![2, 2], vec![matvar!({ a: 1.0 }); 3]),
+            "Type construction error Specified size from dimension 4 does not match number of elements 3."
+        );
+        assert_eq!(
+            err(vec![1, 2], vec![matvar!({ a: 1.0 }), matvar!(2.0)]),
+            "Type construction error Element 1 is not a scalar structure."
+        );
+        assert_eq!(
+            err(
+                vec![1, 2],
+                vec![matvar!({ a: 1.0, b: 1.0 }), matvar!({ b: 1.0, a: 1.0 })]
+            ),
+            "Type construction error Element 1 has fields [\"b\", \"a\"], which differ from fields [\"a\", \"b\"] of the first element."
+        );
+
+        // Without a dimension, the structure array is a row of elements, not of values
+        let fieldnames = vec!["a".to_string(), "b".to_string()];
+        let s = StructureArray::new(vec![], fieldnames, vec![matvar!(1.0); 4]).unwrap();
+        assert_eq!(s.dim, vec![1, 2]);
+    }
 }
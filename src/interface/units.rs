@@ -0,0 +1,310 @@
+//! Units and scales stored next to the data.
+//!
+//! Many lab MAT-files store a variable `x` together with siblings like `x_units` holding `'mV'`
+//! and `x_scale` holding `1e-3`. [`MatFile::resolve_units`] pairs such variables into a
+//! [`Quantity`], whose [`Quantity::scaled`] values are the physical values. The names of the
+//! siblings are configured with a [`UnitConvention`]. Siblings of the wrong class are skipped
+//! with a warning in [`Quantity::warnings`].
+
+use indexmap::IndexMap;
+use indexmap::IndexSet;
+
+use crate::interface::error::MatrwError;
+use crate::interface::matfile::MatFile;
+use crate::interface::types::matlab_types::MatlabType;
+use crate::interface::types::numeric_array::NumericArray;
+use crate::interface::variable::{FromMatVariable, MatVariable};
+
+/// Numeric data with the units, scale and offset of its sibling variables
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quantity {
+    /// Data as stored
+    pub data: NumericArray,
+    /// Units, if the file names them
+    pub units: Option<String>,
+    /// Factor of the stored values, `1.0` without a scale sibling
+    pub scale: f64,
+    /// Added to the scaled values, `0.0` without an offset sibling
+    pub offset: f64,
+    /// Siblings skipped, because they are of the wrong class
+    pub warnings: Vec<String>,
+}
+
+impl Quantity {
+    /// Dimensions of the data, like [`NumericArray::dim`]
+    pub fn dim(&self) -> &[usize] {
+        &self.data.dim
+    }
+
+    /// Stored values times `scale` plus `offset`, in column-major order. Complex data gives the
+    /// scaled real parts.
+    pub fn scaled(&self) -> Vec<f64> {
+        let mut values = self.data.value.to_f64();
+        for x in values.iter_mut() {
+            *x = *x * self.scale + self.offset;
+        }
+        values
+    }
+}
+
+/// Names of the variables holding the units, scale and offset of a variable
+///
+/// A pattern gives the name of the sibling, with `{}` standing for the name of the variable,
+/// e.g. `{}_units`. The patterns of each kind are tried in the order they were added.
+///
+/// Example
+/// ```
+/// use matrw::{UnitConvention, matfile, matvar};
+///
+/// let matfile = matfile!(
+///     voltage: matvar!([100i16, 250i16]),
+///     voltageUnit: matvar!("V"),
+///     voltageGain: matvar!(0.01),
+/// );
+///
+/// let convention = UnitConvention::empty().units_pattern("{}Unit").scale_pattern("{}Gain");
+/// let quantities = matfile.resolve_units_with(&convention);
+///
+/// assert_eq!(quantities.len(), 1);
+/// assert_eq!(quantities["voltage"].units.as_deref(), Some("V"));
+/// assert_eq!(quantities["voltage"].scaled(), vec![1.0, 2.5]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnitConvention {
+    units: Vec<String>,
+    scale: Vec<String>,
+    offset: Vec<String>,
+}
+
+impl Default for UnitConvention {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UnitConvention {
+    ///
+    /// Convention with the siblings `{}_units` or `{}_unit`, `{}_scale` and `{}_offset`.
+    ///
+    pub fn new() -> Self {
+        Self::empty()
+            .units_pattern("{}_units")
+            .units_pattern("{}_unit")
+            .scale_pattern("{}_scale")
+            .offset_pattern("{}_offset")
+    }
+
+    ///
+    /// Convention without any pattern, to add the patterns of a file format.
+    ///
+    pub fn empty() -> Self {
+        Self {
+            units: Vec::new(),
+            scale: Vec::new(),
+            offset: Vec::new(),
+        }
+    }
+
+    /// Add a pattern of the variable holding the units, a character array.
+    pub fn units_pattern(mut self, pattern: &str) -> Self {
+        self.units.push(pattern.to_string());
+        self
+    }
+
+    /// Add a pattern of the variable holding the scale, a real numeric scalar.
+    pub fn scale_pattern(mut self, pattern: &str) -> Self {
+        self.scale.push(pattern.to_string());
+        self
+    }
+
+    /// Add a pattern of the variable holding the offset, a real numeric scalar.
+    pub fn offset_pattern(mut self, pattern: &str) -> Self {
+        self.offset.push(pattern.to_string());
+        self
+    }
+}
+
+/// First sibling of `name` matching one of `patterns`
+fn sibling<'a>(matfile: &'a MatFile, patterns: &[String], name: &str) -> Option<(String, &'a MatVariable)> {
+    patterns.iter().find_map(|pattern| {
+        let sibling = pattern.replace("{}", name);
+        matfile.get(&sibling).map(|var| (sibling, var))
+    })
+}
+
+fn is_char(arr: &NumericArray) -> bool {
+    matches!(arr.value, MatlabType::UTF8(_) | MatlabType::UTF16(_))
+}
+
+fn real_scalar(name: &str, var: &MatVariable) -> Result<f64, MatrwError> {
+    match var {
+        MatVariable::NumericArray(arr) if arr.is_scalar() && !arr.is_complex() && !is_char(arr) => {
+            Ok(arr.value.to_f64()[0])
+        }
+        _ => Err(MatrwError::AccessError(format!(
+            "Variable '{}' is not a real numeric scalar",
+            name
+        ))),
+    }
+}
+
+impl MatFile {
+    ///
+    /// Pair numeric variables with their units, scale and offset siblings of the default
+    /// [`UnitConvention`], see [`MatFile::resolve_units_with`].
+    ///
+    /// Example
+    /// ```
+    /// use matrw::{matfile, matvar};
+    ///
+    /// let matfile = matfile!(
+    ///     t: matvar!([0.0, 1.0, 2.0]),
+    ///     t_units: matvar!("ms"),
+    ///     temp: matvar!([200u16, 215u16]),
+    ///     temp_scale: matvar!(0.1),
+    ///     temp_offset: matvar!(-273.15),
+    ///     temp_units: matvar!("degC"),
+    ///     label: matvar!("run 1"),
+    /// );
+    ///
+    /// let quantities = matfile.resolve_units();
+    /// assert_eq!(quantities.keys().collect::<Vec<_>>(), ["t", "temp"]);
+    /// assert_eq!(quantities["t"].units.as_deref(), Some("ms"));
+    /// assert!((quantities["temp"].scaled()[1] - -251.65).abs() < 1e-9);
+    /// ```
+    ///
+    pub fn resolve_units(&self) -> IndexMap<String, Quantity> {
+        self.resolve_units_with(&UnitConvention::new())
+    }
+
+    ///
+    /// Pair numeric variables with their units, scale and offset siblings named by `convention`.
+    ///
+    /// Only numeric, non-character variables with at least one sibling are returned, in the
+    /// order of the file. The siblings themselves are not returned. Units siblings, which are no
+    /// character array, and scale or offset siblings, which are no real numeric scalar, are
+    /// skipped with a warning in [`Quantity::warnings`].
+    ///
+    pub fn resolve_units_with(&self, convention: &UnitConvention) -> IndexMap<String, Quantity> {
+        let mut quantities = IndexMap::new();
+        let mut siblings = IndexSet::new();
+
+        for (name, var) in self.iter() {
+            let MatVariable::NumericArray(data) = var else {
+                continue;
+            };
+            if is_char(data) {
+                continue;
+            }
+
+            let units = sibling(self, &convention.units, name);
+            let scale = sibling(self, &convention.scale, name);
+            let offset = sibling(self, &convention.offset, name);
+            if units.is_none() && scale.is_none() && offset.is_none() {
+                continue;
+            }
+
+            let mut warnings = Vec::new();
+            let units = units.and_then(|(sibling, var)| match String::from_matvar(var) {
+                Some(units) => {
+                    siblings.insert(sibling);
+                    Some(units)
+                }
+                None => {
+                    warnings.push(format!("Variable '{}' is not a character array", sibling));
+                    None
+                }
+            });
+            let mut factor = |found: Option<(String, &MatVariable)>, default| match found {
+                Some((sibling, var)) => match real_scalar(&sibling, var) {
+                    Ok(value) => {
+                        siblings.insert(sibling);
+                        value
+                    }
+                    Err(err) => {
+                        warnings.push(err.to_string());
+                        default
+                    }
+                },
+                None => default,
+            };
+            let scale = factor(scale, 1.0);
+            let offset = factor(offset, 0.0);
+
+            quantities.insert(
+                name.clone(),
+                Quantity {
+                    data: data.clone(),
+                    units,
+                    scale,
+                    offset,
+                    warnings,
+                },
+            );
+        }
+
+        quantities.retain(|name, _| !siblings.contains(name));
+        quantities
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{matfile, matvar};
+
+    #[test]
+    fn resolve_units() {
+        let matfile = matfile!(
+            x: matvar!([1i8, 2i8]),
+            x_unit: matvar!("mm"),
+            x_scale: matvar!(2.0),
+            x_scale_units: matvar!("1"),
+            y: matvar!([1.0]),
+            y_offset: matvar!(10.0),
+            z: matvar!([1.0]),
+        );
+        let quantities = matfile.resolve_units();
+        assert_eq!(quantities.keys().collect::<Vec<_>>(), ["x", "y"]);
+        assert_eq!(quantities["x"].units.as_deref(), Some("mm"));
+        assert_eq!(quantities["x"].scaled(), vec![2.0, 4.0]);
+        assert_eq!(quantities["x"].dim(), [1, 2]);
+        assert_eq!(quantities["y"].units, None);
+        assert_eq!(quantities["y"].scaled(), vec![11.0]);
+
+        // Patterns of the same kind are tried in order
+        let convention = UnitConvention::empty()
+            .units_pattern("{}_units")
+            .units_pattern("{}_unit");
+        let both = matfile!(x: matvar!(1.0), x_unit: matvar!("m"), x_units: matvar!("km"));
+        let quantities = both.resolve_units_with(&convention);
+        assert_eq!(quantities["x"].units.as_deref(), Some("km"));
+        assert!(quantities["x"].warnings.is_empty());
+        assert!(both.resolve_units_with(&UnitConvention::empty()).is_empty());
+
+        // Malformed siblings are skipped, the other variables are resolved
+        let bad = matfile!(
+            x: matvar!(1.0),
+            x_units: matvar!(3.0),
+            x_scale: matvar!(2.0),
+            y: matvar!(1.0),
+            y_scale: matvar!([1.0, 2.0]),
+            z: matvar!([1.0, 2.0]),
+            z_units: matvar!("V"),
+        );
+        let quantities = bad.resolve_units();
+        assert_eq!(quantities.keys().collect::<Vec<_>>(), ["x", "y", "z"]);
+        assert_eq!(quantities["x"].units, None);
+        assert_eq!(quantities["x"].scaled(), vec![2.0]);
+        assert_eq!(
+            quantities["x"].warnings,
+            ["Variable 'x_units' is not a character array"]
+        );
+        assert_eq!(quantities["y"].scale, 1.0);
+        assert_eq!(
+            quantities["y"].warnings,
+            ["Variable 'y_scale' is not a real numeric scalar"]
+        );
+        assert!(quantities["z"].warnings.is_empty());
+    }
+}
@@ -1,15 +1,20 @@
 //! Module defining enum [`MatVariable`], which describes different MAT-file variable types.
 
+use binrw::io::Cursor;
+use binrw::{BinReaderExt, BinWrite};
 use paste::paste;
 use std::fmt::{Debug, Display};
 use std::ops;
 
 use crate::check_same_fields;
+use crate::interface::error::MatrwError;
 use crate::interface::index::Index;
 use crate::interface::types::array::ArrayType;
 use crate::interface::types::cell_array::CellArray;
-use crate::interface::types::compressed_array::CompressedArray;
-use crate::interface::types::matlab_types::{MatlabType, MatlabTypeMarker};
+use crate::interface::types::dim::MatDim;
+use crate::interface::types::matlab_types::{
+    FromMatlabType, MatlabType, MatlabTypeMarker, MatlabTypeMarkerComplex, VariableClass,
+};
 use crate::interface::types::numeric_array::NumericArray;
 use crate::interface::types::sparse_array::SparseArray;
 use crate::interface::types::structure::Structure;
@@ -18,7 +23,29 @@ use crate::parser::v7::types::compressed_array::CompressedArray7;
 use crate::parser::v7::variable7::MatVariable7;
 
 /// MAT-file variable wrapper
+///
+/// The enum is `#[non_exhaustive]`, variants for further MATLAB types may be added. Matches need
+/// a wildcard arm. [`MatVariable::kind`], the accessors like [`MatVariable::as_numeric`] and
+/// [`MatVariable::walk`] work without matching.
+///
+/// ```
+/// use matrw::{VariableClass, matvar};
+///
+/// let var = matvar!({ a: [1.0, 2.0], b: "text" });
+///
+/// assert_eq!(var.as_structure().map(|s| s.fieldnames().len()), Some(2));
+/// assert_eq!(var["a"].as_numeric().map(|arr| arr.dim.to_vec()), Some(vec![1, 2]));
+/// assert_eq!(var["b"].kind(), Some(VariableClass::Char));
+/// assert!(var["a"].as_cell().is_none());
+/// ```
+///
+/// # Cloning
+///
+/// Variables own their data, no buffers are shared between variables. [`Clone`] copies all
+/// data, so mutating a clone never changes the original. [`MatVariable::deep_clone`] names this
+/// guarantee explicitly.
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub enum MatVariable {
     ///
     /// Full (dense) numeric arrays of arbitrary dimensions. Can contain the numeric types (`i8`, `i16`, `i32`, `i64`, `u8`, `u16`, `u32`, `u64`, `f32`, `f64`) and the character type (`char`).
@@ -282,15 +309,6 @@ pub enum MatVariable {
     ///
     Null,
     ///
-    /// Wrapper type which applies compression on write when using option `compress=true` in function
-    /// [`crate::save_matfile_v7`].
-    ///
-    /// <div class="warning">
-    /// This type is used implicitly. It should not be used manually.
-    /// </div>
-    ///
-    Compressed(CompressedArray),
-    ///
     /// Support type used for description of unsupported types.
     ///
     Unsupported,
@@ -299,23 +317,148 @@ pub enum MatVariable {
 impl MatVariable {
     /// Get array dimensions.
     ///
+    /// [`MatVariable::Null`] and unsupported variables return `[0, 0]`.
+    ///
     /// # Example
     ///
     /// ```
-    /// # use matrw::matvar;
+    /// # use matrw::{matvar, MatVariable};
     /// let var = matvar!([[1.0, 2.0], [42.0, 43.0]]);
     ///
     /// assert_eq!(var.dim(), vec![2, 2]);
+    /// assert_eq!(var.dim().to_string(), "2x2");
+    /// assert_eq!(MatVariable::Null.dim(), vec![0, 0]);
     /// ```
     ///
-    pub fn dim(&self) -> Vec<usize> {
+    pub fn dim(&self) -> MatDim {
         match self {
             MatVariable::NumericArray(val) => val.dim.clone(),
             MatVariable::CellArray(val) => val.dim.clone(),
-            MatVariable::Structure(_) => vec![1, 1],
+            MatVariable::Structure(_) => MatDim::from_slice(&[1, 1]),
             MatVariable::StructureArray(val) => val.dim.clone(),
             MatVariable::SparseArray(val) => val.dim.clone(),
-            _ => unimplemented!(),
+            MatVariable::Null | MatVariable::Unsupported => MatDim::from_slice(&[0, 0]),
+        }
+    }
+
+    /// Number of elements, see [`MatVariable::dim`]. A structure counts as one element.
+    pub fn numel(&self) -> usize {
+        self.dim().numel()
+    }
+
+    /// True for exactly one element, like MATLAB's `isscalar`
+    pub fn is_scalar(&self) -> bool {
+        self.dim().is_scalar()
+    }
+
+    ///
+    /// True for no elements, like MATLAB's `isempty`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use matrw::{matvar, MatVariable};
+    /// let empty = matvar!([]);
+    ///
+    /// assert!(empty.is_empty() && !empty.is_scalar());
+    /// assert_eq!(empty.to_scalar::<f64>(), None);
+    /// assert!(MatVariable::Null.is_empty());
+    /// assert!(matvar!({ a: 1.0 }).is_scalar());
+    /// ```
+    ///
+    pub fn is_empty(&self) -> bool {
+        self.dim().is_empty()
+    }
+
+    /// Copy the variable including all its data.
+    ///
+    /// The copy shares no memory with `self`. This is what [`Clone`] does for `MatVariable`,
+    /// `deep_clone` states it at the call site and keeps meaning it, should cheaper clones be
+    /// added.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use matrw::matvar;
+    /// let var = matvar!({ a: [1.0, 2.0] });
+    /// let mut copy = var.deep_clone();
+    /// copy.as_structure_mut().unwrap().value.insert("b".to_string(), matvar!(3.0));
+    ///
+    /// assert!(var.get("b").is_none());
+    /// assert_eq!(copy["a"], var["a"]);
+    /// ```
+    ///
+    pub fn deep_clone(&self) -> MatVariable {
+        self.clone()
+    }
+
+    /// Sort the fields of this and all nested structures alphabetically.
+    ///
+    /// Fields are otherwise kept in insertion order, or in file order for variables read from a
+    /// file. Sorting makes the order independent of how the variable was constructed, e.g. for
+    /// comparing printed variables, see also [`crate::SaveOptions::sort_struct_fields`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use matrw::matvar;
+    /// let mut var = matvar!({ b: 1.0, a: { d: 1.0, c: 2.0 } });
+    /// var.sort_keys();
+    ///
+    /// assert_eq!(var.fieldnames(), Some(vec!["a".to_string(), "b".to_string()]));
+    /// assert_eq!(var["a"].fieldnames(), Some(vec!["c".to_string(), "d".to_string()]));
+    /// ```
+    ///
+    pub fn sort_keys(&mut self) {
+        match self {
+            MatVariable::Structure(s) => {
+                s.sort_fields();
+                s.value.values_mut().for_each(MatVariable::sort_keys);
+            }
+            MatVariable::StructureArray(s) => {
+                s.sort_fields();
+                s.value.iter_mut().for_each(MatVariable::sort_keys);
+            }
+            MatVariable::CellArray(c) => c.value.iter_mut().for_each(MatVariable::sort_keys),
+            _ => {}
+        }
+    }
+
+    /// Get the class of the variable, `None` for [`MatVariable::Null`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use matrw::{matvar, MatVariable, VariableClass};
+    /// assert_eq!(matvar!([1i16, 2i16]).kind(), Some(VariableClass::Int16));
+    /// assert_eq!(matvar!({ a: 1.0 }).kind(), Some(VariableClass::Struct));
+    /// assert_eq!(MatVariable::Unsupported.kind(), Some(VariableClass::Object));
+    /// assert_eq!(MatVariable::Null.kind(), None);
+    /// ```
+    ///
+    pub fn kind(&self) -> Option<VariableClass> {
+        VariableClass::of(self)
+    }
+
+    /// Get the class name, like MATLAB's `class` returns it. `None` for [`MatVariable::Null`].
+    ///
+    /// Sparse arrays are `double` or `logical`. The class of objects is not known, they are
+    /// `object`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use matrw::matvar;
+    /// assert_eq!(matvar!([1u8]).class_name(), Some("uint8"));
+    /// assert_eq!(matvar!("text").class_name(), Some("char"));
+    /// assert_eq!(matvar!([[1.0, 0.0], [0.0, 2.0]]).to_sparse().unwrap().class_name(), Some("double"));
+    /// ```
+    ///
+    pub fn class_name(&self) -> Option<&'static str> {
+        match self {
+            MatVariable::SparseArray(arr) if arr.is_logical() => Some("logical"),
+            MatVariable::SparseArray(_) => Some("double"),
+            var => var.kind().map(VariableClass::name),
         }
     }
 
@@ -379,10 +522,110 @@ impl MatVariable {
         }
     }
 
-    /// If [`MatVariable`] is of type [`MatVariable::NumericArray`],
-    /// return real part as scalar value. Otherwise, returns [`None`].
+    /// `true`, if the variable is a logical [`MatVariable::NumericArray`] or
+    /// [`MatVariable::SparseArray`], like MATLAB's `islogical`.
     ///
-    /// If `NumericArray` contains more than one value, the first value is returned.
+    /// Logical arrays hold [`MatlabType::BOOL`] values and have the class `logical`, not one of
+    /// the numeric classes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use matrw::matvar;
+    /// assert!(matvar!([true, false]).is_logical());
+    /// assert!(matvar!([[true, false], [false, true]]).to_sparse().unwrap().is_logical());
+    /// assert!(!matvar!([1u8, 0u8]).is_logical());
+    /// assert!(!matvar!({ a: true }).is_logical());
+    /// ```
+    ///
+    pub fn is_logical(&self) -> bool {
+        match self {
+            MatVariable::NumericArray(val) => val.is_logical(),
+            MatVariable::SparseArray(val) => val.is_logical(),
+            _ => false,
+        }
+    }
+
+    /// Convert into a [`StructureArray`], keeping the dimensions.
+    ///
+    /// Cell arrays of scalar structures are converted with
+    /// [`CellArray::try_into_structure_array`], a scalar structure gives a 1x1 structure array and
+    /// structure arrays are returned as they are. Other variables give
+    /// [`MatrwError::AccessError`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use matrw::matvar;
+    /// let records = matvar!([{ id: 1.0 }, { id: 2.0, extra: 0.0 }]);
+    /// assert!(records.try_into_structure_array().is_err());
+    ///
+    /// let records = matvar!([{ id: 1.0 }, { id: 2.0 }]);
+    /// assert_eq!(records.try_into_structure_array().unwrap().dim, vec![1, 2]);
+    /// ```
+    ///
+    pub fn try_into_structure_array(self) -> Result<StructureArray, MatrwError> {
+        match self {
+            MatVariable::CellArray(cells) => cells.try_into_structure_array(),
+            MatVariable::StructureArray(s) => Ok(s),
+            s @ MatVariable::Structure(_) => StructureArray::from_structures(vec![1, 1], vec![s]),
+            _ => Err(MatrwError::AccessError(
+                "Variable is no cell array of structures".to_string(),
+            )),
+        }
+    }
+
+    /// `true`, if `vars` is not empty and all variables have the same dimensions.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use matrw::{matvar, MatVariable};
+    /// assert!(MatVariable::same_shape(&[matvar!([1.0, 2.0]), matvar!(["a", "b"])]));
+    /// assert!(!MatVariable::same_shape(&[matvar!([1.0, 2.0]), matvar!(3.0)]));
+    /// assert!(!MatVariable::same_shape(&[]));
+    /// ```
+    ///
+    pub fn same_shape(vars: &[MatVariable]) -> bool {
+        let Some((first, rest)) = vars.split_first() else {
+            return false;
+        };
+        let dim = first.dim();
+        rest.iter().all(|var| var.dim() == dim)
+    }
+
+    /// `true`, if `vars` is not empty and all variables are [`MatVariable::NumericArray`]s with
+    /// values of the same [`MatlabType`] variant, which are all real or all complex.
+    ///
+    /// Arrays of the same shape passing this check can be joined into one array, as [`crate::matvar`]
+    /// does for `[a, b]`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use matrw::{matvar, MatVariable};
+    /// assert!(MatVariable::same_numeric_class(&[matvar!(1u8), matvar!([2u8, 3u8])]));
+    /// assert!(!MatVariable::same_numeric_class(&[matvar!(1u8), matvar!(2.0)]));
+    /// assert!(!MatVariable::same_numeric_class(&[matvar!(1.0), matvar!({ a: 1.0 })]));
+    /// ```
+    ///
+    pub fn same_numeric_class(vars: &[MatVariable]) -> bool {
+        let class = |var: &MatVariable| match var {
+            MatVariable::NumericArray(arr) => Some((std::mem::discriminant(&arr.value), arr.is_complex())),
+            _ => None,
+        };
+        let Some((first, rest)) = vars.split_first() else {
+            return false;
+        };
+        let first = class(first);
+        first.is_some() && rest.iter().all(|var| class(var) == first)
+    }
+
+    /// If [`MatVariable`] is of type [`MatVariable::NumericArray`] with a single element,
+    /// return real part as scalar value. Otherwise returns [`None`].
+    ///
+    /// Arrays of more than one value, like the character array `'abc'`, give [`None`] instead of
+    /// their first value. Use [`MatVariable::to_vec`] or [`MatVariable::to_matlab_string`] for them.
     ///
     /// # Example
     ///
@@ -395,15 +638,13 @@ impl MatVariable {
     ///
     pub fn to_scalar<T: MatlabTypeMarker>(&self) -> Option<T> {
         match self {
-            MatVariable::NumericArray(val) => val.real_to_scalar(),
+            MatVariable::NumericArray(val) if val.is_scalar() => val.real_to_scalar(),
             _ => None,
         }
     }
 
-    /// If [`MatVariable`] is of type [`MatVariable::NumericArray`],
-    /// return complex part as scalar value. Otherwise, returns [`None`].
-    ///
-    /// If `NumericArray` contains more than one value, the first value is returned.
+    /// If [`MatVariable`] is of type [`MatVariable::NumericArray`] with a single complex element,
+    /// return complex part as scalar value. Otherwise returns [`None`].
     ///
     /// # Example
     ///
@@ -416,11 +657,59 @@ impl MatVariable {
     ///
     pub fn comp_to_scalar<T: MatlabTypeMarker>(&self) -> Option<T> {
         match self {
-            MatVariable::NumericArray(val) => val.comp_to_scalar(),
+            MatVariable::NumericArray(val) if val.is_scalar() => val.comp_to_scalar(),
             _ => None,
         }
     }
 
+    /// If [`MatVariable`] is a character row vector, return it as `String`. Otherwise returns
+    /// [`None`], see [`FromMatVariable`] for `String`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use matrw::matvar;
+    /// let var = matvar!("abc");
+    ///
+    /// assert_eq!(var.to_matlab_string().as_deref(), Some("abc"));
+    /// assert_eq!(var.to_char(), None);
+    /// assert_eq!(matvar!("a").to_char(), Some('a'));
+    /// ```
+    ///
+    pub fn to_matlab_string(&self) -> Option<String> {
+        String::from_matvar(self)
+    }
+
+    /// Return the strings of a cell array of character row vectors, like MATLAB's `cellstr`, or
+    /// the rows of a character matrix, including their padding. A character row vector gives a
+    /// single string. Otherwise returns [`None`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use matrw::matvar;
+    /// let var = matvar!(["ab", "c"]);
+    ///
+    /// assert_eq!(var.to_vec_string(), Some(vec!["ab".to_string(), "c".to_string()]));
+    /// assert_eq!(matvar!(["ab", 1.0]).to_vec_string(), None);
+    /// ```
+    ///
+    pub fn to_vec_string(&self) -> Option<Vec<String>> {
+        match self {
+            MatVariable::CellArray(cells) => cells.value.iter().map(String::from_matvar).collect(),
+            MatVariable::NumericArray(arr) if arr.dim.len() == 2 && arr.dim[0] > 1 => {
+                let chars = char::inner_ref(&arr.value)?;
+                let rows = arr.dim[0];
+                Some(
+                    (0..rows)
+                        .map(|r| chars.iter().skip(r).step_by(rows).collect())
+                        .collect(),
+                )
+            }
+            _ => self.to_matlab_string().map(|s| vec![s]),
+        }
+    }
+
     /// If [`MatVariable`] is of type [`MatVariable::NumericArray`],
     /// return cloned inner real `Vec`. Otherwise, returns [`None`].
     ///
@@ -475,11 +764,45 @@ impl MatVariable {
     ///
     pub fn to_sparse(self) -> Option<MatVariable> {
         match self {
-            MatVariable::NumericArray(val) => val.to_sparse(),
+            MatVariable::NumericArray(val) => val.to_sparse().ok(),
             _ => None,
         }
     }
 
+    /// Convert all numeric arrays to `class`, including arrays inside structures and cells.
+    ///
+    /// Real and imaginary parts are converted like [`VariableClass::cast`]. Character, logical and
+    /// sparse arrays are left unchanged, since MATLAB stores them in fixed classes. Returns
+    /// [`MatrwError::TypeConstruction`], if `class` is not numeric.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use matrw::{matvar, VariableClass};
+    /// let mut var = matvar!({ a: [1.6, 2.0], b: "x" });
+    /// var.convert_numeric(VariableClass::Int8).unwrap();
+    ///
+    /// assert_eq!(var["a"].to_vec_i8(), Some(vec![2, 2]));
+    /// ```
+    ///
+    pub fn convert_numeric(&mut self, class: VariableClass) -> Result<(), MatrwError> {
+        if !class.is_numeric() {
+            return Err(MatrwError::TypeConstruction(format!(
+                "Class {} is not numeric.",
+                class
+            )));
+        }
+        self.walk_mut(|_, var| {
+            if let MatVariable::NumericArray(arr) = var
+                && VariableClass::numeric(&arr.value).is_some()
+            {
+                arr.value = class.cast(&arr.value).expect("Class is numeric");
+                arr.value_cmp = arr.value_cmp.as_ref().and_then(|cmp| class.cast(cmp));
+            }
+        });
+        Ok(())
+    }
+
     /// Return iterator over all elements in column-major order.
     ///
     /// # Example
@@ -545,7 +868,9 @@ macro_rules! impl_MatVariable_to_vec {
             //
             // to_vec_<$ret>
             //
-            #[doc = concat!("If [`MatVariable`] is of type [`MatVariable::NumericArray`], returns cloned `Vec<", stringify!($ret),">`. Otherwise, returns [`None`].")]
+            #[doc = concat!("If [`MatVariable`] is of type [`MatVariable::NumericArray`], returns cloned `Vec<", stringify!($ret),">` in column-major order. Otherwise, returns [`None`].")]
+            #[doc = ""]
+            #[doc = "For the matrix `[1 2; 3 4]` this is `[1, 3, 2, 4]`, see [`NumericArray::to_row_major_vec`] for the rows one after another."]
             pub fn [<to_vec_ $ret>](&self) -> Option<Vec<$ret>> {
                 match self {
                     MatVariable::NumericArray(val) => val.real_to_vec::<$ret>(),
@@ -576,6 +901,97 @@ macro_rules! impl_MatVariable_comp_to_vec {
     };
 }
 
+macro_rules! impl_MatVariable_as {
+    ($($variant: ident => $name: ident: $ty: ty),*) => {
+        paste! {
+            $(
+            //
+            // as_<$name>, as_<$name>_mut
+            //
+            #[doc = concat!("If [`MatVariable`] is of type [`MatVariable::", stringify!($variant), "`], returns a reference to the [`", stringify!($ty), "`]. Otherwise, returns [`None`].")]
+            pub fn [<as_ $name>](&self) -> Option<&$ty> {
+                match self {
+                    MatVariable::$variant(val) => Some(val),
+                    _ => None,
+                }
+            }
+
+            #[doc = concat!("If [`MatVariable`] is of type [`MatVariable::", stringify!($variant), "`], returns a mutable reference to the [`", stringify!($ty), "`]. Otherwise, returns [`None`].")]
+            pub fn [<as_ $name _mut>](&mut self) -> Option<&mut $ty> {
+                match self {
+                    MatVariable::$variant(val) => Some(val),
+                    _ => None,
+                }
+            }
+            )*
+        }
+    };
+}
+
+impl MatVariable {
+    impl_MatVariable_as!(
+        NumericArray => numeric: NumericArray,
+        SparseArray => sparse: SparseArray,
+        StructureArray => structure_array: StructureArray,
+        CellArray => cell: CellArray,
+        Structure => structure: Structure
+    );
+
+    /// Return if the variable is [`MatVariable::Null`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use matrw::matvar;
+    /// let var = matvar!({ a: 1.0 });
+    ///
+    /// assert!(var["b"].is_null());
+    /// assert!(!var["a"].is_null());
+    /// ```
+    ///
+    pub fn is_null(&self) -> bool {
+        matches!(self, MatVariable::Null)
+    }
+
+    ///
+    /// `[]`, an empty 0x0 array of class double.
+    ///
+    /// This is the single representation of empty cell elements, like the elements of
+    /// `cell(1, 3)` read from a file or [`MatVariable::Null`] placed into a [`CellArray`].
+    ///
+    pub fn empty_value() -> Self {
+        MatVariable::NumericArray(
+            NumericArray::new(vec![0, 0], MatlabType::new(), None).expect("Dimension has no elements"),
+        )
+    }
+
+    /// Return if the variable is `[]` or [`MatVariable::Null`], what unset cell elements and
+    /// fields hold, see [`MatVariable::empty_value`].
+    ///
+    /// Empty arrays of other classes or sizes, like `''` or `zeros(0, 3)`, are values of their
+    /// own and return `false`, [`MatVariable::is_empty`] is true for them.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use matrw::{matvar, MatVariable};
+    /// assert!(matvar!([]).is_empty_value());
+    /// assert!(MatVariable::Null.is_empty_value());
+    /// assert!(!matvar!("").is_empty_value());
+    /// assert!(matvar!("").is_empty());
+    /// ```
+    ///
+    pub fn is_empty_value(&self) -> bool {
+        match self {
+            MatVariable::Null => true,
+            MatVariable::NumericArray(arr) => {
+                arr.dim == [0, 0] && !arr.is_complex() && matches!(arr.value, MatlabType::F64(_))
+            }
+            _ => false,
+        }
+    }
+}
+
 impl MatVariable {
     impl_MatVariable_to!(u8, i8, u16, i16, u32, i32, u64, i64, f32, f64, char, bool);
     impl_MatVariable_comp_to!(u8, i8, u16, i16, u32, i32, u64, i64, f32, f64, char, bool);
@@ -587,8 +1003,22 @@ impl MatVariable {
 // Index
 // ============================================================================
 
+/// Indexing returning an owned value.
+///
+/// Elements of numeric and sparse arrays are not stored as [`MatVariable`], so they cannot be
+/// returned by reference with `[]`. [`OwnedIndex::elem`] constructs a 1x1 array of the element
+/// instead, and [`MatVariable::Null`], if the index is out of bounds.
+///
+/// ```
+/// use matrw::{matvar, OwnedIndex};
+///
+/// let a = matvar!([[1.0, 2.0], [3.0, 4.0]]);
+/// assert_eq!(a.elem([1, 0]).to_f64(), Some(3.0));
+/// assert_eq!(a.elem(3).to_f64(), Some(4.0));
+/// ```
 pub trait OwnedIndex<Idx> {
     type Output;
+    /// Element at `index`, a linear column-major index or subscripts
     fn elem(&self, index: Idx) -> Self::Output;
 }
 
@@ -616,6 +1046,107 @@ where
     }
 }
 
+impl MatVariable {
+    /// Return a reference to a structure field or an element of a cell or structure array.
+    ///
+    /// Unlike indexing with `[]`, which returns [`MatVariable::Null`] for anything not found,
+    /// this returns `None`, if the field or element does not exist. This distinguishes missing
+    /// data from stored empty values.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use matrw::matvar;
+    /// let var = matvar!({ a: [], b: 1.0 });
+    ///
+    /// assert!(var.get("a").is_some());
+    /// assert!(var.get("c").is_none());
+    /// assert!(var.get("b").and_then(|b| b.get(0)).is_none());
+    /// ```
+    ///
+    pub fn get<T: Index>(&self, index: T) -> Option<&MatVariable> {
+        index.index_into_ref(self)
+    }
+}
+
+// ============================================================================
+// Conversion
+// ============================================================================
+
+/// Types that can be extracted from a [`MatVariable`].
+///
+/// Implemented for
+/// - numeric scalars, bool and char, from 1x1 arrays of the same class,
+/// - `Vec<T>` of these types, from arrays of the same class in column-major order,
+/// - `String`, from character row vectors.
+///
+/// Used by [`CellArray::to_vec_of`] and [`Structure::get_as`].
+pub trait FromMatVariable: Sized {
+    /// Description of the expected type, used in error messages.
+    fn expected() -> String;
+
+    fn from_matvar(var: &MatVariable) -> Option<Self>;
+}
+
+/// `expected` with its indefinite article, as in "an i32 scalar" or "a u8 array".
+///
+/// Type names like `f64` are read by their letter, "ef", so take "an" as well.
+pub(crate) fn with_article(expected: &str) -> String {
+    let mut chars = expected.chars();
+    let an = match (chars.next(), chars.next()) {
+        (Some('a' | 'e' | 'i' | 'o'), _) => true,
+        (Some('f'), Some(c)) => c.is_ascii_digit(),
+        _ => false,
+    };
+    format!("{} {}", if an { "an" } else { "a" }, expected)
+}
+
+impl<T: MatlabTypeMarker> FromMatVariable for T {
+    fn expected() -> String {
+        format!("{} scalar", std::any::type_name::<T>())
+    }
+
+    fn from_matvar(var: &MatVariable) -> Option<Self> {
+        match var {
+            MatVariable::NumericArray(arr) if arr.is_scalar() => T::inner_ref(&arr.value)?.first().copied(),
+            _ => None,
+        }
+    }
+}
+
+impl<T: MatlabTypeMarker> FromMatVariable for Vec<T> {
+    fn expected() -> String {
+        format!("{} array", std::any::type_name::<T>())
+    }
+
+    fn from_matvar(var: &MatVariable) -> Option<Self> {
+        match var {
+            MatVariable::NumericArray(arr) => T::inner_ref(&arr.value).cloned(),
+            _ => None,
+        }
+    }
+}
+
+impl FromMatVariable for String {
+    fn expected() -> String {
+        "character row vector".to_string()
+    }
+
+    fn from_matvar(var: &MatVariable) -> Option<Self> {
+        match var {
+            MatVariable::NumericArray(arr) if arr.dim.len() == 2 && arr.dim[0] <= 1 => {
+                let chars = char::inner_ref(&arr.value)?;
+                // Decode surrogate pairs, which are one character per code unit in the array
+                match arr.stored_utf16_units() {
+                    Some(units) if units.len() == chars.len() => Some(String::from_utf16_lossy(units)),
+                    _ => Some(chars.iter().collect()),
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
 // ============================================================================
 // Iterator
 // ============================================================================
@@ -734,7 +1265,7 @@ where
 /// ```
 impl<T> From<Vec<(T, T)>> for MatVariable
 where
-    T: MatlabTypeMarker,
+    T: MatlabTypeMarkerComplex,
 {
     fn from(value: Vec<(T, T)>) -> Self {
         let real = value.iter().map(|x| x.0).collect::<Vec<T>>();
@@ -773,6 +1304,13 @@ where
 
 /// Create a `MatVariable` from `(T, T)`.
 ///
+/// Only numeric types can be complex, logical and character arrays can't:
+///
+/// ```compile_fail
+/// # use matrw::MatVariable;
+/// let s = MatVariable::from((true, false));
+/// ```
+///
 /// # Example
 ///
 /// ```
@@ -781,7 +1319,7 @@ where
 /// ```
 impl<T> From<(T, T)> for MatVariable
 where
-    T: MatlabTypeMarker,
+    T: MatlabTypeMarkerComplex,
 {
     fn from(value: (T, T)) -> Self {
         MatVariable::NumericArray(
@@ -822,13 +1360,67 @@ impl From<Vec<MatVariable>> for MatVariable {
         // If inputs are structs with all equal fieldnames, make a struct array,
         // otherwise make a cell array.
         if value.iter().all(|x| matches!(x, MatVariable::Structure(_))) && check_same_fields(&value) {
-            MatVariable::StructureArray(StructureArray::from_structures(vec![1, value.len()], value))
+            MatVariable::StructureArray(
+                StructureArray::from_structures(vec![1, value.len()], value)
+                    .expect("Structures have the same fields"),
+            )
         } else {
             MatVariable::CellArray(CellArray::new(vec![1, value.len()], value).unwrap())
         }
     }
 }
 
+impl MatVariable {
+    ///
+    /// Element of the variable as the version 7 parser reads and writes it.
+    ///
+    /// This is an advanced API for byte-level control of the written file, e.g. to write an
+    /// element built by hand with [`crate::MatFileWriter::write_parser_v7`]. The element has no
+    /// name, set it with [`MatVariable7::set_name`]. Writing a variable as element skips the
+    /// transformations of [`crate::SaveOptions`].
+    ///
+    /// Returns [`MatrwError::TypeConstruction`] for [`MatVariable::Null`], which has no element.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use matrw::{MatVariable, matvar};
+    /// let var = matvar!([1.0, 2.0]);
+    ///
+    /// let mut element = var.to_parser_v7().unwrap();
+    /// element.set_name("x");
+    /// assert_eq!(element.name(), "x");
+    /// assert_eq!(MatVariable::from_parser_v7(&element).unwrap(), var);
+    /// ```
+    ///
+    pub fn to_parser_v7(&self) -> Result<MatVariable7, MatrwError> {
+        match self {
+            MatVariable::Null => Err(MatrwError::TypeConstruction(
+                "Null variables have no MAT-file element".to_string(),
+            )),
+            var => Ok(MatVariable7::from(var.clone())),
+        }
+    }
+
+    ///
+    /// Variable, which a file containing the element `element` reads as, see
+    /// [`MatVariable::to_parser_v7`].
+    ///
+    /// The element is encoded and read back, so values held in a storage type different from
+    /// their class, e.g. doubles stored as `uint8`, are converted to their class. Compressed
+    /// elements are read as the variable they contain, objects and unknown elements as
+    /// [`MatVariable::Unsupported`].
+    ///
+    /// Returns [`MatrwError::BinrwError`], if the element can't be encoded or read back.
+    ///
+    pub fn from_parser_v7(element: &MatVariable7) -> Result<Self, MatrwError> {
+        let mut raw = Cursor::new(Vec::new());
+        element.write_le(&mut raw)?;
+        raw.set_position(0);
+        Ok(MatVariable::from(raw.read_le::<MatVariable7>()?))
+    }
+}
+
 impl From<MatVariable7> for MatVariable {
     fn from(value: MatVariable7) -> Self {
         match value {
@@ -840,10 +1432,8 @@ impl From<MatVariable7> for MatVariable {
             MatVariable7::Sparse(v) => MatVariable::SparseArray(SparseArray::from(v)),
             MatVariable7::ObjectMCOS(_) => MatVariable::Unsupported,
             MatVariable7::ObjectHandle(_) => MatVariable::Unsupported,
-            MatVariable7::Empty(_) => MatVariable::NumericArray(
-                NumericArray::new(vec![0, 0], MatlabType::new(), None)
-                    .expect("Could not create NumericArray."),
-            ),
+            MatVariable7::Unknown(_) => MatVariable::Unsupported,
+            MatVariable7::Empty(_) => MatVariable::empty_value(),
         }
     }
 }
@@ -859,10 +1449,8 @@ impl From<CompressedArray7> for MatVariable {
             MatVariable7::Sparse(v) => MatVariable::SparseArray(SparseArray::from(v)),
             MatVariable7::ObjectMCOS(_) => MatVariable::Unsupported,
             MatVariable7::ObjectHandle(_) => MatVariable::Unsupported,
-            MatVariable7::Empty(_) => MatVariable::NumericArray(
-                NumericArray::new(vec![0, 0], MatlabType::new(), None)
-                    .expect("Could not create NumericArray."),
-            ),
+            MatVariable7::Unknown(_) => MatVariable::Unsupported,
+            MatVariable7::Empty(_) => MatVariable::empty_value(),
         }
     }
 }
@@ -880,22 +1468,29 @@ impl Display for MatVariable {
             MatVariable::StructureArray(_v) => todo!(),
             MatVariable::SparseArray(v) => write!(f, "{}", v),
             MatVariable::Null => todo!(),
-            MatVariable::Compressed(_v) => todo!(),
             MatVariable::Unsupported => todo!(),
         }
     }
 }
 
-#[allow(unused)]
+/// Variables are equal if their dimensions and contents match. Structures are only equal with
+/// the same field order, unlike with MATLAB's `isequal`, which ignores it. Compare
+/// [`MatVariable::content_hash`], or sort the fields with [`Structure::sort_fields`] first, to
+/// ignore the field order.
 impl PartialEq for MatVariable {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
             (Self::NumericArray(l0), Self::NumericArray(r0)) => l0 == r0,
-            (Self::CellArray(l0), Self::CellArray(r0)) => todo!(),
-            (Self::Structure(l0), Self::Structure(r0)) => todo!(),
-            (Self::StructureArray(l0), Self::StructureArray(r0)) => todo!(),
-            (Self::SparseArray(l0), Self::SparseArray(r0)) => todo!(),
-            (Self::Compressed(l0), Self::Compressed(r0)) => todo!(),
+            (Self::CellArray(l0), Self::CellArray(r0)) => l0.dim == r0.dim && l0.value == r0.value,
+            (Self::Structure(l0), Self::Structure(r0)) => l0.value.iter().eq(r0.value.iter()),
+            (Self::StructureArray(l0), Self::StructureArray(r0)) => l0.dim == r0.dim && l0.value == r0.value,
+            (Self::SparseArray(l0), Self::SparseArray(r0)) => {
+                l0.dim == r0.dim
+                    && l0.ir == r0.ir
+                    && l0.jc == r0.jc
+                    && l0.value == r0.value
+                    && l0.value_cmp == r0.value_cmp
+            }
             _ => core::mem::discriminant(self) == core::mem::discriminant(other),
         }
     }
@@ -906,9 +1501,104 @@ impl PartialEq for MatVariable {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::matvar;
+
+    #[test]
+    fn equality_of_field_order() {
+        let ab = matvar!({ a: 1.0, b: "text" });
+        let ba = matvar!({ b: "text", a: 1.0 });
+        assert_ne!(ab, ba);
+        assert_eq!(ab.content_hash(), ba.content_hash());
+
+        let (MatVariable::Structure(mut ab), MatVariable::Structure(mut ba)) = (ab, ba) else {
+            unreachable!()
+        };
+        ab.sort_fields();
+        ba.sort_fields();
+        assert_eq!(MatVariable::Structure(ab), MatVariable::Structure(ba));
+    }
+
+    #[test]
+    fn same_shape_and_numeric_class() {
+        let scalars = [matvar!(1i8), matvar!(2i8)];
+        assert!(MatVariable::same_shape(&scalars) && MatVariable::same_numeric_class(&scalars));
+
+        let mixed = [matvar!(1i8), matvar!(2.0)];
+        assert!(MatVariable::same_shape(&mixed) && !MatVariable::same_numeric_class(&mixed));
+        assert!(MatVariable::same_numeric_class(&[matvar!("a"), matvar!("bc")]));
+        assert!(!MatVariable::same_numeric_class(&[matvar!(true), matvar!(1u8)]));
+        assert!(!MatVariable::same_numeric_class(&[MatVariable::Null]));
+        assert!(MatVariable::same_shape(&[MatVariable::Null, matvar!([])]));
+
+        // Arrays of different classes are collected in a cell array instead of failing
+        assert!(matches!(matvar!([1i8, 2.0]), MatVariable::CellArray(_)));
+        assert!(matches!(matvar!([1i8, 2i8]), MatVariable::NumericArray(_)));
+    }
 
     #[test]
     fn print_variable_size() {
         println!("MatVariable size: {}", size_of::<MatVariable>());
     }
+
+    #[test]
+    fn dim_of_all_variants() {
+        assert_eq!(crate::matvar!([1, 2, 3]).dim(), vec![1, 3]);
+        assert_eq!(MatVariable::Null.dim(), vec![0, 0]);
+        assert_eq!(MatVariable::Unsupported.dim(), vec![0, 0]);
+        assert_eq!(crate::matvar!({}).dim(), vec![1, 1]);
+    }
+
+    #[test]
+    fn class_names() {
+        use crate::matvar;
+
+        let cases = [
+            (matvar!(1.0), "double"),
+            (matvar!(1f32), "single"),
+            (matvar!(1i8), "int8"),
+            (matvar!(1u64), "uint64"),
+            (matvar!("a"), "char"),
+            (matvar!(true), "logical"),
+            (matvar!({ a: 1.0 }), "struct"),
+            (matvar!([{ a: 1.0 }, { a: 2.0 }]), "struct"),
+            (MatVariable::from(vec![matvar!(1.0), matvar!("a")]), "cell"),
+            (matvar!(1i32), "int32"),
+            (MatVariable::Unsupported, "object"),
+        ];
+        for (var, name) in cases {
+            assert_eq!(var.class_name(), Some(name));
+            assert_eq!(var.kind().map(|kind| kind.to_string()).as_deref(), Some(name));
+        }
+
+        let sparse = matvar!([[true, false], [false, true]]).to_sparse().unwrap();
+        assert_eq!(sparse.kind(), Some(VariableClass::Sparse));
+        assert_eq!(sparse.class_name(), Some("logical"));
+        assert_eq!(MatVariable::Null.class_name(), None);
+    }
+
+    #[test]
+    fn char_scalars_and_strings() {
+        let text = matvar!("ab");
+        assert_eq!(text.to_char(), None);
+        assert_eq!(text.to_scalar::<char>(), None);
+        assert_eq!(matvar!([1.0, 2.0]).to_scalar::<f64>(), None);
+        assert_eq!(matvar!([(1.0, 2.0), (3.0, 4.0)]).comp_to_scalar::<f64>(), None);
+        assert_eq!(matvar!('x').to_scalar::<char>(), Some('x'));
+
+        assert_eq!(text.to_matlab_string().as_deref(), Some("ab"));
+        assert_eq!(text.to_vec_string(), Some(vec!["ab".to_string()]));
+        assert_eq!(matvar!(1.0).to_matlab_string(), None);
+
+        // Rows of a character matrix
+        let MatVariable::NumericArray(mut rows) = matvar!("abcd") else {
+            unreachable!()
+        };
+        rows.dim = vec![2, 2].into();
+        let rows = MatVariable::NumericArray(rows);
+        assert_eq!(rows.to_matlab_string(), None);
+        assert_eq!(
+            rows.to_vec_string(),
+            Some(vec!["ac".to_string(), "bd".to_string()])
+        );
+    }
 }
@@ -0,0 +1,244 @@
+//! Depth-first traversal of nested variables.
+//!
+//! [`MatVariable::walk`] and [`MatFile::walk`] call a closure for every node of a variable tree,
+//! passing the [`Path`] of the node.
+
+use std::fmt::Display;
+
+use crate::interface::matfile::MatFile;
+use crate::interface::variable::MatVariable;
+
+/// Step from a node to one of its children
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathElem {
+    /// Structure field, or variable of a [`MatFile`]
+    Field(String),
+    /// Zero-based column-major index into a structure array
+    Element(usize),
+    /// Zero-based column-major index into a cell array
+    Cell(usize),
+}
+
+/// Position of a node relative to the root of a traversal.
+///
+/// The path is displayed in MATLAB syntax with one-based indices, e.g. `s(2).c{1}`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Path(Vec<PathElem>);
+
+impl Path {
+    ///
+    /// Construct the path of the root node.
+    ///
+    pub fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    ///
+    /// Steps from the root to the node.
+    ///
+    pub fn elems(&self) -> &[PathElem] {
+        &self.0
+    }
+
+    ///
+    /// Number of steps from the root, `0` for the root itself.
+    ///
+    pub fn depth(&self) -> usize {
+        self.0.len()
+    }
+
+    ///
+    /// Return if the path points to the root node.
+    ///
+    pub fn is_root(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Display for Path {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, elem) in self.0.iter().enumerate() {
+            match elem {
+                PathElem::Field(name) if i == 0 => write!(f, "{}", name)?,
+                PathElem::Field(name) => write!(f, ".{}", name)?,
+                PathElem::Element(n) => write!(f, "({})", n + 1)?,
+                PathElem::Cell(n) => write!(f, "{{{}}}", n + 1)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl MatVariable {
+    ///
+    /// Visit this variable and all nested variables depth-first.
+    ///
+    /// Parents are visited before their children. Children are structure fields in field
+    /// order, and elements of structure and cell arrays in column-major order.
+    ///
+    /// ```
+    /// use matrw::matvar;
+    ///
+    /// let var = matvar!({ a: 1.0, b: { c: "x" } });
+    ///
+    /// let mut paths = Vec::new();
+    /// var.walk(|path, _| paths.push(path.to_string()));
+    ///
+    /// assert_eq!(paths, vec!["", "a", "b", "b.c"]);
+    /// ```
+    ///
+    pub fn walk<F>(&self, mut f: F)
+    where
+        F: FnMut(&Path, &MatVariable),
+    {
+        walk(self, &mut Path::new(), &mut f);
+    }
+
+    ///
+    /// Visit this variable and all nested variables depth-first, with mutable access.
+    ///
+    /// A node is visited before its children, so children replaced by the closure are
+    /// traversed in their new form.
+    ///
+    pub fn walk_mut<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&Path, &mut MatVariable),
+    {
+        walk_mut(self, &mut Path::new(), &mut f);
+    }
+}
+
+impl MatFile {
+    ///
+    /// Visit all variables and their nested variables depth-first.
+    ///
+    /// Paths start with the variable name, see [`MatVariable::walk`].
+    ///
+    pub fn walk<F>(&self, mut f: F)
+    where
+        F: FnMut(&Path, &MatVariable),
+    {
+        let mut path = Path::new();
+        for (name, var) in self.iter() {
+            path.0.push(PathElem::Field(name.clone()));
+            walk(var, &mut path, &mut f);
+            path.0.pop();
+        }
+    }
+
+    ///
+    /// Visit all variables and their nested variables depth-first, with mutable access.
+    ///
+    /// See [`MatVariable::walk_mut`].
+    ///
+    pub fn walk_mut<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&Path, &mut MatVariable),
+    {
+        let mut path = Path::new();
+        for (name, var) in self.iter_mut() {
+            path.0.push(PathElem::Field(name.clone()));
+            walk_mut(var, &mut path, &mut f);
+            path.0.pop();
+        }
+    }
+}
+
+fn walk<F>(var: &MatVariable, path: &mut Path, f: &mut F)
+where
+    F: FnMut(&Path, &MatVariable),
+{
+    f(path, var);
+
+    let mut visit = |elem: PathElem, child: &MatVariable| {
+        path.0.push(elem);
+        walk(child, path, f);
+        path.0.pop();
+    };
+    match var {
+        MatVariable::Structure(s) => s
+            .value
+            .iter()
+            .for_each(|(name, v)| visit(PathElem::Field(name.clone()), v)),
+        MatVariable::StructureArray(s) => s
+            .value
+            .iter()
+            .enumerate()
+            .for_each(|(i, v)| visit(PathElem::Element(i), v)),
+        MatVariable::CellArray(c) => c
+            .value
+            .iter()
+            .enumerate()
+            .for_each(|(i, v)| visit(PathElem::Cell(i), v)),
+        _ => {}
+    }
+}
+
+fn walk_mut<F>(var: &mut MatVariable, path: &mut Path, f: &mut F)
+where
+    F: FnMut(&Path, &mut MatVariable),
+{
+    f(path, var);
+
+    let mut visit = |elem: PathElem, child: &mut MatVariable| {
+        path.0.push(elem);
+        walk_mut(child, path, f);
+        path.0.pop();
+    };
+    match var {
+        MatVariable::Structure(s) => s
+            .value
+            .iter_mut()
+            .for_each(|(name, v)| visit(PathElem::Field(name.clone()), v)),
+        MatVariable::StructureArray(s) => s
+            .value
+            .iter_mut()
+            .enumerate()
+            .for_each(|(i, v)| visit(PathElem::Element(i), v)),
+        MatVariable::CellArray(c) => c
+            .value
+            .iter_mut()
+            .enumerate()
+            .for_each(|(i, v)| visit(PathElem::Cell(i), v)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CellArray, MatlabType, matfile, matvar};
+
+    #[test]
+    fn walk_paths() {
+        let matfile = matfile!(
+            s: matvar!([{ a: 1.0 }, { a: 2.0 }]),
+            c: MatVariable::CellArray(CellArray::new(vec![1, 2], vec![matvar!(1.0), matvar!({ b: 1 })]).unwrap()),
+        );
+
+        let mut paths = Vec::new();
+        matfile.walk(|path, _| paths.push(path.to_string()));
+
+        assert_eq!(
+            paths,
+            vec![
+                "s", "s(1)", "s(1).a", "s(2)", "s(2).a", "c", "c{1}", "c{2}", "c{2}.b"
+            ]
+        );
+    }
+
+    #[test]
+    fn walk_mut_replaces_leaves() {
+        let mut var = matvar!({ a: 1.0, b: { c: 2.0, d: "x" } });
+
+        var.walk_mut(|path, node| {
+            if path.depth() > 1 && matches!(node.numeric_type(), Some(MatlabType::F64(_))) {
+                *node = matvar!(0.0);
+            }
+        });
+
+        assert_eq!(var["a"].to_f64(), Some(1.0));
+        assert_eq!(var["b"]["c"].to_f64(), Some(0.0));
+        assert_eq!(var["b"]["d"].numeric_type(), Some(&MatlabType::from("x")));
+    }
+}
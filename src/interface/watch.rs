@@ -0,0 +1,254 @@
+//! Reloading of MAT-files, which are rewritten while they are used.
+//!
+//! [`MatFileWatcher`] polls the modification time and size of a file and loads it again, when
+//! either changes, e.g. to visualize a file which a running MATLAB session saves periodically.
+//! Files modified shortly before a poll are compared by content as well, since modification times
+//! have a coarse resolution on some file systems.
+//! The variables are compared with the previous version by their
+//! [`crate::MatVariable::content_hash`], to report which of them changed.
+
+use std::fs;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use indexmap::IndexMap;
+
+use crate::interface::error::MatrwError;
+use crate::interface::fileio::{LoadOptions, load_matfile_from_u8_with_options};
+use crate::interface::matfile::MatFile;
+
+/// MAT-file loaded again by [`MatFileWatcher`]
+#[derive(Debug)]
+pub struct MatFileUpdate {
+    /// New content of the file
+    pub matfile: MatFile,
+    /// Names of the variables, which are new or differ from the previous version
+    pub changed: Vec<String>,
+    /// Names of the variables of the previous version, which are no longer part of the file
+    pub removed: Vec<String>,
+}
+
+/// Watcher of a MAT-file, which loads the file again when it is rewritten
+///
+/// Changes are detected by polling the modification time and size of the file, so no platform
+/// specific file notification is needed. The modification time may have a resolution of up to
+/// two seconds, so a file rewritten with the same size within that time would go unnoticed. Polls
+/// within two seconds of a modification therefore also read the file and compare its content
+/// with the loaded version. The first poll always loads the file.
+///
+/// Example
+/// ```
+/// use matrw::{MatFileWatcher, matfile, matvar, save_matfile_v7};
+///
+/// let path = std::env::temp_dir().join(format!("doc_watcher_{}.mat", std::process::id()));
+/// let path = path.to_str().unwrap();
+/// save_matfile_v7(path, matfile!(a: matvar!(1.0), b: matvar!(2.0)), false).unwrap();
+///
+/// let mut watcher = MatFileWatcher::new(path);
+/// let update = watcher.poll().unwrap().unwrap();
+/// assert_eq!(update.changed, ["a", "b"]);
+///
+/// // Unchanged file
+/// assert!(watcher.poll().unwrap().is_none());
+///
+/// save_matfile_v7(path, matfile!(a: matvar!(1.0), b: matvar!([2.0, 3.0])), false).unwrap();
+/// let update = watcher.poll().unwrap().unwrap();
+/// assert_eq!(update.changed, ["b"]);
+/// assert_eq!(update.matfile["b"], matvar!([2.0, 3.0]));
+/// # let _ = std::fs::remove_file(path);
+/// ```
+#[derive(Debug, Clone)]
+pub struct MatFileWatcher {
+    path: String,
+    options: LoadOptions,
+    stamp: Option<Stamp>,
+    hashes: IndexMap<String, u64>,
+}
+
+/// State of the file when it was last loaded or compared
+#[derive(Debug, Clone)]
+struct Stamp {
+    modified: Option<SystemTime>,
+    len: u64,
+    /// Hash of the content
+    hash: u64,
+    /// Time before the content was read
+    checked: SystemTime,
+}
+
+/// Resolution of modification times on the coarsest common file systems, FAT's two seconds
+const MODIFIED_RESOLUTION: Duration = Duration::from_secs(2);
+
+impl Stamp {
+    /// Whether the file is unchanged, because it has the same stamp and was modified long enough
+    /// before `checked`, that a later rewrite changes its modification time
+    fn unchanged(&self, modified: Option<SystemTime>, len: u64) -> bool {
+        self.modified == modified
+            && self.len == len
+            && modified.is_some_and(|m| m + MODIFIED_RESOLUTION <= self.checked)
+    }
+}
+
+impl MatFileWatcher {
+    ///
+    /// Watch the MAT-file at `path`.
+    ///
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            options: LoadOptions::new(),
+            stamp: None,
+            hashes: IndexMap::new(),
+        }
+    }
+
+    ///
+    /// Load the file with `options`.
+    ///
+    pub fn options(mut self, options: LoadOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    ///
+    /// Path of the watched file.
+    ///
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    ///
+    /// Load the file, if it was modified since the last successful load.
+    ///
+    /// Returns `None`, if the file is unchanged. Fails with the errors of
+    /// [`crate::load_matfile_with_options`], e.g. if the file is missing or only partly written.
+    /// The file is loaded again by the next poll in that case.
+    ///
+    pub fn poll(&mut self) -> Result<Option<MatFileUpdate>, MatrwError> {
+        let meta = fs::metadata(&self.path)?;
+        let (modified, len) = (meta.modified().ok(), meta.len());
+        if self.stamp.as_ref().is_some_and(|s| s.unchanged(modified, len)) {
+            return Ok(None);
+        }
+
+        let checked = SystemTime::now();
+        let data = fs::read(&self.path)?;
+        let mut hasher = DefaultHasher::new();
+        data.hash(&mut hasher);
+        let stamp = Stamp {
+            modified,
+            len,
+            hash: hasher.finish(),
+            checked,
+        };
+        if self.stamp.as_ref().is_some_and(|s| s.hash == stamp.hash) {
+            self.stamp = Some(stamp);
+            return Ok(None);
+        }
+
+        let matfile = load_matfile_from_u8_with_options(&data, &self.options)?;
+        let hashes: IndexMap<String, u64> = matfile
+            .iter()
+            .map(|(name, var)| (name.clone(), var.content_hash()))
+            .collect();
+
+        let changed = hashes
+            .iter()
+            .filter(|(name, hash)| self.hashes.get(*name) != Some(hash))
+            .map(|(name, _)| name.clone())
+            .collect();
+        let removed = self
+            .hashes
+            .keys()
+            .filter(|name| !hashes.contains_key(*name))
+            .cloned()
+            .collect();
+
+        self.stamp = Some(stamp);
+        self.hashes = hashes;
+        Ok(Some(MatFileUpdate {
+            matfile,
+            changed,
+            removed,
+        }))
+    }
+
+    ///
+    /// Poll the file every `interval` and pass each update to `on_update`, until it returns
+    /// `false`.
+    ///
+    /// Files which can't be loaded, e.g. because they are being written, are retried with the
+    /// next poll. Updates without changed or removed variables are passed on as well, because
+    /// the file was rewritten.
+    ///
+    pub fn watch<F>(&mut self, interval: Duration, mut on_update: F)
+    where
+        F: FnMut(MatFileUpdate) -> bool,
+    {
+        loop {
+            if let Ok(Some(update)) = self.poll()
+                && !on_update(update)
+            {
+                return;
+            }
+            thread::sleep(interval);
+        }
+    }
+
+    /// Load the file with the next poll, even if it is unchanged.
+    pub fn forget_modification(&mut self) {
+        self.stamp = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{matfile, matvar, save_matfile_v7};
+
+    #[test]
+    fn poll_changes() {
+        let path = std::env::temp_dir().join(format!("matrw_watcher_{}.mat", std::process::id()));
+        let path = path.to_str().unwrap();
+        let _ = fs::remove_file(path);
+
+        let mut watcher = MatFileWatcher::new(path);
+        assert!(matches!(watcher.poll(), Err(MatrwError::IoError(_))));
+
+        save_matfile_v7(path, matfile!(a: matvar!(1.0), b: matvar!("text")), false).unwrap();
+        let update = watcher.poll().unwrap().unwrap();
+        assert_eq!(update.changed, ["a", "b"]);
+        assert!(update.removed.is_empty());
+        assert!(watcher.poll().unwrap().is_none());
+
+        // Rewritten with the same size right after loading
+        save_matfile_v7(path, matfile!(a: matvar!(2.0), b: matvar!("text")), false).unwrap();
+        let update = watcher.poll().unwrap().unwrap();
+        assert_eq!(update.changed, ["a"]);
+        assert!(watcher.poll().unwrap().is_none());
+
+        // Partly written files are loaded again with the next poll
+        fs::write(path, b"MATLAB 5.0").unwrap();
+        assert!(watcher.poll().is_err());
+        save_matfile_v7(path, matfile!(a: matvar!(0.5), c: matvar!("text")), true).unwrap();
+        let update = watcher.poll().unwrap().unwrap();
+        assert_eq!(update.changed, ["a", "c"]);
+        assert_eq!(update.removed, ["b"]);
+
+        // Same content rewritten
+        watcher.forget_modification();
+        let update = watcher.poll().unwrap().unwrap();
+        assert!(update.changed.is_empty() && update.removed.is_empty());
+
+        let mut updates = 0;
+        watcher.forget_modification();
+        watcher.watch(Duration::from_millis(1), |update| {
+            updates += 1;
+            assert_eq!(update.matfile["a"], matvar!(0.5));
+            false
+        });
+        assert_eq!(updates, 1);
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,460 @@
+//! Incremental writing of MAT-files.
+//!
+//! [`MatFileWriter`] writes one variable at a time, so a file can be produced without holding all
+//! of its variables in memory, and allows to write custom data elements.
+
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+
+use binrw::io::{Cursor, Seek, Write};
+use binrw::{BinWrite, Endian};
+
+use crate::interface::error::MatrwError;
+use crate::interface::fileio::{SaveOptions, prepare_variable};
+use crate::interface::hash::sha256;
+use crate::interface::helper::invalid_variable_name;
+use crate::interface::metadata::METADATA_VARIABLE;
+use crate::interface::variable::MatVariable;
+use crate::parser::header::{MatFileHeader, MatFileVerFlag};
+use crate::parser::v7::flags::MatFileDataTypes;
+use crate::parser::v7::types::numeric_array::NumericArray7;
+use crate::parser::v7::types::subelements::tag::{TAG_SIZE, element_size, padding};
+use crate::parser::v7::variable7::MatVariable7;
+use crate::parser::v7::zlib::{self, Segment};
+
+/// Bytes of data after the name, from which [`SaveOptions::deduplicate`] reuses compressed data.
+/// Smaller data is compressed quickly, and better together with the name.
+const DEDUPLICATE_MIN: usize = 1024;
+
+/// Writer of a version 7 MAT-file, variable by variable.
+///
+/// The header is written on construction, each variable when it is passed to
+/// [`MatFileWriter::write_variable`]. The [`SaveOptions`] apply to every variable, metadata set
+/// with [`SaveOptions::metadata`] is written by [`MatFileWriter::finish`]. Subsystem data is not
+/// written.
+///
+/// Example
+/// ```
+/// use matrw::{MatFileWriter, SaveOptions, load_matfile_from_u8, matvar};
+/// use std::io::Cursor;
+///
+/// let mut writer = MatFileWriter::new(Cursor::new(Vec::new()), &SaveOptions::new().compress(true)).unwrap();
+/// writer.write_variable("a", matvar!(1.0)).unwrap();
+/// writer.write_variable("b", matvar!("text")).unwrap();
+/// let data = writer.finish().unwrap().into_inner();
+///
+/// let matfile = load_matfile_from_u8(&data).unwrap();
+/// assert_eq!(matfile["b"], matvar!("text"));
+/// ```
+pub struct MatFileWriter<W: Write + Seek> {
+    writer: W,
+    endian: Endian,
+    options: SaveOptions,
+    /// Compressed data after the names of the written variables by length and SHA-256 digest of
+    /// the uncompressed data, see [`SaveOptions::deduplicate`]
+    segments: HashMap<(usize, [u8; 32]), Segment>,
+}
+
+impl<W: Write + Seek> MatFileWriter<W> {
+    ///
+    /// Start a MAT-file in `writer` and write its header.
+    ///
+    pub fn new(writer: W, options: &SaveOptions) -> Result<Self, MatrwError> {
+        let header = MatFileHeader::new(MatFileVerFlag::V7);
+        Self::with_header(writer, options, header)
+    }
+
+    /// Start a MAT-file with `header`, which determines the byte order.
+    pub(crate) fn with_header(
+        mut writer: W,
+        options: &SaveOptions,
+        header: MatFileHeader,
+    ) -> Result<Self, MatrwError> {
+        let endian = header.matfile_endian;
+        header.write_options(&mut writer, endian, ())?;
+
+        Ok(Self {
+            writer,
+            endian,
+            options: options.clone(),
+            segments: HashMap::new(),
+        })
+    }
+
+    ///
+    /// Write the variable `var` called `name`.
+    ///
+    /// Fails with [`MatrwError::TypeConstruction`], if `name` is not a valid MATLAB variable name,
+    /// see [`crate::MatFile::insert`], or the variable exceeds the maximum element size.
+    ///
+    pub fn write_variable(&mut self, name: &str, var: MatVariable) -> Result<(), MatrwError> {
+        if let Some(err) = invalid_variable_name(name) {
+            return Err(MatrwError::TypeConstruction(err));
+        }
+        self.write_variable_unchecked(name, var)
+    }
+
+    /// Write a variable without validating its name, for reserved names like the metadata
+    /// variable.
+    pub(crate) fn write_variable_unchecked(
+        &mut self,
+        name: &str,
+        var: MatVariable,
+    ) -> Result<(), MatrwError> {
+        let var = prepare_variable(name, var, &self.options)?;
+        if self.options.is_compress() {
+            let mut raw = Cursor::new(Vec::new());
+            write_element(&mut raw, self.endian, name, var)?;
+            self.write_compressed(name, raw.into_inner())
+        } else {
+            write_element(&mut self.writer, self.endian, name, var)
+        }
+    }
+
+    /// Write the top-level element `element`, as encoded by another writer with the same options
+    /// and byte order.
+    pub(crate) fn write_encoded(&mut self, element: &[u8]) -> Result<(), MatrwError> {
+        self.writer.write_all(element)?;
+        Ok(())
+    }
+
+    /// Write the uncompressed element `raw` of the variable `name` in a compressed element.
+    ///
+    /// With [`SaveOptions::compress_if_smaller`], `raw` is written as is, if compression doesn't
+    /// make it smaller.
+    fn write_compressed(&mut self, name: &str, raw: Vec<u8>) -> Result<(), MatrwError> {
+        let compressed = match header_size(&raw, self.endian) {
+            Some(split) if self.options.is_deduplicate() && raw.len() - split >= DEDUPLICATE_MIN => {
+                self.compress_deduplicated(&raw, split)?
+            }
+            _ => zlib::compress(&raw)?,
+        };
+        if self.options.is_compress_if_smaller() && compressed.len() + TAG_SIZE as usize >= raw.len() {
+            self.writer.write_all(&raw)?;
+            return Ok(());
+        }
+        let size = u32::try_from(compressed.len()).map_err(|_| {
+            MatrwError::TypeConstruction(format!(
+                "Variable '{}' exceeds the maximum MAT-file element size of 4 GiB.",
+                name
+            ))
+        })?;
+        (MatFileDataTypes::MiCOMPRESSED as u32).write_options(&mut self.writer, self.endian, ())?;
+        size.write_options(&mut self.writer, self.endian, ())?;
+        self.writer.write_all(&compressed)?;
+        Ok(())
+    }
+
+    /// Compress the element `raw`, whose first `split` bytes hold tag, flags, dimensions and name,
+    /// reusing the compressed data after them from identical variables.
+    fn compress_deduplicated(&mut self, raw: &[u8], split: usize) -> Result<Vec<u8>, MatrwError> {
+        let (head, data) = raw.split_at(split);
+        let segment = match self.segments.entry((data.len(), sha256(data))) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(zlib::compress_segment(data, zlib::DEFAULT_LEVEL)?),
+        };
+        let head = zlib::stored_segment(head);
+        Ok(zlib::join_segments(&[&head, segment]))
+    }
+
+    ///
+    /// Write the parser element `element` unchanged, see [`MatVariable::to_parser_v7`].
+    ///
+    /// The element keeps its name, classes and storage, only [`SaveOptions::compress`] applies.
+    /// Its name is not validated.
+    ///
+    /// Example
+    /// ```
+    /// use matrw::{MatFileWriter, SaveOptions, load_matfile_from_u8, matvar};
+    /// use std::io::Cursor;
+    ///
+    /// let mut element = matvar!([1.0, 2.0]).to_parser_v7().unwrap();
+    /// element.set_name("x");
+    ///
+    /// let mut writer = MatFileWriter::new(Cursor::new(Vec::new()), &SaveOptions::new()).unwrap();
+    /// writer.write_parser_v7(&element).unwrap();
+    /// let data = writer.finish().unwrap().into_inner();
+    ///
+    /// assert_eq!(load_matfile_from_u8(&data).unwrap()["x"], matvar!([1.0, 2.0]));
+    /// ```
+    ///
+    pub fn write_parser_v7(&mut self, element: &MatVariable7) -> Result<(), MatrwError> {
+        if self.options.is_compress() {
+            let mut raw = Cursor::new(Vec::new());
+            element.write_options(&mut raw, self.endian, ())?;
+            self.write_compressed(&element.name(), raw.into_inner())
+        } else {
+            element.write_options(&mut self.writer, self.endian, ())?;
+            Ok(())
+        }
+    }
+
+    ///
+    /// Write a top-level data element with the type tag `data_type` and the content `data`.
+    ///
+    /// The element is written in the normal data element format: the tag, the number of bytes of
+    /// `data` and `data`, padded to a multiple of 8 bytes. `data` is written unchanged, in
+    /// particular it must already be in the byte order of the file, which is the native one.
+    ///
+    /// This allows to write vendor-specific extensions and constructs matrw does not support.
+    /// matrw skips elements it cannot read, see [`crate::MatFile::warnings`]. MATLAB may refuse
+    /// to load files with unknown elements.
+    ///
+    /// Example
+    /// ```
+    /// use matrw::{MatFileWriter, SaveOptions, load_matfile_from_u8, matvar};
+    /// use std::io::Cursor;
+    ///
+    /// let mut writer = MatFileWriter::new(Cursor::new(Vec::new()), &SaveOptions::new()).unwrap();
+    /// writer.write_variable("a", matvar!(1.0)).unwrap();
+    /// writer.write_raw_element(0x4242, b"vendor data").unwrap();
+    /// let data = writer.finish().unwrap().into_inner();
+    ///
+    /// let matfile = load_matfile_from_u8(&data).unwrap();
+    /// assert_eq!(matfile["a"], matvar!(1.0));
+    /// assert_eq!(matfile.warnings().len(), 1);
+    /// ```
+    ///
+    pub fn write_raw_element(&mut self, data_type: u32, data: &[u8]) -> Result<(), MatrwError> {
+        let size = u32::try_from(data.len()).map_err(|_| {
+            MatrwError::TypeConstruction(
+                "Data element exceeds the maximum element size of 4 GiB.".to_string(),
+            )
+        })?;
+
+        data_type.write_options(&mut self.writer, self.endian, ())?;
+        size.write_options(&mut self.writer, self.endian, ())?;
+        self.writer.write_all(data)?;
+        self.writer.write_all(&[0; 8][..padding(size, false) as usize])?;
+        Ok(())
+    }
+
+    ///
+    /// Write the metadata, if set in the options, flush and return the underlying writer.
+    ///
+    pub fn finish(mut self) -> Result<W, MatrwError> {
+        if let Some(metadata) = self.options.take_metadata() {
+            self.write_variable_unchecked(METADATA_VARIABLE, metadata.to_matvar())?;
+        }
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Bytes of tag, array flags, dimensions and name at the start of the array element `raw`,
+/// `None` for other elements.
+fn header_size(raw: &[u8], endian: Endian) -> Option<usize> {
+    let bytes = |pos: usize, n: usize| raw.get(pos..pos + n);
+    let number = |pos: usize, n: usize| {
+        let bytes = bytes(pos, n)?;
+        let fold = |v: u32, &b: &u8| (v << 8) | b as u32;
+        Some(match endian {
+            Endian::Little => bytes.iter().rev().fold(0, fold),
+            Endian::Big => bytes.iter().fold(0, fold),
+        })
+    };
+    // Size of the subelement at `pos`. Data types take the first two bytes of a tag, small
+    // elements have their number of bytes in the other two.
+    let subelement = |pos: usize| match number(pos + 2, 2)? {
+        0 => Some(element_size(number(pos + 4, 4)?, false) as usize),
+        size => Some(element_size(size, true) as usize),
+    };
+
+    if bytes(0, 1)? != [MatFileDataTypes::MiMATRIX as u8] {
+        return None;
+    }
+    let dims = TAG_SIZE as usize + element_size(8, false) as usize;
+    let name = dims + subelement(dims)?;
+    let end = name + subelement(name)?;
+    (end <= raw.len()).then_some(end)
+}
+
+/// Write the uncompressed element of `var` called `name`, scalars without building the
+/// general element, see [`NumericArray7::write_scalar`].
+fn write_element<W: Write + Seek>(
+    writer: &mut W,
+    endian: Endian,
+    name: &str,
+    var: MatVariable,
+) -> Result<(), MatrwError> {
+    match var {
+        MatVariable::NumericArray(arr) if NumericArray7::is_scalar(&arr) => {
+            NumericArray7::write_scalar(arr, name, writer, endian)?;
+        }
+        var => {
+            let mut var7 = MatVariable7::from(var);
+            var7.set_name(name);
+            var7.write_options(writer, endian, ())?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Metadata, load_matfile_from_u8, matvar};
+    use binrw::io::Cursor;
+
+    #[test]
+    fn scalars_match_general_elements() {
+        use crate::parser::v7::types::subelements::array_numeric_data::array_data::DoubleStorage;
+        use crate::{MatlabType, NumericArray};
+
+        let scalar = |value: MatlabType| NumericArray::new(vec![1, 1], value, None).unwrap();
+        let mut scalars = vec![
+            scalar(MatlabType::U8(vec![7])),
+            scalar(MatlabType::I8(vec![-7])),
+            scalar(MatlabType::U16(vec![700])),
+            scalar(MatlabType::I16(vec![-700])),
+            scalar(MatlabType::U32(vec![70000])),
+            scalar(MatlabType::I32(vec![-70000])),
+            scalar(MatlabType::U64(vec![u64::MAX])),
+            scalar(MatlabType::I64(vec![i64::MIN])),
+            scalar(MatlabType::F32(vec![0.5])),
+            scalar(MatlabType::BOOL(vec![true])),
+        ];
+        for x in [
+            0.0,
+            42.0,
+            -3.0,
+            300.0,
+            -70000.0,
+            1e10,
+            0.37,
+            f64::NAN,
+            f64::INFINITY,
+        ] {
+            scalars.push(scalar(MatlabType::F64(vec![x])));
+            let mut arr = scalar(MatlabType::F64(vec![x]));
+            arr.double_storage = DoubleStorage {
+                int64: true,
+                single: true,
+            };
+            scalars.push(arr);
+        }
+
+        for arr in scalars {
+            assert!(NumericArray7::is_scalar(&arr));
+            for name in ["", "a", "abcd", "abcde", "parameter_with_a_long_name"] {
+                let mut fast = Cursor::new(Vec::new());
+                NumericArray7::write_scalar(arr.clone(), name, &mut fast, Endian::Little).unwrap();
+
+                let mut general = Cursor::new(Vec::new());
+                let mut var7 = MatVariable7::from(MatVariable::NumericArray(arr.clone()));
+                var7.set_name(name);
+                var7.write_options(&mut general, Endian::Little, ()).unwrap();
+
+                assert_eq!(fast.into_inner(), general.into_inner(), "{arr:?} {name}");
+            }
+        }
+
+        assert!(!NumericArray7::is_scalar(&NumericArray::from("a")));
+        let row = NumericArray::new(vec![1, 2], MatlabType::F64(vec![1.0, 2.0]), None).unwrap();
+        assert!(!NumericArray7::is_scalar(&row));
+    }
+
+    #[test]
+    fn write_variables_and_raw_elements() {
+        let options = SaveOptions::new().metadata(Metadata::new("tool", "1.0"));
+        let mut writer = MatFileWriter::new(Cursor::new(Vec::new()), &options).unwrap();
+        writer.write_variable("a", matvar!([1.0, 2.0])).unwrap();
+        assert!(writer.write_variable("1a", matvar!(1.0)).is_err());
+
+        // Unpadded data is padded to 8 bytes
+        writer.write_raw_element(0x42, b"abc").unwrap();
+        writer.write_variable("b", matvar!({ c: "text" })).unwrap();
+        let data = writer.finish().unwrap().into_inner();
+        assert_eq!(data.len() % 8, 0);
+
+        let matfile = load_matfile_from_u8(&data).unwrap();
+        assert_eq!(matfile["a"], matvar!([1.0, 2.0]));
+        assert_eq!(matfile["b"]["c"], matvar!("text"));
+        assert_eq!(matfile.metadata().map(|m| m.tool), Some("tool".to_string()));
+        assert_eq!(matfile.warnings().len(), 1);
+    }
+
+    #[test]
+    fn deduplicate() {
+        use crate::{MatFile, save_matfile_v7_to_u8_with_options};
+
+        let table = matvar!(vec![0.5; 2000]);
+        let vars = [
+            table.clone(),
+            matvar!(1.0),
+            matvar!("text"),
+            matvar!({ a: [1u16, 2u16], c: ["x", "yz"] }),
+            matvar!([{ a: 1.0 }, { a: 2.0 }]),
+            MatVariable::Null,
+        ];
+        let matfile = || {
+            let mut matfile = MatFile::new();
+            for (i, var) in vars.iter().enumerate() {
+                matfile.insert(&format!("v{i}"), var.clone());
+                matfile.insert(&format!("copy_with_a_long_name{i}"), var.clone());
+            }
+            matfile.insert("other", matvar!(vec![1.5; 2000]));
+            matfile
+        };
+
+        let save = |options: &SaveOptions| save_matfile_v7_to_u8_with_options(matfile(), options).unwrap();
+        let compressed = SaveOptions::new().compress(true);
+        let deduplicated = save(&compressed.clone().deduplicate(true));
+        let loaded = load_matfile_from_u8(&deduplicated).unwrap();
+        for (i, var) in vars.iter().enumerate() {
+            let var = if var.is_empty_value() {
+                &MatVariable::empty_value()
+            } else {
+                var
+            };
+            assert_eq!(&loaded[format!("v{i}").as_str()], var);
+            assert_eq!(&loaded[format!("copy_with_a_long_name{i}").as_str()], var);
+        }
+        assert_eq!(loaded["other"], matvar!(vec![1.5; 2000]));
+        if cfg!(feature = "deflate") {
+            // Names and tags of the large variables are stored uncompressed
+            assert!(deduplicated.len() < save(&compressed).len() + 3 * 64);
+        }
+
+        // Without compression the option has no effect
+        let plain = save(&SaveOptions::new());
+        assert_eq!(save(&SaveOptions::new().deduplicate(true)), plain);
+
+        let mut raw = Cursor::new(Vec::new());
+        write_element(&mut raw, Endian::Big, "abcde", table).unwrap();
+        assert_eq!(header_size(raw.get_ref(), Endian::Big), Some(8 + 16 + 16 + 16));
+        assert_eq!(header_size(&raw.get_ref()[..40], Endian::Big), None);
+        assert_eq!(header_size(&[0; 64], Endian::Big), None);
+    }
+
+    #[test]
+    fn parser_elements() {
+        let var = matvar!({ a: [1u16, 2u16], c: "text" });
+        let mut element = var.to_parser_v7().unwrap();
+        element.set_name("s");
+
+        for options in [
+            SaveOptions::new(),
+            SaveOptions::new().compress(cfg!(feature = "deflate")),
+        ] {
+            let mut writer = MatFileWriter::new(Cursor::new(Vec::new()), &options).unwrap();
+            writer.write_parser_v7(&element).unwrap();
+            writer.write_variable("b", matvar!(1.0)).unwrap();
+            let data = writer.finish().unwrap().into_inner();
+
+            let matfile = load_matfile_from_u8(&data).unwrap();
+            assert_eq!(matfile["s"], var);
+            assert_eq!(matfile["b"], matvar!(1.0));
+        }
+
+        assert!(MatVariable::Null.to_parser_v7().is_err());
+        let empty = MatVariable::Unsupported.to_parser_v7().unwrap();
+        assert_eq!(MatVariable::from_parser_v7(&empty).unwrap().dim(), [0, 0]);
+        // Doubles held as integers in the element are read as doubles
+        let element = matvar!([1.0, 300.0]).to_parser_v7().unwrap();
+        assert_eq!(
+            MatVariable::from_parser_v7(&element).unwrap(),
+            matvar!([1.0, 300.0])
+        );
+    }
+}
@@ -0,0 +1,345 @@
+//! Export of MAT-files to spreadsheets.
+//!
+//! [`MatFile::export_xlsx`] writes each 2D variable to a worksheet of an Office Open XML
+//! workbook, which Excel and LibreOffice open:
+//! - real numeric arrays as numbers, with NaN and infinite values left empty,
+//! - logical arrays as `TRUE` and `FALSE`,
+//! - character arrays with one row of characters per cell of the first column.
+//!
+//! Fields of structures are written to sheets named by their path, e.g. `s.gain`. Complex,
+//! sparse, cell and structure arrays, empty arrays and arrays of more than two dimensions are
+//! skipped.
+//!
+//! Sheet names are cut to the 31 characters Excel allows, names taken already end with `~2`,
+//! `~3`, and so on. The workbook is stored without compression, in a ZIP archive of at most
+//! 4 GiB.
+
+use std::collections::HashSet;
+
+use crate::interface::error::MatrwError;
+use crate::interface::matfile::MatFile;
+use crate::interface::types::matlab_types::MatlabType;
+use crate::interface::types::numeric_array::NumericArray;
+use crate::interface::variable::MatVariable;
+use crate::interface::zip::ZipWriter;
+
+/// Maximum length of worksheet names
+const MAX_SHEET_NAME: usize = 31;
+/// Maximum number of rows of a worksheet
+const MAX_ROWS: usize = 1_048_576;
+/// Maximum number of columns of a worksheet
+const MAX_COLUMNS: usize = 16_384;
+
+const XML_HEADER: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#;
+const MAIN_NS: &str = "http://schemas.openxmlformats.org/spreadsheetml/2006/main";
+const REL_NS: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships";
+const PACKAGE_REL_NS: &str = "http://schemas.openxmlformats.org/package/2006/relationships";
+
+impl MatFile {
+    ///
+    /// Write the 2D variables to the worksheets of the spreadsheet `path`, see the
+    /// [module documentation](crate::interface::xlsx).
+    ///
+    /// Returns [`MatrwError::AccessError`], if no variable can be written to a worksheet, an
+    /// array exceeds the 1048576 rows or 16384 columns of a worksheet, or the workbook exceeds
+    /// the 4 GiB of a ZIP archive without the ZIP64 format.
+    ///
+    /// Example
+    /// ```
+    /// use matrw::{matfile, matvar};
+    ///
+    /// let matfile = matfile!(
+    ///     gain: matvar!([[1.0, 2.0], [3.0, 4.0]]),
+    ///     config: matvar!({ label: "motor", enabled: true }),
+    /// );
+    ///
+    /// let path = std::env::temp_dir().join("results.xlsx");
+    /// matfile.export_xlsx(path.to_str().unwrap()).unwrap();
+    /// # let _ = std::fs::remove_file(path);
+    /// ```
+    ///
+    pub fn export_xlsx(&self, path: &str) -> Result<(), MatrwError> {
+        std::fs::write(path, self.to_xlsx()?)?;
+        Ok(())
+    }
+
+    ///
+    /// Spreadsheet of the 2D variables, see [`MatFile::export_xlsx`].
+    ///
+    pub fn to_xlsx(&self) -> Result<Vec<u8>, MatrwError> {
+        let mut sheets = Vec::new();
+        for (name, var) in self.iter() {
+            collect_sheets(name, var, &mut sheets)?;
+        }
+        if sheets.is_empty() {
+            return Err(MatrwError::AccessError(
+                "No variable can be written to a worksheet".to_string(),
+            ));
+        }
+
+        let mut taken = HashSet::new();
+        let names: Vec<String> = sheets
+            .iter()
+            .map(|(path, _)| sheet_name(path, &mut taken))
+            .collect();
+
+        let mut zip = ZipWriter::default();
+        zip.add("[Content_Types].xml", content_types(sheets.len()).as_bytes())?;
+        zip.add(
+            "_rels/.rels",
+            relationships(&[(
+                "http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument",
+                "xl/workbook.xml".to_string(),
+            )])
+            .as_bytes(),
+        )?;
+        zip.add("xl/workbook.xml", workbook(&names).as_bytes())?;
+        let targets: Vec<_> = (1..=sheets.len())
+            .map(|n| {
+                (
+                    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet",
+                    format!("worksheets/sheet{n}.xml"),
+                )
+            })
+            .collect();
+        zip.add("xl/_rels/workbook.xml.rels", relationships(&targets).as_bytes())?;
+        for (n, (_, sheet)) in sheets.iter().enumerate() {
+            zip.add(&format!("xl/worksheets/sheet{}.xml", n + 1), sheet.as_bytes())?;
+        }
+        zip.finish()
+    }
+}
+
+/// Add the worksheets of `var` at `path` to `sheets`, as pairs of path and worksheet XML
+fn collect_sheets(
+    path: &str,
+    var: &MatVariable,
+    sheets: &mut Vec<(String, String)>,
+) -> Result<(), MatrwError> {
+    match var {
+        MatVariable::NumericArray(arr) if arr.dim.len() == 2 && !arr.is_empty() && !arr.is_complex() => {
+            let (rows, columns) = match arr.value {
+                MatlabType::UTF8(_) | MatlabType::UTF16(_) => (arr.dim[0], 1),
+                _ => (arr.dim[0], arr.dim[1]),
+            };
+            if rows > MAX_ROWS || columns > MAX_COLUMNS {
+                return Err(MatrwError::AccessError(format!(
+                    "Variable '{}' of size {:?} exceeds the {} rows and {} columns of a worksheet",
+                    path, arr.dim, MAX_ROWS, MAX_COLUMNS
+                )));
+            }
+            sheets.push((path.to_string(), worksheet(arr)));
+        }
+        MatVariable::Structure(s) => {
+            for (field, value) in s.value.iter() {
+                collect_sheets(&format!("{path}.{field}"), value, sheets)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Unique name of the sheet of `path`, `taken` holds the lowercase names used so far
+fn sheet_name(path: &str, taken: &mut HashSet<String>) -> String {
+    let cut = |len: usize| path.chars().take(len).collect::<String>();
+    let mut name = cut(MAX_SHEET_NAME);
+    let mut n = 2;
+    // Excel compares sheet names case-insensitively
+    while !taken.insert(name.to_lowercase()) {
+        let suffix = format!("~{n}");
+        name = cut(MAX_SHEET_NAME - suffix.len()) + &suffix;
+        n += 1;
+    }
+    name
+}
+
+/// Column letters of the zero-based column `j`: `A` to `Z`, `AA`, `AB`, and so on
+fn column_name(mut j: usize) -> String {
+    let mut letters = Vec::new();
+    loop {
+        letters.push(b'A' + (j % 26) as u8);
+        if j < 26 {
+            break;
+        }
+        j = j / 26 - 1;
+    }
+    letters.iter().rev().map(|&b| b as char).collect()
+}
+
+/// Text with the characters of XML markup escaped and the characters XML does not allow removed
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c < ' ' || c == '\u{FFFE}' || c == '\u{FFFF}' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Worksheet XML of the 2D array `arr`
+fn worksheet(arr: &NumericArray) -> String {
+    let rows = arr.dim[0];
+    let columns = arr.dim[1];
+    let mut xml = format!(r#"{XML_HEADER}<worksheet xmlns="{MAIN_NS}"><sheetData>"#);
+
+    match &arr.value {
+        MatlabType::UTF8(chars) | MatlabType::UTF16(chars) => {
+            for i in 0..rows {
+                let text: String = (0..columns).map(|j| chars[i + j * rows]).collect();
+                xml.push_str(&format!(
+                    r#"<row r="{r}"><c r="A{r}" t="inlineStr"><is><t xml:space="preserve">{}</t></is></c></row>"#,
+                    escape(&text),
+                    r = i + 1
+                ));
+            }
+        }
+        value => {
+            let logical = matches!(value, MatlabType::BOOL(_));
+            let values = value.to_f64();
+            let letters: Vec<String> = (0..columns).map(column_name).collect();
+            for i in 0..rows {
+                xml.push_str(&format!(r#"<row r="{}">"#, i + 1));
+                for (j, letter) in letters.iter().enumerate() {
+                    let x = values[i + j * rows];
+                    let cell = format!("{}{}", letter, i + 1);
+                    if logical {
+                        xml.push_str(&format!(r#"<c r="{cell}" t="b"><v>{x}</v></c>"#));
+                    } else if x.is_finite() {
+                        xml.push_str(&format!(r#"<c r="{cell}"><v>{x}</v></c>"#));
+                    }
+                }
+                xml.push_str("</row>");
+            }
+        }
+    }
+
+    xml.push_str("</sheetData></worksheet>");
+    xml
+}
+
+fn workbook(names: &[String]) -> String {
+    let mut xml = format!(r#"{XML_HEADER}<workbook xmlns="{MAIN_NS}" xmlns:r="{REL_NS}"><sheets>"#);
+    for (n, name) in names.iter().enumerate() {
+        xml.push_str(&format!(
+            r#"<sheet name="{}" sheetId="{id}" r:id="rId{id}"/>"#,
+            escape(name),
+            id = n + 1
+        ));
+    }
+    xml.push_str("</sheets></workbook>");
+    xml
+}
+
+/// Relationships to `targets`, pairs of relationship type and target, with the ids `rId1`,
+/// `rId2`, and so on
+fn relationships(targets: &[(&str, String)]) -> String {
+    let mut xml = format!(r#"{XML_HEADER}<Relationships xmlns="{PACKAGE_REL_NS}">"#);
+    for (n, (kind, target)) in targets.iter().enumerate() {
+        xml.push_str(&format!(
+            r#"<Relationship Id="rId{}" Type="{}" Target="{}"/>"#,
+            n + 1,
+            kind,
+            target
+        ));
+    }
+    xml.push_str("</Relationships>");
+    xml
+}
+
+fn content_types(sheets: usize) -> String {
+    let mut xml = format!(
+        concat!(
+            r#"{}<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">"#,
+            r#"<Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/>"#,
+            r#"<Default Extension="xml" ContentType="application/xml"/>"#,
+            r#"<Override PartName="/xl/workbook.xml" "#,
+            r#"ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/>"#,
+        ),
+        XML_HEADER
+    );
+    for n in 1..=sheets {
+        xml.push_str(&format!(
+            concat!(
+                r#"<Override PartName="/xl/worksheets/sheet{}.xml" "#,
+                r#"ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/>"#,
+            ),
+            n
+        ));
+    }
+    xml.push_str("</Types>");
+    xml
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interface::zip::read_member;
+    use crate::{matfile, matvar};
+
+    /// Content of the file `name` of the ZIP archive `zip`
+    fn unzip(zip: &[u8], name: &str) -> String {
+        String::from_utf8(read_member(std::io::Cursor::new(zip), name).unwrap()).unwrap()
+    }
+
+    fn array(dim: Vec<usize>, value: Vec<f64>) -> MatVariable {
+        MatVariable::NumericArray(NumericArray::new(dim, MatlabType::from(value), None).unwrap())
+    }
+
+    #[test]
+    fn export_xlsx() {
+        let matfile = matfile!(
+            m: array(vec![2, 2], vec![1.5, 3.0, f64::NAN, 4.0]),
+            flags: matvar!([true, false]),
+            s: matvar!({ label: "a<b", cube: array(vec![2, 2, 2], vec![1.0; 8]) }),
+            z: array(vec![0, 0], vec![]),
+        );
+        let xlsx = matfile.to_xlsx().unwrap();
+
+        let workbook = unzip(&xlsx, "xl/workbook.xml");
+        assert!(workbook.contains(r#"<sheet name="m" sheetId="1" r:id="rId1"/>"#));
+        assert!(workbook.contains(r#"<sheet name="flags" sheetId="2" r:id="rId2"/>"#));
+        assert!(workbook.contains(r#"<sheet name="s.label" sheetId="3" r:id="rId3"/>"#));
+        assert!(!workbook.contains("cube") && !workbook.contains(r#"name="z""#));
+        assert!(unzip(&xlsx, "[Content_Types].xml").contains("/xl/worksheets/sheet3.xml"));
+        assert!(unzip(&xlsx, "xl/_rels/workbook.xml.rels").contains(r#"Target="worksheets/sheet3.xml""#));
+
+        let m = unzip(&xlsx, "xl/worksheets/sheet1.xml");
+        assert!(m.contains(r#"<row r="1"><c r="A1"><v>1.5</v></c></row>"#));
+        assert!(m.contains(r#"<row r="2"><c r="A2"><v>3</v></c><c r="B2"><v>4</v></c></row>"#));
+        let flags = unzip(&xlsx, "xl/worksheets/sheet2.xml");
+        assert!(flags.contains(r#"<c r="A1" t="b"><v>1</v></c><c r="B1" t="b"><v>0</v></c>"#));
+        let label = unzip(&xlsx, "xl/worksheets/sheet3.xml");
+        assert!(label.contains(r#"<t xml:space="preserve">a&lt;b</t>"#));
+
+        let err = matfile!(c: matvar!({ z: array(vec![0, 0], vec![]) }))
+            .to_xlsx()
+            .unwrap_err();
+        assert_eq!(err.to_string(), "No variable can be written to a worksheet");
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(
+            [0, 25, 26, 27, 701, 702, 16383].map(column_name),
+            ["A", "Z", "AA", "AB", "ZZ", "AAA", "XFD"]
+        );
+
+        let mut taken = HashSet::new();
+        let long = "measurement.channel_one.voltage";
+        assert_eq!(sheet_name("a", &mut taken), "a");
+        assert_eq!(sheet_name("A", &mut taken), "A~2");
+        assert_eq!(sheet_name(&format!("{long}_raw"), &mut taken), long);
+        assert_eq!(
+            sheet_name(&format!("{long}_cal"), &mut taken),
+            "measurement.channel_one.volta~2"
+        );
+    }
+}
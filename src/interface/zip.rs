@@ -0,0 +1,524 @@
+//! ZIP archives.
+//!
+//! [`load_matfile_from_zip`] loads a MAT-file from a member of a ZIP archive without extracting
+//! it, e.g. from a bundle of exported MAT-files, and [`zip_members`] lists the members of an
+//! archive. Members stored without compression or compressed with deflate are read, the latter
+//! requires the feature `deflate`. Archives in the ZIP64 format are read, encrypted members are
+//! not.
+//!
+//! The member is decompressed into memory while it is read, since variables are located by
+//! seeking, like with [`crate::load_matfile_gz`]. The other members aren't read.
+
+use std::io::{self, Read, Seek, SeekFrom};
+
+use crate::interface::error::MatrwError;
+use crate::interface::fileio::load_matfile_from_u8;
+use crate::interface::matfile::MatFile;
+
+/// Signature of local file headers
+const LOCAL_HEADER: u32 = 0x0403_4b50;
+/// Signature of central directory entries
+const DIRECTORY_ENTRY: u32 = 0x0201_4b50;
+/// Signature of the end of central directory record
+const END_OF_DIRECTORY: u32 = 0x0605_4b50;
+/// Signature of the ZIP64 end of central directory record
+const ZIP64_END_OF_DIRECTORY: u32 = 0x0606_4b50;
+/// Signature of the ZIP64 end of central directory locator
+const ZIP64_LOCATOR: u32 = 0x0706_4b50;
+/// Size of the end of central directory record without comment
+const END_OF_DIRECTORY_SIZE: usize = 22;
+
+///
+/// Load the MAT-file `member` of the ZIP archive `archive`, e.g. an open `.zip` file, see the
+/// [module documentation](crate::interface::zip).
+///
+/// Returns
+/// - [`MatrwError::IoError`], if `archive` cannot be read or is no valid ZIP archive,
+/// - [`MatrwError::AccessError`], if the archive has no member `member` or its compression
+///   method is not supported,
+/// - the errors of [`load_matfile_from_u8`], if the member is no valid MAT-file.
+///
+/// Requires the feature `zip`.
+///
+/// Example
+/// ```no_run
+/// use matrw::{load_matfile_from_zip, zip_members};
+/// use std::fs::File;
+///
+/// let mut archive = File::open("export.zip").unwrap();
+/// for member in zip_members(&mut archive).unwrap() {
+///     if member.ends_with(".mat") {
+///         let matfile = load_matfile_from_zip(&mut archive, &member).unwrap();
+///         println!("{member}: {} variables", matfile.iter().count());
+///     }
+/// }
+/// ```
+///
+pub fn load_matfile_from_zip<R: Read + Seek>(archive: R, member: &str) -> Result<MatFile, MatrwError> {
+    load_matfile_from_u8(&read_member(archive, member)?)
+}
+
+///
+/// Names of the members of the ZIP archive `archive`, in the order of its central directory,
+/// see [`load_matfile_from_zip`].
+///
+/// Requires the feature `zip`.
+///
+pub fn zip_members<R: Read + Seek>(archive: R) -> Result<Vec<String>, MatrwError> {
+    let mut archive = archive;
+    Ok(read_directory(&mut archive)?
+        .into_iter()
+        .map(|entry| entry.name)
+        .collect())
+}
+
+/// Member of an archive, as listed in the central directory
+struct Entry {
+    name: String,
+    flags: u16,
+    method: u16,
+    crc: u32,
+    compressed_size: u64,
+    size: u64,
+    /// Offset of the local file header
+    offset: u64,
+}
+
+fn invalid(msg: &str) -> MatrwError {
+    MatrwError::IoError(io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Invalid ZIP archive: {msg}"),
+    ))
+}
+
+fn u16_at(data: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([data[at], data[at + 1]])
+}
+
+fn u32_at(data: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(data[at..at + 4].try_into().unwrap())
+}
+
+fn u64_at(data: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(data[at..at + 8].try_into().unwrap())
+}
+
+/// `len` bytes at `offset` of `archive`
+fn read_at<R: Read + Seek>(archive: &mut R, offset: u64, len: usize) -> Result<Vec<u8>, MatrwError> {
+    archive.seek(SeekFrom::Start(offset))?;
+    let mut data = Vec::new();
+    archive.take(len as u64).read_to_end(&mut data)?;
+    if data.len() < len {
+        return Err(invalid("unexpected end of file"));
+    }
+    Ok(data)
+}
+
+/// Entries of the central directory of `archive`
+fn read_directory<R: Read + Seek>(archive: &mut R) -> Result<Vec<Entry>, MatrwError> {
+    let len = archive.seek(SeekFrom::End(0))?;
+
+    // The record ends the archive, followed by a comment of at most 65535 bytes
+    let tail_len = len.min((END_OF_DIRECTORY_SIZE + u16::MAX as usize) as u64);
+    let tail = read_at(archive, len - tail_len, tail_len as usize)?;
+    let end = (0..=tail.len().saturating_sub(END_OF_DIRECTORY_SIZE))
+        .rev()
+        .find(|&at| tail.len() >= at + END_OF_DIRECTORY_SIZE && u32_at(&tail, at) == END_OF_DIRECTORY)
+        .ok_or_else(|| invalid("end of central directory not found"))?;
+    let record = &tail[end..];
+
+    let mut count = u16_at(record, 10) as u64;
+    let mut size = u32_at(record, 12) as u64;
+    let mut offset = u32_at(record, 16) as u64;
+
+    // Values which don't fit are in the ZIP64 record, which the locator before the record points to
+    if count == u16::MAX as u64 || size == u32::MAX as u64 || offset == u32::MAX as u64 {
+        let record_offset = len - tail_len + end as u64;
+        let locator = match record_offset.checked_sub(20) {
+            Some(at) => read_at(archive, at, 20)?,
+            None => return Err(invalid("no ZIP64 locator")),
+        };
+        if u32_at(&locator, 0) != ZIP64_LOCATOR {
+            return Err(invalid("no ZIP64 locator"));
+        }
+        let record = read_at(archive, u64_at(&locator, 8), 56)?;
+        if u32_at(&record, 0) != ZIP64_END_OF_DIRECTORY {
+            return Err(invalid("no ZIP64 end of central directory"));
+        }
+        count = u64_at(&record, 32);
+        size = u64_at(&record, 40);
+        offset = u64_at(&record, 48);
+    }
+
+    if offset.checked_add(size).is_none_or(|end| end > len) {
+        return Err(invalid("central directory exceeds the file"));
+    }
+    let directory = read_at(archive, offset, size as usize)?;
+
+    let mut entries = Vec::new();
+    let mut pos = 0;
+    while (entries.len() as u64) < count {
+        if directory.len() < pos + 46 || u32_at(&directory, pos) != DIRECTORY_ENTRY {
+            return Err(invalid("truncated central directory"));
+        }
+        let entry = &directory[pos..];
+        let name_len = u16_at(entry, 28) as usize;
+        let extra_len = u16_at(entry, 30) as usize;
+        let comment_len = u16_at(entry, 32) as usize;
+        if entry.len() < 46 + name_len + extra_len + comment_len {
+            return Err(invalid("truncated central directory"));
+        }
+
+        let mut size = u32_at(entry, 24) as u64;
+        let mut compressed_size = u32_at(entry, 20) as u64;
+        let mut local_offset = u32_at(entry, 42) as u64;
+
+        // The ZIP64 extra field holds the values which don't fit, in this order
+        let mut extra = &entry[46 + name_len..46 + name_len + extra_len];
+        while extra.len() >= 4 {
+            let (id, field_len) = (u16_at(extra, 0), u16_at(extra, 2) as usize);
+            let field = extra
+                .get(4..4 + field_len)
+                .ok_or_else(|| invalid("truncated extra field"))?;
+            if id == 0x0001 {
+                let mut values = field.chunks_exact(8).map(|v| u64_at(v, 0));
+                for value in [&mut size, &mut compressed_size, &mut local_offset] {
+                    if *value == u32::MAX as u64 {
+                        *value = values
+                            .next()
+                            .ok_or_else(|| invalid("truncated ZIP64 extra field"))?;
+                    }
+                }
+            }
+            extra = &extra[4 + field_len..];
+        }
+
+        entries.push(Entry {
+            name: String::from_utf8_lossy(&entry[46..46 + name_len]).into_owned(),
+            flags: u16_at(entry, 8),
+            method: u16_at(entry, 10),
+            crc: u32_at(entry, 16),
+            compressed_size,
+            size,
+            offset: local_offset,
+        });
+        pos += 46 + name_len + extra_len + comment_len;
+    }
+    Ok(entries)
+}
+
+/// Content of the member `name` of `archive`
+pub(crate) fn read_member<R: Read + Seek>(archive: R, name: &str) -> Result<Vec<u8>, MatrwError> {
+    let mut archive = archive;
+    let entry = read_directory(&mut archive)?
+        .into_iter()
+        .find(|entry| entry.name == name)
+        .ok_or_else(|| MatrwError::AccessError(format!("No member '{name}' in ZIP archive")))?;
+    if entry.flags & 1 != 0 {
+        return Err(MatrwError::AccessError(format!(
+            "Member '{name}' is encrypted, which is not supported"
+        )));
+    }
+
+    let header = read_at(&mut archive, entry.offset, 30)?;
+    if u32_at(&header, 0) != LOCAL_HEADER {
+        return Err(invalid("no local file header"));
+    }
+    let skip = u16_at(&header, 26) as i64 + u16_at(&header, 28) as i64;
+    archive.seek(SeekFrom::Current(skip))?;
+
+    // One byte more than declared is read to detect members longer than their size
+    let compressed = archive.take(entry.compressed_size);
+    let mut data = Vec::new();
+    match entry.method {
+        0 => compressed.take(entry.size + 1).read_to_end(&mut data)?,
+        #[cfg(feature = "deflate")]
+        8 => flate2::read::DeflateDecoder::new(compressed)
+            .take(entry.size + 1)
+            .read_to_end(&mut data)?,
+        #[cfg(not(feature = "deflate"))]
+        8 => {
+            return Err(MatrwError::AccessError(format!(
+                "Member '{name}' is compressed with deflate, which requires the feature `deflate`"
+            )));
+        }
+        method => {
+            return Err(MatrwError::AccessError(format!(
+                "Member '{name}' is compressed with the unsupported method {method}"
+            )));
+        }
+    };
+
+    if data.len() as u64 != entry.size {
+        return Err(invalid(&format!("member '{name}' doesn't match its size")));
+    }
+    if crc32(&data) != entry.crc {
+        return Err(invalid(&format!("member '{name}' doesn't match its CRC-32")));
+    }
+    Ok(data)
+}
+
+/// CRC-32 of ZIP files, polynomial 0xEDB88320 in reflected form
+pub(crate) fn crc32(data: &[u8]) -> u32 {
+    const TABLE: [u32; 256] = {
+        let mut table = [0u32; 256];
+        let mut n = 0;
+        while n < 256 {
+            let mut c = n as u32;
+            let mut k = 0;
+            while k < 8 {
+                c = if c & 1 != 0 {
+                    0xEDB8_8320 ^ (c >> 1)
+                } else {
+                    c >> 1
+                };
+                k += 1;
+            }
+            table[n] = c;
+            n += 1;
+        }
+        table
+    };
+
+    !data.iter().fold(!0u32, |crc, &b| {
+        TABLE[((crc ^ b as u32) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
+
+/// ZIP archive of uncompressed files, written by [`MatFile::export_xlsx`]
+#[cfg(any(feature = "xlsx", test))]
+#[derive(Default)]
+pub(crate) struct ZipWriter {
+    data: Vec<u8>,
+    /// Central directory entries
+    directory: Vec<u8>,
+    count: u16,
+}
+
+#[cfg(any(feature = "xlsx", test))]
+impl ZipWriter {
+    /// DOS date of 1980-01-01, the earliest date ZIP files can hold
+    const DATE: u16 = (1 << 5) | 1;
+
+    ///
+    /// Add the file `name` with `content`.
+    ///
+    /// Returns [`MatrwError::AccessError`] for archives, which would need the ZIP64 format: of
+    /// 65535 files and more, or files and archives of 4 GiB and more.
+    ///
+    pub(crate) fn add(&mut self, name: &str, content: &[u8]) -> Result<(), MatrwError> {
+        if self.count == u16::MAX - 1 {
+            return Err(MatrwError::AccessError(format!(
+                "ZIP archive exceeds {} files",
+                u16::MAX - 1
+            )));
+        }
+        let name_len = zip32(name.len(), u16::MAX as u64, "File name")? as u16;
+        let offset = zip32(self.data.len(), ZIP32_MAX, "ZIP archive")?;
+        let size = zip32(content.len(), ZIP32_MAX, "File")?;
+        let crc = crc32(content);
+
+        // Version 2.0, no flags, stored, time, date, CRC, sizes, name length, no extra field
+        let common = |out: &mut Vec<u8>| {
+            for x in [20u16, 0, 0, 0, Self::DATE] {
+                out.extend(x.to_le_bytes());
+            }
+            for x in [crc, size, size] {
+                out.extend(x.to_le_bytes());
+            }
+            out.extend(name_len.to_le_bytes());
+            out.extend(0u16.to_le_bytes());
+        };
+
+        self.data.extend(LOCAL_HEADER.to_le_bytes());
+        common(&mut self.data);
+        self.data.extend(name.as_bytes());
+        self.data.extend(content);
+
+        self.directory.extend(DIRECTORY_ENTRY.to_le_bytes());
+        self.directory.extend(20u16.to_le_bytes());
+        common(&mut self.directory);
+        // Comment length, disk, internal and external attributes, offset of the local header
+        for x in [0u16, 0, 0] {
+            self.directory.extend(x.to_le_bytes());
+        }
+        for x in [0u32, offset] {
+            self.directory.extend(x.to_le_bytes());
+        }
+        self.directory.extend(name.as_bytes());
+        self.count += 1;
+        Ok(())
+    }
+
+    /// Archive of the files added, fails like [`ZipWriter::add`]
+    pub(crate) fn finish(mut self) -> Result<Vec<u8>, MatrwError> {
+        let offset = zip32(self.data.len(), ZIP32_MAX, "ZIP archive")?;
+        let size = zip32(self.directory.len(), ZIP32_MAX, "ZIP archive")?;
+        zip32(self.data.len() + self.directory.len(), ZIP32_MAX, "ZIP archive")?;
+        self.data.append(&mut self.directory);
+
+        self.data.extend(END_OF_DIRECTORY.to_le_bytes());
+        for x in [0u16, 0, self.count, self.count] {
+            self.data.extend(x.to_le_bytes());
+        }
+        for x in [size, offset] {
+            self.data.extend(x.to_le_bytes());
+        }
+        self.data.extend(0u16.to_le_bytes());
+        Ok(self.data)
+    }
+}
+
+/// Largest size and offset of archives without ZIP64, which marks larger values with `u32::MAX`
+#[cfg(any(feature = "xlsx", test))]
+const ZIP32_MAX: u64 = u32::MAX as u64 - 1;
+
+/// `len` of `what`, if it is at most `max`
+#[cfg(any(feature = "xlsx", test))]
+fn zip32(len: usize, max: u64, what: &str) -> Result<u32, MatrwError> {
+    if len as u64 > max {
+        return Err(MatrwError::AccessError(format!(
+            "{what} exceeds {max} bytes, which requires the unsupported ZIP64 format"
+        )));
+    }
+    Ok(len as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{matfile, matvar, save_matfile_v7_to_u8};
+    use std::io::Cursor;
+
+    /// Archive of one member `name`, with `content` stored as `method`, in the ZIP64 format
+    fn zip64(name: &str, content: &[u8], method: u16, stored: &[u8]) -> Vec<u8> {
+        let mut zip = Vec::new();
+        let field = |out: &mut Vec<u8>, xs: &[u32]| xs.iter().for_each(|x| out.extend(x.to_le_bytes()));
+        let short = |out: &mut Vec<u8>, xs: &[u16]| xs.iter().for_each(|x| out.extend(x.to_le_bytes()));
+
+        field(&mut zip, &[LOCAL_HEADER]);
+        short(&mut zip, &[45, 0, method, 0, 0]);
+        field(
+            &mut zip,
+            &[crc32(content), stored.len() as u32, content.len() as u32],
+        );
+        short(&mut zip, &[name.len() as u16, 0]);
+        zip.extend(name.as_bytes());
+        zip.extend(stored);
+
+        // Sizes and offset only in the extra field
+        let directory = zip.len() as u64;
+        field(&mut zip, &[DIRECTORY_ENTRY]);
+        short(&mut zip, &[45, 45, 0, method, 0, 0]);
+        field(&mut zip, &[crc32(content), u32::MAX, u32::MAX]);
+        short(&mut zip, &[name.len() as u16, 28, 0, 0, 0]);
+        field(&mut zip, &[0, u32::MAX]);
+        zip.extend(name.as_bytes());
+        short(&mut zip, &[1, 24]);
+        for x in [content.len() as u64, stored.len() as u64, 0] {
+            zip.extend(x.to_le_bytes());
+        }
+        let directory_size = zip.len() as u64 - directory;
+
+        let record = zip.len() as u64;
+        field(&mut zip, &[ZIP64_END_OF_DIRECTORY]);
+        zip.extend(44u64.to_le_bytes());
+        short(&mut zip, &[45, 45]);
+        field(&mut zip, &[0, 0]);
+        for x in [1, 1, directory_size, directory] {
+            zip.extend(x.to_le_bytes());
+        }
+        field(&mut zip, &[ZIP64_LOCATOR, 0]);
+        zip.extend(record.to_le_bytes());
+        field(&mut zip, &[1]);
+
+        field(&mut zip, &[END_OF_DIRECTORY]);
+        short(&mut zip, &[0, 0, u16::MAX, u16::MAX]);
+        field(&mut zip, &[u32::MAX, u32::MAX]);
+        short(&mut zip, &[0]);
+        zip
+    }
+
+    #[test]
+    fn load_members() {
+        let run = |n: f64| save_matfile_v7_to_u8(matfile!(x: matvar!(n)), true).unwrap();
+        let mut writer = ZipWriter::default();
+        writer.add("readme.txt", b"Exported runs").unwrap();
+        writer.add("export/run1.mat", &run(1.0)).unwrap();
+        writer.add("export/run2.mat", &run(2.0)).unwrap();
+        let mut archive = Cursor::new(writer.finish().unwrap());
+
+        assert_eq!(
+            zip_members(&mut archive).unwrap(),
+            ["readme.txt", "export/run1.mat", "export/run2.mat"]
+        );
+        let matfile = load_matfile_from_zip(&mut archive, "export/run2.mat").unwrap();
+        assert_eq!(matfile["x"], matvar!(2.0));
+        assert_eq!(read_member(&mut archive, "readme.txt").unwrap(), b"Exported runs");
+
+        let err = load_matfile_from_zip(&mut archive, "run3.mat").unwrap_err();
+        assert_eq!(err.to_string(), "No member 'run3.mat' in ZIP archive");
+        assert!(load_matfile_from_zip(&mut archive, "readme.txt").is_err());
+
+        // Corrupted content
+        let mut data = archive.into_inner();
+        data[42] ^= 1;
+        let err = read_member(Cursor::new(data), "readme.txt").unwrap_err();
+        assert!(err.to_string().contains("doesn't match its CRC-32"), "{err}");
+        assert!(zip_members(Cursor::new(b"MATLAB 5.0".to_vec())).is_err());
+    }
+
+    #[test]
+    fn load_zip64_members() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+
+        let content = run_data();
+        let archive = zip64("run.mat", &content, 0, &content);
+        assert_eq!(zip_members(Cursor::new(&archive)).unwrap(), ["run.mat"]);
+        let matfile = load_matfile_from_zip(Cursor::new(&archive), "run.mat").unwrap();
+        assert_eq!(matfile["x"], matvar!([1.0, 2.0]));
+
+        let archive = zip64("run.mat", &content, 12, &content);
+        let err = load_matfile_from_zip(Cursor::new(&archive), "run.mat").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Member 'run.mat' is compressed with the unsupported method 12"
+        );
+    }
+
+    #[test]
+    fn writer_limits() {
+        let mut writer = ZipWriter::default();
+        for n in 0..u16::MAX - 1 {
+            writer.add(&n.to_string(), b"").unwrap();
+        }
+        let err = writer.add("last", b"").unwrap_err();
+        assert_eq!(err.to_string(), "ZIP archive exceeds 65534 files");
+        let archive = writer.finish().unwrap();
+        assert_eq!(zip_members(Cursor::new(archive)).unwrap().len(), 65534);
+
+        let err = ZipWriter::default().add(&"a".repeat(70_000), b"").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "File name exceeds 65535 bytes, which requires the unsupported ZIP64 format"
+        );
+    }
+
+    #[cfg(feature = "deflate")]
+    #[test]
+    fn load_deflated_members() {
+        use flate2::{Compression, write::DeflateEncoder};
+        use std::io::Write;
+
+        let content = run_data();
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&content).unwrap();
+        let archive = zip64("run.mat", &content, 8, &encoder.finish().unwrap());
+        let matfile = load_matfile_from_zip(Cursor::new(archive), "run.mat").unwrap();
+        assert_eq!(matfile["x"], matvar!([1.0, 2.0]));
+    }
+
+    fn run_data() -> Vec<u8> {
+        save_matfile_v7_to_u8(matfile!(x: matvar!([1.0, 2.0])), false).unwrap()
+    }
+}
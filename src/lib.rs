@@ -11,7 +11,7 @@
 //!
 //! MAT-files store structured numerical data in a binary format. This library offers a [serde_json](https://docs.rs/serde_json)-like interface for ergonomically reading and writing MAT-file data.
 //!
-//! Currently, matrw supports serialization and deserialization of version 7 MAT-files. The parser currently handles the following data types:
+//! Currently, matrw supports serialization and deserialization of version 7 MAT-files, including uncompressed `-v6` files, which can also be written with `save_matfile_v6`. The parser currently handles the following data types:
 //!
 //! - [x] numeric arrays
 //! - [x] structure arrays
@@ -204,15 +204,41 @@
 //! # let _ = std::fs::remove_file("test.mat");
 //! ```
 //!
+//! ## Array types
+//!
+//! The variants of [`MatVariable`] wrap the array types [`NumericArray`], [`SparseArray`],
+//! [`CellArray`], [`Structure`] and [`StructureArray`], with public fields. Data is stored in
+//! column-major order like in MATLAB, and one-dimensional dimensions are normalized to row
+//! vectors, see [`NumericArray`] for the invariants of the fields.
+//!
+//! ```
+//! use matrw::{MatlabType, MatVariable, NumericArray, OwnedIndex};
+//!
+//! // 2x3 matrix, column-major
+//! let m = NumericArray::new(vec![2, 3], MatlabType::from(vec![1.0, 4.0, 2.0, 5.0, 3.0, 6.0]), None).unwrap();
+//! let m = MatVariable::NumericArray(m);
+//!
+//! // Elements of numeric arrays are returned by value
+//! assert_eq!(m.elem([1, 2]).to_f64(), Some(6.0));
+//! ```
+//!
+//! ## Order of variables and fields
+//!
+//! Variables and structure fields keep their insertion order, or the order of the file they were
+//! read from, when iterated, printed or written. This is guaranteed, see
+//! [`MatFile#order`]; [`MatFile::sort_keys`] and [`MatVariable::sort_keys`] sort by name instead.
+//!
 //! # Typed Interface
 //!
-//! The typed interface is used, when the structural content of a MAT-file is *known* at compile time. It is provided using the [`serde`] framework.
+//! The typed interface is used, when the structural content of a MAT-file is *known* at compile time. It is provided using the [`serde`] framework and requires the feature `serde`.
 //!
 //! ## Writing MAT-files
 //!
 //! Types implementing [`serde::Serialize`] can be serialized into [`MatFile`], using the function [`to_matfile`].
 //!
 //! ```standalone_crate
+//! # #[cfg(feature = "serde")]
+//! # fn main() {
 //! use matrw::{save_matfile_v7, to_matfile};
 //! use serde::{Serialize};
 //!
@@ -246,6 +272,9 @@
 //! let _ = save_matfile_v7("test.mat", mat, false);
 //!
 //! # let _ = std::fs::remove_file("test.mat");
+//! # }
+//! # #[cfg(not(feature = "serde"))]
+//! # fn main() {}
 //! ```
 //!
 //! ## Reading MAT-files
@@ -253,6 +282,8 @@
 //! [`MatFile`] can be deserialized into a custom type implementing [`serde::Deserialize`], using the function [`from_matfile`].
 //!
 //! ```standalone_crate
+//! # #[cfg(feature = "serde")]
+//! # fn main() {
 //! use matrw::{save_matfile_v7, to_matfile, load_matfile, from_matfile};
 //! use serde::{Deserialize};
 //! # use serde::{Serialize};
@@ -299,9 +330,59 @@
 //! assert_eq!(mat.h.f1, 42.);
 //!
 //! # let _ = std::fs::remove_file("test.mat");
+//! # }
+//! # #[cfg(not(feature = "serde"))]
+//! # fn main() {}
+//! ```
+//!
+//! ## Text formats
+//!
+//! [`MatVariable`] implements [`serde::Serialize`] and [`serde::Deserialize`] itself, so variables
+//! of unknown structure convert to and from self-describing formats like JSON, TOML or YAML. This
+//! suits configuration-style MAT-files of nested structures, scalars and strings. Numbers read
+//! from text are double scalars, like with MATLAB's `jsondecode`. The features `toml` and `yaml`
+//! add [`to_toml_string`], [`from_toml_str`], [`to_yaml_string`] and [`from_yaml_str`], which
+//! write and read these formats without further crates.
+//!
+//! ```
+//! # #[cfg(feature = "serde")]
+//! # fn main() {
+//! use matrw::{MatVariable, matvar};
+//!
+//! let config: MatVariable = serde_json::from_str(r#"{"gain": 2, "name": "motor"}"#).unwrap();
+//! assert_eq!(config, matvar!({ gain: 2.0, name: "motor" }));
+//! assert_eq!(serde_json::to_string(&config).unwrap(), r#"{"gain":2.0,"name":"motor"}"#);
+//! # }
+//! # #[cfg(not(feature = "serde"))]
+//! # fn main() {}
 //! ```
 //!
+//! # Optional features
+//!
+//! The default features only read and write untyped v7 MAT-files, with compression. `full` enables
+//! all of the features below, with the default deflate implementation.
+//! [`capabilities`] reports the features compiled in, with the supported MAT-file versions and
+//! classes.
+//!
+//! - `serde`: The typed interface [`to_matfile`] and [`from_matfile`], conversion of [`MatVariable`] to and from text formats, [`Schema::from_type`] and `testing::assert_roundtrip`.
+//! - `toml`: Writes structures as TOML documents and reads them back with [`to_toml_string`] and [`from_toml_str`], for editing configuration-style MAT-files as text. Enables `serde`.
+//! - `yaml`: Writes variables as YAML documents and reads them back with [`to_yaml_string`] and [`from_yaml_str`]. Enables `serde`.
+//! - `chrono`: Parses the creation time of read headers into `HeaderInfo::created_at`. Creation times are written in UTC with and without it.
+//! - `num-complex`: Re-exports `Complex64` and `Complex32` from [num-complex](https://docs.rs/num-complex), which can be used as fields in the typed interface to read and write complex scalars.
+//! - `regex`: Renames variables and structure fields matching a [regex](https://docs.rs/regex) with `MatFile::rename_matching` and `MatFile::rename_matching_recursive`, and re-exports `Regex`.
+//! - `xlsx`: Exports the 2D variables of a file to the worksheets of an Excel workbook with `MatFile::export_xlsx`. Enables `zip`.
+//! - `zip`: Loads MAT-files from the members of ZIP archives without extracting them with `load_matfile_from_zip`, and lists the members with `zip_members`.
+//! - `capi`: Exposes a C-compatible API in the module `capi`, declared in `include/matrw.h`, for use from C and C++.
+//! - `testing`: Enables [proptest](https://docs.rs/proptest) strategies generating arbitrary MAT-file variables in `testing::strategies`.
+//! - `deflate-miniz` (default), `deflate-zlib-ng`, `deflate-zlib-rs`: Select the deflate implementation of [flate2](https://docs.rs/flate2) used for compressed variables: the pure Rust miniz_oxide, the C library zlib-ng or the pure Rust zlib-rs. Without any of them, compressed variables are written as uncompressed deflate blocks, which MATLAB can read, and compressed variables written by MATLAB can't be read. With any of them, `load_matfile_gz` and `save_matfile_v7_gz` read and write gzip-compressed MAT-files, e.g. `data.mat.gz`, decompressing and compressing them while they are read and written. Other whole-file compression or encryption, e.g. zstd or age, wraps the streams of `load_matfile_from_stream` and `save_matfile_v7_to_writer`.
+//!
+
+// Sizes of MAT-file elements are 32-bit and have to fit into `usize`
+#[cfg(not(any(target_pointer_width = "32", target_pointer_width = "64")))]
+compile_error!("matrw requires a target with 32-bit or 64-bit pointers.");
 
+#[cfg(feature = "capi")]
+pub mod capi;
 #[doc(hidden)]
 pub mod interface;
 #[doc(hidden)]
@@ -315,27 +396,116 @@ pub mod __private {
 
 #[doc(inline)]
 pub use interface::{
+    capabilities::{Capabilities, FormatSupport, FormatVersion, capabilities},
     error::MatrwError,
-    fileio::{load_matfile, load_matfile_from_u8, save_matfile_v7},
-    matfile::MatFile,
-    types::matlab_types::MatlabType,
-    variable::MatVariable,
+    events::{DataPart, Event, read_events},
+    fileio::{
+        LoadOptions, SaveOptions, concat_variable, load_matfile, load_matfile_from_reader,
+        load_matfile_from_stream, load_matfile_from_u8, load_matfile_from_u8_with_options, load_matfile_lazy,
+        load_matfile_lazy_from_u8, load_matfile_with_options, save_matfile_sharded, save_matfile_v6,
+        save_matfile_v6_to_u8, save_matfile_v7, save_matfile_v7_to_u8, save_matfile_v7_to_u8_with_options,
+        save_matfile_v7_to_writer, save_matfile_v7_with_options,
+    },
+    matfile::{MatFile, VariableInfo},
+    metadata::{HeaderInfo, LikelyWriter, METADATA_VARIABLE, Metadata, VARIABLE_META_VARIABLE},
+    roundtrip::{RoundTripDifference, RoundTripReport, roundtrip_check, roundtrip_check_u8},
+    schema::{Schema, SchemaViolation, ValidationReport, VariableClass, VariableSchema},
+    skip_report::{SkipReason, SkipReport, SkippedElement},
+    stats::{ClassStats, VariableStats},
+    timeseries::TimeSeries,
+    transcode::{TranscodeOptions, transcode, transcode_u8},
+    types::array::{ind2sub, sub2ind},
+    types::dim::MatDim,
+    types::matlab_types::{
+        MatlabType, MatlabTypeMarker, MatlabTypeMarkerComplex, NumericVisitor, NumericVisitorMut,
+    },
+    units::{Quantity, UnitConvention},
+    variable::{FromMatVariable, MatVariable},
+    watch::{MatFileUpdate, MatFileWatcher},
+    writer::MatFileWriter,
 };
 
-#[doc(hidden)]
+#[cfg(feature = "deflate")]
+#[doc(inline)]
+pub use interface::fileio::{
+    load_matfile_gz, load_matfile_gz_from_reader, save_matfile_v7_gz, save_matfile_v7_gz_to_writer,
+};
+
+#[cfg(feature = "zip")]
+#[doc(inline)]
+pub use interface::zip::{load_matfile_from_zip, zip_members};
+
+#[doc(inline)]
 pub use interface::variable::OwnedIndex;
 
-#[doc(hidden)]
+#[doc(inline)]
+pub use interface::index::Idx;
+
+#[doc(inline)]
+pub use interface::types::structure::MergePolicy;
+
+#[doc(inline)]
+pub use interface::visit::{Path, PathElem};
+
+#[doc(inline)]
+pub use parser::v7::flags::{MatFileDataTypes, MatlabArrayTypes};
+
+#[doc(inline)]
+pub use parser::v7::variable7::MatVariable7;
+
+#[doc(inline)]
 pub use interface::types::{
-    cell_array::CellArray, numeric_array::NumericArray, sparse_array::SparseArray, structure::Structure,
+    aligned::AlignedBuffer,
+    cell_array::CellArray,
+    numeric_array::{ByteOrder, NumericArray, PrecisionInfo},
+    sparse_array::{SparseArray, SparseColumn},
+    structure::Structure,
     structure_array::StructureArray,
 };
 
+#[doc(inline)]
+pub use interface::types::bitmask::BitMask;
+
 #[doc(hidden)]
-pub use interface::types::{
-    numeric_array::{check_same_dim, check_same_type},
-    structure::check_same_fields,
+pub use interface::types::structure::check_same_fields;
+
+#[doc(hidden)]
+#[allow(deprecated)]
+pub use interface::types::numeric_array::{check_same_dim, check_same_type};
+
+#[cfg(feature = "serde")]
+#[doc(inline)]
+pub use interface::serde::{
+    de::{ShapeHints, from_matfile, from_matfile_checked},
+    ser::to_matfile,
+    sparse::SparseMatrix,
 };
 
+#[cfg(feature = "toml")]
 #[doc(inline)]
-pub use interface::serde::{de::from_matfile, ser::to_matfile};
+pub use interface::serde::toml::{from_toml_str, to_toml_string};
+
+#[cfg(feature = "yaml")]
+#[doc(inline)]
+pub use interface::serde::yaml::{from_yaml_str, to_yaml_string};
+
+#[doc(inline)]
+pub use interface::bench_support;
+
+#[doc(inline)]
+pub use interface::codegen;
+
+#[doc(inline)]
+pub use interface::testing;
+
+#[doc(inline)]
+pub use interface::fixtures;
+
+#[doc(inline)]
+pub use interface::compat;
+
+#[cfg(feature = "num-complex")]
+pub use num_complex::{Complex32, Complex64};
+
+#[cfg(feature = "regex")]
+pub use regex::Regex;
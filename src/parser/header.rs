@@ -1,24 +1,25 @@
 use binrw::*;
 use binrw::{BinResult, Endian};
 
-use chrono::Local;
-
 // from MAT-file spec: https://www.mathworks.com/help/pdf_doc/matlab/matfile_format.pdf
 const HEADER_TEXT_FIELD: usize = 116; // 0..116
 const HEADER_SUBSYSTEM_DATA_OFFSET_FIELD: usize = 8; // 116..124
 const HEADER_FLAG_FIELDS_VERSION: usize = 2; // 124..126
 const HEADER_FLAG_FIELDS_ENDIAN: usize = 2; // 126..128
+/// Position of the subsystem data offset in the header
+pub const HEADER_SUBSYSTEM_DATA_OFFSET_POS: usize = HEADER_TEXT_FIELD;
 pub const HEADER_SIZE: usize = HEADER_TEXT_FIELD
     + HEADER_SUBSYSTEM_DATA_OFFSET_FIELD
     + HEADER_FLAG_FIELDS_VERSION
     + HEADER_FLAG_FIELDS_ENDIAN;
 
+/// Version of the header text. SciPy omits the comma after `MAT-file`, so the magic ends before.
 #[binrw]
 #[derive(Debug, PartialEq)]
 pub enum MatFileVerFlag {
-    #[brw(magic = b"MATLAB 5.0 MAT-file, ")]
+    #[brw(magic = b"MATLAB 5.0 MAT-file")]
     V7,
-    #[brw(magic = b"MATLAB 7.3 MAT-file, ")]
+    #[brw(magic = b"MATLAB 7.3 MAT-file")]
     V73,
 }
 
@@ -39,9 +40,12 @@ fn write_text(matfile_ver: &MatFileVerFlag) -> BinResult<()> {
         unimplemented!()
     }
 
-    let datetime = format!("{}", Local::now().format("%a %b %d %H:%M:%S %Y"));
+    let datetime = crate::interface::helper::utc_header_time();
     text.push_str("Created on: ");
     text.push_str(datetime.as_str());
+    text.push_str(", written by matrw ");
+    text.push_str(env!("CARGO_PKG_VERSION"));
+    text.truncate(HEADER_TEXT_FIELD);
 
     let pad = HEADER_TEXT_FIELD - text.len();
     text.push_str(String::from_utf8(vec![32u8; pad]).unwrap().as_str());
@@ -82,6 +86,10 @@ fn write_endian(end: &Endian) -> BinResult<()> {
 #[binrw]
 #[derive(Debug)]
 pub struct MatFileHeader {
+    /// Descriptive text as read, the text is generated when writing
+    #[br(restore_position, count = HEADER_TEXT_FIELD)]
+    #[bw(ignore)]
+    pub text: Vec<u8>,
     #[brw(pad_size_to = HEADER_TEXT_FIELD)]
     #[bw(write_with = write_text)]
     pub matfile_ver: MatFileVerFlag,
@@ -103,6 +111,7 @@ impl MatFileHeader {
         };
 
         Self {
+            text: Vec::new(),
             matfile_ver: ver,
             header_subsystem_data_offset_field: 0u64,
             header_flag_fields_version: 1,
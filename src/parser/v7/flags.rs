@@ -1,46 +1,226 @@
 use binrw::*;
+use std::fmt;
 
 /// MAT-File Data Types, see Table 1-1, <https://www.mathworks.com/help/pdf_doc/matlab/matfile_format.pdf>
-#[derive(PartialEq, Debug, BinRead, BinWrite, Clone)]
+///
+/// The type a data element is stored as. For numeric arrays it can differ from the class, e.g.
+/// MATLAB stores integral doubles as `miUINT8`. [`MatFileDataTypes::name`] and `Display` give the
+/// names used by the MAT-file specification.
+#[derive(PartialEq, Eq, Hash, Debug, BinRead, BinWrite, Clone, Copy)]
 #[brw(repr = u8)]
+#[non_exhaustive]
 pub enum MatFileDataTypes {
+    /// `miINT8`
     MiINT8 = 1,
+    /// `miUINT8`
     MiUINT8 = 2,
+    /// `miINT16`
     MiINT16 = 3,
+    /// `miUINT16`
     MiUINT16 = 4,
+    /// `miINT32`
     MiINT32 = 5,
+    /// `miUINT32`
     MiUINT32 = 6,
+    /// `miSINGLE`
     MiSINGLE = 7,
+    /// Reserved
+    MiRESERVED8 = 8,
+    /// `miDOUBLE`
     MiDOUBLE = 9,
+    /// Reserved
+    MiRESERVED10 = 10,
+    /// Reserved
+    MiRESERVED11 = 11,
+    /// `miINT64`
     MiINT64 = 12,
+    /// `miUINT64`
     MiUINT64 = 13,
+    /// `miMATRIX`, an array
     MiMATRIX = 14,
+    /// `miCOMPRESSED`, a zlib compressed data element
     MiCOMPRESSED = 15,
+    /// `miUTF8`
     MiUTF8 = 16,
+    /// `miUTF16`
     MiUTF16 = 17,
+    /// `miUTF32`
     MiUTF32 = 18,
 }
 
+impl MatFileDataTypes {
+    ///
+    /// Name of the data type in the MAT-file specification.
+    ///
+    /// ```
+    /// use matrw::MatFileDataTypes;
+    ///
+    /// assert_eq!(MatFileDataTypes::MiDOUBLE.name(), "miDOUBLE");
+    /// ```
+    ///
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::MiINT8 => "miINT8",
+            Self::MiUINT8 => "miUINT8",
+            Self::MiINT16 => "miINT16",
+            Self::MiUINT16 => "miUINT16",
+            Self::MiINT32 => "miINT32",
+            Self::MiUINT32 => "miUINT32",
+            Self::MiSINGLE => "miSINGLE",
+            Self::MiDOUBLE => "miDOUBLE",
+            Self::MiRESERVED8 | Self::MiRESERVED10 | Self::MiRESERVED11 => "reserved",
+            Self::MiINT64 => "miINT64",
+            Self::MiUINT64 => "miUINT64",
+            Self::MiMATRIX => "miMATRIX",
+            Self::MiCOMPRESSED => "miCOMPRESSED",
+            Self::MiUTF8 => "miUTF8",
+            Self::MiUTF16 => "miUTF16",
+            Self::MiUTF32 => "miUTF32",
+        }
+    }
+
+    /// Data type of the tag value `value`, `None` for unknown values
+    pub(crate) fn from_u32(value: u32) -> Option<Self> {
+        use MatFileDataTypes::*;
+        [
+            MiINT8,
+            MiUINT8,
+            MiINT16,
+            MiUINT16,
+            MiINT32,
+            MiUINT32,
+            MiSINGLE,
+            MiRESERVED8,
+            MiDOUBLE,
+            MiRESERVED10,
+            MiRESERVED11,
+            MiINT64,
+            MiUINT64,
+            MiMATRIX,
+            MiCOMPRESSED,
+            MiUTF8,
+            MiUTF16,
+            MiUTF32,
+        ]
+        .into_iter()
+        .find(|t| *t as u32 == value)
+    }
+}
+
+impl fmt::Display for MatFileDataTypes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
 /// MATLAB Array Types (Classes), see Table 1-3, <https://www.mathworks.com/help/pdf_doc/matlab/matfile_format.pdf>
-#[derive(PartialEq, Debug, BinRead, BinWrite, Clone, Copy, Default)]
+///
+/// The class of an array as stored in its array flags. Logical arrays have class
+/// [`MatlabArrayTypes::MxUINT8CLASS`] and the logical flag set. [`MatlabArrayTypes::name`] and
+/// `Display` give the names returned by MATLAB's `class` function.
+#[derive(PartialEq, Eq, Hash, Debug, BinRead, BinWrite, Clone, Copy, Default)]
 #[brw(repr = u8)]
+#[non_exhaustive]
 pub enum MatlabArrayTypes {
+    /// `cell`
     MxCELLCLASS = 1,
+    /// `struct`
     MxSTRUCTCLASS = 2,
+    /// `object`
     MxOBJECTCLASS = 3,
+    /// `char`
     MxCHARCLASS = 4,
+    /// `sparse`, double or logical
     MxSPARSECLASS = 5,
+    /// `double`
     #[default]
     MxDOUBLECLASS = 6,
+    /// `single`
     MxSINGLECLASS = 7,
+    /// `int8`
     MxINT8CLASS = 8,
+    /// `uint8`
     MxUINT8CLASS = 9,
+    /// `int16`
     MxINT16CLASS = 10,
+    /// `uint16`
     MxUINT16CLASS = 11,
+    /// `int32`
     MxINT32CLASS = 12,
+    /// `uint32`
     MxUINT32CLASS = 13,
+    /// `int64`
     MxINT64CLASS = 14,
+    /// `uint64`
     MxUINT64CLASS = 15,
+    /// `function_handle`
     MxHANDLECLASS = 16,
+    /// `opaque`, e.g. `string`, `table` and other classdef objects
     MxOPAQUECLASS = 17,
 }
+
+impl MatlabArrayTypes {
+    ///
+    /// Name of the class in MATLAB.
+    ///
+    /// ```
+    /// use matrw::MatlabArrayTypes;
+    ///
+    /// assert_eq!(MatlabArrayTypes::MxUINT8CLASS.name(), "uint8");
+    /// assert_eq!(MatlabArrayTypes::MxDOUBLECLASS.to_string(), "double");
+    /// ```
+    ///
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::MxCELLCLASS => "cell",
+            Self::MxSTRUCTCLASS => "struct",
+            Self::MxOBJECTCLASS => "object",
+            Self::MxCHARCLASS => "char",
+            Self::MxSPARSECLASS => "sparse",
+            Self::MxDOUBLECLASS => "double",
+            Self::MxSINGLECLASS => "single",
+            Self::MxINT8CLASS => "int8",
+            Self::MxUINT8CLASS => "uint8",
+            Self::MxINT16CLASS => "int16",
+            Self::MxUINT16CLASS => "uint16",
+            Self::MxINT32CLASS => "int32",
+            Self::MxUINT32CLASS => "uint32",
+            Self::MxINT64CLASS => "int64",
+            Self::MxUINT64CLASS => "uint64",
+            Self::MxHANDLECLASS => "function_handle",
+            Self::MxOPAQUECLASS => "opaque",
+        }
+    }
+
+    /// Class of the value `value` in the array flags, `None` for unknown values
+    pub(crate) fn from_u8(value: u8) -> Option<Self> {
+        use MatlabArrayTypes::*;
+        [
+            MxCELLCLASS,
+            MxSTRUCTCLASS,
+            MxOBJECTCLASS,
+            MxCHARCLASS,
+            MxSPARSECLASS,
+            MxDOUBLECLASS,
+            MxSINGLECLASS,
+            MxINT8CLASS,
+            MxUINT8CLASS,
+            MxINT16CLASS,
+            MxUINT16CLASS,
+            MxINT32CLASS,
+            MxUINT32CLASS,
+            MxINT64CLASS,
+            MxUINT64CLASS,
+            MxHANDLECLASS,
+            MxOPAQUECLASS,
+        ]
+        .into_iter()
+        .find(|t| *t as u8 == value)
+    }
+}
+
+impl fmt::Display for MatlabArrayTypes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
@@ -1,27 +1,307 @@
+use std::io::{Read, Seek, SeekFrom};
+
 use binrw::*;
 use indexmap::IndexMap;
 
+use super::flags::MatFileDataTypes;
+use super::sparse_reader::{SparseConversion, read_sparse};
+use super::types::compressed_array::{
+    compressed_variable_name, decompress_variable, decompressed_size, read_bytes,
+};
 use super::variable7::MatVariable7;
-use crate::interface::matfile::MatFile;
+use super::zlib;
+use crate::MatrwError;
+use crate::interface::matfile::{Entry, MatFile, VariableInfo};
+use crate::interface::skip_report::{SkipReport, SkippedElement};
+use crate::interface::types::compressed_array::CompressedArray;
+use crate::interface::types::sparse_array::SparseArray;
+use crate::interface::variable::MatVariable;
 
 #[parser(reader, endian)]
 pub fn parse_variable7() -> BinResult<IndexMap<String, MatVariable7>> {
     let mut map = IndexMap::new();
 
     loop {
-        let data = match MatVariable7::read_options(reader, endian, ()) {
+        let start = reader.stream_position()?;
+        let mut data = match MatVariable7::read_options(reader, endian, ()) {
             Ok(d) => d,
-            Err(err) if err.is_eof() => break,
+            Err(_) if !starts_element(reader, start)? => break,
             Err(err) => return Err(err),
         };
+        if let MatVariable7::Compressed(c) = &mut data {
+            c.value_mut()
+                .set_unknown_range(start, reader.stream_position()? - start);
+        }
 
-        let name = data.name();
+        // Variables without name are named when converted to a `MatFile`, they must not replace
+        // each other before. Keys starting with a digit are no valid names.
+        let name = match data.name() {
+            name if name.is_empty() => map.len().to_string(),
+            name => name,
+        };
         map.insert(name, data);
     }
 
     Ok(map)
 }
 
+/// Whether an element starts at `start`, i.e. at least its data type can be read there.
+///
+/// Reading the variables ends, when no further element starts. An element, which starts but ends
+/// early, is a truncated file and an error, independent of how many elements were read before.
+fn starts_element<R: Read + Seek>(reader: &mut R, start: u64) -> BinResult<bool> {
+    reader.seek(SeekFrom::Start(start))?;
+    let starts = reader.read_type::<u32>(Endian::Little).is_ok();
+    reader.seek(SeekFrom::Start(start))?;
+    Ok(starts)
+}
+
+/// Insert a variable read from a file into `map`.
+///
+/// Skipped data elements, also inside cells and structures, are reported in `warnings` and
+/// `skipped`, as well as numeric arrays whose values are fitted to their dimensions, see
+/// [`MatVariable7::length_mismatch`]. Skipped top-level arrays are kept as
+/// [`MatVariable::Unsupported`], if their name is known. Variables with empty name are inserted
+/// under a placeholder name, see [`unnamed_variable`].
+///
+/// Returns the index of the inserted variable in `map`, if any.
+pub(crate) fn insert_variable(
+    map: &mut IndexMap<String, Entry>,
+    warnings: &mut Vec<String>,
+    skipped: &mut SkipReport,
+    data: MatVariable7,
+) -> Option<usize> {
+    let data = match data {
+        MatVariable7::Compressed(c) => c.value(),
+        data => data,
+    };
+    if let MatVariable7::Unknown(unknown) = &data {
+        warnings.push(unknown.warning());
+        skipped.push(SkippedElement::from(unknown));
+        // Skipped elements without known name are dropped
+        unknown.array_name()?;
+    }
+    let name = match data.name() {
+        name if name.is_empty() => unnamed_variable(map, warnings),
+        name => name,
+    };
+    let mut nested = Vec::new();
+    data.nested_unknown(&name, &mut nested);
+    for (path, unknown) in nested {
+        warnings.push(unknown.nested_warning(&path));
+        skipped.push(SkippedElement::nested(unknown, path));
+    }
+    if let Some((declared, stored)) = data.length_mismatch() {
+        let fitted = if stored > declared {
+            format!("the last {} values were dropped", stored - declared)
+        } else {
+            format!("{} zeros were appended", declared - stored)
+        };
+        warnings.push(format!(
+            "Variable '{name}' declares {declared} elements but stores {stored} values, {fitted}"
+        ));
+    }
+    let (index, _) = map.insert_full(name, MatVariable::from(data).into());
+    Some(index)
+}
+
+/// Placeholder name `unnamed_<n>` of a top-level variable with empty name, using the smallest
+/// `n` not taken in `map`. The renaming is reported in `warnings`.
+///
+/// Only elements of structures and cells have empty names in files written by MATLAB.
+fn unnamed_variable(map: &IndexMap<String, Entry>, warnings: &mut Vec<String>) -> String {
+    let name = (1..)
+        .map(|n| format!("unnamed_{n}"))
+        .find(|name| !map.contains_key(name))
+        .expect("Unused placeholder name");
+    warnings.push(format!("Variable without name was read as '{name}'"));
+    name
+}
+
+/// Conversion applied to each variable while reading, see [`parse_variables`]
+pub(crate) type Convert<'a> = &'a dyn Fn(&mut MatVariable) -> Result<(), MatrwError>;
+
+/// Limits and conversions applied while reading, see [`parse_variables`]
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct ParseOptions {
+    /// Memory the variables may take, see [`crate::LoadOptions::max_memory`]
+    pub max_memory: Option<u64>,
+    /// Top-level arrays read as sparse arrays, see [`crate::LoadOptions::sparse_below_density`]
+    pub sparse: Option<SparseConversion>,
+}
+
+/// Read all variables.
+///
+/// With `lazy`, the data of compressed variables is kept compressed, except for compressed
+/// variables whose name can't be determined from their header. With `convert`, all variables are
+/// decompressed and passed to `convert` right after they are read, before the next variable is
+/// read. Errors of `convert` stop reading. With `strict`, numeric arrays storing another number of
+/// values than their dimensions declare are an error instead of a warning.
+///
+/// With `options.max_memory`, reading fails with [`MatrwError::MemoryLimit`] as soon as the
+/// variables read would take more memory, see [`MatVariable::memory_usage`]. The sizes of
+/// elements and of decompressed data declared by their tags, and the sizes of arrays declared by
+/// their dimensions, are checked before they are allocated. Compressed data is decompressed up to
+/// the remaining memory only. With `options.sparse`, top-level arrays are read as sparse arrays
+/// while they are read, if they qualify, see [`read_sparse`]. Both disable `lazy`.
+///
+/// Returns the variables with their sizes in the file, the warnings and the report about skipped
+/// data elements.
+pub(crate) fn parse_variables<R: Read + Seek>(
+    reader: &mut R,
+    endian: Endian,
+    lazy: bool,
+    convert: Option<Convert>,
+    strict: bool,
+    options: ParseOptions,
+) -> Result<MatFile, MatrwError> {
+    let max_memory = options.max_memory;
+    let lazy = lazy && convert.is_none() && max_memory.is_none() && options.sparse.is_none();
+    let mut map = IndexMap::new();
+    let mut info = IndexMap::new();
+    let mut warnings = Vec::new();
+    let mut skipped = SkipReport::default();
+    let mut used = 0u64;
+    // Fails, if `bytes` more than the memory used so far exceed the limit
+    let reserve = |used: u64, bytes: u64| match max_memory {
+        Some(limit) if used.saturating_add(bytes) > limit => Err(MatrwError::MemoryLimit(limit)),
+        _ => Ok(()),
+    };
+    // Converts the inserted variable and counts its memory
+    let finish = |entry: &mut Entry, used: &mut u64| {
+        if let Some(convert) = convert {
+            convert(entry.value_mut())?;
+        }
+        if max_memory.is_some() {
+            *used += entry.value_mut().memory_usage();
+            reserve(*used, 0)?;
+        }
+        Ok::<_, MatrwError>(())
+    };
+    let mut insert = |map: &mut IndexMap<String, Entry>,
+                      info: &mut IndexMap<String, VariableInfo>,
+                      warnings: &mut Vec<String>,
+                      used: &mut u64,
+                      data: MatVariable7,
+                      size: VariableInfo| {
+        if strict && let Some((declared, stored)) = data.length_mismatch() {
+            return Err(MatrwError::TypeConstruction(format!(
+                "Variable '{}' declares {} elements, but stores {} values.",
+                data.name(),
+                declared,
+                stored
+            )));
+        }
+        // Values are fitted to the declared dimensions on conversion
+        reserve(*used, data.declared_bytes())?;
+        let Some(index) = insert_variable(map, warnings, &mut skipped, data) else {
+            return Ok(());
+        };
+        let (name, entry) = map.get_index_mut(index).expect("Variable was inserted");
+        info.insert(name.clone(), size);
+        finish(entry, used)
+    };
+    let insert_sparse = |map: &mut IndexMap<String, Entry>,
+                         info: &mut IndexMap<String, VariableInfo>,
+                         warnings: &mut Vec<String>,
+                         used: &mut u64,
+                         (name, sparse): (String, SparseArray),
+                         size: VariableInfo| {
+        let name = match name {
+            name if name.is_empty() => unnamed_variable(map, warnings),
+            name => name,
+        };
+        info.insert(name.clone(), size);
+        let (index, _) = map.insert_full(name, MatVariable::SparseArray(sparse).into());
+        let (_, entry) = map.get_index_mut(index).expect("Variable was inserted");
+        finish(entry, used)
+    };
+
+    loop {
+        let start = reader.stream_position()?;
+        let data_type = match reader.read_type::<u32>(endian) {
+            Ok(d) => d,
+            Err(err) if err.is_eof() => break,
+            Err(err) => return Err(err.into()),
+        };
+
+        if data_type != MatFileDataTypes::MiCOMPRESSED as u32 {
+            if data_type == MatFileDataTypes::MiMATRIX as u32
+                && let Some(conversion) = options.sparse
+            {
+                let num_bytes = reader.read_type::<u32>(endian)?;
+                reader.seek(SeekFrom::Start(start))?;
+                if let Some(sparse) = read_sparse(reader, endian, conversion) {
+                    let disk_bytes = 8 + num_bytes as u64;
+                    reader.seek(SeekFrom::Start(start + disk_bytes))?;
+                    let size = VariableInfo {
+                        offset: start,
+                        disk_bytes,
+                        uncompressed_bytes: disk_bytes,
+                        compressed: false,
+                    };
+                    insert_sparse(&mut map, &mut info, &mut warnings, &mut used, sparse, size)?;
+                    continue;
+                }
+                // Read again as a dense array
+                reader.seek(SeekFrom::Start(start + 4))?;
+            }
+            if max_memory.is_some()
+                && data_type == MatFileDataTypes::MiMATRIX as u32
+                && let Ok(num_bytes) = reader.read_type::<u32>(endian)
+            {
+                reserve(used, 8 + num_bytes as u64)?;
+            }
+            reader.seek(SeekFrom::Start(start))?;
+            let data = MatVariable7::read_options(reader, endian, ())?;
+            let disk_bytes = reader.stream_position()? - start;
+            let size = VariableInfo {
+                offset: start,
+                disk_bytes,
+                uncompressed_bytes: disk_bytes,
+                compressed: false,
+            };
+            insert(&mut map, &mut info, &mut warnings, &mut used, data, size)?;
+            continue;
+        }
+
+        let num_bytes = reader.read_type::<u32>(endian)?;
+        let data = read_bytes(reader, num_bytes as u64)?;
+        let size = VariableInfo {
+            offset: start,
+            disk_bytes: reader.stream_position()? - start,
+            uncompressed_bytes: decompressed_size(&data, endian)?,
+            compressed: true,
+        };
+
+        match compressed_variable_name(&data, endian)? {
+            Some(name) if lazy => {
+                let name = match name {
+                    name if name.is_empty() => unnamed_variable(&map, &mut warnings),
+                    name => name,
+                };
+                info.insert(name.clone(), size);
+                map.insert(name, Entry::Compressed(CompressedArray::lazy(data, endian)));
+            }
+            _ => {
+                if let Some(conversion) = options.sparse
+                    && let Some(sparse) = read_sparse(&mut zlib::decoder(data.as_slice()), endian, conversion)
+                {
+                    insert_sparse(&mut map, &mut info, &mut warnings, &mut used, sparse, size)?;
+                    continue;
+                }
+                reserve(used, size.uncompressed_bytes)?;
+                let remaining = max_memory.map_or(u64::MAX, |limit| limit.saturating_sub(used));
+                let mut data = decompress_variable(&data, endian, remaining)?;
+                data.set_unknown_range(start, reader.stream_position()? - start);
+                insert(&mut map, &mut info, &mut warnings, &mut used, data, size)?;
+            }
+        }
+    }
+
+    Ok(MatFile::from_variables(map, info, warnings, skipped))
+}
+
 #[binrw::writer(writer, endian)]
 pub fn write_variable7(data: &IndexMap<String, MatVariable7>) -> BinResult<()> {
     for (_, val) in data.iter() {
@@ -72,6 +352,7 @@ impl From<MatFile> for MatFile7 {
 mod tests {
     use super::*;
     use binrw::BinReaderExt;
+    #[cfg(feature = "deflate")]
     use binrw::io::Cursor; // A no_std reimplementation of std::io // extension traits for use with readers and writers
 
     /// Binary representation of a mat file containing two variables 'a' and 'b'.
@@ -80,6 +361,7 @@ mod tests {
     /// `>> b = pi;`
     /// `>> save('example.mat', 'a', 'b');`
     ///
+    #[cfg(feature = "deflate")]
     const MATFILE7: [u8; 222] = [
         0x4d, 0x41, 0x54, 0x4c, 0x41, 0x42, 0x20, 0x35, 0x2e, 0x30, 0x20, 0x4d, 0x41, 0x54, 0x2d, 0x66, 0x69,
         0x6c, 0x65, 0x2c, 0x20, 0x50, 0x6c, 0x61, 0x74, 0x66, 0x6f, 0x72, 0x6d, 0x3a, 0x20, 0x47, 0x4c, 0x4e,
@@ -97,11 +379,13 @@ mod tests {
         0x21,
     ];
 
+    #[cfg(feature = "deflate")]
     #[test]
     fn parse_matfile7() {
         let data = Cursor::new(&MATFILE7[128..]).read_le::<MatFile7>().unwrap();
         println!("Deserialized data: {:#?}", data);
     }
+    #[cfg(feature = "deflate")]
     #[test]
     fn print_matfile7_varnames() {
         let matfile = Cursor::new(&MATFILE7[128..]).read_le::<MatFile7>().unwrap();
@@ -6,5 +6,8 @@
 
 pub mod flags;
 pub mod matfile7;
+pub mod sparse_reader;
+pub mod stream;
 pub mod types;
 pub mod variable7;
+pub mod zlib;
@@ -0,0 +1,340 @@
+//! Dense numeric arrays read as sparse arrays, see [`crate::LoadOptions::sparse_below_density`].
+//!
+//! The values of an array are read in chunks and only the nonzero values are kept, so a mostly
+//! zero array never takes its dense size in memory. Reading stops as soon as an array has more
+//! nonzero values than the density allows, and the caller reads it again as a dense array.
+
+use std::io::{self, Read};
+
+use binrw::Endian;
+
+use super::flags::{MatFileDataTypes, MatlabArrayTypes};
+use super::types::compressed_array::{read_element, read_u32};
+use super::types::subelements::array_flags::ArrayFlagBits;
+use super::types::subelements::tag::{SMALL_DATA_MAX, padding, split_small_tag};
+use crate::interface::types::matlab_types::MatlabType;
+use crate::interface::types::sparse_array::SparseArray;
+
+/// Bytes of values read at a time
+const CHUNK_BYTES: usize = 1 << 16;
+
+/// Arrays converted to sparse arrays while they are read
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SparseConversion {
+    /// Largest fraction of nonzero elements
+    pub density: f64,
+    /// Whether integer and single arrays are converted as well, to double sparse arrays
+    pub numeric: bool,
+}
+
+/// Decoder of one value of a data type to a double
+type Decode = fn(&[u8], Endian) -> f64;
+
+macro_rules! decoder {
+    ($t:ty) => {
+        (std::mem::size_of::<$t>(), |b: &[u8], endian: Endian| {
+            let b = b.try_into().expect("Value of the size of its type");
+            match endian {
+                Endian::Little => <$t>::from_le_bytes(b) as f64,
+                Endian::Big => <$t>::from_be_bytes(b) as f64,
+            }
+        })
+    };
+}
+
+/// Size and decoder of the values of `data_type`, `None` for non-numeric data types
+fn decoder(data_type: u32) -> Option<(usize, Decode)> {
+    use MatFileDataTypes::*;
+
+    Some(match data_type {
+        t if t == MiINT8 as u32 => decoder!(i8),
+        t if t == MiUINT8 as u32 => decoder!(u8),
+        t if t == MiINT16 as u32 => decoder!(i16),
+        t if t == MiUINT16 as u32 => decoder!(u16),
+        t if t == MiINT32 as u32 => decoder!(i32),
+        t if t == MiUINT32 as u32 => decoder!(u32),
+        t if t == MiSINGLE as u32 => decoder!(f32),
+        t if t == MiDOUBLE as u32 => decoder!(f64),
+        t if t == MiINT64 as u32 => decoder!(i64),
+        t if t == MiUINT64 as u32 => decoder!(u64),
+        _ => return None,
+    })
+}
+
+/// Data type MATLAB stores the values of an integer or single array of `class` in.
+///
+/// Values of other data types are cast to the class like the dense parser does, which this
+/// reader doesn't replicate.
+fn native_data_type(class: u32) -> Option<MatFileDataTypes> {
+    use {MatFileDataTypes::*, MatlabArrayTypes::*};
+
+    [
+        (MxSINGLECLASS, MiSINGLE),
+        (MxINT8CLASS, MiINT8),
+        (MxUINT8CLASS, MiUINT8),
+        (MxINT16CLASS, MiINT16),
+        (MxUINT16CLASS, MiUINT16),
+        (MxINT32CLASS, MiINT32),
+        (MxUINT32CLASS, MiUINT32),
+        (MxINT64CLASS, MiINT64),
+        (MxUINT64CLASS, MiUINT64),
+    ]
+    .into_iter()
+    .find(|&(c, _)| c as u32 == class)
+    .map(|(_, data_type)| data_type)
+}
+
+/// How the values of an array are read
+#[derive(Clone, Copy, PartialEq)]
+enum Values {
+    /// Doubles, stored in any numeric data type
+    Double,
+    /// Logicals, stored in any numeric data type
+    Logical,
+    /// Integers or singles, stored in the given data type only
+    Numeric(MatFileDataTypes),
+}
+
+/// Read the `miMATRIX` element at `reader`, starting with its tag, as a sparse array with its
+/// name.
+///
+/// Returns `None`, if the element is no 2D double or logical array, or integer or single array
+/// with `conversion.numeric`, of at least two elements, of which at most the fraction
+/// `conversion.density` is nonzero, or if it can't be read. The reader is left anywhere within
+/// the element.
+pub(crate) fn read_sparse<R: Read>(
+    reader: &mut R,
+    endian: Endian,
+    conversion: SparseConversion,
+) -> Option<(String, SparseArray)> {
+    read(reader, endian, conversion).ok().flatten()
+}
+
+fn read<R: Read>(
+    reader: &mut R,
+    endian: Endian,
+    conversion: SparseConversion,
+) -> io::Result<Option<(String, SparseArray)>> {
+    if read_u32(reader, endian)? != MatFileDataTypes::MiMATRIX as u32 {
+        return Ok(None);
+    }
+    let _num_bytes = read_u32(reader, endian)?;
+
+    let (_, flags) = read_element(reader, endian)?;
+    let Some(flags) = flags.get(..4) else {
+        return Ok(None);
+    };
+    let flags = u32_from(flags, endian);
+    let class = flags & 0xff;
+    let bits = ArrayFlagBits::from_bits((flags >> 8) as u8);
+    let values = match native_data_type(class) {
+        _ if bits.is_logical && !bits.is_complex => Values::Logical,
+        _ if class == MatlabArrayTypes::MxDOUBLECLASS as u32 => Values::Double,
+        Some(data_type) if conversion.numeric => Values::Numeric(data_type),
+        _ => return Ok(None),
+    };
+    if bits.is_logical && values != Values::Logical {
+        return Ok(None);
+    }
+
+    let (_, dims) = read_element(reader, endian)?;
+    if dims.len() != 8 {
+        return Ok(None);
+    }
+    let rows = u32_from(&dims[..4], endian) as usize;
+    let cols = u32_from(&dims[4..], endian) as usize;
+    let Some(numel) = rows.checked_mul(cols).filter(|&n| n >= 2) else {
+        return Ok(None);
+    };
+    let (_, name) = read_element(reader, endian)?;
+    let name = String::from_utf8_lossy(&name).into_owned();
+
+    let max_nonzero = (conversion.density * numel as f64).floor().max(0.0) as usize;
+    let mut index = Vec::new();
+    let mut re = Vec::new();
+    let complete = for_each_value(reader, endian, values, numel, |k, x| {
+        if x != 0.0 {
+            index.push(k);
+            re.push(x);
+        }
+        index.len() <= max_nonzero
+    })?;
+    if !complete {
+        return Ok(None);
+    }
+
+    let mut im = None;
+    if bits.is_complex {
+        // Elements with nonzero real or imaginary part, merged in the order of their index
+        let (mut merged_index, mut merged_re, mut merged_im) = (Vec::new(), Vec::new(), Vec::new());
+        let mut next = 0;
+        let complete = for_each_value(reader, endian, values, numel, |k, y| {
+            let x = match index.get(next) {
+                Some(&i) if i == k => {
+                    next += 1;
+                    re[next - 1]
+                }
+                _ => 0.0,
+            };
+            if x != 0.0 || y != 0.0 {
+                merged_index.push(k);
+                merged_re.push(x);
+                merged_im.push(y);
+            }
+            merged_index.len() <= max_nonzero
+        })?;
+        if !complete {
+            return Ok(None);
+        }
+        (index, re, im) = (merged_index, merged_re, Some(merged_im));
+    }
+
+    let (ir, jc) = compressed_columns(&index, rows, cols);
+    let (value, value_cmp) = match values {
+        Values::Logical => (MatlabType::BOOL(vec![true; index.len()]), None),
+        _ => (MatlabType::F64(re), im.map(MatlabType::F64)),
+    };
+    Ok(SparseArray::new(rows, cols, ir, jc, value, value_cmp)
+        .ok()
+        .map(|sparse| (name, sparse)))
+}
+
+/// Call `f` with the index and value of each of the `numel` values of the data element at
+/// `reader`, until `f` returns `false`.
+///
+/// Returns whether all values were passed to `f`. Elements of another number of values or
+/// another data type than `values` allows are not read and return `false` as well.
+fn for_each_value<R: Read>(
+    reader: &mut R,
+    endian: Endian,
+    values: Values,
+    numel: usize,
+    mut f: impl FnMut(usize, f64) -> bool,
+) -> io::Result<bool> {
+    let tag = read_u32(reader, endian)?;
+    let (data_type, size, small) = match split_small_tag(tag) {
+        Some((data_type, size)) => (data_type, size, true),
+        None => (tag, read_u32(reader, endian)?, false),
+    };
+    let Some((width, decode)) = decoder(data_type) else {
+        return Ok(false);
+    };
+    if (small && size > SMALL_DATA_MAX)
+        || size as usize != numel.saturating_mul(width)
+        || matches!(values, Values::Numeric(t) if t as u32 != data_type)
+    {
+        return Ok(false);
+    }
+
+    let mut chunk = vec![0u8; CHUNK_BYTES.min(size as usize)];
+    let mut k = 0;
+    while k < numel {
+        let count = (numel - k).min(CHUNK_BYTES / width);
+        let bytes = &mut chunk[..count * width];
+        reader.read_exact(bytes)?;
+        for value in bytes.chunks_exact(width) {
+            if !f(k, decode(value, endian)) {
+                return Ok(false);
+            }
+            k += 1;
+        }
+    }
+    io::copy(&mut reader.take(padding(size, small) as u64), &mut io::sink())?;
+
+    Ok(true)
+}
+
+/// Row indices and column starts of the elements at the column-major `index` of a `rows` x
+/// `cols` array, see [`SparseArray::new`].
+fn compressed_columns(index: &[usize], rows: usize, cols: usize) -> (Vec<usize>, Vec<usize>) {
+    let mut ir = Vec::with_capacity(index.len());
+    let mut jc = Vec::with_capacity(cols + 1);
+    jc.push(0);
+    for (n, &k) in index.iter().enumerate() {
+        while jc.len() <= k / rows {
+            jc.push(n);
+        }
+        ir.push(k % rows);
+    }
+    jc.resize(cols + 1, index.len());
+
+    (ir, jc)
+}
+
+fn u32_from(bytes: &[u8], endian: Endian) -> u32 {
+    let bytes = bytes.try_into().expect("4 bytes");
+    match endian {
+        Endian::Little => u32::from_le_bytes(bytes),
+        Endian::Big => u32::from_be_bytes(bytes),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interface::variable::MatVariable;
+    use crate::matvar;
+
+    /// Little-endian `miMATRIX` element of a 1x2 array named `x` with the class `class`, the
+    /// flags `flags` and the values `values` as a small `miUINT8` data element
+    fn element(class: u8, flags: u8, values: [u8; 2]) -> Vec<u8> {
+        let mut data = Vec::new();
+        for word in [14, 40, 6, 8, class as u32 | (flags as u32) << 8, 0, 5, 8, 1, 2] {
+            data.extend(u32::to_le_bytes(word));
+        }
+        data.extend(u32::to_le_bytes(1 << 16 | 1));
+        data.extend(b"x\0\0\0");
+        data.extend(u32::to_le_bytes(2 << 16 | 2));
+        data.extend([values[0], values[1], 0, 0]);
+        data
+    }
+
+    fn read(data: &[u8], numeric: bool) -> Option<MatVariable> {
+        let conversion = SparseConversion {
+            density: 0.5,
+            numeric,
+        };
+        let (name, sparse) = read_sparse(&mut &data[..], Endian::Little, conversion)?;
+        assert_eq!(name, "x");
+        Some(MatVariable::SparseArray(sparse))
+    }
+
+    #[test]
+    fn values_of_smaller_types() {
+        let MatVariable::NumericArray(expected) = matvar!([0.0, 7.0]) else {
+            unreachable!()
+        };
+        let double = MatlabArrayTypes::MxDOUBLECLASS as u8;
+        assert_eq!(
+            read(&element(double, 0, [0, 7]), false),
+            Some(expected.to_sparse().unwrap())
+        );
+        // Too many nonzero values, or integers stored in another type than MATLAB does
+        assert_eq!(read(&element(double, 0, [1, 7]), false), None);
+        let int16 = MatlabArrayTypes::MxINT16CLASS as u8;
+        assert_eq!(read(&element(int16, 0, [0, 7]), true), None);
+
+        let MatVariable::NumericArray(expected) = matvar!([false, true]) else {
+            unreachable!()
+        };
+        let uint8 = MatlabArrayTypes::MxUINT8CLASS as u8;
+        assert_eq!(
+            read(&element(uint8, 0x02, [0, 7]), false),
+            Some(expected.to_sparse().unwrap())
+        );
+        assert_eq!(read(&element(uint8, 0, [0, 7]), false), None);
+        assert!(read(&element(uint8, 0, [0, 7]), true).is_some());
+    }
+
+    #[test]
+    fn columns_of_indices() {
+        // 3x4 array with elements at (1, 0), (0, 2) and (2, 2)
+        let (ir, jc) = compressed_columns(&[1, 6, 8], 3, 4);
+        assert_eq!(ir, vec![1, 0, 2]);
+        assert_eq!(jc, vec![0, 1, 1, 3, 3]);
+
+        let (ir, jc) = compressed_columns(&[], 2, 2);
+        assert!(ir.is_empty());
+        assert_eq!(jc, vec![0, 0, 0]);
+    }
+}
@@ -0,0 +1,179 @@
+//! Reader of MAT-files from streams which can't seek, e.g. decompressors, decryptors or pipes.
+//!
+//! The parser seeks within the top-level element it reads and forward to the next one. The
+//! reader keeps the data from the start of the current top-level element, so only one element is
+//! in memory at a time instead of the whole file.
+
+use std::io::{self, Read, Seek, SeekFrom};
+
+use binrw::Endian;
+
+use crate::parser::header::HEADER_SIZE;
+
+/// Reader of the MAT-file `inner`, seeking within the current top-level element.
+///
+/// Seeking before the current element fails with [`io::ErrorKind::Unsupported`]. Seeking past
+/// the end of the stream is allowed, reading there returns no data. Errors of `inner` are kept,
+/// see [`ElementStream::take_error`].
+pub(crate) struct ElementStream<R> {
+    inner: R,
+    error: Option<io::Error>,
+    /// Data from `buf_start` on
+    buf: Vec<u8>,
+    buf_start: u64,
+    pos: u64,
+    /// Start of the next element, the data before it is dropped once it is reached
+    next: u64,
+    endian: Endian,
+    eof: bool,
+}
+
+impl<R: Read> ElementStream<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            error: None,
+            buf: Vec::new(),
+            buf_start: 0,
+            pos: 0,
+            next: HEADER_SIZE as u64,
+            endian: Endian::Little,
+            eof: false,
+        }
+    }
+
+    /// First error of the stream, which the parser may report as a parse error only
+    pub fn take_error(&mut self) -> Option<io::Error> {
+        self.error.take()
+    }
+
+    fn buf_end(&self) -> u64 {
+        self.buf_start + self.buf.len() as u64
+    }
+
+    /// Read from `inner` until the data up to `end` is buffered, or the stream ends
+    fn fill_to(&mut self, end: u64) -> io::Result<()> {
+        while !self.eof && self.buf_end() < end {
+            let missing = (end - self.buf_end()).min(1 << 16) as usize;
+            let len = self.buf.len();
+            self.buf.resize(len + missing, 0);
+            let read = self.inner.read(&mut self.buf[len..]);
+            self.buf.truncate(len + *read.as_ref().unwrap_or(&0));
+            match read {
+                Ok(read) => self.eof = read == 0,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => {
+                    let kept = io::Error::new(err.kind(), err.to_string());
+                    self.error.get_or_insert(kept);
+                    return Err(err);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Drop the elements before the position
+    fn advance(&mut self) -> io::Result<()> {
+        while self.pos >= self.next {
+            if self.next == HEADER_SIZE as u64 {
+                // Endian indicator at the end of the header, see `MatFileHeader`
+                self.fill_to(self.next)?;
+                let indicator = self.buf.get(HEADER_SIZE - 2..HEADER_SIZE);
+                if indicator == Some(b"MI") {
+                    self.endian = Endian::Big;
+                }
+            }
+            // Drop the data before the next element, and skip what wasn't read of it
+            let keep = (self.next - self.buf_start).min(self.buf.len() as u64) as usize;
+            self.buf.drain(..keep);
+            self.buf_start += keep as u64;
+            if self.buf_start < self.next {
+                io::copy(
+                    &mut (&mut self.inner).take(self.next - self.buf_start),
+                    &mut io::sink(),
+                )?;
+                self.buf_start = self.next;
+            }
+
+            self.fill_to(self.next + 8)?;
+            let Some(size) = self.buf.get(4..8) else {
+                break;
+            };
+            let size = size.try_into().expect("4 bytes");
+            let size = match self.endian {
+                Endian::Little => u32::from_le_bytes(size),
+                Endian::Big => u32::from_be_bytes(size),
+            };
+            self.next += 8 + size as u64;
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for ElementStream<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        self.advance()?;
+        self.fill_to(self.pos + out.len() as u64)?;
+        let start = (self.pos - self.buf_start) as usize;
+        let available = self.buf.get(start..).unwrap_or_default();
+        let len = available.len().min(out.len());
+        out[..len].copy_from_slice(&available[..len]);
+        self.pos += len as u64;
+        Ok(len)
+    }
+}
+
+impl<R: Read> Seek for ElementStream<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let pos = match pos {
+            SeekFrom::Start(pos) => Some(pos),
+            SeekFrom::Current(offset) => self.pos.checked_add_signed(offset),
+            SeekFrom::End(_) => None,
+        };
+        match pos {
+            Some(pos) if pos >= self.buf_start => {
+                self.pos = pos;
+                self.advance()?;
+                Ok(pos)
+            }
+            _ => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "Stream can't seek before the current element or relative to its end",
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_current_element() {
+        // Header, an element of 8 bytes and one of 16 bytes
+        let mut data = vec![b' '; HEADER_SIZE];
+        data[HEADER_SIZE - 2..].copy_from_slice(b"IM");
+        data.extend([14, 0, 0, 0, 8, 0, 0, 0]);
+        data.extend(0..8);
+        data.extend([14, 0, 0, 0, 16, 0, 0, 0]);
+        data.extend(8..24);
+
+        let mut stream = ElementStream::new(data.as_slice());
+        let mut buf = [0u8; 4];
+        stream.seek(SeekFrom::Start(HEADER_SIZE as u64 + 8)).unwrap();
+        stream.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, [0, 1, 2, 3]);
+        stream.seek(SeekFrom::Start(HEADER_SIZE as u64)).unwrap();
+        stream.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, [14, 0, 0, 0]);
+
+        stream.seek(SeekFrom::Current(24)).unwrap();
+        stream.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, [12, 13, 14, 15]);
+        assert!(stream.buf.len() <= 24);
+        assert!(stream.seek(SeekFrom::Start(HEADER_SIZE as u64 + 8)).is_err());
+
+        stream.seek(SeekFrom::Start(1000)).unwrap();
+        assert_eq!(stream.read(&mut buf).unwrap(), 0);
+    }
+}
@@ -19,13 +19,13 @@ pub fn write_value(data: &Vec<MatVariable7>) -> BinResult<()> {
 
 #[binrw]
 #[derive(Debug, Clone)]
-#[br(assert(data_type == MatFileDataTypes::MiMATRIX &&
-            props.array_class == MatlabArrayTypes::MxCELLCLASS))]
 pub struct CellArray7 {
+    #[br(assert(data_type == MatFileDataTypes::MiMATRIX))]
     #[brw(pad_size_to = 4)]
     data_type: MatFileDataTypes,
     #[bw(calc = self.size_data())]
     _num_bytes: u32,
+    #[br(assert(props.array_class == MatlabArrayTypes::MxCELLCLASS))]
     props: ArrayProps,
     #[brw(align_after = 8)]
     dimensions: ArrayDimensions,
@@ -74,17 +74,26 @@ impl CellArray7 {
     pub fn size(&self) -> usize {
         self.size_data() as usize + 8
     }
-    pub fn dim(&self) -> Vec<u32> {
-        self.dimensions.dim().clone()
+    pub fn dim(&self) -> &[u32] {
+        self.dimensions.dim()
+    }
+    pub fn dimensions(&self) -> &ArrayDimensions {
+        &self.dimensions
     }
     pub fn value(self) -> Vec<MatVariable7> {
         self.value
     }
+    pub fn elements(&self) -> &[MatVariable7] {
+        &self.value
+    }
+    pub fn elements_mut(&mut self) -> &mut [MatVariable7] {
+        &mut self.value
+    }
 }
 
 impl From<CellArray> for CellArray7 {
     fn from(value: CellArray) -> Self {
-        let dim = value.dim.into_iter().map(|x| x as u32).collect();
+        let dim = value.dim.iter().map(|&x| x as u32).collect();
         let mut val = vec![];
         for v in value.value.into_iter() {
             val.push(v.into())
@@ -1,44 +1,179 @@
-use crate::interface::types::compressed_array::CompressedArray;
-use crate::parser::v7::flags::MatFileDataTypes;
+use crate::parser::v7::flags::{MatFileDataTypes, MatlabArrayTypes};
+use crate::parser::v7::types::subelements::tag::{SMALL_DATA_MAX, padding, split_small_tag};
 use crate::parser::v7::variable7::MatVariable7;
-use std::io::{Cursor, Read, Seek, Write};
-use std::ops::Deref;
+use crate::parser::v7::zlib;
+use std::cell::RefCell;
+use std::io::{Cursor, Read};
 
-use binrw::io::TakeSeekExt;
 use binrw::*;
-use flate2::Compression;
-use flate2::read::ZlibDecoder;
-use flate2::write::ZlibEncoder;
 
-fn uncompress_data<S: Read + Seek>(data: S) -> Cursor<Vec<u8>> {
-    let mut zlib = ZlibDecoder::new(data);
+/// Largest buffer kept by a thread for the next compressed variable
+const SCRATCH_MAX: usize = 1 << 20;
 
-    let mut buf = vec![];
-    zlib.read_to_end(&mut buf).unwrap();
+thread_local! {
+    /// Buffers for the compressed and decompressed data of the variable being read, reused across
+    /// the variables of a file.
+    static SCRATCH: RefCell<(Vec<u8>, Vec<u8>)> = const { RefCell::new((Vec::new(), Vec::new())) };
+}
+
+/// Run `f` with the buffers of the thread, cleared.
+///
+/// The buffers are taken out for the duration of `f`, so nested calls get buffers of their own.
+fn with_scratch<T>(f: impl FnOnce(&mut Vec<u8>, &mut Vec<u8>) -> T) -> T {
+    let (mut compressed, mut decompressed) = SCRATCH.take();
+    compressed.clear();
+    decompressed.clear();
+
+    let result = f(&mut compressed, &mut decompressed);
+
+    // Buffers of large variables are released
+    if compressed.capacity() > SCRATCH_MAX {
+        compressed = Vec::new();
+    }
+    if decompressed.capacity() > SCRATCH_MAX {
+        decompressed = Vec::new();
+    }
+    SCRATCH.set((compressed, decompressed));
+    result
+}
+
+#[binrw::parser(reader, endian)]
+fn parse_compressed(num_bytes: u32) -> BinResult<Box<MatVariable7>> {
+    with_scratch(|data, buf| {
+        read_bytes_into(reader, num_bytes as u64, data)?;
+        zlib::decompress(data, buf)?;
+        Ok(Box::new(Cursor::new(buf.as_slice()).read_type(endian)?))
+    })
+}
+
+/// Decompress at most `limit` bytes of the data of a compressed element and parse the contained
+/// variable.
+pub fn decompress_variable(data: &[u8], endian: Endian, limit: u64) -> BinResult<MatVariable7> {
+    with_scratch(|_, buf| {
+        zlib::decompress_limited(data, buf, usize::try_from(limit).unwrap_or(usize::MAX))?;
+        Cursor::new(buf.as_slice()).read_type(endian)
+    })
+}
+
+/// Name of the variable in the data of a compressed element.
+///
+/// Only the header of the contained variable is decompressed. Returns `None` for empty
+/// variables and objects, which don't start with dimensions and name.
+pub fn compressed_variable_name(data: &[u8], endian: Endian) -> BinResult<Option<String>> {
+    let mut zlib = zlib::decoder(data);
 
-    Cursor::new(buf)
+    let data_type = read_u32(&mut zlib, endian)?;
+    let num_bytes = read_u32(&mut zlib, endian)?;
+    if data_type != MatFileDataTypes::MiMATRIX as u32 || num_bytes == 0 {
+        return Ok(None);
+    }
+
+    Ok(matrix_name(&mut zlib, endian)?)
+}
+
+/// Bytes of the element in the data of a compressed element, including its tag.
+///
+/// Only the tag of the contained element is decompressed.
+pub fn decompressed_size(data: &[u8], endian: Endian) -> BinResult<u64> {
+    let mut zlib = zlib::decoder(data);
+
+    let _data_type = read_u32(&mut zlib, endian)?;
+    let num_bytes = read_u32(&mut zlib, endian)?;
+    Ok(8 + num_bytes as u64)
+}
+
+/// Name of an array from the data of its `miMATRIX` element.
+///
+/// Returns `None` for objects, which don't start with dimensions and name.
+pub(crate) fn matrix_name<R: Read>(reader: &mut R, endian: Endian) -> std::io::Result<Option<String>> {
+    let (_, flags) = read_element(reader, endian)?;
+    let class = match endian {
+        Endian::Little => flags.first(),
+        Endian::Big => flags.get(3),
+    };
+    if class == Some(&(MatlabArrayTypes::MxOPAQUECLASS as u8)) {
+        return Ok(None);
+    }
+
+    let _dimensions = read_element(reader, endian)?;
+    let (_, name) = read_element(reader, endian)?;
+
+    Ok(Some(String::from_utf8_lossy(&name).into_owned()))
+}
+
+pub(crate) fn read_u32<R: Read + ?Sized>(reader: &mut R, endian: Endian) -> std::io::Result<u32> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+
+    Ok(match endian {
+        Endian::Big => u32::from_be_bytes(buf),
+        Endian::Little => u32::from_le_bytes(buf),
+    })
+}
+
+/// Read `len` bytes.
+///
+/// The buffer grows with the data actually read, so a corrupt size fails with
+/// [`std::io::ErrorKind::UnexpectedEof`] instead of allocating up to 4 GiB upfront, which would
+/// exhaust the address space of 32-bit targets.
+pub(crate) fn read_bytes<R: Read + ?Sized>(reader: &mut R, len: u64) -> std::io::Result<Vec<u8>> {
+    let mut data = Vec::new();
+    read_bytes_into(reader, len, &mut data)?;
+    Ok(data)
+}
+
+/// Read `len` bytes, appending to `data`, see [`read_bytes`].
+fn read_bytes_into<R: Read + ?Sized>(reader: &mut R, len: u64, data: &mut Vec<u8>) -> std::io::Result<()> {
+    let start = data.len();
+    reader.take(len).read_to_end(data)?;
+    if ((data.len() - start) as u64) < len {
+        return Err(std::io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(())
+}
+
+/// Read a data element, returning its data type and its data without padding.
+pub(crate) fn read_element<R: Read + ?Sized>(
+    reader: &mut R,
+    endian: Endian,
+) -> std::io::Result<(u32, Vec<u8>)> {
+    let tag = read_u32(reader, endian)?;
+    let (data_type, size, small) = match split_small_tag(tag) {
+        Some((data_type, size)) => (data_type, size, true),
+        None => (tag, read_u32(reader, endian)?, false),
+    };
+    if small && size > SMALL_DATA_MAX {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "Small data element declares more than 4 bytes",
+        ));
+    }
+
+    let mut data = read_bytes(reader, size as u64 + padding(size, small) as u64)?;
+    data.truncate(size as usize);
+
+    Ok((data_type, data))
 }
 
 #[binrw::writer(writer, endian)]
 #[allow(clippy::borrowed_box)]
 fn compress_data(value: &Box<MatVariable7>) -> BinResult<()> {
-    // Initialize encoder
-    let mut zlib = ZlibEncoder::new(Vec::new(), Compression::new(9));
-
     // Compress value
     let mut c1 = Cursor::new(vec![]);
-    let _ = value.write_options(&mut c1, endian, ());
-    let _ = zlib.write_all(&c1.into_inner());
-    let compressed = zlib.finish().unwrap();
+    value.write_options(&mut c1, endian, ())?;
+    let compressed = zlib::compress(&c1.into_inner())?;
 
     // Calculate size
-    let size = compressed.len() as u32;
+    let size = u32::try_from(compressed.len()).map_err(|_| Error::AssertFail {
+        pos: writer.stream_position().unwrap_or_default(),
+        message: "Compressed variable exceeds the maximum element size of 4 GiB.".to_string(),
+    })?;
     let mut c2 = Cursor::new(vec![]);
-    let _ = size.write_options(&mut c2, endian, ());
+    size.write_options(&mut c2, endian, ())?;
 
     // Write out
-    let _ = writer.write_all(&c2.into_inner());
-    let _ = writer.write_all(&compressed);
+    writer.write_all(&c2.into_inner())?;
+    writer.write_all(&compressed)?;
 
     Ok(())
 }
@@ -51,7 +186,7 @@ pub struct CompressedArray7 {
     data_type: MatFileDataTypes,
     #[bw(ignore)]
     num_bytes: u32,
-    #[br(map_stream = |inner| uncompress_data(inner.take_seek(num_bytes as u64)))]
+    #[br(parse_with = parse_compressed, args(num_bytes))]
     #[bw(write_with = compress_data)]
     value: Box<MatVariable7>,
 }
@@ -73,11 +208,8 @@ impl CompressedArray7 {
     pub fn value(self) -> MatVariable7 {
         *self.value
     }
-}
-
-impl From<CompressedArray> for CompressedArray7 {
-    fn from(value: CompressedArray) -> Self {
-        Self::new(value.value.deref().clone().into())
+    pub(crate) fn value_mut(&mut self) -> &mut MatVariable7 {
+        &mut self.value
     }
 }
 
@@ -101,6 +233,7 @@ mod tests {
     ];
     const VAR_F64_1: &[f64; 9] = &[11., 22., 33., 44., 55., 66., 77., 88., 99.];
 
+    #[cfg(feature = "deflate")]
     #[test]
     fn deserialize_double_compressed() {
         let mut bin = Cursor::new(&DATA_F64_1);
@@ -110,6 +243,29 @@ mod tests {
         // assert!(val == VAR_F64_1);
     }
 
+    #[cfg(feature = "deflate")]
+    #[test]
+    fn lazy_name_and_value() {
+        let name = compressed_variable_name(&DATA_F64_1[8..], Endian::Little).unwrap();
+        assert_eq!(name, Some("a".to_string()));
+        assert_eq!(decompressed_size(&DATA_F64_1[8..], Endian::Little).unwrap(), 72);
+
+        let value = decompress_variable(&DATA_F64_1[8..], Endian::Little, u64::MAX).unwrap();
+        assert_eq!(value.name(), "a");
+    }
+
+    #[test]
+    fn undecodable_data_is_an_error() {
+        let mut data = DATA_F64_1;
+        data[30] ^= 0xff;
+        assert!(Cursor::new(&data).read_le::<CompressedArray7>().is_err());
+        assert!(decompress_variable(&data[8..], Endian::Little, u64::MAX).is_err());
+
+        // Without a deflate implementation, only stored blocks can be read
+        #[cfg(not(feature = "deflate"))]
+        assert!(Cursor::new(&DATA_F64_1).read_le::<CompressedArray7>().is_err());
+    }
+
     #[test]
     fn serialize_double_compressed() {
         let mut bin = Cursor::new(vec![]);
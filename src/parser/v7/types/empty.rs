@@ -4,9 +4,10 @@ use crate::parser::v7::flags::MatFileDataTypes;
 
 #[binrw]
 #[derive(Debug, Clone)]
-#[br(assert(data_type == MatFileDataTypes::MiMATRIX))]
 pub struct Empty7 {
+    #[br(assert(data_type == MatFileDataTypes::MiMATRIX))]
     #[brw(pad_size_to = 4)]
     data_type: MatFileDataTypes,
+    #[br(assert(num_bytes == 0))]
     num_bytes: u32,
 }
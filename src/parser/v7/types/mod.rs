@@ -7,3 +7,4 @@ pub mod sparse_array;
 pub mod structure;
 pub mod structure_array;
 pub mod subelements;
+pub mod unknown;
@@ -7,8 +7,10 @@
 //! MAT-file.
 //!
 
+use binrw::io::{Seek, Write};
 use binrw::*;
 
+use crate::interface::types::dim::MatDim;
 use crate::interface::types::matlab_types::MatlabType;
 use crate::interface::types::numeric_array::NumericArray;
 use crate::parser::v7::flags::{MatFileDataTypes, MatlabArrayTypes};
@@ -16,21 +18,25 @@ use crate::parser::v7::types::subelements::array_dimensions::ArrayDimensions;
 use crate::parser::v7::types::subelements::array_flags::ArrayProps;
 use crate::parser::v7::types::subelements::array_name::ArrayName;
 use crate::parser::v7::types::subelements::array_numeric_data::array_data::ArrayData;
+use crate::parser::v7::types::subelements::tag::{SMALL_DATA_MAX, TAG_SIZE, element_size, padding};
 
 use super::subelements::array_flags::ArrayFlagBits;
-use super::subelements::array_numeric_data::array_data::ArrayDataNew;
+use super::subelements::array_numeric_data::array_data::{ArrayDataNew, DoubleStorage};
 use super::subelements::array_numeric_data::array_data_value::ArrayDataValueVar;
 
 #[binrw]
 #[derive(Debug, Clone)]
-#[br(assert(data_type == MatFileDataTypes::MiMATRIX &&
-            props.array_class != MatlabArrayTypes::MxCELLCLASS &&
-            props.array_class != MatlabArrayTypes::MxSTRUCTCLASS))]
 pub struct NumericArray7 {
+    #[br(assert(data_type == MatFileDataTypes::MiMATRIX))]
     #[brw(pad_size_to = 4)]
     data_type: MatFileDataTypes,
     #[bw(calc = self.size_data())]
     _num_bytes: u32,
+    // Sparse arrays share the subelements up to the name, their row indices would be read as data
+    #[br(assert(props.array_class != MatlabArrayTypes::MxCELLCLASS &&
+                props.array_class != MatlabArrayTypes::MxSTRUCTCLASS &&
+                props.array_class != MatlabArrayTypes::MxSPARSECLASS &&
+                props.array_flags.undefined_combination(props.array_class).is_none()))]
     props: ArrayProps,
     #[brw(align_after = 8)]
     dimensions: ArrayDimensions,
@@ -57,8 +63,8 @@ impl NumericArray7 {
         num_bytes += self.dimensions.size();
         num_bytes += self.name.size();
         num_bytes += self.value.size();
-        if self.value_cmp.is_some() {
-            num_bytes += self.value.size();
+        if let Some(value_cmp) = &self.value_cmp {
+            num_bytes += value_cmp.size();
         }
 
         num_bytes
@@ -66,20 +72,174 @@ impl NumericArray7 {
     pub fn size(&self) -> usize {
         self.size_data() as usize + 8
     }
-    pub fn value(self) -> (String, Vec<usize>, ArrayDataValueVar, Option<ArrayDataValueVar>) {
+    pub fn storage_type(&self) -> MatFileDataTypes {
+        self.value.data_type()
+    }
+    pub fn value(self) -> (String, MatDim, ArrayDataValueVar, Option<ArrayDataValueVar>) {
         let name = self.name();
-        let dim = self
-            .dimensions
-            .dim()
-            .clone()
-            .iter()
-            .map(|&x| x as usize)
-            .collect();
+        let dim = self.dimensions.to_matdim(self.value.num_values());
         let val = self.value.array_data_value_var();
         let val_cmp = self.value_cmp.map(|v| v.array_data_value_var());
 
         (name, dim, val, val_cmp)
     }
+    /// Bytes of the values after conversion, with as many values as the dimensions declare.
+    ///
+    /// Values stored in a smaller type take the size of the class, characters four bytes each.
+    pub fn declared_bytes(&self) -> u64 {
+        let numel = match self.dimensions.dim() {
+            [] => self.value.num_values() as u64,
+            dim => dim.iter().fold(1u64, |n, &d| n.saturating_mul(d as u64)),
+        };
+        let size = match self.props.array_class {
+            _ if self.props.array_flags.is_logical => 1,
+            MatlabArrayTypes::MxINT8CLASS | MatlabArrayTypes::MxUINT8CLASS => 1,
+            MatlabArrayTypes::MxINT16CLASS | MatlabArrayTypes::MxUINT16CLASS => 2,
+            MatlabArrayTypes::MxCHARCLASS => size_of::<char>() as u64,
+            MatlabArrayTypes::MxSINGLECLASS
+            | MatlabArrayTypes::MxINT32CLASS
+            | MatlabArrayTypes::MxUINT32CLASS => 4,
+            _ => 8,
+        };
+        let parts = if self.value_cmp.is_some() { 2 } else { 1 };
+        numel.saturating_mul(size * parts)
+    }
+    /// Number of elements of the dimensions and number of values stored, if they differ for the
+    /// real or the imaginary part.
+    ///
+    /// Some writers store more or fewer values than the dimensions declare.
+    pub fn length_mismatch(&self) -> Option<(usize, usize)> {
+        if self.dimensions.dim().is_empty() {
+            return None;
+        }
+        let declared = self.dimensions.dim().iter().map(|&x| x as usize).product();
+        std::iter::once(&self.value)
+            .chain(&self.value_cmp)
+            .map(|v| v.num_values())
+            .find(|&stored| stored != declared)
+            .map(|stored| (declared, stored))
+    }
+}
+
+impl NumericArray7 {
+    /// Construct a double array, considering the additional storage types of `storage`, see
+    /// [`ArrayData::from_f64`].
+    pub fn new_f64(
+        dim: Vec<u32>,
+        value: Vec<f64>,
+        value_cmp: Option<Vec<f64>>,
+        storage: DoubleStorage,
+    ) -> NumericArray7 {
+        let props = ArrayProps::new(
+            MatlabArrayTypes::MxDOUBLECLASS,
+            ArrayFlagBits::new(value_cmp.is_some(), false, false),
+            0,
+        );
+
+        Self {
+            data_type: MatFileDataTypes::MiMATRIX,
+            props,
+            dimensions: ArrayDimensions::new(dim),
+            name: ArrayName::new("".to_string()),
+            value: ArrayData::from_f64(value, storage),
+            value_cmp: value_cmp.map(|v| ArrayData::from_f64(v, storage)),
+        }
+    }
+
+    /// Construct a character array of UTF-16 code units, see [`ArrayData::from_utf16_units`].
+    pub fn new_utf16(dim: Vec<u32>, units: Vec<u16>) -> NumericArray7 {
+        let props = ArrayProps::new(
+            MatlabArrayTypes::MxCHARCLASS,
+            ArrayFlagBits::new(false, false, false),
+            0,
+        );
+
+        Self {
+            data_type: MatFileDataTypes::MiMATRIX,
+            props,
+            dimensions: ArrayDimensions::new(dim),
+            name: ArrayName::new("".to_string()),
+            value: ArrayData::from_utf16_units(units),
+            value_cmp: None,
+        }
+    }
+}
+
+impl NumericArray7 {
+    /// Whether `value` can be written with [`NumericArray7::write_scalar`]: a real 1x1 numeric
+    /// or logical array.
+    pub fn is_scalar(value: &NumericArray) -> bool {
+        value.dim == [1, 1]
+            && value.value_cmp.is_none()
+            && !value.utf16_storage
+            && !matches!(value.value, MatlabType::UTF8(_) | MatlabType::UTF16(_))
+    }
+
+    /// Write the scalar `value` called `name`, see [`NumericArray7::is_scalar`].
+    ///
+    /// The element is the one of the converted [`NumericArray7`], but flags, dimensions and name
+    /// are written directly, without constructing them. This is the common case of files with
+    /// thousands of scalar parameters.
+    pub fn write_scalar<W: Write + Seek>(
+        value: NumericArray,
+        name: &str,
+        writer: &mut W,
+        endian: Endian,
+    ) -> BinResult<()> {
+        use MatlabArrayTypes::*;
+        use MatlabType::*;
+
+        let (class, is_logical, data) = match value.value {
+            F64(v) => (MxDOUBLECLASS, false, ArrayData::from_f64(v, value.double_storage)),
+            F32(v) => (MxSINGLECLASS, false, ArrayData::new(v)),
+            U8(v) => (MxUINT8CLASS, false, ArrayData::new(v)),
+            I8(v) => (MxINT8CLASS, false, ArrayData::new(v)),
+            U16(v) => (MxUINT16CLASS, false, ArrayData::new(v)),
+            I16(v) => (MxINT16CLASS, false, ArrayData::new(v)),
+            U32(v) => (MxUINT32CLASS, false, ArrayData::new(v)),
+            I32(v) => (MxINT32CLASS, false, ArrayData::new(v)),
+            U64(v) => (MxUINT64CLASS, false, ArrayData::new(v)),
+            I64(v) => (MxINT64CLASS, false, ArrayData::new(v)),
+            BOOL(v) => (MxUINT8CLASS, true, ArrayData::new(v)),
+            UTF8(_) | UTF16(_) => {
+                return Err(Error::AssertFail {
+                    pos: writer.stream_position()?,
+                    message: "Character arrays are not written as scalars.".to_string(),
+                });
+            }
+        };
+        let props = ArrayProps::new(class, ArrayFlagBits::new(false, false, is_logical), 0);
+
+        // Names of up to 4 bytes use the small data element format, like `ArrayName`
+        let name = name.as_bytes();
+        let small_name = !name.is_empty() && name.len() <= SMALL_DATA_MAX as usize;
+        let name_size = element_size(name.len() as u32, small_name);
+        let num_bytes = props.size() + element_size(8, false) + name_size + data.size();
+
+        let start = writer.stream_position()?;
+        (MatFileDataTypes::MiMATRIX as u32).write_options(writer, endian, ())?;
+        num_bytes.write_options(writer, endian, ())?;
+        props.write_options(writer, endian, ())?;
+        for x in [MatFileDataTypes::MiINT32 as u32, 8, 1, 1] {
+            x.write_options(writer, endian, ())?;
+        }
+        if small_name {
+            (MatFileDataTypes::MiINT8 as u16).write_options(writer, endian, ())?;
+            (name.len() as u16).write_options(writer, endian, ())?;
+        } else {
+            (MatFileDataTypes::MiINT8 as u32).write_options(writer, endian, ())?;
+            (name.len() as u32).write_options(writer, endian, ())?;
+        }
+        writer.write_all(name)?;
+        writer.write_all(&[0; 8][..padding(name.len() as u32, small_name) as usize])?;
+        data.write_options(writer, endian, ())?;
+
+        // Padding of the data, if not written with it
+        let written = writer.stream_position()? - start;
+        let end = (TAG_SIZE + num_bytes) as u64;
+        writer.write_all(&[0; 8][..end.saturating_sub(written) as usize])?;
+        Ok(())
+    }
 }
 
 pub trait NumericArrayNew<T> {
@@ -169,8 +329,10 @@ impl From<NumericArray> for NumericArray7 {
 
         let dim = value.dim.iter().map(|x| *x as u32).collect();
 
-        if value.value.is_empty() {
-            return Self::new(dim, Vec::<u8>::new(), None);
+        if value.utf16_storage
+            && let Some(units) = value.utf16_storage_units()
+        {
+            return Self::new_utf16(dim, units);
         }
 
         match (value.numeric_type(), value.is_complex()) {
@@ -219,10 +381,11 @@ impl From<NumericArray> for NumericArray7 {
                 value.value.inner::<f32>().unwrap(),
                 Some(value.value_cmp.unwrap().inner::<f32>().unwrap()),
             ),
-            (F64(_), true) => Self::new(
+            (F64(_), _) => Self::new_f64(
                 dim,
                 value.value.inner::<f64>().unwrap(),
-                Some(value.value_cmp.unwrap().inner::<f64>().unwrap()),
+                value.value_cmp.map(|v| v.inner::<f64>().unwrap()),
+                value.double_storage,
             ),
             (U8(_), false) => Self::new(dim, value.value.inner::<u8>().unwrap(), None),
             (I8(_), false) => Self::new(dim, value.value.inner::<i8>().unwrap(), None),
@@ -233,11 +396,11 @@ impl From<NumericArray> for NumericArray7 {
             (U64(_), false) => Self::new(dim, value.value.inner::<u64>().unwrap(), None),
             (I64(_), false) => Self::new(dim, value.value.inner::<i64>().unwrap(), None),
             (F32(_), false) => Self::new(dim, value.value.inner::<f32>().unwrap(), None),
-            (F64(_), false) => Self::new(dim, value.value.inner::<f64>().unwrap(), None),
-            (UTF8(_), false) => Self::new(dim, value.value.inner::<char>().unwrap(), None),
-            (UTF16(_), false) => Self::new(dim, value.value.inner::<char>().unwrap(), None),
-            (BOOL(_), false) => Self::new(dim, value.value.inner::<bool>().unwrap(), None),
-            _ => unimplemented!(),
+            // Imaginary parts of character and logical arrays are rejected before saving, they are
+            // dropped here
+            (UTF8(_), _) => Self::new(dim, value.value.inner::<char>().unwrap(), None),
+            (UTF16(_), _) => Self::new(dim, value.value.inner::<char>().unwrap(), None),
+            (BOOL(_), _) => Self::new(dim, value.value.inner::<bool>().unwrap(), None),
         }
     }
 }
@@ -248,6 +411,10 @@ mod tests {
     use binrw::BinReaderExt;
     use binrw::io::Cursor; // A no_std reimplementation of std::io // extension traits for use with readers and writers
 
+    use crate::interface::types::sparse_array::SparseArray;
+    use crate::interface::variable::MatVariable;
+    use crate::parser::v7::variable7::MatVariable7;
+
     /// Binary representation of a mat file containing a variable `var_uint8` with a single u8 value.
     /// To reproduce, in a MATLAB session with a clean workspace run
     /// `>> var_empty = [];`
@@ -286,6 +453,45 @@ mod tests {
         assert_eq!(inner, DATA_EMPTY);
     }
 
+    #[test]
+    fn empty_keeps_class_and_dim() {
+        let arr = NumericArray::new(vec![5, 0], MatlabType::I16(vec![]), None).unwrap();
+        let data = NumericArray7::from(arr);
+        assert_eq!(data.props.array_class, MatlabArrayTypes::MxINT16CLASS);
+        assert_eq!(data.dimensions.dim(), &vec![5, 0]);
+
+        let arr = NumericArray::new(vec![0, 3], MatlabType::BOOL(vec![]), None).unwrap();
+        let data = NumericArray7::from(arr);
+        assert_eq!(data.props.array_class, MatlabArrayTypes::MxUINT8CLASS);
+        assert!(data.props.array_flags.is_logical);
+        assert_eq!(data.dimensions.dim(), &vec![0, 3]);
+    }
+
+    #[test]
+    fn sparse_is_not_numeric() {
+        let arr = SparseArray::new(
+            3,
+            3,
+            vec![2],
+            vec![0, 0, 0, 1],
+            MatlabType::BOOL(vec![true]),
+            None,
+        )
+        .unwrap();
+        let mut bin = Cursor::new(vec![]);
+        MatVariable7::from(MatVariable::SparseArray(arr))
+            .write_le(&mut bin)
+            .unwrap();
+
+        bin.set_position(0);
+        assert!(bin.read_le::<NumericArray7>().is_err());
+        bin.set_position(0);
+        assert!(matches!(
+            bin.read_le::<MatVariable7>().unwrap(),
+            MatVariable7::Sparse(_)
+        ));
+    }
+
     /// Binary representation of a mat file containing a variable `var_uint8` with a single u8 value.
     /// To reproduce, in a MATLAB session with a clean workspace run
     /// `>> var_uint8 = uint8(99);`
@@ -8,12 +8,12 @@ use crate::parser::v7::variable7::MatVariable7;
 
 #[binrw]
 #[derive(Debug, Clone)]
-#[br(assert(data_type == MatFileDataTypes::MiMATRIX &&
-            props.array_class == MatlabArrayTypes::MxOPAQUECLASS))]
 pub struct ObjectMCOS7 {
+    #[br(assert(data_type == MatFileDataTypes::MiMATRIX))]
     #[brw(pad_size_to = 4)]
     data_type: MatFileDataTypes,
     num_bytes: u32,
+    #[br(assert(props.array_class == MatlabArrayTypes::MxOPAQUECLASS))]
     props: ArrayProps,
     #[brw(align_after = 8)]
     name: ArrayName,
@@ -37,12 +37,12 @@ impl ObjectMCOS7 {
 
 #[binrw]
 #[derive(Debug, Clone)]
-#[br(assert(data_type == MatFileDataTypes::MiMATRIX &&
-            props.array_class == MatlabArrayTypes::MxHANDLECLASS))]
 pub struct ObjectHandle7 {
+    #[br(assert(data_type == MatFileDataTypes::MiMATRIX))]
     #[brw(pad_size_to = 4)]
     data_type: MatFileDataTypes,
     num_bytes: u32,
+    #[br(assert(props.array_class == MatlabArrayTypes::MxHANDLECLASS))]
     props: ArrayProps,
     #[brw(align_after = 8)]
     dimensions: ArrayDimensions,
@@ -1,6 +1,7 @@
 use binrw::*;
 
 use super::subelements::array_numeric_data::array_data_value::ArrayDataValueVar;
+use crate::interface::types::dim::MatDim;
 use crate::interface::types::matlab_types::MatlabType;
 use crate::interface::types::sparse_array::SparseArray;
 use crate::parser::v7::flags::{MatFileDataTypes, MatlabArrayTypes};
@@ -11,25 +12,27 @@ use crate::parser::v7::types::subelements::array_name::ArrayName;
 use crate::parser::v7::types::subelements::array_numeric_data::array_data::{
     ArrayDataSparse, ArrayDataSparseNew,
 };
+use crate::parser::v7::types::subelements::sparse_indices::SparseIndices;
 
 #[binrw]
 #[derive(Debug, Clone)]
-#[br(assert(data_type == MatFileDataTypes::MiMATRIX &&
-            props.array_class == MatlabArrayTypes::MxSPARSECLASS))]
 pub struct SparseArray7 {
+    #[br(assert(data_type == MatFileDataTypes::MiMATRIX))]
     #[brw(pad_size_to = 4)]
     data_type: MatFileDataTypes,
     #[bw(calc = self.size_data())]
     _num_bytes: u32,
+    #[br(assert(props.array_class == MatlabArrayTypes::MxSPARSECLASS &&
+                props.array_flags.undefined_combination(props.array_class).is_none()))]
     props: ArrayProps,
     #[brw(align_after = 8)]
     dimensions: ArrayDimensions,
     #[brw(align_after = 8)]
     name: ArrayName,
     #[brw(align_after = 8)]
-    ir: ArrayDimensions,
+    ir: SparseIndices,
     #[brw(align_after = 8)]
-    jc: ArrayDimensions,
+    jc: SparseIndices,
     #[br(args(props.array_flags.is_logical))]
     #[brw(align_after = 8)]
     value: ArrayDataSparse,
@@ -45,8 +48,14 @@ impl SparseArray7 {
     pub fn name(&self) -> String {
         self.name.name()
     }
-    pub fn dim(&self) -> Vec<u32> {
-        self.dimensions.dim().clone()
+    pub fn dim(&self) -> &[u32] {
+        self.dimensions.dim()
+    }
+    pub fn nzmax(&self) -> u32 {
+        self.props.sparse_num
+    }
+    pub fn set_nzmax(&mut self, nzmax: u32) {
+        self.props.sparse_num = nzmax
     }
     pub fn size_data(&self) -> u32 {
         let mut num_bytes = 0;
@@ -56,8 +65,8 @@ impl SparseArray7 {
         num_bytes += self.jc.size();
         num_bytes += self.name.size();
         num_bytes += self.value.size();
-        if self.value_cmp.is_some() {
-            num_bytes += self.value.size();
+        if let Some(value_cmp) = &self.value_cmp {
+            num_bytes += value_cmp.size();
         }
         if self.dimensions.is_empty() {
             num_bytes += 4
@@ -72,22 +81,16 @@ impl SparseArray7 {
         self,
     ) -> (
         String,
-        Vec<usize>,
+        MatDim,
         Vec<usize>,
         Vec<usize>,
         ArrayDataValueVar,
         Option<ArrayDataValueVar>,
     ) {
         let name = self.name();
-        let dim = self
-            .dimensions
-            .dim()
-            .clone()
-            .iter()
-            .map(|&x| x as usize)
-            .collect();
-        let ir = self.ir.dim().clone().iter().map(|&x| x as usize).collect();
-        let jc = self.jc.dim().clone().iter().map(|&x| x as usize).collect();
+        let dim = self.dimensions.to_matdim(0);
+        let ir = self.ir.indices().to_vec();
+        let jc = self.jc.indices().to_vec();
         let val = self.value.array_data_value_var();
         let val_cmp = self.value_cmp.map(|v| v.array_data_value_var());
 
@@ -100,8 +103,8 @@ pub trait SparseArrayNew<T> {
     fn new(
         name: String,
         dim: Vec<u32>,
-        dim_ir: Vec<u32>,
-        dim_jc: Vec<u32>,
+        dim_ir: Vec<usize>,
+        dim_jc: Vec<usize>,
         value: Vec<T>,
         value_cmp: Option<Vec<T>>,
     ) -> SparseArray7;
@@ -113,8 +116,8 @@ macro_rules! impl_SparseArrayNew {
             fn new(
                 name: String,
                 dim: Vec<u32>,
-                dim_ir: Vec<u32>,
-                dim_jc: Vec<u32>,
+                dim_ir: Vec<usize>,
+                dim_jc: Vec<usize>,
                 value: Vec<$t1>,
                 value_cmp: Option<Vec<$t1>>,
             ) -> Self {
@@ -131,8 +134,8 @@ macro_rules! impl_SparseArrayNew {
 
                 let dimensions = ArrayDimensions::new(dim);
                 let name = ArrayName::new(name);
-                let ir = ArrayDimensions::new(dim_ir);
-                let jc = ArrayDimensions::new(dim_jc);
+                let ir = SparseIndices::new(dim_ir);
+                let jc = SparseIndices::new(dim_jc);
                 let value = ArrayDataSparse::new(value);
                 let value_cmp = value_cmp.map(|v| ArrayDataSparse::new(v));
 
@@ -158,8 +161,8 @@ impl SparseArrayNew<bool> for SparseArray7 {
     fn new(
         name: String,
         dim: Vec<u32>,
-        dim_ir: Vec<u32>,
-        dim_jc: Vec<u32>,
+        dim_ir: Vec<usize>,
+        dim_jc: Vec<usize>,
         value: Vec<bool>,
         value_cmp: Option<Vec<bool>>,
     ) -> SparseArray7 {
@@ -176,8 +179,8 @@ impl SparseArrayNew<bool> for SparseArray7 {
 
         let dimensions = ArrayDimensions::new(dim);
         let name = ArrayName::new(name);
-        let ir = ArrayDimensions::new(dim_ir);
-        let jc = ArrayDimensions::new(dim_jc);
+        let ir = SparseIndices::new(dim_ir);
+        let jc = SparseIndices::new(dim_jc);
         let value = ArrayDataSparse::new(value);
         let value_cmp = value_cmp.map(ArrayDataSparse::new);
 
@@ -199,10 +202,11 @@ impl From<SparseArray> for SparseArray7 {
         use MatlabType::*;
 
         let dim = value.dim.iter().map(|x| *x as u32).collect();
-        let ir = value.ir.iter().map(|x| *x as u32).collect();
-        let jc = value.jc.iter().map(|x| *x as u32).collect();
+        let ir = value.ir.clone();
+        let jc = value.jc.clone();
+        let nzmax = value.nzmax() as u32;
 
-        match (value.numeric_type(), value.is_complex()) {
+        let mut sparse = match (value.numeric_type(), value.is_complex()) {
             (F64(_), true) => Self::new(
                 "".to_string(),
                 dim,
@@ -219,7 +223,8 @@ impl From<SparseArray> for SparseArray7 {
                 value.value.inner::<f64>().unwrap(),
                 None,
             ),
-            (BOOL(_), false) => Self::new(
+            // The imaginary part of a logical array is rejected before saving, it is dropped here
+            (BOOL(_), _) => Self::new(
                 "".to_string(),
                 dim,
                 ir,
@@ -228,7 +233,10 @@ impl From<SparseArray> for SparseArray7 {
                 None,
             ),
             _ => unimplemented!(),
-        }
+        };
+        sparse.set_nzmax(nzmax);
+
+        sparse
     }
 }
 
@@ -303,4 +311,33 @@ mod tests {
         }
         assert!(val_cmp.is_none());
     }
+
+    /// `a = sparse([3], [3], [1.0], 3, 3)` with row and column indices stored as `miINT64`, like
+    /// writers other than MATLAB store them for large matrices
+    const DATA_INT64_INDICES: &[u8; 120] = &[
+        0x0e, 0x00, 0x00, 0x00, 0x70, 0x00, 0x00, 0x00, 0x06, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x05,
+        0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x03, 0x00,
+        0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x61, 0x00, 0x00, 0x00, 0x0c, 0x00, 0x00,
+        0x00, 0x08, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x0c, 0x00, 0x00, 0x00,
+        0x20, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x09, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xf0,
+        0x3f,
+    ];
+
+    #[test]
+    fn sparse_array_int64_indices() {
+        let data = Cursor::new(DATA_INT64_INDICES).read_le::<SparseArray7>().unwrap();
+        assert_eq!(data.size(), DATA_INT64_INDICES.len());
+
+        // Written as read
+        let mut written = Cursor::new(vec![]);
+        data.write_le(&mut written).unwrap();
+        assert_eq!(written.into_inner(), DATA_INT64_INDICES);
+
+        let sparse = SparseArray::from(data);
+        assert_eq!(sparse.ir, vec![2]);
+        assert_eq!(sparse.jc, vec![0, 0, 0, 1]);
+        assert_eq!(sparse.value, MatlabType::F64(vec![1.0]));
+    }
 }
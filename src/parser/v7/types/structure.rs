@@ -12,13 +12,13 @@ use super::subelements::array_flags::ArrayFlagBits;
 
 #[binrw]
 #[derive(Debug, Clone)]
-#[br(assert(data_type == MatFileDataTypes::MiMATRIX &&
-            props.array_class == MatlabArrayTypes::MxSTRUCTCLASS))]
 pub struct Structure7 {
+    #[br(assert(data_type == MatFileDataTypes::MiMATRIX))]
     #[brw(pad_size_to = 4)]
     data_type: MatFileDataTypes,
     #[bw(calc = self.size_data())]
     _num_bytes: u32,
+    #[br(assert(props.array_class == MatlabArrayTypes::MxSTRUCTCLASS))]
     props: ArrayProps,
     #[brw(align_after = 8)]
     #[br(assert(dimensions.dim().iter().product::<u32>() == 1))]
@@ -27,7 +27,7 @@ pub struct Structure7 {
     name: ArrayName,
     #[brw(align_after = 8)]
     fieldnames: ArrayFieldNames,
-    #[br(count = fieldnames.fieldnames().len())]
+    #[br(count = fieldnames.field_count())]
     value: Vec<MatVariable7>,
 }
 
@@ -80,6 +80,12 @@ impl Structure7 {
     pub fn value(self) -> Vec<MatVariable7> {
         self.value
     }
+    pub fn elements(&self) -> &[MatVariable7] {
+        &self.value
+    }
+    pub fn elements_mut(&mut self) -> &mut [MatVariable7] {
+        &mut self.value
+    }
 }
 
 impl From<Structure> for Structure7 {
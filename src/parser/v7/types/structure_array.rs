@@ -12,13 +12,13 @@ use super::subelements::array_flags::ArrayFlagBits;
 
 #[binrw]
 #[derive(Debug, Clone)]
-#[br(assert(data_type == MatFileDataTypes::MiMATRIX &&
-            props.array_class == MatlabArrayTypes::MxSTRUCTCLASS))]
 pub struct StructureArray7 {
+    #[br(assert(data_type == MatFileDataTypes::MiMATRIX))]
     #[brw(pad_size_to = 4)]
     data_type: MatFileDataTypes,
     #[bw(calc = self.size_data())]
     _num_bytes: u32,
+    #[br(assert(props.array_class == MatlabArrayTypes::MxSTRUCTCLASS))]
     props: ArrayProps,
     #[brw(align_after = 8)]
     dimensions: ArrayDimensions,
@@ -26,7 +26,7 @@ pub struct StructureArray7 {
     name: ArrayName,
     #[brw(align_after = 8)]
     fieldnames: ArrayFieldNames,
-    #[br(count = fieldnames.field_number*dimensions.dim().iter().product::<u32>(), align_after = 8)]
+    #[br(count = fieldnames.field_count() * dimensions.dim().iter().product::<u32>() as usize, align_after = 8)]
     value: Vec<MatVariable7>,
 }
 
@@ -73,7 +73,7 @@ impl StructureArray7 {
     pub fn size(&self) -> usize {
         self.size_data() as usize + 8
     }
-    pub fn dim(&self) -> &Vec<u32> {
+    pub fn dim(&self) -> &[u32] {
         self.dimensions.dim()
     }
     pub fn fieldnames(&self) -> Vec<String> {
@@ -82,12 +82,18 @@ impl StructureArray7 {
     pub fn value(self) -> Vec<MatVariable7> {
         self.value
     }
+    pub fn elements(&self) -> &[MatVariable7] {
+        &self.value
+    }
+    pub fn elements_mut(&mut self) -> &mut [MatVariable7] {
+        &mut self.value
+    }
 }
 
 impl From<StructureArray> for StructureArray7 {
     fn from(value: StructureArray) -> Self {
         let fieldnames = value.fieldnames();
-        let dim = value.dim.into_iter().map(|x| x as u32).collect();
+        let dim = value.dim.iter().map(|&x| x as u32).collect();
         let mut val = vec![];
         for v in value.value.into_iter() {
             for w in fieldnames.clone().into_iter() {
@@ -1,8 +1,12 @@
 //! Module containing types for matching *Array Dimension Subelements*.
 
+use std::ops::Deref;
+
 use binrw::*;
 
+use crate::interface::types::dim::MatDim;
 use crate::parser::v7::flags::MatFileDataTypes;
+use crate::parser::v7::types::subelements::tag::{SMALL_DATA_MAX, element_size};
 
 #[binrw]
 #[derive(Debug, Clone)]
@@ -22,23 +26,76 @@ impl ArrayDimensions {
     pub fn size(&self) -> u32 {
         match self {
             ArrayDimensions::DataNormal(v) => v.size(),
-            ArrayDimensions::DataSmall(v) => v.size() as u32,
+            ArrayDimensions::DataSmall(v) => v.size(),
         }
     }
-    pub fn dim(&self) -> &Vec<u32> {
+    pub fn dim(&self) -> &[u32] {
         match self {
             ArrayDimensions::DataNormal(v) => &v.dimensions,
             ArrayDimensions::DataSmall(v) => &v.dimensions,
         }
     }
+    /// Dimensions as [`MatDim`], without any stored dimensions those of a row of `numel`
+    /// elements.
+    pub fn to_matdim(&self, numel: usize) -> MatDim {
+        match self.dim() {
+            [] => MatDim::from_slice(&[1, numel]),
+            dim => dim.iter().map(|&x| x as usize).collect(),
+        }
+    }
     pub fn is_empty(&self) -> bool {
         match self {
-            ArrayDimensions::DataNormal(v) => v.dimensions.clone().into_iter().product::<u32>() == 0,
-            ArrayDimensions::DataSmall(v) => v.dimensions.clone().into_iter().product::<u32>() == 0,
+            ArrayDimensions::DataNormal(v) => v.dimensions.iter().product::<u32>() == 0,
+            ArrayDimensions::DataSmall(v) => v.dimensions.iter().product::<u32>() == 0,
         }
     }
 }
 
+/// Number of dimensions stored without allocation, like [`crate::MatDim`] does
+const INLINE: usize = 4;
+
+/// Dimensions as stored in the file, up to four of them without allocation
+#[derive(Debug, Clone)]
+pub enum Dimensions {
+    Inline(u8, [u32; INLINE]),
+    Heap(Vec<u32>),
+}
+
+impl Deref for Dimensions {
+    type Target = [u32];
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Dimensions::Inline(len, dims) => &dims[..*len as usize],
+            Dimensions::Heap(dims) => dims,
+        }
+    }
+}
+
+impl From<Vec<u32>> for Dimensions {
+    fn from(dim: Vec<u32>) -> Self {
+        Dimensions::Heap(dim)
+    }
+}
+
+#[parser(reader, endian)]
+fn parse_dimensions(count: usize) -> BinResult<Dimensions> {
+    if count > INLINE {
+        let args = VecArgs::builder().count(count).finalize();
+        return Vec::<u32>::read_options(reader, endian, args).map(Dimensions::Heap);
+    }
+    let mut dims = [0; INLINE];
+    for dim in dims.iter_mut().take(count) {
+        *dim = u32::read_options(reader, endian, ())?;
+    }
+    Ok(Dimensions::Inline(count as u8, dims))
+}
+
+#[writer(writer, endian)]
+fn write_dimensions(dim: &Dimensions) -> BinResult<()> {
+    dim.deref().write_options(writer, endian, ())
+}
+
 #[derive(Debug, Clone)]
 #[binrw]
 pub struct ArrayDimensionsNormal {
@@ -51,8 +108,9 @@ pub struct ArrayDimensionsNormal {
     /// Number of bytes tag
     data_size: u32,
     /// Array dimensions
-    #[br(count = (data_size / 4) as usize)]
-    dimensions: Vec<u32>,
+    #[br(parse_with = parse_dimensions, args((data_size / 4) as usize))]
+    #[bw(write_with = write_dimensions)]
+    dimensions: Dimensions,
 }
 
 impl ArrayDimensionsNormal {
@@ -61,11 +119,11 @@ impl ArrayDimensionsNormal {
             data_type: MatFileDataTypes::MiINT32,
             check: 0_u16,
             data_size: (4 * dim.len()) as u32,
-            dimensions: dim,
+            dimensions: dim.into(),
         }
     }
     pub fn size(&self) -> u32 {
-        8 + self.data_size + self.data_size % 8
+        element_size(self.data_size, false)
     }
 }
 
@@ -77,10 +135,12 @@ pub struct ArrayDimensionsSmall {
     #[br(assert(data_type == MatFileDataTypes::MiINT32))]
     data_type: MatFileDataTypes,
     /// Number of bytes tag
+    #[br(assert(data_size as u32 <= SMALL_DATA_MAX))]
     data_size: u16,
     /// Array dimensions
-    #[br(count = (data_size / 4) as usize)]
-    dimensions: Vec<u32>,
+    #[br(parse_with = parse_dimensions, args((data_size / 4) as usize))]
+    #[bw(write_with = write_dimensions)]
+    dimensions: Dimensions,
 }
 
 impl ArrayDimensionsSmall {
@@ -88,11 +148,11 @@ impl ArrayDimensionsSmall {
         Self {
             data_type: MatFileDataTypes::MiINT32,
             data_size: (4 * dim.len()) as u16,
-            dimensions: dim,
+            dimensions: dim.into(),
         }
     }
-    pub fn size(&self) -> u16 {
-        4 + self.data_size
+    pub fn size(&self) -> u32 {
+        element_size(self.data_size as u32, true)
     }
 }
 
@@ -108,11 +168,11 @@ mod tests {
         let mut bin = Cursor::new(b"\x05\x00\x00\x00\x08\x00\x00\x00\x02\x00\x00\x00\x03\x00\x00\x00");
         let data = bin.read_le::<ArrayDimensions>().unwrap();
         println!("Deserialized data: {:#?}", &data);
-        assert!(data.dim() == &vec![2, 3]);
+        assert!(data.dim() == [2, 3]);
 
         // Serialize
         let mut bin_new = Cursor::new(vec![]);
-        let data_new = ArrayDimensions::new(data.dim().clone());
+        let data_new = ArrayDimensions::new(data.dim().to_vec());
         data_new.write_le(&mut bin_new).unwrap();
         println!("Serialized data: {:?}", bin_new);
         assert!(bin_new.into_inner().to_vec() == bin.into_inner().to_vec());
@@ -124,11 +184,11 @@ mod tests {
         let mut bin = Cursor::new(b"\x05\x00\x00\x00\x08\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00");
         let data = bin.read_le::<ArrayDimensions>().unwrap();
         println!("Deserialized data: {:#?}", &data);
-        assert!(data.dim() == &vec![0, 0]);
+        assert!(data.dim() == [0, 0]);
 
         // Serialize
         let mut bin_new = Cursor::new(vec![]);
-        let data_new = ArrayDimensions::new(data.dim().clone());
+        let data_new = ArrayDimensions::new(data.dim().to_vec());
         data_new.write_le(&mut bin_new).unwrap();
         println!("Serialized data: {:?}", bin_new);
         assert!(bin_new.into_inner().to_vec() == bin.into_inner().to_vec());
@@ -141,11 +201,11 @@ mod tests {
             Cursor::new(b"\x05\x00\x00\x00\x0c\x00\x00\x00\x02\x00\x00\x00\x03\x00\x00\x00\x02\x00\x00\x00");
         let data = bin.read_le::<ArrayDimensions>().unwrap();
         println!("Deserialized data: {:#?}", &data);
-        assert!(*data.dim() == vec![2, 3, 2]);
+        assert!(data.dim() == [2, 3, 2]);
 
         // Serialize
         let mut bin_new = Cursor::new(vec![]);
-        let data_new = ArrayDimensions::new(data.dim().clone());
+        let data_new = ArrayDimensions::new(data.dim().to_vec());
         data_new.write_le(&mut bin_new).unwrap();
         println!("Serialized data: {:?}", bin_new);
         assert!(bin_new.into_inner().to_vec() == bin.into_inner().to_vec());
@@ -5,19 +5,7 @@ use binrw::*;
 use std::fmt::Debug;
 
 use crate::parser::v7::flags::MatFileDataTypes;
-
-#[parser(reader)]
-fn parse_fieldnames(field_number: u32, length: u32) -> BinResult<Vec<Vec<u8>>> {
-    let mut v = Vec::new();
-
-    for _ in 0..field_number {
-        let mut buf = vec![0; length as usize];
-        reader.read_exact(&mut buf).unwrap();
-
-        v.push(buf);
-    }
-    Ok(v)
-}
+use crate::parser::v7::types::subelements::tag::element_size;
 
 #[derive(Clone)]
 #[binrw]
@@ -41,10 +29,10 @@ pub struct ArrayFieldNames {
     #[br(calc = data_size / length)]
     #[bw(ignore)]
     pub field_number: u32,
-    /// Data
-    #[br(parse_with = parse_fieldnames, args(field_number, length))]
+    /// Field names of `length` bytes each, padded with null bytes, in a single buffer
+    #[br(count = (field_number * length) as usize)]
     #[bw(pad_size_to = 8, if(*field_number > 0))]
-    field_names: Vec<Vec<u8>>,
+    field_names: Vec<u8>,
 }
 
 impl ArrayFieldNames {
@@ -71,7 +59,7 @@ impl ArrayFieldNames {
                 max_length = 3;
             }
 
-            let mut field_names_conv = vec![];
+            let mut field_names_conv = Vec::with_capacity(max_length * field_names.len());
             for field_name in field_names.iter() {
                 let mut name_str = field_name.clone();
 
@@ -82,10 +70,9 @@ impl ArrayFieldNames {
                 // (leave one for the null-termination byte)
                 name_str.truncate(63);
 
-                let v_small = name_str.into_bytes();
-                let mut v_large: Vec<u8> = vec![0; max_length];
-                v_large.splice(0..v_small.len(), v_small);
-                field_names_conv.push(v_large);
+                let end = field_names_conv.len() + max_length;
+                field_names_conv.extend(name_str.into_bytes());
+                field_names_conv.resize(end, 0);
             }
 
             Self {
@@ -110,25 +97,25 @@ impl ArrayFieldNames {
         }
     }
     pub fn size(&self) -> u32 {
-        let padding = if self.data_size.is_multiple_of(8) {
-            0
-        } else {
-            8 - self.data_size % 8
-        };
-        16 + self.data_size + padding
+        // Small element of the field name length, followed by the field names
+        element_size(self.data_size_length as u32, true) + element_size(self.data_size, false)
+    }
+    /// Field names as stored, with their padding
+    fn padded_names(&self) -> impl Iterator<Item = &[u8]> {
+        self.field_names.chunks(self.length.max(1) as usize)
+    }
+    pub fn field_count(&self) -> usize {
+        self.field_number as usize
     }
     pub fn fieldnames(&self) -> Vec<String> {
-        let mut v = Vec::new();
-
-        for buf in &self.field_names {
-            let name = String::from_utf8(buf.clone())
-                .unwrap()
-                .trim_matches(char::from(0))
-                .to_string();
-            v.push(name);
-        }
-
-        v
+        self.padded_names()
+            .map(|name| {
+                std::str::from_utf8(name)
+                    .unwrap()
+                    .trim_matches(char::from(0))
+                    .to_string()
+            })
+            .collect()
     }
 }
 
@@ -141,11 +128,7 @@ impl Debug for ArrayFieldNames {
         dbs.field("data_type", &self.data_type);
         dbs.field("data_size", &self.data_size);
         dbs.field("field_number", &self.field_number);
-        let mut v = Vec::new();
-        for fname in self.field_names.iter() {
-            let s = String::from_utf8(fname.clone()).unwrap();
-            v.push(s);
-        }
+        let v: Vec<_> = self.padded_names().map(String::from_utf8_lossy).collect();
         dbs.field("field_names", &v);
         dbs.finish()
     }
@@ -182,12 +165,7 @@ mod tests {
         println!("Deserialized data: {:#?}", &data);
         assert!(data.field_number == 1);
 
-        let field_names = vec![
-            String::from_utf8(data.field_names[0].clone())
-                .unwrap()
-                .trim_matches(char::from(0))
-                .to_string(),
-        ];
+        let field_names = data.fieldnames();
         assert!(field_names[0] == "a");
 
         // Serialize
@@ -207,16 +185,7 @@ mod tests {
         println!("Deserialized data: {:#?}", &data);
         assert!(data.field_number == 2);
 
-        let field_names = vec![
-            String::from_utf8(data.field_names[0].clone())
-                .unwrap()
-                .trim_matches(char::from(0))
-                .to_string(),
-            String::from_utf8(data.field_names[1].clone())
-                .unwrap()
-                .trim_matches(char::from(0))
-                .to_string(),
-        ];
+        let field_names = data.fieldnames();
         assert!(field_names[0] == "a");
         assert!(field_names[1] == "b");
 
@@ -237,16 +206,7 @@ mod tests {
         println!("Deserialized data: {:#?}", &data);
         assert!(data.field_number == 2);
 
-        let field_names = vec![
-            String::from_utf8(data.field_names[0].clone())
-                .unwrap()
-                .trim_matches(char::from(0))
-                .to_string(),
-            String::from_utf8(data.field_names[1].clone())
-                .unwrap()
-                .trim_matches(char::from(0))
-                .to_string(),
-        ];
+        let field_names = data.fieldnames();
         assert!(field_names[0] == "long_field_name");
         assert!(field_names[1] == "looooooonger_field_name");
 
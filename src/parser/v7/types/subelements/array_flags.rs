@@ -48,6 +48,32 @@ impl ArrayFlagBits {
             is_logical,
         }
     }
+
+    /// Flags of the flags byte `raw` of the array flags subelement
+    pub fn from_bits(raw: u8) -> Self {
+        Self::new(
+            raw & 0b00001000 != 0,
+            raw & 0b00000100 != 0,
+            raw & 0b00000010 != 0,
+        )
+    }
+
+    /// Description of the arrays of class `class` with these flags, if MATLAB does not define
+    /// them: complex logical and complex character arrays.
+    ///
+    /// Arrays with these flags are skipped when read. Other combinations are read: the logical
+    /// flag makes an array of any numeric or character class logical, the complex and logical
+    /// flags of cell and structure arrays are ignored, like the global flag of all arrays.
+    pub fn undefined_combination(&self, class: MatlabArrayTypes) -> Option<&'static str> {
+        use MatlabArrayTypes::*;
+
+        match (self.is_complex, self.is_logical, class) {
+            (_, _, MxCELLCLASS | MxSTRUCTCLASS | MxOBJECTCLASS) => None,
+            (true, true, _) => Some("complex logical"),
+            (true, false, MxCHARCLASS) => Some("complex character"),
+            _ => None,
+        }
+    }
 }
 
 impl Debug for ArrayFlagBits {
@@ -315,4 +341,34 @@ mod tests {
         println!("  Serialized data: {:?}", bin_new);
         assert!(bin_new.into_inner().to_vec() == bin.into_inner().to_vec());
     }
+
+    #[test]
+    fn undefined_combinations() {
+        use MatlabArrayTypes::*;
+
+        assert_eq!(
+            ArrayFlagBits::from_bits(0x0a).undefined_combination(MxUINT8CLASS),
+            Some("complex logical")
+        );
+        assert_eq!(
+            ArrayFlagBits::from_bits(0x0e).undefined_combination(MxCHARCLASS),
+            Some("complex logical")
+        );
+        assert_eq!(
+            ArrayFlagBits::from_bits(0x08).undefined_combination(MxCHARCLASS),
+            Some("complex character")
+        );
+        assert_eq!(
+            ArrayFlagBits::from_bits(0x08).undefined_combination(MxDOUBLECLASS),
+            None
+        );
+        assert_eq!(
+            ArrayFlagBits::from_bits(0x02).undefined_combination(MxCHARCLASS),
+            None
+        );
+        assert_eq!(
+            ArrayFlagBits::from_bits(0x0e).undefined_combination(MxCELLCLASS),
+            None
+        );
+    }
 }
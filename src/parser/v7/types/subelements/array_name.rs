@@ -4,6 +4,7 @@ use binrw::*;
 use std::fmt::Debug;
 
 use crate::parser::v7::flags::MatFileDataTypes;
+use crate::parser::v7::types::subelements::tag::{SMALL_DATA_MAX, element_size};
 
 #[binrw]
 #[derive(Debug, Clone)]
@@ -33,24 +34,9 @@ impl ArrayName {
     }
     pub fn size(&self) -> u32 {
         match self {
-            ArrayName::Empty(_) => 8,
-            ArrayName::Normal(s) => {
-                let padding = if (s.data_size % 8) == 0 {
-                    0
-                } else {
-                    8 - s.data_size % 8
-                };
-
-                8 + s.data_size + padding
-            }
-            ArrayName::Small(s) => {
-                let padding = if (s.data_size % 4) == 0 {
-                    0
-                } else {
-                    4 - s.data_size % 4
-                };
-                (4 + s.data_size + padding) as u32
-            }
+            ArrayName::Empty(_) => element_size(0, false),
+            ArrayName::Normal(s) => element_size(s.data_size, false),
+            ArrayName::Small(s) => element_size(s.data_size as u32, true),
         }
     }
 }
@@ -60,12 +46,18 @@ impl ArrayName {
 #[derive(Clone)]
 pub struct ArrayNameEmpty {
     /// Temporary field to distinguish from *Small Data Element Format*
-    #[brw(restore_position)]
+    #[br(restore_position)]
     #[br(assert(check == 1))]
+    #[bw(ignore)]
     check: u64,
     /// Data type tag
     #[br(assert(data_type == MatFileDataTypes::MiINT8))]
+    #[bw(pad_size_to = 4)]
     data_type: MatFileDataTypes,
+    /// Number of bytes tag, written instead of seeking back over `check`, so that names can be
+    /// written to streams. When read, it is skipped by the alignment after the name.
+    #[br(ignore)]
+    data_size: u32,
 }
 
 impl ArrayNameEmpty {
@@ -73,6 +65,7 @@ impl ArrayNameEmpty {
         Self {
             check: 1,
             data_type: MatFileDataTypes::MiINT8,
+            data_size: 0,
         }
     }
 }
@@ -144,6 +137,7 @@ pub struct ArrayNameSmall {
     #[brw(pad_size_to = 2)]
     data_type: MatFileDataTypes,
     /// Number of bytes tag
+    #[br(assert(data_size as u32 <= SMALL_DATA_MAX))]
     data_size: u16,
     /// Data
     #[br(count = data_size as u32)]
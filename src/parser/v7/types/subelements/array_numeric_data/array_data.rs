@@ -7,6 +7,7 @@ use super::array_data_normal::{ArrayDataNormal, ArrayDataSparseNormal};
 use super::array_data_small::{ArrayDataSmall, ArrayDataSparseSmall};
 use super::array_data_value::*;
 use crate::parser::v7::flags::{MatFileDataTypes, MatlabArrayTypes};
+use crate::parser::v7::types::subelements::tag::element_size;
 
 #[binrw]
 #[derive(Debug, Clone)]
@@ -23,6 +24,13 @@ impl ArrayData {
             ArrayData::DataSmall(d) => d.value,
         }
     }
+    /// Data type tag the values are stored with in the MAT-file
+    pub fn data_type(&self) -> MatFileDataTypes {
+        match self {
+            ArrayData::DataNormal(d) => d.data_type,
+            ArrayData::DataSmall(d) => d.data_type,
+        }
+    }
 }
 
 pub trait ArrayDataNew<T> {
@@ -101,6 +109,23 @@ impl ArrayDataNew<char> for ArrayData {
     }
 }
 
+impl ArrayData {
+    /// Character data of UTF-16 code units, stored as `miUINT16` like MATLAB's `-v6` format.
+    pub fn from_utf16_units(value: Vec<u16>) -> ArrayData {
+        let nelem = value.len();
+
+        let data_type = MatFileDataTypes::MiUINT16;
+        let data_size = std::mem::size_of::<u16>() * nelem;
+        let value_wrapped = ArrayDataValueVar::ArrayValueUTF16Units(value);
+
+        if nelem > 2 {
+            ArrayData::DataNormal(ArrayDataNormal::new(data_type, data_size as u32, value_wrapped))
+        } else {
+            ArrayData::DataSmall(ArrayDataSmall::new(data_type, data_size as u16, value_wrapped))
+        }
+    }
+}
+
 impl ArrayDataNew<f32> for ArrayData {
     fn new(value: Vec<f32>) -> ArrayData {
         let nelem = value.len();
@@ -117,62 +142,41 @@ impl ArrayDataNew<f32> for ArrayData {
     }
 }
 
-impl ArrayDataNew<f64> for ArrayData {
-    fn new(value: Vec<f64>) -> ArrayData {
-        let mut can_be_u8 = true;
-        let mut can_be_i8 = true;
-        let mut can_be_u16 = true;
-        let mut can_be_i16 = true;
-        let mut can_be_u32 = true;
-        let mut can_be_i32 = true;
-
-        // Test for each f64 array element, if it can be represented by a
-        // smaller integer type.
-        for e in &value {
-            if e.fract() != 0.0 {
-                // Check if any element has fractional part
-                can_be_u8 = false;
-                can_be_i8 = false;
-                can_be_u16 = false;
-                can_be_i16 = false;
-                can_be_u32 = false;
-                can_be_i32 = false;
-
-                break;
-            }
+/// Return if every element of `$value` converts to `$t` and back to an identical bit pattern.
+///
+/// This rules out fractional and out of range values, but also `NaN`, infinities and `-0.0`,
+/// which would otherwise be stored as an integer and read back as a different number.
+macro_rules! exact_as {
+    ($value: expr, $t: ty) => {
+        $value
+            .iter()
+            .all(|&e| ((e as $t) as f64).to_bits() == e.to_bits())
+    };
+}
 
-            // Check u8 bounds
-            if can_be_u8 && (*e < u8::MIN as f64 || *e > u8::MAX as f64) {
-                can_be_u8 = false;
-            }
-            // Check i8 bounds
-            if can_be_i8 && (*e < i8::MIN as f64 || *e > i8::MAX as f64) {
-                can_be_i8 = false;
-            }
-            // Check u16 bounds
-            if can_be_u16 && (*e < u16::MIN as f64 || *e > u16::MAX as f64) {
-                can_be_u16 = false;
-            }
-            // Check i16 bounds
-            if can_be_i16 && (*e < i16::MIN as f64 || *e > i16::MAX as f64) {
-                can_be_i16 = false;
-            }
-            // Check u32 bounds
-            if can_be_u32 && (*e < u32::MIN as f64 || *e > u32::MAX as f64) {
-                can_be_u32 = false;
-            }
-            // Check i32 bounds
-            if can_be_i32 && (*e < i32::MIN as f64 || *e > i32::MAX as f64) {
-                can_be_i32 = false;
-            }
+/// Additional storage types considered for double data
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DoubleStorage {
+    /// Integral data not fitting into 32-bit integers is stored as `miUINT64` or `miINT64`
+    pub int64: bool,
+    /// Data exactly representable in single precision is stored as `miSINGLE`
+    pub single: bool,
+}
 
-            // Early exit if no integer possible
-            if !can_be_u8 && !can_be_i8 && !can_be_u16 && !can_be_i16 && !can_be_u32 && !can_be_i32 {
-                break;
-            }
-        }
+impl ArrayDataNew<f64> for ArrayData {
+    fn new(value: Vec<f64>) -> ArrayData {
+        ArrayData::from_f64(value, DoubleStorage::default())
+    }
+}
 
-        let (data_type, value_wrapped, data_size, is_normal) = if can_be_u8 {
+impl ArrayData {
+    /// Construct double data, stored with the smallest integer type representing all values
+    /// exactly.
+    ///
+    /// MATLAB considers 32-bit integer types only. `storage` enables further candidates, which
+    /// are also only chosen, if they represent all values exactly.
+    pub fn from_f64(value: Vec<f64>, storage: DoubleStorage) -> ArrayData {
+        let (data_type, value_wrapped, data_size, is_normal) = if exact_as!(value, u8) {
             let data_type = MatFileDataTypes::MiUINT8;
             let value_new: Vec<u8> = value.iter().map(|&x| x as u8).collect();
             let nelem = value_new.len();
@@ -184,7 +188,7 @@ impl ArrayDataNew<f64> for ArrayData {
                 data_size,
                 is_normal,
             )
-        } else if can_be_i8 {
+        } else if exact_as!(value, i8) {
             let data_type = MatFileDataTypes::MiINT8;
             let value_new: Vec<i8> = value.iter().map(|&x| x as i8).collect();
             let nelem = value_new.len();
@@ -196,7 +200,7 @@ impl ArrayDataNew<f64> for ArrayData {
                 data_size,
                 is_normal,
             )
-        } else if can_be_u16 {
+        } else if exact_as!(value, u16) {
             let data_type = MatFileDataTypes::MiUINT16;
             let value_new: Vec<u16> = value.iter().map(|&x| x as u16).collect();
             let nelem = value_new.len();
@@ -208,7 +212,7 @@ impl ArrayDataNew<f64> for ArrayData {
                 data_size,
                 is_normal,
             )
-        } else if can_be_i16 {
+        } else if exact_as!(value, i16) {
             let data_type = MatFileDataTypes::MiINT16;
             let value_new: Vec<i16> = value.iter().map(|&x| x as i16).collect();
             let nelem = value_new.len();
@@ -220,7 +224,7 @@ impl ArrayDataNew<f64> for ArrayData {
                 data_size,
                 is_normal,
             )
-        } else if can_be_u32 {
+        } else if exact_as!(value, u32) {
             let data_type = MatFileDataTypes::MiUINT32;
             let value_new: Vec<u32> = value.iter().map(|&x| x as u32).collect();
             let nelem = value_new.len();
@@ -232,7 +236,7 @@ impl ArrayDataNew<f64> for ArrayData {
                 data_size,
                 is_normal,
             )
-        } else if can_be_i32 {
+        } else if exact_as!(value, i32) {
             let data_type = MatFileDataTypes::MiINT32;
             let value_new: Vec<i32> = value.iter().map(|&x| x as i32).collect();
             let nelem = value_new.len();
@@ -244,6 +248,42 @@ impl ArrayDataNew<f64> for ArrayData {
                 data_size,
                 is_normal,
             )
+        } else if storage.int64 && exact_as!(value, u64) {
+            let data_type = MatFileDataTypes::MiUINT64;
+            let value_new: Vec<u64> = value.iter().map(|&x| x as u64).collect();
+            let nelem = value_new.len();
+            let data_size = std::mem::size_of::<u64>() * nelem;
+            let is_normal = nelem > 0;
+            (
+                data_type,
+                ArrayDataValueVar::ArrayValueU64(value_new),
+                data_size,
+                is_normal,
+            )
+        } else if storage.int64 && exact_as!(value, i64) {
+            let data_type = MatFileDataTypes::MiINT64;
+            let value_new: Vec<i64> = value.iter().map(|&x| x as i64).collect();
+            let nelem = value_new.len();
+            let data_size = std::mem::size_of::<i64>() * nelem;
+            let is_normal = nelem > 0;
+            (
+                data_type,
+                ArrayDataValueVar::ArrayValueI64(value_new),
+                data_size,
+                is_normal,
+            )
+        } else if storage.single && exact_as!(value, f32) {
+            let data_type = MatFileDataTypes::MiSINGLE;
+            let value_new: Vec<f32> = value.iter().map(|&x| x as f32).collect();
+            let nelem = value_new.len();
+            let data_size = std::mem::size_of::<f32>() * nelem;
+            let is_normal = nelem > 1;
+            (
+                data_type,
+                ArrayDataValueVar::ArrayValueF32(value_new),
+                data_size,
+                is_normal,
+            )
         } else {
             let data_type = MatFileDataTypes::MiDOUBLE;
             let nelem = value.len();
@@ -266,26 +306,17 @@ impl ArrayDataNew<f64> for ArrayData {
 }
 
 impl ArrayData {
+    /// Number of values
+    pub fn num_values(&self) -> usize {
+        match self {
+            ArrayData::DataNormal(d) => d.value.num_values(),
+            ArrayData::DataSmall(d) => d.value.num_values(),
+        }
+    }
     pub fn size(&self) -> u32 {
         match self {
-            ArrayData::DataNormal(v) => {
-                let padding = if (v.data_size % 8) == 0 {
-                    0
-                } else {
-                    8 - v.data_size % 8
-                };
-                8 + v.data_size + padding
-            }
-            ArrayData::DataSmall(v) => {
-                let padding = if v.data_size == 0 {
-                    4
-                } else if (v.data_size % 4) == 0 {
-                    0
-                } else {
-                    4 - v.data_size % 4
-                };
-                (4 + v.data_size + padding) as u32
-            }
+            ArrayData::DataNormal(v) => element_size(v.data_size, false),
+            ArrayData::DataSmall(v) => element_size(v.data_size as u32, true),
         }
     }
 }
@@ -320,24 +351,8 @@ impl ArrayDataSparse {
     }
     pub fn size(&self) -> u32 {
         match self {
-            ArrayDataSparse::DataNormal(v) => {
-                let padding = if (v.data_size % 8) == 0 {
-                    0
-                } else {
-                    8 - v.data_size % 8
-                };
-                8 + v.data_size + padding
-            }
-            ArrayDataSparse::DataSmall(v) => {
-                let padding = if v.data_size == 0 {
-                    4
-                } else if (v.data_size % 4) == 0 {
-                    0
-                } else {
-                    4 - v.data_size % 4
-                };
-                (4 + v.data_size + padding) as u32
-            }
+            ArrayDataSparse::DataNormal(v) => element_size(v.data_size, false),
+            ArrayDataSparse::DataSmall(v) => element_size(v.data_size as u32, true),
         }
     }
 }
@@ -722,6 +737,37 @@ mod tests {
         assert!(bin.into_inner() == DATA1);
     }
 
+    #[test]
+    fn serialize_data_double_inexact_integers() {
+        // Values only stored as integers, if they read back bit-identical
+        assert_eq!(
+            ArrayData::new(vec![1.0, 255.0]).data_type(),
+            MatFileDataTypes::MiUINT8
+        );
+        assert_eq!(
+            ArrayData::new(vec![1.0, -128.0]).data_type(),
+            MatFileDataTypes::MiINT8
+        );
+        assert_eq!(
+            ArrayData::new(vec![1.0, 256.0]).data_type(),
+            MatFileDataTypes::MiUINT16
+        );
+        assert_eq!(
+            ArrayData::new(vec![-1.0, 3e9]).data_type(),
+            MatFileDataTypes::MiDOUBLE
+        );
+
+        for value in [-0.0, f64::NAN, f64::INFINITY, 0.5] {
+            let data = ArrayData::new(vec![1.0, value]);
+            assert_eq!(data.data_type(), MatFileDataTypes::MiDOUBLE);
+            if let ArrayDataValueVar::ArrayValueF64(val) = data.array_data_value_var() {
+                assert_eq!(val[1].to_bits(), value.to_bits());
+            } else {
+                panic!("Not f64")
+            }
+        }
+    }
+
     /*
      *
      * logical
@@ -822,4 +868,73 @@ mod tests {
         data.write_le(&mut bin).unwrap();
         println!("Ser  bin: {:?}", bin);
     }
+
+    /// Parse `bin` followed by a small `miUINT8` element `[42]` and return the parsed data,
+    /// after checking that the following element is read in sync.
+    fn parse_in_sync(bin: &[u8], class: MatlabArrayTypes) -> BinResult<ArrayData> {
+        let mut stream = bin.to_vec();
+        stream.extend(b"\x02\x00\x01\x00\x2a\x00\x00\x00");
+
+        let mut reader = Cursor::new(stream);
+        let data = reader.read_le_args::<ArrayData>((class, false))?;
+        assert_eq!(reader.position(), data.size() as u64);
+        let next = reader.read_le_args::<ArrayData>((MatlabArrayTypes::MxUINT8CLASS, false))?;
+        assert!(matches!(next.array_data_value_var(), ArrayDataValueVar::ArrayValueU8(v) if v == [42]));
+        Ok(data)
+    }
+
+    #[test]
+    fn element_sizes_of_matlab_output() {
+        use MatlabArrayTypes::*;
+        let captured: [(&[u8], MatlabArrayTypes); 4] = [
+            (DATA_U16_1, MxUINT16CLASS),
+            (DATA_U16_2, MxUINT16CLASS),
+            (DATA_U32_1, MxUINT32CLASS),
+            (DATA_CHAR_1, MxCHARCLASS),
+        ];
+        for (bin, class) in captured {
+            let data = parse_in_sync(bin, class).unwrap();
+            assert_eq!(data.size() as usize, bin.len(), "{bin:?}");
+        }
+    }
+
+    #[test]
+    fn element_sizes_match_written_bytes() {
+        fn check<T: Clone>(value: Vec<T>, class: MatlabArrayTypes)
+        where
+            ArrayData: ArrayDataNew<T>,
+        {
+            let data = ArrayData::new(value);
+            let mut bin = Cursor::new(vec![]);
+            data.write_le(&mut bin).unwrap();
+            let bin = bin.into_inner();
+            assert_eq!(data.size() as usize, bin.len(), "{data:?}");
+            assert_eq!(parse_in_sync(&bin, class).unwrap().size(), data.size());
+        }
+
+        for n in 0..=17 {
+            check(vec![7u8; n], MatlabArrayTypes::MxUINT8CLASS);
+            check(vec![7u16; n], MatlabArrayTypes::MxUINT16CLASS);
+            check(vec![7u32; n], MatlabArrayTypes::MxUINT32CLASS);
+            check(vec![7.5f64; n], MatlabArrayTypes::MxDOUBLECLASS);
+            check(vec!['a'; n], MatlabArrayTypes::MxCHARCLASS);
+        }
+    }
+
+    #[test]
+    fn malformed_small_elements() {
+        use MatlabArrayTypes::*;
+
+        // Small element declaring more bytes than fit
+        assert!(parse_in_sync(b"\x02\x00\x05\x00\x01\x02\x03\x04", MxUINT8CLASS).is_err());
+        // Size no multiple of the data type, in both formats
+        assert!(parse_in_sync(b"\x04\x00\x03\x00\x37\x00\x42\x00", MxUINT16CLASS).is_err());
+        assert!(
+            parse_in_sync(
+                b"\x09\x00\x00\x00\x0c\x00\x00\x00\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+                MxDOUBLECLASS
+            )
+            .is_err()
+        );
+    }
 }
@@ -10,11 +10,11 @@ use crate::parser::v7::flags::{MatFileDataTypes, MatlabArrayTypes};
 #[br(import(arr_type: MatlabArrayTypes, is_logical: bool))]
 pub struct ArrayDataNormal {
     #[brw(pad_size_to = 2)]
-    data_type: MatFileDataTypes,
+    pub(crate) data_type: MatFileDataTypes,
     #[br(assert(check == 0))]
     check: u16,
     pub data_size: u32,
-    #[br(parse_with = parse_array_data, args(&data_type, data_size, arr_type, is_logical))]
+    #[br(parse_with = parse_array_data, args(&data_type, data_size, false, arr_type, is_logical))]
     #[bw(write_with = write_array_data)]
     pub value: ArrayDataValueVar,
 }
@@ -58,7 +58,7 @@ pub struct ArrayDataSparseNormal {
     /// Number of bytes tag
     pub data_size: u32,
     /// Array dimensions
-    #[br(parse_with = parse_array_data_sparse, args(&data_type, data_size, is_logical))]
+    #[br(parse_with = parse_array_data_sparse, args(&data_type, data_size, false, is_logical))]
     #[bw(write_with = write_array_data)]
     pub value: ArrayDataValueVar,
 }
@@ -10,9 +10,11 @@ use crate::parser::v7::flags::{MatFileDataTypes, MatlabArrayTypes};
 #[br(import(arrtype: MatlabArrayTypes, is_logical: bool))]
 pub struct ArrayDataSmall {
     #[brw(pad_size_to = 2)]
-    data_type: MatFileDataTypes,
+    pub(crate) data_type: MatFileDataTypes,
+    // A tag with zero size is in the normal format, read as small only if it is truncated
+    #[br(assert(data_size != 0))]
     pub data_size: u16,
-    #[br(parse_with = parse_array_data, args(&data_type, data_size as u32, arrtype, is_logical))]
+    #[br(parse_with = parse_array_data, args(&data_type, data_size as u32, true, arrtype, is_logical))]
     #[bw(pad_size_to = 4, write_with = write_array_data)]
     pub value: ArrayDataValueVar,
 }
@@ -39,8 +41,10 @@ impl std::fmt::Display for ArrayDataSmall {
 pub struct ArrayDataSparseSmall {
     #[brw(pad_size_to = 2)]
     data_type: MatFileDataTypes,
+    // A tag with zero size is in the normal format, read as small only if it is truncated
+    #[br(assert(data_size != 0))]
     pub data_size: u16,
-    #[br(parse_with = parse_array_data_sparse, args(&data_type, data_size as u32, is_logical))]
+    #[br(parse_with = parse_array_data_sparse, args(&data_type, data_size as u32, true, is_logical))]
     #[bw(pad_size_to = 4, write_with = write_array_data)]
     pub value: ArrayDataValueVar,
 }
@@ -6,7 +6,7 @@ use crate::parser::v7::flags::MatFileDataTypes;
 
 #[binrw]
 #[derive(Debug, Clone)]
-#[br(import(data_type: &MatFileDataTypes, data_size: u32))]
+#[br(import(data_type: &MatFileDataTypes, data_size: u32), return_unexpected_error)]
 pub enum ArrayDataValueVarRaw {
     #[br(pre_assert(*data_type == MatFileDataTypes::MiUINT8))]
     ArrayValueU8(
@@ -58,13 +58,13 @@ pub enum ArrayDataValueVarRaw {
     ),
     #[br(pre_assert(*data_type == MatFileDataTypes::MiSINGLE))]
     ArrayValueF32(
-        #[br(count = data_size as usize / mem::size_of::<f32>())]
+        #[br(parse_with = read_values, args_raw = data_size as usize / mem::size_of::<f32>())]
         #[bw(align_after = 8)]
         Vec<f32>,
     ),
     #[br(pre_assert(*data_type == MatFileDataTypes::MiDOUBLE))]
     ArrayValueF64(
-        #[br(count = data_size as usize / mem::size_of::<f64>())]
+        #[br(parse_with = read_values, args_raw = data_size as usize / mem::size_of::<f64>())]
         #[bw(align_after = 8)]
         Vec<f64>,
     ),
@@ -80,9 +80,46 @@ pub enum ArrayDataValueVarRaw {
         #[bw(align_after = 8)]
         Vec<u16>,
     ),
+    #[br(pre_assert(*data_type == MatFileDataTypes::MiUTF32))]
+    ArrayValueUTF32(
+        #[br(count = data_size as usize / mem::size_of::<u32>())]
+        #[bw(align_after = 8)]
+        Vec<u32>,
+    ),
     // ArrayValueEmpty,
 }
 
+/// Read `count` values with one read per chunk, instead of one read per value.
+///
+/// The bytes are read into the vector as stored and swapped afterwards, if the byte order of the
+/// file differs from the host. binrw does this for integers, but reads floats value by value.
+/// The vector grows while reading, so a corrupt count fails at the end of the data instead of
+/// allocating its size up front.
+fn read_values<R: io::Read + io::Seek, T: bytemuck::Pod>(
+    reader: &mut R,
+    endian: Endian,
+    count: usize,
+) -> BinResult<Vec<T>> {
+    const CHUNK_BYTES: usize = 1 << 16;
+
+    let mut values = Vec::new();
+    while values.len() < count {
+        let start = values.len();
+        let end = count.min(start + start.max(CHUNK_BYTES / mem::size_of::<T>()));
+        values.resize(end, T::zeroed());
+        reader.read_exact(bytemuck::cast_slice_mut(&mut values[start..end]))?;
+    }
+
+    if endian != Endian::NATIVE {
+        let bytes: &mut [u8] = bytemuck::cast_slice_mut(&mut values);
+        for value in bytes.chunks_exact_mut(mem::size_of::<T>()) {
+            value.reverse();
+        }
+    }
+
+    Ok(values)
+}
+
 #[derive(Debug, Clone)]
 pub enum ArrayDataValueVar {
     ArrayValueU8(Vec<u8>),
@@ -97,11 +134,80 @@ pub enum ArrayDataValueVar {
     ArrayValueF64(Vec<f64>),
     ArrayValueUTF8(Vec<char>),
     ArrayValueUTF16(Vec<char>),
+    /// UTF-16 code units containing surrogates, kept as stored
+    ArrayValueUTF16Units(Vec<u16>),
     ArrayValueBOOL(Vec<bool>),
 }
 
+impl ArrayDataValueVar {
+    /// Number of values
+    pub fn num_values(&self) -> usize {
+        use ArrayDataValueVar::*;
+        match self {
+            ArrayValueU8(v) => v.len(),
+            ArrayValueI8(v) => v.len(),
+            ArrayValueU16(v) | ArrayValueUTF16Units(v) => v.len(),
+            ArrayValueI16(v) => v.len(),
+            ArrayValueU32(v) => v.len(),
+            ArrayValueI32(v) => v.len(),
+            ArrayValueU64(v) => v.len(),
+            ArrayValueI64(v) => v.len(),
+            ArrayValueF32(v) => v.len(),
+            ArrayValueF64(v) => v.len(),
+            ArrayValueUTF8(v) | ArrayValueUTF16(v) => v.len(),
+            ArrayValueBOOL(v) => v.len(),
+        }
+    }
+}
+
+/// One character per UTF-16 code unit, like MATLAB counts characters. Surrogates have no
+/// character of their own and become U+FFFD.
+pub fn utf16_unit_chars(units: &[u16]) -> Vec<char> {
+    units
+        .iter()
+        .map(|&u| char::from_u32(u as u32).unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
+
 impl Display for ArrayDataValueVar {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{:?}", self)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use binrw::io::Cursor;
+
+    #[test]
+    fn read_floats() {
+        let values: Vec<f64> = (0..20_000).map(|i| i as f64 * 0.5 - 7.25).collect();
+        let le: Vec<u8> = values.iter().flat_map(|x| x.to_le_bytes()).collect();
+        let be: Vec<u8> = values.iter().flat_map(|x| x.to_be_bytes()).collect();
+        let size = le.len() as u32;
+
+        let raw = Cursor::new(&le)
+            .read_le_args::<ArrayDataValueVarRaw>((&MatFileDataTypes::MiDOUBLE, size))
+            .unwrap();
+        assert!(matches!(raw, ArrayDataValueVarRaw::ArrayValueF64(v) if v == values));
+        let raw = Cursor::new(&be)
+            .read_be_args::<ArrayDataValueVarRaw>((&MatFileDataTypes::MiDOUBLE, size))
+            .unwrap();
+        assert!(matches!(raw, ArrayDataValueVarRaw::ArrayValueF64(v) if v == values));
+
+        let singles: Vec<f32> = values.iter().map(|&x| x as f32).collect();
+        let le: Vec<u8> = singles.iter().flat_map(|x| x.to_le_bytes()).collect();
+        let raw = Cursor::new(&le)
+            .read_le_args::<ArrayDataValueVarRaw>((&MatFileDataTypes::MiSINGLE, le.len() as u32))
+            .unwrap();
+        assert!(matches!(raw, ArrayDataValueVarRaw::ArrayValueF32(v) if v == singles));
+
+        // Truncated data
+        assert!(
+            Cursor::new(&le[..le.len() - 1])
+                .read_le_args::<ArrayDataValueVarRaw>((&MatFileDataTypes::MiSINGLE, le.len() as u32))
+                .is_err()
+        );
+    }
+}
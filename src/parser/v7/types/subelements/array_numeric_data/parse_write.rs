@@ -2,28 +2,46 @@ use binrw::*;
 
 use super::array_data_value::*;
 use crate::parser::v7::flags::{MatFileDataTypes, MatlabArrayTypes};
+use crate::parser::v7::types::subelements::tag::parse_data;
+
+/// Raw values of an element with `data_size` bytes, with the padding skipped
+fn read_raw<R: io::Read + io::Seek>(
+    reader: &mut R,
+    data_type: &MatFileDataTypes,
+    data_size: u32,
+    small: bool,
+) -> BinResult<ArrayDataValueVarRaw> {
+    parse_data(reader, data_size, small, |reader| {
+        reader.read_le_args::<ArrayDataValueVarRaw>((data_type, data_size))
+    })
+}
 
 #[parser(reader)]
 pub fn parse_array_data(
     data_type: &MatFileDataTypes,
     data_size: u32,
+    small: bool,
     arr_type: MatlabArrayTypes,
     is_logical: bool,
 ) -> BinResult<ArrayDataValueVar> {
-    let data = reader.read_le_args::<ArrayDataValueVarRaw>((data_type, data_size))?;
+    let data = read_raw(reader, data_type, data_size, small)?;
+
+    // MATLAB stores logicals as uint8, other writers use any numeric storage type and class
+    if is_logical {
+        return match data.to_f64() {
+            Some(v) => Ok(ArrayDataValueVar::ArrayValueBOOL(
+                v.iter().map(|&x| x != 0.0).collect(),
+            )),
+            None => Err(Error::NoVariantMatch {
+                pos: reader.stream_position()?,
+            }),
+        };
+    }
 
     use {ArrayDataValueVarRaw::*, MatlabArrayTypes::*};
     match (data, arr_type) {
         // u8
-        (ArrayValueU8(v), MxUINT8CLASS) => {
-            if is_logical {
-                Ok(ArrayDataValueVar::ArrayValueBOOL(
-                    v.iter().map(|&x| x != 0).collect(),
-                ))
-            } else {
-                Ok(ArrayDataValueVar::ArrayValueU8(v))
-            }
-        }
+        (ArrayValueU8(v), MxUINT8CLASS) => Ok(ArrayDataValueVar::ArrayValueU8(v)),
         (ArrayValueU8(v), MxINT8CLASS) => Ok(ArrayDataValueVar::ArrayValueI8(
             v.iter().map(|&x| x as i8).collect(),
         )),
@@ -314,11 +332,25 @@ pub fn parse_array_data(
         (ArrayValueF64(v), MxDOUBLECLASS) => Ok(ArrayDataValueVar::ArrayValueF64(v)),
         // utf8
         (ArrayValueUTF8(v), MxCHARCLASS) => Ok(ArrayDataValueVar::ArrayValueUTF8(
-            String::from_utf8(v).unwrap().chars().collect(),
+            String::from_utf8_lossy(&v).chars().collect(),
         )),
-        // utf16
-        (ArrayValueUTF16(v), MxCHARCLASS) => Ok(ArrayDataValueVar::ArrayValueUTF16(
-            String::from_utf16(&v).unwrap().chars().collect(),
+        // Characters of -v6 files and some third-party writers, one byte per character
+        (ArrayValueU8(v), MxCHARCLASS) => Ok(ArrayDataValueVar::ArrayValueUTF8(
+            v.iter().map(|&x| x as char).collect(),
+        )),
+        // utf16, MATLAB itself stores characters as uint16 code units
+        (ArrayValueUTF16(v) | ArrayValueU16(v), MxCHARCLASS) => {
+            if v.iter().any(|u| (0xD800..0xE000).contains(u)) {
+                Ok(ArrayDataValueVar::ArrayValueUTF16Units(v))
+            } else {
+                Ok(ArrayDataValueVar::ArrayValueUTF16(utf16_unit_chars(&v)))
+            }
+        }
+        // utf32, rare in practice, one code point per character
+        (ArrayValueUTF32(v), MxCHARCLASS) => Ok(ArrayDataValueVar::ArrayValueUTF8(
+            v.iter()
+                .map(|&x| char::from_u32(x).unwrap_or(char::REPLACEMENT_CHARACTER))
+                .collect(),
         )),
         //
         _ => Err(Error::NoVariantMatch {
@@ -331,23 +363,43 @@ pub fn parse_array_data(
 pub fn parse_array_data_sparse(
     data_type: &MatFileDataTypes,
     data_size: u32,
+    small: bool,
     is_logical: bool,
 ) -> BinResult<ArrayDataValueVar> {
-    let data = reader.read_le_args::<ArrayDataValueVarRaw>((data_type, data_size))?;
+    let data = read_raw(reader, data_type, data_size, small)?;
 
-    use ArrayDataValueVarRaw::*;
-    match data {
-        ArrayValueU8(v) => {
-            if is_logical {
-                Ok(ArrayDataValueVar::ArrayValueBOOL(
-                    v.iter().map(|&x| x != 0).collect(),
-                ))
-            } else {
-                Ok(ArrayDataValueVar::ArrayValueU8(v))
-            }
+    // Sparse arrays are double or logical, but MATLAB stores integral values in smaller types
+    match (data, is_logical) {
+        (ArrayDataValueVarRaw::ArrayValueF64(v), false) => Ok(ArrayDataValueVar::ArrayValueF64(v)),
+        (data, is_logical) => match data.to_f64() {
+            Some(v) if is_logical => Ok(ArrayDataValueVar::ArrayValueBOOL(
+                v.iter().map(|&x| x != 0.0).collect(),
+            )),
+            Some(v) => Ok(ArrayDataValueVar::ArrayValueF64(v)),
+            None => Err(Error::NoVariantMatch {
+                pos: reader.stream_position()?,
+            }),
+        },
+    }
+}
+
+impl ArrayDataValueVarRaw {
+    /// Values of numeric storage types as double
+    fn to_f64(&self) -> Option<Vec<f64>> {
+        use ArrayDataValueVarRaw::*;
+        match self {
+            ArrayValueU8(v) => Some(v.iter().map(|&x| x as f64).collect()),
+            ArrayValueI8(v) => Some(v.iter().map(|&x| x as f64).collect()),
+            ArrayValueU16(v) => Some(v.iter().map(|&x| x as f64).collect()),
+            ArrayValueI16(v) => Some(v.iter().map(|&x| x as f64).collect()),
+            ArrayValueU32(v) => Some(v.iter().map(|&x| x as f64).collect()),
+            ArrayValueI32(v) => Some(v.iter().map(|&x| x as f64).collect()),
+            ArrayValueU64(v) => Some(v.iter().map(|&x| x as f64).collect()),
+            ArrayValueI64(v) => Some(v.iter().map(|&x| x as f64).collect()),
+            ArrayValueF32(v) => Some(v.iter().map(|&x| x as f64).collect()),
+            ArrayValueF64(v) => Some(v.clone()),
+            ArrayValueUTF8(_) | ArrayValueUTF16(_) | ArrayValueUTF32(_) => None,
         }
-        ArrayValueF64(v) => Ok(ArrayDataValueVar::ArrayValueF64(v)),
-        _ => panic!(),
     }
 }
 
@@ -376,8 +428,165 @@ pub fn write_array_data(value: &ArrayDataValueVar) -> BinResult<()> {
                 .map(|x| *x as u16)
                 .collect(),
         ),
+        ArrayValueUTF16Units(v) => ArrayDataValueVarRaw::ArrayValueU16(v.to_owned()),
         ArrayValueBOOL(v) => ArrayDataValueVarRaw::ArrayValueU8(v.iter().map(|x| *x as u8).collect()),
     };
 
     raw_data.write_options(writer, endian, ())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use binrw::io::Cursor;
+
+    use MatFileDataTypes::*;
+    use MatlabArrayTypes::*;
+
+    const STORAGE: [MatFileDataTypes; 10] = [
+        MiUINT8, MiINT8, MiUINT16, MiINT16, MiUINT32, MiINT32, MiUINT64, MiINT64, MiSINGLE, MiDOUBLE,
+    ];
+    const CLASSES: [MatlabArrayTypes; 10] = [
+        MxUINT8CLASS,
+        MxINT8CLASS,
+        MxUINT16CLASS,
+        MxINT16CLASS,
+        MxUINT32CLASS,
+        MxINT32CLASS,
+        MxUINT64CLASS,
+        MxINT64CLASS,
+        MxSINGLECLASS,
+        MxDOUBLECLASS,
+    ];
+
+    /// Little endian representation of `values` in `data_type`
+    fn encode(data_type: MatFileDataTypes, values: &[u8]) -> Vec<u8> {
+        values
+            .iter()
+            .flat_map(|&x| match data_type {
+                MiUINT8 | MiINT8 | MiUTF8 => vec![x],
+                MiUINT16 | MiINT16 | MiUTF16 => (x as u16).to_le_bytes().to_vec(),
+                MiUINT32 | MiINT32 | MiUTF32 => (x as u32).to_le_bytes().to_vec(),
+                MiUINT64 | MiINT64 => (x as u64).to_le_bytes().to_vec(),
+                MiSINGLE => (x as f32).to_le_bytes().to_vec(),
+                MiDOUBLE => (x as f64).to_le_bytes().to_vec(),
+                _ => unreachable!(),
+            })
+            .collect()
+    }
+
+    fn parse(
+        data_type: MatFileDataTypes,
+        values: &[u8],
+        class: MatlabArrayTypes,
+        is_logical: bool,
+    ) -> BinResult<ArrayDataValueVar> {
+        let data = encode(data_type, values);
+        parse_array_data(
+            &mut Cursor::new(&data),
+            Endian::Little,
+            (&data_type, data.len() as u32, false, class, is_logical),
+        )
+    }
+
+    /// Class of the parsed values and the values as double
+    fn class_values(value: &ArrayDataValueVar) -> (MatlabArrayTypes, Vec<f64>) {
+        use ArrayDataValueVar::*;
+        match value {
+            ArrayValueU8(v) => (MxUINT8CLASS, v.iter().map(|&x| x as f64).collect()),
+            ArrayValueI8(v) => (MxINT8CLASS, v.iter().map(|&x| x as f64).collect()),
+            ArrayValueU16(v) => (MxUINT16CLASS, v.iter().map(|&x| x as f64).collect()),
+            ArrayValueI16(v) => (MxINT16CLASS, v.iter().map(|&x| x as f64).collect()),
+            ArrayValueU32(v) => (MxUINT32CLASS, v.iter().map(|&x| x as f64).collect()),
+            ArrayValueI32(v) => (MxINT32CLASS, v.iter().map(|&x| x as f64).collect()),
+            ArrayValueU64(v) => (MxUINT64CLASS, v.iter().map(|&x| x as f64).collect()),
+            ArrayValueI64(v) => (MxINT64CLASS, v.iter().map(|&x| x as f64).collect()),
+            ArrayValueF32(v) => (MxSINGLECLASS, v.iter().map(|&x| x as f64).collect()),
+            ArrayValueF64(v) => (MxDOUBLECLASS, v.clone()),
+            ArrayValueUTF8(v) | ArrayValueUTF16(v) => {
+                (MxCHARCLASS, v.iter().map(|&x| x as u32 as f64).collect())
+            }
+            ArrayValueUTF16Units(v) => (MxCHARCLASS, v.iter().map(|&x| x as f64).collect()),
+            ArrayValueBOOL(v) => (MxUINT8CLASS, v.iter().map(|&x| x as u8 as f64).collect()),
+        }
+    }
+
+    #[test]
+    fn numeric_storage_class_matrix() {
+        for data_type in STORAGE {
+            for class in CLASSES {
+                let value = parse(data_type, &[0, 1, 127], class, false)
+                    .unwrap_or_else(|e| panic!("{data_type:?} as {class:?}: {e}"));
+                assert_eq!(
+                    class_values(&value),
+                    (class, vec![0.0, 1.0, 127.0]),
+                    "{data_type:?} as {class:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn logical_from_any_numeric_storage() {
+        for data_type in STORAGE {
+            for class in [MxUINT8CLASS, MxDOUBLECLASS] {
+                let value = parse(data_type, &[0, 1, 2], class, true).unwrap();
+                assert!(
+                    matches!(&value, ArrayDataValueVar::ArrayValueBOOL(v) if v == &[false, true, true]),
+                    "{data_type:?} as {class:?}: {value}"
+                );
+            }
+        }
+        assert!(parse(MiUTF8, b"ab", MxUINT8CLASS, true).is_err());
+    }
+
+    #[test]
+    fn char_storage() {
+        for data_type in [MiUTF8, MiUTF16, MiUTF32, MiUINT8, MiUINT16] {
+            let value = parse(data_type, b"ab", MxCHARCLASS, false).unwrap();
+            assert_eq!(
+                class_values(&value),
+                (MxCHARCLASS, vec![97.0, 98.0]),
+                "{data_type:?}"
+            );
+        }
+        assert!(parse(MiDOUBLE, b"ab", MxCHARCLASS, false).is_err());
+    }
+
+    #[test]
+    fn char_surrogates_and_invalid_utf8() {
+        // 'a', a surrogate pair and an unpaired surrogate
+        let data: Vec<u8> = [0x61u16, 0xD83D, 0xDE00, 0xD800]
+            .iter()
+            .flat_map(|u| u.to_le_bytes())
+            .collect();
+        let args = (&MiUINT16, data.len() as u32, false, MxCHARCLASS, false);
+        let value = parse_array_data(&mut Cursor::new(&data), Endian::Little, args).unwrap();
+        assert!(
+            matches!(&value, ArrayDataValueVar::ArrayValueUTF16Units(v) if v == &[0x61, 0xD83D, 0xDE00, 0xD800])
+        );
+
+        let data = b"a\xffb";
+        let args = (&MiUTF8, data.len() as u32, false, MxCHARCLASS, false);
+        let value = parse_array_data(&mut Cursor::new(data), Endian::Little, args).unwrap();
+        assert!(matches!(&value, ArrayDataValueVar::ArrayValueUTF8(v) if v == &['a', '\u{FFFD}', 'b']));
+    }
+
+    #[test]
+    fn sparse_storage() {
+        for data_type in STORAGE {
+            let data = encode(data_type, &[0, 3]);
+            let args = (&data_type, data.len() as u32, false, false);
+            let value = parse_array_data_sparse(&mut Cursor::new(&data), Endian::Little, args).unwrap();
+            assert_eq!(
+                class_values(&value),
+                (MxDOUBLECLASS, vec![0.0, 3.0]),
+                "{data_type:?}"
+            );
+
+            let args = (&data_type, data.len() as u32, false, true);
+            let value = parse_array_data_sparse(&mut Cursor::new(&data), Endian::Little, args).unwrap();
+            assert!(matches!(value, ArrayDataValueVar::ArrayValueBOOL(v) if v == [false, true]));
+        }
+    }
+}
@@ -3,3 +3,5 @@ pub mod array_fieldname;
 pub mod array_flags;
 pub mod array_name;
 pub mod array_numeric_data;
+pub mod sparse_indices;
+pub mod tag;
@@ -0,0 +1,240 @@
+//! Module containing types for matching the *Row Index* and *Column Index Subelements* of sparse
+//! arrays.
+//!
+//! MATLAB stores both as `miINT32`. Other writers use other integer types, e.g. `miUINT32` or
+//! `miINT64` for matrices with many nonzeros. They are read from any integer type and kept in the
+//! type they were read with. New indices are written as `miINT64`, if they don't fit `miINT32`.
+
+use binrw::io::{Read, Seek, Write};
+use binrw::*;
+
+use crate::parser::v7::flags::MatFileDataTypes;
+use crate::parser::v7::types::subelements::tag::{SMALL_DATA_MAX, element_size, parse_data};
+
+#[binrw]
+#[derive(Debug, Clone)]
+pub enum SparseIndices {
+    DataNormal(SparseIndicesNormal),
+    DataSmall(SparseIndicesSmall),
+}
+
+impl SparseIndices {
+    /// Indices stored as `miINT32`, like MATLAB writes them, or as `miINT64`, if an index
+    /// exceeds the range of `miINT32`
+    pub fn new(indices: Vec<usize>) -> Self {
+        let (data_type, size) = match indices.iter().max() {
+            Some(&max) if max > i32::MAX as usize => (MatFileDataTypes::MiINT64, 8),
+            _ => (MatFileDataTypes::MiINT32, 4),
+        };
+        let data_size = size * indices.len() as u32;
+        if data_size <= SMALL_DATA_MAX {
+            Self::DataSmall(SparseIndicesSmall {
+                data_type,
+                data_size: data_size as u16,
+                indices,
+            })
+        } else {
+            Self::DataNormal(SparseIndicesNormal {
+                data_type,
+                check: 0,
+                data_size,
+                indices,
+            })
+        }
+    }
+    pub fn size(&self) -> u32 {
+        match self {
+            SparseIndices::DataNormal(v) => element_size(v.data_size, false),
+            SparseIndices::DataSmall(v) => element_size(v.data_size as u32, true),
+        }
+    }
+    pub fn indices(&self) -> &[usize] {
+        match self {
+            SparseIndices::DataNormal(v) => &v.indices,
+            SparseIndices::DataSmall(v) => &v.indices,
+        }
+    }
+    /// Integer type the indices are stored as
+    pub fn data_type(&self) -> MatFileDataTypes {
+        match self {
+            SparseIndices::DataNormal(v) => v.data_type,
+            SparseIndices::DataSmall(v) => v.data_type,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+#[binrw]
+pub struct SparseIndicesNormal {
+    /// Data type tag
+    #[brw(pad_size_to = 2)]
+    #[br(assert(index_size(data_type).is_some()))]
+    data_type: MatFileDataTypes,
+    #[br(assert(check == 0))]
+    check: u16,
+    /// Number of bytes tag
+    data_size: u32,
+    #[br(parse_with = parse_indices, args(data_type, data_size, false))]
+    #[bw(write_with = write_indices, args(*data_type))]
+    indices: Vec<usize>,
+}
+
+#[derive(Debug, Clone)]
+#[binrw]
+pub struct SparseIndicesSmall {
+    /// Data type tag
+    #[brw(pad_size_to = 2)]
+    #[br(assert(index_size(data_type).is_some()))]
+    data_type: MatFileDataTypes,
+    /// Number of bytes tag
+    #[br(assert(data_size as u32 <= SMALL_DATA_MAX))]
+    data_size: u16,
+    #[br(parse_with = parse_indices, args(data_type, data_size as u32, true))]
+    #[bw(write_with = write_indices, args(*data_type))]
+    indices: Vec<usize>,
+}
+
+/// Size of an index stored as `data_type`, `None` for types which aren't integers
+fn index_size(data_type: MatFileDataTypes) -> Option<u32> {
+    use MatFileDataTypes::*;
+    match data_type {
+        MiINT8 | MiUINT8 => Some(1),
+        MiINT16 | MiUINT16 => Some(2),
+        MiINT32 | MiUINT32 => Some(4),
+        MiINT64 | MiUINT64 => Some(8),
+        _ => None,
+    }
+}
+
+/// Read `count` values of type `T` and convert them to indices, failing on negative values
+fn read_as<R: Read + Seek, T>(reader: &mut R, endian: Endian, count: usize) -> BinResult<Vec<usize>>
+where
+    T: for<'a> BinRead<Args<'a> = ()> + Copy + TryInto<usize> + std::fmt::Display + 'static,
+{
+    let pos = reader.stream_position()?;
+    let values = Vec::<T>::read_options(reader, endian, VecArgs { count, inner: () })?;
+    values
+        .into_iter()
+        .map(|x| {
+            x.try_into().map_err(|_| Error::AssertFail {
+                pos,
+                message: format!("Sparse index {x} is out of range"),
+            })
+        })
+        .collect()
+}
+
+#[parser(reader, endian)]
+fn parse_indices(data_type: MatFileDataTypes, data_size: u32, small: bool) -> BinResult<Vec<usize>> {
+    use MatFileDataTypes::*;
+
+    let size = index_size(data_type).expect("Integer type checked before");
+    let count = (data_size / size) as usize;
+    parse_data(reader, data_size, small, |reader| match data_type {
+        MiINT8 => read_as::<_, i8>(reader, endian, count),
+        MiUINT8 => read_as::<_, u8>(reader, endian, count),
+        MiINT16 => read_as::<_, i16>(reader, endian, count),
+        MiUINT16 => read_as::<_, u16>(reader, endian, count),
+        MiINT32 => read_as::<_, i32>(reader, endian, count),
+        MiUINT32 => read_as::<_, u32>(reader, endian, count),
+        MiINT64 => read_as::<_, i64>(reader, endian, count),
+        _ => read_as::<_, u64>(reader, endian, count),
+    })
+}
+
+#[binrw::writer(writer, endian)]
+#[allow(clippy::ptr_arg)]
+fn write_indices(indices: &Vec<usize>, data_type: MatFileDataTypes) -> BinResult<()> {
+    use MatFileDataTypes::*;
+
+    fn write<W: Write + Seek, T: for<'a> BinWrite<Args<'a> = ()>>(
+        writer: &mut W,
+        endian: Endian,
+        values: impl Iterator<Item = T>,
+    ) -> BinResult<()> {
+        values
+            .into_iter()
+            .try_for_each(|x| x.write_options(writer, endian, ()))
+    }
+
+    // Indices keep the type they were read with, so they fit into it
+    let values = indices.iter().copied();
+    match data_type {
+        MiINT8 => write(writer, endian, values.map(|x| x as i8)),
+        MiUINT8 => write(writer, endian, values.map(|x| x as u8)),
+        MiINT16 => write(writer, endian, values.map(|x| x as i16)),
+        MiUINT16 => write(writer, endian, values.map(|x| x as u16)),
+        MiINT32 => write(writer, endian, values.map(|x| x as i32)),
+        MiUINT32 => write(writer, endian, values.map(|x| x as u32)),
+        MiINT64 => write(writer, endian, values.map(|x| x as i64)),
+        _ => write(writer, endian, values.map(|x| x as u64)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use binrw::BinReaderExt;
+    use binrw::io::Cursor;
+
+    #[test]
+    fn index_types() {
+        // miINT32, as written by MATLAB
+        let mut bin = Cursor::new(b"\x05\x00\x00\x00\x08\x00\x00\x00\x02\x00\x00\x00\x07\x00\x00\x00");
+        let data = bin.read_le::<SparseIndices>().unwrap();
+        assert_eq!(data.indices(), [2, 7]);
+        assert_eq!(data.data_type(), MatFileDataTypes::MiINT32);
+
+        // miINT64, as written for large matrices by other writers
+        let mut bin = Cursor::new(b"\x0c\x00\x00\x00\x10\x00\x00\x00\x02\x00\x00\x00\x00\x00\x00\x00\x07\x00\x00\x80\x00\x00\x00\x00");
+        let data = bin.read_le::<SparseIndices>().unwrap();
+        assert_eq!(data.indices(), [2, (1 << 31) + 7]);
+        assert_eq!(data.size(), 24);
+        let mut written = Cursor::new(vec![]);
+        data.write_le(&mut written).unwrap();
+        assert_eq!(written.into_inner(), bin.into_inner());
+
+        // miUINT32 in the small data element format, big endian
+        let mut bin = Cursor::new(b"\x06\x00\x00\x04\x00\x00\x00\x09");
+        let data = bin.read_be::<SparseIndices>().unwrap();
+        assert_eq!(data.indices(), [9]);
+
+        // Negative indices and non-integer types are no indices
+        let mut bin = Cursor::new(b"\x05\x00\x04\x00\xff\xff\xff\xff");
+        assert!(bin.read_le::<SparseIndices>().is_err());
+        let mut bin = Cursor::new(b"\x09\x00\x00\x00\x08\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00");
+        assert!(bin.read_le::<SparseIndices>().is_err());
+    }
+
+    #[test]
+    fn new_as_int32() {
+        for (indices, expected) in [
+            (vec![], &b"\x05\x00\x00\x00"[..]),
+            (vec![3], b"\x05\x00\x04\x00\x03\x00\x00\x00"),
+            (
+                vec![0, 1],
+                b"\x05\x00\x00\x00\x08\x00\x00\x00\x00\x00\x00\x00\x01\x00\x00\x00",
+            ),
+        ] {
+            let data = SparseIndices::new(indices);
+            let mut written = Cursor::new(vec![]);
+            data.write_le(&mut written).unwrap();
+            assert_eq!(written.into_inner(), expected);
+        }
+    }
+
+    #[test]
+    fn new_as_int64() {
+        let large = i32::MAX as usize + 1;
+        for indices in [vec![large], vec![0, large]] {
+            let data = SparseIndices::new(indices.clone());
+            assert_eq!(data.data_type(), MatFileDataTypes::MiINT64);
+            assert_eq!(data.size(), 8 + 8 * indices.len() as u32);
+
+            let mut written = Cursor::new(vec![]);
+            data.write_le(&mut written).unwrap();
+            written.set_position(0);
+            assert_eq!(written.read_le::<SparseIndices>().unwrap().indices(), indices);
+        }
+    }
+}
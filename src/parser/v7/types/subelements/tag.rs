@@ -0,0 +1,155 @@
+//! Module containing the layout of data element tags and their padding.
+//!
+//! A data element starts with an 8 byte tag of data type and number of bytes, followed by the
+//! data, padded to a multiple of 8 bytes. Elements with at most 4 bytes of data may use the
+//! *Small Data Element Format*, which packs data type and number of bytes into 4 bytes, followed
+//! by the data padded to 4 bytes. All sizes and padding of elements are computed here.
+
+use binrw::io::{Read, Seek, SeekFrom};
+use binrw::{BinResult, Error};
+
+/// Size of a tag in the normal format
+pub const TAG_SIZE: u32 = 8;
+
+/// Size of a tag in the small data element format
+pub const SMALL_TAG_SIZE: u32 = 4;
+
+/// Maximum number of data bytes of a small data element
+pub const SMALL_DATA_MAX: u32 = 4;
+
+/// Data type and number of bytes of a tag in the small data element format, `None` for tags in
+/// the normal format.
+///
+/// Normal tags start with the data type, which fits into the lower 16 bits. Small tags store the
+/// number of bytes in the upper 16 bits.
+pub fn split_small_tag(tag: u32) -> Option<(u32, u32)> {
+    (tag >> 16 != 0).then_some((tag & 0xffff, tag >> 16))
+}
+
+/// Number of padding bytes after `data_size` bytes of data.
+pub fn padding(data_size: u32, small: bool) -> u32 {
+    if small {
+        SMALL_DATA_MAX.saturating_sub(data_size)
+    } else {
+        (8 - data_size % 8) % 8
+    }
+}
+
+/// Size of an element with `data_size` bytes of data, including tag and padding.
+pub fn element_size(data_size: u32, small: bool) -> u32 {
+    if small {
+        SMALL_TAG_SIZE + SMALL_DATA_MAX
+    } else {
+        TAG_SIZE + data_size + padding(data_size, false)
+    }
+}
+
+/// Check that `data_size` bytes fit into the small data element format.
+pub fn check_small_size(data_size: u32, pos: u64) -> BinResult<()> {
+    if data_size > SMALL_DATA_MAX {
+        return Err(Error::AssertFail {
+            pos,
+            message: format!("Small data element declares {data_size} bytes, at most {SMALL_DATA_MAX} fit"),
+        });
+    }
+    Ok(())
+}
+
+/// Parse the data of an element with `data_size` bytes with `parse` and skip the padding.
+///
+/// Fails, if `parse` does not consume exactly `data_size` bytes, e.g. if the size is no multiple
+/// of the size of the data type. An inconsistent size can't desynchronize the following elements.
+pub fn parse_data<R, T, F>(reader: &mut R, data_size: u32, small: bool, parse: F) -> BinResult<T>
+where
+    R: Read + Seek,
+    F: FnOnce(&mut R) -> BinResult<T>,
+{
+    let start = reader.stream_position()?;
+    if small {
+        check_small_size(data_size, start)?;
+    }
+
+    let value = parse(reader)?;
+
+    let consumed = reader.stream_position()? - start;
+    if consumed != data_size as u64 {
+        return Err(Error::AssertFail {
+            pos: start,
+            message: format!("Data element declares {data_size} bytes, but its data has {consumed} bytes"),
+        });
+    }
+
+    // The last element of a file may lack its padding, seeking past the end is not an error
+    reader.seek(SeekFrom::Current(padding(data_size, small) as i64))?;
+
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use binrw::io::Cursor;
+
+    #[test]
+    fn sizes() {
+        let normal: Vec<(u32, u32)> = (0..=17)
+            .map(|n| (padding(n, false), element_size(n, false)))
+            .collect();
+        let expected = [
+            (0, 8),
+            (7, 16),
+            (6, 16),
+            (5, 16),
+            (4, 16),
+            (3, 16),
+            (2, 16),
+            (1, 16),
+            (0, 16),
+            (7, 24),
+            (6, 24),
+            (5, 24),
+            (4, 24),
+            (3, 24),
+            (2, 24),
+            (1, 24),
+            (0, 24),
+            (7, 32),
+        ];
+        assert_eq!(normal, expected);
+
+        for n in 0..=4 {
+            assert_eq!(padding(n, true), 4 - n);
+            assert_eq!(element_size(n, true), 8);
+        }
+    }
+
+    #[test]
+    fn small_tags() {
+        // Little endian 'miINT8' with 3 bytes, as written by MATLAB for the name 'abc'
+        assert_eq!(
+            split_small_tag(u32::from_le_bytes(*b"\x01\x00\x03\x00")),
+            Some((1, 3))
+        );
+        assert_eq!(split_small_tag(u32::from_le_bytes(*b"\x0e\x00\x00\x00")), None);
+    }
+
+    #[test]
+    fn parse_data_consistency() {
+        let read_u16 = |r: &mut Cursor<&[u8]>| -> BinResult<Vec<u16>> {
+            let mut buf = [0u8; 2];
+            r.read_exact(&mut buf)?;
+            Ok(vec![u16::from_le_bytes(buf)])
+        };
+
+        // Small element, followed by the next element
+        let mut data = Cursor::new(&b"\x01\x00\xff\xff\x2a\x00\x00\x00"[..]);
+        assert_eq!(parse_data(&mut data, 2, true, read_u16).unwrap(), vec![1]);
+        assert_eq!(data.position(), 4);
+
+        // Declared size more than data read, and more than fits into a small element
+        let mut data = Cursor::new(&b"\x01\x00\x00\x00\x00\x00\x00\x00"[..]);
+        assert!(parse_data(&mut data, 3, false, read_u16).is_err());
+        let mut data = Cursor::new(&b"\x01\x00\x00\x00\x00\x00\x00\x00"[..]);
+        assert!(parse_data(&mut data, 6, true, read_u16).is_err());
+    }
+}
@@ -0,0 +1,173 @@
+use binrw::io::{Read, Seek, SeekFrom, Write};
+use binrw::*;
+
+use crate::parser::v7::flags::{MatFileDataTypes, MatlabArrayTypes};
+use crate::parser::v7::types::compressed_array::{matrix_name, read_bytes};
+use crate::parser::v7::types::subelements::array_flags::ArrayFlagBits;
+use crate::parser::v7::types::subelements::tag::{SMALL_DATA_MAX, TAG_SIZE, padding, split_small_tag};
+
+/// Data element, which is not a supported variable.
+///
+/// Elements with reserved or unknown data types and arrays with unsupported contents are
+/// skipped. The name of arrays is kept, if their header can be read. Compressed elements, which
+/// can't be decompressed, are an error and not skipped.
+#[derive(Debug, Clone)]
+pub struct Unknown7 {
+    /// Position of the element in the stream
+    pub position: u64,
+    /// Data type of the element, as stored
+    pub data_type: u32,
+    /// Bytes of the element including tag and padding
+    pub size: u64,
+    name: Option<String>,
+    class: Option<u8>,
+    /// Flags byte of the array flags
+    flags: Option<u8>,
+}
+
+impl Unknown7 {
+    /// Name of the array, `None` for other elements
+    pub fn array_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Name of the array, or a description of the element if it has no name
+    pub fn name(&self) -> String {
+        match &self.name {
+            Some(name) => name.clone(),
+            None => format!("<data element at offset {}>", self.position),
+        }
+    }
+
+    /// Class of the array as stored in its flags, `None` for other elements
+    pub fn array_class(&self) -> Option<u8> {
+        self.class
+    }
+
+    /// Set the range of the element in the file, for elements read from compressed data, which
+    /// take the range of the compressed element.
+    pub fn set_range(&mut self, position: u64, size: u64) {
+        self.position = position;
+        self.size = size;
+    }
+
+    /// Kind of array MATLAB does not define, if the flags of the array name one
+    fn undefined_combination(&self) -> Option<&'static str> {
+        self.class
+            .and_then(MatlabArrayTypes::from_u8)
+            .zip(self.flags)
+            .and_then(|(class, flags)| ArrayFlagBits::from_bits(flags).undefined_combination(class))
+    }
+
+    /// Reason the element was skipped
+    pub fn warning(&self) -> String {
+        let undefined = self.undefined_combination();
+        match (&self.name, undefined, MatFileDataTypes::from_u32(self.data_type)) {
+            (Some(name), Some(kind), _) => {
+                format!("Variable '{name}' is a {kind} array, which MATLAB does not define, and was not read")
+            }
+            (Some(name), None, _) => format!("Variable '{name}' has unsupported contents and was not read"),
+            (None, _, Some(data_type)) => format!(
+                "Skipped data element of type {data_type} at offset {}",
+                self.position
+            ),
+            (None, _, None) => format!(
+                "Skipped data element of unknown type {} at offset {}",
+                self.data_type, self.position
+            ),
+        }
+    }
+
+    /// Reason the element at `path` inside a cell or structure was skipped
+    pub fn nested_warning(&self, path: &str) -> String {
+        match self.undefined_combination() {
+            Some(kind) => {
+                format!("Element '{path}' is a {kind} array, which MATLAB does not define, and was not read")
+            }
+            None => format!("Element '{path}' has unsupported contents and was not read"),
+        }
+    }
+}
+
+impl BinRead for Unknown7 {
+    type Args<'a> = ();
+
+    fn read_options<R: Read + Seek>(
+        reader: &mut R,
+        endian: Endian,
+        _args: Self::Args<'_>,
+    ) -> BinResult<Self> {
+        let position = reader.stream_position()?;
+        let tag = u32::read_options(reader, endian, ())?;
+
+        if let Some((data_type, _)) = split_small_tag(tag) {
+            reader.seek(SeekFrom::Current(SMALL_DATA_MAX as i64))?;
+            return Ok(Self {
+                position,
+                data_type,
+                size: TAG_SIZE as u64,
+                name: None,
+                class: None,
+                flags: None,
+            });
+        }
+
+        if tag == MatFileDataTypes::MiCOMPRESSED as u32 {
+            return Err(Error::AssertFail {
+                pos: position,
+                message: "Compressed data element could not be decompressed".to_string(),
+            });
+        }
+
+        let num_bytes = u32::read_options(reader, endian, ())?;
+        let data = read_bytes(reader, num_bytes as u64)?;
+        // The last element of a file may lack the padding
+        let padding = padding(num_bytes, false);
+        reader.seek(SeekFrom::Current(padding as i64))?;
+
+        let (name, class, flags) = if tag == MatFileDataTypes::MiMATRIX as u32 {
+            let (class, flags) = matrix_flags(&data, endian).unzip();
+            (
+                matrix_name(&mut data.as_slice(), endian).ok().flatten(),
+                class,
+                flags,
+            )
+        } else {
+            (None, None, None)
+        };
+
+        Ok(Self {
+            position,
+            data_type: tag,
+            size: TAG_SIZE as u64 + num_bytes as u64 + padding as u64,
+            name,
+            class,
+            flags,
+        })
+    }
+}
+
+/// Class and flags byte in the array flags at the start of the data of a `miMATRIX` element
+fn matrix_flags(data: &[u8], endian: Endian) -> Option<(u8, u8)> {
+    let flags = data.get(TAG_SIZE as usize..TAG_SIZE as usize + 4)?;
+    match endian {
+        Endian::Little => Some((flags[0], flags[1])),
+        Endian::Big => Some((flags[3], flags[2])),
+    }
+}
+
+impl BinWrite for Unknown7 {
+    type Args<'a> = ();
+
+    fn write_options<W: Write + Seek>(
+        &self,
+        writer: &mut W,
+        _endian: Endian,
+        _args: Self::Args<'_>,
+    ) -> BinResult<()> {
+        Err(Error::AssertFail {
+            pos: writer.stream_position()?,
+            message: "Skipped data elements can't be written.".to_string(),
+        })
+    }
+}
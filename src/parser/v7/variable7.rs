@@ -9,6 +9,7 @@ use crate::{
         sparse_array::SparseArray7,
         structure::Structure7,
         structure_array::StructureArray7,
+        unknown::Unknown7,
     },
 };
 
@@ -16,7 +17,12 @@ use binrw::binrw;
 
 use super::types::numeric_array::NumericArrayNew;
 
+/// Data element of a file, the variants are tried in order.
+///
+/// Variants check data type and array class right after reading them, so a variant not matching
+/// an element fails before parsing, and allocating, its data.
 #[binrw]
+#[br(return_unexpected_error)]
 #[derive(Debug, Clone)]
 pub enum MatVariable7 {
     ObjectMCOS(ObjectMCOS7),
@@ -29,6 +35,8 @@ pub enum MatVariable7 {
     StructureArray(StructureArray7),
     Sparse(SparseArray7),
     Empty(Empty7),
+    /// Data element which is none of the above, tried last
+    Unknown(Unknown7),
 }
 
 impl MatVariable7 {
@@ -53,7 +61,8 @@ impl MatVariable7 {
             MatVariable7::ObjectMCOS(val) => val.name(),
             MatVariable7::ObjectHandle(val) => val.name(),
             MatVariable7::Sparse(val) => val.name(),
-            _ => unimplemented!("{:#?}", self),
+            MatVariable7::Unknown(val) => val.name(),
+            MatVariable7::Empty(_) => String::new(),
         }
     }
     pub fn size(&self) -> usize {
@@ -67,21 +76,87 @@ impl MatVariable7 {
             _ => unimplemented!(),
         }
     }
+    /// First numeric array of this or a nested variable, which stores another number of values
+    /// than its dimensions declare, see [`NumericArray7::length_mismatch`].
+    pub fn length_mismatch(&self) -> Option<(usize, usize)> {
+        let nested = |elements: &[MatVariable7]| elements.iter().find_map(|v| v.length_mismatch());
+        match self {
+            MatVariable7::Numeric(val) => val.length_mismatch(),
+            MatVariable7::Cell(val) => nested(val.elements()),
+            MatVariable7::Structure(val) => nested(val.elements()),
+            MatVariable7::StructureArray(val) => nested(val.elements()),
+            _ => None,
+        }
+    }
+    /// Bytes of the values of this and all nested numeric arrays after conversion, as their
+    /// dimensions declare, see [`NumericArray7::declared_bytes`].
+    pub fn declared_bytes(&self) -> u64 {
+        let nested = |elements: &[MatVariable7]| elements.iter().map(|v| v.declared_bytes()).sum();
+        match self {
+            MatVariable7::Numeric(val) => val.declared_bytes(),
+            MatVariable7::Cell(val) => nested(val.elements()),
+            MatVariable7::Structure(val) => nested(val.elements()),
+            MatVariable7::StructureArray(val) => nested(val.elements()),
+            _ => 0,
+        }
+    }
+    /// Set the range of this and each nested skipped element, for variables read from compressed
+    /// data, see [`Unknown7::set_range`].
+    pub fn set_unknown_range(&mut self, position: u64, size: u64) {
+        let elements = match self {
+            MatVariable7::Unknown(val) => return val.set_range(position, size),
+            MatVariable7::Compressed(val) => return val.value_mut().set_unknown_range(position, size),
+            MatVariable7::Cell(val) => val.elements_mut(),
+            MatVariable7::Structure(val) => val.elements_mut(),
+            MatVariable7::StructureArray(val) => val.elements_mut(),
+            _ => return,
+        };
+        for val in elements.iter_mut() {
+            val.set_unknown_range(position, size);
+        }
+    }
+    /// Skipped elements nested in this cell or structure, with their path below `path`, e.g.
+    /// `s.field`, `s(2).field` or `c{2}`, in the order of the file.
+    pub fn nested_unknown<'a>(&'a self, path: &str, found: &mut Vec<(String, &'a Unknown7)>) {
+        let mut visit = |path: String, val: &'a MatVariable7| match val {
+            MatVariable7::Unknown(unknown) => found.push((path, unknown)),
+            val => val.nested_unknown(&path, found),
+        };
+        match self {
+            MatVariable7::Cell(val) => {
+                for (i, v) in val.elements().iter().enumerate() {
+                    visit(format!("{}{{{}}}", path, i + 1), v);
+                }
+            }
+            MatVariable7::Structure(val) => {
+                for (field, v) in val.fieldnames().iter().zip(val.elements()) {
+                    visit(format!("{}.{}", path, field), v);
+                }
+            }
+            MatVariable7::StructureArray(val) => {
+                let fields = val.fieldnames();
+                for (i, v) in val.elements().iter().enumerate() {
+                    let field = &fields[i % fields.len()];
+                    visit(format!("{}({}).{}", path, i / fields.len() + 1, field), v);
+                }
+            }
+            _ => {}
+        }
+    }
 }
 
 impl From<MatVariable> for MatVariable7 {
     fn from(value: MatVariable) -> Self {
         match value {
-            MatVariable::Compressed(v) => MatVariable7::Compressed(CompressedArray7::from(v)),
             MatVariable::NumericArray(v) => MatVariable7::Numeric(NumericArray7::from(v)),
             MatVariable::CellArray(v) => MatVariable7::Cell(CellArray7::from(v)),
             MatVariable::Structure(v) => MatVariable7::Structure(Structure7::from(v)),
             MatVariable::StructureArray(v) => MatVariable7::StructureArray(StructureArray7::from(v)),
             MatVariable::SparseArray(v) => MatVariable7::Sparse(SparseArray7::from(v)),
-            MatVariable::Unsupported => {
-                MatVariable7::Numeric(NumericArray7::new(vec![1, 1], Vec::<f64>::new(), None))
+            // Written as `[]`, like empty cells are read
+            MatVariable::Null | MatVariable::Unsupported => {
+                MatVariable7::Numeric(NumericArray7::new(vec![0, 0], Vec::<f64>::new(), None))
             }
-            _ => unimplemented!(),
         }
     }
 }
@@ -0,0 +1,481 @@
+//! Module containing the zlib streams of compressed data elements.
+//!
+//! With one of the features `deflate-miniz`, `deflate-zlib-ng` or `deflate-zlib-rs`, which
+//! select the backend of [flate2](https://docs.rs/flate2), streams are compressed and
+//! decompressed with flate2.
+//!
+//! Without those features, matrw builds without any deflate implementation. Variables are then
+//! written as zlib streams of *stored*, i.e. uncompressed, deflate blocks, which any zlib
+//! implementation, including MATLAB's, can read. Only such streams can be read, compressed
+//! variables written by MATLAB fail to load.
+
+use std::io::{Read, Result};
+
+/// Reader decompressing the zlib stream `reader`.
+#[cfg(feature = "deflate")]
+pub fn decoder<R: Read>(reader: R) -> impl Read {
+    flate2::read::ZlibDecoder::new(reader)
+}
+
+/// Reader decompressing the zlib stream `reader`.
+#[cfg(not(feature = "deflate"))]
+pub fn decoder<R: Read>(reader: R) -> impl Read {
+    stored::StoredDecoder::new(reader)
+}
+
+/// Decompress the zlib stream `data`, appending to `out`.
+///
+/// Each thread keeps its decompressor for the next stream, so reading many small compressed
+/// variables does not allocate and initialize a decompressor for each of them. Data after the
+/// end of the stream is ignored.
+pub fn decompress(data: &[u8], out: &mut Vec<u8>) -> Result<()> {
+    decompress_limited(data, out, usize::MAX)
+}
+
+/// Decompress at most `limit` bytes of the zlib stream `data`, appending to `out`, see
+/// [`decompress`].
+///
+/// The data after the first `limit` bytes is ignored and never allocated, so a small stream
+/// can't inflate to more memory than expected.
+#[cfg(feature = "deflate")]
+pub fn decompress_limited(data: &[u8], out: &mut Vec<u8>, limit: usize) -> Result<()> {
+    use flate2::{Decompress, FlushDecompress, Status};
+    use std::cell::RefCell;
+    use std::io::{Error, ErrorKind};
+
+    thread_local! {
+        static DECOMPRESS: RefCell<Option<Decompress>> = const { RefCell::new(None) };
+    }
+
+    let mut zlib = DECOMPRESS.take().unwrap_or_else(|| Decompress::new(true));
+    zlib.reset(true);
+
+    let start = out.len();
+    let end = start.saturating_add(limit);
+    let result = loop {
+        if out.len() >= end {
+            break Ok(());
+        }
+        if out.len() == out.capacity() {
+            out.reserve_exact(data.len().max(4096).min(end - out.len()));
+        }
+        let consumed = zlib.total_in() as usize;
+        let produced = zlib.total_out();
+        match zlib.decompress_vec(&data[consumed..], out, FlushDecompress::None) {
+            Ok(Status::StreamEnd) => break Ok(()),
+            // Truncated stream, the data decompressed so far is kept like a streaming decoder does
+            Ok(_) if zlib.total_in() as usize == data.len() && zlib.total_out() == produced => break Ok(()),
+            Ok(_) => {}
+            Err(err) => break Err(Error::new(ErrorKind::InvalidInput, err)),
+        }
+    };
+    // Spare capacity of a reused buffer may have taken more
+    out.truncate(end);
+
+    DECOMPRESS.set(Some(zlib));
+    result
+}
+
+/// Decompress at most `limit` bytes of the zlib stream `data`, appending to `out`.
+#[cfg(not(feature = "deflate"))]
+pub fn decompress_limited(data: &[u8], out: &mut Vec<u8>, limit: usize) -> Result<()> {
+    stored::StoredDecoder::new(data)
+        .take(limit as u64)
+        .read_to_end(out)
+        .map(|_| ())
+}
+
+/// Compression level of [`compress`], the best compression like MATLAB uses
+pub const DEFAULT_LEVEL: u32 = 9;
+
+/// Compress `data` into a zlib stream with the level [`DEFAULT_LEVEL`].
+pub fn compress(data: &[u8]) -> Result<Vec<u8>> {
+    compress_with_level(data, DEFAULT_LEVEL)
+}
+
+/// Compress `data` into a zlib stream with the level `level` from 0 to 9.
+///
+/// Like [`decompress`], each thread keeps its compressor for the next stream of the same level.
+#[cfg(feature = "deflate")]
+pub fn compress_with_level(data: &[u8], level: u32) -> Result<Vec<u8>> {
+    use flate2::{Compress, Compression, FlushCompress, Status};
+    use std::cell::RefCell;
+    use std::io::Error;
+
+    thread_local! {
+        static COMPRESS: RefCell<Option<(u32, Compress)>> = const { RefCell::new(None) };
+    }
+
+    let level = level.min(9);
+    let mut zlib = match COMPRESS.take() {
+        Some((l, zlib)) if l == level => zlib,
+        _ => Compress::new(Compression::new(level), true),
+    };
+    zlib.reset();
+
+    // Incompressible data grows by a few bytes per block
+    let mut out = Vec::with_capacity(data.len() / 2 + 64);
+    let result = loop {
+        if out.len() == out.capacity() {
+            out.reserve(data.len() / 2 + 64);
+        }
+        let consumed = zlib.total_in() as usize;
+        match zlib.compress_vec(&data[consumed..], &mut out, FlushCompress::Finish) {
+            Ok(Status::StreamEnd) => break Ok(out),
+            Ok(_) => {}
+            Err(err) => break Err(Error::other(err)),
+        }
+    };
+
+    COMPRESS.set(Some((level, zlib)));
+    result
+}
+
+/// Compress `data` into a zlib stream, the level is ignored for stored blocks.
+#[cfg(not(feature = "deflate"))]
+pub fn compress_with_level(data: &[u8], _level: u32) -> Result<Vec<u8>> {
+    Ok(stored::encode(data))
+}
+
+/// Deflate blocks of some data, which end on a byte boundary and don't refer to data before
+/// them, so that segments can be joined into one zlib stream, see [`join_segments`].
+///
+/// A variable shared by several variables of a file is compressed once into a segment, which is
+/// joined with the segment of the differing names for each of them.
+#[derive(Debug, Clone)]
+pub struct Segment {
+    blocks: Vec<u8>,
+    adler: u32,
+    len: usize,
+}
+
+impl Segment {
+    /// Bytes of the compressed blocks
+    pub fn compressed_len(&self) -> usize {
+        self.blocks.len()
+    }
+}
+
+/// Segment of `data` in stored, i.e. uncompressed, blocks, for short data like element tags.
+pub fn stored_segment(data: &[u8]) -> Segment {
+    let mut blocks = Vec::with_capacity(data.len() + 5 * data.len().div_ceil(stored::BLOCK_SIZE));
+    stored::push_blocks(&mut blocks, data, false);
+    Segment {
+        blocks,
+        adler: adler32(1, data),
+        len: data.len(),
+    }
+}
+
+/// Segment of `data` compressed with the level `level` from 0 to 9.
+#[cfg(feature = "deflate")]
+pub fn compress_segment(data: &[u8], level: u32) -> Result<Segment> {
+    use flate2::{Compress, Compression, FlushCompress};
+    use std::io::Error;
+
+    // A full flush ends the blocks on a byte boundary and resets the window
+    let mut zlib = Compress::new(Compression::new(level.min(9)), false);
+    let mut blocks = Vec::with_capacity(data.len() / 2 + 64);
+    loop {
+        if blocks.len() == blocks.capacity() {
+            blocks.reserve(data.len() / 2 + 64);
+        }
+        let consumed = zlib.total_in() as usize;
+        zlib.compress_vec(&data[consumed..], &mut blocks, FlushCompress::Full)
+            .map_err(Error::other)?;
+        if zlib.total_in() as usize == data.len() && blocks.len() < blocks.capacity() {
+            break;
+        }
+    }
+
+    Ok(Segment {
+        blocks,
+        adler: adler32(1, data),
+        len: data.len(),
+    })
+}
+
+/// Segment of `data`, the level is ignored for stored blocks.
+#[cfg(not(feature = "deflate"))]
+pub fn compress_segment(data: &[u8], _level: u32) -> Result<Segment> {
+    Ok(stored_segment(data))
+}
+
+/// zlib stream of the data of `segments`, one after the other.
+pub fn join_segments(segments: &[&Segment]) -> Vec<u8> {
+    let size = segments.iter().map(|s| s.blocks.len()).sum::<usize>();
+    let mut out = Vec::with_capacity(size + 11);
+    out.extend_from_slice(&stored::HEADER);
+
+    let mut adler = 1;
+    for segment in segments {
+        out.extend_from_slice(&segment.blocks);
+        adler = adler32_combine(adler, segment.adler, segment.len);
+    }
+    // Empty final block
+    stored::push_blocks(&mut out, &[], true);
+
+    out.extend_from_slice(&adler.to_be_bytes());
+    out
+}
+
+const ADLER_MOD: u32 = 65521;
+
+/// Adler-32 checksum of zlib streams, continuing `adler` with `data`.
+fn adler32(adler: u32, data: &[u8]) -> u32 {
+    let (mut a, mut b) = (adler & 0xffff, adler >> 16);
+    // Largest number of bytes, whose sums can't overflow before the reduction
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= ADLER_MOD;
+        b %= ADLER_MOD;
+    }
+    (b << 16) | a
+}
+
+/// Checksum of two data joined, from the checksums `adler1` and `adler2` of both and the length
+/// `len2` of the second, like zlib's `adler32_combine`.
+fn adler32_combine(adler1: u32, adler2: u32, len2: usize) -> u32 {
+    let m = ADLER_MOD as u64;
+    let rem = len2 as u64 % m;
+    let (a1, b1) = ((adler1 & 0xffff) as u64, (adler1 >> 16) as u64);
+    let (a2, b2) = ((adler2 & 0xffff) as u64, (adler2 >> 16) as u64);
+
+    // The sums of the second part start at 1, instead of the sums of the first
+    let a = (a1 + a2 + m - 1) % m;
+    let b = (rem * a1 + b1 + b2 + m - rem) % m;
+    ((b << 16) | a) as u32
+}
+
+/// zlib streams of stored deflate blocks, see RFC 1950 and RFC 1951.
+#[cfg_attr(feature = "deflate", allow(dead_code))]
+mod stored {
+    use super::adler32;
+    use std::io::{Error, ErrorKind, Read, Result};
+
+    /// Maximum number of bytes of a stored block
+    pub const BLOCK_SIZE: usize = 0xffff;
+
+    /// Header of a stream with a 32K window, without preset dictionary
+    pub const HEADER: [u8; 2] = [0x78, 0x01];
+
+    fn invalid(message: &str) -> Error {
+        Error::new(ErrorKind::InvalidData, message)
+    }
+
+    /// Append `data` in stored blocks, the last one final with `last`. Empty data takes one
+    /// block only if it is final.
+    pub fn push_blocks(out: &mut Vec<u8>, data: &[u8], last: bool) {
+        let blocks = data.len().div_ceil(BLOCK_SIZE).max(last as usize);
+        for i in 0..blocks {
+            let block = &data[(i * BLOCK_SIZE).min(data.len())..((i + 1) * BLOCK_SIZE).min(data.len())];
+            let len = block.len() as u16;
+            // BFINAL in the lowest bit, BTYPE 00 for stored blocks
+            out.push((last && i + 1 == blocks) as u8);
+            out.extend_from_slice(&len.to_le_bytes());
+            out.extend_from_slice(&(!len).to_le_bytes());
+            out.extend_from_slice(block);
+        }
+    }
+
+    /// zlib stream of `data` in stored blocks.
+    pub fn encode(data: &[u8]) -> Vec<u8> {
+        let blocks = data.len().div_ceil(BLOCK_SIZE).max(1);
+        let mut out = Vec::with_capacity(data.len() + 5 * blocks + 6);
+        out.extend_from_slice(&HEADER);
+        push_blocks(&mut out, data, true);
+        out.extend_from_slice(&adler32(1, data).to_be_bytes());
+        out
+    }
+
+    /// Reader of a zlib stream of stored blocks.
+    pub struct StoredDecoder<R: Read> {
+        reader: R,
+        started: bool,
+        /// Bytes left in the current block
+        remaining: usize,
+        last_block: bool,
+        finished: bool,
+        adler: u32,
+    }
+
+    impl<R: Read> StoredDecoder<R> {
+        pub fn new(reader: R) -> Self {
+            Self {
+                reader,
+                started: false,
+                remaining: 0,
+                last_block: false,
+                finished: false,
+                adler: 1,
+            }
+        }
+
+        fn read_header(&mut self) -> Result<()> {
+            let mut header = [0u8; 2];
+            self.reader.read_exact(&mut header)?;
+            let valid = header[0] & 0x0f == 8 && u16::from_be_bytes(header) % 31 == 0;
+            if !valid || header[1] & 0x20 != 0 {
+                return Err(invalid("Invalid zlib stream header"));
+            }
+            self.started = true;
+            Ok(())
+        }
+
+        fn read_block_header(&mut self) -> Result<()> {
+            let mut header = [0u8; 5];
+            self.reader.read_exact(&mut header)?;
+            if (header[0] >> 1) & 0b11 != 0 {
+                return Err(invalid(
+                    "Compressed data uses deflate compression, which requires the `deflate` feature of matrw",
+                ));
+            }
+            let len = u16::from_le_bytes([header[1], header[2]]);
+            let nlen = u16::from_le_bytes([header[3], header[4]]);
+            if len != !nlen {
+                return Err(invalid("Corrupt length of stored deflate block"));
+            }
+            self.last_block = header[0] & 1 == 1;
+            self.remaining = len as usize;
+            Ok(())
+        }
+
+        fn read_checksum(&mut self) -> Result<()> {
+            let mut checksum = [0u8; 4];
+            self.reader.read_exact(&mut checksum)?;
+            if u32::from_be_bytes(checksum) != self.adler {
+                return Err(invalid("Checksum mismatch of zlib stream"));
+            }
+            self.finished = true;
+            Ok(())
+        }
+    }
+
+    impl<R: Read> Read for StoredDecoder<R> {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            if !self.started {
+                self.read_header()?;
+            }
+            while self.remaining == 0 && !self.finished {
+                if self.last_block {
+                    self.read_checksum()?;
+                } else {
+                    self.read_block_header()?;
+                }
+            }
+            if self.finished || buf.is_empty() {
+                return Ok(0);
+            }
+
+            let n = buf.len().min(self.remaining);
+            self.reader.read_exact(&mut buf[..n])?;
+            self.adler = adler32(self.adler, &buf[..n]);
+            self.remaining -= n;
+            Ok(n)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stored_roundtrip() {
+        for len in [0, 1, 1000, 0xffff, 0x10000, 200_000] {
+            let data: Vec<u8> = (0..len).map(|i| (i * 7 % 251) as u8).collect();
+            let encoded = stored::encode(&data);
+
+            let mut decoded = Vec::new();
+            stored::StoredDecoder::new(encoded.as_slice())
+                .read_to_end(&mut decoded)
+                .unwrap();
+            assert_eq!(decoded, data);
+
+            // Stored streams are valid zlib streams for any backend
+            let mut decoded = Vec::new();
+            decoder(encoded.as_slice()).read_to_end(&mut decoded).unwrap();
+            assert_eq!(decoded, data);
+        }
+    }
+
+    #[test]
+    fn joined_segments() {
+        let data: Vec<u8> = (0..200_000).map(|i| (i * 7 % 251) as u8).collect();
+        let (head, body) = data.split_at(70_000);
+        let body = compress_segment(body, DEFAULT_LEVEL).unwrap();
+        for head in [&head[..0], &head[..5], head] {
+            let stream = join_segments(&[&stored_segment(head), &body]);
+            let mut decoded = Vec::new();
+            decompress(&stream, &mut decoded).unwrap();
+            assert_eq!(decoded[..head.len()], *head);
+            assert_eq!(decoded[head.len()..], data[70_000..]);
+        }
+        assert_eq!(join_segments(&[]), stored::encode(&[]));
+
+        for (a, b) in [(&data[..0], &data[..3]), (&data[..100_000], &data[100_000..])] {
+            let joined = adler32_combine(adler32(1, a), adler32(1, b), b.len());
+            assert_eq!(joined, adler32(adler32(1, a), b));
+        }
+    }
+
+    #[test]
+    fn decompress_limited_allocates_limit() {
+        let data = vec![7u8; 100_000];
+        let compressed = compress(&data).unwrap();
+        let mut decoded = b"x".to_vec();
+        decompress_limited(&compressed, &mut decoded, 1000).unwrap();
+        assert_eq!(decoded[1..], data[..1000]);
+        assert!(decoded.capacity() < 10_000);
+
+        let mut decoded = Vec::new();
+        decompress_limited(&compressed, &mut decoded, 200_000).unwrap();
+        assert_eq!(decoded, data);
+    }
+
+    #[test]
+    fn stored_rejects_invalid_streams() {
+        let read = |data: &[u8]| {
+            let mut out = Vec::new();
+            stored::StoredDecoder::new(data).read_to_end(&mut out)
+        };
+
+        let mut encoded = stored::encode(b"abc");
+        *encoded.last_mut().unwrap() ^= 1;
+        assert!(read(&encoded).is_err());
+        assert!(read(&encoded[..6]).is_err());
+        assert!(read(b"\x78\x9c\x4b\x4c\x4a\x06\x00\x02\x4d\x01\x27").is_err());
+        assert!(read(b"\x00\x00").is_err());
+    }
+
+    #[cfg(feature = "deflate")]
+    #[test]
+    fn compress_roundtrip() {
+        let data = b"aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
+        let compressed = compress(data).unwrap();
+        assert!(compressed.len() < data.len());
+        // The compressor of the thread is reset for every stream
+        assert_eq!(compress(data).unwrap(), compressed);
+        let fast = compress_with_level(data, 1).unwrap();
+        assert_eq!(compress(data).unwrap(), compressed);
+        let mut decoded = Vec::new();
+        decompress(&fast, &mut decoded).unwrap();
+        assert_eq!(decoded, data);
+
+        let mut decoded = Vec::new();
+        decoder(compressed.as_slice()).read_to_end(&mut decoded).unwrap();
+        assert_eq!(decoded, data);
+
+        // The decompressor of the thread is reset for every stream
+        for _ in 0..2 {
+            let mut decoded = b"x".to_vec();
+            decompress(&compressed, &mut decoded).unwrap();
+            assert_eq!(&decoded[1..], data);
+        }
+        assert!(decompress(&compressed[..4], &mut Vec::new()).is_ok());
+        assert!(decompress(b"\x78\x9c\xff\xff", &mut Vec::new()).is_err());
+    }
+}
@@ -0,0 +1,85 @@
+//! Test suite concerning empty arrays of arbitrary class and shape.
+
+use matrw::*;
+
+#[path = "shared_functions.rs"]
+mod shared_functions;
+
+use crate::shared_functions::TestFile;
+
+fn roundtrip(path: &str, compress: bool) -> MatFile {
+    let mut matfile = MatFile::new();
+    matfile.insert(
+        "zeros_0x3",
+        MatVariable::NumericArray(NumericArray::new(vec![0, 3], MatlabType::F64(vec![]), None).unwrap()),
+    );
+    matfile.insert(
+        "int16_5x0",
+        MatVariable::NumericArray(NumericArray::new(vec![5, 0], MatlabType::I16(vec![]), None).unwrap()),
+    );
+    matfile.insert(
+        "single_cmp_0x2",
+        MatVariable::NumericArray(
+            NumericArray::new(vec![0, 2], MatlabType::F32(vec![]), Some(MatlabType::F32(vec![]))).unwrap(),
+        ),
+    );
+    matfile.insert(
+        "logical_2x0x3",
+        MatVariable::NumericArray(NumericArray::new(vec![2, 0, 3], MatlabType::BOOL(vec![]), None).unwrap()),
+    );
+    matfile.insert(
+        "char_1x0",
+        MatVariable::NumericArray(NumericArray::new(vec![1, 0], MatlabType::UTF8(vec![]), None).unwrap()),
+    );
+    matfile.insert(
+        "cell_0x2",
+        MatVariable::CellArray(CellArray::new(vec![0, 2], vec![]).unwrap()),
+    );
+
+    save_matfile_v7(path, matfile, compress).unwrap();
+    load_matfile(path).unwrap()
+}
+
+fn check(matfile: &MatFile) {
+    let cases = [
+        ("zeros_0x3", vec![0, 3], MatlabType::F64(vec![])),
+        ("int16_5x0", vec![5, 0], MatlabType::I16(vec![])),
+        ("single_cmp_0x2", vec![0, 2], MatlabType::F32(vec![])),
+        ("logical_2x0x3", vec![2, 0, 3], MatlabType::BOOL(vec![])),
+        ("char_1x0", vec![1, 0], MatlabType::UTF8(vec![])),
+    ];
+
+    for (name, dim, value) in cases {
+        let MatVariable::NumericArray(arr) = &matfile[name] else {
+            panic!("{name} is not a numeric array");
+        };
+        assert_eq!(arr.dim, dim, "{name}");
+        assert_eq!(arr.value, value, "{name}");
+    }
+
+    assert_eq!(matfile["single_cmp_0x2"].is_complex(), Some(true));
+
+    let MatVariable::CellArray(cell) = &matfile["cell_0x2"] else {
+        panic!("cell_0x2 is not a cell array");
+    };
+    assert_eq!(cell.dim, vec![0, 2]);
+    assert!(cell.value.is_empty());
+}
+
+#[test]
+fn empty_arrays_roundtrip() {
+    let file = TestFile {
+        path: "tests/empty-arrays.mat",
+    };
+
+    check(&roundtrip(file.path, false));
+}
+
+#[test]
+fn empty_arrays_roundtrip_compressed() {
+    let file = TestFile {
+        path: "tests/empty-arrays-compressed.mat",
+    };
+
+    check(&roundtrip(file.path, true));
+}
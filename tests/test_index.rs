@@ -10,3 +10,42 @@ fn index_vec_data() {
     println!("Second: {}", a.elem(1));
     println!(" Third: {}", a.elem(2));
 }
+
+#[test]
+fn index_from_end() {
+    let a = matvar!([1, 2, 3]);
+    assert_eq!(a.elem(Idx::End).to_i32(), Some(3));
+    assert_eq!(a.elem(Idx::End - 2).to_i32(), Some(1));
+    assert_eq!(a.elem(Idx::End - 3), MatVariable::Null);
+
+    let s = matvar!([{ f: 1.0 }, { f: 2.0 }]);
+    assert_eq!(s[Idx::End]["f"].to_f64(), Some(2.0));
+
+    let g = matvar!([[[1, 2], [3, 4]], [[5, 6], [7, 8]]]);
+    assert_eq!(g.elem([Idx::End, Idx::At(0), Idx::End]), g.elem([1, 0, 1]));
+    assert_eq!(g.elem([Idx::End, Idx::End]), MatVariable::Null);
+}
+
+#[test]
+fn get_missing_or_empty() {
+    let mat = matfile!(
+        s: matvar!({ empty: [], inner: { x: 1.0 } }),
+        c: matvar!(["abc", 42.]),
+    );
+
+    assert!(mat.get("s").is_some());
+    assert!(mat.get("missing").is_none());
+
+    let s = mat.get("s").unwrap();
+    assert_eq!(s.get("empty").map(|e| e.dim().into_vec()), Some(vec![0, 0]));
+    assert!(s.get("missing").is_none());
+    assert_eq!(
+        s.get("inner").and_then(|i| i.get("x")).and_then(|x| x.to_f64()),
+        Some(1.0)
+    );
+
+    let c = mat.get("c").unwrap();
+    assert_eq!(c.get(1).and_then(|v| v.to_f64()), Some(42.));
+    assert!(c.get(2).is_none());
+    assert!(c.get("abc").is_none());
+}
@@ -1,5 +1,7 @@
 //! Test suite concerning the readout of MAT-file data via the public interface.
 
+#![cfg(feature = "serde")]
+
 use once_cell::sync::Lazy;
 use serde::Deserialize;
 use std::sync::Mutex;
@@ -0,0 +1,33 @@
+#![cfg(feature = "testing")]
+
+use matrw::testing::strategies::{arb_matfile, arb_matvariable};
+use matrw::*;
+use proptest::prelude::*;
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    /// Variables are read back unchanged
+    #[test]
+    fn variable_roundtrip(var in arb_matvariable(), compress in any::<bool>()) {
+        let mut matfile = MatFile::new();
+        matfile.insert("a", var.clone());
+
+        let data = save_matfile_v7_to_u8(matfile, compress).unwrap();
+        let loaded = load_matfile_from_u8(&data).unwrap();
+
+        prop_assert_eq!(&loaded["a"], &var);
+    }
+
+    /// Variable names and order are preserved
+    #[test]
+    fn matfile_roundtrip(matfile in arb_matfile()) {
+        let expected: Vec<(String, MatVariable)> = matfile.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+
+        let data = save_matfile_v7_to_u8(matfile, false).unwrap();
+        let loaded = load_matfile_from_u8(&data).unwrap();
+        let loaded: Vec<(String, MatVariable)> = loaded.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+
+        prop_assert_eq!(loaded, expected);
+    }
+}
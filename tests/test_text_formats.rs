@@ -0,0 +1,156 @@
+//! Test suite of the TOML and YAML documents against the parsers of Python's standard library and
+//! PyYAML.
+
+#![cfg(all(feature = "toml", feature = "yaml"))]
+
+use std::process::Command;
+
+use matrw::*;
+
+macro_rules! skip_if_missing {
+    ($tool:expr) => {
+        if which::which($tool).is_err() {
+            eprintln!("test skipped ({} not found)", $tool);
+            return;
+        }
+    };
+}
+
+/// Output of the Python `script` with `input` on stdin, or `None` if a module is missing
+fn python(script: &str, input: &str) -> Option<String> {
+    use std::io::Write;
+
+    let mut child = Command::new("python3")
+        .args(["-c", script])
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    let out = child.wait_with_output().unwrap();
+
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    if stderr.contains("ModuleNotFoundError") {
+        eprintln!("test skipped ({})", stderr.trim());
+        return None;
+    }
+    assert!(out.status.success(), "{stderr}");
+    Some(String::from_utf8(out.stdout).unwrap())
+}
+
+fn config() -> MatVariable {
+    matvar!({
+        name: "pump \"P-1\"\tmotor é ✓",
+        enabled: true,
+        gain: 2.5,
+        offset: (-0.125),
+        tiny: 1.5e-7,
+        count: 3u8,
+        limits: [0.0, 10.0],
+        flags: [true, false, true],
+        matrix: [[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]],
+        tags: ["fast", "quiet # not a comment", "yes"],
+        empty: [],
+        nested: { id: 7.0, unit: "m/s", deeper: { x: 1.0 } },
+        stages: [{ k: 1.0, sub: { a: 2.0 } }, { k: 2.0, sub: { a: 3.0 } }],
+        last: "after the nested ones",
+    })
+}
+
+/// `config` as read back from text, where numbers are doubles
+fn config_read() -> MatVariable {
+    let mut config = config();
+    let MatVariable::Structure(s) = &mut config else {
+        unreachable!()
+    };
+    s.value.insert("count".to_string(), matvar!(3.0));
+    config
+}
+
+#[test]
+fn toml_read_by_tomllib() {
+    skip_if_missing!("python3");
+
+    let text = to_toml_string(&config()).unwrap();
+    let script = "import json, sys, tomllib; print(json.dumps(tomllib.loads(sys.stdin.read())))";
+    let Some(json) = python(script, &text) else { return };
+    let read: MatVariable = serde_json::from_str(&json).unwrap();
+    assert_eq!(read, config_read(), "{text}");
+}
+
+#[test]
+fn toml_written_by_hand() {
+    skip_if_missing!("python3");
+
+    // TOML as people write it, compared to what tomllib reads of it
+    let text = r#"
+        # Pump configuration
+        title = "Pump"  # trailing comment
+        path = 'C:\Users\pump'
+        "quoted key" = 1
+        site."example.com".enabled = true
+        ints = [ 1_000, 0xff, 0o17, 0b101, -7, +3 ]
+        floats = [ 6.25e-3, -0.01, 5e+22, 1E6, 3_141.5 ]
+        mixed = [ 1, "two", [3, 4] ]
+        matrix = [
+            [1, 2],  # first row
+            [3, 4],
+        ]
+        text = """
+        first \
+          second"""
+        raw = '''
+        a\b'''
+        point = { x = 1, y = 2.5, z.w = "deep" }
+        empty = {}
+
+        [motor]
+        id = 7
+        limits.min = 0
+        limits.max = 10
+
+        [[stage]]
+        k = 1
+
+        [stage.sub]
+        a = 2
+
+        [[stage]]
+        k = 2
+    "#;
+    let script = "import json, sys, tomllib; print(json.dumps(tomllib.loads(sys.stdin.read())))";
+    let Some(json) = python(script, text) else { return };
+    let expected: MatVariable = serde_json::from_str(&json).unwrap();
+    assert_eq!(from_toml_str(text).unwrap(), expected);
+}
+
+#[test]
+fn yaml_read_by_pyyaml() {
+    skip_if_missing!("python3");
+
+    let text = to_yaml_string(&config()).unwrap();
+    let script = "import json, sys, yaml; print(json.dumps(yaml.safe_load(sys.stdin.read())))";
+    let Some(json) = python(script, &text) else { return };
+    let read: MatVariable = serde_json::from_str(&json).unwrap();
+    assert_eq!(read, config_read(), "{text}");
+}
+
+#[test]
+fn yaml_written_by_pyyaml() {
+    skip_if_missing!("python3");
+
+    // Block and flow styles of PyYAML, with its escapes of non-ASCII characters
+    let json = serde_json::to_string(&config()).unwrap();
+    for flow_style in ["False", "None"] {
+        let script = format!(
+            "import json, sys, yaml; \
+             print(yaml.safe_dump(json.loads(sys.stdin.read()), sort_keys=False, width=1000, \
+             default_flow_style={flow_style}))"
+        );
+        let Some(text) = python(&script, &json) else {
+            return;
+        };
+        assert_eq!(from_yaml_str(&text).unwrap(), config_read(), "{text}");
+    }
+}
@@ -1,5 +1,7 @@
 //! Test suite concerning the serde interface.
 
+#![cfg(feature = "serde")]
+
 use serde::Serialize;
 
 use matrw::*;
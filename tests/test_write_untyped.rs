@@ -38,3 +38,85 @@ fn create_data_and_write_compressed() {
 
     let _ = save_matfile_v7(file.path, matfile, true);
 }
+
+#[test]
+/// Character data read from UTF-16 encoded files can be written again
+fn write_utf16_chars() {
+    let var = MatVariable::NumericArray(
+        NumericArray::new(vec![1, 3], MatlabType::UTF16(vec!['a', 'b', 'c']), None).unwrap(),
+    );
+    let data = save_matfile_v7_to_u8(matfile!(a: var), false).unwrap();
+    let loaded = load_matfile_from_u8(&data).unwrap();
+
+    assert_eq!(loaded["a"].to_vec::<char>(), Some(vec!['a', 'b', 'c']));
+}
+
+#[test]
+/// Logical matrices and N-D logical arrays keep class and dimensions
+fn write_logical_arrays() {
+    let m = matvar!([[true, false, true], [false, false, true]]);
+    let g = matvar!([[[true, false], [false, true]], [[true, true], [false, false]]]);
+
+    for compress in [false, true] {
+        let data = save_matfile_v7_to_u8(matfile!(m: m.clone(), g: g.clone()), compress).unwrap();
+        let loaded = load_matfile_from_u8(&data).unwrap();
+
+        assert_eq!(loaded["m"], m);
+        assert_eq!(loaded["m"].dim(), vec![2, 3]);
+        assert_eq!(
+            loaded["m"].to_vec_bool(),
+            Some(vec![true, false, false, false, true, true])
+        );
+        assert_eq!(loaded["m"].elem([1, 2]).to_bool(), Some(true));
+
+        assert_eq!(loaded["g"], g);
+        assert_eq!(loaded["g"].dim(), vec![2, 2, 2]);
+        assert_eq!(loaded["g"].elem([0, 1, 1]).to_bool(), Some(true));
+        assert_eq!(loaded["g"].elem([1, 0, 1]).to_bool(), Some(false));
+    }
+}
+
+#[test]
+/// Single arrays keep their class, double arrays are only stored as single on request
+fn write_single_arrays() {
+    use matrw::parser::v7::flags::MatFileDataTypes;
+
+    let storage = |var: &MatVariable| match var {
+        MatVariable::NumericArray(arr) => arr.storage_type(),
+        _ => None,
+    };
+    let matfile = || {
+        matfile!(
+            a: matvar!([1.5f32, 2.0f32, 3.25f32]),
+            b: matvar!([1f32, 2f32]),
+            c: matvar!([0.5, 0.25]),
+            d: matvar!([0.1, 0.5]),
+        )
+    };
+
+    for compress in [false, true] {
+        for single in [false, true] {
+            let options = SaveOptions::new()
+                .compress(compress)
+                .compress_floats_to_single(single);
+            let data = save_matfile_v7_to_u8_with_options(matfile(), &options).unwrap();
+            let loaded = load_matfile_from_u8(&data).unwrap();
+
+            let original = matfile();
+            for name in ["a", "b", "c", "d"] {
+                assert_eq!(loaded[name], original[name]);
+            }
+            assert_eq!(storage(&loaded["a"]), Some(MatFileDataTypes::MiSINGLE));
+            assert_eq!(storage(&loaded["b"]), Some(MatFileDataTypes::MiSINGLE));
+            assert_eq!(loaded["b"].numeric_type(), Some(&MatlabType::F32(vec![1.0, 2.0])));
+
+            let c = if single {
+                MatFileDataTypes::MiSINGLE
+            } else {
+                MatFileDataTypes::MiDOUBLE
+            };
+            assert_eq!(storage(&loaded["c"]), Some(c));
+            assert_eq!(storage(&loaded["d"]), Some(MatFileDataTypes::MiDOUBLE));
+        }
+    }
+}